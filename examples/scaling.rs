@@ -48,7 +48,8 @@ impl State for GameState {
                 ScalingMode::ShowAll => ScalingMode::ShowAllPixelPerfect,
                 ScalingMode::ShowAllPixelPerfect => ScalingMode::Crop,
                 ScalingMode::Crop => ScalingMode::CropPixelPerfect,
-                ScalingMode::CropPixelPerfect => ScalingMode::Fixed,
+                ScalingMode::CropPixelPerfect => ScalingMode::PixelPerfectSmooth,
+                ScalingMode::PixelPerfectSmooth => ScalingMode::Fixed,
             };
 
             self.set_mode(next);
@@ -79,7 +80,7 @@ impl State for GameState {
     }
 
     fn event(&mut self, _: &mut Context, event: Event) -> tetra::Result {
-        if let Event::Resized { width, height } = event {
+        if let Event::Resized { width, height, .. } = event {
             self.scaler.set_outer_size(width, height);
         }
 
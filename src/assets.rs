@@ -0,0 +1,588 @@
+//! Functions and types relating to loading and caching assets.
+//!
+//! # Caching
+//!
+//! An [`AssetCache`] ensures that each asset is only loaded once, no matter how many
+//! times it is requested. Assets can then be unloaded explicitly, or evicted in bulk
+//! once nothing else is using them (e.g. when moving between levels).
+//!
+//! ```no_run
+//! # use tetra::assets::AssetCache;
+//! # use tetra::graphics::Texture;
+//! # use tetra::Context;
+//! # fn example(ctx: &mut Context) -> tetra::Result {
+//! let mut textures: AssetCache<Texture> = AssetCache::new();
+//!
+//! let player = textures.get_or_load(ctx, "./player.png")?;
+//! let player_again = textures.get_or_load(ctx, "./player.png")?; // Not loaded twice!
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! # Background Loading
+//!
+//! Loading a large number of assets up front can cause your game to freeze for
+//! a noticeable amount of time. An [`AssetLoader`] splits that work up - the files
+//! are read and decoded on worker threads, and then the GPU resources are created
+//! on the main thread, a few at a time each frame. This means you can display a
+//! loading screen with an accurate progress bar while your assets load.
+//!
+//! ```no_run
+//! # use tetra::assets::{AssetLoader, PendingAsset};
+//! # use tetra::graphics::Texture;
+//! # use tetra::{Context, State};
+//! struct LoadingState {
+//!     loader: AssetLoader,
+//!     player: PendingAsset<Texture>,
+//! }
+//!
+//! impl State for LoadingState {
+//!     fn update(&mut self, ctx: &mut Context) -> tetra::Result {
+//!         self.loader.update(ctx)?;
+//!
+//!         if self.loader.is_finished() {
+//!             let player = self.loader.get(&self.player).unwrap();
+//!             // Switch to the main game state...
+//!         }
+//!
+//!         Ok(())
+//!     }
+//! }
+//! ```
+
+use std::any::Any;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use hashbrown::HashMap;
+
+use crate::error::Result;
+use crate::graphics::{ImageData, Texture};
+use crate::Context;
+
+#[cfg(feature = "audio")]
+use crate::audio::Sound;
+
+#[cfg(feature = "font_ttf")]
+use crate::fs;
+#[cfg(feature = "font_ttf")]
+use crate::graphics::text::{Font, VectorFontBuilder};
+
+/// Implemented for types that can be stored in an [`AssetCache`].
+///
+/// This is implemented for Tetra's built-in asset types, but you can also implement
+/// it for your own types (e.g. level data). Handles should be cheap to clone - usually
+/// this is achieved by wrapping the data in an [`Rc`](std::rc::Rc) or an [`Arc`].
+pub trait Asset: Clone {
+    /// Loads the asset from the given path.
+    ///
+    /// # Errors
+    ///
+    /// If the asset fails to load, an error should be returned.
+    fn load(ctx: &mut Context, path: &Path) -> Result<Self>;
+
+    /// Returns the number of handles to the underlying data that currently exist,
+    /// including this one.
+    ///
+    /// This is used by [`AssetCache::unload_unused`] to determine whether an asset
+    /// is still in use.
+    fn handle_count(&self) -> usize;
+
+    /// Returns an estimate of how much memory the asset is using, in bytes.
+    ///
+    /// Defaults to `0`.
+    fn memory_usage(&self) -> usize {
+        0
+    }
+}
+
+impl Asset for Texture {
+    fn load(ctx: &mut Context, path: &Path) -> Result<Texture> {
+        Texture::new(ctx, path)
+    }
+
+    fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.data)
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.data.handle.size_in_bytes()
+    }
+}
+
+#[cfg(feature = "audio")]
+impl Asset for Sound {
+    fn load(_: &mut Context, path: &Path) -> Result<Sound> {
+        Sound::new(path)
+    }
+
+    fn handle_count(&self) -> usize {
+        Arc::strong_count(&self.data)
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.data.bytes().len()
+    }
+}
+
+#[cfg(feature = "font_ttf")]
+impl Asset for VectorFontBuilder {
+    fn load(_: &mut Context, path: &Path) -> Result<VectorFontBuilder> {
+        VectorFontBuilder::new(path)
+    }
+
+    fn handle_count(&self) -> usize {
+        // Fonts created from the builder also count as handles, as they share
+        // the underlying data.
+        VectorFontBuilder::handle_count(self)
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.owned_bytes()
+    }
+}
+
+/// A cache of assets, keyed by path.
+///
+/// Each asset will only be loaded once - subsequent requests for the same path will
+/// return a new handle to the existing asset.
+///
+/// Assets stay in the cache until they are explicitly unloaded, either individually via
+/// [`unload`](AssetCache::unload), or in bulk via [`unload_unused`](AssetCache::unload_unused)
+/// or [`clear`](AssetCache::clear). Unloading an asset only removes it from the cache -
+/// the underlying resource will not be freed until all other handles to it are dropped.
+#[derive(Debug, Clone)]
+pub struct AssetCache<T> {
+    assets: HashMap<PathBuf, T>,
+}
+
+impl<T> AssetCache<T>
+where
+    T: Asset,
+{
+    /// Creates a new, empty cache.
+    pub fn new() -> AssetCache<T> {
+        AssetCache {
+            assets: HashMap::new(),
+        }
+    }
+
+    /// Returns a handle to the asset at the given path, loading it if it is not
+    /// already in the cache.
+    ///
+    /// # Errors
+    ///
+    /// If the asset is not in the cache and fails to load, the error returned by
+    /// [`Asset::load`] will be returned.
+    pub fn get_or_load<P>(&mut self, ctx: &mut Context, path: P) -> Result<T>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+
+        if let Some(asset) = self.assets.get(path) {
+            return Ok(asset.clone());
+        }
+
+        let asset = T::load(ctx, path)?;
+        self.assets.insert(path.to_owned(), asset.clone());
+
+        Ok(asset)
+    }
+
+    /// Returns a handle to the asset with the given key, if it is in the cache.
+    pub fn get<P>(&self, key: P) -> Option<T>
+    where
+        P: AsRef<Path>,
+    {
+        self.assets.get(key.as_ref()).cloned()
+    }
+
+    /// Inserts an asset into the cache under the given key, returning the asset that was
+    /// previously stored there (if any).
+    ///
+    /// The key does not have to be a real path - this can be used to cache assets that
+    /// were created in code, or loaded via some other method.
+    pub fn insert<P>(&mut self, key: P, asset: T) -> Option<T>
+    where
+        P: Into<PathBuf>,
+    {
+        self.assets.insert(key.into(), asset)
+    }
+
+    /// Returns `true` if an asset with the given key is in the cache.
+    pub fn contains<P>(&self, key: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        self.assets.contains_key(key.as_ref())
+    }
+
+    /// Removes the asset with the given key from the cache, returning it if it was present.
+    pub fn unload<P>(&mut self, key: P) -> Option<T>
+    where
+        P: AsRef<Path>,
+    {
+        self.assets.remove(key.as_ref())
+    }
+
+    /// Removes all assets from the cache that are not being used anywhere else (i.e. the
+    /// cache is holding the only handle to them).
+    ///
+    /// Returns the number of assets that were unloaded.
+    pub fn unload_unused(&mut self) -> usize {
+        let before = self.assets.len();
+        self.assets.retain(|_, asset| asset.handle_count() > 1);
+        before - self.assets.len()
+    }
+
+    /// Removes all assets from the cache.
+    pub fn clear(&mut self) {
+        self.assets.clear();
+    }
+
+    /// Returns the number of assets in the cache.
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    /// Returns `true` if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+
+    /// Returns an estimate of how much memory the assets in the cache are using, in bytes.
+    ///
+    /// See [`Asset::memory_usage`] for more details.
+    pub fn memory_usage(&self) -> usize {
+        self.assets.values().map(Asset::memory_usage).sum()
+    }
+
+    /// Returns an iterator over the keys and assets in the cache, in arbitrary order.
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &T)> {
+        self.assets.iter().map(|(k, v)| (k.as_path(), v))
+    }
+}
+
+impl<T> Default for AssetCache<T>
+where
+    T: Asset,
+{
+    fn default() -> AssetCache<T> {
+        AssetCache::new()
+    }
+}
+
+const DEFAULT_WORKER_COUNT: usize = 2;
+const DEFAULT_UPLOAD_BUDGET: Duration = Duration::from_millis(4);
+
+/// A handle to an asset that has been queued for loading by an [`AssetLoader`].
+///
+/// Once the asset has finished loading, it can be retrieved via [`AssetLoader::get`].
+#[derive(Debug)]
+pub struct PendingAsset<T> {
+    id: usize,
+    _phantom: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for PendingAsset<T> {
+    fn clone(&self) -> Self {
+        PendingAsset {
+            id: self.id,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+enum Job {
+    Texture(PathBuf),
+
+    #[cfg(feature = "font_ttf")]
+    Font(PathBuf, f32),
+
+    #[cfg(feature = "audio")]
+    Sound(PathBuf),
+}
+
+enum Decoded {
+    Texture(ImageData),
+
+    #[cfg(feature = "font_ttf")]
+    Font(Vec<u8>, f32),
+
+    #[cfg(feature = "audio")]
+    Sound(Sound),
+}
+
+/// Loads assets in the background, on a pool of worker threads.
+///
+/// Files are read (via the [virtual filesystem](crate::fs)) and decoded on the worker
+/// threads. GPU resources (e.g. textures and font caches) have to be created on the main
+/// thread, so this is done in [`update`](AssetLoader::update), which will stop once
+/// the [upload budget](AssetLoader::set_upload_budget) for the frame has been used up.
+///
+/// Dropping an `AssetLoader` will cancel any loads that have not yet started - the worker
+/// threads will exit once they have finished their current job.
+pub struct AssetLoader {
+    jobs: Sender<(usize, Job)>,
+    results: Receiver<(usize, Result<Decoded>)>,
+
+    loaded: HashMap<usize, Box<dyn Any>>,
+    upload_budget: Duration,
+
+    next_id: usize,
+    completed: usize,
+}
+
+impl AssetLoader {
+    /// Creates a new asset loader, with two worker threads.
+    pub fn new() -> AssetLoader {
+        AssetLoader::with_worker_count(DEFAULT_WORKER_COUNT)
+    }
+
+    /// Creates a new asset loader, with the specified number of worker threads.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `count` is zero.
+    pub fn with_worker_count(count: usize) -> AssetLoader {
+        assert!(count > 0, "asset loader must have at least one worker");
+
+        let (job_sender, job_receiver) = mpsc::channel::<(usize, Job)>();
+        let (result_sender, result_receiver) = mpsc::channel();
+
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        for i in 0..count {
+            let job_receiver = Arc::clone(&job_receiver);
+            let result_sender = result_sender.clone();
+
+            thread::Builder::new()
+                .name(format!("tetra-asset-loader-{}", i))
+                .spawn(move || worker(job_receiver, result_sender))
+                .expect("failed to spawn asset loader thread");
+        }
+
+        AssetLoader {
+            jobs: job_sender,
+            results: result_receiver,
+
+            loaded: HashMap::new(),
+            upload_budget: DEFAULT_UPLOAD_BUDGET,
+
+            next_id: 0,
+            completed: 0,
+        }
+    }
+
+    /// Queues a texture to be loaded from the given file.
+    pub fn load_texture<P>(&mut self, path: P) -> PendingAsset<Texture>
+    where
+        P: AsRef<Path>,
+    {
+        self.queue(Job::Texture(path.as_ref().to_owned()))
+    }
+
+    /// Queues a vector font to be loaded from the given file, at the given size.
+    #[cfg(feature = "font_ttf")]
+    pub fn load_vector_font<P>(&mut self, path: P, size: f32) -> PendingAsset<Font>
+    where
+        P: AsRef<Path>,
+    {
+        self.queue(Job::Font(path.as_ref().to_owned(), size))
+    }
+
+    /// Queues a sound to be loaded from the given file.
+    #[cfg(feature = "audio")]
+    pub fn load_sound<P>(&mut self, path: P) -> PendingAsset<Sound>
+    where
+        P: AsRef<Path>,
+    {
+        self.queue(Job::Sound(path.as_ref().to_owned()))
+    }
+
+    fn queue<T>(&mut self, job: Job) -> PendingAsset<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        // The workers only exit once the sender has been dropped, so this can't fail.
+        let _ = self.jobs.send((id, job));
+
+        PendingAsset {
+            id,
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Creates the GPU resources for any assets that have finished decoding.
+    ///
+    /// This should be called once per frame (e.g. in your [`State::update`](crate::State::update)
+    /// method). At least one asset will be processed per call, if one is available - after
+    /// that, processing will stop once the upload budget has been exceeded.
+    ///
+    /// # Errors
+    ///
+    /// If an asset fails to load, the error that was encountered will be returned. The
+    /// failed asset will still count towards the loader's progress, and the other assets
+    /// will continue loading.
+    pub fn update(&mut self, ctx: &mut Context) -> Result {
+        let start = Instant::now();
+
+        while let Ok((id, decoded)) = self.results.try_recv() {
+            self.completed += 1;
+
+            let asset: Box<dyn Any> = match decoded? {
+                Decoded::Texture(data) => Box::new(Texture::from_loaded_image(ctx, data)?),
+
+                #[cfg(feature = "font_ttf")]
+                Decoded::Font(data, size) => {
+                    Box::new(VectorFontBuilder::from_vec(data)?.with_size(ctx, size)?)
+                }
+
+                #[cfg(feature = "audio")]
+                Decoded::Sound(sound) => Box::new(sound),
+            };
+
+            self.loaded.insert(id, asset);
+
+            if start.elapsed() >= self.upload_budget {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the asset for the given handle, if it has finished loading.
+    pub fn get<T>(&self, asset: &PendingAsset<T>) -> Option<T>
+    where
+        T: Clone + 'static,
+    {
+        self.loaded
+            .get(&asset.id)
+            .and_then(|a| a.downcast_ref::<T>())
+            .cloned()
+    }
+
+    /// Returns the number of assets that have finished loading (including
+    /// those that failed to load).
+    pub fn completed_count(&self) -> usize {
+        self.completed
+    }
+
+    /// Returns the total number of assets that have been queued for loading.
+    pub fn total_count(&self) -> usize {
+        self.next_id
+    }
+
+    /// Returns the proportion of queued assets that have finished loading, from
+    /// `0.0` to `1.0`.
+    ///
+    /// If no assets have been queued, this will return `1.0`.
+    pub fn progress(&self) -> f32 {
+        if self.next_id == 0 {
+            1.0
+        } else {
+            self.completed as f32 / self.next_id as f32
+        }
+    }
+
+    /// Returns `true` if all of the queued assets have finished loading.
+    pub fn is_finished(&self) -> bool {
+        self.completed == self.next_id
+    }
+
+    /// Returns the maximum amount of time that [`update`](AssetLoader::update) will
+    /// spend creating GPU resources each frame.
+    pub fn upload_budget(&self) -> Duration {
+        self.upload_budget
+    }
+
+    /// Sets the maximum amount of time that [`update`](AssetLoader::update) will
+    /// spend creating GPU resources each frame.
+    ///
+    /// Defaults to 4 milliseconds.
+    pub fn set_upload_budget(&mut self, budget: Duration) {
+        self.upload_budget = budget;
+    }
+}
+
+impl Default for AssetLoader {
+    fn default() -> AssetLoader {
+        AssetLoader::new()
+    }
+}
+
+fn worker(jobs: Arc<Mutex<Receiver<(usize, Job)>>>, results: Sender<(usize, Result<Decoded>)>) {
+    loop {
+        let next = jobs.lock().unwrap_or_else(|e| e.into_inner()).recv();
+
+        let (id, job) = match next {
+            Ok(next) => next,
+            Err(_) => return,
+        };
+
+        let decoded = match job {
+            Job::Texture(path) => ImageData::from_file(path).map(Decoded::Texture),
+
+            #[cfg(feature = "font_ttf")]
+            Job::Font(path, size) => fs::read(path).map(|data| Decoded::Font(data, size)),
+
+            #[cfg(feature = "audio")]
+            Job::Sound(path) => Sound::new(path).map(Decoded::Sound),
+        };
+
+        if results.send((id, decoded)).is_err() {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use super::*;
+
+    #[derive(Clone)]
+    struct TestAsset(Rc<Vec<u8>>);
+
+    impl Asset for TestAsset {
+        fn load(_: &mut Context, _: &Path) -> Result<TestAsset> {
+            unimplemented!()
+        }
+
+        fn handle_count(&self) -> usize {
+            Rc::strong_count(&self.0)
+        }
+
+        fn memory_usage(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[test]
+    fn unload_unused() {
+        let mut cache = AssetCache::new();
+
+        let used = TestAsset(Rc::new(vec![0; 16]));
+        cache.insert("used", used.clone());
+        cache.insert("unused", TestAsset(Rc::new(vec![0; 32])));
+
+        assert_eq!(cache.memory_usage(), 48);
+        assert_eq!(cache.unload_unused(), 1);
+
+        assert!(cache.contains("used"));
+        assert!(!cache.contains("unused"));
+        assert_eq!(cache.memory_usage(), 16);
+
+        drop(used);
+
+        assert_eq!(cache.unload_unused(), 1);
+        assert!(cache.is_empty());
+    }
+}
@@ -3,15 +3,24 @@
 use std::io::Cursor;
 use std::path::Path;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
-use std::sync::Arc;
-use std::time::Duration;
-
-use rodio::source::{Buffered, Empty};
-use rodio::{Decoder, Device as RodioDevice, Sample, Source};
-
+use std::sync::{Arc, Mutex, RwLock, Weak};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use cpal::traits::{DeviceTrait, EventLoopTrait, HostTrait};
+use cpal::{
+    Device as CpalDevice, EventLoop, Format, Sample as CpalSample, SampleRate, StreamData,
+    StreamId, UnknownTypeOutputBuffer,
+};
+use rodio::dynamic_mixer::{self, DynamicMixer, DynamicMixerController};
+use rodio::source::{Buffered, UniformSourceIterator};
+use rodio::{Decoder, Sample, Source};
+
+use crate::debug::{ResourceKind, TrackedResource};
 use crate::error::{Result, TetraError};
 use crate::fs;
-use crate::Context;
+use crate::math::Rng;
+use crate::{Context, ContextBuilder};
 
 /// Sound data that can be played back.
 ///
@@ -31,6 +40,14 @@ use crate::Context;
 /// | MP3 | `audio_mp3` | Yes |
 /// | FLAC | `audio_flac` | No |
 ///
+/// # Default Settings
+///
+/// Each `Sound` has a default volume and speed, which are used by [`play`](Sound::play),
+/// [`repeat`](Sound::repeat) and [`spawn`](Sound::spawn). It can also have a random
+/// amount of variation applied to the volume and speed of each instance, which stops
+/// sound effects that are played repeatedly (such as footsteps or gunshots) from
+/// sounding robotic.
+///
 /// # Performance
 ///
 /// When you create an instance of `Sound`, the audio data is loaded into memory. It is not
@@ -46,7 +63,48 @@ use crate::Context;
 /// example demonstrates how to play several different kinds of sound.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Sound {
-    pub(crate) data: Arc<[u8]>,
+    pub(crate) data: Arc<SoundData>,
+    bus: Option<AudioBus>,
+    volume: f32,
+    speed: f32,
+    volume_variation: f32,
+    speed_variation: f32,
+}
+
+/// The encoded data for a sound.
+///
+/// This is wrapped in a lock so that the data can be swapped out when hot-reloading,
+/// without invalidating existing handles. It's only accessed when a sound is
+/// played, so the overhead is negligible.
+#[derive(Debug)]
+pub(crate) struct SoundData {
+    bytes: RwLock<Arc<[u8]>>,
+    _tracker: TrackedResource,
+}
+
+impl SoundData {
+    #[track_caller]
+    fn new(bytes: Arc<[u8]>) -> SoundData {
+        SoundData {
+            bytes: RwLock::new(bytes),
+            _tracker: TrackedResource::new(ResourceKind::Sound, None),
+        }
+    }
+
+    pub(crate) fn bytes(&self) -> Arc<[u8]> {
+        Arc::clone(&self.bytes.read().unwrap_or_else(|e| e.into_inner()))
+    }
+
+    #[cfg_attr(not(feature = "hot_reload"), allow(dead_code))]
+    pub(crate) fn replace(&self, bytes: Arc<[u8]>) {
+        *self.bytes.write().unwrap_or_else(|e| e.into_inner()) = bytes;
+    }
+}
+
+impl PartialEq for SoundData {
+    fn eq(&self, other: &SoundData) -> bool {
+        self.bytes() == other.bytes()
+    }
 }
 
 impl Sound {
@@ -58,13 +116,26 @@ impl Sound {
     /// # Errors
     ///
     /// * [`TetraError::FailedToLoadAsset`] will be returned if the file could not be loaded.
+    #[track_caller]
     pub fn new<P>(path: P) -> Result<Sound>
     where
         P: AsRef<Path>,
     {
-        Ok(Sound {
-            data: fs::read(path)?.into(),
-        })
+        let path = path.as_ref();
+
+        let sound = Sound {
+            data: Arc::new(SoundData::new(fs::read(path)?.into())),
+            bus: None,
+            volume: 1.0,
+            speed: 1.0,
+            volume_variation: 0.0,
+            speed_variation: 0.0,
+        };
+
+        #[cfg(feature = "hot_reload")]
+        crate::hot_reload::watch_sound(path, &sound);
+
+        Ok(sound)
     }
 
     /// Creates a new sound from a slice of binary data, encoded in one of Tetra's supported
@@ -75,79 +146,221 @@ impl Sound {
     ///
     /// Note that the data is not decoded until playback begins, so this function will not
     /// validate that the data being read is formatted correctly.
+    #[track_caller]
     pub fn from_file_data(data: &[u8]) -> Sound {
-        Sound { data: data.into() }
+        Sound {
+            data: Arc::new(SoundData::new(data.into())),
+            bus: None,
+            volume: 1.0,
+            speed: 1.0,
+            volume_variation: 0.0,
+            speed_variation: 0.0,
+        }
+    }
+
+    /// Creates a new sound from decoded audio data.
+    ///
+    /// This can be used to play back audio that has been generated or processed by
+    /// your game. The data is stored as 16-bit WAV internally, so the `audio_wav`
+    /// feature must be enabled in order for the sound to be played.
+    #[track_caller]
+    pub fn from_audio_data(data: &AudioData) -> Sound {
+        Sound::from_file_data(&encode_wav(data))
+    }
+
+    /// Decodes the sound, returning the raw samples along with their format.
+    ///
+    /// This can be used to draw waveforms, analyze the audio (e.g. for beat detection),
+    /// or process it before creating a new sound via [`Sound::from_audio_data`].
+    ///
+    /// Decoding a sound is relatively expensive, and the result is not cached, so this
+    /// should not be called every frame.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::InvalidSound`] will be returned if the sound data could not be decoded.
+    pub fn decode(&self) -> Result<AudioData> {
+        let decoder =
+            Decoder::new(Cursor::new(self.data.bytes())).map_err(TetraError::InvalidSound)?;
+
+        let channels = decoder.channels();
+        let sample_rate = decoder.sample_rate();
+
+        // Some formats can change their channel count or sample rate partway through,
+        // so everything gets converted to the format of the first frame.
+        let samples: Vec<f32> =
+            UniformSourceIterator::<_, f32>::new(decoder, channels, sample_rate).collect();
+
+        Ok(AudioData {
+            samples,
+            channels,
+            sample_rate,
+        })
+    }
+
+    /// Returns the bus that instances of this sound will be played on, if one has been set.
+    pub fn bus(&self) -> Option<&AudioBus> {
+        self.bus.as_ref()
+    }
+
+    /// Sets the bus that instances of this sound will be played on.
+    ///
+    /// This only affects instances that are created after the bus is set - existing
+    /// instances will stay on the bus they were created with.
+    ///
+    /// Defaults to `None`.
+    pub fn set_bus(&mut self, bus: Option<AudioBus>) {
+        self.bus = bus;
+    }
+
+    /// Returns the default volume of the sound.
+    pub fn volume(&self) -> f32 {
+        self.volume
+    }
+
+    /// Sets the default volume of the sound, which will be used when it is played without
+    /// explicitly specifying a volume.
+    ///
+    /// The parameter is used as a multiplier - for example, `1.0` would result in the
+    /// sound being played back at its original volume.
+    ///
+    /// Defaults to `1.0`.
+    pub fn set_volume(&mut self, volume: f32) {
+        self.volume = volume;
+    }
+
+    /// Returns the default speed of the sound.
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Sets the default speed (and by extension, the pitch) of the sound, which will be
+    /// used when it is played without explicitly specifying a speed.
+    ///
+    /// The parameter is used as a multiplier - for example, `1.0` would result in the
+    /// sound being played back at its original speed.
+    ///
+    /// Defaults to `1.0`.
+    pub fn set_speed(&mut self, speed: f32) {
+        self.speed = speed;
+    }
+
+    /// Returns the amount of random variation that is applied to the volume of each instance.
+    pub fn volume_variation(&self) -> f32 {
+        self.volume_variation
+    }
+
+    /// Sets the amount of random variation that is applied to the volume of each instance.
+    ///
+    /// The parameter is a fraction of the volume that the instance is played at - for
+    /// example, `0.1` would result in a volume anywhere between 90% and 110% of the
+    /// original. This applies to all of the playback methods, including the ones that
+    /// take an explicit volume.
+    ///
+    /// Defaults to `0.0`.
+    pub fn set_volume_variation(&mut self, volume_variation: f32) {
+        self.volume_variation = volume_variation;
+    }
+
+    /// Returns the amount of random variation that is applied to the speed of each instance.
+    pub fn speed_variation(&self) -> f32 {
+        self.speed_variation
+    }
+
+    /// Sets the amount of random variation that is applied to the speed (and by extension,
+    /// the pitch) of each instance.
+    ///
+    /// The parameter is a fraction of the speed that the instance is played at - for
+    /// example, `0.1` would result in a speed anywhere between 90% and 110% of the
+    /// original. This applies to all of the playback methods, including the ones that
+    /// take an explicit speed.
+    ///
+    /// Defaults to `0.0`.
+    pub fn set_speed_variation(&mut self, speed_variation: f32) {
+        self.speed_variation = speed_variation;
     }
 
     /// Plays the sound.
     ///
+    /// The sound's [default volume and speed](Sound#default-settings) will be used.
+    ///
     /// # Errors
     ///
     /// * [`TetraError::NoAudioDevice`] will be returned if no audio device is active.
     /// * [`TetraError::InvalidSound`] will be returned if the sound data could not be decoded.
     pub fn play(&self, ctx: &Context) -> Result<SoundInstance> {
         ctx.audio
-            .play_sound(Arc::clone(&self.data), true, false, 1.0, 1.0)
+            .play_sound(self, true, false, self.volume, self.speed)
             .map(|controls| SoundInstance { controls })
     }
 
     /// Plays the sound repeatedly.
     ///
+    /// The sound's [default volume and speed](Sound#default-settings) will be used.
+    ///
     /// # Errors
     ///
     /// * [`TetraError::NoAudioDevice`] will be returned if no audio device is active.
     /// * [`TetraError::InvalidSound`] will be returned if the sound data could not be decoded.
     pub fn repeat(&self, ctx: &Context) -> Result<SoundInstance> {
         ctx.audio
-            .play_sound(Arc::clone(&self.data), true, true, 1.0, 1.0)
+            .play_sound(self, true, true, self.volume, self.speed)
             .map(|controls| SoundInstance { controls })
     }
 
     /// Spawns a new instance of the sound that is not playing yet.
     ///
+    /// The sound's [default volume and speed](Sound#default-settings) will be used.
+    ///
     /// # Errors
     ///
     /// * [`TetraError::NoAudioDevice`] will be returned if no audio device is active.
     /// * [`TetraError::InvalidSound`] will be returned if the sound data could not be decoded.
     pub fn spawn(&self, ctx: &Context) -> Result<SoundInstance> {
         ctx.audio
-            .play_sound(Arc::clone(&self.data), false, false, 1.0, 1.0)
+            .play_sound(self, false, false, self.volume, self.speed)
             .map(|controls| SoundInstance { controls })
     }
 
     /// Plays the sound, with the provided settings.
     ///
+    /// The provided settings are used instead of the sound's default volume and speed.
+    ///
     /// # Errors
     ///
     /// * [`TetraError::NoAudioDevice`] will be returned if no audio device is active.
     /// * [`TetraError::InvalidSound`] will be returned if the sound data could not be decoded.
     pub fn play_with(&self, ctx: &Context, volume: f32, speed: f32) -> Result<SoundInstance> {
         ctx.audio
-            .play_sound(Arc::clone(&self.data), true, false, volume, speed)
+            .play_sound(self, true, false, volume, speed)
             .map(|controls| SoundInstance { controls })
     }
 
     /// Plays the sound repeatedly, with the provided settings.
     ///
+    /// The provided settings are used instead of the sound's default volume and speed.
+    ///
     /// # Errors
     ///
     /// * [`TetraError::NoAudioDevice`] will be returned if no audio device is active.
     /// * [`TetraError::InvalidSound`] will be returned if the sound data could not be decoded.
     pub fn repeat_with(&self, ctx: &Context, volume: f32, speed: f32) -> Result<SoundInstance> {
         ctx.audio
-            .play_sound(Arc::clone(&self.data), true, true, volume, speed)
+            .play_sound(self, true, true, volume, speed)
             .map(|controls| SoundInstance { controls })
     }
 
     /// Spawns a new instance of the sound that is not playing yet, with the provided settings.
     ///
+    /// The provided settings are used instead of the sound's default volume and speed.
+    ///
     /// # Errors
     ///
     /// * [`TetraError::NoAudioDevice`] will be returned if no audio device is active.
     /// * [`TetraError::InvalidSound`] will be returned if the sound data could not be decoded.
     pub fn spawn_with(&self, ctx: &Context, volume: f32, speed: f32) -> Result<SoundInstance> {
         ctx.audio
-            .play_sound(Arc::clone(&self.data), false, false, volume, speed)
+            .play_sound(self, false, false, volume, speed)
             .map(|controls| SoundInstance { controls })
     }
 }
@@ -254,6 +467,94 @@ pub enum SoundState {
     Stopped,
 }
 
+/// Decoded audio samples, along with the format that they are stored in.
+///
+/// Samples are stored as `f32` values between `-1.0` and `1.0`. If there are multiple
+/// channels, the samples are interleaved (e.g. for stereo audio, the samples alternate
+/// between the left and right channels). A set of samples, one for each channel, is
+/// called a frame.
+///
+/// This can be obtained by decoding a [`Sound`] via [`Sound::decode`], or can be created
+/// from your own samples via [`AudioData::new`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioData {
+    samples: Vec<f32>,
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl AudioData {
+    /// Creates audio data from a set of interleaved samples.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `channels` or `sample_rate` is zero.
+    pub fn new(samples: Vec<f32>, channels: u16, sample_rate: u32) -> AudioData {
+        assert!(channels > 0, "audio data must have at least one channel");
+        assert!(
+            sample_rate > 0,
+            "audio data must have a non-zero sample rate"
+        );
+
+        AudioData {
+            samples,
+            channels,
+            sample_rate,
+        }
+    }
+
+    /// Returns the interleaved samples.
+    pub fn samples(&self) -> &[f32] {
+        &self.samples
+    }
+
+    /// Returns a mutable reference to the interleaved samples.
+    pub fn samples_mut(&mut self) -> &mut [f32] {
+        &mut self.samples
+    }
+
+    /// Returns the interleaved samples, consuming the audio data.
+    pub fn into_samples(self) -> Vec<f32> {
+        self.samples
+    }
+
+    /// Returns an iterator over the samples for a single channel.
+    ///
+    /// If the channel does not exist, the iterator will be empty.
+    pub fn channel(&self, channel: u16) -> impl Iterator<Item = f32> + '_ {
+        let start = if channel < self.channels {
+            channel as usize
+        } else {
+            self.samples.len()
+        };
+
+        self.samples[start..]
+            .iter()
+            .step_by(self.channels as usize)
+            .copied()
+    }
+
+    /// Returns the number of channels.
+    pub fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    /// Returns the sample rate, in frames per second.
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    /// Returns the number of frames.
+    pub fn frame_count(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    /// Returns the length of the audio.
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frame_count() as f64 / self.sample_rate as f64)
+    }
+}
+
 /// Sets the master volume for the game.
 ///
 /// The parameter is used as a multiplier - for example, `1.0` would result in
@@ -267,6 +568,523 @@ pub fn get_master_volume(ctx: &mut Context) -> f32 {
     ctx.audio.master_volume()
 }
 
+/// Pauses all audio output.
+///
+/// Unlike [`SoundInstance::pause`], this does not change the [`SoundState`] of any
+/// instances - they are held in place until [`resume_all`] is called, and then carry
+/// on from where they left off. Sounds that are played while audio is paused will
+/// also wait for it to be resumed.
+pub fn pause_all(ctx: &mut Context) {
+    ctx.audio.master.paused.store(true, Ordering::SeqCst);
+}
+
+/// Resumes audio output after a call to [`pause_all`].
+///
+/// Instances that were paused individually (or whose bus is paused) will stay paused.
+pub fn resume_all(ctx: &mut Context) {
+    ctx.audio.master.paused.store(false, Ordering::SeqCst);
+}
+
+/// Returns whether audio output has been paused via [`pause_all`].
+pub fn is_paused(ctx: &Context) -> bool {
+    ctx.audio.master.paused.load(Ordering::SeqCst)
+}
+
+/// Stops every sound instance that is currently playing or paused, including
+/// sounds that were 'fired and forgotten'.
+pub fn stop_all(ctx: &mut Context) {
+    stop_instances(&ctx.audio.instances);
+}
+
+/// Sets whether audio output should automatically be paused while the window
+/// does not have focus.
+///
+/// This is separate from [`pause_all`] - regaining focus will not resume audio
+/// that was paused manually.
+///
+/// This can also be set at startup via
+/// [`ContextBuilder::pause_audio_on_focus_loss`](crate::ContextBuilder::pause_audio_on_focus_loss).
+pub fn set_pause_on_focus_loss(ctx: &mut Context, pause_on_focus_loss: bool) {
+    ctx.audio.pause_on_focus_loss = pause_on_focus_loss;
+
+    if !pause_on_focus_loss {
+        ctx.audio.master.suspended.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Returns whether audio output will automatically be paused while the window
+/// does not have focus.
+pub fn is_paused_on_focus_loss(ctx: &Context) -> bool {
+    ctx.audio.pause_on_focus_loss
+}
+
+/// Settings for the limiter on the master output.
+///
+/// The limiter stops the combined output of all of the sounds that are playing from
+/// going above a certain level, which would otherwise cause harsh distortion (e.g.
+/// when lots of loud sounds play at once). When the output goes above the threshold,
+/// the volume is lowered instantly, and then recovers over the release time.
+///
+/// See [`set_limiter`] for how to configure this.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limiter {
+    /// The maximum level of the output, as a multiplier of full scale (so `1.0` is the
+    /// loudest level that can be output without clipping).
+    pub threshold: f32,
+
+    /// How long it takes for the volume to recover after the output goes above the threshold.
+    pub release: Duration,
+}
+
+impl Default for Limiter {
+    fn default() -> Limiter {
+        Limiter {
+            threshold: 1.0,
+            release: Duration::from_millis(100),
+        }
+    }
+}
+
+/// Settings for the compressor on the master output.
+///
+/// The compressor reduces the dynamic range of the output, by lowering the volume of
+/// anything louder than the threshold. Unlike the [`Limiter`], this is a gradual effect,
+/// which makes it better suited to evening out the overall mix. It runs before the limiter.
+///
+/// See [`set_compressor`] for how to configure this.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Compressor {
+    /// The level above which the volume will be reduced, as a multiplier of full scale.
+    pub threshold: f32,
+
+    /// How strongly the volume is reduced - for example, at a ratio of `4.0`, a level
+    /// that is 4 times as far above the threshold will only be 1 times as far above in
+    /// the output.
+    pub ratio: f32,
+
+    /// How long it takes for the compressor to react to the output getting louder.
+    pub attack: Duration,
+
+    /// How long it takes for the compressor to recover after the output gets quieter.
+    pub release: Duration,
+}
+
+impl Default for Compressor {
+    fn default() -> Compressor {
+        Compressor {
+            threshold: 0.5,
+            ratio: 4.0,
+            attack: Duration::from_millis(10),
+            release: Duration::from_millis(200),
+        }
+    }
+}
+
+/// Sets the limiter that is applied to the master output, or disables it if `None`
+/// is passed.
+///
+/// By default, the master output has a limiter with a threshold of `1.0` and a release
+/// time of 100 milliseconds, which only has an effect when the output would otherwise
+/// clip.
+pub fn set_limiter(ctx: &mut Context, limiter: Option<Limiter>) {
+    ctx.audio.update_dynamics(|d| d.limiter = limiter);
+}
+
+/// Gets the limiter that is applied to the master output, if any.
+pub fn get_limiter(ctx: &Context) -> Option<Limiter> {
+    ctx.audio.dynamics().limiter
+}
+
+/// Sets the compressor that is applied to the master output, or disables it if `None`
+/// is passed.
+///
+/// The master output does not have a compressor by default.
+pub fn set_compressor(ctx: &mut Context, compressor: Option<Compressor>) {
+    ctx.audio.update_dynamics(|d| d.compressor = compressor);
+}
+
+/// Gets the compressor that is applied to the master output, if any.
+pub fn get_compressor(ctx: &Context) -> Option<Compressor> {
+    ctx.audio.dynamics().compressor
+}
+
+/// The algorithm that is used to convert sounds to the sample rate of the audio output.
+///
+/// This also applies when the speed of a [`SoundInstance`] is changed, as that is
+/// implemented by resampling the sound.
+///
+/// The resampler quality can be set via
+/// [`ContextBuilder::audio_resampler_quality`](crate::ContextBuilder::audio_resampler_quality).
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum ResamplerQuality {
+    /// Each output sample is taken from the nearest input sample.
+    ///
+    /// This is the cheapest option, but introduces audible artifacts, especially when
+    /// the sample rates are very different.
+    Nearest,
+
+    /// Output samples are linearly interpolated between the two nearest input samples.
+    ///
+    /// This is a good balance between quality and performance.
+    Linear,
+
+    /// Output samples are interpolated from the four nearest input samples, using a
+    /// cubic curve.
+    ///
+    /// This gives the smoothest result, but is the most expensive option.
+    Cubic,
+}
+
+/// Information about the audio output that is being used.
+///
+/// This can be retrieved via [`get_output_info`]. The format may not match what was
+/// requested via the [`ContextBuilder`], as the audio device may not support it.
+#[non_exhaustive]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioOutputInfo {
+    /// The name of the audio device.
+    pub device_name: String,
+
+    /// The sample rate of the output, in hertz.
+    pub sample_rate: u32,
+
+    /// The number of channels in the output.
+    pub channels: u16,
+
+    /// The algorithm that is used to convert sounds to the output's sample rate.
+    pub resampler_quality: ResamplerQuality,
+}
+
+/// Returns information about the audio output that was negotiated with the audio device.
+///
+/// Returns `None` if no audio device is active.
+pub fn get_output_info(ctx: &Context) -> Option<AudioOutputInfo> {
+    ctx.audio.output.as_ref().map(|o| o.info.clone())
+}
+
+/// A group of sounds that can be controlled together, such as music, sound
+/// effects or dialogue.
+///
+/// A [`Sound`] can be assigned to a bus via [`Sound::set_bus`], and any instances
+/// that are created from it afterwards will be affected by the bus' volume, and
+/// can be paused or stopped along with the rest of the bus.
+///
+/// Cloning an `AudioBus` will create a new handle to the same bus, rather than
+/// creating a new bus.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::audio::{AudioBus, Sound};
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// let sfx = AudioBus::new();
+///
+/// let mut jump = Sound::new("./examples/resources/jump.wav")?;
+/// jump.set_bus(Some(sfx.clone()));
+/// jump.play(ctx)?;
+///
+/// // Later, when the game is paused:
+/// sfx.pause();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct AudioBus {
+    controls: Arc<BusControls>,
+}
+
+impl AudioBus {
+    /// Creates a new bus, with a volume of `1.0`.
+    pub fn new() -> AudioBus {
+        AudioBus {
+            controls: Arc::new(BusControls {
+                volume: AtomicU32::new(1.0f32.to_bits()),
+                paused: AtomicBool::new(false),
+                instances: Mutex::new(Vec::new()),
+                active: AtomicU32::new(0),
+                ducking: Mutex::new(None),
+                duck_level: AtomicU32::new(1.0f32.to_bits()),
+            }),
+        }
+    }
+
+    /// Returns the volume of the bus.
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.controls.volume.load(Ordering::SeqCst))
+    }
+
+    /// Sets the volume of the bus.
+    ///
+    /// The parameter is used as a multiplier, on top of the volume of each instance
+    /// and the master volume.
+    pub fn set_volume(&self, volume: f32) {
+        self.controls
+            .volume
+            .store(volume.to_bits(), Ordering::SeqCst);
+    }
+
+    /// Pauses all of the sounds on the bus.
+    ///
+    /// As with [`pause_all`], this does not change the [`SoundState`] of the instances
+    /// on the bus.
+    pub fn pause(&self) {
+        self.controls.paused.store(true, Ordering::SeqCst);
+    }
+
+    /// Resumes the sounds on the bus after a call to [`pause`](Self::pause).
+    pub fn resume(&self) {
+        self.controls.paused.store(false, Ordering::SeqCst);
+    }
+
+    /// Returns whether the bus is paused.
+    pub fn is_paused(&self) -> bool {
+        self.controls.paused.load(Ordering::SeqCst)
+    }
+
+    /// Stops every sound instance on the bus.
+    pub fn stop(&self) {
+        stop_instances(&self.controls.instances);
+    }
+
+    /// Makes this bus automatically duck (lower its volume) while any sounds are
+    /// playing on the `trigger` bus.
+    ///
+    /// This is commonly used to make dialogue easier to hear, by ducking the music
+    /// bus whenever the voice bus is active.
+    ///
+    /// * `amount` is how much the volume should be lowered by, from `0.0` (not at all)
+    ///   to `1.0` (silenced entirely).
+    /// * `attack` is how long it takes to fully duck once the trigger bus becomes active.
+    /// * `release` is how long it takes to return to full volume once the trigger bus
+    ///   goes quiet.
+    ///
+    /// A bus can only be ducked by one trigger at a time - calling this method again
+    /// will replace the existing settings. A bus cannot duck itself.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use std::time::Duration;
+    /// # use tetra::audio::AudioBus;
+    /// let music = AudioBus::new();
+    /// let voice = AudioBus::new();
+    ///
+    /// music.set_ducking(&voice, 0.6, Duration::from_millis(100), Duration::from_millis(800));
+    /// ```
+    pub fn set_ducking(
+        &self,
+        trigger: &AudioBus,
+        amount: f32,
+        attack: Duration,
+        release: Duration,
+    ) {
+        if self == trigger {
+            return;
+        }
+
+        let mut ducking = self
+            .controls
+            .ducking
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        let level = ducking.as_ref().map_or(1.0, |d| d.level);
+
+        *ducking = Some(DuckState {
+            trigger: Arc::downgrade(&trigger.controls),
+            amount: amount.clamp(0.0, 1.0),
+            attack,
+            release,
+            level,
+            last_update: Instant::now(),
+        });
+    }
+
+    /// Stops this bus from being ducked, immediately returning it to full volume.
+    pub fn clear_ducking(&self) {
+        *self
+            .controls
+            .ducking
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+        self.controls
+            .duck_level
+            .store(1.0f32.to_bits(), Ordering::SeqCst);
+    }
+
+    /// Returns the multiplier that ducking is currently applying to the bus' volume.
+    ///
+    /// This will be `1.0` if the bus is not being ducked.
+    pub fn ducking_level(&self) -> f32 {
+        f32::from_bits(self.controls.duck_level.load(Ordering::SeqCst))
+    }
+}
+
+impl Default for AudioBus {
+    fn default() -> AudioBus {
+        AudioBus::new()
+    }
+}
+
+impl PartialEq for AudioBus {
+    fn eq(&self, other: &AudioBus) -> bool {
+        Arc::ptr_eq(&self.controls, &other.controls)
+    }
+}
+
+#[derive(Debug)]
+struct BusControls {
+    volume: AtomicU32,
+    paused: AtomicBool,
+    instances: Mutex<Vec<Weak<AudioControls>>>,
+
+    /// The number of sources on this bus that are currently audible.
+    active: AtomicU32,
+
+    ducking: Mutex<Option<DuckState>>,
+    duck_level: AtomicU32,
+}
+
+impl BusControls {
+    /// Advances the ducking envelope, and returns the resulting volume multiplier.
+    ///
+    /// This is called by every source on the bus when they sync with their controls, so
+    /// if the lock is already held, the last calculated level is good enough.
+    fn update_ducking(&self) -> f32 {
+        if let Ok(mut ducking) = self.ducking.try_lock() {
+            if let Some(state) = ducking.as_mut() {
+                let level = state.update(Instant::now());
+                self.duck_level.store(level.to_bits(), Ordering::SeqCst);
+            }
+        }
+
+        f32::from_bits(self.duck_level.load(Ordering::SeqCst))
+    }
+}
+
+#[derive(Debug)]
+struct DuckState {
+    // This is weak so that buses which duck each other don't keep each other alive.
+    trigger: Weak<BusControls>,
+    amount: f32,
+    attack: Duration,
+    release: Duration,
+    level: f32,
+    last_update: Instant,
+}
+
+impl DuckState {
+    fn update(&mut self, now: Instant) -> f32 {
+        let elapsed = now.saturating_duration_since(self.last_update);
+        self.last_update = now;
+
+        let triggered =
+            matches!(self.trigger.upgrade(), Some(t) if t.active.load(Ordering::SeqCst) > 0);
+
+        let (target, time) = if triggered {
+            (1.0 - self.amount, self.attack)
+        } else {
+            (1.0, self.release)
+        };
+
+        // The level moves linearly, taking `time` to cover the full ducking range.
+        let step = if time.as_secs_f32() > 0.0 {
+            self.amount * elapsed.as_secs_f32() / time.as_secs_f32()
+        } else {
+            f32::INFINITY
+        };
+
+        self.level = if self.level > target {
+            (self.level - step).max(target)
+        } else {
+            (self.level + step).min(target)
+        };
+
+        self.level
+    }
+}
+
+#[derive(Debug)]
+struct MasterControls {
+    volume: AtomicU32,
+    paused: AtomicBool,
+    suspended: AtomicBool,
+    dynamics: Mutex<DynamicsSettings>,
+    dynamics_changed: AtomicBool,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct DynamicsSettings {
+    compressor: Option<Compressor>,
+    limiter: Option<Limiter>,
+}
+
+fn encode_wav(data: &AudioData) -> Vec<u8> {
+    let block_align = data.channels * 2;
+    let byte_rate = data.sample_rate * block_align as u32;
+    let data_len = (data.samples.len() * 2) as u32;
+
+    let mut wav = Vec::with_capacity(44 + data_len as usize);
+
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data_len).to_le_bytes());
+    wav.extend_from_slice(b"WAVE");
+
+    wav.extend_from_slice(b"fmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&data.channels.to_le_bytes());
+    wav.extend_from_slice(&data.sample_rate.to_le_bytes());
+    wav.extend_from_slice(&byte_rate.to_le_bytes());
+    wav.extend_from_slice(&block_align.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data_len.to_le_bytes());
+
+    for sample in &data.samples {
+        let sample = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+
+    wav
+}
+
+fn vary(rng: &mut Rng, value: f32, variation: f32) -> f32 {
+    if variation == 0.0 {
+        value
+    } else {
+        value * (1.0 + variation * (rng.next_f32() * 2.0 - 1.0))
+    }
+}
+
+fn register_instance(instances: &Mutex<Vec<Weak<AudioControls>>>, controls: &Arc<AudioControls>) {
+    let mut instances = instances.lock().unwrap_or_else(|e| e.into_inner());
+
+    // Instances are only freed once their source has finished playing, so this
+    // is a good time to clean up the ones that are no longer needed.
+    instances.retain(|i| i.strong_count() > 0);
+    instances.push(Arc::downgrade(controls));
+}
+
+fn stop_instances(instances: &Mutex<Vec<Weak<AudioControls>>>) {
+    let instances = instances.lock().unwrap_or_else(|e| e.into_inner());
+
+    for controls in instances.iter().filter_map(Weak::upgrade) {
+        controls.set_state(SoundState::Stopped);
+    }
+}
+
 #[derive(Debug)]
 struct AudioControls {
     playing: AtomicBool,
@@ -319,41 +1137,211 @@ impl AudioControls {
     }
 }
 
+struct AudioOutput {
+    event_loop: Arc<EventLoop>,
+    stream: StreamId,
+    mixer: Arc<DynamicMixerController<f32>>,
+    info: AudioOutputInfo,
+}
+
+impl AudioOutput {
+    fn new(settings: &ContextBuilder, master: &Arc<MasterControls>) -> Option<AudioOutput> {
+        let host = cpal::default_host();
+        let device = host.default_output_device()?;
+        let event_loop = Arc::new(host.event_loop());
+
+        let requested = choose_format(&device, settings.audio_sample_rate, settings.audio_channels);
+        let default = device.default_output_format().ok();
+
+        // If the requested format is rejected by the device, fall back to its preferred one.
+        let (format, stream) = requested.into_iter().chain(default).find_map(|format| {
+            event_loop
+                .build_output_stream(&device, &format)
+                .ok()
+                .map(|stream| (format, stream))
+        })?;
+
+        event_loop.play_stream(stream.clone()).ok()?;
+
+        let (controller, mixer) = dynamic_mixer::mixer(format.channels, format.sample_rate.0);
+        let mut source = MasterSource::new(mixer, Arc::clone(master));
+
+        let thread_loop = Arc::clone(&event_loop);
+
+        // The event loop never returns, so the thread is detached. Dropping the output
+        // destroys the stream, which stops the callback from being called.
+        thread::Builder::new()
+            .name("tetra audio".into())
+            .spawn(move || {
+                #[cfg(feature = "profiling_support")]
+                profiling::register_thread!("tetra audio");
+
+                thread_loop.run(move |_, data| {
+                    if let Ok(StreamData::Output { buffer }) = data {
+                        write_output(&mut source, buffer);
+                    }
+                })
+            })
+            .ok()?;
+
+        Some(AudioOutput {
+            event_loop,
+            stream,
+            mixer: controller,
+            info: AudioOutputInfo {
+                device_name: device.name().unwrap_or_default(),
+                sample_rate: format.sample_rate.0,
+                channels: format.channels,
+                resampler_quality: settings.audio_resampler_quality,
+            },
+        })
+    }
+}
+
+impl Drop for AudioOutput {
+    fn drop(&mut self) {
+        self.event_loop.destroy_stream(self.stream.clone());
+    }
+}
+
+/// Picks the output format that best matches the requested settings, or `None` if the
+/// device doesn't support them.
+fn choose_format(
+    device: &CpalDevice,
+    sample_rate: Option<u32>,
+    channels: Option<u16>,
+) -> Option<Format> {
+    if sample_rate.is_none() && channels.is_none() {
+        return None;
+    }
+
+    let default = device.default_output_format().ok()?;
+    let sample_rate = sample_rate.unwrap_or(default.sample_rate.0);
+    let channels = channels.unwrap_or(default.channels);
+
+    device
+        .supported_output_formats()
+        .ok()?
+        .filter(|f| {
+            f.channels == channels
+                && f.min_sample_rate.0 <= sample_rate
+                && f.max_sample_rate.0 >= sample_rate
+        })
+        // Prefer the device's default sample format, to avoid an extra conversion.
+        .max_by_key(|f| f.data_type == default.data_type)
+        .map(|f| Format {
+            channels,
+            sample_rate: SampleRate(sample_rate),
+            data_type: f.data_type,
+        })
+}
+
+/// Fills an output buffer from the mixer. All of the decoding, resampling and mixing
+/// happens in here, on the audio thread.
+#[cfg_attr(feature = "profiling_support", profiling::function)]
+fn write_output(source: &mut MasterSource, buffer: UnknownTypeOutputBuffer<'_>) {
+    match buffer {
+        UnknownTypeOutputBuffer::F32(mut buffer) => {
+            for sample in buffer.iter_mut() {
+                *sample = source.next().unwrap_or(0.0);
+            }
+        }
+        UnknownTypeOutputBuffer::I16(mut buffer) => {
+            for sample in buffer.iter_mut() {
+                *sample = source.next().unwrap_or(0.0).to_i16();
+            }
+        }
+        UnknownTypeOutputBuffer::U16(mut buffer) => {
+            for sample in buffer.iter_mut() {
+                *sample = source.next().unwrap_or(0.0).to_u16();
+            }
+        }
+    }
+}
+
 pub(crate) struct AudioDevice {
-    device: Option<RodioDevice>,
-    master_volume: Arc<AtomicU32>,
+    output: Option<AudioOutput>,
+    rng: Mutex<Rng>,
+    master: Arc<MasterControls>,
+    instances: Mutex<Vec<Weak<AudioControls>>>,
+    pause_on_focus_loss: bool,
 }
 
 impl AudioDevice {
-    pub(crate) fn new() -> AudioDevice {
-        let device = rodio::default_output_device();
+    pub(crate) fn new(settings: &ContextBuilder) -> AudioDevice {
+        let master = Arc::new(MasterControls {
+            volume: AtomicU32::new(1.0f32.to_bits()),
+            paused: AtomicBool::new(false),
+            suspended: AtomicBool::new(false),
+            dynamics: Mutex::new(DynamicsSettings {
+                compressor: None,
+                limiter: Some(Limiter::default()),
+            }),
+            dynamics_changed: AtomicBool::new(true),
+        });
 
-        if let Some(active_device) = &device {
-            rodio::play_raw(active_device, Empty::new());
-        }
+        let output = AudioOutput::new(settings, &master);
 
         AudioDevice {
-            device,
-            master_volume: Arc::new(AtomicU32::new(1.0f32.to_bits())),
+            output,
+            rng: Mutex::new(Rng::from_entropy()),
+            master,
+            instances: Mutex::new(Vec::new()),
+            pause_on_focus_loss: settings.pause_audio_on_focus_loss,
         }
     }
 
     fn master_volume(&self) -> f32 {
-        f32::from_bits(self.master_volume.load(Ordering::SeqCst))
+        f32::from_bits(self.master.volume.load(Ordering::SeqCst))
     }
 
     fn set_master_volume(&self, volume: f32) {
-        self.master_volume.store(volume.to_bits(), Ordering::SeqCst);
+        self.master.volume.store(volume.to_bits(), Ordering::SeqCst);
+    }
+
+    fn dynamics(&self) -> DynamicsSettings {
+        *self
+            .master
+            .dynamics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
     }
 
+    fn update_dynamics(&self, f: impl FnOnce(&mut DynamicsSettings)) {
+        f(&mut self
+            .master
+            .dynamics
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()));
+        self.master.dynamics_changed.store(true, Ordering::SeqCst);
+    }
+
+    pub(crate) fn set_focused(&self, focused: bool) {
+        if self.pause_on_focus_loss {
+            self.master.suspended.store(!focused, Ordering::SeqCst);
+        }
+    }
+
+    #[cfg_attr(feature = "profiling_support", profiling::function)]
     fn play_sound(
         &self,
-        data: Arc<[u8]>,
+        sound: &Sound,
         playing: bool,
         repeating: bool,
         volume: f32,
         speed: f32,
     ) -> Result<Arc<AudioControls>> {
+        let bus = sound.bus.as_ref();
+
+        let (volume, speed) = {
+            let mut rng = self.rng.lock().unwrap_or_else(|e| e.into_inner());
+
+            (
+                vary(&mut rng, volume, sound.volume_variation),
+                vary(&mut rng, speed, sound.speed_variation),
+            )
+        };
+
         let controls = Arc::new(AudioControls {
             playing: AtomicBool::new(playing),
             repeating: AtomicBool::new(repeating),
@@ -362,9 +1350,9 @@ impl AudioDevice {
             speed: AtomicU32::new(speed.to_bits()),
         });
 
-        let master_volume = f32::from_bits(self.master_volume.load(Ordering::SeqCst));
+        let master_volume = self.master_volume() * bus.map_or(1.0, AudioBus::volume);
 
-        let data = Decoder::new(Cursor::new(data))
+        let data = Decoder::new(Cursor::new(sound.data.bytes()))
             .map_err(TetraError::InvalidSound)?
             .buffered();
 
@@ -372,11 +1360,14 @@ impl AudioDevice {
             repeat_source: data.clone(),
             data,
 
-            remote_master_volume: Arc::clone(&self.master_volume),
+            remote_master: Arc::clone(&self.master),
+            remote_bus: bus.map(|b| Arc::clone(&b.controls)),
             remote_controls: Arc::clone(&controls),
             time_till_update: 220,
 
             detached: false,
+            audible: false,
+            suspended: self.is_suspended(bus),
             playing,
             repeating,
             rewind: false,
@@ -385,13 +1376,28 @@ impl AudioDevice {
             speed,
         };
 
-        rodio::play_raw(
-            self.device.as_ref().ok_or(TetraError::NoAudioDevice)?,
+        let output = self.output.as_ref().ok_or(TetraError::NoAudioDevice)?;
+
+        output.mixer.add(Resampler::new(
             source.convert_samples(),
-        );
+            output.info.resampler_quality,
+            output.info.sample_rate,
+        ));
+
+        register_instance(&self.instances, &controls);
+
+        if let Some(bus) = bus {
+            register_instance(&bus.controls.instances, &controls);
+        }
 
         Ok(controls)
     }
+
+    fn is_suspended(&self, bus: Option<&AudioBus>) -> bool {
+        self.master.paused.load(Ordering::SeqCst)
+            || self.master.suspended.load(Ordering::SeqCst)
+            || matches!(bus, Some(b) if b.is_paused())
+    }
 }
 
 type TetraSourceData = Buffered<Decoder<Cursor<Arc<[u8]>>>>;
@@ -400,11 +1406,14 @@ struct TetraSource {
     data: TetraSourceData,
     repeat_source: TetraSourceData,
 
-    remote_master_volume: Arc<AtomicU32>,
+    remote_master: Arc<MasterControls>,
+    remote_bus: Option<Arc<BusControls>>,
     remote_controls: Arc<AudioControls>,
     time_till_update: u32,
 
     detached: bool,
+    audible: bool,
+    suspended: bool,
     playing: bool,
     repeating: bool,
     rewind: bool,
@@ -425,9 +1434,20 @@ impl Iterator for TetraSource {
         self.time_till_update -= 1;
 
         if self.time_till_update == 0 {
-            self.master_volume = f32::from_bits(self.remote_master_volume.load(Ordering::SeqCst));
+            self.master_volume = f32::from_bits(self.remote_master.volume.load(Ordering::SeqCst));
+            self.suspended = self.remote_master.paused.load(Ordering::SeqCst)
+                || self.remote_master.suspended.load(Ordering::SeqCst);
+
+            if let Some(bus) = &self.remote_bus {
+                self.master_volume *= f32::from_bits(bus.volume.load(Ordering::SeqCst));
+                self.master_volume *= bus.update_ducking();
+                self.suspended |= bus.paused.load(Ordering::SeqCst);
+            }
+
             self.playing = self.remote_controls.playing.load(Ordering::SeqCst);
 
+            self.set_audible(self.playing && !self.suspended);
+
             // If we're not playing, we don't really care about updating the rest of the state.
             if self.playing {
                 self.repeating = self.remote_controls.repeating.load(Ordering::SeqCst);
@@ -449,6 +1469,11 @@ impl Iterator for TetraSource {
             return if self.detached { None } else { Some(0) };
         }
 
+        // Pausing the bus (or all audio) holds the sound in place, rather than changing its state.
+        if self.suspended {
+            return Some(0);
+        }
+
         if self.rewind {
             self.data = self.repeat_source.clone();
             self.rewind = false;
@@ -491,6 +1516,29 @@ impl Iterator for TetraSource {
     }
 }
 
+impl TetraSource {
+    /// Keeps the bus' count of audible sources up to date, for the purposes of ducking.
+    fn set_audible(&mut self, audible: bool) {
+        if audible != self.audible {
+            if let Some(bus) = &self.remote_bus {
+                if audible {
+                    bus.active.fetch_add(1, Ordering::SeqCst);
+                } else {
+                    bus.active.fetch_sub(1, Ordering::SeqCst);
+                }
+            }
+
+            self.audible = audible;
+        }
+    }
+}
+
+impl Drop for TetraSource {
+    fn drop(&mut self) {
+        self.set_audible(false);
+    }
+}
+
 impl Source for TetraSource {
     #[inline]
     fn current_frame_len(&self) -> Option<usize> {
@@ -521,3 +1569,378 @@ impl Source for TetraSource {
         None
     }
 }
+
+/// The final stage of the mix, which applies effects to the combined output of all
+/// of the sounds.
+struct MasterSource {
+    mixer: DynamicMixer<f32>,
+    remote_master: Arc<MasterControls>,
+    time_till_update: u32,
+    dynamics: Dynamics,
+}
+
+impl MasterSource {
+    fn new(mixer: DynamicMixer<f32>, remote_master: Arc<MasterControls>) -> MasterSource {
+        // Coefficients are applied per sample, rather than per frame.
+        let rate = mixer.sample_rate() as f32 * mixer.channels() as f32;
+
+        MasterSource {
+            mixer,
+            remote_master,
+            time_till_update: 1,
+            dynamics: Dynamics::new(rate),
+        }
+    }
+}
+
+impl Iterator for MasterSource {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        self.time_till_update -= 1;
+
+        if self.time_till_update == 0 {
+            if self.remote_master.dynamics_changed.load(Ordering::SeqCst) {
+                // If the main thread is currently changing the settings, we'll pick them
+                // up next time around rather than blocking the audio thread.
+                if let Ok(settings) = self.remote_master.dynamics.try_lock() {
+                    self.dynamics.settings = *settings;
+                    self.remote_master
+                        .dynamics_changed
+                        .store(false, Ordering::SeqCst);
+                }
+            }
+
+            self.time_till_update = 220;
+        }
+
+        // The mixer runs out of samples when no sounds are playing, but the output
+        // needs to keep going, as more sounds may be played later.
+        let sample = self.mixer.next().unwrap_or(0.0);
+
+        Some(self.dynamics.process(sample))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+impl Source for MasterSource {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.mixer.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.mixer.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Converts a source to the output sample rate.
+///
+/// Rodio's mixer can do this as well, but it only supports linear interpolation, and
+/// only picks up changes to the source's sample rate (i.e. its speed) between frames.
+struct Resampler<S> {
+    source: S,
+    quality: ResamplerQuality,
+    channels: usize,
+    output_rate: u32,
+
+    /// The four most recent input frames, oldest first. Output is interpolated
+    /// between the second and third frames.
+    frames: Vec<f32>,
+
+    /// How many of the frames at the end of `frames` are padding, after the source has ended.
+    padding: usize,
+
+    position: f32,
+    output: Vec<f32>,
+    output_index: usize,
+}
+
+impl<S> Resampler<S>
+where
+    S: Source<Item = f32>,
+{
+    fn new(source: S, quality: ResamplerQuality, output_rate: u32) -> Resampler<S> {
+        let channels = source.channels().max(1) as usize;
+
+        let mut resampler = Resampler {
+            source,
+            quality,
+            channels,
+            output_rate,
+            frames: vec![0.0; channels * 4],
+            padding: 0,
+            position: 0.0,
+            output: vec![0.0; channels],
+            output_index: channels,
+        };
+
+        for _ in 0..3 {
+            resampler.read_frame();
+        }
+
+        // Duplicate the first frame, rather than fading in from silence.
+        let (first, rest) = resampler.frames.split_at_mut(channels);
+        first.copy_from_slice(&rest[..channels]);
+
+        resampler
+    }
+
+    fn read_frame(&mut self) {
+        self.frames.copy_within(self.channels.., 0);
+
+        let start = self.channels * 3;
+        let mut ended = false;
+
+        for sample in &mut self.frames[start..] {
+            *sample = match self.source.next() {
+                Some(value) if !ended => value,
+                _ => {
+                    ended = true;
+                    0.0
+                }
+            };
+        }
+
+        if ended {
+            self.padding += 1;
+        }
+    }
+
+    fn interpolate(&mut self) {
+        let t = self.position;
+        let c = self.channels;
+
+        for (i, out) in self.output.iter_mut().enumerate() {
+            let p0 = self.frames[i];
+            let p1 = self.frames[c + i];
+            let p2 = self.frames[c * 2 + i];
+            let p3 = self.frames[c * 3 + i];
+
+            *out = match self.quality {
+                ResamplerQuality::Nearest => {
+                    if t < 0.5 {
+                        p1
+                    } else {
+                        p2
+                    }
+                }
+                ResamplerQuality::Linear => p1 + (p2 - p1) * t,
+                ResamplerQuality::Cubic => {
+                    let a = -0.5 * p0 + 1.5 * p1 - 1.5 * p2 + 0.5 * p3;
+                    let b = p0 - 2.5 * p1 + 2.0 * p2 - 0.5 * p3;
+                    let c = -0.5 * p0 + 0.5 * p2;
+
+                    ((a * t + b) * t + c) * t + p1
+                }
+            };
+        }
+    }
+}
+
+impl<S> Iterator for Resampler<S>
+where
+    S: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.output_index == self.channels {
+            // Once the second frame is padding, all of the real input has been output.
+            if self.padding >= 3 {
+                return None;
+            }
+
+            self.interpolate();
+            self.output_index = 0;
+
+            self.position += self.source.sample_rate() as f32 / self.output_rate as f32;
+
+            while self.position >= 1.0 && self.padding < 3 {
+                self.read_frame();
+                self.position -= 1.0;
+            }
+        }
+
+        let sample = self.output[self.output_index];
+        self.output_index += 1;
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+impl<S> Source for Resampler<S>
+where
+    S: Source<Item = f32>,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.output_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+struct Dynamics {
+    settings: DynamicsSettings,
+    rate: f32,
+    envelope: f32,
+    limiter_gain: f32,
+}
+
+impl Dynamics {
+    fn new(rate: f32) -> Dynamics {
+        Dynamics {
+            settings: DynamicsSettings::default(),
+            rate,
+            envelope: 0.0,
+            limiter_gain: 1.0,
+        }
+    }
+
+    /// Returns the coefficient for a one-pole filter that takes roughly `time`
+    /// to settle.
+    fn coefficient(&self, time: Duration) -> f32 {
+        let samples = time.as_secs_f32() * self.rate;
+
+        if samples > 0.0 {
+            (-1.0 / samples).exp()
+        } else {
+            0.0
+        }
+    }
+
+    fn process(&mut self, mut sample: f32) -> f32 {
+        if let Some(compressor) = self.settings.compressor {
+            let level = sample.abs();
+
+            let coefficient = if level > self.envelope {
+                self.coefficient(compressor.attack)
+            } else {
+                self.coefficient(compressor.release)
+            };
+
+            self.envelope = level + coefficient * (self.envelope - level);
+
+            if self.envelope > compressor.threshold && compressor.ratio > 0.0 {
+                let compressed = compressor.threshold
+                    + (self.envelope - compressor.threshold) / compressor.ratio;
+
+                sample *= compressed / self.envelope;
+            }
+        }
+
+        if let Some(limiter) = self.settings.limiter {
+            let coefficient = self.coefficient(limiter.release);
+            self.limiter_gain = 1.0 - (1.0 - self.limiter_gain) * coefficient;
+
+            let level = sample.abs();
+
+            if level * self.limiter_gain > limiter.threshold {
+                self.limiter_gain = limiter.threshold / level;
+            }
+
+            sample *= self.limiter_gain;
+        }
+
+        sample
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audio_data_channels() {
+        let data = AudioData::new(vec![0.0, 1.0, 0.5, -1.0, 0.25, 0.0], 2, 44100);
+
+        assert_eq!(data.frame_count(), 3);
+        assert_eq!(data.channel(1).collect::<Vec<_>>(), vec![1.0, -1.0, 0.0]);
+        assert_eq!(data.channel(2).count(), 0);
+    }
+
+    #[cfg(feature = "audio_wav")]
+    #[test]
+    fn audio_data_round_trip() {
+        let data = AudioData::new(vec![0.0, 0.5, -0.5, 1.0], 1, 22050);
+        let decoded = Sound::from_audio_data(&data).decode().unwrap();
+
+        assert_eq!(decoded.channels(), 1);
+        assert_eq!(decoded.sample_rate(), 22050);
+        assert_eq!(decoded.frame_count(), 4);
+        assert!((decoded.samples()[1] - 0.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn resampler_rates() {
+        use rodio::buffer::SamplesBuffer;
+
+        let input = SamplesBuffer::new(2, 22050, vec![0.5f32; 200]);
+        let output: Vec<f32> = Resampler::new(input, ResamplerQuality::Cubic, 44100).collect();
+
+        assert_eq!(output.len(), 400);
+
+        // The last few frames are interpolated towards silence, as the sound has ended.
+        assert!(output[..392].iter().all(|s| (s - 0.5).abs() < 0.001));
+
+        let input = SamplesBuffer::new(1, 44100, vec![0.25f32; 100]);
+        let output: Vec<f32> = Resampler::new(input, ResamplerQuality::Linear, 22050).collect();
+
+        assert_eq!(output.len(), 50);
+    }
+
+    #[test]
+    fn limiter_prevents_clipping() {
+        let mut dynamics = Dynamics::new(44100.0);
+        dynamics.settings.limiter = Some(Limiter::default());
+
+        assert_eq!(dynamics.process(0.5), 0.5);
+        assert!(dynamics.process(3.0) <= 1.0);
+
+        // The gain should recover gradually, rather than jumping back to full volume.
+        let after = dynamics.process(0.5);
+        assert!(after < 0.5 && after > 0.0);
+
+        for _ in 0..44100 {
+            dynamics.process(0.0);
+        }
+
+        assert!((dynamics.process(0.5) - 0.5).abs() < 0.001);
+    }
+}
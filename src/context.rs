@@ -1,15 +1,27 @@
+use std::any::Any;
+use std::fmt::Display;
+use std::panic::{self, AssertUnwindSafe};
 use std::result;
-use std::thread;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
+use crate::debug;
+use crate::error_screen::{self, ErrorScreen};
 use crate::graphics::{self, GraphicsContext};
+use crate::i18n::I18nContext;
 use crate::input::{self, InputContext};
+use crate::lifecycle::{self, EventFilter};
 use crate::platform::{self, GraphicsDevice, Window};
 use crate::time::{self, TimeContext, Timestep};
-use crate::{Result, State, TetraError};
+use crate::{Event, EventKind, EventMode, EventSender, Result, State, TetraError};
 
 #[cfg(feature = "audio")]
-use crate::audio::AudioDevice;
+use crate::audio::{AudioDevice, ResamplerQuality};
+
+#[cfg(feature = "hot_reload")]
+use crate::hot_reload::{self, HotReloadContext};
 
 /// A struct containing all of the 'global' state within the framework.
 pub struct Context {
@@ -20,19 +32,51 @@ pub struct Context {
     pub(crate) graphics: GraphicsContext,
     pub(crate) input: InputContext,
     pub(crate) time: TimeContext,
+    pub(crate) i18n: I18nContext,
+
+    #[cfg(feature = "hot_reload")]
+    pub(crate) hot_reload: HotReloadContext,
+
+    pub(crate) events: EventFilter,
+    pub(crate) custom_event_sender: Sender<Arc<dyn Any + Send + Sync>>,
+    pub(crate) custom_event_receiver: Receiver<Arc<dyn Any + Send + Sync>>,
 
     pub(crate) running: bool,
+    pub(crate) exit_code: i32,
     pub(crate) quit_on_escape: bool,
+    pub(crate) show_on_run: bool,
+
+    #[cfg(feature = "overlay_support")]
+    pub(crate) overlay_active: bool,
 }
 
 impl Context {
+    #[cfg_attr(
+        feature = "tracing_support",
+        tracing::instrument(name = "context_init", skip_all, fields(title = %settings.title))
+    )]
     pub(crate) fn new(settings: &ContextBuilder) -> Result<Context> {
+        debug::set_tracking_resources(settings.track_resources);
+
         // This needs to be initialized ASAP to avoid https://github.com/tomaka/rodio/issues/214
         #[cfg(feature = "audio")]
-        let audio = AudioDevice::new();
+        let audio = AudioDevice::new(settings);
 
         let (window, gl_context, window_width, window_height) = Window::new(settings)?;
-        let mut device = GraphicsDevice::new(gl_context)?;
+        let mut device = GraphicsDevice::new(gl_context, settings.graphics_debug)?;
+
+        #[cfg(feature = "tracing_support")]
+        {
+            let device_info = device.get_info();
+
+            tracing::info!(
+                vendor = %device_info.vendor,
+                renderer = %device_info.renderer,
+                opengl_version = %device_info.opengl_version,
+                glsl_version = %device_info.glsl_version,
+                "created graphics device"
+            );
+        }
 
         if settings.debug_info {
             let device_info = device.get_info();
@@ -43,9 +87,16 @@ impl Context {
             println!("GLSL Version: {}", device_info.glsl_version);
         }
 
-        let graphics = GraphicsContext::new(&mut device, window_width, window_height)?;
+        let graphics = GraphicsContext::new(
+            &mut device,
+            window_width,
+            window_height,
+            settings.multisampling,
+            settings.stencil_buffer,
+        )?;
         let input = InputContext::new();
         let time = TimeContext::new(settings.timestep);
+        let (custom_event_sender, custom_event_receiver) = mpsc::channel();
 
         Ok(Context {
             window,
@@ -56,9 +107,22 @@ impl Context {
             graphics,
             input,
             time,
+            i18n: I18nContext::new(),
+
+            #[cfg(feature = "hot_reload")]
+            hot_reload: HotReloadContext::new(),
+
+            events: EventFilter::new(),
+            custom_event_sender,
+            custom_event_receiver,
 
             running: false,
+            exit_code: 0,
             quit_on_escape: settings.quit_on_escape,
+            show_on_run: settings.visible,
+
+            #[cfg(feature = "overlay_support")]
+            overlay_active: false,
         })
     }
 
@@ -79,6 +143,11 @@ impl Context {
     /// or [`event`](State::event), the game will stop running and this method will
     /// return the error.
     ///
+    /// If the game was quit via [`window::quit_with_result`](crate::window::quit_with_result),
+    /// the exit code can be retrieved afterwards via
+    /// [`window::get_exit_code`](crate::window::get_exit_code), or you can use
+    /// [`run_with_exit_code`](Self::run_with_exit_code) to have it returned directly.
+    ///
     /// # Examples
     ///
     /// ```no_run
@@ -109,23 +178,374 @@ impl Context {
         F: FnOnce(&mut Context) -> result::Result<S, E>,
         E: From<TetraError>,
     {
-        let state = &mut init(self)?;
+        let mut state = init(self)?;
+
+        self.start_running();
+
+        let output = self.game_loop(&mut state);
+
+        // Anything that was dropped by the state being torn down won't get cleaned
+        // up by `present`, so we do it here instead.
+        drop(state);
+        self.stop_running();
+
+        output
+    }
+
+    /// Runs the game, and returns the exit code that it was quit with.
+    ///
+    /// This behaves the same as [`run`](Self::run), but returns the code that was passed to
+    /// [`window::quit_with_result`](crate::window::quit_with_result) once the game stops
+    /// running (or `0`, if the game was quit in any other way).
+    ///
+    /// # Errors
+    ///
+    /// If the [`State`] returns an error from [`update`](State::update), [`draw`](State::draw)
+    /// or [`event`](State::event), the game will stop running and this method will
+    /// return the error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use tetra::{ContextBuilder, State};
+    /// # struct GameState;
+    /// # impl State for GameState {}
+    /// fn main() -> tetra::Result {
+    ///     let exit_code = ContextBuilder::new("Hello, world!", 1280, 720)
+    ///         .build()?
+    ///         .run_with_exit_code(|_| Ok(GameState))?;
+    ///
+    ///     std::process::exit(exit_code);
+    /// }
+    /// ```
+    pub fn run_with_exit_code<S, F, E>(&mut self, init: F) -> result::Result<i32, E>
+    where
+        S: State<E>,
+        F: FnOnce(&mut Context) -> result::Result<S, E>,
+        E: From<TetraError>,
+    {
+        self.run(init)?;
+
+        Ok(self.exit_code)
+    }
+
+    /// Runs the game, displaying a loading state while the game's data is prepared
+    /// on a background thread.
+    ///
+    /// This allows you to show something (e.g. a logo or a progress spinner) on the very
+    /// first frame, rather than leaving the player looking at an empty window while a
+    /// slow [`State`] constructor runs. It works in three stages:
+    ///
+    /// * The `loader` closure is called to create the loading state. This should be
+    ///   quick, as nothing will be shown until it returns.
+    /// * The `load` closure is run on a background thread, while the loading state is
+    ///   updated and drawn as normal. This is where slow, CPU-bound work (such as reading
+    ///   files and decoding images via [`ImageData`](crate::graphics::ImageData)) should go.
+    ///   As it runs on another thread, it does not have access to the [`Context`].
+    /// * Once loading has finished, the loading state is dropped, and the `init` closure is
+    ///   called with the loaded data to create the game's main state. This runs on the main
+    ///   thread, so it can create textures and other GPU resources from the data.
+    ///
+    /// If the player closes the window while loading is in progress, this method will
+    /// return without waiting for the background thread to finish.
+    ///
+    /// # Errors
+    ///
+    /// If any of the closures return an error, or either of the states returns an error
+    /// from [`update`](State::update), [`draw`](State::draw) or [`event`](State::event),
+    /// the game will stop running and this method will return the error.
+    ///
+    /// # Panics
+    ///
+    /// If the `load` closure panics, the panic will be propagated to the main thread.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tetra::graphics::{ImageData, Texture};
+    /// use tetra::{Context, ContextBuilder, State};
+    ///
+    /// struct LoadingScreen;
+    ///
+    /// impl State for LoadingScreen {}
+    ///
+    /// struct GameState {
+    ///     player: Texture,
+    /// }
+    ///
+    /// impl State for GameState {}
+    ///
+    /// fn main() -> tetra::Result {
+    ///     ContextBuilder::new("Hello, world!", 1280, 720)
+    ///         .build()?
+    ///         .run_with_loader(
+    ///             |_| Ok(LoadingScreen),
+    ///             || ImageData::from_file("./examples/resources/player.png"),
+    ///             |ctx, player| {
+    ///                 Ok(GameState {
+    ///                     player: player.to_texture(ctx)?,
+    ///                 })
+    ///             },
+    ///         )
+    /// }
+    /// ```
+    pub fn run_with_loader<L, LF, T, F, S, I, E>(
+        &mut self,
+        loader: LF,
+        load: F,
+        init: I,
+    ) -> result::Result<(), E>
+    where
+        L: State<E>,
+        LF: FnOnce(&mut Context) -> result::Result<L, E>,
+        T: Send + 'static,
+        F: FnOnce() -> result::Result<T, E> + Send + 'static,
+        S: State<E>,
+        I: FnOnce(&mut Context, T) -> result::Result<S, E>,
+        E: From<TetraError> + Send + 'static,
+    {
+        let mut loader = loader(self)?;
+
+        let (sender, receiver) = mpsc::channel();
+
+        let handle = thread::spawn(move || {
+            // The receiver will have been dropped if the game was closed while loading,
+            // in which case there's nobody left to care about the result.
+            let _ = sender.send(load());
+        });
+
+        self.start_running();
+
+        let loaded = self.loader_loop(&mut loader, &receiver, handle);
+
+        drop(loader);
+
+        let output = match loaded {
+            Ok(Some(data)) => init(self, data).and_then(|mut state| {
+                // The time spent loading shouldn't count towards the first update.
+                time::reset(self);
+
+                let output = self.game_loop(&mut state);
+                drop(state);
+
+                output
+            }),
+
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+
+        self.stop_running();
+
+        output
+    }
+
+    /// Runs the game, displaying an error screen in the window if it crashes.
+    ///
+    /// This works the same as [`run`](Self::run), but if the `init` closure or the
+    /// [`State`] returns an error, or panics, the game window will stay open and show
+    /// the error message, rather than silently closing. The player can then copy the
+    /// message to their clipboard (via a button, or by pressing Ctrl+C), which makes
+    /// it much easier for them to report the problem.
+    ///
+    /// Once the player closes the window (or presses Escape), this method will return
+    /// the original error, or resume the original panic.
+    ///
+    /// Note that the error screen is drawn using Tetra's own graphics code - if the
+    /// error was caused by the graphics device itself, the screen may fail to display,
+    /// in which case the error will be returned straight away.
+    ///
+    /// # Errors
+    ///
+    /// If the `init` closure returns an error, or the [`State`] returns an error from
+    /// [`update`](State::update), [`draw`](State::draw) or [`event`](State::event),
+    /// this method will return the error once the error screen has been closed.
+    ///
+    /// # Panics
+    ///
+    /// If the `init` closure or the [`State`] panics, the panic will be resumed once the
+    /// error screen has been closed.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use tetra::{Context, ContextBuilder, State};
+    ///
+    /// struct GameState;
+    ///
+    /// impl GameState {
+    ///     fn new(ctx: &mut Context) -> tetra::Result<GameState> {
+    ///         Ok(GameState)
+    ///     }
+    /// }
+    ///
+    /// impl State for GameState {}
+    ///
+    /// fn main() -> tetra::Result {
+    ///     ContextBuilder::new("Hello, world!", 1280, 720)
+    ///         .build()?
+    ///         .run_with_error_screen(GameState::new)
+    /// }
+    /// ```
+    pub fn run_with_error_screen<S, F, E>(&mut self, init: F) -> result::Result<(), E>
+    where
+        S: State<E>,
+        F: FnOnce(&mut Context) -> result::Result<S, E>,
+        E: From<TetraError> + Display,
+    {
+        // The panic hook is the only place that gets to see the panic's location, so
+        // we temporarily wrap it to capture the full message.
+        let panic_message = Arc::new(Mutex::new(None));
+        let previous_hook = Arc::new(panic::take_hook());
+
+        {
+            let panic_message = Arc::clone(&panic_message);
+            let previous_hook = Arc::clone(&previous_hook);
+            let game_thread = thread::current().id();
+
+            panic::set_hook(Box::new(move |info| {
+                if thread::current().id() == game_thread {
+                    if let Ok(mut message) = panic_message.lock() {
+                        *message = Some(info.to_string());
+                    }
+                }
+
+                previous_hook(info);
+            }));
+        }
+
+        let output = panic::catch_unwind(AssertUnwindSafe(|| {
+            let mut state = init(self)?;
+
+            self.start_running();
+
+            let output = self.game_loop(&mut state);
+            drop(state);
+
+            output
+        }));
+
+        // Dropping our hook releases its reference to the previous one, so we can
+        // put it back exactly as it was.
+        drop(panic::take_hook());
+
+        match Arc::try_unwrap(previous_hook) {
+            Ok(previous_hook) => panic::set_hook(previous_hook),
+            Err(previous_hook) => panic::set_hook(Box::new(move |info| previous_hook(info))),
+        }
+
+        let (title, message) = match &output {
+            Ok(Ok(())) => {
+                self.stop_running();
+                return Ok(());
+            }
+
+            Ok(Err(e)) => ("The game encountered an error:", e.to_string()),
+
+            Err(_) => (
+                "The game crashed:",
+                panic_message
+                    .lock()
+                    .ok()
+                    .and_then(|mut message| message.take())
+                    .unwrap_or_else(|| String::from("An unknown panic occurred.")),
+            ),
+        };
+
+        // The game may have stopped part way through a frame, so we need to make sure
+        // the graphics state isn't left in a weird configuration.
+        error_screen::reset_graphics(self);
+
+        match ErrorScreen::new(self, title, message) {
+            Ok(mut screen) => {
+                self.running = true;
+                self.window.set_visible(true);
+
+                if let Err(e) = self.game_loop::<_, TetraError>(&mut screen) {
+                    log::error!(target: "tetra", "Failed to display error screen: {}", e);
+                }
+            }
+
+            Err(e) => {
+                log::error!(target: "tetra", "Failed to create error screen: {}", e);
+            }
+        }
+
+        self.stop_running();
+
+        match output {
+            Ok(output) => output,
+            Err(payload) => panic::resume_unwind(payload),
+        }
+    }
+
+    /// Queues a custom event, which will be passed to [`State::event`] as an
+    /// [`Event::Custom`] at the start of the next frame.
+    ///
+    /// To send events from other threads, use [`event_sender`](Self::event_sender).
+    pub fn push_event<T>(&mut self, data: T)
+    where
+        T: Any + Send + Sync,
+    {
+        // The receiver is owned by the context, so this can't fail.
+        let _ = self.custom_event_sender.send(Arc::new(data));
+    }
+
+    /// Returns an [`EventSender`], which can be used to send custom events to the game
+    /// from any thread.
+    pub fn event_sender(&self) -> EventSender {
+        EventSender {
+            sender: self.custom_event_sender.clone(),
+        }
+    }
+
+    /// Sets how events of the given kind should be delivered to [`State::event`].
+    ///
+    /// This can be used to reduce the overhead of handling high-frequency events (such
+    /// as mouse motion or gamepad axis movement) in states that don't need every one of
+    /// them, by either combining them or disabling them entirely. See [`EventMode`] for
+    /// the available options.
+    ///
+    /// All kinds of event are [`EventMode::Enabled`] by default.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use tetra::{Context, EventKind, EventMode};
+    /// # fn example(ctx: &mut Context) {
+    /// // Only receive the final mouse position each frame.
+    /// ctx.set_event_mode(EventKind::MouseMoved, EventMode::Coalesced);
+    ///
+    /// // Ignore analog stick movement while in a menu.
+    /// ctx.set_event_mode(EventKind::GamepadAxisMoved, EventMode::Disabled);
+    /// ctx.set_event_mode(EventKind::GamepadStickMoved, EventMode::Disabled);
+    /// # }
+    /// ```
+    pub fn set_event_mode(&mut self, kind: EventKind, mode: EventMode) {
+        self.events.set_mode(kind, mode);
+    }
 
+    /// Returns how events of the given kind are delivered to [`State::event`].
+    pub fn event_mode(&self, kind: EventKind) -> EventMode {
+        self.events.mode(kind)
+    }
+
+    fn start_running(&mut self) {
         time::reset(self);
 
         self.running = true;
-        self.window.set_visible(true);
+        self.exit_code = 0;
 
-        let mut output = Ok(());
-
-        if let Err(e) = self.game_loop(state) {
-            output = Err(e);
+        if self.show_on_run {
+            self.window.set_visible(true);
         }
+    }
+
+    fn stop_running(&mut self) {
+        self.device.delete_pending_resources();
 
         self.running = false;
         self.window.set_visible(false);
-
-        output
     }
 
     pub(crate) fn game_loop<S, E>(&mut self, state: &mut S) -> result::Result<(), E>
@@ -136,46 +556,168 @@ impl Context {
         let mut last_time = Instant::now();
 
         while self.running {
-            let curr_time = Instant::now();
-            let diff_time = curr_time - last_time;
-            last_time = curr_time;
-
-            self.time.fps_tracker.push(diff_time);
+            if let Err(e) = self.frame(state, &mut last_time) {
+                // The state still gets a chance to save before being torn down, but the
+                // original error is more useful to the caller than anything `on_exit`
+                // might return.
+                let _ = state.on_exit(self);
+                return Err(e);
+            }
+        }
 
-            platform::handle_events(self, state)?;
+        state.on_exit(self)
+    }
 
-            match self.time.tick_rate {
-                Some(tick_rate) => {
-                    self.time.delta_time = tick_rate;
-                    self.time.accumulator = (self.time.accumulator + diff_time).min(tick_rate * 8);
+    /// Runs the loading state until the background thread sends back its result,
+    /// or the game is closed.
+    fn loader_loop<L, T, E>(
+        &mut self,
+        loader: &mut L,
+        receiver: &Receiver<result::Result<T, E>>,
+        handle: JoinHandle<()>,
+    ) -> result::Result<Option<T>, E>
+    where
+        L: State<E>,
+        E: From<TetraError>,
+    {
+        let mut last_time = Instant::now();
 
-                    while self.time.accumulator >= tick_rate {
-                        state.update(self)?;
-                        input::clear(self);
+        while self.running {
+            match receiver.try_recv() {
+                Ok(result) => return result.map(Some),
 
-                        self.time.accumulator -= tick_rate;
+                Err(TryRecvError::Disconnected) => {
+                    // The sender only gets dropped without sending if the thread panicked.
+                    if let Err(payload) = handle.join() {
+                        panic::resume_unwind(payload);
                     }
 
-                    self.time.delta_time = diff_time;
+                    unreachable!("loading thread exited without sending a result");
                 }
 
-                None => {
-                    self.time.delta_time = diff_time;
+                Err(TryRecvError::Empty) => {}
+            }
+
+            if let Err(e) = self.frame(loader, &mut last_time) {
+                let _ = loader.on_exit(self);
+                return Err(e);
+            }
+        }
+
+        // The game was closed before loading finished, so the loading state is the one
+        // that needs a chance to clean up.
+        loader.on_exit(self)?;
+
+        Ok(None)
+    }
+
+    fn frame<S, E>(&mut self, state: &mut S, last_time: &mut Instant) -> result::Result<(), E>
+    where
+        S: State<E>,
+        E: From<TetraError>,
+    {
+        #[cfg(feature = "tracing_support")]
+        let _frame_span = tracing::debug_span!("frame").entered();
+
+        #[cfg(feature = "profiling_support")]
+        profiling::scope!("frame");
+
+        let curr_time = Instant::now();
+        let diff_time = curr_time - *last_time;
+        *last_time = curr_time;
+
+        self.time.fps_tracker.push(diff_time);
+        time::finish_stage_timings(self, diff_time);
+
+        let step_time = time::get_step_time(self, diff_time);
+
+        {
+            #[cfg(feature = "tracing_support")]
+            let _span = tracing::debug_span!("handle_events").entered();
+
+            #[cfg(feature = "profiling_support")]
+            profiling::scope!("handle_events");
+
+            platform::handle_events(self, state)?;
+            lifecycle::flush_coalesced(self, state)?;
+
+            // Only the events that were already queued are handled, so that a state which
+            // pushes an event in response to another can't stall the frame.
+            let custom_events: Vec<_> = self.custom_event_receiver.try_iter().collect();
+
+            for data in custom_events {
+                lifecycle::dispatch(self, state, Event::Custom { data })?;
+            }
+        }
+
+        #[cfg(feature = "hot_reload")]
+        hot_reload::poll(self);
+
+        let update_start = Instant::now();
+        self.time.current_stages.events = update_start - curr_time;
+
+        match self.time.tick_rate {
+            Some(tick_rate) => {
+                self.time.delta_time = tick_rate;
+                self.time.accumulator = (self.time.accumulator + step_time).min(tick_rate * 8);
+
+                while self.time.accumulator >= tick_rate {
+                    #[cfg(feature = "tracing_support")]
+                    let _span = tracing::debug_span!("update").entered();
+
+                    #[cfg(feature = "profiling_support")]
+                    profiling::scope!("update");
 
                     state.update(self)?;
                     input::clear(self);
+
+                    self.time.accumulator -= tick_rate;
                 }
+
+                self.time.delta_time = step_time;
             }
 
-            state.draw(self)?;
+            None => {
+                self.time.delta_time = step_time;
 
-            graphics::present(self);
+                #[cfg(feature = "tracing_support")]
+                let _span = tracing::debug_span!("update").entered();
 
-            // This provides a sensible FPS limit when running without vsync, and
-            // avoids CPU usage skyrocketing on some systems.
-            thread::sleep(Duration::from_millis(1));
+                #[cfg(feature = "profiling_support")]
+                profiling::scope!("update");
+
+                state.update(self)?;
+                input::clear(self);
+            }
         }
 
+        let draw_start = Instant::now();
+        self.time.current_stages.update = draw_start - update_start;
+
+        {
+            #[cfg(feature = "tracing_support")]
+            let _span = tracing::debug_span!("draw").entered();
+
+            #[cfg(feature = "profiling_support")]
+            profiling::scope!("draw");
+
+            state.draw(self)?;
+        }
+
+        let present_start = Instant::now();
+        self.time.current_stages.draw = present_start - draw_start;
+
+        graphics::present(self);
+
+        self.time.current_stages.present = present_start.elapsed();
+
+        #[cfg(feature = "profiling_support")]
+        profiling::finish_frame!();
+
+        // This provides a sensible FPS limit when running without vsync, and
+        // avoids CPU usage skyrocketing on some systems.
+        thread::sleep(Duration::from_millis(1));
+
         Ok(())
     }
 }
@@ -206,6 +748,7 @@ pub struct ContextBuilder {
     pub(crate) fullscreen: bool,
     pub(crate) maximized: bool,
     pub(crate) minimized: bool,
+    pub(crate) visible: bool,
     pub(crate) resizable: bool,
     pub(crate) borderless: bool,
     pub(crate) multisampling: u8,
@@ -216,8 +759,18 @@ pub struct ContextBuilder {
     pub(crate) show_mouse: bool,
     pub(crate) grab_mouse: bool,
     pub(crate) relative_mouse_mode: bool,
+    pub(crate) raw_input: bool,
     pub(crate) quit_on_escape: bool,
     pub(crate) debug_info: bool,
+    pub(crate) graphics_debug: bool,
+    pub(crate) track_resources: bool,
+    pub(crate) pause_audio_on_focus_loss: bool,
+    #[cfg(feature = "audio")]
+    pub(crate) audio_sample_rate: Option<u32>,
+    #[cfg(feature = "audio")]
+    pub(crate) audio_channels: Option<u16>,
+    #[cfg(feature = "audio")]
+    pub(crate) audio_resampler_quality: ResamplerQuality,
 }
 
 impl ContextBuilder {
@@ -300,6 +853,20 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets whether or not the window should be shown when the game starts running.
+    ///
+    /// The window is always kept hidden while the `init` closure passed to
+    /// [`Context::run`] is running. If this is set to `false`, it will stay hidden
+    /// after that, until [`window::set_visible`](crate::window::set_visible) is called.
+    /// This allows you to finish loading, and to size and position the window, before
+    /// the player sees it.
+    ///
+    /// Defaults to `true`.
+    pub fn visible(&mut self, visible: bool) -> &mut ContextBuilder {
+        self.visible = visible;
+        self
+    }
+
     /// Sets whether or not the window should be resizable.
     ///
     /// Defaults to `false`.
@@ -324,6 +891,10 @@ impl ContextBuilder {
     /// Note that this setting only applies to the main backbuffer - multisampled canvases can
     /// be created via [`Canvas::builder`](crate::graphics::Canvas::builder).
     ///
+    /// This setting cannot be lowered once the window has been created. If you want to let
+    /// players change it while the game is running, leave this at `0` and use
+    /// [`graphics::set_backbuffer_samples`](crate::graphics::set_backbuffer_samples) instead.
+    ///
     /// Defaults to `0`.
     pub fn multisampling(&mut self, multisampling: u8) -> &mut ContextBuilder {
         self.multisampling = multisampling;
@@ -423,6 +994,23 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets whether or not [`Event::RawInput`](crate::lifecycle::Event::RawInput) events
+    /// should be fired.
+    ///
+    /// Raw input events are tagged with the mouse or keyboard that they came from, which
+    /// allows you to tell multiple devices apart (e.g. for local multiplayer games where
+    /// each player has their own mouse). They are fired in addition to the normal
+    /// input events.
+    ///
+    /// Raw input events are fired very frequently, so they are disabled by default. See
+    /// [`RawInput`](crate::input::RawInput) for details of which platforms support them.
+    ///
+    /// Defaults to `false`.
+    pub fn raw_input(&mut self, raw_input: bool) -> &mut ContextBuilder {
+        self.raw_input = raw_input;
+        self
+    }
+
     /// Sets whether or not the game should close when the Escape key is pressed.
     ///
     /// Defaults to `false`.
@@ -438,6 +1026,95 @@ impl ContextBuilder {
         self
     }
 
+    /// Sets whether or not the graphics device should be created in debug mode.
+    ///
+    /// When enabled (and supported by the driver), any errors or warnings reported
+    /// by OpenGL will be logged via the [`log`](https://docs.rs/log) crate, under the
+    /// `tetra::graphics` target. The objects that Tetra creates (textures, canvases,
+    /// buffers, shaders) will also be labelled, making them easier to identify in
+    /// tools such as RenderDoc.
+    ///
+    /// This has a performance cost, so it should usually only be enabled during development.
+    ///
+    /// Defaults to `false`.
+    pub fn graphics_debug(&mut self, graphics_debug: bool) -> &mut ContextBuilder {
+        self.graphics_debug = graphics_debug;
+        self
+    }
+
+    /// Sets whether or not Tetra should keep track of where resources are created.
+    ///
+    /// When enabled, the location in your code that created each texture, canvas,
+    /// shader and sound will be recorded, and the resources that are still alive can
+    /// be listed via [`debug::get_live_resources`] and [`debug::log_live_resources`].
+    /// This is useful for tracking down handles that are being accidentally kept alive.
+    ///
+    /// Only resources created after the context is built will be tracked.
+    ///
+    /// This has a small performance cost when creating resources, so it should
+    /// usually only be enabled during development.
+    ///
+    /// Defaults to `false`.
+    pub fn track_resources(&mut self, track_resources: bool) -> &mut ContextBuilder {
+        self.track_resources = track_resources;
+        self
+    }
+
+    /// Sets whether or not audio should automatically be paused while the window does
+    /// not have focus.
+    ///
+    /// This can also be changed at runtime via
+    /// [`audio::set_pause_on_focus_loss`](crate::audio::set_pause_on_focus_loss).
+    ///
+    /// Defaults to `false`.
+    pub fn pause_audio_on_focus_loss(
+        &mut self,
+        pause_audio_on_focus_loss: bool,
+    ) -> &mut ContextBuilder {
+        self.pause_audio_on_focus_loss = pause_audio_on_focus_loss;
+        self
+    }
+
+    /// Sets the sample rate that the audio output should use, in hertz.
+    ///
+    /// Lower sample rates use less CPU, while the device's preferred sample rate avoids
+    /// having to resample the mix again in the OS. If the audio device does not support
+    /// the requested sample rate, its preferred sample rate will be used instead - the
+    /// sample rate that is actually in use can be checked via
+    /// [`audio::get_output_info`](crate::audio::get_output_info).
+    ///
+    /// Defaults to `None`, which uses the device's preferred sample rate.
+    #[cfg(feature = "audio")]
+    pub fn audio_sample_rate(&mut self, audio_sample_rate: Option<u32>) -> &mut ContextBuilder {
+        self.audio_sample_rate = audio_sample_rate;
+        self
+    }
+
+    /// Sets the number of channels that the audio output should use.
+    ///
+    /// If the audio device does not support the requested number of channels, its preferred
+    /// channel count will be used instead - the channel count that is actually in use can
+    /// be checked via [`audio::get_output_info`](crate::audio::get_output_info).
+    ///
+    /// Defaults to `None`, which uses the device's preferred channel count.
+    #[cfg(feature = "audio")]
+    pub fn audio_channels(&mut self, audio_channels: Option<u16>) -> &mut ContextBuilder {
+        self.audio_channels = audio_channels;
+        self
+    }
+
+    /// Sets the algorithm that is used to convert sounds to the audio output's sample rate.
+    ///
+    /// Defaults to [`ResamplerQuality::Linear`].
+    #[cfg(feature = "audio")]
+    pub fn audio_resampler_quality(
+        &mut self,
+        audio_resampler_quality: ResamplerQuality,
+    ) -> &mut ContextBuilder {
+        self.audio_resampler_quality = audio_resampler_quality;
+        self
+    }
+
     /// Builds the context.
     ///
     /// # Errors
@@ -459,6 +1136,7 @@ impl Default for ContextBuilder {
             fullscreen: false,
             maximized: false,
             minimized: false,
+            visible: true,
             resizable: false,
             borderless: false,
             multisampling: 0,
@@ -469,8 +1147,18 @@ impl Default for ContextBuilder {
             show_mouse: false,
             grab_mouse: false,
             relative_mouse_mode: false,
+            raw_input: false,
             quit_on_escape: false,
             debug_info: false,
+            graphics_debug: false,
+            track_resources: false,
+            pause_audio_on_focus_loss: false,
+            #[cfg(feature = "audio")]
+            audio_sample_rate: None,
+            #[cfg(feature = "audio")]
+            audio_channels: None,
+            #[cfg(feature = "audio")]
+            audio_resampler_quality: ResamplerQuality::Linear,
         }
     }
 }
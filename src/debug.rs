@@ -0,0 +1,203 @@
+//! Functions and types for diagnosing resource leaks.
+//!
+//! Tetra's resource handles (such as [`Texture`](crate::graphics::Texture) and
+//! [`Sound`](crate::audio::Sound)) are reference counted, so the underlying resource is
+//! only freed once every clone of the handle has been dropped. This makes it easy to
+//! accidentally keep a resource alive by holding onto a stray clone.
+//!
+//! When [resource tracking](crate::ContextBuilder::track_resources) is enabled, Tetra
+//! will record where each resource was created, and you can then use
+//! [`get_live_resources`] or [`log_live_resources`] to find out which ones are still
+//! alive, and where they came from.
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Display, Formatter};
+use std::panic::Location;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use once_cell::sync::Lazy;
+
+static TRACKING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+static REGISTRY: Lazy<Mutex<Registry>> = Lazy::new(|| Mutex::new(Registry::default()));
+
+#[derive(Debug, Default)]
+struct Registry {
+    next_id: u64,
+    live: BTreeMap<u64, LiveResource>,
+}
+
+/// The different kinds of resource that can be tracked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ResourceKind {
+    /// A [`Texture`](crate::graphics::Texture).
+    Texture,
+
+    /// A [`Canvas`](crate::graphics::Canvas).
+    Canvas,
+
+    /// A [`Cubemap`](crate::graphics::Cubemap).
+    Cubemap,
+
+    /// A [`Shader`](crate::graphics::Shader).
+    Shader,
+
+    /// A [`Sound`](crate::audio::Sound).
+    Sound,
+}
+
+impl Display for ResourceKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ResourceKind::Texture => write!(f, "Texture"),
+            ResourceKind::Canvas => write!(f, "Canvas"),
+            ResourceKind::Cubemap => write!(f, "Cubemap"),
+            ResourceKind::Shader => write!(f, "Shader"),
+            ResourceKind::Sound => write!(f, "Sound"),
+        }
+    }
+}
+
+/// Information about a resource that is currently alive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct LiveResource {
+    /// A unique ID for the resource. IDs are assigned in the order that resources
+    /// are created.
+    pub id: u64,
+
+    /// The kind of resource.
+    pub kind: ResourceKind,
+
+    /// The location in your code where the resource was created.
+    ///
+    /// If the resource was created internally by Tetra (e.g. the default texture, or
+    /// the glyph cache of a font), this will point to the Tetra source code instead.
+    pub location: &'static Location<'static>,
+
+    /// The width and height of the resource, in pixels, if it is an image.
+    pub dimensions: Option<(i32, i32)>,
+}
+
+impl Display for LiveResource {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "#{} {}", self.id, self.kind)?;
+
+        if let Some((width, height)) = self.dimensions {
+            write!(f, " ({}x{})", width, height)?;
+        }
+
+        write!(f, " created at {}", self.location)
+    }
+}
+
+/// Returns whether resource tracking is currently enabled.
+///
+/// This can be enabled via [`ContextBuilder::track_resources`](crate::ContextBuilder::track_resources).
+pub fn is_tracking_resources() -> bool {
+    TRACKING_ENABLED.load(Ordering::Relaxed)
+}
+
+/// Returns every tracked resource that is still alive, in the order that they were created.
+///
+/// Resources that were created while tracking was disabled will not be included.
+pub fn get_live_resources() -> Vec<LiveResource> {
+    registry().live.values().cloned().collect()
+}
+
+/// Logs every tracked resource that is still alive, via the [`log`](https://docs.rs/log)
+/// crate, under the `tetra::debug` target.
+///
+/// A good time to call this is after unloading a level or closing a menu - any resources
+/// that should have been freed but are still listed have a handle being kept alive somewhere.
+pub fn log_live_resources() {
+    let registry = registry();
+
+    log::info!(
+        target: "tetra::debug",
+        "{} tracked resources are alive",
+        registry.live.len()
+    );
+
+    for resource in registry.live.values() {
+        log::info!(target: "tetra::debug", "{}", resource);
+    }
+}
+
+pub(crate) fn set_tracking_resources(enabled: bool) {
+    TRACKING_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+fn registry() -> std::sync::MutexGuard<'static, Registry> {
+    REGISTRY.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Registers a resource with the leak tracker (if it is enabled), and unregisters it
+/// when dropped. This should be stored in the shared data of a resource, so that it
+/// lives exactly as long as the resource itself.
+#[derive(Debug)]
+pub(crate) struct TrackedResource {
+    id: Option<u64>,
+}
+
+impl TrackedResource {
+    #[track_caller]
+    pub(crate) fn new(kind: ResourceKind, dimensions: Option<(i32, i32)>) -> TrackedResource {
+        if !is_tracking_resources() {
+            return TrackedResource { id: None };
+        }
+
+        let location = Location::caller();
+        let mut registry = registry();
+
+        let id = registry.next_id;
+        registry.next_id += 1;
+
+        registry.live.insert(
+            id,
+            LiveResource {
+                id,
+                kind,
+                location,
+                dimensions,
+            },
+        );
+
+        TrackedResource { id: Some(id) }
+    }
+}
+
+impl Drop for TrackedResource {
+    fn drop(&mut self) {
+        if let Some(id) = self.id {
+            registry().live.remove(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tracked_resources_are_removed_on_drop() {
+        set_tracking_resources(true);
+
+        let tracked = TrackedResource::new(ResourceKind::Sound, None);
+        let id = tracked.id.unwrap();
+
+        let resource = get_live_resources()
+            .into_iter()
+            .find(|r| r.id == id)
+            .unwrap();
+
+        assert_eq!(resource.kind, ResourceKind::Sound);
+        assert_eq!(resource.location.file(), file!());
+
+        drop(tracked);
+
+        assert!(get_live_resources().iter().all(|r| r.id != id));
+    }
+}
@@ -38,9 +38,29 @@ pub enum TetraError {
         path: PathBuf,
     },
 
+    /// Returned when your game fails to write a file. This is usually caused by
+    /// some form of permission issues, or by the disk being full.
+    FailedToSaveFile {
+        /// The underlying reason for the error.
+        reason: io::Error,
+
+        /// The path to the file that failed to save.
+        path: PathBuf,
+    },
+
+    /// Returned when your game's save data could not be serialized or deserialized.
+    #[cfg(feature = "serde_support")]
+    InvalidSaveData(serde_json::Error),
+
+    /// Returned when a localization file could not be parsed.
+    InvalidLocalization(String),
+
     /// Returned when a color is invalid.
     InvalidColor,
 
+    /// Returned when texture atlas data could not be parsed.
+    InvalidAtlas(String),
+
     /// Returned when a texture's data is invalid.
     InvalidTexture(ImageError),
 
@@ -74,6 +94,24 @@ pub enum TetraError {
 
     /// Returned when a shape cannot be tessellated.
     TessellationError(TessellationError),
+
+    /// Returned when your game tries to use a feature that is not supported by the
+    /// current graphics device. The [`Capabilities`](crate::graphics::Capabilities)
+    /// of the device can be queried ahead of time via
+    /// [`graphics::get_capabilities`](crate::graphics::get_capabilities).
+    UnsupportedFeature(String),
+
+    /// Returned when an archive could not be mounted, either because it is not in a
+    /// supported format or because it is malformed.
+    InvalidArchive(String),
+
+    /// Returned when the passes in a [`RenderGraph`](crate::graphics::RenderGraph) could
+    /// not be scheduled.
+    InvalidRenderGraph(String),
+
+    /// Returned when the faces of a [`Cubemap`](crate::graphics::Cubemap) are not square,
+    /// or are not all the same size.
+    InvalidCubemap(String),
 }
 
 impl Display for TetraError {
@@ -85,7 +123,14 @@ impl Display for TetraError {
             TetraError::FailedToLoadAsset { path, .. } => {
                 write!(f, "Failed to load asset from {}", path.to_string_lossy())
             }
+            TetraError::FailedToSaveFile { path, .. } => {
+                write!(f, "Failed to save file to {}", path.to_string_lossy())
+            }
+            #[cfg(feature = "serde_support")]
+            TetraError::InvalidSaveData(_) => write!(f, "Invalid save data"),
+            TetraError::InvalidLocalization(msg) => write!(f, "Invalid localization: {}", msg),
             TetraError::InvalidColor => write!(f, "Invalid color"),
+            TetraError::InvalidAtlas(msg) => write!(f, "Invalid texture atlas: {}", msg),
             TetraError::InvalidTexture(_) => write!(f, "Invalid texture data"),
             TetraError::InvalidShader(msg) => write!(f, "Invalid shader source: {}", msg),
             TetraError::InvalidFont => write!(f, "Invalid font data"),
@@ -100,6 +145,14 @@ impl Display for TetraError {
                 write!(f, "Failed to change display mode: {}", msg)
             }
             TetraError::NoAudioDevice => write!(f, "No audio device available for playback"),
+            TetraError::UnsupportedFeature(feature) => write!(
+                f,
+                "The current graphics device does not support {}",
+                feature
+            ),
+            TetraError::InvalidArchive(msg) => write!(f, "Invalid archive: {}", msg),
+            TetraError::InvalidRenderGraph(msg) => write!(f, "Invalid render graph: {}", msg),
+            TetraError::InvalidCubemap(msg) => write!(f, "Invalid cubemap: {}", msg),
             TetraError::TessellationError(e) => {
                 write!(
                     f,
@@ -116,7 +169,12 @@ impl Error for TetraError {
         match self {
             TetraError::PlatformError(_) => None,
             TetraError::FailedToLoadAsset { reason, .. } => Some(reason),
+            TetraError::FailedToSaveFile { reason, .. } => Some(reason),
+            #[cfg(feature = "serde_support")]
+            TetraError::InvalidSaveData(reason) => Some(reason),
+            TetraError::InvalidLocalization(_) => None,
             TetraError::InvalidColor => None,
+            TetraError::InvalidAtlas(_) => None,
             TetraError::InvalidTexture(reason) => Some(reason),
             TetraError::InvalidShader(_) => None,
             TetraError::InvalidFont => None,
@@ -125,6 +183,10 @@ impl Error for TetraError {
             TetraError::NotEnoughData { .. } => None,
             TetraError::NoAudioDevice => None,
             TetraError::FailedToChangeDisplayMode(_) => None,
+            TetraError::UnsupportedFeature(_) => None,
+            TetraError::InvalidArchive(_) => None,
+            TetraError::InvalidRenderGraph(_) => None,
+            TetraError::InvalidCubemap(_) => None,
 
             // This should return the inner error, but Lyon doesn't implement Error for some reason,
             // so we can't :(
@@ -0,0 +1,322 @@
+//! A fallback screen that is shown in the game window when the game crashes.
+
+use crate::error::Result;
+use crate::graphics::{self, Color, DrawParams, FilterMode, Rectangle, StencilState, Texture};
+use crate::input::{self, Key, KeyModifier, MouseButton};
+use crate::math::Vec2;
+use crate::window;
+use crate::{Context, State};
+
+/// An 8x16 monospaced font, covering the printable ASCII characters. Each glyph is
+/// stored as 4-bit coverage values, two pixels per byte, in row-major order.
+///
+/// This was rasterized from DejaVu Sans Mono - see `LICENSE-DejaVu` for the font's license.
+const FONT_DATA: &[u8] = include_bytes!("resources/error_font.bin");
+
+const GLYPH_WIDTH: i32 = 8;
+const GLYPH_HEIGHT: i32 = 16;
+const GLYPH_COUNT: i32 = 95;
+const ATLAS_COLUMNS: i32 = 16;
+
+/// The atlas has an extra solid glyph on the end, which is used to draw rectangles.
+const SOLID_GLYPH: i32 = GLYPH_COUNT;
+
+const BACKGROUND_COLOR: Color = Color::rgb(0.16, 0.05, 0.07);
+const TEXT_COLOR: Color = Color::rgb(0.95, 0.92, 0.92);
+const DIM_TEXT_COLOR: Color = Color::rgb(0.7, 0.6, 0.62);
+const BUTTON_COLOR: Color = Color::rgb(0.35, 0.14, 0.17);
+const BUTTON_HOVER_COLOR: Color = Color::rgb(0.5, 0.2, 0.24);
+
+pub(crate) struct ErrorScreen {
+    title: &'static str,
+    message: String,
+    font: Texture,
+    button: Rectangle,
+    copied: bool,
+}
+
+impl ErrorScreen {
+    pub(crate) fn new(
+        ctx: &mut Context,
+        title: &'static str,
+        message: String,
+    ) -> Result<ErrorScreen> {
+        let atlas_rows = (GLYPH_COUNT + 1 + ATLAS_COLUMNS - 1) / ATLAS_COLUMNS;
+        let atlas_width = ATLAS_COLUMNS * GLYPH_WIDTH;
+        let atlas_height = atlas_rows * GLYPH_HEIGHT;
+
+        let mut pixels = vec![0; (atlas_width * atlas_height * 4) as usize];
+        let glyph_size = (GLYPH_WIDTH * GLYPH_HEIGHT) as usize;
+
+        for glyph in 0..=SOLID_GLYPH {
+            let origin_x = (glyph % ATLAS_COLUMNS) * GLYPH_WIDTH;
+            let origin_y = (glyph / ATLAS_COLUMNS) * GLYPH_HEIGHT;
+
+            for i in 0..glyph_size {
+                let coverage = if glyph == SOLID_GLYPH {
+                    15
+                } else {
+                    let byte = FONT_DATA[glyph as usize * glyph_size / 2 + i / 2];
+
+                    if i % 2 == 0 {
+                        byte >> 4
+                    } else {
+                        byte & 0x0F
+                    }
+                };
+
+                let x = origin_x + i as i32 % GLYPH_WIDTH;
+                let y = origin_y + i as i32 / GLYPH_WIDTH;
+                let offset = ((y * atlas_width + x) * 4) as usize;
+
+                pixels[offset..offset + 4].copy_from_slice(&[255, 255, 255, coverage * 17]);
+            }
+        }
+
+        let mut font = Texture::from_rgba(ctx, atlas_width, atlas_height, &pixels)?;
+        font.set_filter_mode(ctx, FilterMode::Nearest);
+
+        Ok(ErrorScreen {
+            title,
+            message,
+            font,
+            button: Rectangle::default(),
+            copied: false,
+        })
+    }
+
+    fn report(&self) -> String {
+        format!("{}\n\n{}", self.title, self.message)
+    }
+
+    fn draw_text(
+        &self,
+        ctx: &mut Context,
+        text: &str,
+        position: Vec2<f32>,
+        scale: f32,
+        color: Color,
+    ) {
+        for (i, ch) in text.chars().enumerate() {
+            let glyph = match ch {
+                ' '..='~' => ch as i32 - ' ' as i32,
+                _ => '?' as i32 - ' ' as i32,
+            };
+
+            self.draw_glyph(
+                ctx,
+                glyph,
+                DrawParams::new()
+                    .position(position + Vec2::new(i as f32 * GLYPH_WIDTH as f32 * scale, 0.0))
+                    .scale(Vec2::broadcast(scale))
+                    .color(color),
+            );
+        }
+    }
+
+    fn draw_rectangle(&self, ctx: &mut Context, rectangle: Rectangle, color: Color) {
+        self.draw_glyph(
+            ctx,
+            SOLID_GLYPH,
+            DrawParams::new()
+                .position(Vec2::new(rectangle.x, rectangle.y))
+                .scale(Vec2::new(
+                    rectangle.width / GLYPH_WIDTH as f32,
+                    rectangle.height / GLYPH_HEIGHT as f32,
+                ))
+                .color(color),
+        );
+    }
+
+    fn draw_glyph(&self, ctx: &mut Context, glyph: i32, params: DrawParams) {
+        let region = Rectangle::new(
+            ((glyph % ATLAS_COLUMNS) * GLYPH_WIDTH) as f32,
+            ((glyph / ATLAS_COLUMNS) * GLYPH_HEIGHT) as f32,
+            GLYPH_WIDTH as f32,
+            GLYPH_HEIGHT as f32,
+        );
+
+        self.font.draw_region(ctx, region, params);
+    }
+}
+
+impl State for ErrorScreen {
+    fn update(&mut self, ctx: &mut Context) -> Result {
+        let ctrl_c = input::is_key_modifier_down(ctx, KeyModifier::Ctrl)
+            && input::is_key_pressed(ctx, Key::C);
+
+        let clicked = input::is_mouse_button_pressed(ctx, MouseButton::Left)
+            && self.button.contains_point(input::get_mouse_position(ctx));
+
+        if ctrl_c || clicked {
+            match input::set_clipboard_text(ctx, &self.report()) {
+                Ok(()) => self.copied = true,
+                Err(e) => log::error!(target: "tetra", "Failed to copy error report: {}", e),
+            }
+        }
+
+        if input::is_key_pressed(ctx, Key::Escape) {
+            window::quit(ctx);
+        }
+
+        Ok(())
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> Result {
+        graphics::clear(ctx, BACKGROUND_COLOR);
+
+        let (width, height) = window::get_size(ctx);
+        let scale = if width >= 1600 && height >= 900 {
+            2.0
+        } else {
+            1.0
+        };
+
+        let char_width = GLYPH_WIDTH as f32 * scale;
+        let line_height = GLYPH_HEIGHT as f32 * scale + 4.0 * scale;
+        let margin = 32.0 * scale;
+
+        let columns = ((width as f32 - margin * 2.0) / char_width).max(1.0) as usize;
+
+        // Leave room for the title at the top, and the button/instructions at the bottom.
+        let max_lines = ((height as f32 - margin * 2.0) / line_height) as usize;
+        let max_lines = max_lines.saturating_sub(5);
+
+        let mut position = Vec2::new(margin, margin);
+
+        self.draw_text(ctx, self.title, position, scale, TEXT_COLOR);
+        position.y += line_height * 2.0;
+
+        let mut lines = wrap(&self.message, columns);
+
+        if lines.len() > max_lines {
+            lines.truncate(max_lines.saturating_sub(1));
+            lines.push("...".into());
+        }
+
+        for line in &lines {
+            self.draw_text(ctx, line, position, scale, DIM_TEXT_COLOR);
+            position.y += line_height;
+        }
+
+        let label = if self.copied {
+            "Copied!"
+        } else {
+            "Copy to clipboard"
+        };
+
+        let padding = 8.0 * scale;
+
+        self.button = Rectangle::new(
+            margin,
+            height as f32 - margin - line_height * 2.0 - padding * 2.0,
+            "Copy to clipboard".len() as f32 * char_width + padding * 2.0,
+            GLYPH_HEIGHT as f32 * scale + padding * 2.0,
+        );
+
+        let hovered = self.button.contains_point(input::get_mouse_position(ctx));
+
+        self.draw_rectangle(
+            ctx,
+            self.button,
+            if hovered {
+                BUTTON_HOVER_COLOR
+            } else {
+                BUTTON_COLOR
+            },
+        );
+
+        self.draw_text(
+            ctx,
+            label,
+            Vec2::new(self.button.x + padding, self.button.y + padding),
+            scale,
+            TEXT_COLOR,
+        );
+
+        self.draw_text(
+            ctx,
+            "Press Ctrl+C to copy the error, or Escape to quit.",
+            Vec2::new(margin, height as f32 - margin - line_height),
+            scale,
+            DIM_TEXT_COLOR,
+        );
+
+        Ok(())
+    }
+}
+
+/// Resets the graphics state, as the game may have crashed part way through a frame.
+pub(crate) fn reset_graphics(ctx: &mut Context) {
+    graphics::reset_canvas(ctx);
+    graphics::reset_shader(ctx);
+    graphics::reset_blend_state(ctx);
+    graphics::reset_transform_matrix(ctx);
+    graphics::reset_scissor(ctx);
+    graphics::set_stencil_state(ctx, StencilState::disabled());
+    graphics::set_color_mask(ctx, true, true, true, true);
+}
+
+/// Splits text into lines of at most `columns` characters, breaking at spaces
+/// where possible.
+fn wrap(text: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut line_len = 0;
+
+        for word in paragraph.split(' ') {
+            let mut word: Vec<char> = word.chars().collect();
+
+            if line_len > 0 && line_len + 1 + word.len() > columns {
+                lines.push(std::mem::take(&mut line));
+                line_len = 0;
+            }
+
+            if line_len > 0 {
+                line.push(' ');
+                line_len += 1;
+            }
+
+            // Words that are too long to fit on a line by themselves get split.
+            while line_len + word.len() > columns {
+                let split = columns - line_len;
+                line.extend(word.drain(..split));
+                lines.push(std::mem::take(&mut line));
+                line_len = 0;
+            }
+
+            line_len += word.len();
+            line.extend(word);
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_text() {
+        assert_eq!(
+            wrap("the quick brown fox\njumps", 10),
+            ["the quick", "brown fox", "jumps"]
+        );
+
+        assert_eq!(wrap("abcdefghij klm", 4), ["abcd", "efgh", "ij", "klm"]);
+        assert_eq!(wrap("", 4), Vec::<String>::new());
+    }
+
+    #[test]
+    fn font_data_size() {
+        assert_eq!(
+            FONT_DATA.len(),
+            (GLYPH_COUNT * GLYPH_WIDTH * GLYPH_HEIGHT / 2) as usize
+        );
+    }
+}
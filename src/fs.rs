@@ -1,50 +1,1044 @@
-//! Internal utilities for reading files while retaining context about file paths.
+//! Functions and types relating to the virtual filesystem.
+//!
+//! By default, Tetra loads assets from paths relative to the current working
+//! directory. Directories and archives can also be 'mounted', after which all of
+//! Tetra's loaders (e.g. [`Texture::new`](crate::graphics::Texture::new),
+//! [`Font::vector`](crate::graphics::text::Font::vector) and
+//! [`Sound::new`](crate::audio::Sound::new)) will search them for files before
+//! falling back to the working directory.
+//!
+//! Mounts are searched in reverse order of when they were mounted - in other words,
+//! the most recently mounted source takes priority. This makes it easy to layer
+//! mods or patches on top of your base game data:
+//!
+//! ```no_run
+//! # fn main() -> tetra::Result {
+//! use tetra::fs;
+//!
+//! fs::mount("./data.pak")?;
+//! fs::mount("./mods/better_textures")?;
+//!
+//! // This will be loaded from `./mods/better_textures/player.png` if it exists,
+//! // and from `data.pak` otherwise.
+//! let bytes = fs::read("player.png")?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The mount table is global, rather than being stored in the [`Context`](crate::Context),
+//! so that it can be used by loaders that do not take a context, and from other threads.
+//!
+//! # Supported Formats
+//!
+//! | Format | Cargo feature | Enabled by default? |
+//! |-|-|-|
+//! | Directories | N/A | Yes |
+//! | PAK | N/A | Yes |
+//! | ZIP | `fs_zip` | No |
+//!
+//! The PAK format is the simple archive format used by Quake - a 12 byte header (the
+//! magic bytes `PACK`, followed by the offset and length of the directory as
+//! little-endian `u32`s), followed by the file data and a directory of 64 byte entries
+//! (a 56 byte null-terminated path, followed by the offset and length of the file).
+//!
+//! # Embedding Assets
+//!
+//! For small games, it can be convenient to ship a single executable, rather than an
+//! executable and a folder of assets. To do this, call [`bundle_assets`] from your
+//! game's [build script](https://doc.rust-lang.org/cargo/reference/build-scripts.html)
+//! (adding Tetra to your `[build-dependencies]`), and then mount the bundle at runtime
+//! via the [`include_assets!`](crate::include_assets) macro:
+//!
+//! ```ignore
+//! // build.rs
+//! fn main() {
+//!     tetra::fs::bundle_assets("./resources").unwrap();
+//! }
+//!
+//! // main.rs
+//! fn main() -> tetra::Result {
+//!     tetra::include_assets!()?;
+//!
+//!     // This will now be loaded from the copy of `./resources/player.png` that was
+//!     // embedded into the executable.
+//!     let bytes = tetra::fs::read("player.png")?;
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! The directory is stored as a PAK archive, so the same limitations apply (see below).
+//!
+//! # Save Data
+//!
+//! [`user_dir`] returns a per-user directory that your game can write settings and save
+//! files to, in the conventional location for the current platform:
+//!
+//! | Platform | Location |
+//! |-|-|
+//! | Windows | `%APPDATA%\{app_name}` |
+//! | macOS | `$HOME/Library/Application Support/{app_name}` |
+//! | Linux/other | `$XDG_DATA_HOME/{app_name}` or `$HOME/.local/share/{app_name}` |
+//!
+//! Files can be written there via [`write`], or, if the `serde_support` feature is enabled,
+//! any serializable type can be stored via [`save`] and [`load`]:
+//!
+//! ```ignore
+//! # fn main() -> tetra::Result {
+//! use serde::{Deserialize, Serialize};
+//! use tetra::fs;
+//!
+//! #[derive(Serialize, Deserialize, Default)]
+//! struct Settings {
+//!     volume: f32,
+//! }
+//!
+//! let path = fs::user_dir("my_game")?.join("settings.json");
+//!
+//! let settings: Settings = if path.exists() {
+//!     fs::load(&path)?
+//! } else {
+//!     Settings::default()
+//! };
+//!
+//! fs::save(&path, &settings)?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Writes are atomic - the data is first written to a temporary file, which then replaces
+//! the original. This means that a crash or power loss partway through saving will not
+//! leave your player with a corrupted save file.
 
 // To avoid warnings in the rare case where all features are disabled at the same time:
 #![allow(unused)]
 
-use std::fs;
-use std::path::Path;
+use std::convert::TryFrom;
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::SystemTime;
 
-use image::{self, DynamicImage, ImageError};
+use hashbrown::HashMap;
+use image::{self, DynamicImage, ImageFormat};
+use once_cell::sync::Lazy;
+
+#[cfg(feature = "fs_zip")]
+use std::sync::Mutex;
 
 use crate::error::{Result, TetraError};
 
-pub(crate) fn read<P>(path: P) -> Result<Vec<u8>>
+static MOUNTS: Lazy<RwLock<Vec<Mount>>> = Lazy::new(|| RwLock::new(Vec::new()));
+
+// A panic while the mount table is locked can't leave it in an invalid state,
+// so it's safe to ignore poisoning.
+fn mounts() -> RwLockReadGuard<'static, Vec<Mount>> {
+    MOUNTS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn mounts_mut() -> RwLockWriteGuard<'static, Vec<Mount>> {
+    MOUNTS.write().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Mounts a directory or archive, so that assets can be loaded from it.
+///
+/// The type of the source will be determined based on whether the path points
+/// to a directory, or on the file extension otherwise (`.pak` or `.zip`).
+///
+/// Newly mounted sources take priority over sources that were mounted previously.
+/// Mounting a path that is already mounted will move it to the top of the search order.
+///
+/// # Errors
+///
+/// * [`TetraError::FailedToLoadAsset`] will be returned if the path could not be opened.
+/// * [`TetraError::InvalidArchive`] will be returned if the path points to a file that
+///   is not a supported archive, or if the archive is malformed.
+pub fn mount<P>(path: P) -> Result
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    let metadata = fs::metadata(path).map_err(|e| TetraError::FailedToLoadAsset {
+        reason: e,
+        path: path.to_owned(),
+    })?;
+
+    let source = if metadata.is_dir() {
+        Source::Directory
+    } else {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("pak") => Source::Pak(PakArchive::open(path)?),
+
+            #[cfg(feature = "fs_zip")]
+            Some("zip") => Source::Zip(open_zip(path)?),
+
+            _ => {
+                return Err(TetraError::InvalidArchive(format!(
+                    "{} is not a supported archive format",
+                    path.display()
+                )))
+            }
+        }
+    };
+
+    let mut mounts = mounts_mut();
+
+    mounts.retain(|m| m.path != path);
+    mounts.push(Mount {
+        path: path.to_owned(),
+        source,
+    });
+
+    Ok(())
+}
+
+/// Mounts a PAK archive that is stored in memory (e.g. via [`include_bytes`]).
+///
+/// The archive will be listed in [`get_mounts`] under the given name, which can also be
+/// passed to [`unmount`] to remove it. Most of the time, you will want to use the
+/// [`include_assets!`](crate::include_assets) macro rather than calling this directly.
+///
+/// # Errors
+///
+/// * [`TetraError::InvalidArchive`] will be returned if the data is not a valid PAK archive.
+pub fn mount_embedded<P>(name: P, data: &'static [u8]) -> Result
+where
+    P: AsRef<Path>,
+{
+    let name = name.as_ref();
+    let source = Source::Pak(PakArchive::from_static(name, data)?);
+
+    let mut mounts = mounts_mut();
+
+    mounts.retain(|m| m.path != name);
+    mounts.push(Mount {
+        path: name.to_owned(),
+        source,
+    });
+
+    Ok(())
+}
+
+/// Packs the contents of a directory into a PAK archive, so that it can be mounted via [`mount`]
+/// or [`mount_embedded`].
+///
+/// Files are stored under their path relative to the directory, so for example, `dir/sprites/player.png`
+/// can be loaded from the archive as `sprites/player.png`.
+///
+/// # Errors
+///
+/// * [`TetraError::FailedToLoadAsset`] will be returned if the directory or one of its files
+///   could not be read.
+/// * [`TetraError::FailedToSaveFile`] will be returned if the archive could not be written.
+/// * [`TetraError::InvalidArchive`] will be returned if a file path is too long to be stored
+///   in a PAK archive (55 bytes), or if the archive would be larger than 4GB.
+pub fn write_pak<P, Q>(dir: P, output: Q) -> Result
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let mut files = Vec::new();
+    collect_files(dir.as_ref(), dir.as_ref(), &mut files)?;
+
+    // Sorting makes the output reproducible, regardless of the order the OS lists files in.
+    files.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let data = write_pak_archive(&files)?;
+
+    write(output, data)
+}
+
+/// Bundles a directory into the build output, so that it can be embedded into your game
+/// via the [`include_assets!`](crate::include_assets) macro.
+///
+/// This should be called from your game's build script - see the
+/// [module-level documentation](self#embedding-assets) for an example.
+///
+/// # Errors
+///
+/// * [`TetraError::PlatformError`] will be returned if this is called outside of a build script.
+/// * Any of the errors returned by [`write_pak`] may also be returned.
+pub fn bundle_assets<P>(dir: P) -> Result
+where
+    P: AsRef<Path>,
+{
+    let dir = dir.as_ref();
+
+    let out_dir = env_path("OUT_DIR").ok_or_else(|| {
+        TetraError::PlatformError("bundle_assets must be called from a build script".into())
+    })?;
+
+    println!("cargo:rerun-if-changed={}", dir.display());
+
+    write_pak(dir, out_dir.join(BUNDLE_FILE_NAME))
+}
+
+/// The name of the file that [`bundle_assets`] writes to. This needs to be kept in sync
+/// with the [`include_assets!`](crate::include_assets) macro.
+const BUNDLE_FILE_NAME: &str = "tetra_assets.pak";
+
+/// Mounts the assets that were bundled by [`fs::bundle_assets`](crate::fs::bundle_assets)
+/// in your game's build script.
+///
+/// The assets are embedded into your game's executable, and are mounted under the name
+/// `<embedded>`. This expands to a call to [`fs::mount_embedded`](crate::fs::mount_embedded),
+/// so it returns a [`Result`](crate::Result).
+///
+/// See the [`fs` module documentation](crate::fs#embedding-assets) for an example.
+#[macro_export]
+macro_rules! include_assets {
+    () => {
+        $crate::fs::mount_embedded(
+            "<embedded>",
+            include_bytes!(concat!(env!("OUT_DIR"), "/tetra_assets.pak")),
+        )
+    };
+}
+
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) -> Result {
+    let to_error = |e| TetraError::FailedToLoadAsset {
+        reason: e,
+        path: dir.to_owned(),
+    };
+
+    for entry in fs::read_dir(dir).map_err(to_error)? {
+        let path = entry.map_err(to_error)?.path();
+
+        if path.is_dir() {
+            collect_files(root, &path, files)?;
+        } else {
+            let name = path
+                .strip_prefix(root)
+                .ok()
+                .and_then(archive_path)
+                .ok_or_else(|| {
+                    TetraError::InvalidArchive(format!(
+                        "{} cannot be stored in a PAK archive",
+                        path.display()
+                    ))
+                })?;
+
+            files.push((name, path));
+        }
+    }
+
+    Ok(())
+}
+
+fn write_pak_archive(files: &[(String, PathBuf)]) -> Result<Vec<u8>> {
+    let too_large = || TetraError::InvalidArchive("PAK archives cannot exceed 4GB".into());
+
+    let mut data = vec![0; 12];
+    let mut directory = Vec::with_capacity(files.len() * PAK_ENTRY_SIZE as usize);
+
+    for (name, path) in files {
+        if name.len() >= PAK_NAME_SIZE {
+            return Err(TetraError::InvalidArchive(format!(
+                "{} is too long to be stored in a PAK archive",
+                name
+            )));
+        }
+
+        let contents = fs::read(path).map_err(|e| TetraError::FailedToLoadAsset {
+            reason: e,
+            path: path.clone(),
+        })?;
+
+        let offset = u32::try_from(data.len()).map_err(|_| too_large())?;
+        let length = u32::try_from(contents.len()).map_err(|_| too_large())?;
+
+        let mut entry_name = [0; PAK_NAME_SIZE];
+        entry_name[..name.len()].copy_from_slice(name.as_bytes());
+
+        directory.extend_from_slice(&entry_name);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(&length.to_le_bytes());
+
+        data.extend_from_slice(&contents);
+    }
+
+    let directory_offset = u32::try_from(data.len()).map_err(|_| too_large())?;
+    let directory_length = u32::try_from(directory.len()).map_err(|_| too_large())?;
+
+    data[0..4].copy_from_slice(PAK_MAGIC);
+    data[4..8].copy_from_slice(&directory_offset.to_le_bytes());
+    data[8..12].copy_from_slice(&directory_length.to_le_bytes());
+    data.extend_from_slice(&directory);
+
+    Ok(data)
+}
+
+/// Unmounts a previously mounted directory or archive.
+///
+/// Returns `true` if the path was mounted, or `false` otherwise.
+pub fn unmount<P>(path: P) -> bool
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    let mut mounts = mounts_mut();
+    let len = mounts.len();
+
+    mounts.retain(|m| m.path != path);
+
+    mounts.len() != len
+}
+
+/// Unmounts all directories and archives.
+pub fn unmount_all() {
+    mounts_mut().clear();
+}
+
+/// Returns the paths that are currently mounted, in the order they will be searched.
+pub fn get_mounts() -> Vec<PathBuf> {
+    mounts().iter().rev().map(|m| m.path.clone()).collect()
+}
+
+/// Returns `true` if a file exists at the given path, either in one of the mounted
+/// sources or relative to the working directory.
+pub fn exists<P>(path: P) -> bool
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+
+    mounts().iter().any(|m| m.contains(path)) || path.is_file()
+}
+
+/// Reads the contents of a file into a byte vector.
+///
+/// The mounted sources will be searched first, followed by the working directory.
+///
+/// # Errors
+///
+/// * [`TetraError::FailedToLoadAsset`] will be returned if the file could not be loaded.
+#[cfg_attr(
+    feature = "tracing_support",
+    tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+)]
+pub fn read<P>(path: P) -> Result<Vec<u8>>
 where
     P: AsRef<Path>,
 {
     let path_ref = path.as_ref();
 
-    fs::read(path_ref).map_err(|e| TetraError::FailedToLoadAsset {
+    let result = mounts()
+        .iter()
+        .rev()
+        .find_map(|m| m.read(path_ref))
+        .unwrap_or_else(|| fs::read(path_ref));
+
+    result.map_err(|e| TetraError::FailedToLoadAsset {
         reason: e,
         path: path_ref.to_owned(),
     })
 }
 
-pub(crate) fn read_to_image<P>(path: P) -> Result<DynamicImage>
+/// Reads the contents of a file into a string.
+///
+/// The mounted sources will be searched first, followed by the working directory.
+///
+/// # Errors
+///
+/// * [`TetraError::FailedToLoadAsset`] will be returned if the file could not be loaded,
+///   or if it did not contain valid UTF-8.
+#[cfg_attr(
+    feature = "tracing_support",
+    tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+)]
+pub fn read_to_string<P>(path: P) -> Result<String>
 where
     P: AsRef<Path>,
 {
     let path_ref = path.as_ref();
 
-    image::open(path_ref).map_err(|e| match e {
-        ImageError::IoError(inner) => TetraError::FailedToLoadAsset {
-            reason: inner,
-            path: path_ref.to_owned(),
-        },
-        _ => TetraError::InvalidTexture(e),
+    String::from_utf8(read(path_ref)?).map_err(|e| TetraError::FailedToLoadAsset {
+        reason: io::Error::new(io::ErrorKind::InvalidData, e),
+        path: path_ref.to_owned(),
     })
 }
 
-pub(crate) fn read_to_string<P>(path: P) -> Result<String>
+/// Returns the directory that save data and settings for the given app should be stored in,
+/// creating it if it does not already exist.
+///
+/// See the [module-level documentation](self#save-data) for the location that is used
+/// on each platform.
+///
+/// # Errors
+///
+/// * [`TetraError::PlatformError`] will be returned if the location of the user's data
+///   directory could not be determined.
+/// * [`TetraError::FailedToSaveFile`] will be returned if the directory could not be created.
+pub fn user_dir(app_name: &str) -> Result<PathBuf> {
+    let root = user_data_root().ok_or_else(|| {
+        TetraError::PlatformError("could not determine the user's data directory".into())
+    })?;
+
+    let path = root.join(app_name);
+
+    fs::create_dir_all(&path).map_err(|e| TetraError::FailedToSaveFile {
+        reason: e,
+        path: path.clone(),
+    })?;
+
+    Ok(path)
+}
+
+#[cfg(target_os = "windows")]
+fn user_data_root() -> Option<PathBuf> {
+    env_path("APPDATA")
+}
+
+#[cfg(target_os = "macos")]
+fn user_data_root() -> Option<PathBuf> {
+    env_path("HOME").map(|home| home.join("Library/Application Support"))
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn user_data_root() -> Option<PathBuf> {
+    // Relative paths in XDG variables are invalid, and should be ignored.
+    env_path("XDG_DATA_HOME")
+        .filter(|path| path.is_absolute())
+        .or_else(|| env_path("HOME").map(|home| home.join(".local/share")))
+}
+
+fn env_path(key: &str) -> Option<PathBuf> {
+    env::var_os(key)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Writes data to a file, replacing its contents if it already exists.
+///
+/// Unlike [`read`], this always writes to the real filesystem, rather than to any of
+/// the mounted sources. The write is atomic - see the
+/// [module-level documentation](self#save-data) for more details.
+///
+/// # Errors
+///
+/// * [`TetraError::FailedToSaveFile`] will be returned if the file could not be written.
+pub fn write<P, C>(path: P, contents: C) -> Result
 where
     P: AsRef<Path>,
+    C: AsRef<[u8]>,
 {
-    let path_ref = path.as_ref();
+    let path = path.as_ref();
 
-    fs::read_to_string(path_ref).map_err(|e| TetraError::FailedToLoadAsset {
+    write_atomic(path, contents.as_ref()).map_err(|e| TetraError::FailedToSaveFile {
         reason: e,
-        path: path_ref.to_owned(),
+        path: path.to_owned(),
     })
 }
+
+fn write_atomic(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path is not a file"))?;
+
+    let mut temp_name = OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".tmp");
+
+    let temp_path = path.with_file_name(temp_name);
+
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents)?;
+
+        // Make sure the data has actually hit the disk before we replace the
+        // original file, otherwise a crash could leave us with neither.
+        file.sync_all()
+    });
+
+    let result = result.and_then(|_| fs::rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+
+    result
+}
+
+/// Serializes a value and writes it to a file, replacing its contents if it already exists.
+///
+/// The data is stored as JSON. Like [`write`], this always writes to the real filesystem,
+/// and the write is atomic.
+///
+/// # Errors
+///
+/// * [`TetraError::InvalidSaveData`] will be returned if the value could not be serialized.
+/// * [`TetraError::FailedToSaveFile`] will be returned if the file could not be written.
+#[cfg(feature = "serde_support")]
+pub fn save<P, T>(path: P, value: &T) -> Result
+where
+    P: AsRef<Path>,
+    T: serde::Serialize + ?Sized,
+{
+    let data = serde_json::to_vec_pretty(value).map_err(TetraError::InvalidSaveData)?;
+    write(path, data)
+}
+
+/// Reads a file that was written by [`save`], and deserializes it.
+///
+/// Save data belongs to the player rather than the game, so unlike [`read`], this always
+/// reads from the real filesystem, and ignores the mounted sources.
+///
+/// # Errors
+///
+/// * [`TetraError::FailedToLoadAsset`] will be returned if the file could not be read.
+/// * [`TetraError::InvalidSaveData`] will be returned if the file could not be deserialized.
+#[cfg(feature = "serde_support")]
+pub fn load<P, T>(path: P) -> Result<T>
+where
+    P: AsRef<Path>,
+    T: serde::de::DeserializeOwned,
+{
+    let path = path.as_ref();
+
+    let data = fs::read(path).map_err(|e| TetraError::FailedToLoadAsset {
+        reason: e,
+        path: path.to_owned(),
+    })?;
+
+    serde_json::from_slice(&data).map_err(TetraError::InvalidSaveData)
+}
+
+/// Returns the time that a file was last modified, resolving the path in the same way as
+/// [`read`]. Files inside archives do not have a modification time.
+pub(crate) fn modified(path: &Path) -> Option<SystemTime> {
+    let mounts = mounts();
+
+    let full_path = match mounts.iter().rev().find(|m| m.contains(path)) {
+        Some(Mount {
+            path: root,
+            source: Source::Directory,
+        }) => root.join(archive_path(path)?),
+        Some(_) => return None,
+        None => path.to_owned(),
+    };
+
+    fs::metadata(full_path).and_then(|m| m.modified()).ok()
+}
+
+#[cfg_attr(
+    feature = "tracing_support",
+    tracing::instrument(skip_all, fields(path = %path.as_ref().display()))
+)]
+pub(crate) fn read_to_image<P>(path: P) -> Result<DynamicImage>
+where
+    P: AsRef<Path>,
+{
+    let path_ref = path.as_ref();
+    let data = read(path_ref)?;
+
+    // Some formats (e.g. TGA) can't be detected from their contents, so we
+    // prefer to go by the file extension.
+    let image = match ImageFormat::from_path(path_ref) {
+        Ok(format) => image::load_from_memory_with_format(&data, format),
+        Err(_) => image::load_from_memory(&data),
+    };
+
+    image.map_err(TetraError::InvalidTexture)
+}
+
+struct Mount {
+    path: PathBuf,
+    source: Source,
+}
+
+enum Source {
+    Directory,
+    Pak(PakArchive),
+
+    #[cfg(feature = "fs_zip")]
+    Zip(Mutex<zip::ZipArchive<BufReader<File>>>),
+}
+
+impl Mount {
+    fn contains(&self, path: &Path) -> bool {
+        let name = match archive_path(path) {
+            Some(name) => name,
+            None => return false,
+        };
+
+        match &self.source {
+            Source::Directory => self.path.join(name).is_file(),
+            Source::Pak(pak) => pak.entries.contains_key(&name),
+
+            #[cfg(feature = "fs_zip")]
+            Source::Zip(zip) => {
+                let mut zip = zip.lock().unwrap_or_else(|e| e.into_inner());
+                let found = zip.by_name(&name).is_ok();
+                found
+            }
+        }
+    }
+
+    /// Returns `None` if the file does not exist in this mount, so that the
+    /// search can continue on to the next one.
+    fn read(&self, path: &Path) -> Option<io::Result<Vec<u8>>> {
+        match &self.source {
+            Source::Directory => {
+                let full_path = directory_path(&self.path, path)?;
+
+                if full_path.is_file() {
+                    Some(fs::read(full_path))
+                } else {
+                    None
+                }
+            }
+
+            Source::Pak(pak) => {
+                let entry = pak.entries.get(&archive_path(path)?)?;
+                Some(pak.read(entry))
+            }
+
+            #[cfg(feature = "fs_zip")]
+            Source::Zip(zip) => {
+                let name = archive_path(path)?;
+                let mut zip = zip.lock().unwrap_or_else(|e| e.into_inner());
+
+                let result = match zip.by_name(&name) {
+                    Ok(mut file) => {
+                        let mut buffer = Vec::with_capacity(file.size() as usize);
+                        Some(file.read_to_end(&mut buffer).map(|_| buffer))
+                    }
+                    Err(zip::result::ZipError::FileNotFound) => None,
+                    Err(e) => Some(Err(io::Error::new(io::ErrorKind::InvalidData, e))),
+                };
+
+                result
+            }
+        }
+    }
+}
+
+/// Resolves a path relative to a mounted directory. Absolute paths and paths
+/// that escape the directory are not resolved.
+fn directory_path(root: &Path, path: &Path) -> Option<PathBuf> {
+    archive_path(path).map(|p| root.join(p))
+}
+
+/// Converts a path into the normalized, forward-slash separated form
+/// used inside archives.
+fn archive_path(path: &Path) -> Option<String> {
+    let mut parts = Vec::new();
+
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_str()?),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("/"))
+    }
+}
+
+const PAK_MAGIC: &[u8; 4] = b"PACK";
+const PAK_ENTRY_SIZE: u32 = 64;
+const PAK_NAME_SIZE: usize = 56;
+
+/// The most entries that space will be reserved for up front when reading a PAK
+/// directory - larger archives will grow the map as they go.
+const PAK_MAX_PREALLOCATED_ENTRIES: u32 = 1024;
+
+struct PakEntry {
+    offset: u64,
+    length: usize,
+}
+
+struct PakArchive {
+    data: PakData,
+    entries: HashMap<String, PakEntry>,
+}
+
+enum PakData {
+    File(PathBuf),
+    Memory(&'static [u8]),
+}
+
+impl PakArchive {
+    fn open(path: &Path) -> Result<PakArchive> {
+        let file = File::open(path).map_err(|e| TetraError::FailedToLoadAsset {
+            reason: e,
+            path: path.to_owned(),
+        })?;
+
+        let entries = read_pak_directory(&mut BufReader::new(file)).map_err(|e| {
+            TetraError::InvalidArchive(format!("{} is not a valid PAK file: {}", path.display(), e))
+        })?;
+
+        Ok(PakArchive {
+            data: PakData::File(path.to_owned()),
+            entries,
+        })
+    }
+
+    fn from_static(name: &Path, data: &'static [u8]) -> Result<PakArchive> {
+        let entries = read_pak_directory(&mut io::Cursor::new(data)).map_err(|e| {
+            TetraError::InvalidArchive(format!("{} is not a valid PAK file: {}", name.display(), e))
+        })?;
+
+        Ok(PakArchive {
+            data: PakData::Memory(data),
+            entries,
+        })
+    }
+
+    fn read(&self, entry: &PakEntry) -> io::Result<Vec<u8>> {
+        match &self.data {
+            PakData::File(path) => {
+                let mut file = File::open(path)?;
+                let file_length = file.metadata()?.len();
+
+                // The file may have changed since it was mounted, so the entry needs to
+                // be checked again before allocating space for it.
+                if !pak_entry_in_bounds(entry.offset, entry.length as u64, file_length) {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "entry is out of bounds",
+                    ));
+                }
+
+                let mut buffer = vec![0; entry.length];
+
+                file.seek(SeekFrom::Start(entry.offset))?;
+                file.read_exact(&mut buffer)?;
+
+                Ok(buffer)
+            }
+
+            PakData::Memory(data) => usize::try_from(entry.offset)
+                .ok()
+                .and_then(|start| data.get(start..start.checked_add(entry.length)?))
+                .map(|bytes| bytes.to_vec())
+                .ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::UnexpectedEof, "entry is out of bounds")
+                }),
+        }
+    }
+}
+
+fn read_pak_directory<R>(reader: &mut R) -> io::Result<HashMap<String, PakEntry>>
+where
+    R: Read + Seek,
+{
+    let mut magic = [0; 4];
+    reader.read_exact(&mut magic)?;
+
+    if &magic != PAK_MAGIC {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "missing PACK header",
+        ));
+    }
+
+    let directory_offset = read_u32(reader)?;
+    let directory_length = read_u32(reader)?;
+
+    if directory_length % PAK_ENTRY_SIZE != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "directory length is not a multiple of the entry size",
+        ));
+    }
+
+    // None of the offsets in the header can be trusted, so they're all checked against
+    // the actual size of the archive before anything is allocated.
+    let archive_length = reader.seek(SeekFrom::End(0))?;
+
+    if !pak_entry_in_bounds(
+        directory_offset.into(),
+        directory_length.into(),
+        archive_length,
+    ) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "directory is out of bounds",
+        ));
+    }
+
+    reader.seek(SeekFrom::Start(directory_offset.into()))?;
+
+    let count = directory_length / PAK_ENTRY_SIZE;
+    let mut entries = HashMap::with_capacity(count.min(PAK_MAX_PREALLOCATED_ENTRIES) as usize);
+
+    for _ in 0..count {
+        let mut name = [0; PAK_NAME_SIZE];
+        reader.read_exact(&mut name)?;
+
+        let offset = read_u32(reader)?;
+        let length = read_u32(reader)?;
+
+        let name_length = name.iter().position(|&b| b == 0).unwrap_or(PAK_NAME_SIZE);
+        let name = std::str::from_utf8(&name[..name_length])
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        if !pak_entry_in_bounds(offset.into(), length.into(), archive_length) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{} is out of bounds", name),
+            ));
+        }
+
+        entries.insert(
+            name.to_owned(),
+            PakEntry {
+                offset: offset.into(),
+                length: length as usize,
+            },
+        );
+    }
+
+    Ok(entries)
+}
+
+/// Returns whether a range of bytes fits within an archive of the given length.
+fn pak_entry_in_bounds(offset: u64, length: u64, archive_length: u64) -> bool {
+    matches!(offset.checked_add(length), Some(end) if end <= archive_length)
+}
+
+fn read_u32<R>(reader: &mut R) -> io::Result<u32>
+where
+    R: Read,
+{
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+
+    Ok(u32::from_le_bytes(bytes))
+}
+
+#[cfg(feature = "fs_zip")]
+fn open_zip(path: &Path) -> Result<Mutex<zip::ZipArchive<BufReader<File>>>> {
+    let file = File::open(path).map_err(|e| TetraError::FailedToLoadAsset {
+        reason: e,
+        path: path.to_owned(),
+    })?;
+
+    let archive = zip::ZipArchive::new(BufReader::new(file)).map_err(|e| {
+        TetraError::InvalidArchive(format!("{} is not a valid ZIP file: {}", path.display(), e))
+    })?;
+
+    Ok(Mutex::new(archive))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn atomic_write() {
+        let dir = env::temp_dir().join(format!("tetra_fs_test_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("save.dat");
+
+        write(&path, b"first").unwrap();
+        write(&path, b"second").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn archive_path_normalization() {
+        assert_eq!(
+            archive_path(Path::new("./sprites/player.png")).as_deref(),
+            Some("sprites/player.png")
+        );
+
+        assert_eq!(archive_path(Path::new("../secret.txt")), None);
+        assert_eq!(archive_path(Path::new("/etc/passwd")), None);
+        assert_eq!(archive_path(Path::new(".")), None);
+    }
+
+    #[test]
+    fn pak_directory() {
+        let contents = b"hello";
+
+        let mut data = Vec::new();
+        data.extend_from_slice(PAK_MAGIC);
+        data.extend_from_slice(&(12 + contents.len() as u32).to_le_bytes());
+        data.extend_from_slice(&PAK_ENTRY_SIZE.to_le_bytes());
+        data.extend_from_slice(contents);
+
+        let mut name = [0; PAK_NAME_SIZE];
+        name[..9].copy_from_slice(b"hello.txt");
+        data.extend_from_slice(&name);
+        data.extend_from_slice(&12u32.to_le_bytes());
+        data.extend_from_slice(&(contents.len() as u32).to_le_bytes());
+
+        let entries = read_pak_directory(&mut Cursor::new(data)).unwrap();
+        let entry = &entries["hello.txt"];
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entry.offset, 12);
+        assert_eq!(entry.length, 5);
+    }
+
+    #[test]
+    fn pak_round_trip() {
+        let dir = env::temp_dir().join(format!("tetra_pak_test_{}", std::process::id()));
+        fs::create_dir_all(dir.join("sprites")).unwrap();
+        fs::write(dir.join("a.txt"), b"hello").unwrap();
+        fs::write(dir.join("sprites/b.txt"), b"world").unwrap();
+
+        let mut files = Vec::new();
+        collect_files(&dir, &dir, &mut files).unwrap();
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let data: &'static [u8] = Box::leak(write_pak_archive(&files).unwrap().into_boxed_slice());
+        let pak = PakArchive::from_static(Path::new("<test>"), data).unwrap();
+
+        assert_eq!(pak.read(&pak.entries["a.txt"]).unwrap(), b"hello");
+        assert_eq!(pak.read(&pak.entries["sprites/b.txt"]).unwrap(), b"world");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn pak_invalid_header() {
+        assert!(read_pak_directory(&mut Cursor::new(b"ZIPS\0\0\0\0\0\0\0\0".to_vec())).is_err());
+    }
+
+    #[test]
+    fn pak_out_of_bounds() {
+        let pak = |directory_length: u32, entry_length: u32| {
+            let mut data = Vec::new();
+            data.extend_from_slice(PAK_MAGIC);
+            data.extend_from_slice(&12u32.to_le_bytes());
+            data.extend_from_slice(&directory_length.to_le_bytes());
+            data.extend_from_slice(&[0; PAK_NAME_SIZE]);
+            data.extend_from_slice(&0u32.to_le_bytes());
+            data.extend_from_slice(&entry_length.to_le_bytes());
+            data
+        };
+
+        assert!(read_pak_directory(&mut Cursor::new(pak(PAK_ENTRY_SIZE, 76))).is_ok());
+
+        // A directory that claims to be larger than the archive shouldn't be trusted
+        // when reserving space for the entries.
+        assert!(read_pak_directory(&mut Cursor::new(pak(u32::MAX - 63, 0))).is_err());
+
+        // The same goes for entries that claim to be larger than the archive.
+        assert!(read_pak_directory(&mut Cursor::new(pak(PAK_ENTRY_SIZE, 77))).is_err());
+        assert!(read_pak_directory(&mut Cursor::new(pak(PAK_ENTRY_SIZE, u32::MAX))).is_err());
+    }
+}
@@ -5,33 +5,75 @@
 //! hardware. This allows us to minimize the number of draw calls made, speeding up
 //! rendering.
 
+mod accessibility;
 pub mod animation;
+mod atlas;
+mod cached;
 mod camera;
 mod canvas;
+mod collision;
 mod color;
+mod command_buffer;
+mod cubemap;
 mod drawparams;
+mod mask;
+mod material;
 pub mod mesh;
+mod mode7;
+mod outline;
+mod parallax;
+mod pool;
+mod preprocessor;
+mod quantize;
+mod query;
 mod rectangle;
+mod render_graph;
+mod retro;
 pub mod scaling;
 mod shader;
+mod sorting;
 pub mod text;
 mod texture;
+mod trail;
 
+pub use accessibility::*;
+pub use atlas::*;
+pub use cached::*;
 pub use camera::*;
 pub use canvas::*;
+pub use collision::*;
 pub use color::*;
+pub use command_buffer::*;
+pub use cubemap::*;
 pub use drawparams::*;
+pub use mask::*;
+pub use material::*;
+pub use mode7::*;
+pub use outline::*;
+pub use parallax::*;
+pub use pool::*;
+pub use preprocessor::*;
+pub use quantize::*;
+pub use query::*;
 pub use rectangle::*;
+pub use render_graph::*;
+pub use retro::*;
 pub use shader::*;
+pub use sorting::SortingLayer;
 pub use texture::*;
+pub use trail::*;
+
+use std::cmp::Ordering;
+use std::sync::Arc;
 
 use crate::error::Result;
-use crate::math::{FrustumPlanes, Mat4, Vec2};
-use crate::platform::{GraphicsDevice, RawIndexBuffer, RawVertexBuffer};
+use crate::math::{FrustumPlanes, Mat3, Mat4, Vec2, Vec3, Vec4};
+use crate::platform::{GraphicsDevice, RawBackbuffer, RawIndexBuffer, RawQuery, RawVertexBuffer};
 use crate::window;
 use crate::Context;
 
 use self::mesh::{BufferUsage, Vertex, VertexWinding};
+use self::sorting::SortingLayers;
 
 const MAX_SPRITES: usize = 2048;
 const MAX_VERTICES: usize = MAX_SPRITES * 4; // Cannot be greater than 32767!
@@ -44,20 +86,109 @@ pub(crate) struct GraphicsContext {
 
     texture: Option<Texture>,
     default_texture: Texture,
+    white_pixel_texture: Option<Texture>,
     default_filter_mode: FilterMode,
+    default_premultiplied_alpha: bool,
+    default_anisotropy: u8,
+    filter_override: Option<FilterMode>,
 
     shader: Option<Shader>,
     default_shader: Shader,
 
     canvas: Option<Canvas>,
 
+    // Whether we've already warned about the active canvas being drawn to itself,
+    // to avoid flooding the log every frame.
+    feedback_warned: bool,
+
+    // When multisampling is enabled at runtime, we render to an offscreen framebuffer
+    // rather than directly to the window, and then resolve it when presenting.
+    backbuffer: Option<RawBackbuffer>,
+    window_samples: u8,
+    window_stencil_buffer: bool,
+
+    // When an output filter is enabled, we render to an offscreen canvas rather than
+    // directly to the window, and then run it through the filters when presenting.
+    screen_canvas: Option<Canvas>,
+    scratch_canvas: Option<Canvas>,
+    retro_filter: Option<(RetroFilter, Shader)>,
+    quantize_filter: Option<QuantizeState>,
+    accessibility_filter: Option<(AccessibilityFilter, Shader)>,
+    border_color: Color,
+
+    // Only compiled if a `ScreenScaler` is using `ScalingMode::PixelPerfectSmooth`.
+    sharp_bilinear_shader: Option<Shader>,
+
     projection_matrix: Mat4<f32>,
+    custom_projection_matrix: Option<Mat4<f32>>,
     transform_matrix: Mat4<f32>,
+    depth_test: bool,
+
+    // The GPU can only run one occlusion query at a time, so we keep track of which one
+    // is active, in order to end it if another query begins.
+    active_query: Option<Arc<RawQuery>>,
 
     vertex_data: Vec<Vertex>,
     element_count: usize,
 
     blend_state: BlendState,
+    pixel_snapping: bool,
+
+    // When sorting is enabled, quads are queued up here rather than being written
+    // straight into `vertex_data`, and are sorted when the batch is flushed.
+    sorting: bool,
+    draw_depth: f32,
+    sorted_quads: Vec<SortedQuad>,
+
+    sorting_layers: SortingLayers,
+    sorting_layer: SortingLayer,
+    sorting_layer_rank: usize,
+    order_in_layer: i32,
+    y_sorting: bool,
+
+    // While the pre-present hook is running, the 'screen' is the window itself,
+    // rather than whichever offscreen framebuffer is usually standing in for it.
+    pre_present_hook: Option<PresentHook>,
+    pre_present_hook_changed: bool,
+    presenting: bool,
+
+    // Incremented every time the screen is presented, so that resources can tell
+    // how long it has been since they were last used.
+    frame: u64,
+}
+
+type PresentHook = Box<dyn FnMut(&mut Context)>;
+
+struct SortedQuad {
+    layer: usize,
+    order: i32,
+    depth: f32,
+    texture: Texture,
+    shader: Option<Shader>,
+    filter_mode: Option<FilterMode>,
+    vertices: [Vertex; 4],
+}
+
+fn filter_key(filter_mode: Option<FilterMode>) -> u8 {
+    match filter_mode {
+        None => 0,
+        Some(FilterMode::Nearest) => 1,
+        Some(FilterMode::Linear) => 2,
+    }
+}
+
+impl SortedQuad {
+    fn cmp_batch(&self, other: &SortedQuad) -> Ordering {
+        let shader_ptr = |quad: &SortedQuad| quad.shader.as_ref().map(|s| Arc::as_ptr(&s.data));
+
+        self.layer
+            .cmp(&other.layer)
+            .then_with(|| self.order.cmp(&other.order))
+            .then_with(|| self.depth.total_cmp(&other.depth))
+            .then_with(|| Arc::as_ptr(&self.texture.data).cmp(&Arc::as_ptr(&other.texture.data)))
+            .then_with(|| shader_ptr(self).cmp(&shader_ptr(other)))
+            .then_with(|| filter_key(self.filter_mode).cmp(&filter_key(other.filter_mode)))
+    }
 }
 
 impl GraphicsContext {
@@ -65,6 +196,8 @@ impl GraphicsContext {
         device: &mut GraphicsDevice,
         window_width: i32,
         window_height: i32,
+        window_samples: u8,
+        window_stencil_buffer: bool,
     ) -> Result<GraphicsContext> {
         let vertex_buffer = device.new_vertex_buffer(MAX_VERTICES, BufferUsage::Dynamic)?;
         let index_buffer = device.new_index_buffer(MAX_INDICES, BufferUsage::Static)?;
@@ -96,20 +229,60 @@ impl GraphicsContext {
 
             texture: None,
             default_texture,
+            white_pixel_texture: None,
             default_filter_mode,
+            default_premultiplied_alpha: false,
+            default_anisotropy: 1,
+            filter_override: None,
 
             shader: None,
             default_shader,
 
             canvas: None,
 
+            feedback_warned: false,
+
+            backbuffer: None,
+            window_samples,
+            window_stencil_buffer,
+
+            screen_canvas: None,
+            scratch_canvas: None,
+            retro_filter: None,
+            quantize_filter: None,
+            accessibility_filter: None,
+            border_color: Color::BLACK,
+
+            sharp_bilinear_shader: None,
+
             projection_matrix: ortho(window_width as f32, window_height as f32, false),
+            custom_projection_matrix: None,
             transform_matrix: Mat4::identity(),
+            depth_test: false,
+
+            active_query: None,
 
             vertex_data: Vec::with_capacity(MAX_VERTICES),
             element_count: 0,
 
             blend_state: BlendState::default(),
+            pixel_snapping: false,
+
+            sorting: false,
+            draw_depth: 0.0,
+            sorted_quads: Vec::new(),
+
+            sorting_layers: SortingLayers::new(),
+            sorting_layer: SortingLayer::DEFAULT,
+            sorting_layer_rank: 0,
+            order_in_layer: 0,
+            y_sorting: false,
+
+            pre_present_hook: None,
+            pre_present_hook_changed: false,
+            presenting: false,
+
+            frame: 0,
         })
     }
 }
@@ -138,10 +311,6 @@ pub(crate) fn push_quad(
     //
     // TODO: This function really needs cleaning up before it can be exposed publicly.
 
-    if ctx.graphics.element_count + 6 > MAX_INDICES {
-        flush(ctx);
-    }
-
     let mut fx = (x1 - params.origin.x) * params.scale.x;
     let mut fy = (y1 - params.origin.y) * params.scale.y;
     let mut fx2 = (x2 - params.origin.x) * params.scale.x;
@@ -184,14 +353,168 @@ pub(crate) fn push_quad(
         )
     };
 
-    ctx.graphics.vertex_data.extend_from_slice(&[
+    let vertices = [
         Vertex::new(Vec2::new(ox1, oy1), Vec2::new(u1, v1), params.color),
         Vertex::new(Vec2::new(ox2, oy2), Vec2::new(u1, v2), params.color),
         Vertex::new(Vec2::new(ox3, oy3), Vec2::new(u2, v2), params.color),
         Vertex::new(Vec2::new(ox4, oy4), Vec2::new(u2, v1), params.color),
-    ]);
+    ];
+
+    push_vertices(ctx, vertices, params);
+}
+
+/// Adds a quad that has already been transformed into screen space to the batch.
+///
+/// The overlay and shader params are taken from the `DrawParams`, but the positions,
+/// UVs and colors are not.
+pub(crate) fn push_vertices(ctx: &mut Context, mut vertices: [Vertex; 4], params: &DrawParams) {
+    if !ctx.graphics.sorting {
+        set_filter_override(ctx, params.filter_mode);
+
+        if ctx.graphics.element_count + 6 > MAX_INDICES {
+            flush(ctx);
+        }
+    }
+
+    if params.overlay_amount != 0.0 {
+        let overlay = params
+            .overlay_color
+            .with_alpha(params.overlay_amount)
+            .into();
+
+        for vertex in &mut vertices {
+            vertex.overlay = overlay;
+        }
+    }
+
+    if params.shader_params != Vec4::zero() {
+        for vertex in &mut vertices {
+            vertex.shader_params = params.shader_params;
+        }
+    }
+
+    if ctx.graphics.sorting {
+        if let Some(texture) = &ctx.graphics.texture {
+            ctx.graphics.sorted_quads.push(SortedQuad {
+                layer: ctx.graphics.sorting_layer_rank,
+                order: ctx.graphics.order_in_layer,
+                depth: if ctx.graphics.y_sorting {
+                    params.position.y
+                } else {
+                    ctx.graphics.draw_depth
+                },
+                texture: texture.clone(),
+                shader: ctx.graphics.shader.clone(),
+                filter_mode: params.filter_mode,
+                vertices,
+            });
+        }
+    } else {
+        ctx.graphics.vertex_data.extend_from_slice(&vertices);
+        ctx.graphics.element_count += 6;
+    }
+}
+
+/// Draws a solid rectangle, using a white pixel from the current texture if possible.
+pub(crate) fn fill_rect(ctx: &mut Context, rect: Rectangle, color: Color) {
+    let uv = set_white_texture(ctx);
+
+    push_quad(
+        ctx,
+        rect.x,
+        rect.y,
+        rect.right(),
+        rect.bottom(),
+        uv.x,
+        uv.y,
+        uv.x,
+        uv.y,
+        &DrawParams::new().color(color),
+    );
+}
+
+/// Binds the texture that should be used for untextured geometry, and returns the UV
+/// co-ordinates of a white texel within it.
+///
+/// If the current texture has a white pixel, it is used, so that the batch doesn't need
+/// to be flushed. Otherwise, the texture passed to [`set_white_pixel`] is used, falling
+/// back to the 1x1 default texture if there isn't one.
+pub(crate) fn set_white_texture(ctx: &mut Context) -> Vec2<f32> {
+    if let Some(texture) = ctx.graphics.texture.clone() {
+        if let Some(uv) = texture.data.white_pixel_uv(&mut ctx.device) {
+            return uv;
+        }
+    }
+
+    let fallback = ctx
+        .graphics
+        .white_pixel_texture
+        .clone()
+        .and_then(|texture| {
+            let uv = texture.data.white_pixel_uv(&mut ctx.device)?;
+            Some((texture, uv))
+        });
+
+    let (texture, uv) = match fallback {
+        Some(fallback) => fallback,
+        None => (ctx.graphics.default_texture.clone(), Vec2::new(0.5, 0.5)),
+    };
+
+    set_texture(ctx, &texture);
+
+    uv
+}
+
+/// Sets a pixel within a texture that is opaque white, so that untextured geometry can
+/// be drawn using that texture.
+///
+/// Usually, drawing a shape in between two sprites means switching from the sprites'
+/// texture to a plain white one and back again, which forces the batch to be flushed
+/// each time. To avoid this, Tetra draws shapes using a white pixel from the current
+/// texture where it can. Textures that are created from RGBA data are searched for a
+/// suitable pixel the first time a shape is drawn while they are bound (see
+/// [`Texture::white_pixel`]), and the glyph atlases used for text always contain one -
+/// this function can be used to assign a pixel to any other texture, or to skip the
+/// search.
+///
+/// The texture will also be used for untextured geometry that is drawn while a texture
+/// without a white pixel is bound (or while no texture is bound), in place of the 1x1
+/// default texture.
+///
+/// This affects untextured [`Mesh`](mesh::Mesh)es, shapes drawn via
+/// [`GeometryBuilder::draw`](mesh::GeometryBuilder::draw), and the rectangles drawn by the
+/// [`ui`](crate::ui) widgets.
+///
+/// The pixel should be surrounded by other white pixels if linear filtering is enabled,
+/// as otherwise the edges of the pixel may bleed into the color of shapes.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, TextureAtlas};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, atlas: &TextureAtlas) {
+/// // The atlas contains a small white square, named 'white'.
+/// let region = atlas.get("white").unwrap();
+///
+/// graphics::set_white_pixel(
+///     ctx,
+///     atlas.texture(),
+///     Vec2::new(region.frame.x as i32 + 1, region.frame.y as i32 + 1),
+/// );
+/// # }
+/// ```
+pub fn set_white_pixel(ctx: &mut Context, texture: &Texture, position: Vec2<i32>) {
+    texture.data.set_white_pixel(WhitePixel::Found(position));
+    ctx.graphics.white_pixel_texture = Some(texture.clone());
+}
 
-    ctx.graphics.element_count += 6;
+/// Stops using the texture that was passed to [`set_white_pixel`] as a fallback, so that
+/// untextured geometry will be drawn using a separate 1x1 white texture when the current
+/// texture doesn't have a white pixel.
+pub fn reset_white_pixel(ctx: &mut Context) {
+    ctx.graphics.white_pixel_texture = None;
 }
 
 pub(crate) fn set_texture(ctx: &mut Context, texture: &Texture) {
@@ -200,11 +523,22 @@ pub(crate) fn set_texture(ctx: &mut Context, texture: &Texture) {
 
 pub(crate) fn set_texture_ex(ctx: &mut Context, texture: Option<&Texture>) {
     if texture != ctx.graphics.texture.as_ref() {
-        flush(ctx);
+        // Sorted quads keep track of their own texture, so there's no need to flush.
+        if !ctx.graphics.sorting {
+            flush(ctx);
+        }
+
         ctx.graphics.texture = texture.cloned();
     }
 }
 
+fn set_filter_override(ctx: &mut Context, filter_mode: Option<FilterMode>) {
+    if filter_mode != ctx.graphics.filter_override {
+        flush(ctx);
+        ctx.graphics.filter_override = filter_mode;
+    }
+}
+
 /// Sets the blend state used for future drawing operations.
 ///
 /// The blend state will be used to determine how drawn content will be blended
@@ -223,6 +557,68 @@ pub fn reset_blend_state(ctx: &mut Context) {
     set_blend_state(ctx, Default::default());
 }
 
+/// Returns whether pixel snapping is enabled.
+pub fn is_pixel_snapping_enabled(ctx: &Context) -> bool {
+    ctx.graphics.pixel_snapping
+}
+
+/// Sets whether pixel snapping is enabled.
+///
+/// When enabled, the corners of everything that is drawn will be rounded to the nearest
+/// whole pixel of the current render target, after the transform matrix has been applied.
+/// This avoids the shimmering and seams that can occur in pixel art games when sprites
+/// are drawn at fractional positions (e.g. when following a camera that moves smoothly).
+///
+/// For [`Mesh`](mesh::Mesh)es, only the position that the mesh is drawn at will be
+/// snapped, rather than each individual vertex.
+///
+/// Changing this setting will trigger a [`flush`] to the graphics hardware.
+///
+/// Defaults to `false`.
+pub fn set_pixel_snapping(ctx: &mut Context, enabled: bool) {
+    if enabled != ctx.graphics.pixel_snapping {
+        flush(ctx);
+        ctx.graphics.pixel_snapping = enabled;
+    }
+}
+
+/// Rounds a position to the nearest whole pixel.
+///
+/// `f32::round` rounds halfway cases away from zero, which would cause a sprite that
+/// straddles the origin to gain or lose a pixel - rounding up consistently avoids this.
+fn snap(value: f32) -> f32 {
+    (value + 0.5).floor()
+}
+
+fn snap_vertices(vertices: &mut [Vertex], transform: Mat4<f32>) {
+    if transform == Mat4::identity() {
+        for vertex in vertices {
+            vertex.position = vertex.position.map(snap);
+        }
+    } else {
+        // The snapping has to happen in the target's space, so we transform the vertices,
+        // round them, and then transform them back again.
+        let inverse = transform.inverted();
+
+        for vertex in vertices {
+            let transformed = transform * Vec4::new(vertex.position.x, vertex.position.y, 0.0, 1.0);
+            let snapped = Vec4::new(snap(transformed.x), snap(transformed.y), transformed.z, 1.0);
+
+            vertex.position = Vec2::from(inverse * snapped);
+        }
+    }
+}
+
+/// Snaps the translation of a model-view matrix to the nearest whole pixel.
+pub(crate) fn snap_matrix(matrix: Mat4<f32>) -> Mat4<f32> {
+    let mut matrix = matrix;
+
+    matrix.cols.w.x = snap(matrix.cols.w.x);
+    matrix.cols.w.y = snap(matrix.cols.w.y);
+
+    matrix
+}
+
 /// Sets the shader that is currently being used for rendering.
 ///
 /// If the shader is different from the one that is currently in use, this will trigger a
@@ -239,11 +635,206 @@ pub fn reset_shader(ctx: &mut Context) {
 
 pub(crate) fn set_shader_ex(ctx: &mut Context, shader: Option<&Shader>) {
     if shader != ctx.graphics.shader.as_ref() {
-        flush(ctx);
+        if !ctx.graphics.sorting {
+            flush(ctx);
+        }
+
         ctx.graphics.shader = shader.cloned();
     }
 }
 
+/// Starts sorting draws, in order to reduce the number of times the renderer needs to
+/// [`flush`].
+///
+/// Normally, everything is drawn in the order that it was submitted, and switching
+/// between textures or shaders forces the current batch to be sent to the graphics
+/// hardware. While sorting is enabled, sprites and text are instead queued up, and then
+/// re-ordered by their [sorting layer](set_sorting_layers),
+/// [order in layer](set_order_in_layer), [draw depth](set_draw_depth), texture and
+/// shader when the batch is flushed. This means that draws with the same depth may no
+/// longer appear in the order that they were submitted, but interleaved draws from a
+/// handful of textures can be rendered in far fewer draw calls.
+///
+/// Some things will still trigger a flush while sorting is enabled, as they can't be
+/// reordered - for example, changing the canvas, blend state or transform matrix, or
+/// drawing a [`Mesh`](mesh::Mesh). Shader uniforms should not be changed between
+/// sorted draws, as only the final values will be used.
+///
+/// Sorting stays enabled (including across frames) until [`end_sorted`] is called.
+pub fn begin_sorted(ctx: &mut Context) {
+    if !ctx.graphics.sorting {
+        flush(ctx);
+        ctx.graphics.sorting = true;
+    }
+}
+
+/// Draws any queued sorted draws, and returns to drawing everything in the order
+/// that it was submitted.
+pub fn end_sorted(ctx: &mut Context) {
+    if ctx.graphics.sorting {
+        flush(ctx);
+        ctx.graphics.sorting = false;
+    }
+}
+
+/// Returns whether draws are currently being sorted.
+///
+/// See [`begin_sorted`] for more information.
+pub fn is_sorting(ctx: &Context) -> bool {
+    ctx.graphics.sorting
+}
+
+/// Sets the depth that future draws will be sorted by, while sorting is enabled.
+///
+/// Draws with a lower depth will appear behind draws with a higher depth. This has
+/// no effect when sorting is disabled.
+///
+/// Defaults to `0.0`.
+pub fn set_draw_depth(ctx: &mut Context, depth: f32) {
+    ctx.graphics.draw_depth = depth;
+}
+
+/// Returns the depth that future draws will be sorted by, while sorting is enabled.
+pub fn get_draw_depth(ctx: &Context) -> f32 {
+    ctx.graphics.draw_depth
+}
+
+/// Defines the sorting layers, and the order that they will be drawn in.
+///
+/// Layers are given from back to front - all draws on a layer will appear behind
+/// draws on the layers that come after it, regardless of their order in layer or draw
+/// depth. The [default layer](SortingLayer::DEFAULT) is always present - if it is not
+/// included in the list, it will be placed at the back.
+///
+/// Layers whose names were already in use will keep their existing handles, so this
+/// can be called again to reorder the layers. Layers that are removed will be treated as
+/// the default layer. Draws that are already queued will keep the order they were given
+/// when they were made.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, Texture};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, background: &Texture, player: &Texture) {
+/// graphics::set_sorting_layers(ctx, &["Background", "Default", "UI"]);
+///
+/// let background_layer = graphics::find_sorting_layer(ctx, "Background").unwrap();
+///
+/// graphics::begin_sorted(ctx);
+///
+/// // Drawn on the default layer, but still appears in front of the background.
+/// player.draw(ctx, Vec2::new(64.0, 64.0));
+///
+/// graphics::set_sorting_layer(ctx, background_layer);
+/// background.draw(ctx, Vec2::new(0.0, 0.0));
+///
+/// graphics::end_sorted(ctx);
+/// # }
+/// ```
+pub fn set_sorting_layers(ctx: &mut Context, names: &[&str]) {
+    ctx.graphics.sorting_layers.set(names);
+    ctx.graphics.sorting_layer_rank = ctx.graphics.sorting_layers.rank(ctx.graphics.sorting_layer);
+}
+
+/// Returns the handle of the sorting layer with the given name, or `None` if no
+/// such layer has been defined.
+pub fn find_sorting_layer(ctx: &Context, name: &str) -> Option<SortingLayer> {
+    ctx.graphics.sorting_layers.find(name)
+}
+
+/// Returns the name of a sorting layer, or `None` if it has been removed.
+pub fn get_sorting_layer_name(ctx: &Context, layer: SortingLayer) -> Option<&str> {
+    ctx.graphics.sorting_layers.name(layer)
+}
+
+/// Sets the sorting layer that future draws will be placed on, while sorting is enabled.
+///
+/// Defaults to [`SortingLayer::DEFAULT`].
+pub fn set_sorting_layer(ctx: &mut Context, layer: SortingLayer) {
+    ctx.graphics.sorting_layer = layer;
+    ctx.graphics.sorting_layer_rank = ctx.graphics.sorting_layers.rank(layer);
+}
+
+/// Returns the sorting layer that future draws will be placed on, while sorting is enabled.
+pub fn get_sorting_layer(ctx: &Context) -> SortingLayer {
+    ctx.graphics.sorting_layer
+}
+
+/// Sets the order of future draws within their sorting layer, while sorting is enabled.
+///
+/// Draws with a lower order will appear behind draws with a higher order on the same
+/// layer. Draws with the same order are then sorted by their [draw depth](set_draw_depth).
+///
+/// Defaults to `0`.
+pub fn set_order_in_layer(ctx: &mut Context, order: i32) {
+    ctx.graphics.order_in_layer = order;
+}
+
+/// Returns the order of future draws within their sorting layer, while sorting is enabled.
+pub fn get_order_in_layer(ctx: &Context) -> i32 {
+    ctx.graphics.order_in_layer
+}
+
+/// Sets whether future draws should be sorted by their Y position, while sorting is enabled.
+///
+/// This is useful for top-down games, where objects that are lower down the screen should
+/// appear in front of objects that are higher up, so that characters can walk in front of
+/// and behind props. Each draw is sorted by the Y co-ordinate of its
+/// [position](DrawParams::position) (i.e. where its origin ends up), so setting the origin
+/// to the base of each sprite (e.g. via [`Origin::BottomCenter`]) usually gives the best
+/// results.
+///
+/// While this is enabled, the Y position replaces the [draw depth](set_draw_depth). Draws
+/// are still grouped by their [sorting layer](set_sorting_layer) and
+/// [order in layer](set_order_in_layer) first, so Y-sorting can be limited to a single
+/// layer by only enabling it while drawing that layer.
+///
+/// Defaults to `false`.
+pub fn set_y_sorting(ctx: &mut Context, enabled: bool) {
+    ctx.graphics.y_sorting = enabled;
+}
+
+/// Returns whether future draws will be sorted by their Y position, while sorting is enabled.
+pub fn is_y_sorting_enabled(ctx: &Context) -> bool {
+    ctx.graphics.y_sorting
+}
+
+/// Sorts the queued quads, and writes them into the vertex buffer in order,
+/// flushing whenever the texture or shader changes.
+fn submit_sorted_quads(ctx: &mut Context) {
+    let mut quads = std::mem::take(&mut ctx.graphics.sorted_quads);
+    quads.sort_by(SortedQuad::cmp_batch);
+
+    let texture = ctx.graphics.texture.take();
+    let shader = ctx.graphics.shader.take();
+
+    ctx.graphics.sorting = false;
+
+    for quad in quads.drain(..) {
+        set_texture_ex(ctx, Some(&quad.texture));
+        set_shader_ex(ctx, quad.shader.as_ref());
+        set_filter_override(ctx, quad.filter_mode);
+
+        if ctx.graphics.element_count + 6 > MAX_INDICES {
+            flush(ctx);
+        }
+
+        ctx.graphics.vertex_data.extend_from_slice(&quad.vertices);
+        ctx.graphics.element_count += 6;
+    }
+
+    flush(ctx);
+
+    ctx.graphics.sorting = true;
+    ctx.graphics.texture = texture;
+    ctx.graphics.shader = shader;
+
+    // Hold onto the allocation for the next batch.
+    ctx.graphics.sorted_quads = quads;
+}
+
 /// Sets the renderer to redirect all drawing commands to the specified canvas.
 ///
 /// If the canvas is different from the one that is currently in use, this will trigger a
@@ -263,6 +854,7 @@ pub(crate) fn set_canvas_ex(ctx: &mut Context, canvas: Option<&Canvas>) {
         resolve_canvas(ctx);
 
         ctx.graphics.canvas = canvas.cloned();
+        ctx.graphics.feedback_warned = false;
 
         match &ctx.graphics.canvas {
             None => {
@@ -272,7 +864,7 @@ pub(crate) fn set_canvas_ex(ctx: &mut Context, canvas: Option<&Canvas>) {
                 ctx.graphics.projection_matrix = ortho(width as f32, height as f32, false);
                 ctx.device.viewport(0, 0, physical_width, physical_height);
 
-                ctx.device.set_canvas(None);
+                bind_screen(ctx);
             }
 
             Some(r) => {
@@ -287,6 +879,40 @@ pub(crate) fn set_canvas_ex(ctx: &mut Context, canvas: Option<&Canvas>) {
     }
 }
 
+/// Returns whether the texture belongs to the canvas that is currently being rendered to.
+///
+/// Sampling from a texture while rendering to it is undefined behaviour in OpenGL, so
+/// the drawing methods use this to skip the draw (and log a warning) instead.
+pub(crate) fn is_feedback_loop(ctx: &mut Context, texture: &Texture) -> bool {
+    let is_target = match &ctx.graphics.canvas {
+        Some(canvas) => &canvas.texture == texture,
+        None => false,
+    };
+
+    if is_target && !ctx.graphics.feedback_warned {
+        log::warn!(
+            "A canvas was drawn while it was the active render target, which is not supported - \
+             the draw will be skipped. Use a `PingPongCanvas` for feedback effects instead."
+        );
+
+        ctx.graphics.feedback_warned = true;
+    }
+
+    is_target
+}
+
+/// Binds whichever framebuffer is currently standing in for the window.
+fn bind_screen(ctx: &mut Context) {
+    let screen = match (&ctx.graphics.screen_canvas, &ctx.graphics.backbuffer) {
+        _ if ctx.graphics.presenting => None,
+        (Some(canvas), _) => Some(&*canvas.handle),
+        (None, Some(backbuffer)) => Some(&backbuffer.canvas),
+        (None, None) => None,
+    };
+
+    ctx.device.set_canvas(screen);
+}
+
 fn resolve_canvas(ctx: &mut Context) {
     if let Some(c) = &ctx.graphics.canvas {
         if c.multisample.is_some() {
@@ -301,8 +927,19 @@ fn resolve_canvas(ctx: &mut Context) {
 /// automatically flush when necessary. Try to keep flushing to a minimum,
 /// as this will reduce the number of draw calls made to the
 /// graphics device.
+#[cfg_attr(
+    feature = "tracing_support",
+    tracing::instrument(level = "trace", skip_all)
+)]
+#[cfg_attr(feature = "profiling_support", profiling::function)]
 pub fn flush(ctx: &mut Context) {
+    if ctx.graphics.sorting && !ctx.graphics.sorted_quads.is_empty() {
+        submit_sorted_quads(ctx);
+    }
+
     if !ctx.graphics.vertex_data.is_empty() {
+        let projection_matrix = get_render_projection_matrix(ctx);
+
         let texture = match &ctx.graphics.texture {
             None => return,
             Some(t) => t,
@@ -318,10 +955,14 @@ pub fn flush(ctx: &mut Context) {
         // but we can't do that without breaking changes.
         let _ = shader.set_default_uniforms(
             &mut ctx.device,
-            ctx.graphics.projection_matrix * ctx.graphics.transform_matrix,
+            projection_matrix * ctx.graphics.transform_matrix,
             Color::WHITE,
         );
 
+        if ctx.graphics.pixel_snapping {
+            snap_vertices(&mut ctx.graphics.vertex_data, ctx.graphics.transform_matrix);
+        }
+
         ctx.device.cull_face(true);
 
         // Because canvas rendering is effectively done upside-down, the winding order is the opposite
@@ -337,6 +978,15 @@ pub fn flush(ctx: &mut Context) {
             0,
         );
 
+        #[cfg(feature = "tracing_support")]
+        tracing::trace!(
+            vertices = ctx.graphics.vertex_data.len(),
+            elements = ctx.graphics.element_count,
+            "drawing batch"
+        );
+
+        ctx.device.set_filter_override(ctx.graphics.filter_override);
+
         ctx.device.draw(
             &ctx.graphics.vertex_buffer,
             Some(&ctx.graphics.index_buffer),
@@ -346,6 +996,8 @@ pub fn flush(ctx: &mut Context) {
             ctx.graphics.element_count,
         );
 
+        ctx.device.set_filter_override(None);
+
         ctx.graphics.vertex_data.clear();
         ctx.graphics.element_count = 0;
     }
@@ -358,12 +1010,492 @@ pub fn flush(ctx: &mut Context) {
 ///
 /// You usually will not have to call this manually, as it is called for you at the end of every
 /// frame. Note that calling it will trigger a [`flush`] to the graphics hardware.
+///
+/// Any GPU resources whose last handle was dropped since the previous call (including
+/// handles that were dropped on other threads) will be deleted at this point.
+#[cfg_attr(
+    feature = "tracing_support",
+    tracing::instrument(level = "trace", skip_all)
+)]
+#[cfg_attr(feature = "profiling_support", profiling::function)]
 pub fn present(ctx: &mut Context) {
     flush(ctx);
 
+    if ctx.graphics.screen_canvas.is_some() {
+        apply_output_filters(ctx);
+    } else if let Some(backbuffer) = &ctx.graphics.backbuffer {
+        ctx.device.resolve_backbuffer(backbuffer);
+    }
+
+    run_pre_present_hook(ctx);
+    swap_buffers(ctx);
+
+    ctx.graphics.frame += 1;
+
+    // Resources that were dropped during the frame (possibly on other threads) are
+    // cleaned up here, now that nothing else can be using them.
+    ctx.device.delete_pending_resources();
+}
+
+/// Sets a function that will be called at the end of every [`present`], just before
+/// the frame is displayed.
+///
+/// This allows external renderers (such as UI libraries, capture tools or streaming
+/// overlays) to draw on top of the final frame. The hook runs after the scene has been
+/// flushed, and after any output filters (such as the [`RetroFilter`]) and multisample
+/// resolves have been applied, so anything it draws will not be affected by them.
+///
+/// While the hook is running, the screen refers to the window itself, rather than any
+/// offscreen buffer that Tetra may be using behind the scenes. Anything drawn via the
+/// graphics API will be flushed once the hook returns, and the active canvas will be put
+/// back the way it was before the hook was called. If the hook makes raw OpenGL calls,
+/// it is responsible for restoring any state that it changes.
+///
+/// Only one hook can be set at a time - setting a new hook will replace the old one.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, Color, DrawParams, Rectangle};
+/// # use tetra::graphics::mesh::{Mesh, ShapeStyle};
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// let border = Mesh::rectangle(
+///     ctx,
+///     ShapeStyle::Stroke(2.0),
+///     Rectangle::new(0.0, 0.0, 64.0, 64.0),
+/// )?;
+///
+/// graphics::set_pre_present_hook(ctx, move |ctx| {
+///     border.draw(ctx, DrawParams::new().color(Color::RED));
+/// });
+/// # Ok(())
+/// # }
+/// ```
+pub fn set_pre_present_hook<F>(ctx: &mut Context, hook: F)
+where
+    F: FnMut(&mut Context) + 'static,
+{
+    ctx.graphics.pre_present_hook = Some(Box::new(hook));
+    ctx.graphics.pre_present_hook_changed = true;
+}
+
+/// Removes the function set via [`set_pre_present_hook`], if there is one.
+pub fn clear_pre_present_hook(ctx: &mut Context) {
+    ctx.graphics.pre_present_hook = None;
+    ctx.graphics.pre_present_hook_changed = true;
+}
+
+/// Returns whether a function has been set via [`set_pre_present_hook`].
+pub fn has_pre_present_hook(ctx: &Context) -> bool {
+    ctx.graphics.pre_present_hook.is_some()
+}
+
+fn swap_buffers(ctx: &mut Context) {
+    // Overlays (such as the Steam overlay) draw on top of the frame when the buffers are
+    // swapped, using whatever state they find. So that they cover the whole window, the
+    // window's framebuffer is bound, and scissoring is switched off, beforehand.
+    #[cfg(feature = "overlay_support")]
+    if ctx.overlay_active {
+        let scissor_test = ctx.device.is_scissor_test_enabled();
+        let (physical_width, physical_height) = window::get_physical_size(ctx);
+
+        ctx.device.set_canvas(None);
+        ctx.device.viewport(0, 0, physical_width, physical_height);
+        ctx.device.scissor_test(false);
+
+        ctx.window.swap_buffers();
+
+        // Put things back the way the user left them.
+        ctx.device.scissor_test(scissor_test);
+
+        match &ctx.graphics.canvas {
+            None => bind_screen(ctx),
+            Some(canvas) => {
+                let (width, height) = canvas.size();
+
+                ctx.device.set_canvas(Some(&canvas.handle));
+                ctx.device.viewport(0, 0, width, height);
+            }
+        }
+
+        return;
+    }
+
     ctx.window.swap_buffers();
 }
 
+fn run_pre_present_hook(ctx: &mut Context) {
+    let mut hook = match ctx.graphics.pre_present_hook.take() {
+        Some(hook) => hook,
+        None => return,
+    };
+
+    let canvas = ctx.graphics.canvas.clone();
+
+    ctx.graphics.pre_present_hook_changed = false;
+    ctx.graphics.presenting = true;
+    set_canvas_ex(ctx, None);
+    bind_screen(ctx);
+
+    hook(ctx);
+
+    // This flushes anything the hook drew, and resolves any canvas it left active.
+    set_canvas_ex(ctx, None);
+    flush(ctx);
+
+    ctx.graphics.presenting = false;
+
+    match canvas {
+        Some(canvas) => set_canvas_ex(ctx, Some(&canvas)),
+        None => bind_screen(ctx),
+    }
+
+    // The hook may have replaced or cleared itself while it was running.
+    if !ctx.graphics.pre_present_hook_changed {
+        ctx.graphics.pre_present_hook = Some(hook);
+    }
+}
+
+/// Returns the settings for the CRT filter, if it is enabled.
+pub fn get_retro_filter(ctx: &Context) -> Option<RetroFilter> {
+    ctx.graphics
+        .retro_filter
+        .as_ref()
+        .map(|(filter, _)| *filter)
+}
+
+/// Sets the CRT filter that will be applied to the screen, or disables it if `None`
+/// is passed.
+///
+/// The filter is applied when [`present`] is called, so it affects everything that has been
+/// drawn to the screen (but not to canvases, until they are drawn to the screen themselves).
+/// Behind the scenes, enabling a filter causes Tetra to render to an offscreen canvas rather
+/// than directly to the window.
+///
+/// # Errors
+///
+/// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
+///   filter's shader could not be compiled.
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+///   if the underlying graphics API encounters an error.
+pub fn set_retro_filter(ctx: &mut Context, filter: Option<RetroFilter>) -> Result {
+    ctx.graphics.retro_filter = match (filter, ctx.graphics.retro_filter.take()) {
+        (Some(filter), Some((_, shader))) => Some((filter, shader)),
+        (Some(filter), None) => Some((filter, RetroFilter::create_shader(ctx)?)),
+        (None, _) => None,
+    };
+
+    update_screen_canvas(ctx)
+}
+
+/// Returns the settings for the quantize filter, if it is enabled.
+pub fn get_quantize_filter(ctx: &Context) -> Option<&QuantizeFilter> {
+    ctx.graphics.quantize_filter.as_ref().map(|q| &q.filter)
+}
+
+/// Sets the filter that will be used to reduce the screen to a limited palette, or
+/// disables it if `None` is passed.
+///
+/// Like [`set_retro_filter`], the filter is applied when [`present`] is called. If both
+/// filters are enabled, the colors will be quantized before the CRT effects are applied.
+///
+/// The palette is read back from the GPU when this function is called, so if you are
+/// using a [`Palette::Texture`] and you change its contents, you will need to call this
+/// function again.
+///
+/// # Errors
+///
+/// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
+///   filter's shader could not be compiled.
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+///   if the underlying graphics API encounters an error.
+pub fn set_quantize_filter(ctx: &mut Context, filter: Option<QuantizeFilter>) -> Result {
+    let previous = ctx.graphics.quantize_filter.take();
+
+    ctx.graphics.quantize_filter = match filter {
+        Some(filter) => Some(QuantizeState::new(ctx, filter, previous.map(|q| q.shader))?),
+        None => None,
+    };
+
+    update_screen_canvas(ctx)
+}
+
+/// Returns the settings for the accessibility filter, if it is enabled.
+pub fn get_accessibility_filter(ctx: &Context) -> Option<AccessibilityFilter> {
+    ctx.graphics
+        .accessibility_filter
+        .as_ref()
+        .map(|(filter, _)| *filter)
+}
+
+/// Sets the filter that will be used to simulate color vision deficiencies and/or
+/// increase the contrast of the screen, or disables it if `None` is passed.
+///
+/// Like [`set_retro_filter`], the filter is applied when [`present`] is called. It runs
+/// after all of the other output filters.
+///
+/// # Errors
+///
+/// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
+///   filter's shader could not be compiled.
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+///   if the underlying graphics API encounters an error.
+pub fn set_accessibility_filter(ctx: &mut Context, filter: Option<AccessibilityFilter>) -> Result {
+    ctx.graphics.accessibility_filter = match (filter, ctx.graphics.accessibility_filter.take()) {
+        (Some(filter), Some((_, shader))) => Some((filter, shader)),
+        (Some(filter), None) => Some((filter, AccessibilityFilter::create_shader(ctx)?)),
+        (None, _) => None,
+    };
+
+    update_screen_canvas(ctx)
+}
+
+/// Returns the color used to fill the parts of the window that the output filters
+/// do not draw to.
+pub fn get_border_color(ctx: &Context) -> Color {
+    ctx.graphics.border_color
+}
+
+/// Sets the color used to fill the parts of the window that the output filters do not
+/// draw to, such as the corners that are left uncovered by a [`RetroFilter`]'s curvature.
+///
+/// This has no effect if no output filters are enabled. To change the color of the
+/// letterboxing added by a [`ScreenScaler`](scaling::ScreenScaler), use
+/// [`ScreenScaler::set_letterbox_color`](scaling::ScreenScaler::set_letterbox_color).
+///
+/// Defaults to [`Color::BLACK`].
+pub fn set_border_color(ctx: &mut Context, color: Color) {
+    ctx.graphics.border_color = color;
+}
+
+fn output_filter_count(ctx: &Context) -> usize {
+    ctx.graphics.quantize_filter.is_some() as usize
+        + ctx.graphics.retro_filter.is_some() as usize
+        + ctx.graphics.accessibility_filter.is_some() as usize
+}
+
+/// Creates, resizes or destroys the offscreen canvases used by the output filters,
+/// depending on which filters are enabled.
+fn update_screen_canvas(ctx: &mut Context) -> Result {
+    flush(ctx);
+
+    let filter_count = output_filter_count(ctx);
+
+    if filter_count == 0 {
+        ctx.graphics.screen_canvas = None;
+        ctx.graphics.scratch_canvas = None;
+    } else {
+        let (width, height) = window::get_physical_size(ctx);
+        let samples = get_backbuffer_samples(ctx);
+
+        let outdated = match &ctx.graphics.screen_canvas {
+            Some(canvas) => {
+                canvas.size() != (width, height) || canvas.multisample.is_some() != (samples > 0)
+            }
+            None => true,
+        };
+
+        if outdated {
+            let mut canvas = Canvas::builder(width, height)
+                .samples(samples)
+                .stencil_buffer(ctx.graphics.window_stencil_buffer)
+                .build(ctx)?;
+
+            canvas.set_filter_mode(ctx, FilterMode::Linear);
+            ctx.graphics.screen_canvas = Some(canvas);
+        }
+
+        let scratch_outdated = match &ctx.graphics.scratch_canvas {
+            Some(canvas) => canvas.size() != (width, height),
+            None => true,
+        };
+
+        if filter_count < 2 {
+            ctx.graphics.scratch_canvas = None;
+        } else if scratch_outdated {
+            let mut canvas = Canvas::new(ctx, width, height)?;
+
+            canvas.set_filter_mode(ctx, FilterMode::Linear);
+            ctx.graphics.scratch_canvas = Some(canvas);
+        }
+    }
+
+    if ctx.graphics.canvas.is_none() {
+        bind_screen(ctx);
+    }
+
+    Ok(())
+}
+
+/// Runs the contents of the screen canvas through each of the enabled output filters,
+/// with the last one drawing to the window.
+fn apply_output_filters(ctx: &mut Context) {
+    let screen = match ctx.graphics.screen_canvas.clone() {
+        Some(screen) => screen,
+        None => return,
+    };
+
+    let (width, height) = screen.size();
+    let resolution = Vec2::new(width as f32, height as f32);
+
+    let mut passes = Vec::with_capacity(output_filter_count(ctx));
+
+    if let Some(quantize) = ctx.graphics.quantize_filter.clone() {
+        quantize.set_uniforms(ctx);
+        passes.push(quantize.shader);
+    }
+
+    if let Some((filter, shader)) = ctx.graphics.retro_filter.clone() {
+        let border_color = ctx.graphics.border_color;
+
+        filter.set_uniforms(ctx, &shader, resolution);
+        shader.set_uniform(ctx, "u_border_color", border_color);
+        passes.push(shader);
+    }
+
+    if let Some((filter, shader)) = ctx.graphics.accessibility_filter.clone() {
+        filter.set_uniforms(ctx, &shader);
+        passes.push(shader);
+    }
+
+    // Scissor and blend settings are meant for the user's own drawing, so they're
+    // switched off while the filters run.
+    let scissor_test = ctx.device.is_scissor_test_enabled();
+
+    ctx.device.scissor_test(false);
+    ctx.device.set_blend_state(BlendState::default());
+    ctx.device.set_depth_test(false);
+    ctx.device.cull_face(false);
+    ctx.device.viewport(0, 0, width, height);
+
+    let mut source = screen;
+
+    for (i, shader) in passes.iter().enumerate() {
+        if source.multisample.is_some() {
+            ctx.device
+                .resolve(&source.handle, &source.texture.data.handle);
+        }
+
+        let target = if i == passes.len() - 1 {
+            None
+        } else if Some(&source) == ctx.graphics.scratch_canvas.as_ref() {
+            ctx.graphics.screen_canvas.clone()
+        } else {
+            ctx.graphics.scratch_canvas.clone()
+        };
+
+        match &target {
+            Some(target) => ctx.device.set_canvas(Some(&target.handle)),
+            None => ctx.device.set_canvas(None),
+        }
+
+        ctx.device.clear(ctx.graphics.border_color);
+
+        // The screen canvas is drawn to the right way up (unlike a normal canvas),
+        // so the texture needs to be flipped vertically.
+        let (w, h) = (width as f32, height as f32);
+
+        let vertices = [
+            Vertex::new(Vec2::new(0.0, 0.0), Vec2::new(0.0, 1.0), Color::WHITE),
+            Vertex::new(Vec2::new(0.0, h), Vec2::new(0.0, 0.0), Color::WHITE),
+            Vertex::new(Vec2::new(w, h), Vec2::new(1.0, 0.0), Color::WHITE),
+            Vertex::new(Vec2::new(w, 0.0), Vec2::new(1.0, 1.0), Color::WHITE),
+        ];
+
+        let _ = shader.set_default_uniforms(&mut ctx.device, ortho(w, h, false), Color::WHITE);
+
+        ctx.device
+            .set_vertex_buffer_data(&ctx.graphics.vertex_buffer, &vertices, 0);
+
+        ctx.device.draw(
+            &ctx.graphics.vertex_buffer,
+            Some(&ctx.graphics.index_buffer),
+            &source.texture.data.handle,
+            &shader.data.handle,
+            0,
+            6,
+        );
+
+        if let Some(target) = target {
+            source = target;
+        }
+    }
+
+    ctx.device.scissor_test(scissor_test);
+    ctx.device.set_blend_state(ctx.graphics.blend_state);
+    ctx.device.set_depth_test(ctx.graphics.depth_test);
+
+    // Put things back the way the user left them.
+    match &ctx.graphics.canvas {
+        None => bind_screen(ctx),
+        Some(canvas) => {
+            let (width, height) = canvas.size();
+
+            ctx.device.set_canvas(Some(&canvas.handle));
+            ctx.device.viewport(0, 0, width, height);
+        }
+    }
+}
+
+/// Returns the number of samples that are being used for multisample anti-aliasing
+/// when drawing to the screen.
+///
+/// If this is `0`, multisampling is disabled.
+pub fn get_backbuffer_samples(ctx: &Context) -> u8 {
+    match &ctx.graphics.backbuffer {
+        Some(backbuffer) => backbuffer.samples,
+        None => ctx.graphics.window_samples,
+    }
+}
+
+/// Sets the number of samples that should be used for multisample anti-aliasing
+/// when drawing to the screen.
+///
+/// Unlike [`ContextBuilder::multisampling`](crate::ContextBuilder::multisampling),
+/// this can be changed while the game is running, which makes it suitable for
+/// exposing in a graphics settings menu. Behind the scenes, Tetra will render to an
+/// offscreen buffer with the requested number of samples, and resolve it to the
+/// window when [`present`] is called.
+///
+/// The number of samples will be clamped to the
+/// [maximum supported by the device](Capabilities::max_samples). The sample count
+/// cannot be reduced below the value passed to `ContextBuilder::multisampling`, so if
+/// you want to be able to turn multisampling off at runtime, leave that setting at `0`.
+///
+/// The contents of the screen will be lost when this setting is changed.
+///
+/// # Errors
+///
+/// * [`TetraError::UnsupportedFeature`](crate::TetraError::UnsupportedFeature) will be
+///   returned if multisampling is not supported by the device.
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+///   if the underlying graphics API encounters an error.
+pub fn set_backbuffer_samples(ctx: &mut Context, samples: u8) -> Result {
+    flush(ctx);
+
+    if samples <= ctx.graphics.window_samples {
+        ctx.graphics.backbuffer = None;
+    } else {
+        let (width, height) = window::get_physical_size(ctx);
+
+        ctx.graphics.backbuffer = Some(ctx.device.new_backbuffer(
+            width,
+            height,
+            samples,
+            ctx.graphics.window_stencil_buffer,
+        )?);
+    }
+
+    if ctx.graphics.screen_canvas.is_some() {
+        update_screen_canvas(ctx)?;
+    } else if ctx.graphics.canvas.is_none() {
+        bind_screen(ctx);
+    }
+
+    Ok(())
+}
+
 /// Returns the filter mode that will be used by newly created textures and canvases.
 pub fn get_default_filter_mode(ctx: &Context) -> FilterMode {
     ctx.graphics.default_filter_mode
@@ -374,6 +1506,43 @@ pub fn set_default_filter_mode(ctx: &mut Context, filter_mode: FilterMode) {
     ctx.graphics.default_filter_mode = filter_mode;
 }
 
+/// Returns the level of anisotropic filtering that will be used by newly created textures
+/// and canvases.
+pub fn get_default_anisotropy(ctx: &Context) -> u8 {
+    ctx.graphics.default_anisotropy
+}
+
+/// Sets the level of anisotropic filtering that will be used by newly created textures
+/// and canvases.
+///
+/// See [`Texture::set_anisotropy`] for more details.
+///
+/// Defaults to `1` (no anisotropic filtering).
+pub fn set_default_anisotropy(ctx: &mut Context, level: u8) {
+    ctx.graphics.default_anisotropy = level.max(1);
+}
+
+/// Returns whether textures that are loaded from files will have their alpha premultiplied.
+pub fn get_default_premultiplied_alpha(ctx: &Context) -> bool {
+    ctx.graphics.default_premultiplied_alpha
+}
+
+/// Sets whether textures that are loaded from files will have their alpha premultiplied.
+///
+/// This applies to [`Texture::new`], [`Texture::from_file_data`] and textures loaded via
+/// an [`AssetLoader`](crate::assets::AssetLoader). Textures created from raw pixel data
+/// are not affected - use [`ImageData::premultiply`] if you need to premultiply those.
+///
+/// Premultiplied textures should be drawn with a premultiplied [`BlendState`] (e.g.
+/// [`BlendState::alpha(true)`](BlendState::alpha)), and give better results than
+/// non-premultiplied textures when they are scaled or filtered, as transparent pixels
+/// no longer bleed their color into the edges of the image.
+///
+/// Defaults to `false`.
+pub fn set_default_premultiplied_alpha(ctx: &mut Context, premultiplied: bool) {
+    ctx.graphics.default_premultiplied_alpha = premultiplied;
+}
+
 /// Information about the device currently being used to render graphics.
 #[derive(Debug, Clone)]
 pub struct GraphicsDeviceInfo {
@@ -398,6 +1567,159 @@ pub fn get_device_info(ctx: &Context) -> GraphicsDeviceInfo {
     ctx.device.get_info()
 }
 
+/// The features and limits of the device currently being used to render graphics.
+///
+/// These are queried once, when the context is created. Using a feature that is not
+/// supported will return [`TetraError::UnsupportedFeature`](crate::TetraError::UnsupportedFeature),
+/// so you can check these ahead of time if you want to provide a fallback.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Capabilities {
+    /// The maximum width/height of a texture or canvas, in pixels.
+    pub max_texture_size: i32,
+
+    /// The maximum number of samples that can be used for multisample anti-aliasing.
+    ///
+    /// If this is `0`, multisampling is not supported. Requests for more samples than this
+    /// will be clamped to this value.
+    pub max_samples: u8,
+
+    /// Whether floating point textures are supported. This is required for
+    /// [HDR canvases](CanvasBuilder::hdr).
+    pub float_textures: bool,
+
+    /// Whether instanced rendering (e.g. [`Mesh::draw_instanced`](mesh::Mesh::draw_instanced))
+    /// is supported.
+    pub instancing: bool,
+
+    /// Whether sRGB textures and framebuffers are supported.
+    pub srgb: bool,
+
+    /// Whether sampler objects are supported. This is required for
+    /// [`DrawParams::filter_mode`].
+    pub sampler_objects: bool,
+
+    /// Whether texture swizzling is supported. If it is not, [single channel textures](Texture::from_r8)
+    /// will be expanded to RGBA when they are created.
+    pub texture_swizzle: bool,
+
+    /// The maximum level of anisotropic filtering that can be applied to a texture.
+    ///
+    /// If this is `1`, anisotropic filtering is not supported. Requests for a higher
+    /// level than this will be clamped to this value.
+    pub max_anisotropy: u8,
+}
+
+/// Retrieves the features and limits of the device currently being used to render graphics.
+pub fn get_capabilities(ctx: &Context) -> Capabilities {
+    ctx.device.get_capabilities()
+}
+
+/// The different kinds of GPU resource that are tracked by [`get_memory_stats`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GpuResourceKind {
+    /// The pixel data of a [`Texture`] or a [`Cubemap`].
+    Texture,
+
+    /// The color buffer of a [`Canvas`].
+    Canvas,
+
+    /// A renderbuffer, used for multisampled canvases, stencil buffers and the backbuffer.
+    Renderbuffer,
+
+    /// A buffer of vertex data, used by a [`VertexBuffer`](mesh::VertexBuffer) or
+    /// by the sprite batcher.
+    VertexBuffer,
+
+    /// A buffer of index data, used by an [`IndexBuffer`](mesh::IndexBuffer) or
+    /// by the sprite batcher.
+    IndexBuffer,
+}
+
+/// The estimated memory usage of a single GPU resource.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GpuResource {
+    /// The kind of resource.
+    pub kind: GpuResourceKind,
+
+    /// The estimated size of the resource, in bytes.
+    pub size: usize,
+
+    /// The width and height of the resource, in pixels, if it is an image.
+    pub dimensions: Option<(i32, i32)>,
+}
+
+/// Estimated GPU memory usage, as returned by [`get_memory_stats`].
+///
+/// All sizes are in bytes, and are calculated from the dimensions and formats of
+/// the resources - the graphics driver may pad or compress resources, and may use
+/// extra memory for internal bookkeeping, so the real usage may differ slightly.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct MemoryStats {
+    /// The memory used by textures.
+    pub textures: usize,
+
+    /// The memory used by the color buffers of canvases.
+    pub canvases: usize,
+
+    /// The memory used by renderbuffers.
+    pub renderbuffers: usize,
+
+    /// The memory used by vertex buffers.
+    pub vertex_buffers: usize,
+
+    /// The memory used by index buffers.
+    pub index_buffers: usize,
+
+    /// Every live resource, sorted from largest to smallest.
+    pub resources: Vec<GpuResource>,
+}
+
+impl MemoryStats {
+    pub(crate) fn from_resources(mut resources: Vec<GpuResource>) -> MemoryStats {
+        resources.sort_by_key(|r| std::cmp::Reverse(r.size));
+
+        let mut stats = MemoryStats::default();
+
+        for resource in &resources {
+            let total = match resource.kind {
+                GpuResourceKind::Texture => &mut stats.textures,
+                GpuResourceKind::Canvas => &mut stats.canvases,
+                GpuResourceKind::Renderbuffer => &mut stats.renderbuffers,
+                GpuResourceKind::VertexBuffer => &mut stats.vertex_buffers,
+                GpuResourceKind::IndexBuffer => &mut stats.index_buffers,
+            };
+
+            *total += resource.size;
+        }
+
+        stats.resources = resources;
+        stats
+    }
+
+    /// Returns the total memory used by all tracked resources.
+    pub fn total(&self) -> usize {
+        self.textures
+            + self.canvases
+            + self.renderbuffers
+            + self.vertex_buffers
+            + self.index_buffers
+    }
+}
+
+/// Returns an estimate of the GPU memory currently being used by the game's textures,
+/// canvases and buffers.
+///
+/// This includes resources that Tetra creates internally, such as the sprite batcher's
+/// buffers and the default texture. It can be useful for diagnosing leaks (e.g. a
+/// steadily growing number of canvases) and for finding oversized texture atlases.
+pub fn get_memory_stats(ctx: &Context) -> MemoryStats {
+    MemoryStats::from_resources(ctx.device.get_resources())
+}
+
 /// Returns the current transform matrix.
 pub fn get_transform_matrix(ctx: &Context) -> Mat4<f32> {
     ctx.graphics.transform_matrix
@@ -419,6 +1741,148 @@ pub fn reset_transform_matrix(ctx: &mut Context) {
     set_transform_matrix(ctx, Mat4::identity());
 }
 
+/// Returns the current projection matrix.
+///
+/// Unless it has been overridden via [`set_projection_matrix`], this is an orthographic
+/// projection that maps the size of the screen (or the current canvas) to device
+/// co-ordinates.
+pub fn get_projection_matrix(ctx: &Context) -> Mat4<f32> {
+    ctx.graphics
+        .custom_projection_matrix
+        .unwrap_or(ctx.graphics.projection_matrix)
+}
+
+/// Sets the projection matrix, overriding the default orthographic projection.
+///
+/// Combined with a 3D [transform matrix](set_transform_matrix), this allows for '2.5D'
+/// effects such as perspective floors, or cards that flip over in 3D, without having to
+/// write a custom vertex shader. [`perspective`] creates a projection that is a good
+/// starting point for this.
+///
+/// The matrix should be written as if the target is the right way up - Tetra will
+/// flip it automatically when rendering to a canvas. It will stay in effect (including
+/// when switching between canvases) until [`reset_projection_matrix`] is called.
+///
+/// Note that quads that end up facing away from the viewer will be culled. To draw the
+/// back of a flipping card, draw a second quad that is rotated 180 degrees.
+pub fn set_projection_matrix(ctx: &mut Context, matrix: Mat4<f32>) {
+    flush(ctx);
+
+    ctx.graphics.custom_projection_matrix = Some(matrix);
+}
+
+/// Resets the projection matrix back to the default orthographic projection.
+pub fn reset_projection_matrix(ctx: &mut Context) {
+    flush(ctx);
+
+    ctx.graphics.custom_projection_matrix = None;
+}
+
+/// Returns the projection matrix that should be passed to shaders, taking into account
+/// whether the target is stored upside-down.
+pub(crate) fn get_render_projection_matrix(ctx: &Context) -> Mat4<f32> {
+    match ctx.graphics.custom_projection_matrix {
+        Some(matrix) if ctx.graphics.canvas.is_some() => {
+            Mat4::<f32>::scaling_3d(Vec3::new(1.0, -1.0, 1.0)) * matrix
+        }
+        Some(matrix) => matrix,
+        None => ctx.graphics.projection_matrix,
+    }
+}
+
+/// Creates a perspective projection matrix, for use with [`set_projection_matrix`].
+///
+/// The projection is set up so that anything drawn at a Z co-ordinate of `0.0` appears
+/// exactly where it would with the default orthographic projection, for a target of the
+/// given size. Positive Z co-ordinates move things towards the viewer, and negative Z
+/// co-ordinates move things away. This means that existing 2D drawing code can be mixed
+/// freely with 3D transforms.
+///
+/// `fov_y` is the vertical field of view, in radians - lower values give a flatter
+/// perspective. The viewer is positioned at a Z co-ordinate of `height / 2 / tan(fov_y / 2)`,
+/// and anything closer than a tenth of that distance, or further away than ten times
+/// that distance, will be clipped.
+pub fn perspective(width: f32, height: f32, fov_y: f32) -> Mat4<f32> {
+    let distance = (height / 2.0) / (fov_y / 2.0).tan();
+
+    let projection =
+        Mat4::perspective_rh_no(fov_y, width / height, distance / 10.0, distance * 10.0);
+
+    let mut view = Mat4::translation_3d(Vec3::new(-width / 2.0, -height / 2.0, -distance));
+    view.scale_3d(Vec3::new(1.0, -1.0, 1.0));
+
+    projection * view
+}
+
+/// Creates a transform matrix that draws a graphic at the given 3D position, rotated
+/// so that it always faces the viewer.
+///
+/// `view` should be the transform matrix that is being used for the rest of the scene
+/// (e.g. a camera that is tilted to look across a floor). The returned matrix already
+/// includes the view transform, so it can be passed straight to
+/// [`set_transform_matrix`]. Graphics should then be drawn at the origin, with their
+/// [origin](DrawParams::origin) set to the point that should be anchored to the position
+/// (usually the bottom center).
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, DrawParams, Origin, Texture};
+/// # use tetra::math::{Mat4, Vec3};
+/// # fn example(ctx: &mut Context, tree: &Texture, view: Mat4<f32>) {
+/// graphics::set_transform_matrix(
+///     ctx,
+///     graphics::billboard_matrix(view, Vec3::new(100.0, 200.0, 0.0)),
+/// );
+///
+/// tree.draw(ctx, DrawParams::new().origin_preset(Origin::BottomCenter));
+///
+/// graphics::set_transform_matrix(ctx, view);
+/// # }
+/// ```
+pub fn billboard_matrix(view: Mat4<f32>, position: Vec3<f32>) -> Mat4<f32> {
+    // Undoing the view's rotation makes the graphic parallel to the screen again.
+    // The rotation is orthonormal, so its inverse is just its transpose.
+    let rotation = Mat4::from(Mat3::from(view).transposed());
+
+    view * Mat4::translation_3d(position) * rotation
+}
+
+/// Enables or disables depth testing.
+///
+/// While depth testing is enabled, anything that is drawn will be hidden behind anything
+/// that has already been drawn closer to the viewer, regardless of draw order. This is
+/// mainly useful alongside a [perspective projection](set_projection_matrix). Note that
+/// the depth is tested per-pixel, so transparent parts of a sprite will still hide things
+/// that are drawn behind them later - draw transparent graphics from back to front instead.
+///
+/// Depth testing requires the target to have a depth buffer. Canvases and the screen get
+/// a combined depth/stencil buffer when they are created with a stencil buffer, via
+/// [`CanvasBuilder::stencil_buffer`] and
+/// [`ContextBuilder::stencil_buffer`](crate::ContextBuilder::stencil_buffer). Use
+/// [`clear_depth`] to reset the depth buffer at the start of each frame.
+///
+/// Defaults to `false`.
+pub fn set_depth_test(ctx: &mut Context, enabled: bool) {
+    flush(ctx);
+
+    ctx.graphics.depth_test = enabled;
+    ctx.device.set_depth_test(enabled);
+}
+
+/// Returns whether depth testing is enabled.
+pub fn is_depth_test_enabled(ctx: &Context) -> bool {
+    ctx.graphics.depth_test
+}
+
+/// Clears the depth buffer, so that the next draws will not be hidden by anything
+/// drawn previously.
+pub fn clear_depth(ctx: &mut Context) {
+    flush(ctx);
+    ctx.device.clear_depth();
+}
+
 /// Sets the scissor rectangle.
 ///
 /// While the scissor is enabled, any rendering that falls outside the specified rectangle of
@@ -505,6 +1969,48 @@ pub fn set_color_mask(ctx: &mut Context, red: bool, green: bool, blue: bool, alp
 }
 
 pub(crate) fn set_viewport_size(ctx: &mut Context) {
+    if let Some(backbuffer) = &ctx.graphics.backbuffer {
+        let (physical_width, physical_height) = window::get_physical_size(ctx);
+
+        if (backbuffer.width, backbuffer.height) != (physical_width, physical_height) {
+            let samples = backbuffer.samples;
+
+            if let Err(e) = set_backbuffer_samples(ctx, samples) {
+                log::error!(
+                    target: "tetra::graphics",
+                    "Failed to resize backbuffer, disabling multisampling: {}",
+                    e
+                );
+
+                ctx.graphics.backbuffer = None;
+
+                if ctx.graphics.canvas.is_none() {
+                    bind_screen(ctx);
+                }
+            }
+        }
+    }
+
+    if ctx.graphics.screen_canvas.is_some() {
+        if let Err(e) = update_screen_canvas(ctx) {
+            log::error!(
+                target: "tetra::graphics",
+                "Failed to resize screen canvas, disabling output filters: {}",
+                e
+            );
+
+            ctx.graphics.retro_filter = None;
+            ctx.graphics.quantize_filter = None;
+            ctx.graphics.accessibility_filter = None;
+            ctx.graphics.screen_canvas = None;
+            ctx.graphics.scratch_canvas = None;
+
+            if ctx.graphics.canvas.is_none() {
+                bind_screen(ctx);
+            }
+        }
+    }
+
     if ctx.graphics.canvas.is_none() {
         let (width, height) = window::get_size(ctx);
         let (physical_width, physical_height) = window::get_physical_size(ctx);
@@ -985,3 +2491,42 @@ impl StencilState {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::mesh::{IndexBuffer, Mesh, VertexBuffer};
+    use super::*;
+
+    fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn handles_are_send_and_sync() {
+        assert_send_sync::<Texture>();
+        assert_send_sync::<Canvas>();
+        assert_send_sync::<Shader>();
+        assert_send_sync::<VertexBuffer>();
+        assert_send_sync::<IndexBuffer>();
+        assert_send_sync::<Mesh>();
+        assert_send_sync::<DrawCommandBuffer>();
+
+        #[cfg(feature = "audio")]
+        assert_send_sync::<crate::audio::Sound>();
+    }
+
+    #[test]
+    fn perspective_matches_ortho_at_zero_depth() {
+        let perspective = perspective(640.0, 480.0, std::f32::consts::FRAC_PI_3);
+        let ortho = ortho(640.0, 480.0, false);
+
+        for point in [
+            Vec4::new(0.0, 0.0, 0.0, 1.0),
+            Vec4::new(160.0, 400.0, 0.0, 1.0),
+        ] {
+            let a = perspective * point;
+            let b = ortho * point;
+
+            assert!((a.x / a.w - b.x).abs() < 0.001);
+            assert!((a.y / a.w - b.y).abs() < 0.001);
+        }
+    }
+}
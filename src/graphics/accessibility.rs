@@ -0,0 +1,180 @@
+use crate::error::Result;
+use crate::graphics::shader::{self, Shader};
+use crate::math::Mat3;
+use crate::Context;
+
+const ACCESSIBILITY_FRAGMENT_SHADER: &str = include_str!("../resources/accessibility.frag");
+
+/// A type of color vision deficiency (color blindness).
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum ColorBlindness {
+    /// Reduced sensitivity to red light.
+    Protanopia,
+
+    /// Reduced sensitivity to green light. This is the most common type.
+    Deuteranopia,
+
+    /// Reduced sensitivity to blue light.
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    /// The simulation matrices from Machado, Oliveira and Fernandes (2009), at full severity.
+    fn matrix(self) -> Mat3<f32> {
+        match self {
+            #[rustfmt::skip]
+            ColorBlindness::Protanopia => Mat3::new(
+                0.152286, 1.052583, -0.204868,
+                0.114503, 0.786281, 0.099216,
+                -0.003882, -0.048116, 1.051998,
+            ),
+
+            #[rustfmt::skip]
+            ColorBlindness::Deuteranopia => Mat3::new(
+                0.367322, 0.860646, -0.227968,
+                0.280085, 0.672501, 0.047413,
+                -0.011820, 0.042940, 0.968881,
+            ),
+
+            #[rustfmt::skip]
+            ColorBlindness::Tritanopia => Mat3::new(
+                1.255528, -0.076749, -0.178779,
+                -0.078411, 0.930809, 0.147602,
+                0.004733, 0.691367, 0.303900,
+            ),
+        }
+    }
+}
+
+/// Settings for a filter that helps to make a game more accessible.
+///
+/// The filter can simulate how the screen looks to players with color vision
+/// deficiencies, which is useful for checking that important information isn't conveyed
+/// by color alone. It can also apply a high contrast mode, which can be offered to players
+/// as an option.
+///
+/// The filter is applied to everything drawn to the screen when
+/// [`present`](super::present) is called - it can be enabled via
+/// [`set_accessibility_filter`](super::set_accessibility_filter). It runs after any
+/// other output filters, so that the simulation reflects the final image.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// use tetra::graphics::{self, AccessibilityFilter, ColorBlindness};
+///
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// // While testing:
+/// graphics::set_accessibility_filter(
+///     ctx,
+///     Some(AccessibilityFilter::simulate(ColorBlindness::Deuteranopia)),
+/// )?;
+///
+/// // From an options menu:
+/// graphics::set_accessibility_filter(ctx, Some(AccessibilityFilter::high_contrast()))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct AccessibilityFilter {
+    /// The type of color vision deficiency to simulate, if any.
+    pub color_blindness: Option<ColorBlindness>,
+
+    /// How severe the simulated color vision deficiency is, from `0.0` (normal vision)
+    /// to `1.0` (complete loss of one type of color receptor).
+    pub severity: f32,
+
+    /// Whether high contrast mode is enabled.
+    pub high_contrast: bool,
+}
+
+impl AccessibilityFilter {
+    /// Creates a filter that simulates the given color vision deficiency at full severity.
+    pub const fn simulate(color_blindness: ColorBlindness) -> AccessibilityFilter {
+        AccessibilityFilter {
+            color_blindness: Some(color_blindness),
+            severity: 1.0,
+            high_contrast: false,
+        }
+    }
+
+    /// Creates a filter that only enables high contrast mode.
+    pub const fn high_contrast() -> AccessibilityFilter {
+        AccessibilityFilter {
+            color_blindness: None,
+            severity: 1.0,
+            high_contrast: true,
+        }
+    }
+
+    pub(crate) fn create_shader(ctx: &mut Context) -> Result<Shader> {
+        Shader::from_string(
+            ctx,
+            shader::DEFAULT_VERTEX_SHADER,
+            ACCESSIBILITY_FRAGMENT_SHADER,
+        )
+    }
+
+    pub(crate) fn set_uniforms(&self, ctx: &mut Context, shader: &Shader) {
+        shader.set_uniform(ctx, "u_color_matrix", self.color_matrix());
+        shader.set_uniform(
+            ctx,
+            "u_contrast",
+            if self.high_contrast { 0.5 } else { 0.0 },
+        );
+    }
+
+    fn color_matrix(&self) -> Mat3<f32> {
+        let identity = Mat3::identity();
+
+        match self.color_blindness {
+            Some(color_blindness) => {
+                let severity = self.severity.clamp(0.0, 1.0);
+                identity * (1.0 - severity) + color_blindness.matrix() * severity
+            }
+            None => identity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec3;
+
+    #[test]
+    fn simulation_preserves_white() {
+        for &kind in &[
+            ColorBlindness::Protanopia,
+            ColorBlindness::Deuteranopia,
+            ColorBlindness::Tritanopia,
+        ] {
+            let mut filter = AccessibilityFilter::simulate(kind);
+            let white = filter.color_matrix() * Vec3::one();
+
+            assert!((white - Vec3::one()).magnitude() < 1e-3);
+
+            filter.severity = 0.0;
+            assert_eq!(filter.color_matrix(), Mat3::identity());
+        }
+    }
+}
@@ -0,0 +1,467 @@
+//! Functions and types relating to texture atlases.
+
+use std::collections::HashMap;
+#[cfg(feature = "texture_packer")]
+use std::path::Path;
+
+use crate::graphics::{DrawParams, Rectangle, Texture, TextureRegion};
+use crate::math::Vec2;
+use crate::Context;
+
+#[cfg(feature = "texture_packer")]
+use crate::error::{Result, TetraError};
+
+/// A named region of a [`TextureAtlas`].
+///
+/// Tools that generate texture atlases will often trim the transparent space from
+/// around each sprite, and may rotate sprites to pack them more efficiently. These
+/// adjustments are undone when the region is drawn via [`TextureAtlas::draw`], so
+/// the sprite appears in the same place as it would have in the original image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
+pub struct AtlasRegion {
+    /// The area of the texture that contains the sprite.
+    ///
+    /// If the sprite is rotated, this is the rotated area (i.e. the width and height
+    /// will be swapped, relative to the sprite).
+    pub frame: Rectangle,
+
+    /// Whether the sprite is stored rotated 90 degrees clockwise.
+    pub rotated: bool,
+
+    /// The offset of the trimmed sprite from the top left of the original image.
+    pub offset: Vec2<f32>,
+
+    /// The size of the original image, before it was trimmed.
+    pub source_size: Vec2<f32>,
+}
+
+impl AtlasRegion {
+    /// Creates a new region that has not been trimmed or rotated.
+    pub fn new(frame: Rectangle) -> AtlasRegion {
+        AtlasRegion {
+            frame,
+            rotated: false,
+            offset: Vec2::zero(),
+            source_size: Vec2::new(frame.width, frame.height),
+        }
+    }
+
+    /// Returns the width and height of the original image, before it was trimmed.
+    pub fn size(&self) -> Vec2<f32> {
+        self.source_size
+    }
+
+    /// Returns whether any space has been trimmed from around the sprite.
+    pub fn is_trimmed(&self) -> bool {
+        self.offset != Vec2::zero() || self.source_size != self.trimmed_size()
+    }
+
+    /// Returns the size of the sprite after trimming, but before rotation.
+    fn trimmed_size(&self) -> Vec2<f32> {
+        if self.rotated {
+            Vec2::new(self.frame.height, self.frame.width)
+        } else {
+            Vec2::new(self.frame.width, self.frame.height)
+        }
+    }
+
+    /// Adjusts a set of params (which are relative to the original, untrimmed image)
+    /// so that they can be used to draw the region's frame.
+    fn adjust_params(&self, params: DrawParams) -> DrawParams {
+        if self.rotated {
+            // The frame is stored rotated clockwise, so a point `p` in the sprite maps
+            // to `(height - p.y, p.x)` in the frame. Rotating back the other way means
+            // the scale axes also need to be swapped.
+            let height = self.trimmed_size().y;
+
+            DrawParams {
+                origin: Vec2::new(
+                    height - (params.origin.y - self.offset.y),
+                    params.origin.x - self.offset.x,
+                ),
+                scale: Vec2::new(params.scale.y, params.scale.x),
+                rotation: params.rotation - std::f32::consts::FRAC_PI_2,
+                ..params
+            }
+        } else {
+            DrawParams {
+                origin: params.origin - self.offset,
+                ..params
+            }
+        }
+    }
+}
+
+/// A texture containing multiple named sprites.
+///
+/// Atlases can be built manually via [`insert`](Self::insert), or loaded from the
+/// JSON data exported by [TexturePacker](https://www.codeandweb.com/texturepacker)
+/// (and compatible tools, such as free-tex-packer) when the `texture_packer` feature
+/// is enabled. Both the 'JSON (Hash)' and 'JSON (Array)' formats are supported.
+///
+/// # Performance
+///
+/// Creating a `TextureAtlas` is cheap, but loading one from a file will also
+/// load the texture, which is expensive.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{AtlasRegion, Rectangle, Texture, TextureAtlas};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// let texture = Texture::new(ctx, "./sprites/sheet.png")?;
+///
+/// let mut atlas = TextureAtlas::new(texture);
+/// atlas.insert("coin", AtlasRegion::new(Rectangle::new(0.0, 0.0, 16.0, 16.0)));
+///
+/// atlas.draw(ctx, "coin", Vec2::new(32.0, 32.0));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureAtlas {
+    texture: Texture,
+    regions: HashMap<String, AtlasRegion>,
+}
+
+impl TextureAtlas {
+    /// Creates a new atlas with no regions.
+    pub fn new(texture: Texture) -> TextureAtlas {
+        TextureAtlas {
+            texture,
+            regions: HashMap::new(),
+        }
+    }
+
+    /// Loads an atlas from a TexturePacker JSON file.
+    ///
+    /// The texture will be loaded from the path specified in the file's metadata,
+    /// relative to the JSON file.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::FailedToLoadAsset`] will be returned if the JSON file or the
+    ///   texture could not be loaded.
+    /// * [`TetraError::InvalidAtlas`] will be returned if the JSON could not be parsed,
+    ///   or does not specify an image.
+    /// * [`TetraError::InvalidTexture`] will be returned if the texture data was invalid.
+    /// * [`TetraError::PlatformError`] will be returned if the GPU texture could not
+    ///   be created.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use tetra::Context;
+    /// # use tetra::graphics::TextureAtlas;
+    /// # use tetra::math::Vec2;
+    /// # fn example(ctx: &mut Context) -> tetra::Result {
+    /// let atlas = TextureAtlas::from_texture_packer(ctx, "./sprites/sheet.json")?;
+    ///
+    /// atlas.draw(ctx, "player_idle_0", Vec2::new(32.0, 32.0));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "texture_packer")]
+    pub fn from_texture_packer<P>(ctx: &mut Context, path: P) -> Result<TextureAtlas>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let json = crate::fs::read_to_string(path)?;
+        let data = texture_packer::parse(&json)?;
+
+        let image = data.image.ok_or_else(|| {
+            TetraError::InvalidAtlas("the metadata does not specify an image".into())
+        })?;
+
+        let image_path = match path.parent() {
+            Some(parent) => parent.join(image),
+            None => image.into(),
+        };
+
+        let texture = Texture::new(ctx, image_path)?;
+
+        Ok(TextureAtlas {
+            texture,
+            regions: data.regions,
+        })
+    }
+
+    /// Creates an atlas from TexturePacker JSON data, using an already loaded texture.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::InvalidAtlas`] will be returned if the JSON could not be parsed.
+    #[cfg(feature = "texture_packer")]
+    pub fn from_texture_packer_data(texture: Texture, json: &str) -> Result<TextureAtlas> {
+        let data = texture_packer::parse(json)?;
+
+        Ok(TextureAtlas {
+            texture,
+            regions: data.regions,
+        })
+    }
+
+    /// Returns a reference to the atlas' texture.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns the region with the given name, if it exists.
+    pub fn get(&self, name: &str) -> Option<&AtlasRegion> {
+        self.regions.get(name)
+    }
+
+    /// Returns the region with the given name as a [`TextureRegion`], if it exists.
+    ///
+    /// A `TextureRegion` only stores the area of the texture that was packed, so any
+    /// trimming or rotation will not be undone when it is drawn.
+    pub fn texture_region(&self, name: &str) -> Option<TextureRegion> {
+        self.regions
+            .get(name)
+            .map(|region| TextureRegion::new(self.texture.clone(), region.frame))
+    }
+
+    /// Returns whether the atlas contains a region with the given name.
+    pub fn contains(&self, name: &str) -> bool {
+        self.regions.contains_key(name)
+    }
+
+    /// Adds a region to the atlas, returning the previous region with that name
+    /// (if there was one).
+    pub fn insert<S>(&mut self, name: S, region: AtlasRegion) -> Option<AtlasRegion>
+    where
+        S: Into<String>,
+    {
+        self.regions.insert(name.into(), region)
+    }
+
+    /// Returns an iterator over the names and regions in the atlas, in an
+    /// unspecified order.
+    pub fn regions(&self) -> impl Iterator<Item = (&str, &AtlasRegion)> {
+        self.regions
+            .iter()
+            .map(|(name, region)| (name.as_str(), region))
+    }
+
+    /// Returns the number of regions in the atlas.
+    pub fn len(&self) -> usize {
+        self.regions.len()
+    }
+
+    /// Returns whether the atlas has no regions.
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// Draws the region with the given name to the screen (or to a canvas, if one
+    /// is enabled).
+    ///
+    /// The params are relative to the original, untrimmed image, so trimmed and
+    /// rotated regions will be drawn in the correct place. If there is no region
+    /// with the given name, nothing will be drawn.
+    pub fn draw<P>(&self, ctx: &mut Context, name: &str, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        if let Some(region) = self.regions.get(name) {
+            self.draw_region(ctx, region, params);
+        }
+    }
+
+    /// Draws a region to the screen (or to a canvas, if one is enabled).
+    ///
+    /// This is useful if you want to look up a region once, rather than on every draw.
+    pub fn draw_region<P>(&self, ctx: &mut Context, region: &AtlasRegion, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        let params = params.into().resolve_origin(region.source_size);
+
+        self.texture
+            .draw_region(ctx, region.frame, region.adjust_params(params));
+    }
+}
+
+#[cfg(feature = "texture_packer")]
+mod texture_packer {
+    use std::collections::HashMap;
+
+    use serde_json::{Map, Value};
+
+    use super::AtlasRegion;
+    use crate::error::{Result, TetraError};
+    use crate::graphics::Rectangle;
+    use crate::math::Vec2;
+
+    pub struct AtlasData {
+        pub image: Option<String>,
+        pub regions: HashMap<String, AtlasRegion>,
+    }
+
+    pub fn parse(json: &str) -> Result<AtlasData> {
+        let root: Value =
+            serde_json::from_str(json).map_err(|e| TetraError::InvalidAtlas(e.to_string()))?;
+
+        let mut regions = HashMap::new();
+
+        match root.get("frames") {
+            // JSON (Hash) format
+            Some(Value::Object(frames)) => {
+                for (name, frame) in frames {
+                    regions.insert(name.clone(), parse_frame(name, frame)?);
+                }
+            }
+
+            // JSON (Array) format
+            Some(Value::Array(frames)) => {
+                for frame in frames {
+                    let name = frame
+                        .get("filename")
+                        .and_then(Value::as_str)
+                        .ok_or_else(|| invalid("a frame is missing its filename"))?;
+
+                    regions.insert(name.to_owned(), parse_frame(name, frame)?);
+                }
+            }
+
+            _ => return Err(invalid("missing frames")),
+        }
+
+        let image = root
+            .get("meta")
+            .and_then(|meta| meta.get("image"))
+            .and_then(Value::as_str)
+            .map(str::to_owned);
+
+        Ok(AtlasData { image, regions })
+    }
+
+    fn parse_frame(name: &str, frame: &Value) -> Result<AtlasRegion> {
+        let rect = frame
+            .get("frame")
+            .and_then(Value::as_object)
+            .ok_or_else(|| invalid(format!("frame '{}' has no bounds", name)))?;
+
+        let x = number(rect, "x", name)?;
+        let y = number(rect, "y", name)?;
+        let w = number(rect, "w", name)?;
+        let h = number(rect, "h", name)?;
+
+        let rotated = frame
+            .get("rotated")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        let offset = match frame.get("spriteSourceSize").and_then(Value::as_object) {
+            Some(sss) => Vec2::new(number(sss, "x", name)?, number(sss, "y", name)?),
+            None => Vec2::zero(),
+        };
+
+        let source_size = match frame.get("sourceSize").and_then(Value::as_object) {
+            Some(ss) => Vec2::new(number(ss, "w", name)?, number(ss, "h", name)?),
+            None => Vec2::new(w, h),
+        };
+
+        // TexturePacker gives the unrotated size of the frame, but we want the area
+        // that it takes up in the texture.
+        let frame = if rotated {
+            Rectangle::new(x, y, h, w)
+        } else {
+            Rectangle::new(x, y, w, h)
+        };
+
+        Ok(AtlasRegion {
+            frame,
+            rotated,
+            offset,
+            source_size,
+        })
+    }
+
+    fn number(object: &Map<String, Value>, key: &str, name: &str) -> Result<f32> {
+        object
+            .get(key)
+            .and_then(Value::as_f64)
+            .map(|n| n as f32)
+            .ok_or_else(|| invalid(format!("frame '{}' is missing '{}'", name, key)))
+    }
+
+    fn invalid<S>(msg: S) -> TetraError
+    where
+        S: Into<String>,
+    {
+        TetraError::InvalidAtlas(msg.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vec4;
+
+    fn transform(params: &DrawParams, point: Vec2<f32>) -> Vec2<f32> {
+        let transformed = params.to_matrix() * Vec4::new(point.x, point.y, 0.0, 1.0);
+        Vec2::new(transformed.x, transformed.y)
+    }
+
+    #[test]
+    fn rotated_regions_are_drawn_upright() {
+        let region = AtlasRegion {
+            frame: Rectangle::new(10.0, 10.0, 20.0, 30.0),
+            rotated: true,
+            offset: Vec2::new(4.0, 2.0),
+            source_size: Vec2::new(40.0, 30.0),
+        };
+
+        let params = DrawParams::new()
+            .position(Vec2::new(100.0, 50.0))
+            .origin(Vec2::new(20.0, 15.0))
+            .scale(Vec2::new(2.0, 3.0))
+            .rotation(0.7);
+
+        let adjusted = region.adjust_params(params.clone());
+
+        // The trimmed sprite is 30x20. A point in it maps to (20 - y, x) in the frame.
+        for &(x, y) in &[(0.0, 0.0), (30.0, 0.0), (0.0, 20.0), (12.0, 7.0)] {
+            let expected = transform(&params, Vec2::new(x, y) + region.offset);
+            let actual = transform(&adjusted, Vec2::new(20.0 - y, x));
+
+            assert!(expected.distance(actual) < 1e-3);
+        }
+    }
+
+    #[cfg(feature = "texture_packer")]
+    #[test]
+    fn parse_texture_packer_json() {
+        let hash = r#"{
+            "frames": {
+                "coin": {
+                    "frame": {"x": 2, "y": 4, "w": 16, "h": 8},
+                    "rotated": true,
+                    "trimmed": true,
+                    "spriteSourceSize": {"x": 1, "y": 3, "w": 16, "h": 8},
+                    "sourceSize": {"w": 20, "h": 12}
+                }
+            },
+            "meta": {"image": "sheet.png"}
+        }"#;
+
+        let data = texture_packer::parse(hash).unwrap();
+        let coin = data.regions["coin"];
+
+        assert_eq!(data.image.as_deref(), Some("sheet.png"));
+        assert_eq!(coin.frame, Rectangle::new(2.0, 4.0, 8.0, 16.0));
+        assert_eq!(coin.offset, Vec2::new(1.0, 3.0));
+        assert!(coin.is_trimmed());
+
+        let array = r#"{"frames": [{"filename": "a", "frame": {"x": 0, "y": 0, "w": 4, "h": 4}}]}"#;
+        let data = texture_packer::parse(array).unwrap();
+
+        assert!(!data.regions["a"].is_trimmed());
+        assert!(texture_packer::parse(r#"{"meta": {}}"#).is_err());
+    }
+}
@@ -0,0 +1,156 @@
+use crate::error::Result;
+use crate::graphics::{self, BlendState, Canvas, Color, DrawParams};
+use crate::math::Mat4;
+use crate::Context;
+
+/// A canvas that caches the result of a set of drawing commands, only rendering them
+/// again when it has been marked as dirty.
+///
+/// This is useful for content that is expensive to draw, but rarely changes - for
+/// example, a UI panel made up of many sprites, or a large block of text. Drawing the
+/// cached canvas is a single quad, no matter how complex its contents are.
+///
+/// The contents of the canvas are stored with premultiplied alpha, so that translucent
+/// edges blend correctly when the canvas is drawn. [`draw`](Self::draw) takes care of
+/// this for you.
+///
+/// For caching a [`Text`](super::text::Text), see [`CachedText`](super::text::CachedText),
+/// which handles the sizing and invalidation automatically.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{CachedCanvas, DrawParams, Texture};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, panel: &mut CachedCanvas, tile: &Texture) -> tetra::Result {
+/// // Only runs the closure if the panel is dirty.
+/// panel.update(ctx, |ctx| {
+///     for x in 0..20 {
+///         tile.draw(ctx, Vec2::new(x as f32 * 16.0, 0.0));
+///     }
+/// })?;
+///
+/// panel.draw(ctx, Vec2::new(32.0, 32.0));
+///
+/// // Call this whenever the contents need to change.
+/// panel.mark_dirty();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedCanvas {
+    canvas: Option<Canvas>,
+    width: i32,
+    height: i32,
+    dirty: bool,
+}
+
+impl CachedCanvas {
+    /// Creates a new cached canvas of the given size.
+    ///
+    /// The GPU canvas will not be created until the first time the cache is updated.
+    pub fn new(width: i32, height: i32) -> CachedCanvas {
+        CachedCanvas {
+            canvas: None,
+            width,
+            height,
+            dirty: true,
+        }
+    }
+
+    /// Marks the cache as dirty, so that it will be rendered again the next time
+    /// [`update`](Self::update) is called.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Returns whether the cache needs to be rendered again.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Returns the size of the cache.
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    /// Sets the size of the cache.
+    ///
+    /// If the size is different from the current size, the cache will be marked as
+    /// dirty, and the canvas will be recreated the next time it is updated.
+    pub fn set_size(&mut self, width: i32, height: i32) {
+        if (width, height) != (self.width, self.height) {
+            self.width = width;
+            self.height = height;
+            self.canvas = None;
+            self.dirty = true;
+        }
+    }
+
+    /// Returns the underlying canvas, if it has been created.
+    pub fn canvas(&self) -> Option<&Canvas> {
+        self.canvas.as_ref()
+    }
+
+    /// If the cache is dirty, clears it and runs the given function to render its
+    /// contents again. Otherwise, this does nothing.
+    ///
+    /// While the function is running, the cache's canvas will be active, and the
+    /// transform matrix will be reset. Both will be put back the way they were
+    /// afterwards.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+    ///   if the canvas could not be created.
+    pub fn update<F>(&mut self, ctx: &mut Context, render: F) -> Result
+    where
+        F: FnOnce(&mut Context),
+    {
+        if !self.dirty {
+            return Ok(());
+        }
+
+        let canvas = match &self.canvas {
+            Some(canvas) => canvas.clone(),
+            None => {
+                let canvas = Canvas::new(ctx, self.width.max(1), self.height.max(1))?;
+                self.canvas = Some(canvas.clone());
+                canvas
+            }
+        };
+
+        let previous_canvas = ctx.graphics.canvas.clone();
+        let previous_transform = graphics::get_transform_matrix(ctx);
+
+        graphics::set_canvas(ctx, &canvas);
+        graphics::set_transform_matrix(ctx, Mat4::identity());
+        graphics::clear(ctx, Color::rgba(0.0, 0.0, 0.0, 0.0));
+
+        render(ctx);
+
+        graphics::set_canvas_ex(ctx, previous_canvas.as_ref());
+        graphics::set_transform_matrix(ctx, previous_transform);
+
+        self.dirty = false;
+
+        Ok(())
+    }
+
+    /// Draws the cached contents to the screen (or to a canvas, if one is enabled).
+    ///
+    /// If the cache has never been updated, nothing will be drawn.
+    pub fn draw<P>(&self, ctx: &mut Context, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        if let Some(canvas) = &self.canvas {
+            let previous_blend_state = ctx.graphics.blend_state;
+
+            graphics::set_blend_state(ctx, BlendState::alpha(true));
+            canvas.draw(ctx, params);
+            graphics::set_blend_state(ctx, previous_blend_state);
+        }
+    }
+}
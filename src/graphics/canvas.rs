@@ -1,7 +1,7 @@
-use std::rc::Rc;
+use std::sync::Arc;
 
 use crate::error::Result;
-use crate::graphics::{DrawParams, FilterMode, Texture};
+use crate::graphics::{self, DrawParams, FilterMode, Texture};
 use crate::platform::{RawCanvas, RawRenderbuffer};
 use crate::Context;
 
@@ -12,7 +12,7 @@ use super::ImageData;
 /// By default, Tetra's canvases are fairly simple - they just provide a [`Texture`] that you
 /// can render things to. However, they can also be configured with extra features via this
 /// builder, such as multisampling and additional buffers.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct CanvasBuilder {
     width: i32,
     height: i32,
@@ -48,6 +48,10 @@ impl CanvasBuilder {
     /// done automatically once you switch to a different canvas/the backbuffer. Until this step takes place,
     /// your rendering will *not* be reflected in the canvas' underlying [`texture`](Canvas::texture) (and by
     /// extension, in the output of [`draw`](Canvas::draw) and [`get_data`](Canvas::get_data)).
+    /// If you need to sample the canvas before then, you can call [`Canvas::resolve`] to resolve it manually.
+    ///
+    /// The graphics driver may use more samples than requested - use [`Canvas::samples`] to find out
+    /// how many were actually used.
     pub fn samples(&mut self, samples: u8) -> &mut CanvasBuilder {
         self.samples = samples;
         self
@@ -77,6 +81,11 @@ impl CanvasBuilder {
     ///
     /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if the underlying
     /// graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`](crate::TetraError::UnsupportedFeature) will be returned
+    ///   if the canvas is larger than the maximum texture size, or if it uses multisampling or HDR
+    ///   and the device does not support them. See [`Capabilities`](crate::graphics::Capabilities)
+    ///   for more information.
+    #[track_caller]
     pub fn build(&self, ctx: &mut Context) -> Result<Canvas> {
         let attachments = ctx.device.new_canvas(
             self.width,
@@ -87,11 +96,15 @@ impl CanvasBuilder {
             self.hdr,
         )?;
 
+        let mut texture = Texture::from_raw(attachments.color, ctx.graphics.default_filter_mode);
+        texture.apply_default_anisotropy(ctx);
+
         Ok(Canvas {
-            handle: Rc::new(attachments.canvas),
-            texture: Texture::from_raw(attachments.color, ctx.graphics.default_filter_mode),
-            stencil_buffer: attachments.depth_stencil.map(Rc::new),
-            multisample: attachments.multisample_color.map(Rc::new),
+            handle: Arc::new(attachments.canvas),
+            texture,
+            stencil_buffer: attachments.depth_stencil.map(Arc::new),
+            multisample: attachments.multisample_color.map(Arc::new),
+            samples: attachments.samples,
         })
     }
 }
@@ -114,7 +127,7 @@ impl CanvasBuilder {
 /// any pending draw calls to the GPU. It's usually a good idea to do your rendering
 /// to a canvas all in one go, if you can.
 ///
-/// You can clone a canvas cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/rc/struct.Rc.html)
+/// You can clone a canvas cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/sync/struct.Arc.html)
 /// handle to a GPU resource. However, this does mean that modifying a canvas (e.g.
 /// drawing to it) will also affect any clones that exist of it.
 ///
@@ -125,10 +138,11 @@ impl CanvasBuilder {
 /// the screen.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Canvas {
-    pub(crate) handle: Rc<RawCanvas>,
+    pub(crate) handle: Arc<RawCanvas>,
     pub(crate) texture: Texture,
-    pub(crate) stencil_buffer: Option<Rc<RawRenderbuffer>>,
-    pub(crate) multisample: Option<Rc<RawRenderbuffer>>,
+    pub(crate) stencil_buffer: Option<Arc<RawRenderbuffer>>,
+    pub(crate) multisample: Option<Arc<RawRenderbuffer>>,
+    pub(crate) samples: u8,
 }
 
 impl Canvas {
@@ -138,6 +152,11 @@ impl Canvas {
     ///
     /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if the underlying
     /// graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`](crate::TetraError::UnsupportedFeature) will be returned
+    ///   if the canvas is larger than the maximum texture size, or if it uses multisampling or HDR
+    ///   and the device does not support them. See [`Capabilities`](crate::graphics::Capabilities)
+    ///   for more information.
+    #[track_caller]
     pub fn new(ctx: &mut Context, width: i32, height: i32) -> Result<Canvas> {
         CanvasBuilder::new(width, height).build(ctx)
     }
@@ -159,12 +178,19 @@ impl Canvas {
     /// done automatically once you switch to a different canvas/the backbuffer. Until this step takes place,
     /// your rendering will *not* be reflected in the canvas' underlying [`texture`](Self::texture) (and by
     /// extension, in the output of [`draw`](Self::draw) and [`get_data`](Self::get_data)).
+    /// If you need to sample the canvas before then, you can call [`resolve`](Self::resolve) to resolve it
+    /// manually.
     ///
     /// # Errors
     ///
     /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if the underlying
     /// graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`](crate::TetraError::UnsupportedFeature) will be returned
+    ///   if the canvas is larger than the maximum texture size, or if it uses multisampling or HDR
+    ///   and the device does not support them. See [`Capabilities`](crate::graphics::Capabilities)
+    ///   for more information.
     #[deprecated(since = "0.6.4", note = "use Canvas::builder instead")]
+    #[track_caller]
     pub fn multisampled(ctx: &mut Context, width: i32, height: i32, samples: u8) -> Result<Canvas> {
         CanvasBuilder::new(width, height)
             .samples(samples)
@@ -172,6 +198,10 @@ impl Canvas {
     }
 
     /// Draws the canvas to the screen (or to another canvas, if one is enabled).
+    ///
+    /// A canvas cannot be drawn while it is the active render target - if you try, nothing
+    /// will be drawn, and a warning will be logged. If you need to repeatedly apply an
+    /// effect to the output of the previous frame, use a [`PingPongCanvas`].
     pub fn draw<P>(&self, ctx: &mut Context, params: P)
     where
         P: Into<DrawParams>,
@@ -204,6 +234,18 @@ impl Canvas {
         self.texture.set_filter_mode(ctx, filter_mode);
     }
 
+    /// Returns the level of anisotropic filtering being used by the canvas.
+    pub fn anisotropy(&self) -> u8 {
+        self.texture.anisotropy()
+    }
+
+    /// Sets the level of anisotropic filtering that should be used by the canvas.
+    ///
+    /// See [`Texture::set_anisotropy`] for more details.
+    pub fn set_anisotropy(&mut self, ctx: &mut Context, level: u8) {
+        self.texture.set_anisotropy(ctx, level);
+    }
+
     /// Gets the canvas' data from the GPU.
     ///
     /// This can be useful if you need to do some image processing on the CPU,
@@ -267,6 +309,34 @@ impl Canvas {
         self.texture.replace_data(ctx, data)
     }
 
+    /// Returns the number of samples that the canvas uses for multisample anti-aliasing.
+    ///
+    /// This may be higher than the number that was requested when the canvas was
+    /// created, as graphics drivers are allowed to round the sample count up to a
+    /// level that the hardware supports. If the canvas is not multisampled, this will
+    /// be `0`.
+    pub fn samples(&self) -> u8 {
+        self.samples
+    }
+
+    /// Resolves the multisampled content of the canvas into its underlying texture.
+    ///
+    /// This usually happens automatically when you switch to a different canvas or
+    /// back to the screen, but if you need to sample from the canvas before then (e.g.
+    /// to read back the result of a pass mid-frame), you can call this to do it early.
+    /// If the canvas is currently active, any pending draw calls will be flushed first.
+    ///
+    /// This does nothing if the canvas is not multisampled.
+    pub fn resolve(&self, ctx: &mut Context) {
+        if self.multisample.is_some() {
+            if ctx.graphics.canvas.as_ref() == Some(self) {
+                graphics::flush(ctx);
+            }
+
+            ctx.device.resolve(&self.handle, &self.texture.data.handle);
+        }
+    }
+
     /// Returns a reference to the canvas' underlying texture.
     ///
     /// If this is the currently active canvas, you may want to unbind it or call
@@ -278,3 +348,103 @@ impl Canvas {
         &self.texture
     }
 }
+
+/// A pair of canvases, for effects that need to read from the previous result while
+/// rendering the next one (e.g. feedback trails, blurs with multiple passes, or cellular
+/// automata running on the GPU).
+///
+/// A canvas cannot be drawn while it is also being rendered to, so effects like these
+/// need two canvases: one to read from (the 'front'), and one to write to (the 'back').
+/// After rendering, calling [`swap`](Self::swap) makes the result available for the
+/// next pass.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, Color, DrawParams, PingPongCanvas};
+/// # fn example(ctx: &mut Context, buffers: &mut PingPongCanvas) {
+/// // Draw the previous frame, slightly faded, and then whatever is new this frame.
+/// graphics::set_canvas(ctx, buffers.back());
+/// graphics::clear(ctx, Color::BLACK);
+/// buffers.front().draw(ctx, DrawParams::new().color(Color::rgba(1.0, 1.0, 1.0, 0.9)));
+/// // ...
+/// graphics::reset_canvas(ctx);
+///
+/// buffers.swap();
+/// buffers.draw(ctx, DrawParams::new());
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct PingPongCanvas {
+    front: Canvas,
+    back: Canvas,
+}
+
+impl PingPongCanvas {
+    /// Creates a new pair of canvases, with the default settings.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if the
+    ///   underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`](crate::TetraError::UnsupportedFeature) will be returned
+    ///   if the canvases are larger than the maximum texture size.
+    #[track_caller]
+    pub fn new(ctx: &mut Context, width: i32, height: i32) -> Result<PingPongCanvas> {
+        PingPongCanvas::from_builder(ctx, &CanvasBuilder::new(width, height))
+    }
+
+    /// Creates a new pair of canvases, using the settings from the given builder.
+    ///
+    /// # Errors
+    ///
+    /// See [`CanvasBuilder::build`].
+    #[track_caller]
+    pub fn from_builder(ctx: &mut Context, builder: &CanvasBuilder) -> Result<PingPongCanvas> {
+        Ok(PingPongCanvas {
+            front: builder.build(ctx)?,
+            back: builder.build(ctx)?,
+        })
+    }
+
+    /// Returns the canvas containing the most recent result, which should be read from.
+    pub fn front(&self) -> &Canvas {
+        &self.front
+    }
+
+    /// Returns the canvas that the next result should be rendered to.
+    pub fn back(&self) -> &Canvas {
+        &self.back
+    }
+
+    /// Swaps the front and back canvases.
+    ///
+    /// This should be called after you have finished rendering to the back canvas.
+    pub fn swap(&mut self) {
+        std::mem::swap(&mut self.front, &mut self.back);
+    }
+
+    /// Draws the front canvas to the screen (or to another canvas, if one is enabled).
+    pub fn draw<P>(&self, ctx: &mut Context, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        self.front.draw(ctx, params);
+    }
+
+    /// Returns the width of the canvases.
+    pub fn width(&self) -> i32 {
+        self.front.width()
+    }
+
+    /// Returns the height of the canvases.
+    pub fn height(&self) -> i32 {
+        self.front.height()
+    }
+
+    /// Returns the size of the canvases.
+    pub fn size(&self) -> (i32, i32) {
+        self.front.size()
+    }
+}
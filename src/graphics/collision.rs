@@ -0,0 +1,341 @@
+use crate::graphics::{AlphaMask, DrawParams, ImageData, Rectangle};
+use crate::math::Vec2;
+
+/// A pixel mask for pixel-perfect collision detection between sprites.
+///
+/// This is an [`AlphaMask`] combined with the bounding box of its opaque pixels, so that
+/// most collision checks can be rejected by comparing bounding boxes, and only the pixels
+/// where the boxes overlap need to be compared. This is the classic approach to collision
+/// in games that don't use a physics engine.
+///
+/// Collision checks take the [`DrawParams`] that each sprite is drawn with, so they will
+/// match what is seen on screen, even if the sprites are rotated, scaled or flipped. The
+/// color of the parameters is ignored. Checks between transformed sprites are done by
+/// sampling the center of each pixel, so they may miss overlaps that are smaller than a
+/// pixel.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{DrawParams, Texture};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, player: &Texture, enemy: &Texture) {
+/// let player_mask = player.to_collision_mask(ctx, 0.5);
+/// let enemy_mask = enemy.to_collision_mask(ctx, 0.5);
+///
+/// let player_params = DrawParams::new().position(Vec2::new(32.0, 32.0));
+/// let enemy_params = DrawParams::new()
+///     .position(Vec2::new(40.0, 36.0))
+///     .rotation(0.25);
+///
+/// if player_mask.overlaps(&player_params, &enemy_mask, &enemy_params) {
+///     // Ouch!
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CollisionMask {
+    mask: AlphaMask,
+    bounds: Option<Rectangle<i32>>,
+}
+
+impl CollisionMask {
+    /// Creates a collision mask from the given image data.
+    ///
+    /// Pixels with an alpha value greater than the threshold (from `0.0` to `1.0`) are
+    /// considered solid.
+    pub fn from_image_data(data: &ImageData, threshold: f32) -> CollisionMask {
+        CollisionMask::from_alpha_mask(AlphaMask::from_image_data(data, threshold))
+    }
+
+    /// Creates a collision mask from an existing alpha mask.
+    pub fn from_alpha_mask(mask: AlphaMask) -> CollisionMask {
+        let mut bounds: Option<(Vec2<i32>, Vec2<i32>)> = None;
+
+        for y in 0..mask.height() {
+            for x in 0..mask.width() {
+                let position = Vec2::new(x, y);
+
+                if mask.is_opaque_at(position) {
+                    bounds = Some(match bounds {
+                        Some((min, max)) => (
+                            Vec2::partial_min(min, position),
+                            Vec2::partial_max(max, position),
+                        ),
+                        None => (position, position),
+                    });
+                }
+            }
+        }
+
+        CollisionMask {
+            mask,
+            bounds: bounds.map(|(min, max)| {
+                Rectangle::new(min.x, min.y, max.x - min.x + 1, max.y - min.y + 1)
+            }),
+        }
+    }
+
+    /// Returns the underlying alpha mask.
+    pub fn alpha_mask(&self) -> &AlphaMask {
+        &self.mask
+    }
+
+    /// Returns the width of the mask.
+    pub fn width(&self) -> i32 {
+        self.mask.width()
+    }
+
+    /// Returns the height of the mask.
+    pub fn height(&self) -> i32 {
+        self.mask.height()
+    }
+
+    /// Returns the bounding box of the solid pixels in the mask, or `None` if the mask
+    /// has no solid pixels.
+    pub fn bounds(&self) -> Option<Rectangle<i32>> {
+        self.bounds
+    }
+
+    /// Returns the bounding box of the solid pixels in the mask, when it is drawn with
+    /// the given parameters, or `None` if the mask has no solid pixels.
+    pub fn world_bounds(&self, params: &DrawParams) -> Option<Rectangle> {
+        let bounds = self.bounds?;
+        let transform = Transform::new(params, self.mask.size());
+
+        let corners = [
+            Vec2::new(bounds.x, bounds.y),
+            Vec2::new(bounds.right(), bounds.y),
+            Vec2::new(bounds.x, bounds.bottom()),
+            Vec2::new(bounds.right(), bounds.bottom()),
+        ]
+        .map(|corner| transform.to_world(corner.as_()));
+
+        Some(bounding_box(&corners))
+    }
+
+    /// Returns whether a point hits a solid pixel of the mask, when it is drawn with
+    /// the given parameters.
+    pub fn contains_point(&self, point: Vec2<f32>, params: &DrawParams) -> bool {
+        self.mask.hit_test(point, params)
+    }
+
+    /// Returns whether any solid pixels of the mask overlap the given rectangle, when
+    /// the mask is drawn with the given parameters.
+    pub fn overlaps_rect(&self, params: &DrawParams, rect: Rectangle) -> bool {
+        let bounds = match self.world_bounds(params) {
+            Some(bounds) if bounds.intersects(&rect) => bounds,
+            _ => return false,
+        };
+
+        let transform = Transform::new(params, self.mask.size());
+
+        // If the rectangle covers all of the solid pixels, there's no need to check
+        // them individually.
+        if transform.is_axis_aligned() && rect.contains(&bounds) {
+            return true;
+        }
+
+        self.any_solid_in(&transform, &overlap(&bounds, &rect), |world| {
+            rect.contains_point(world)
+        })
+    }
+
+    /// Returns whether any solid pixels of the two masks overlap, when they are drawn
+    /// with the given parameters.
+    pub fn overlaps(
+        &self,
+        params: &DrawParams,
+        other: &CollisionMask,
+        other_params: &DrawParams,
+    ) -> bool {
+        let (bounds, other_bounds) =
+            match (self.world_bounds(params), other.world_bounds(other_params)) {
+                (Some(a), Some(b)) if a.intersects(&b) => (a, b),
+                _ => return false,
+            };
+
+        let area = overlap(&bounds, &other_bounds);
+
+        let transform = Transform::new(params, self.mask.size());
+        let other_transform = Transform::new(other_params, other.mask.size());
+
+        // Iterate over whichever mask has the fewest pixels in the overlapping area,
+        // and look up the other mask at the center of each of them.
+        let (a, a_transform, b, b_transform) =
+            if transform.pixel_area() >= other_transform.pixel_area() {
+                (self, &transform, other, &other_transform)
+            } else {
+                (other, &other_transform, self, &transform)
+            };
+
+        a.any_solid_in(a_transform, &area, |world| {
+            b_transform
+                .to_local(world)
+                .map(|local| b.mask.is_opaque_at(local.map(|v| v.floor() as i32)))
+                .unwrap_or(false)
+        })
+    }
+
+    /// Calls `test` with the world position of the center of each solid pixel that
+    /// falls within the given world space area, returning `true` as soon as it does.
+    fn any_solid_in<F>(&self, transform: &Transform, area: &Rectangle, mut test: F) -> bool
+    where
+        F: FnMut(Vec2<f32>) -> bool,
+    {
+        let bounds = match self.bounds {
+            Some(bounds) => bounds,
+            None => return false,
+        };
+
+        let corners = [
+            area.top_left(),
+            area.top_right(),
+            area.bottom_left(),
+            area.bottom_right(),
+        ]
+        .map(|corner| transform.to_local_unchecked(corner));
+
+        let local = bounding_box(&corners);
+
+        let min_x = (local.x.floor() as i32).max(bounds.x);
+        let min_y = (local.y.floor() as i32).max(bounds.y);
+        let max_x = (local.right().ceil() as i32).min(bounds.right());
+        let max_y = (local.bottom().ceil() as i32).min(bounds.bottom());
+
+        for y in min_y..max_y {
+            for x in min_x..max_x {
+                if self.mask.is_opaque_at(Vec2::new(x, y)) {
+                    let center = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+
+                    if test(transform.to_world(center)) {
+                        return true;
+                    }
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// The transformation applied by a set of `DrawParams`, and its inverse.
+struct Transform {
+    position: Vec2<f32>,
+    origin: Vec2<f32>,
+    scale: Vec2<f32>,
+    sin: f32,
+    cos: f32,
+}
+
+impl Transform {
+    fn new(params: &DrawParams, size: (i32, i32)) -> Transform {
+        let params = params
+            .clone()
+            .resolve_origin(Vec2::new(size.0 as f32, size.1 as f32));
+
+        let (sin, cos) = params.rotation.sin_cos();
+
+        Transform {
+            position: params.position,
+            origin: params.origin,
+            scale: params.scale,
+            sin,
+            cos,
+        }
+    }
+
+    fn is_axis_aligned(&self) -> bool {
+        self.sin == 0.0
+    }
+
+    fn pixel_area(&self) -> f32 {
+        (self.scale.x * self.scale.y).abs()
+    }
+
+    fn to_world(&self, local: Vec2<f32>) -> Vec2<f32> {
+        let scaled = (local - self.origin) * self.scale;
+
+        self.position
+            + Vec2::new(
+                self.cos * scaled.x - self.sin * scaled.y,
+                self.sin * scaled.x + self.cos * scaled.y,
+            )
+    }
+
+    fn to_local(&self, world: Vec2<f32>) -> Option<Vec2<f32>> {
+        if self.scale.x == 0.0 || self.scale.y == 0.0 {
+            None
+        } else {
+            Some(self.to_local_unchecked(world))
+        }
+    }
+
+    fn to_local_unchecked(&self, world: Vec2<f32>) -> Vec2<f32> {
+        let offset = world - self.position;
+
+        let rotated = Vec2::new(
+            self.cos * offset.x + self.sin * offset.y,
+            -self.sin * offset.x + self.cos * offset.y,
+        );
+
+        rotated / self.scale + self.origin
+    }
+}
+
+fn bounding_box(points: &[Vec2<f32>]) -> Rectangle {
+    let min = points.iter().copied().reduce(Vec2::partial_min).unwrap();
+    let max = points.iter().copied().reduce(Vec2::partial_max).unwrap();
+
+    Rectangle::new(min.x, min.y, max.x - min.x, max.y - min.y)
+}
+
+fn overlap(a: &Rectangle, b: &Rectangle) -> Rectangle {
+    let x = a.x.max(b.x);
+    let y = a.y.max(b.y);
+
+    Rectangle::new(
+        x,
+        y,
+        a.right().min(b.right()) - x,
+        a.bottom().min(b.bottom()) - y,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mask_overlaps() {
+        // A 2x2 image with only the top left and bottom right pixels solid.
+        let mut pixels = vec![0; 2 * 2 * 4];
+        pixels[3] = 255;
+        pixels[15] = 255;
+
+        let data = ImageData::from_rgba8(2, 2, pixels).unwrap();
+        let mask = CollisionMask::from_image_data(&data, 0.5);
+
+        assert_eq!(mask.bounds(), Some(Rectangle::new(0, 0, 2, 2)));
+
+        let at = |x, y| DrawParams::new().position(Vec2::new(x, y));
+
+        // The bounding boxes overlap, but the solid pixels don't.
+        assert!(!mask.overlaps(&at(0.0, 0.0), &mask, &at(1.0, 0.0)));
+        assert!(mask.overlaps(&at(0.0, 0.0), &mask, &at(1.0, 1.0)));
+        assert!(mask.overlaps(
+            &at(0.0, 0.0),
+            &mask,
+            &at(1.0, 1.0).scale(Vec2::new(0.5, 0.5))
+        ));
+
+        // Flipping the image horizontally puts its solid pixels on the other diagonal.
+        let flipped = |x| at(x, 0.0).scale(Vec2::new(-1.0, 1.0));
+        assert!(!mask.overlaps(&at(0.0, 0.0), &mask, &flipped(2.0)));
+        assert!(mask.overlaps(&at(0.0, 0.0), &mask, &flipped(3.0)));
+
+        assert!(mask.overlaps_rect(&at(0.0, 0.0), Rectangle::new(1.0, 1.0, 1.0, 1.0)));
+        assert!(!mask.overlaps_rect(&at(0.0, 0.0), Rectangle::new(1.0, 0.0, 1.0, 1.0)));
+    }
+}
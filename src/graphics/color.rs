@@ -3,7 +3,7 @@
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Sub, SubAssign};
 
 use crate::error::{Result, TetraError};
-use crate::math::Vec4;
+use crate::math::{Lerp, Vec4};
 
 /// An RGBA color.
 ///
@@ -217,6 +217,19 @@ impl From<[u8; 4]> for Color {
     }
 }
 
+impl Lerp<f32> for Color {
+    type Output = Color;
+
+    fn lerp_unclamped(from: Color, to: Color, factor: f32) -> Color {
+        Color::rgba(
+            from.r + (to.r - from.r) * factor,
+            from.g + (to.g - from.g) * factor,
+            from.b + (to.b - from.b) * factor,
+            from.a + (to.a - from.a) * factor,
+        )
+    }
+}
+
 impl Add for Color {
     type Output = Color;
 
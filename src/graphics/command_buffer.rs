@@ -0,0 +1,261 @@
+use crate::graphics::mesh::Mesh;
+use crate::graphics::{
+    self, BlendState, Canvas, Color, DrawParams, Rectangle, Shader, SortingLayer, Texture,
+};
+use crate::math::Mat4;
+use crate::Context;
+
+#[derive(Debug, Clone)]
+enum DrawCommand {
+    Draw {
+        texture: Texture,
+        region: Option<Rectangle>,
+        params: DrawParams,
+    },
+    DrawMesh {
+        mesh: Mesh,
+        params: DrawParams,
+    },
+    Clear(Color),
+    SetShader(Option<Shader>),
+    SetCanvas(Option<Canvas>),
+    SetBlendState(BlendState),
+    SetTransformMatrix(Mat4<f32>),
+    SetDrawDepth(f32),
+    SetSortingLayer(SortingLayer),
+    SetOrderInLayer(i32),
+}
+
+/// A list of drawing commands, which can be recorded without access to the [`Context`],
+/// and then executed later.
+///
+/// Recording a command buffer does not touch the graphics device, and the buffer can
+/// be sent between threads. This means that game systems can build up their draw lists
+/// in parallel (e.g. one buffer per system, or per chunk of a large world), and then
+/// hand them over to the main thread to be executed.
+///
+/// Commands refer to resources via their handles ([`Texture`], [`Mesh`], [`Shader`] and
+/// [`Canvas`] are all cheap to clone, and can be sent between threads), so the resources
+/// themselves still need to be created on the main thread.
+///
+/// Executing a buffer does not consume it, so a buffer can also be used to cache a list
+/// of draws that rarely change. State that is changed by the commands (such as the shader
+/// or the transform matrix) is not restored after the buffer has been executed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{DrawCommandBuffer, Texture};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, texture: Texture, positions: Vec<Vec2<f32>>) {
+/// let handle = std::thread::spawn(move || {
+///     let mut commands = DrawCommandBuffer::new();
+///
+///     for position in positions {
+///         commands.draw(&texture, position);
+///     }
+///
+///     commands
+/// });
+///
+/// let commands = handle.join().unwrap();
+/// commands.execute(ctx);
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DrawCommandBuffer {
+    commands: Vec<DrawCommand>,
+}
+
+impl DrawCommandBuffer {
+    /// Creates a new, empty command buffer.
+    pub fn new() -> DrawCommandBuffer {
+        DrawCommandBuffer {
+            commands: Vec::new(),
+        }
+    }
+
+    /// Creates a new, empty command buffer, with space for the given number of commands.
+    pub fn with_capacity(capacity: usize) -> DrawCommandBuffer {
+        DrawCommandBuffer {
+            commands: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Records a command to draw a texture.
+    ///
+    /// See [`Texture::draw`].
+    pub fn draw<P>(&mut self, texture: &Texture, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        self.commands.push(DrawCommand::Draw {
+            texture: texture.clone(),
+            region: None,
+            params: params.into(),
+        });
+    }
+
+    /// Records a command to draw a region of a texture.
+    ///
+    /// See [`Texture::draw_region`].
+    pub fn draw_region<P>(&mut self, texture: &Texture, region: Rectangle, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        self.commands.push(DrawCommand::Draw {
+            texture: texture.clone(),
+            region: Some(region),
+            params: params.into(),
+        });
+    }
+
+    /// Records a command to draw a mesh.
+    ///
+    /// See [`Mesh::draw`].
+    pub fn draw_mesh<P>(&mut self, mesh: &Mesh, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        self.commands.push(DrawCommand::DrawMesh {
+            mesh: mesh.clone(),
+            params: params.into(),
+        });
+    }
+
+    /// Records a command to clear the screen (or the active canvas) to the given color.
+    ///
+    /// See [`graphics::clear`].
+    pub fn clear_target(&mut self, color: Color) {
+        self.commands.push(DrawCommand::Clear(color));
+    }
+
+    /// Records a command to change the active shader.
+    ///
+    /// See [`graphics::set_shader`].
+    pub fn set_shader(&mut self, shader: &Shader) {
+        self.commands
+            .push(DrawCommand::SetShader(Some(shader.clone())));
+    }
+
+    /// Records a command to go back to the default shader.
+    ///
+    /// See [`graphics::reset_shader`].
+    pub fn reset_shader(&mut self) {
+        self.commands.push(DrawCommand::SetShader(None));
+    }
+
+    /// Records a command to start drawing to a canvas.
+    ///
+    /// See [`graphics::set_canvas`].
+    pub fn set_canvas(&mut self, canvas: &Canvas) {
+        self.commands
+            .push(DrawCommand::SetCanvas(Some(canvas.clone())));
+    }
+
+    /// Records a command to go back to drawing to the screen.
+    ///
+    /// See [`graphics::reset_canvas`].
+    pub fn reset_canvas(&mut self) {
+        self.commands.push(DrawCommand::SetCanvas(None));
+    }
+
+    /// Records a command to change the blend state.
+    ///
+    /// See [`graphics::set_blend_state`].
+    pub fn set_blend_state(&mut self, blend_state: BlendState) {
+        self.commands.push(DrawCommand::SetBlendState(blend_state));
+    }
+
+    /// Records a command to change the transform matrix.
+    ///
+    /// See [`graphics::set_transform_matrix`].
+    pub fn set_transform_matrix(&mut self, matrix: Mat4<f32>) {
+        self.commands.push(DrawCommand::SetTransformMatrix(matrix));
+    }
+
+    /// Records a command to reset the transform matrix.
+    ///
+    /// See [`graphics::reset_transform_matrix`].
+    pub fn reset_transform_matrix(&mut self) {
+        self.set_transform_matrix(Mat4::identity());
+    }
+
+    /// Records a command to change the draw depth.
+    ///
+    /// See [`graphics::set_draw_depth`].
+    pub fn set_draw_depth(&mut self, depth: f32) {
+        self.commands.push(DrawCommand::SetDrawDepth(depth));
+    }
+
+    /// Records a command to change the sorting layer.
+    ///
+    /// See [`graphics::set_sorting_layer`].
+    pub fn set_sorting_layer(&mut self, layer: SortingLayer) {
+        self.commands.push(DrawCommand::SetSortingLayer(layer));
+    }
+
+    /// Records a command to change the order in layer.
+    ///
+    /// See [`graphics::set_order_in_layer`].
+    pub fn set_order_in_layer(&mut self, order: i32) {
+        self.commands.push(DrawCommand::SetOrderInLayer(order));
+    }
+
+    /// Moves all of the commands from another buffer to the end of this one, leaving
+    /// the other buffer empty.
+    ///
+    /// This can be used to merge together buffers that were recorded in parallel.
+    pub fn append(&mut self, other: &mut DrawCommandBuffer) {
+        self.commands.append(&mut other.commands);
+    }
+
+    /// Returns the number of commands in the buffer.
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Returns `true` if the buffer contains no commands.
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+
+    /// Removes all of the commands from the buffer, keeping the allocated memory.
+    pub fn clear(&mut self) {
+        self.commands.clear();
+    }
+
+    /// Executes the commands in the buffer, in the order they were recorded.
+    pub fn execute(&self, ctx: &mut Context) {
+        for command in &self.commands {
+            match command {
+                DrawCommand::Draw {
+                    texture,
+                    region: None,
+                    params,
+                } => texture.draw(ctx, params.clone()),
+
+                DrawCommand::Draw {
+                    texture,
+                    region: Some(region),
+                    params,
+                } => texture.draw_region(ctx, *region, params.clone()),
+
+                DrawCommand::DrawMesh { mesh, params } => mesh.draw(ctx, params.clone()),
+                DrawCommand::Clear(color) => graphics::clear(ctx, *color),
+                DrawCommand::SetShader(shader) => graphics::set_shader_ex(ctx, shader.as_ref()),
+                DrawCommand::SetCanvas(canvas) => graphics::set_canvas_ex(ctx, canvas.as_ref()),
+                DrawCommand::SetBlendState(state) => graphics::set_blend_state(ctx, *state),
+
+                DrawCommand::SetTransformMatrix(matrix) => {
+                    graphics::set_transform_matrix(ctx, *matrix)
+                }
+
+                DrawCommand::SetDrawDepth(depth) => graphics::set_draw_depth(ctx, *depth),
+                DrawCommand::SetSortingLayer(layer) => graphics::set_sorting_layer(ctx, *layer),
+                DrawCommand::SetOrderInLayer(order) => graphics::set_order_in_layer(ctx, *order),
+            }
+        }
+    }
+}
@@ -0,0 +1,165 @@
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::debug::{ResourceKind, TrackedResource};
+use crate::error::{Result, TetraError};
+use crate::graphics::{FilterMode, ImageData};
+use crate::platform::RawTexture;
+use crate::Context;
+
+#[derive(Debug)]
+pub(crate) struct CubemapSharedData {
+    pub(crate) handle: RawTexture,
+    filter_mode: FilterMode,
+    _tracker: TrackedResource,
+}
+
+impl PartialEq for CubemapSharedData {
+    fn eq(&self, other: &CubemapSharedData) -> bool {
+        self.handle.eq(&other.handle)
+    }
+}
+
+/// A texture made up of six square faces, which can be sampled by direction rather
+/// than by position.
+///
+/// Cubemaps cannot be drawn directly - instead, they can be passed to a custom
+/// [`Shader`](super::Shader) via [`set_uniform`](super::Shader::set_uniform), and then
+/// accessed in GLSL via a `samplerCube`. This is useful for skybox-style backgrounds
+/// in 2.5D scenes, and for faking reflections on shiny surfaces.
+///
+/// The faces are given in the order that OpenGL expects them: positive X, negative X,
+/// positive Y, negative Y, positive Z, negative Z. Each face must be square, and all
+/// of the faces must be the same size.
+///
+/// # Performance
+///
+/// Creating a cubemap is as expensive as creating six textures, so try to reuse them.
+///
+/// You can clone a cubemap cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/sync/struct.Arc.html)
+/// handle to a GPU resource.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{Cubemap, Shader};
+/// # fn example(ctx: &mut Context, shader: &Shader) -> tetra::Result {
+/// let sky = Cubemap::new(
+///     ctx,
+///     [
+///         "./sky/right.png",
+///         "./sky/left.png",
+///         "./sky/top.png",
+///         "./sky/bottom.png",
+///         "./sky/front.png",
+///         "./sky/back.png",
+///     ],
+/// )?;
+///
+/// // In the shader: `uniform samplerCube u_sky;`
+/// shader.set_uniform(ctx, "u_sky", &sky);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cubemap {
+    pub(crate) data: Arc<CubemapSharedData>,
+}
+
+impl Cubemap {
+    /// Creates a new cubemap from six image files.
+    ///
+    /// The format of each face will be determined based on the file extension.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`] will be returned if the faces are larger than the
+    ///   [maximum texture size](crate::graphics::Capabilities::max_texture_size) supported by the device.
+    /// * [`TetraError::FailedToLoadAsset`] will be returned if any of the files could not be loaded.
+    /// * [`TetraError::InvalidTexture`] will be returned if any of the image data was invalid.
+    /// * [`TetraError::InvalidCubemap`] will be returned if the faces are not square, or are not
+    ///   all the same size.
+    #[track_caller]
+    pub fn new<P>(ctx: &mut Context, paths: [P; 6]) -> Result<Cubemap>
+    where
+        P: AsRef<Path>,
+    {
+        let [px, nx, py, ny, pz, nz] = paths;
+
+        Cubemap::from_image_data(
+            ctx,
+            [
+                &ImageData::from_file(px)?,
+                &ImageData::from_file(nx)?,
+                &ImageData::from_file(py)?,
+                &ImageData::from_file(ny)?,
+                &ImageData::from_file(pz)?,
+                &ImageData::from_file(nz)?,
+            ],
+        )
+    }
+
+    /// Creates a new cubemap from six [`ImageData`]s.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`] will be returned if the faces are larger than the
+    ///   [maximum texture size](crate::graphics::Capabilities::max_texture_size) supported by the device.
+    /// * [`TetraError::InvalidCubemap`] will be returned if the faces are not square, or are not
+    ///   all the same size.
+    #[track_caller]
+    pub fn from_image_data(ctx: &mut Context, faces: [&ImageData; 6]) -> Result<Cubemap> {
+        let size = faces[0].width();
+
+        for face in &faces {
+            if face.width() != size || face.height() != size {
+                return Err(TetraError::InvalidCubemap(format!(
+                    "expected {}x{} faces, found {}x{}",
+                    size,
+                    size,
+                    face.width(),
+                    face.height()
+                )));
+            }
+        }
+
+        let filter_mode = ctx.graphics.default_filter_mode;
+
+        let handle = ctx.device.new_cubemap(
+            size,
+            [
+                faces[0].as_bytes(),
+                faces[1].as_bytes(),
+                faces[2].as_bytes(),
+                faces[3].as_bytes(),
+                faces[4].as_bytes(),
+                faces[5].as_bytes(),
+            ],
+            filter_mode,
+        )?;
+
+        Ok(Cubemap {
+            data: Arc::new(CubemapSharedData {
+                handle,
+                filter_mode,
+                _tracker: TrackedResource::new(ResourceKind::Cubemap, Some((size, size))),
+            }),
+        })
+    }
+
+    /// Returns the width and height of each face of the cubemap.
+    pub fn size(&self) -> i32 {
+        self.data.handle.width()
+    }
+
+    /// Returns the filter mode that the cubemap was created with.
+    ///
+    /// This is the [default filter mode](crate::graphics::set_default_filter_mode) at the
+    /// time of creation.
+    pub fn filter_mode(&self) -> FilterMode {
+        self.data.filter_mode
+    }
+}
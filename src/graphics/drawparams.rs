@@ -1,5 +1,5 @@
-use crate::graphics::Color;
-use crate::math::{Mat4, Vec2, Vec3};
+use crate::graphics::{Color, FilterMode};
+use crate::math::{Mat4, Vec2, Vec3, Vec4};
 
 /// Parameters that can be manipulated when drawing an object.
 ///
@@ -22,11 +22,64 @@ pub struct DrawParams {
     /// relative to the center of the image.
     pub origin: Vec2<f32>,
 
+    /// The origin of the graphic, relative to its size. Defaults to `None`.
+    ///
+    /// If this is set, it will replace [`origin`](Self::origin) when the graphic is drawn.
+    /// `(0.0, 0.0)` is the top left of the graphic, and `(1.0, 1.0)` is the bottom right,
+    /// so there is no need to work out the size of each texture region by hand. Graphics
+    /// that do not have a well-defined size (such as meshes) will ignore this.
+    pub origin_normalized: Option<Vec2<f32>>,
+
     /// The rotation of the graphic, in radians. Defaults to `0.0`.
     pub rotation: f32,
 
     /// A color to multiply the graphic by. Defaults to [`Color::WHITE`].
     pub color: Color,
+
+    /// A flat color to blend the graphic towards. Defaults to [`Color::WHITE`].
+    ///
+    /// Unlike [`color`](Self::color), which multiplies the graphic (and so can only ever
+    /// make it darker), this replaces the graphic's color while keeping its shape, which
+    /// is useful for effects like flashing a sprite white when it takes damage. The amount
+    /// of blending is controlled by [`overlay_amount`](Self::overlay_amount).
+    ///
+    /// This is implemented in the default shader - custom shaders can access it via the
+    /// `a_overlay` vertex attribute. Meshes use the overlay stored in each
+    /// [`Vertex`](crate::graphics::mesh::Vertex) instead.
+    ///
+    /// The blending assumes that the texture does not have premultiplied alpha - with
+    /// premultiplied textures, semi-transparent edges may appear brighter than expected.
+    pub overlay_color: Color,
+
+    /// How much the graphic is blended towards the [`overlay_color`](Self::overlay_color),
+    /// from `0.0` (not at all) to `1.0` (a solid color). Defaults to `0.0`.
+    pub overlay_amount: f32,
+
+    /// Four arbitrary values to pass to the shader. Defaults to `(0.0, 0.0, 0.0, 0.0)`.
+    ///
+    /// Changing a uniform requires the current batch to be flushed, so using uniforms
+    /// for values that differ for each sprite (e.g. how far a dissolve effect has
+    /// progressed) means every sprite is drawn separately. These values are instead
+    /// stored in each vertex, so sprites with different values can still be batched
+    /// together (and [sorted](crate::graphics::begin_sorted)).
+    ///
+    /// The values are available in vertex shaders via the `a_params` attribute. The default
+    /// vertex shader passes them through to the fragment shader as `v_params`, so custom
+    /// fragment shaders can use them without having to write a vertex shader. Meshes use
+    /// the values stored in each [`Vertex`](crate::graphics::mesh::Vertex) instead.
+    pub shader_params: Vec4<f32>,
+
+    /// The filter mode to use when sampling the graphic's texture. Defaults to `None`.
+    ///
+    /// If this is `None`, the texture's own [filter mode](crate::graphics::Texture::filter_mode)
+    /// will be used. Setting it allows the same texture to be drawn with different filtering
+    /// in different places (e.g. crisp in the game world, but smoothed in a zoomed out
+    /// minimap), without having to create a second copy of it.
+    ///
+    /// This requires [sampler object support](crate::graphics::Capabilities::sampler_objects) -
+    /// if the device does not support them, the override will be ignored. Overriding the
+    /// filter mode will also disable anisotropic filtering for the draw.
+    pub filter_mode: Option<FilterMode>,
 }
 
 impl DrawParams {
@@ -48,8 +101,37 @@ impl DrawParams {
     }
 
     /// Sets the origin of the graphic.
+    ///
+    /// This will clear any normalized origin that was previously set.
     pub fn origin(mut self, origin: Vec2<f32>) -> DrawParams {
         self.origin = origin;
+        self.origin_normalized = None;
+        self
+    }
+
+    /// Sets the origin of the graphic, relative to its size.
+    ///
+    /// `(0.0, 0.0)` is the top left of the graphic, and `(1.0, 1.0)` is the bottom right.
+    pub fn origin_normalized(mut self, origin: Vec2<f32>) -> DrawParams {
+        self.origin_normalized = Some(origin);
+        self
+    }
+
+    /// Sets the origin of the graphic to one of the preset positions.
+    pub fn origin_preset(self, origin: Origin) -> DrawParams {
+        self.origin_normalized(origin.to_normalized())
+    }
+
+    /// Replaces the normalized origin (if one is set) with the equivalent origin
+    /// in pixels, for a graphic of the given size.
+    ///
+    /// Tetra's drawable types call this automatically, so you only need to use it if
+    /// you are implementing your own.
+    pub fn resolve_origin(mut self, size: Vec2<f32>) -> DrawParams {
+        if let Some(normalized) = self.origin_normalized.take() {
+            self.origin = normalized * size;
+        }
+
         self
     }
 
@@ -65,10 +147,33 @@ impl DrawParams {
         self
     }
 
+    /// Sets a flat color to blend the graphic towards, and the amount of blending (from
+    /// `0.0` to `1.0`).
+    pub fn overlay_color(mut self, color: Color, amount: f32) -> DrawParams {
+        self.overlay_color = color;
+        self.overlay_amount = amount;
+        self
+    }
+
+    /// Sets four arbitrary values to pass to the shader.
+    pub fn shader_params(mut self, shader_params: Vec4<f32>) -> DrawParams {
+        self.shader_params = shader_params;
+        self
+    }
+
+    /// Sets the filter mode to use when sampling the graphic's texture, overriding
+    /// the texture's own filter mode.
+    pub fn filter_mode(mut self, filter_mode: FilterMode) -> DrawParams {
+        self.filter_mode = Some(filter_mode);
+        self
+    }
+
     /// Creates a new transformation matrix equivalent to this set of params.
     ///
     /// This method does not take into account `color`, as it cannot
-    /// be represented via a matrix.
+    /// be represented via a matrix. It also does not take into account
+    /// `origin_normalized`, as the size of the graphic is not known - use
+    /// [`resolve_origin`](Self::resolve_origin) first if you need it.
     pub fn to_matrix(&self) -> Mat4<f32> {
         let mut matrix = Mat4::translation_2d(-self.origin);
         matrix.scale_3d(Vec3::from(self.scale));
@@ -84,8 +189,67 @@ impl Default for DrawParams {
             position: Vec2::new(0.0, 0.0),
             scale: Vec2::new(1.0, 1.0),
             origin: Vec2::new(0.0, 0.0),
+            origin_normalized: None,
             rotation: 0.0,
             color: Color::WHITE,
+            overlay_color: Color::WHITE,
+            overlay_amount: 0.0,
+            shader_params: Vec4::zero(),
+            filter_mode: None,
+        }
+    }
+}
+
+/// Preset positions for the origin of a graphic.
+///
+/// These can be applied via [`DrawParams::origin_preset`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Origin {
+    /// The top left corner.
+    TopLeft,
+
+    /// The center of the top edge.
+    TopCenter,
+
+    /// The top right corner.
+    TopRight,
+
+    /// The center of the left edge.
+    CenterLeft,
+
+    /// The center.
+    Center,
+
+    /// The center of the right edge.
+    CenterRight,
+
+    /// The bottom left corner.
+    BottomLeft,
+
+    /// The center of the bottom edge.
+    BottomCenter,
+
+    /// The bottom right corner.
+    BottomRight,
+}
+
+impl Origin {
+    /// Returns the position of the origin, relative to the size of the graphic.
+    pub fn to_normalized(self) -> Vec2<f32> {
+        match self {
+            Origin::TopLeft => Vec2::new(0.0, 0.0),
+            Origin::TopCenter => Vec2::new(0.5, 0.0),
+            Origin::TopRight => Vec2::new(1.0, 0.0),
+            Origin::CenterLeft => Vec2::new(0.0, 0.5),
+            Origin::Center => Vec2::new(0.5, 0.5),
+            Origin::CenterRight => Vec2::new(1.0, 0.5),
+            Origin::BottomLeft => Vec2::new(0.0, 1.0),
+            Origin::BottomCenter => Vec2::new(0.5, 1.0),
+            Origin::BottomRight => Vec2::new(1.0, 1.0),
         }
     }
 }
@@ -0,0 +1,182 @@
+use crate::graphics::{DrawParams, ImageData, Rectangle};
+use crate::math::Vec2;
+
+/// A record of which pixels of an image are opaque, stored on the CPU.
+///
+/// This can be used for pixel-accurate hit testing of irregularly shaped sprites (e.g.
+/// for mouse picking), where testing against a bounding box would be too imprecise.
+/// Each pixel is stored as a single bit, so masks are cheap to keep around.
+///
+/// Masks can be created from [`ImageData`], or read back from a texture via
+/// [`Texture::alpha_mask`](super::Texture::alpha_mask).
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{DrawParams, Texture, Camera};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, texture: &Texture, camera: &Camera) {
+/// let mask = texture.alpha_mask(ctx, 0.5);
+///
+/// let params = DrawParams::new()
+///     .position(Vec2::new(128.0, 64.0))
+///     .rotation(0.5);
+///
+/// if mask.hit_test(camera.mouse_position(ctx), &params) {
+///     // The mouse is over an opaque part of the sprite.
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AlphaMask {
+    width: i32,
+    height: i32,
+    bits: Vec<u64>,
+}
+
+impl AlphaMask {
+    /// Creates a mask from the given image data.
+    ///
+    /// Pixels with an alpha value greater than the threshold (from `0.0` to `1.0`) are
+    /// considered opaque. A threshold of `0.0` means that any pixel that is not completely
+    /// transparent will be counted.
+    pub fn from_image_data(data: &ImageData, threshold: f32) -> AlphaMask {
+        let (width, height) = data.size();
+        let threshold = (threshold.clamp(0.0, 1.0) * 255.0) as u8;
+
+        let mut bits = vec![0; (width * height) as usize / 64 + 1];
+
+        for (i, pixel) in data.as_bytes().chunks_exact(4).enumerate() {
+            if pixel[3] > threshold {
+                bits[i / 64] |= 1 << (i % 64);
+            }
+        }
+
+        AlphaMask {
+            width,
+            height,
+            bits,
+        }
+    }
+
+    /// Returns the width of the mask.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Returns the height of the mask.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Returns the size of the mask.
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    /// Returns whether the pixel at the given position is opaque.
+    ///
+    /// Positions outside of the mask are considered transparent.
+    pub fn is_opaque_at(&self, position: Vec2<i32>) -> bool {
+        if position.x < 0 || position.y < 0 || position.x >= self.width || position.y >= self.height
+        {
+            return false;
+        }
+
+        let i = (position.y * self.width + position.x) as usize;
+
+        self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    /// Returns whether a point hits an opaque pixel of the image, when it is drawn with the
+    /// given parameters.
+    ///
+    /// The point should be in the same co-ordinate space as the parameters - for example,
+    /// if the image is drawn with a [`Camera`](super::Camera) applied, use
+    /// [`Camera::mouse_position`](super::Camera::mouse_position) rather than the position
+    /// of the mouse on the screen.
+    pub fn hit_test(&self, point: Vec2<f32>, params: &DrawParams) -> bool {
+        self.hit_test_region(
+            point,
+            Rectangle::new(0.0, 0.0, self.width as f32, self.height as f32),
+            params,
+        )
+    }
+
+    /// Returns whether a point hits an opaque pixel of a region of the image, when that
+    /// region is drawn with the given parameters.
+    ///
+    /// This can be used to hit test sprites that are drawn from a sprite sheet (e.g. via
+    /// [`Texture::draw_region`](super::Texture::draw_region)). See [`hit_test`](Self::hit_test)
+    /// for more information.
+    pub fn hit_test_region(
+        &self,
+        point: Vec2<f32>,
+        region: Rectangle,
+        params: &DrawParams,
+    ) -> bool {
+        let params = params
+            .clone()
+            .resolve_origin(Vec2::new(region.width, region.height));
+
+        if params.scale.x == 0.0 || params.scale.y == 0.0 {
+            return false;
+        }
+
+        // This is the inverse of the transformation applied by `DrawParams::to_matrix`.
+        let mut local = point - params.position;
+
+        if params.rotation != 0.0 {
+            let (sin, cos) = (-params.rotation).sin_cos();
+            local = Vec2::new(cos * local.x - sin * local.y, sin * local.x + cos * local.y);
+        }
+
+        local = local / params.scale + params.origin;
+
+        if local.x < 0.0 || local.y < 0.0 || local.x >= region.width || local.y >= region.height {
+            return false;
+        }
+
+        self.is_opaque_at(Vec2::new(
+            (region.x + local.x).floor() as i32,
+            (region.y + local.y).floor() as i32,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hit_testing() {
+        // A 2x2 image with only the top right pixel opaque.
+        let data = ImageData::from_rgba8(
+            2,
+            2,
+            vec![0, 0, 0, 0, 0, 0, 0, 255, 0, 0, 0, 0, 0, 0, 0, 64],
+        )
+        .unwrap();
+
+        let mask = AlphaMask::from_image_data(&data, 0.5);
+
+        assert!(mask.is_opaque_at(Vec2::new(1, 0)));
+        assert!(!mask.is_opaque_at(Vec2::new(1, 1)));
+        assert!(!mask.is_opaque_at(Vec2::new(2, 0)));
+
+        let params = DrawParams::new()
+            .position(Vec2::new(10.0, 10.0))
+            .scale(Vec2::new(4.0, 4.0));
+
+        assert!(mask.hit_test(Vec2::new(15.0, 11.0), &params));
+        assert!(!mask.hit_test(Vec2::new(11.0, 11.0), &params));
+
+        // Flipped horizontally, so the image extends to the left of the position.
+        let flipped = params.scale(Vec2::new(-4.0, 4.0));
+
+        assert!(mask.hit_test(Vec2::new(4.0, 11.0), &flipped));
+        assert!(!mask.hit_test(Vec2::new(7.0, 11.0), &flipped));
+        assert!(!mask.hit_test(Vec2::new(15.0, 11.0), &flipped));
+    }
+}
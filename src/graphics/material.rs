@@ -0,0 +1,133 @@
+use std::fmt::{self, Debug, Formatter};
+use std::sync::Arc;
+
+use crate::graphics::{self, Shader, UniformValue};
+use crate::Context;
+
+/// A shader, bundled together with a set of uniform values.
+///
+/// Setting uniforms on a [`Shader`] directly affects every draw that uses it, which means
+/// that sharing a shader between multiple objects with different parameters requires
+/// re-setting the uniforms before each one is drawn. A material instead stores its own
+/// copy of the uniform values, so each object can own a material, and the whole set of
+/// values can be applied in one go via [`graphics::set_material`](crate::graphics::set_material).
+///
+/// Textures can also be stored in a material, as [`Texture`](crate::graphics::Texture)
+/// implements [`UniformValue`].
+///
+/// Cloning a material is cheap, as the uniform values are reference-counted. Changing a
+/// uniform on a clone will not affect the original.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, Color, DrawParams, Material, Shader, Texture};
+/// # fn example(ctx: &mut Context, shader: Shader, texture: Texture) {
+/// let mut hit_flash = Material::new(shader);
+/// hit_flash.set_uniform("u_flash_color", Color::RED);
+/// hit_flash.set_uniform("u_flash_amount", 0.5);
+///
+/// graphics::set_material(ctx, &hit_flash);
+/// texture.draw(ctx, DrawParams::new());
+/// graphics::reset_shader(ctx);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Material {
+    shader: Shader,
+    uniforms: Vec<(String, Arc<dyn UniformValue + Send + Sync>)>,
+}
+
+impl Material {
+    /// Creates a new material for the given shader, with no uniform values set.
+    pub fn new(shader: Shader) -> Material {
+        Material {
+            shader,
+            uniforms: Vec::new(),
+        }
+    }
+
+    /// Returns the shader that the material uses.
+    pub fn shader(&self) -> &Shader {
+        &self.shader
+    }
+
+    /// Sets the shader that the material uses.
+    ///
+    /// The stored uniform values will be kept.
+    pub fn set_shader(&mut self, shader: Shader) {
+        self.shader = shader;
+    }
+
+    /// Sets the value of a uniform in the material.
+    ///
+    /// This will not affect the shader until the material is applied. If a value has
+    /// already been set for this uniform, it will be replaced.
+    pub fn set_uniform<V>(&mut self, name: &str, value: V)
+    where
+        V: UniformValue + Send + Sync + 'static,
+    {
+        let value = Arc::new(value);
+
+        match self.uniforms.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.uniforms.push((name.to_owned(), value)),
+        }
+    }
+
+    /// Removes a uniform value from the material.
+    ///
+    /// Returns `true` if a value was set for the uniform. Note that the shader will
+    /// retain whatever value was last applied to it.
+    pub fn remove_uniform(&mut self, name: &str) -> bool {
+        let len = self.uniforms.len();
+        self.uniforms.retain(|(n, _)| n != name);
+        self.uniforms.len() != len
+    }
+
+    /// Returns whether a value has been set for the given uniform.
+    pub fn has_uniform(&self, name: &str) -> bool {
+        self.uniforms.iter().any(|(n, _)| n == name)
+    }
+
+    /// Returns the names of the uniforms that have values set, in the order they were added.
+    pub fn uniform_names(&self) -> impl Iterator<Item = &str> {
+        self.uniforms.iter().map(|(n, _)| n.as_str())
+    }
+
+    /// Applies the material's uniform values to its shader, without making it active.
+    ///
+    /// In most cases, you will want to use [`graphics::set_material`](crate::graphics::set_material)
+    /// instead, which also takes care of flushing any pending draws that were made using the
+    /// previous uniform values.
+    pub fn apply(&self, ctx: &mut Context) {
+        for (name, value) in &self.uniforms {
+            self.shader.set_uniform(ctx, name, &**value);
+        }
+    }
+}
+
+impl Debug for Material {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Material")
+            .field("shader", &self.shader)
+            .field("uniforms", &self.uniform_names().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Sets the material that will be used when rendering.
+///
+/// This makes the material's shader active (as with [`set_shader`](graphics::set_shader)),
+/// and then applies its uniform values. Any pending draws are flushed first, so that they
+/// are rendered with the values that were active when they were made. This happens even
+/// if [sorting](graphics::begin_sorted) is enabled, as draws using different uniform values
+/// cannot be batched together.
+///
+/// To go back to the default shader, call [`reset_shader`](graphics::reset_shader).
+pub fn set_material(ctx: &mut Context, material: &Material) {
+    graphics::flush(ctx);
+    graphics::set_shader(ctx, &material.shader);
+    material.apply(ctx);
+}
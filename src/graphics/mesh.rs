@@ -9,7 +9,8 @@
 
 pub use lyon_tessellation::path::builder::BorderRadii;
 
-use std::rc::Rc;
+use std::ops::Range;
+use std::sync::{Arc, Mutex, MutexGuard};
 
 use bytemuck::{Pod, Zeroable};
 use lyon_tessellation::geom::euclid::{Point2D, Size2D};
@@ -17,12 +18,12 @@ use lyon_tessellation::math::{Angle, Point, Rect, Vector};
 use lyon_tessellation::path::builder::{Build, PathBuilder};
 use lyon_tessellation::path::{Polygon, Winding};
 use lyon_tessellation::{
-    BuffersBuilder, FillOptions, FillTessellator, FillVertex, FillVertexConstructor, StrokeOptions,
-    StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
+    BuffersBuilder, FillOptions, FillRule, FillTessellator, FillVertex, FillVertexConstructor,
+    StrokeOptions, StrokeTessellator, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
 };
 
 use crate::graphics::{self, Color, DrawParams, Rectangle, Texture};
-use crate::math::Vec2;
+use crate::math::{Vec2, Vec4};
 use crate::platform::{RawIndexBuffer, RawVertexBuffer};
 use crate::Context;
 use crate::{Result, TetraError};
@@ -47,6 +48,22 @@ pub struct Vertex {
     /// This will be multiplied by the `color` of the `DrawParams` when drawing a
     /// mesh.
     pub color: Color,
+
+    /// A flat color that the sampled texture color will be blended towards.
+    ///
+    /// The red, green and blue channels give the color, and the alpha channel gives the
+    /// amount, from `0` (no overlay) to `255` (solid color). The default shader applies
+    /// this before the texture color is multiplied by [`color`](Self::color).
+    ///
+    /// To keep vertices small, this is stored as 8-bit RGBA rather than as a [`Color`] -
+    /// use `Color::into` to convert. Shaders receive it as a normalized `vec4`.
+    pub overlay: [u8; 4],
+
+    /// Four arbitrary values that will be passed to the shader.
+    ///
+    /// These are not used by the default shader, but can be read by custom shaders via
+    /// the `a_params` attribute. See [`DrawParams::shader_params`] for more information.
+    pub shader_params: Vec4<f32>,
 }
 
 impl Vertex {
@@ -56,6 +73,8 @@ impl Vertex {
             position,
             uv,
             color,
+            overlay: [0; 4],
+            shader_params: Vec4::zero(),
         }
     }
 }
@@ -100,6 +119,34 @@ impl VertexWinding {
     }
 }
 
+/// The type of primitive that a mesh's vertices (or indices) describe.
+///
+/// Most geometry in Tetra is made up of triangle lists, but other topologies can
+/// be more efficient for certain kinds of data - for example, a line strip can be
+/// used to draw a debug path without needing to triangulate it, and a triangle strip
+/// can be used to draw a trail with half as many vertices as a triangle list.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PrimitiveTopology {
+    /// Every three vertices form a separate triangle.
+    Triangles,
+
+    /// Every vertex after the first two forms a triangle with the previous two vertices.
+    TriangleStrip,
+
+    /// Every vertex after the first two forms a triangle with the previous vertex and
+    /// the first vertex.
+    TriangleFan,
+
+    /// Every two vertices form a separate line, one pixel wide.
+    Lines,
+
+    /// Every vertex after the first forms a line with the previous vertex, one pixel wide.
+    LineStrip,
+
+    /// Every vertex is drawn as a single point, one pixel in size.
+    Points,
+}
+
 /// Vertex data, stored in GPU memory.
 ///
 /// This data can be drawn to the screen via a [`Mesh`].
@@ -110,13 +157,20 @@ impl VertexWinding {
 /// can be relatively slow. You should try to minimize how often you do this - for example, if a piece
 /// of geometry does not change from frame to frame, reuse the buffer instead of recreating it.
 ///
-/// You can clone a vertex buffer cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/rc/struct.Rc.html)
+/// You can clone a vertex buffer cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/sync/struct.Arc.html)
 /// handle to a GPU resource. However, this does mean that modifying a buffer (e.g.
 /// calling `set_data`) will also affect any clones that exist of it.
 ///
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct VertexBuffer {
-    handle: Rc<RawVertexBuffer>,
+    handle: Arc<RawVertexBuffer>,
+    shadow: Option<Arc<Mutex<Vec<Vertex>>>>,
+}
+
+impl PartialEq for VertexBuffer {
+    fn eq(&self, other: &VertexBuffer) -> bool {
+        self.handle == other.handle
+    }
 }
 
 impl VertexBuffer {
@@ -151,10 +205,20 @@ impl VertexBuffer {
         ctx.device.set_vertex_buffer_data(&buffer, vertices, 0);
 
         Ok(VertexBuffer {
-            handle: Rc::new(buffer),
+            handle: Arc::new(buffer),
+            shadow: None,
         })
     }
 
+    /// Creates a new vertex buffer that also keeps a copy of its data on the CPU, so
+    /// that it can be added to the sprite batch.
+    pub(crate) fn with_shadow(ctx: &mut Context, vertices: &[Vertex]) -> Result<VertexBuffer> {
+        let mut buffer = VertexBuffer::new(ctx, vertices)?;
+        buffer.shadow = Some(Arc::new(Mutex::new(vertices.to_vec())));
+
+        Ok(buffer)
+    }
+
     /// Uploads new vertex data to the GPU.
     ///
     /// # Panics
@@ -163,6 +227,33 @@ impl VertexBuffer {
     pub fn set_data(&self, ctx: &mut Context, vertices: &[Vertex], offset: usize) {
         ctx.device
             .set_vertex_buffer_data(&self.handle, vertices, offset);
+
+        if let Some(shadow) = &self.shadow {
+            shadow.lock().unwrap_or_else(|e| e.into_inner())[offset..offset + vertices.len()]
+                .copy_from_slice(vertices);
+        }
+    }
+
+    /// Uploads new data for a sub-range of the buffer, starting at the given vertex offset.
+    ///
+    /// Only the given vertices will be sent to the GPU, so this can be significantly cheaper
+    /// than reuploading the whole buffer when only part of a mesh has changed.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range being written to is out of bounds.
+    pub fn set_sub_data(&self, ctx: &mut Context, offset: usize, vertices: &[Vertex]) {
+        self.set_data(ctx, vertices, offset);
+    }
+
+    /// Returns the number of vertices that the buffer can hold.
+    pub fn len(&self) -> usize {
+        self.handle.count()
+    }
+
+    /// Returns `true` if the buffer cannot hold any vertices.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
     }
 
     /// Creates a mesh using this buffer.
@@ -193,12 +284,19 @@ impl VertexBuffer {
 /// can be relatively slow. You should try to minimize how often you do this - for example, if a piece
 /// of geometry does not change from frame to frame, reuse the buffer instead of recreating it.
 ///
-/// You can clone an index buffer cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/rc/struct.Rc.html)
+/// You can clone an index buffer cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/sync/struct.Arc.html)
 /// handle to a GPU resource. However, this does mean that modifying a buffer (e.g.
 /// calling `set_data`) will also affect any clones that exist of it.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug)]
 pub struct IndexBuffer {
-    handle: Rc<RawIndexBuffer>,
+    handle: Arc<RawIndexBuffer>,
+    shadow: Option<Arc<Mutex<Vec<u32>>>>,
+}
+
+impl PartialEq for IndexBuffer {
+    fn eq(&self, other: &IndexBuffer) -> bool {
+        self.handle == other.handle
+    }
 }
 
 impl IndexBuffer {
@@ -233,10 +331,20 @@ impl IndexBuffer {
         ctx.device.set_index_buffer_data(&buffer, indices, 0);
 
         Ok(IndexBuffer {
-            handle: Rc::new(buffer),
+            handle: Arc::new(buffer),
+            shadow: None,
         })
     }
 
+    /// Creates a new index buffer that also keeps a copy of its data on the CPU, so
+    /// that it can be added to the sprite batch.
+    pub(crate) fn with_shadow(ctx: &mut Context, indices: &[u32]) -> Result<IndexBuffer> {
+        let mut buffer = IndexBuffer::new(ctx, indices)?;
+        buffer.shadow = Some(Arc::new(Mutex::new(indices.to_vec())));
+
+        Ok(buffer)
+    }
+
     /// Sends new index data to the GPU.
     ///
     /// # Panics
@@ -245,6 +353,11 @@ impl IndexBuffer {
     pub fn set_data(&self, ctx: &mut Context, indices: &[u32], offset: usize) {
         ctx.device
             .set_index_buffer_data(&self.handle, indices, offset);
+
+        if let Some(shadow) = &self.shadow {
+            shadow.lock().unwrap_or_else(|e| e.into_inner())[offset..offset + indices.len()]
+                .copy_from_slice(indices);
+        }
     }
 }
 
@@ -254,6 +367,60 @@ struct DrawRange {
     count: usize,
 }
 
+/// Vertex writes that have been queued on a mesh, but not yet uploaded.
+///
+/// Writes that overlap or touch are merged, so that each contiguous dirty range only
+/// results in a single upload.
+#[derive(Clone, Debug, Default)]
+struct DirtyVertices {
+    // Sorted by offset, and never overlapping or adjacent.
+    chunks: Vec<(usize, Vec<Vertex>)>,
+}
+
+impl DirtyVertices {
+    fn write(&mut self, offset: usize, vertices: &[Vertex]) {
+        if vertices.is_empty() {
+            return;
+        }
+
+        let end = offset + vertices.len();
+
+        let first = self
+            .chunks
+            .iter()
+            .position(|(o, v)| o + v.len() >= offset)
+            .unwrap_or(self.chunks.len());
+
+        let last = self.chunks[first..]
+            .iter()
+            .position(|(o, _)| *o > end)
+            .map(|i| first + i)
+            .unwrap_or(self.chunks.len());
+
+        let merged = self.chunks.drain(first..last).collect::<Vec<_>>();
+
+        let start = merged.first().map_or(offset, |(o, _)| offset.min(*o));
+        let end = merged.last().map_or(end, |(o, v)| end.max(o + v.len()));
+
+        let mut data = vec![Vertex::zeroed(); end - start];
+
+        for (o, v) in &merged {
+            data[o - start..o - start + v.len()].copy_from_slice(v);
+        }
+
+        data[offset - start..offset - start + vertices.len()].copy_from_slice(vertices);
+
+        self.chunks.insert(first, (start, data));
+    }
+
+    fn range(&self) -> Option<Range<usize>> {
+        let start = self.chunks.first()?.0;
+        let (o, v) = self.chunks.last()?;
+
+        Some(start..o + v.len())
+    }
+}
+
 /// Ways of drawing a shape.
 #[derive(Copy, Clone, Debug)]
 pub enum ShapeStyle {
@@ -285,7 +452,11 @@ pub enum ShapeStyle {
 /// each type to understand their performance characteristics.
 ///
 /// Note that, unlike most rendering in Tetra, mesh rendering is *not* batched by default - each time you
-/// draw the mesh will result in a seperate draw call.
+/// draw the mesh will result in a seperate draw call. The exception is small, untextured meshes
+/// that were created via [`GeometryBuilder`] (or the shape constructors), which are added to the
+/// same batch as sprites when they are drawn with the default shader. These are drawn using a
+/// [white pixel](Texture::white_pixel) from the current texture where possible, so shapes can be
+/// interleaved with sprites without the batch needing to be flushed.
 ///
 /// # Examples
 ///
@@ -295,7 +466,7 @@ pub enum ShapeStyle {
 /// The [`shapes`](https://github.com/17cupsofcoffee/tetra/blob/main/examples/shapes.rs) example demonstrates
 /// how to draw primitive shapes, both through the simplified API on `Mesh`, and the more powerful
 /// [`GeometryBuilder`] API.  
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct Mesh {
     vertex_buffer: VertexBuffer,
     index_buffer: Option<IndexBuffer>,
@@ -303,6 +474,23 @@ pub struct Mesh {
     draw_range: Option<DrawRange>,
     winding: VertexWinding,
     backface_culling: bool,
+    topology: PrimitiveTopology,
+    dirty: Mutex<DirtyVertices>,
+}
+
+impl Clone for Mesh {
+    fn clone(&self) -> Mesh {
+        Mesh {
+            vertex_buffer: self.vertex_buffer.clone(),
+            index_buffer: self.index_buffer.clone(),
+            texture: self.texture.clone(),
+            draw_range: self.draw_range,
+            winding: self.winding,
+            backface_culling: self.backface_culling,
+            topology: self.topology,
+            dirty: Mutex::new(self.dirty().clone()),
+        }
+    }
 }
 
 impl Mesh {
@@ -315,6 +503,8 @@ impl Mesh {
             draw_range: None,
             winding: VertexWinding::CounterClockwise,
             backface_culling: true,
+            topology: PrimitiveTopology::Triangles,
+            dirty: Mutex::new(DirtyVertices::default()),
         }
     }
 
@@ -327,6 +517,8 @@ impl Mesh {
             winding: VertexWinding::CounterClockwise,
             draw_range: None,
             backface_culling: true,
+            topology: PrimitiveTopology::Triangles,
+            dirty: Mutex::new(DirtyVertices::default()),
         }
     }
 
@@ -335,7 +527,72 @@ impl Mesh {
     where
         P: Into<DrawParams>,
     {
-        self.draw_instanced(ctx, 1, params);
+        let params = params.into();
+
+        if !self.try_push_to_batch(ctx, &params) {
+            self.draw_instanced(ctx, 1, params);
+        }
+    }
+
+    /// Adds the mesh to the sprite batch, if it can be drawn that way without changing
+    /// how it looks.
+    ///
+    /// Returns `false` if the mesh needs to be drawn separately.
+    fn try_push_to_batch(&self, ctx: &mut Context, params: &DrawParams) -> bool {
+        let (vertices, indices) = match (&self.vertex_buffer.shadow, &self.index_buffer) {
+            (
+                Some(vertices),
+                Some(IndexBuffer {
+                    shadow: Some(indices),
+                    ..
+                }),
+            ) => (vertices, indices),
+            _ => return false,
+        };
+
+        let batchable = self.texture.is_none()
+            && self.topology == PrimitiveTopology::Triangles
+            && ctx.graphics.shader.is_none()
+            && ctx.graphics.custom_projection_matrix.is_none()
+            && !ctx.graphics.sorting
+            && !ctx.graphics.pixel_snapping;
+
+        if !batchable {
+            return false;
+        }
+
+        self.upload_vertices(ctx);
+
+        let vertices = vertices.lock().unwrap_or_else(|e| e.into_inner());
+        let indices = indices.lock().unwrap_or_else(|e| e.into_inner());
+
+        let indices = match self.draw_range {
+            Some(range) => {
+                let start = range.start.min(indices.len());
+                let end = (range.start + range.count).min(indices.len());
+
+                &indices[start..end]
+            }
+            None => &indices[..],
+        };
+
+        let facing = match (self.backface_culling, self.winding) {
+            (true, VertexWinding::CounterClockwise) => Facing::Keep,
+            (true, VertexWinding::Clockwise) => Facing::Reverse,
+            (false, _) => Facing::Front,
+        };
+
+        // Meshes don't pick up the overlay or shader params from the `DrawParams` when
+        // they are drawn separately, so the batched version shouldn't either.
+        let params = DrawParams {
+            overlay_amount: 0.0,
+            shader_params: Vec4::zero(),
+            ..params.clone()
+        };
+
+        push_triangles(ctx, &vertices, indices, &params, facing);
+
+        true
     }
 
     /// Draws multiple instances of the mesh to the screen (or to a canvas,
@@ -356,6 +613,8 @@ impl Mesh {
     {
         graphics::flush(ctx);
 
+        self.upload_vertices(ctx);
+
         let texture = self
             .texture
             .as_ref()
@@ -368,13 +627,19 @@ impl Mesh {
             .unwrap_or(&ctx.graphics.default_shader);
 
         let params = params.into();
-        let model_matrix = params.to_matrix();
+        let mut model_view_matrix = ctx.graphics.transform_matrix * params.to_matrix();
+
+        if ctx.graphics.pixel_snapping {
+            model_view_matrix = graphics::snap_matrix(model_view_matrix);
+        }
+
+        let projection_matrix = graphics::get_render_projection_matrix(ctx);
 
         // TODO: Failing to apply the defaults should be handled more gracefully than this,
         // but we can't do that without breaking changes.
         let _ = shader.set_default_uniforms(
             &mut ctx.device,
-            ctx.graphics.projection_matrix * ctx.graphics.transform_matrix * model_matrix,
+            projection_matrix * model_view_matrix,
             params.color,
         );
 
@@ -393,6 +658,8 @@ impl Mesh {
             (_, None) => (0, self.vertex_buffer.handle.count()),
         };
 
+        ctx.device.set_filter_override(params.filter_mode);
+
         ctx.device.draw_instanced(
             &self.vertex_buffer.handle,
             self.index_buffer.as_ref().map(|i| &*i.handle),
@@ -401,7 +668,10 @@ impl Mesh {
             start,
             count,
             instances,
+            self.topology,
         );
+
+        ctx.device.set_filter_override(None);
     }
 
     /// Gets a reference to the vertex buffer contained within this mesh.
@@ -412,6 +682,59 @@ impl Mesh {
     /// Sets the vertex buffer that will be used when drawing the mesh.
     pub fn set_vertex_buffer(&mut self, vertex_buffer: VertexBuffer) {
         self.vertex_buffer = vertex_buffer;
+        self.dirty
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .chunks
+            .clear();
+    }
+
+    /// Queues new data for a range of the mesh's vertices, starting at the given offset.
+    ///
+    /// The data will not be sent to the GPU straight away - instead, the mesh tracks
+    /// which ranges of its vertex buffer are dirty, and uploads only those ranges the
+    /// next time it is drawn (or when [`upload_vertices`](Self::upload_vertices) is called).
+    /// This makes it cheap to deform a mesh by updating a handful of vertices each frame.
+    ///
+    /// Writes that overlap or are next to each other will be merged into a single upload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range being written to is outside of the mesh's vertex buffer.
+    pub fn set_vertices(&mut self, offset: usize, vertices: &[Vertex]) {
+        assert!(
+            offset + vertices.len() <= self.vertex_buffer.len(),
+            "tried to write out of bounds vertex data"
+        );
+
+        self.dirty
+            .get_mut()
+            .unwrap_or_else(|e| e.into_inner())
+            .write(offset, vertices);
+    }
+
+    /// Returns the range of vertices that have been changed via [`set_vertices`](Self::set_vertices),
+    /// but not yet uploaded to the GPU.
+    ///
+    /// If there are multiple separate dirty ranges, this will return the range that covers all of them.
+    pub fn dirty_range(&self) -> Option<Range<usize>> {
+        self.dirty().range()
+    }
+
+    /// Uploads any vertex data that has been changed via [`set_vertices`](Self::set_vertices).
+    ///
+    /// This is done automatically when the mesh is drawn, so you will usually only need to
+    /// call this if the vertex buffer is shared with another mesh.
+    pub fn upload_vertices(&self, ctx: &mut Context) {
+        let mut dirty = self.dirty();
+
+        for (offset, vertices) in dirty.chunks.drain(..) {
+            self.vertex_buffer.set_sub_data(ctx, offset, &vertices);
+        }
+    }
+
+    fn dirty(&self) -> MutexGuard<'_, DirtyVertices> {
+        self.dirty.lock().unwrap_or_else(|e| e.into_inner())
     }
 
     /// Gets a reference to the index buffer contained within this mesh.
@@ -487,6 +810,23 @@ impl Mesh {
         self.backface_culling = enabled;
     }
 
+    /// Returns the type of primitive that the mesh's data describes.
+    ///
+    /// The default topology is [`PrimitiveTopology::Triangles`].
+    pub fn topology(&self) -> PrimitiveTopology {
+        self.topology
+    }
+
+    /// Sets the type of primitive that the mesh's data describes.
+    ///
+    /// This applies to the vertex data if the mesh is not indexed, or to the index data
+    /// if it is. Backface culling has no effect on lines or points.
+    ///
+    /// The default topology is [`PrimitiveTopology::Triangles`].
+    pub fn set_topology(&mut self, topology: PrimitiveTopology) {
+        self.topology = topology;
+    }
+
     /// Sets the range of vertices (or indices, if the mesh is indexed) that should be included
     /// when drawing this mesh.
     ///
@@ -604,6 +944,28 @@ impl Mesh {
             .build_mesh(ctx)
     }
 
+    /// Creates a new polygon mesh, with holes cut out of it.
+    ///
+    /// If you need to draw multiple shapes, consider using [`GeometryBuilder`] to generate a combined mesh
+    /// instead.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::TessellationError`](crate::TetraError::TessellationError) will be returned if the shape
+    ///   could not be turned into vertex data.
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if the underlying
+    ///   graphics API encounters an error.
+    pub fn polygon_with_holes(
+        ctx: &mut Context,
+        style: ShapeStyle,
+        points: &[Vec2<f32>],
+        holes: &[&[Vec2<f32>]],
+    ) -> Result<Mesh> {
+        GeometryBuilder::new()
+            .polygon_with_holes(style, points, holes)?
+            .build_mesh(ctx)
+    }
+
     /// Creates a new polyline mesh.
     ///
     /// If you need to draw multiple shapes, consider using [`GeometryBuilder`] to generate a combined mesh
@@ -653,6 +1015,82 @@ impl StrokeVertexConstructor<Vertex> for TetraVertexConstructor {
     }
 }
 
+/// The largest number of indices that a mesh can have while still being drawn as part
+/// of the sprite batch.
+const MAX_BATCHED_INDICES: usize = 1536;
+
+/// How the winding of triangles should be adjusted when they are added to the sprite
+/// batch, which culls clockwise triangles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Facing {
+    /// Triangles are added as-is.
+    Keep,
+
+    /// The winding of every triangle is reversed.
+    Reverse,
+
+    /// Triangles are turned to face the front, so that none of them are culled.
+    Front,
+}
+
+/// Adds indexed triangles to the sprite batch, transforming them on the CPU and
+/// drawing them using a white pixel from the current texture where possible.
+fn push_triangles(
+    ctx: &mut Context,
+    vertices: &[Vertex],
+    indices: &[u32],
+    params: &DrawParams,
+    facing: Facing,
+) {
+    let matrix = params.to_matrix();
+    let world_matrix = ctx.graphics.transform_matrix * matrix;
+    let uv = graphics::set_white_texture(ctx);
+
+    let transform = |vertex: &Vertex| {
+        let position = matrix * Vec4::new(vertex.position.x, vertex.position.y, 0.0, 1.0);
+
+        Vertex {
+            position: position.xy(),
+            uv,
+            color: vertex.color * params.color,
+            ..*vertex
+        }
+    };
+
+    // The batch is made up of quads, so each triangle is drawn as a quad with its
+    // last two corners in the same place.
+    for triangle in indices.chunks_exact(3) {
+        let (a, b, c) = match (
+            vertices.get(triangle[0] as usize),
+            vertices.get(triangle[1] as usize),
+            vertices.get(triangle[2] as usize),
+        ) {
+            (Some(a), Some(b), Some(c)) => (a, b, c),
+            _ => continue,
+        };
+
+        let reverse = match facing {
+            Facing::Keep => false,
+            Facing::Reverse => true,
+            Facing::Front => {
+                // Triangles that are front-facing in the batch have a negative cross
+                // product once they're in world space, as the Y axis points down.
+                let world = |v: &Vertex| {
+                    (world_matrix * Vec4::new(v.position.x, v.position.y, 0.0, 1.0)).xy()
+                };
+
+                let (ab, ac) = (world(b) - world(a), world(c) - world(a));
+                ab.x * ac.y - ab.y * ac.x > 0.0
+            }
+        };
+
+        let (a, b, c) = if reverse { (a, c, b) } else { (a, b, c) };
+        let c = transform(c);
+
+        graphics::push_vertices(ctx, [transform(a), transform(b), c, c], params);
+    }
+}
+
 /// A builder for creating primitive shape geometry, and associated buffers/meshes.
 ///
 /// # Performance
@@ -898,6 +1336,72 @@ impl GeometryBuilder {
         Ok(self)
     }
 
+    /// Adds a polygon, with holes cut out of it.
+    ///
+    /// The polygon and its holes can be concave, and the holes can be in either winding order.
+    /// If holes overlap each other, the overlapping area will be filled back in.
+    ///
+    /// When stroked, the outlines of both the polygon and the holes will be drawn.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::TessellationError`](crate::TetraError::TessellationError) will be returned if the shape
+    ///   could not be turned into vertex data.
+    pub fn polygon_with_holes(
+        &mut self,
+        style: ShapeStyle,
+        points: &[Vec2<f32>],
+        holes: &[&[Vec2<f32>]],
+    ) -> Result<&mut GeometryBuilder> {
+        let mut builder = BuffersBuilder::new(&mut self.data, TetraVertexConstructor(self.color));
+
+        let contours: Vec<Vec<Point>> = std::iter::once(points)
+            .chain(holes.iter().copied())
+            .map(|contour| {
+                contour
+                    .iter()
+                    .map(|point| Point::new(point.x, point.y))
+                    .collect()
+            })
+            .collect();
+
+        let polygons = contours.iter().map(|points| Polygon {
+            points,
+            closed: true,
+        });
+
+        match style {
+            ShapeStyle::Fill => {
+                // The even-odd fill rule is what makes the holes empty.
+                let options = FillOptions::default().with_fill_rule(FillRule::EvenOdd);
+                let mut tessellator = FillTessellator::new();
+
+                let mut builder = tessellator.builder(&options, &mut builder);
+
+                for polygon in polygons {
+                    builder.add_polygon(polygon);
+                }
+
+                builder.build().map_err(TetraError::TessellationError)?;
+            }
+
+            ShapeStyle::Stroke(width) => {
+                let options = StrokeOptions::default().with_line_width(width);
+                let mut tessellator = StrokeTessellator::new();
+
+                let mut builder = tessellator.builder(&options, &mut builder);
+
+                for polygon in polygons {
+                    builder.add_polygon(polygon);
+                }
+
+                builder.build().map_err(TetraError::TessellationError)?;
+            }
+        }
+
+        Ok(self)
+    }
+
     /// Adds a polyline.
     ///
     /// # Errors
@@ -973,10 +1477,19 @@ impl GeometryBuilder {
     /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if the underlying
     /// graphics API encounters an error.
     pub fn build_buffers(&self, ctx: &mut Context) -> Result<(VertexBuffer, IndexBuffer)> {
-        Ok((
-            VertexBuffer::new(ctx, &self.data.vertices)?,
-            IndexBuffer::new(ctx, &self.data.indices)?,
-        ))
+        // Small meshes keep a copy of their data on the CPU, so that they can be drawn
+        // as part of the sprite batch.
+        if self.data.indices.len() <= MAX_BATCHED_INDICES {
+            Ok((
+                VertexBuffer::with_shadow(ctx, &self.data.vertices)?,
+                IndexBuffer::with_shadow(ctx, &self.data.indices)?,
+            ))
+        } else {
+            Ok((
+                VertexBuffer::new(ctx, &self.data.vertices)?,
+                IndexBuffer::new(ctx, &self.data.indices)?,
+            ))
+        }
     }
 
     /// Builds a mesh from the generated geometry.
@@ -992,6 +1505,31 @@ impl GeometryBuilder {
 
         Ok(Mesh::indexed(vertex_buffer, index_buffer))
     }
+
+    /// Draws the generated geometry to the screen (or to a canvas, if one is enabled),
+    /// without creating a mesh.
+    ///
+    /// Unlike drawing a [`Mesh`], this adds the geometry to the same batch as sprites,
+    /// transforming it on the CPU. This is cheaper than creating a mesh for geometry that
+    /// changes every frame, or that is only made up of a few shapes. The geometry is drawn
+    /// using a [white pixel](Texture::white_pixel) from the current texture where possible,
+    /// so shapes can be drawn in between sprites from the same texture atlas without the
+    /// batch needing to be flushed.
+    ///
+    /// As with meshes, the normalized origin in the `DrawParams` is ignored, as the
+    /// size of the geometry is not known.
+    pub fn draw<P>(&self, ctx: &mut Context, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        push_triangles(
+            ctx,
+            &self.data.vertices,
+            &self.data.indices,
+            &params.into(),
+            Facing::Keep,
+        );
+    }
 }
 
 impl Default for GeometryBuilder {
@@ -999,3 +1537,152 @@ impl Default for GeometryBuilder {
         GeometryBuilder::new()
     }
 }
+
+/// Offsets a polyline or polygon by the given distance, returning the new points.
+///
+/// Positive distances move the line to the left of its direction of travel (as it
+/// appears on screen), which is outwards for a polygon whose points are in clockwise
+/// order. Negative distances move it in the opposite direction. Corners are mitered,
+/// but very sharp corners are limited to four times the distance, to avoid long spikes.
+///
+/// If `closed` is `true`, the last point will be treated as connecting back to the first.
+///
+/// This can be used in combination with [`GeometryBuilder`] - for example, to
+/// generate the border of a piece of destructible terrain, or to shrink a polygon
+/// to create an inner outline.
+pub fn offset_polyline(points: &[Vec2<f32>], distance: f32, closed: bool) -> Vec<Vec2<f32>> {
+    const MITER_LIMIT: f32 = 4.0;
+
+    let mut points = points.to_vec();
+    points.dedup();
+
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+
+    if points.len() < 2 {
+        return points;
+    }
+
+    let normal = |from: Vec2<f32>, to: Vec2<f32>| {
+        let direction = (to - from).normalized();
+        Vec2::new(direction.y, -direction.x)
+    };
+
+    let len = points.len();
+
+    (0..len)
+        .map(|i| {
+            let point = points[i];
+
+            let previous = if i > 0 {
+                Some(points[i - 1])
+            } else if closed {
+                Some(points[len - 1])
+            } else {
+                None
+            };
+
+            let next = if i < len - 1 {
+                Some(points[i + 1])
+            } else if closed {
+                Some(points[0])
+            } else {
+                None
+            };
+
+            let offset = match (previous, next) {
+                (Some(previous), Some(next)) => {
+                    let n1 = normal(previous, point);
+                    let n2 = normal(point, next);
+                    let miter = n1 + n2;
+
+                    if miter.magnitude_squared() < f32::EPSILON {
+                        // The line doubles back on itself, so there's no sensible miter.
+                        n1 * distance
+                    } else {
+                        let miter = miter.normalized();
+                        let scale = (1.0 / miter.dot(n2)).min(MITER_LIMIT);
+
+                        miter * distance * scale
+                    }
+                }
+                (Some(previous), None) => normal(previous, point) * distance,
+                (None, Some(next)) => normal(point, next) * distance,
+                (None, None) => Vec2::zero(),
+            };
+
+            point + offset
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertices(x: f32, count: usize) -> Vec<Vertex> {
+        vec![Vertex::new(Vec2::new(x, 0.0), Vec2::zero(), Color::WHITE); count]
+    }
+
+    #[test]
+    fn vertex_layout_is_packed() {
+        // The attribute offsets in the graphics device depend on this layout.
+        assert_eq!(std::mem::size_of::<Vertex>(), 52);
+        assert_eq!(std::mem::align_of::<Vertex>(), 4);
+    }
+
+    #[test]
+    fn dirty_vertices_merge() {
+        let mut dirty = DirtyVertices::default();
+
+        dirty.write(10, &vertices(1.0, 2));
+        dirty.write(0, &vertices(2.0, 2));
+        assert_eq!(dirty.chunks.len(), 2);
+        assert_eq!(dirty.range(), Some(0..12));
+
+        // Touches the first chunk and overlaps the second, so everything merges.
+        dirty.write(2, &vertices(3.0, 9));
+        assert_eq!(dirty.chunks.len(), 1);
+
+        let (offset, data) = &dirty.chunks[0];
+        assert_eq!(*offset, 0);
+        assert_eq!(data.len(), 12);
+        assert_eq!(data[1].position.x, 2.0);
+        assert_eq!(data[10].position.x, 3.0);
+        assert_eq!(data[11].position.x, 1.0);
+    }
+
+    fn assert_points_eq(actual: &[Vec2<f32>], expected: &[Vec2<f32>]) {
+        assert_eq!(actual.len(), expected.len());
+
+        for (a, e) in actual.iter().zip(expected) {
+            assert!(a.distance(*e) < 0.0001, "expected {}, found {}", e, a);
+        }
+    }
+
+    #[test]
+    fn offset_square() {
+        let square = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(4.0, 0.0),
+            Vec2::new(4.0, 4.0),
+            Vec2::new(0.0, 4.0),
+        ];
+
+        assert_points_eq(
+            &offset_polyline(&square, 1.0, true),
+            &[
+                Vec2::new(-1.0, -1.0),
+                Vec2::new(5.0, -1.0),
+                Vec2::new(5.0, 5.0),
+                Vec2::new(-1.0, 5.0),
+            ],
+        );
+
+        assert_points_eq(
+            &offset_polyline(&square[..2], -2.0, false),
+            &[Vec2::new(0.0, 2.0), Vec2::new(4.0, 2.0)],
+        );
+    }
+}
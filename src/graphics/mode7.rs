@@ -0,0 +1,192 @@
+use crate::error::Result;
+use crate::graphics::shader::{self, Shader};
+use crate::graphics::{self, DrawParams, Texture};
+use crate::math::Vec2;
+use crate::Context;
+
+const MODE7_FRAGMENT_SHADER: &str = include_str!("../resources/mode7.frag");
+
+/// Renders a texture as a ground plane stretching off into the distance, in the style
+/// of the SNES's 'Mode 7'.
+///
+/// This is commonly used for the track in racing games, or for the world map in JRPGs.
+/// The plane is viewed by a camera that sits above it, looking towards the horizon - the
+/// camera's [`position`](Self::position) and [`angle`](Self::angle) are measured in
+/// texels of the plane's texture, with an angle of `0.0` facing along the positive X axis.
+///
+/// The plane is drawn into a rectangle of the given viewport size, with everything above
+/// the [`horizon`](Self::horizon) left transparent (so a sky can be drawn behind it).
+/// Objects on the plane (such as other racers, or trees) can be positioned using
+/// [`project`](Self::project).
+///
+/// Each `Mode7` owns a shader, so it should be created once and then reused. Drawing
+/// the plane flushes any pending draws, both before and after.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{Mode7, Texture};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, track: &Texture) -> tetra::Result {
+/// let mut mode7 = Mode7::new(ctx, 320.0, 240.0)?;
+/// mode7.position = Vec2::new(512.0, 512.0);
+/// mode7.angle = 0.5;
+///
+/// mode7.draw(ctx, track, Vec2::zero());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mode7 {
+    shader: Shader,
+
+    /// The position of the camera on the plane, in texels.
+    pub position: Vec2<f32>,
+
+    /// The direction that the camera is facing, in radians.
+    pub angle: f32,
+
+    /// How high the camera is above the plane, in texels.
+    pub height: f32,
+
+    /// The Y co-ordinate of the horizon, in pixels from the top of the viewport.
+    pub horizon: f32,
+
+    /// The horizontal field of view of the camera, in radians.
+    pub fov: f32,
+
+    /// Whether the texture should repeat infinitely. If this is `false`, the area outside
+    /// of the texture will be transparent.
+    pub repeat: bool,
+
+    /// The width of the viewport that the plane is drawn into.
+    pub viewport_width: f32,
+
+    /// The height of the viewport that the plane is drawn into.
+    pub viewport_height: f32,
+}
+
+impl Mode7 {
+    /// Creates a new Mode 7 renderer, with the given viewport size.
+    ///
+    /// The camera starts at `(0.0, 0.0)`, with a height of `32.0`, the horizon a third of
+    /// the way down the viewport, and a field of view of 90 degrees.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if
+    ///   the underlying graphics API encounters an error.
+    pub fn new(ctx: &mut Context, viewport_width: f32, viewport_height: f32) -> Result<Mode7> {
+        let shader =
+            Shader::from_string(ctx, shader::DEFAULT_VERTEX_SHADER, MODE7_FRAGMENT_SHADER)?;
+
+        Ok(Mode7 {
+            shader,
+            position: Vec2::zero(),
+            angle: 0.0,
+            height: 32.0,
+            horizon: viewport_height / 3.0,
+            fov: std::f32::consts::FRAC_PI_2,
+            repeat: false,
+            viewport_width,
+            viewport_height,
+        })
+    }
+
+    /// Projects a point on the plane into the viewport.
+    ///
+    /// Returns the position in the viewport, along with how much an object standing at
+    /// that point should be scaled by (so that objects get smaller as they move towards
+    /// the horizon). Returns `None` if the point is behind the camera.
+    pub fn project(&self, point: Vec2<f32>) -> Option<(Vec2<f32>, f32)> {
+        let (forward, right) = self.axes();
+        let focal_length = self.focal_length();
+
+        let offset = point - self.position;
+        let depth = offset.dot(forward);
+
+        if depth <= f32::EPSILON {
+            return None;
+        }
+
+        let scale = focal_length / depth;
+
+        let position = Vec2::new(
+            self.viewport_width / 2.0 + offset.dot(right) * scale,
+            self.horizon + self.height * scale,
+        );
+
+        Some((position, scale))
+    }
+
+    /// Draws the plane, using the given texture.
+    ///
+    /// The params are applied to the viewport rectangle as a whole, so they can be used
+    /// to position it on the screen.
+    pub fn draw<P>(&self, ctx: &mut Context, texture: &Texture, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        if graphics::is_feedback_loop(ctx, texture) {
+            return;
+        }
+
+        let params = params
+            .into()
+            .resolve_origin(Vec2::new(self.viewport_width, self.viewport_height));
+
+        let (forward, _) = self.axes();
+        let (texture_width, texture_height) = texture.size();
+
+        let previous_shader = ctx.graphics.shader.clone();
+
+        graphics::flush(ctx);
+        graphics::set_shader(ctx, &self.shader);
+
+        self.shader.set_uniform(
+            ctx,
+            "u_viewport",
+            Vec2::new(self.viewport_width, self.viewport_height),
+        );
+        self.shader.set_uniform(
+            ctx,
+            "u_texture_size",
+            Vec2::new(texture_width as f32, texture_height as f32),
+        );
+        self.shader.set_uniform(ctx, "u_position", self.position);
+        self.shader.set_uniform(ctx, "u_forward", forward);
+        self.shader.set_uniform(ctx, "u_height", self.height);
+        self.shader.set_uniform(ctx, "u_horizon", self.horizon);
+        self.shader
+            .set_uniform(ctx, "u_focal_length", self.focal_length());
+        self.shader
+            .set_uniform(ctx, "u_repeat", if self.repeat { 1.0 } else { 0.0 });
+
+        graphics::set_texture(ctx, texture);
+        graphics::push_quad(
+            ctx,
+            0.0,
+            0.0,
+            self.viewport_width,
+            self.viewport_height,
+            0.0,
+            0.0,
+            1.0,
+            1.0,
+            &params,
+        );
+
+        graphics::flush(ctx);
+        graphics::set_shader_ex(ctx, previous_shader.as_ref());
+    }
+
+    fn axes(&self) -> (Vec2<f32>, Vec2<f32>) {
+        let (sin, cos) = self.angle.sin_cos();
+        (Vec2::new(cos, sin), Vec2::new(-sin, cos))
+    }
+
+    fn focal_length(&self) -> f32 {
+        (self.viewport_width / 2.0) / (self.fov / 2.0).tan()
+    }
+}
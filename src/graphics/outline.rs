@@ -0,0 +1,222 @@
+use crate::error::Result;
+use crate::graphics::shader::{self, Shader};
+use crate::graphics::{self, Color, DrawParams, Rectangle, Texture};
+use crate::math::{Vec2, Vec4};
+use crate::Context;
+
+const OUTLINE_FRAGMENT_SHADER: &str = include_str!("../resources/outline.frag");
+
+/// The maximum thickness of an outline, in texels.
+const MAX_THICKNESS: f32 = 16.0;
+
+/// How an [`Outline`] is drawn.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum OutlineMode {
+    /// The graphic is drawn as normal, with the outline around its edges.
+    Outline,
+
+    /// The graphic is drawn as a solid shape in the outline color, along with the
+    /// outline itself. This is useful for hit flashes, or for showing a character
+    /// that is hidden behind a wall.
+    Silhouette,
+}
+
+/// An effect that draws textures with a solid colored outline, or as a solid silhouette.
+///
+/// This is commonly used to highlight the object that is selected or under the mouse.
+/// The outline is found by checking the alpha of the surrounding texels, so it follows
+/// the shape of the graphic rather than its bounding box.
+///
+/// Each outline owns a shader, so it should be created once and then reused. Drawing
+/// with an outline flushes any pending draws, both before and after, so that each draw
+/// can use different settings.
+///
+/// The thickness is measured in texels, so the outline will be scaled along with the
+/// graphic. When drawing a region of a texture (e.g. from a sprite sheet), make sure
+/// there is enough transparent padding around each sprite, as texels outside of the
+/// region are not taken into account.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{Color, DrawParams, Outline, Texture};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, texture: &Texture) -> tetra::Result {
+/// let highlight = Outline::new(ctx, Color::rgb(1.0, 0.8, 0.0), 2.0)?;
+///
+/// highlight.draw(ctx, texture, Vec2::new(64.0, 64.0));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Outline {
+    shader: Shader,
+    color: Color,
+    thickness: f32,
+    mode: OutlineMode,
+}
+
+impl Outline {
+    /// Creates a new outline effect, with the given color and thickness (in texels).
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if
+    ///   the underlying graphics API encounters an error.
+    pub fn new(ctx: &mut Context, color: Color, thickness: f32) -> Result<Outline> {
+        let shader =
+            Shader::from_string(ctx, shader::DEFAULT_VERTEX_SHADER, OUTLINE_FRAGMENT_SHADER)?;
+
+        Ok(Outline {
+            shader,
+            color,
+            thickness: thickness.clamp(0.0, MAX_THICKNESS),
+            mode: OutlineMode::Outline,
+        })
+    }
+
+    /// Creates a new effect that draws textures as a solid silhouette of the given color,
+    /// with no outline.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if
+    ///   the underlying graphics API encounters an error.
+    pub fn silhouette(ctx: &mut Context, color: Color) -> Result<Outline> {
+        let mut outline = Outline::new(ctx, color, 0.0)?;
+        outline.mode = OutlineMode::Silhouette;
+        Ok(outline)
+    }
+
+    /// Returns the color of the outline.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Sets the color of the outline.
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Returns the thickness of the outline, in texels.
+    pub fn thickness(&self) -> f32 {
+        self.thickness
+    }
+
+    /// Sets the thickness of the outline, in texels.
+    ///
+    /// The thickness will be clamped between `0.0` and `16.0`.
+    pub fn set_thickness(&mut self, thickness: f32) {
+        self.thickness = thickness.clamp(0.0, MAX_THICKNESS);
+    }
+
+    /// Returns how the outline is drawn.
+    pub fn mode(&self) -> OutlineMode {
+        self.mode
+    }
+
+    /// Sets how the outline is drawn.
+    pub fn set_mode(&mut self, mode: OutlineMode) {
+        self.mode = mode;
+    }
+
+    /// Draws a texture with the outline applied.
+    pub fn draw<P>(&self, ctx: &mut Context, texture: &Texture, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        let (width, height) = texture.size();
+
+        self.draw_region(
+            ctx,
+            texture,
+            Rectangle::new(0.0, 0.0, width as f32, height as f32),
+            params,
+        );
+    }
+
+    /// Draws a region of a texture with the outline applied.
+    ///
+    /// The area that is drawn will be expanded by the thickness of the outline, so that
+    /// it is not cut off at the edges of the region.
+    pub fn draw_region<P>(&self, ctx: &mut Context, texture: &Texture, region: Rectangle, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        if graphics::is_feedback_loop(ctx, texture) {
+            return;
+        }
+
+        let params = params
+            .into()
+            .resolve_origin(Vec2::new(region.width, region.height));
+
+        let texture_width = texture.width() as f32;
+        let texture_height = texture.height() as f32;
+        let padding = self.thickness.ceil();
+
+        let previous_shader = ctx.graphics.shader.clone();
+
+        graphics::flush(ctx);
+        graphics::set_shader(ctx, &self.shader);
+
+        self.shader.set_uniform(
+            ctx,
+            "u_texel_size",
+            Vec2::new(1.0 / texture_width, 1.0 / texture_height),
+        );
+        self.shader.set_uniform(
+            ctx,
+            "u_region",
+            Vec4::new(
+                region.x / texture_width,
+                region.y / texture_height,
+                region.right() / texture_width,
+                region.bottom() / texture_height,
+            ),
+        );
+        self.shader.set_uniform(ctx, "u_thickness", self.thickness);
+        self.shader.set_uniform(ctx, "u_outline_color", self.color);
+        self.shader.set_uniform(
+            ctx,
+            "u_silhouette",
+            if self.mode == OutlineMode::Silhouette {
+                1.0
+            } else {
+                0.0
+            },
+        );
+        self.shader.set_uniform(
+            ctx,
+            "u_premultiplied",
+            if texture.is_premultiplied() { 1.0 } else { 0.0 },
+        );
+
+        graphics::set_texture(ctx, texture);
+        graphics::push_quad(
+            ctx,
+            -padding,
+            -padding,
+            region.width + padding,
+            region.height + padding,
+            (region.x - padding) / texture_width,
+            (region.y - padding) / texture_height,
+            (region.right() + padding) / texture_width,
+            (region.bottom() + padding) / texture_height,
+            &params,
+        );
+
+        graphics::flush(ctx);
+        graphics::set_shader_ex(ctx, previous_shader.as_ref());
+    }
+}
@@ -0,0 +1,143 @@
+use crate::graphics::{Camera, Color, DrawParams, Rectangle, Texture};
+use crate::math::Vec2;
+use crate::Context;
+
+/// A background layer that scrolls at a different speed to the camera, to give an
+/// illusion of depth.
+///
+/// The layer should be drawn while the camera's transform matrix is applied (via
+/// [`graphics::set_transform_matrix`](super::set_transform_matrix)). Only the parts of
+/// the layer that are visible to the camera will be drawn, so repeating layers can
+/// scroll forever.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, Camera, ParallaxLayer, Texture};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, camera: &Camera, mountains: Texture) {
+/// let mut layer = ParallaxLayer::new(mountains);
+/// layer.scroll_factor = Vec2::new(0.25, 0.0);
+/// layer.offset = Vec2::new(0.0, 120.0);
+///
+/// graphics::set_transform_matrix(ctx, camera.as_matrix());
+/// layer.draw(ctx, camera);
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParallaxLayer {
+    /// The texture to draw.
+    pub texture: Texture,
+
+    /// How fast the layer scrolls relative to the camera, on each axis.
+    ///
+    /// `1.0` scrolls along with the rest of the world, `0.0` stays fixed on the screen,
+    /// and values in between appear to be further away. Values greater than `1.0` can be
+    /// used for foreground layers that appear closer than the world.
+    ///
+    /// Defaults to `(1.0, 1.0)`.
+    pub scroll_factor: Vec2<f32>,
+
+    /// The position of the top left of the layer, when the camera is at `(0.0, 0.0)`.
+    ///
+    /// Defaults to `(0.0, 0.0)`.
+    pub offset: Vec2<f32>,
+
+    /// Whether the texture should be repeated horizontally. Defaults to `true`.
+    pub repeat_x: bool,
+
+    /// Whether the texture should be repeated vertically. Defaults to `false`.
+    pub repeat_y: bool,
+
+    /// A color to multiply the layer by. Defaults to [`Color::WHITE`].
+    pub color: Color,
+
+    /// Whether the layer's position should be rounded to the nearest pixel on the screen.
+    ///
+    /// As each layer scrolls at a different speed, the layers usually end up at sub-pixel
+    /// positions, which can cause pixel art to shimmer as the camera moves. Enabling this
+    /// keeps the texels aligned with the screen, at the cost of less smooth movement for
+    /// slow-moving layers. Defaults to `false`.
+    pub pixel_snapping: bool,
+}
+
+impl ParallaxLayer {
+    /// Creates a new layer, which scrolls along with the camera and repeats horizontally.
+    pub fn new(texture: Texture) -> ParallaxLayer {
+        ParallaxLayer {
+            texture,
+            scroll_factor: Vec2::new(1.0, 1.0),
+            offset: Vec2::new(0.0, 0.0),
+            repeat_x: true,
+            repeat_y: false,
+            color: Color::WHITE,
+            pixel_snapping: false,
+        }
+    }
+
+    /// Returns the position of the top left of the layer in world space, as seen by
+    /// the given camera.
+    pub fn position(&self, camera: &Camera) -> Vec2<f32> {
+        let position = self.offset + camera.position * (Vec2::one() - self.scroll_factor);
+
+        if self.pixel_snapping {
+            (position * camera.scale).round() / camera.scale
+        } else {
+            position
+        }
+    }
+
+    /// Draws the parts of the layer that are visible to the given camera.
+    pub fn draw(&self, ctx: &mut Context, camera: &Camera) {
+        let (width, height) = self.texture.size();
+        let size = Vec2::new(width as f32, height as f32);
+
+        if size.x <= 0.0 || size.y <= 0.0 {
+            return;
+        }
+
+        let position = self.position(camera);
+        let visible = camera.visible_rect();
+
+        let (x, dest_width) = if self.repeat_x {
+            tile_span(position.x, size.x, visible.x, visible.right())
+        } else {
+            (position.x, size.x)
+        };
+
+        let (y, dest_height) = if self.repeat_y {
+            tile_span(position.y, size.y, visible.y, visible.bottom())
+        } else {
+            (position.y, size.y)
+        };
+
+        self.texture.draw_tiled(
+            ctx,
+            Rectangle::new(0.0, 0.0, size.x, size.y),
+            Rectangle::new(x, y, dest_width, dest_height),
+            DrawParams::new().color(self.color),
+        );
+    }
+}
+
+/// Returns the start and length of a run of whole tiles, aligned to `position`, that
+/// covers the range from `visible_start` to `visible_end`.
+fn tile_span(position: f32, size: f32, visible_start: f32, visible_end: f32) -> (f32, f32) {
+    let start = position + ((visible_start - position) / size).floor() * size;
+    let count = ((visible_end - start) / size).ceil().max(1.0);
+
+    (start, count * size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tile_spans() {
+        assert_eq!(tile_span(0.0, 100.0, 150.0, 350.0), (100.0, 300.0));
+        assert_eq!(tile_span(30.0, 100.0, -120.0, 80.0), (-170.0, 300.0));
+        assert_eq!(tile_span(0.0, 100.0, 0.0, 100.0), (0.0, 100.0));
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::graphics::{Canvas, CanvasBuilder};
+use crate::Context;
+
+/// A pool of canvases, which can be used as temporary render targets.
+///
+/// Effects such as blur chains often need scratch canvases that only live for part of
+/// a frame. Creating a canvas is expensive, so rather than creating new ones every
+/// frame, a `RenderTargetPool` hands out canvases that were created previously, and
+/// only allocates new GPU memory when none of the right size are free.
+///
+/// A canvas returns to the pool once every clone of it has been dropped (including
+/// the one held by the renderer, if it is the active canvas). In practice, this means
+/// that canvases requested during a frame are reclaimed by the end of that frame, as
+/// long as you don't store them anywhere. Canvases that have not been used for a
+/// while are released, so the pool will shrink back down after a spike in usage.
+///
+/// Note that the contents of a canvas are not cleared when it is handed out, so it
+/// will usually contain whatever was last drawn to it. Any changes made to its settings
+/// (such as the filter mode) will also be kept.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, Canvas, Color, DrawParams, RenderTargetPool};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, pool: &mut RenderTargetPool, scene: &Canvas) -> tetra::Result {
+/// let (width, height) = scene.size();
+/// let half = pool.get(ctx, width / 2, height / 2)?;
+///
+/// graphics::set_canvas(ctx, &half);
+/// graphics::clear(ctx, Color::BLACK);
+/// scene.draw(ctx, DrawParams::new().scale(Vec2::new(0.5, 0.5)));
+/// graphics::reset_canvas(ctx);
+///
+/// half.draw(ctx, DrawParams::new().scale(Vec2::new(2.0, 2.0)));
+///
+/// // `half` is dropped here, so it can be reused next time.
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct RenderTargetPool {
+    entries: Vec<PoolEntry>,
+    max_idle_frames: u64,
+}
+
+#[derive(Debug, Clone)]
+struct PoolEntry {
+    settings: CanvasBuilder,
+    canvas: Canvas,
+    last_used: u64,
+}
+
+impl PoolEntry {
+    fn is_free(&self) -> bool {
+        // The texture is checked as well, as it may have been cloned separately.
+        Arc::strong_count(&self.canvas.handle) == 1
+            && Arc::strong_count(&self.canvas.texture.data) == 1
+    }
+}
+
+impl RenderTargetPool {
+    /// Creates a new, empty pool.
+    pub fn new() -> RenderTargetPool {
+        RenderTargetPool {
+            entries: Vec::new(),
+            max_idle_frames: 60,
+        }
+    }
+
+    /// Returns a canvas of the given size, with the default settings.
+    ///
+    /// # Errors
+    ///
+    /// See [`CanvasBuilder::build`].
+    pub fn get(&mut self, ctx: &mut Context, width: i32, height: i32) -> Result<Canvas> {
+        self.get_with(ctx, &CanvasBuilder::new(width, height))
+    }
+
+    /// Returns a canvas with the settings from the given builder.
+    ///
+    /// # Errors
+    ///
+    /// See [`CanvasBuilder::build`].
+    pub fn get_with(&mut self, ctx: &mut Context, settings: &CanvasBuilder) -> Result<Canvas> {
+        let frame = ctx.graphics.frame;
+
+        self.release_idle(frame);
+
+        let existing = self
+            .entries
+            .iter_mut()
+            .find(|e| e.settings == *settings && e.is_free());
+
+        if let Some(entry) = existing {
+            entry.last_used = frame;
+            return Ok(entry.canvas.clone());
+        }
+
+        let canvas = settings.build(ctx)?;
+
+        self.entries.push(PoolEntry {
+            settings: settings.clone(),
+            canvas: canvas.clone(),
+            last_used: frame,
+        });
+
+        Ok(canvas)
+    }
+
+    /// Returns the number of frames that a canvas can go unused before it is released.
+    pub fn max_idle_frames(&self) -> u64 {
+        self.max_idle_frames
+    }
+
+    /// Sets the number of frames that a canvas can go unused before it is released.
+    ///
+    /// Idle canvases are checked for whenever a canvas is requested from the pool.
+    ///
+    /// Defaults to `60`.
+    pub fn set_max_idle_frames(&mut self, frames: u64) {
+        self.max_idle_frames = frames;
+    }
+
+    /// Returns the number of canvases that the pool has allocated, including ones
+    /// that are currently in use.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the pool has not allocated any canvases.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Releases all of the canvases that are not currently in use.
+    pub fn clear(&mut self) {
+        self.entries.retain(|e| !e.is_free());
+    }
+
+    fn release_idle(&mut self, frame: u64) {
+        let max_idle_frames = self.max_idle_frames;
+
+        self.entries
+            .retain(|e| !e.is_free() || frame.saturating_sub(e.last_used) <= max_idle_frames);
+    }
+}
+
+impl Default for RenderTargetPool {
+    fn default() -> RenderTargetPool {
+        RenderTargetPool::new()
+    }
+}
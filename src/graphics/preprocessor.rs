@@ -0,0 +1,359 @@
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+use hashbrown::HashSet;
+
+use crate::error::{Result, TetraError};
+use crate::fs;
+use crate::graphics::{self, Shader};
+use crate::Context;
+
+/// The GLSL version header that a [`ShaderPreprocessor`] will emit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderVersion {
+    /// GLSL 1.50, which is used by OpenGL 3.2 Core.
+    ///
+    /// This is the version that Tetra's built-in shaders are written in.
+    Glsl150,
+
+    /// GLSL ES 3.00, which is used by OpenGL ES 3.0 and WebGL 2.
+    ///
+    /// As GLSL ES requires floating point precision to be declared in fragment shaders,
+    /// `precision highp float;` will be emitted after the version directive.
+    Gles300,
+}
+
+impl ShaderVersion {
+    /// Returns the header that will be inserted at the start of the shader.
+    pub fn header(self) -> &'static str {
+        match self {
+            ShaderVersion::Glsl150 => "#version 150\n",
+            ShaderVersion::Gles300 => "#version 300 es\nprecision highp float;\n",
+        }
+    }
+}
+
+/// A simple preprocessor for GLSL shader code.
+///
+/// This runs before the shader is passed to the graphics driver, and allows the same
+/// shader source to be shared between multiple shaders and backends:
+///
+/// * `#include "path"` directives are replaced with the contents of the given file. Files
+///   are loaded via the [`fs`](crate::fs) module, so they can come from a mounted archive.
+///   Paths are relative to the file containing the directive (or relative to the root of
+///   the asset search path, for shaders that were not loaded from a file). Each file will
+///   only be included once per shader, so headers do not need include guards.
+/// * Any `#version` directives in the source are removed, and replaced with a single
+///   header for the configured [`ShaderVersion`].
+/// * Defines can be injected at the start of the shader, via [`define`](Self::define).
+///
+/// Creating the preprocessor via [`for_context`](Self::for_context) will also add a set of
+/// defines that describe the engine and the current device:
+///
+/// | Define | Value |
+/// | - | - |
+/// | `TETRA` | Always `1`. |
+/// | `TETRA_CANVAS_FLIP` | `1` if the contents of canvases are stored upside-down (as is the case with OpenGL), `0` otherwise. |
+/// | `TETRA_MAX_TEXTURE_SIZE` | The maximum width/height of a texture on the current device. |
+///
+/// `#line` directives are emitted around included files, so line numbers in compile errors
+/// will point to the original source. The source string number in these errors is the
+/// order in which the file was first included, with `0` being the top-level shader.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::ShaderPreprocessor;
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// let shader = ShaderPreprocessor::for_context(ctx)
+///     .define("MAX_LIGHTS", 8)
+///     .load_shader(ctx, "./shaders/lit.vert", "./shaders/lit.frag")?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ShaderPreprocessor {
+    version: ShaderVersion,
+    defines: Vec<(String, String)>,
+}
+
+impl ShaderPreprocessor {
+    /// Creates a new preprocessor with no defines, which will emit the given version header.
+    pub fn new(version: ShaderVersion) -> ShaderPreprocessor {
+        ShaderPreprocessor {
+            version,
+            defines: Vec::new(),
+        }
+    }
+
+    /// Creates a new preprocessor, configured for the current device.
+    ///
+    /// The version header will be chosen based on the GLSL version that the device
+    /// reports, and the engine defines listed in the [type-level docs](Self) will be added.
+    pub fn for_context(ctx: &Context) -> ShaderPreprocessor {
+        let info = graphics::get_device_info(ctx);
+        let capabilities = graphics::get_capabilities(ctx);
+
+        let version = if info.glsl_version.contains(" ES ") {
+            ShaderVersion::Gles300
+        } else {
+            ShaderVersion::Glsl150
+        };
+
+        let mut preprocessor = ShaderPreprocessor::new(version);
+
+        preprocessor
+            .define("TETRA", 1)
+            .define("TETRA_CANVAS_FLIP", 1)
+            .define("TETRA_MAX_TEXTURE_SIZE", capabilities.max_texture_size);
+
+        preprocessor
+    }
+
+    /// Returns the version header that will be emitted.
+    pub fn version(&self) -> ShaderVersion {
+        self.version
+    }
+
+    /// Sets the version header that will be emitted.
+    pub fn set_version(&mut self, version: ShaderVersion) -> &mut ShaderPreprocessor {
+        self.version = version;
+        self
+    }
+
+    /// Adds a define, which will be inserted after the version header.
+    ///
+    /// If a define with the same name has already been added, its value will be replaced.
+    pub fn define<V>(&mut self, name: &str, value: V) -> &mut ShaderPreprocessor
+    where
+        V: ToString,
+    {
+        let value = value.to_string();
+
+        match self.defines.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = value,
+            None => self.defines.push((name.to_owned(), value)),
+        }
+
+        self
+    }
+
+    /// Removes a previously added define.
+    pub fn undefine(&mut self, name: &str) -> &mut ShaderPreprocessor {
+        self.defines.retain(|(n, _)| n != name);
+        self
+    }
+
+    /// Loads a shader file and runs it through the preprocessor.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::FailedToLoadAsset`](crate::TetraError::FailedToLoadAsset) will be returned
+    ///   if the file, or any of the files it includes, could not be loaded.
+    /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if an
+    ///   `#include` directive was malformed.
+    pub fn process_file<P>(&self, path: P) -> Result<String>
+    where
+        P: AsRef<Path>,
+    {
+        let path = normalize(path.as_ref());
+        let source = fs::read_to_string(&path)?;
+
+        self.process(&source, Some(&path))
+    }
+
+    /// Runs a string of shader code through the preprocessor.
+    ///
+    /// Relative `#include` paths will be resolved from the root of the asset search path.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::FailedToLoadAsset`](crate::TetraError::FailedToLoadAsset) will be returned
+    ///   if any of the included files could not be loaded.
+    /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if an
+    ///   `#include` directive was malformed.
+    pub fn process_str(&self, source: &str) -> Result<String> {
+        self.process(source, None)
+    }
+
+    /// Loads a vertex and fragment shader from the given files, runs them through the
+    /// preprocessor, and then compiles them into a [`Shader`].
+    ///
+    /// Note that shaders created this way will not be reloaded by the `hot_reload` feature.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if the
+    ///   underlying graphics API encounters an error.
+    /// * [`TetraError::FailedToLoadAsset`](crate::TetraError::FailedToLoadAsset) will be returned
+    ///   if the files, or any of the files they include, could not be loaded.
+    /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
+    ///   shader could not be preprocessed or compiled.
+    #[track_caller]
+    pub fn load_shader<P>(
+        &self,
+        ctx: &mut Context,
+        vertex_path: P,
+        fragment_path: P,
+    ) -> Result<Shader>
+    where
+        P: AsRef<Path>,
+    {
+        let vertex = self.process_file(vertex_path)?;
+        let fragment = self.process_file(fragment_path)?;
+
+        Shader::from_string(ctx, &vertex, &fragment)
+    }
+
+    fn process(&self, source: &str, path: Option<&Path>) -> Result<String> {
+        let mut output = String::with_capacity(source.len());
+
+        output.push_str(self.version.header());
+
+        for (name, value) in &self.defines {
+            let _ = writeln!(output, "#define {} {}", name, value);
+        }
+
+        let mut state = IncludeState {
+            included: HashSet::new(),
+            next_source: 1,
+        };
+
+        if let Some(path) = path {
+            state.included.insert(path.to_owned());
+        }
+
+        output.push_str("#line 1 0\n");
+        expand(source, path, 0, &mut state, &mut output)?;
+
+        Ok(output)
+    }
+}
+
+struct IncludeState {
+    included: HashSet<PathBuf>,
+    next_source: u32,
+}
+
+fn expand(
+    source: &str,
+    path: Option<&Path>,
+    source_number: u32,
+    state: &mut IncludeState,
+    output: &mut String,
+) -> Result {
+    for (i, line) in source.lines().enumerate() {
+        let directive = line.trim_start();
+
+        if directive.starts_with("#version") {
+            // Keep the line numbering intact.
+            output.push('\n');
+        } else if let Some(rest) = directive.strip_prefix("#include") {
+            let target = parse_include(rest).ok_or_else(|| {
+                TetraError::InvalidShader(format!(
+                    "{}:{}: malformed #include directive",
+                    path.map(|p| p.display().to_string())
+                        .unwrap_or_else(|| "<string>".into()),
+                    i + 1
+                ))
+            })?;
+
+            let include_path = match path.and_then(Path::parent) {
+                Some(dir) => normalize(&dir.join(target)),
+                None => normalize(Path::new(target)),
+            };
+
+            if state.included.insert(include_path.clone()) {
+                let contents = fs::read_to_string(&include_path)?;
+                let include_number = state.next_source;
+
+                state.next_source += 1;
+
+                let _ = writeln!(output, "#line 1 {}", include_number);
+                expand(
+                    &contents,
+                    Some(&include_path),
+                    include_number,
+                    state,
+                    output,
+                )?;
+                let _ = writeln!(output, "#line {} {}", i + 2, source_number);
+            } else {
+                output.push('\n');
+            }
+        } else {
+            output.push_str(line);
+            output.push('\n');
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_include(rest: &str) -> Option<&str> {
+    let rest = rest.trim();
+
+    let inner = rest
+        .strip_prefix('"')
+        .and_then(|r| r.strip_suffix('"'))
+        .or_else(|| rest.strip_prefix('<').and_then(|r| r.strip_suffix('>')))?;
+
+    if inner.is_empty() {
+        None
+    } else {
+        Some(inner)
+    }
+}
+
+/// Lexically resolves `.` and `..` components, as mounted sources do not accept them.
+fn normalize(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push("..");
+                }
+            }
+            other => result.push(other),
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_and_defines() {
+        let mut preprocessor = ShaderPreprocessor::new(ShaderVersion::Gles300);
+        preprocessor.define("MAX_LIGHTS", 4).define("MAX_LIGHTS", 8);
+
+        let output = preprocessor
+            .process_str("#version 150\nvoid main() {}\n")
+            .unwrap();
+
+        assert_eq!(
+            output,
+            "#version 300 es\nprecision highp float;\n#define MAX_LIGHTS 8\n#line 1 0\n\nvoid main() {}\n"
+        );
+    }
+
+    #[test]
+    fn include_parsing() {
+        assert_eq!(parse_include(" \"common.glsl\""), Some("common.glsl"));
+        assert_eq!(parse_include("<lib/noise.glsl>"), Some("lib/noise.glsl"));
+        assert_eq!(parse_include("\""), None);
+        assert_eq!(parse_include("common.glsl"), None);
+
+        assert_eq!(
+            normalize(Path::new("./shaders/lib/../common.glsl")),
+            Path::new("shaders/common.glsl")
+        );
+    }
+}
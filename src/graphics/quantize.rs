@@ -0,0 +1,393 @@
+use crate::error::Result;
+use crate::graphics::shader::{self, Shader};
+use crate::graphics::{Color, FilterMode, Texture};
+use crate::Context;
+
+const QUANTIZE_FRAGMENT_SHADER: &str = include_str!("../resources/quantize.frag");
+
+const BAYER_SIZE: usize = 8;
+const BLUE_NOISE_SIZE: usize = 32;
+
+/// The largest palette that can be used with a [`QuantizeFilter`].
+pub const MAX_PALETTE_SIZE: usize = 256;
+
+/// The set of colors that a [`QuantizeFilter`] reduces the screen to.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Palette {
+    /// Each color channel is reduced to the given number of bits (e.g. `1` gives 8 colors,
+    /// `2` gives 64 colors).
+    BitDepth(u8),
+
+    /// Each pixel is snapped to the closest color in a texture. Every pixel of the texture
+    /// is treated as a palette entry, reading left to right and then top to bottom, up to a
+    /// maximum of [`MAX_PALETTE_SIZE`] entries.
+    Texture(Texture),
+}
+
+impl Palette {
+    /// Creates a palette from a list of colors.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+    ///   if the underlying graphics API encounters an error.
+    pub fn from_colors(ctx: &mut Context, colors: &[Color]) -> Result<Palette> {
+        let data: Vec<u8> = colors.iter().flat_map(|c| <[u8; 4]>::from(*c)).collect();
+
+        let mut texture = Texture::from_rgba(ctx, colors.len() as i32, 1, &data)?;
+        texture.set_filter_mode(ctx, FilterMode::Nearest);
+
+        Ok(Palette::Texture(texture))
+    }
+}
+
+/// The pattern used to dither colors when they are quantized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Dithering {
+    /// No dithering - colors will snap straight to the closest palette entry, which
+    /// can cause banding.
+    None,
+
+    /// An 8x8 Bayer matrix, which gives the cross-hatched look of old PC and console games.
+    Ordered,
+
+    /// A blue noise pattern, which looks less regular than ordered dithering while
+    /// avoiding the clumping of white noise.
+    BlueNoise,
+}
+
+/// Settings for a filter that reduces the screen to a limited palette, with
+/// optional dithering.
+///
+/// The filter is applied to everything drawn to the screen when
+/// [`present`](super::present) is called - it can be enabled via
+/// [`set_quantize_filter`](super::set_quantize_filter). If a
+/// [`RetroFilter`](super::RetroFilter) is also enabled, the colors will be
+/// quantized first.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// use tetra::graphics::{self, Color, Dithering, Palette, QuantizeFilter};
+///
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// let game_boy = Palette::from_colors(
+///     ctx,
+///     &[
+///         Color::hex("#0f380f"),
+///         Color::hex("#306230"),
+///         Color::hex("#8bac0f"),
+///         Color::hex("#9bbc0f"),
+///     ],
+/// )?;
+///
+/// graphics::set_quantize_filter(ctx, Some(QuantizeFilter::new(game_boy, Dithering::Ordered)))?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuantizeFilter {
+    /// The colors that the screen will be reduced to.
+    pub palette: Palette,
+
+    /// The pattern that will be used to dither the colors.
+    pub dithering: Dithering,
+
+    /// How much noise the dithering adds, relative to the distance between the colors
+    /// in the palette. `1.0` is usually a good starting point - higher values will blend
+    /// colors more smoothly, at the cost of a grainier image.
+    pub dither_strength: f32,
+}
+
+impl QuantizeFilter {
+    /// Creates a new quantize filter, with a dither strength of `1.0`.
+    pub fn new(palette: Palette, dithering: Dithering) -> QuantizeFilter {
+        QuantizeFilter {
+            palette,
+            dithering,
+            dither_strength: 1.0,
+        }
+    }
+}
+
+/// The GPU resources needed to apply a [`QuantizeFilter`].
+#[derive(Debug, Clone)]
+pub(crate) struct QuantizeState {
+    pub(crate) filter: QuantizeFilter,
+    pub(crate) shader: Shader,
+    dither: Texture,
+    palette_size: i32,
+    spread: f32,
+}
+
+impl QuantizeState {
+    pub(crate) fn new(
+        ctx: &mut Context,
+        filter: QuantizeFilter,
+        shader: Option<Shader>,
+    ) -> Result<QuantizeState> {
+        let shader = match shader {
+            Some(shader) => shader,
+            None => {
+                Shader::from_string(ctx, shader::DEFAULT_VERTEX_SHADER, QUANTIZE_FRAGMENT_SHADER)?
+            }
+        };
+
+        let dither = match filter.dithering {
+            Dithering::None => threshold_texture(ctx, 1, &[128])?,
+            Dithering::Ordered => threshold_texture(ctx, BAYER_SIZE, &bayer(BAYER_SIZE))?,
+            Dithering::BlueNoise => {
+                threshold_texture(ctx, BLUE_NOISE_SIZE, &blue_noise(BLUE_NOISE_SIZE))?
+            }
+        };
+
+        let (palette_size, step) = match &filter.palette {
+            Palette::BitDepth(bits) => (0, 1.0 / levels(*bits)),
+            Palette::Texture(texture) => {
+                let colors = texture
+                    .get_data(ctx)
+                    .as_bytes()
+                    .chunks_exact(4)
+                    .take(MAX_PALETTE_SIZE)
+                    .map(|p| Color::rgb8(p[0], p[1], p[2]))
+                    .collect::<Vec<_>>();
+
+                (colors.len() as i32, palette_step(&colors))
+            }
+        };
+
+        let spread = match filter.dithering {
+            Dithering::None => 0.0,
+            _ => filter.dither_strength * step,
+        };
+
+        Ok(QuantizeState {
+            filter,
+            shader,
+            dither,
+            palette_size,
+            spread,
+        })
+    }
+
+    pub(crate) fn set_uniforms(&self, ctx: &mut Context) {
+        let palette = match &self.filter.palette {
+            Palette::Texture(texture) => texture,
+            Palette::BitDepth(_) => &self.dither,
+        };
+
+        let levels = match self.filter.palette {
+            Palette::BitDepth(bits) => levels(bits),
+            Palette::Texture(_) => 1.0,
+        };
+
+        self.shader.set_uniform(ctx, "u_dither", &self.dither);
+        self.shader.set_uniform(ctx, "u_palette", palette);
+        self.shader
+            .set_uniform(ctx, "u_palette_size", self.palette_size);
+        self.shader.set_uniform(ctx, "u_levels", levels);
+        self.shader.set_uniform(ctx, "u_spread", self.spread);
+    }
+}
+
+fn levels(bits: u8) -> f32 {
+    ((1u32 << bits.clamp(1, 8)) - 1) as f32
+}
+
+/// Estimates how far apart the colors in a palette are, by averaging the distance from
+/// each color to its nearest neighbour.
+fn palette_step(colors: &[Color]) -> f32 {
+    if colors.len() < 2 {
+        return 0.0;
+    }
+
+    let total: f32 = colors
+        .iter()
+        .enumerate()
+        .map(|(i, a)| {
+            colors
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| i != *j)
+                .map(|(_, b)| {
+                    ((a.r - b.r).powi(2) + (a.g - b.g).powi(2) + (a.b - b.b).powi(2)).sqrt()
+                })
+                .fold(f32::INFINITY, f32::min)
+        })
+        .sum();
+
+    total / colors.len() as f32
+}
+
+fn threshold_texture(ctx: &mut Context, size: usize, values: &[u8]) -> Result<Texture> {
+    let data: Vec<u8> = values.iter().flat_map(|v| [*v, *v, *v, 255]).collect();
+
+    let mut texture = Texture::from_rgba(ctx, size as i32, size as i32, &data)?;
+    texture.set_filter_mode(ctx, FilterMode::Nearest);
+
+    Ok(texture)
+}
+
+/// Converts a ranking of `count` cells into evenly spaced thresholds.
+fn rank_to_threshold(rank: usize, count: usize) -> u8 {
+    ((rank * 2 + 1) * 255 / (count * 2)) as u8
+}
+
+/// Generates a Bayer matrix of the given size, which must be a power of two.
+fn bayer(size: usize) -> Vec<u8> {
+    let count = size * size;
+
+    (0..count)
+        .map(|i| {
+            let (x, y) = (i % size, i / size);
+            let mut rank = 0;
+            let mut bit = size / 2;
+
+            // Interleave the bits of x ^ y and y, from the most significant down.
+            while bit > 0 {
+                rank =
+                    (rank << 2) | (((x ^ y) & bit != 0) as usize) << 1 | ((y & bit != 0) as usize);
+                bit /= 2;
+            }
+
+            rank_to_threshold(rank, count)
+        })
+        .collect()
+}
+
+/// Generates a tileable blue noise pattern using the void-and-cluster method.
+fn blue_noise(size: usize) -> Vec<u8> {
+    const SIGMA: f32 = 1.5;
+
+    let count = size * size;
+
+    // A Gaussian filter, wrapping around the edges so that the pattern tiles.
+    let kernel: Vec<f32> = (0..count)
+        .map(|i| {
+            let dx = (i % size).min(size - i % size) as f32;
+            let dy = (i / size).min(size - i / size) as f32;
+
+            (-(dx * dx + dy * dy) / (2.0 * SIGMA * SIGMA)).exp()
+        })
+        .collect();
+
+    let mut energy = vec![0.0; count];
+    let mut pattern = vec![false; count];
+
+    let toggle = |pattern: &mut [bool], energy: &mut [f32], p: usize| {
+        let sign = if pattern[p] { -1.0 } else { 1.0 };
+        let (px, py) = (p % size, p / size);
+
+        pattern[p] = !pattern[p];
+
+        for (q, e) in energy.iter_mut().enumerate() {
+            let dx = (q % size + size - px) % size;
+            let dy = (q / size + size - py) % size;
+
+            *e += sign * kernel[dy * size + dx];
+        }
+    };
+
+    let tightest_cluster = |pattern: &[bool], energy: &[f32]| {
+        (0..count)
+            .filter(|p| pattern[*p])
+            .max_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+    };
+
+    let largest_void = |pattern: &[bool], energy: &[f32]| {
+        (0..count)
+            .filter(|p| !pattern[*p])
+            .min_by(|a, b| energy[*a].total_cmp(&energy[*b]))
+    };
+
+    // Start from a sparse pseudo-random pattern, using a fixed seed so that the
+    // output is the same every time.
+    let mut seed = 0x2545_f491_u32;
+    let initial = count / 10;
+
+    while pattern.iter().filter(|p| **p).count() < initial {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+
+        let p = seed as usize % count;
+
+        if !pattern[p] {
+            toggle(&mut pattern, &mut energy, p);
+        }
+    }
+
+    // Spread the initial points out evenly, by repeatedly moving the most
+    // clustered point into the biggest gap.
+    for _ in 0..count {
+        let cluster = tightest_cluster(&pattern, &energy).unwrap();
+        toggle(&mut pattern, &mut energy, cluster);
+
+        let void = largest_void(&pattern, &energy).unwrap();
+        toggle(&mut pattern, &mut energy, void);
+
+        if void == cluster {
+            break;
+        }
+    }
+
+    let mut ranks = vec![0; count];
+
+    // Rank the initial points, removing the most clustered first.
+    let (mut removed_pattern, mut removed_energy) = (pattern.clone(), energy.clone());
+
+    for rank in (0..initial).rev() {
+        let cluster = tightest_cluster(&removed_pattern, &removed_energy).unwrap();
+        toggle(&mut removed_pattern, &mut removed_energy, cluster);
+        ranks[cluster] = rank;
+    }
+
+    // Then rank the rest of the cells, filling in the biggest gaps first.
+    for rank in initial..count {
+        let void = largest_void(&pattern, &energy).unwrap();
+        toggle(&mut pattern, &mut energy, void);
+        ranks[void] = rank;
+    }
+
+    ranks
+        .into_iter()
+        .map(|rank| rank_to_threshold(rank, count))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bayer_matrix() {
+        assert_eq!(
+            bayer(2),
+            [
+                rank_to_threshold(0, 4),
+                rank_to_threshold(2, 4),
+                rank_to_threshold(3, 4),
+                rank_to_threshold(1, 4),
+            ]
+        );
+
+        let mut values = bayer(8);
+        values.sort_unstable();
+        values.dedup();
+        assert_eq!(values.len(), 64);
+    }
+
+    #[test]
+    fn blue_noise_uses_every_threshold() {
+        let mut values = blue_noise(16);
+        values.sort_unstable();
+
+        let expected: Vec<u8> = (0..256).map(|r| rank_to_threshold(r, 256)).collect();
+        assert_eq!(values, expected);
+    }
+}
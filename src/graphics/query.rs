@@ -0,0 +1,165 @@
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::graphics;
+use crate::platform::RawQuery;
+use crate::Context;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum QueryState {
+    Idle,
+    Active,
+    Pending,
+    Ready(u32),
+}
+
+/// A query that counts how many pixels were drawn between two points in time.
+///
+/// This can be used to find out whether something was actually visible on screen - for
+/// example, if a light or a particle emitter that covers a large area was completely
+/// hidden behind a wall (via [stencils](super::set_stencil_state) or
+/// [depth testing](super::set_depth_test)), the expensive passes that apply its effect
+/// can be skipped next frame.
+///
+/// Queries run on the GPU, which usually lags behind the CPU by a frame or two, so
+/// the result will not be available straight away. Rather than waiting, it is usually
+/// best to check for the result the next time you need it, and to keep using the
+/// previous result until a new one is ready.
+///
+/// Only one query can be active at a time. Beginning a query while another one is
+/// active will end the other query first.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{OcclusionQuery, Texture};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, query: &mut OcclusionQuery, light: &Texture, visible: &mut bool) {
+/// // Keep the last known result until a new one is available.
+/// if let Some(result) = query.is_visible(ctx) {
+///     *visible = result;
+/// }
+///
+/// query.begin(ctx);
+/// light.draw(ctx, Vec2::new(64.0, 64.0));
+/// query.end(ctx);
+///
+/// if *visible {
+///     // Run the expensive lighting passes...
+/// }
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct OcclusionQuery {
+    handle: Arc<RawQuery>,
+    state: QueryState,
+}
+
+impl OcclusionQuery {
+    /// Creates a new occlusion query.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned if
+    ///   the underlying graphics API encounters an error.
+    pub fn new(ctx: &mut Context) -> Result<OcclusionQuery> {
+        let handle = ctx.device.new_query()?;
+
+        Ok(OcclusionQuery {
+            handle: Arc::new(handle),
+            state: QueryState::Idle,
+        })
+    }
+
+    /// Starts counting the pixels that are drawn.
+    ///
+    /// Any pending draw calls will be flushed first, so that they are not counted.
+    /// If the query already has a result that has not been retrieved, it will be
+    /// discarded.
+    pub fn begin(&mut self, ctx: &mut Context) {
+        if ctx.graphics.active_query.as_ref() == Some(&self.handle) {
+            return;
+        }
+
+        graphics::flush(ctx);
+
+        if ctx.graphics.active_query.take().is_some() {
+            ctx.device.end_query();
+        }
+
+        ctx.device.begin_query(&self.handle);
+        ctx.graphics.active_query = Some(Arc::clone(&self.handle));
+
+        self.state = QueryState::Active;
+    }
+
+    /// Stops counting the pixels that are drawn.
+    ///
+    /// Any pending draw calls will be flushed first, so that they are counted.
+    pub fn end(&mut self, ctx: &mut Context) {
+        if self.state != QueryState::Active {
+            return;
+        }
+
+        graphics::flush(ctx);
+
+        if ctx.graphics.active_query.as_ref() == Some(&self.handle) {
+            ctx.device.end_query();
+            ctx.graphics.active_query = None;
+        }
+
+        self.state = QueryState::Pending;
+    }
+
+    /// Returns the number of samples that were drawn while the query was active, or
+    /// `None` if the result is not available yet.
+    ///
+    /// This does not wait for the GPU. `None` will also be returned if the query has never
+    /// been run, or if it is currently active. Once a result is available, it will continue
+    /// to be returned until the query is run again.
+    ///
+    /// When rendering to a multisampled target, each pixel may count as multiple samples.
+    pub fn samples_passed(&mut self, ctx: &mut Context) -> Option<u32> {
+        if self.state == QueryState::Pending {
+            if let Some(result) = ctx.device.get_query_result(&self.handle, false) {
+                self.state = QueryState::Ready(result);
+            }
+        }
+
+        match self.state {
+            QueryState::Ready(result) => Some(result),
+            _ => None,
+        }
+    }
+
+    /// Returns whether anything was drawn while the query was active, or `None` if the
+    /// result is not available yet.
+    ///
+    /// See [`samples_passed`](Self::samples_passed) for more details.
+    pub fn is_visible(&mut self, ctx: &mut Context) -> Option<bool> {
+        self.samples_passed(ctx).map(|samples| samples > 0)
+    }
+
+    /// Waits for the GPU to finish running the query, and then returns the number of
+    /// samples that were drawn while it was active.
+    ///
+    /// This will stall the CPU until all of the commands before the end of the query
+    /// have been processed, so [`samples_passed`](Self::samples_passed) should be
+    /// preferred where possible. If the query is active, it will be ended first. If it
+    /// has never been run, `0` will be returned.
+    pub fn wait_for_result(&mut self, ctx: &mut Context) -> u32 {
+        self.end(ctx);
+
+        if self.state == QueryState::Pending {
+            if let Some(result) = ctx.device.get_query_result(&self.handle, true) {
+                self.state = QueryState::Ready(result);
+            }
+        }
+
+        match self.state {
+            QueryState::Ready(result) => result,
+            _ => 0,
+        }
+    }
+}
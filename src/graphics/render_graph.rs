@@ -0,0 +1,387 @@
+use std::fmt::{self, Debug, Formatter};
+
+use crate::error::{Result, TetraError};
+use crate::graphics::{self, Canvas, CanvasBuilder, Color, RenderTargetPool};
+use crate::Context;
+
+type PassFn<'a> = Box<dyn FnOnce(&mut Context, &PassResources<'_>) -> Result + 'a>;
+
+/// A handle to a temporary render target, declared as part of a [`RenderGraph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderTarget(usize);
+
+/// Where a render pass draws to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PassOutput {
+    /// The pass draws to the screen.
+    Screen,
+
+    /// The pass draws to a render target.
+    Target(RenderTarget),
+}
+
+impl From<RenderTarget> for PassOutput {
+    fn from(target: RenderTarget) -> PassOutput {
+        PassOutput::Target(target)
+    }
+}
+
+/// The render targets that are available to a pass while it is running.
+pub struct PassResources<'r> {
+    canvases: &'r [Option<Canvas>],
+}
+
+impl PassResources<'_> {
+    /// Returns the canvas that has been allocated for a render target.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the target is not currently allocated. Targets are only guaranteed to be
+    /// allocated while running a pass that declared them as an input or an output.
+    pub fn canvas(&self, target: RenderTarget) -> &Canvas {
+        self.canvases
+            .get(target.0)
+            .and_then(Option::as_ref)
+            .expect("render target is not available in this pass - was it declared as an input?")
+    }
+}
+
+struct Pass<'a> {
+    name: String,
+    inputs: Vec<RenderTarget>,
+    output: PassOutput,
+    execute: Option<PassFn<'a>>,
+}
+
+/// A description of a multi-pass rendering pipeline, which Tetra will schedule and run.
+///
+/// Effects like lighting and bloom are built out of several passes, each drawing into
+/// a canvas that later passes read from. Managing these by hand is error-prone - the
+/// passes have to run in the right order, and each one needs a scratch canvas that is
+/// the right size. A render graph instead lets you declare each pass along with the
+/// [render targets](RenderTarget) it reads from and writes to, and then takes care of:
+///
+/// * Running the passes in an order where every pass runs after the passes that write
+///   to its inputs. Passes that don't depend on each other run in the order they were added.
+/// * Skipping passes whose output is never used by a pass that draws to the screen.
+/// * Allocating a canvas for each target from a [`RenderTargetPool`], and returning it to
+///   the pool once the last pass that uses it has run, so that it can be reused by later
+///   targets in the same graph.
+/// * Switching to the right canvas before each pass, and flushing after it. Each target is
+///   cleared to transparent black before the first pass that writes to it.
+///
+/// Graphs are cheap to build, and are consumed when they are executed - the usual pattern
+/// is to build one each frame, so that the passes can borrow your game's state. The pool
+/// should be kept between frames.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{self, DrawParams, PassOutput, RenderGraph, RenderTargetPool};
+/// # fn draw_scene(ctx: &mut Context) {}
+/// # fn example(ctx: &mut Context, pool: &mut RenderTargetPool) -> tetra::Result {
+/// let mut graph = RenderGraph::new();
+///
+/// let scene = graph.create_target(640, 480);
+/// let blurred = graph.create_target(320, 240);
+///
+/// graph.add_pass("scene", &[], scene, |ctx, _| {
+///     draw_scene(ctx);
+///     Ok(())
+/// });
+///
+/// graph.add_pass("blur", &[scene], blurred, move |ctx, targets| {
+///     targets.canvas(scene).draw(ctx, DrawParams::new().scale([0.5, 0.5].into()));
+///     Ok(())
+/// });
+///
+/// graph.add_pass("composite", &[scene, blurred], PassOutput::Screen, move |ctx, targets| {
+///     targets.canvas(scene).draw(ctx, DrawParams::new());
+///     targets.canvas(blurred).draw(ctx, DrawParams::new().scale([2.0, 2.0].into()));
+///     Ok(())
+/// });
+///
+/// graph.execute(ctx, pool)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct RenderGraph<'a> {
+    targets: Vec<CanvasBuilder>,
+    passes: Vec<Pass<'a>>,
+}
+
+impl<'a> RenderGraph<'a> {
+    /// Creates a new, empty render graph.
+    pub fn new() -> RenderGraph<'a> {
+        RenderGraph {
+            targets: Vec::new(),
+            passes: Vec::new(),
+        }
+    }
+
+    /// Declares a render target of the given size, with the default canvas settings.
+    pub fn create_target(&mut self, width: i32, height: i32) -> RenderTarget {
+        self.create_target_with(&CanvasBuilder::new(width, height))
+    }
+
+    /// Declares a render target with the settings from the given builder.
+    pub fn create_target_with(&mut self, settings: &CanvasBuilder) -> RenderTarget {
+        self.targets.push(settings.clone());
+        RenderTarget(self.targets.len() - 1)
+    }
+
+    /// Adds a pass to the graph.
+    ///
+    /// The pass will be able to read from the canvases of the given inputs via the
+    /// [`PassResources`] that are passed to it, and everything it draws will go to the
+    /// output. A pass cannot use its own output as an input.
+    pub fn add_pass<O, F>(&mut self, name: &str, inputs: &[RenderTarget], output: O, execute: F)
+    where
+        O: Into<PassOutput>,
+        F: FnOnce(&mut Context, &PassResources<'_>) -> Result + 'a,
+    {
+        self.passes.push(Pass {
+            name: name.to_owned(),
+            inputs: inputs.to_vec(),
+            output: output.into(),
+            execute: Some(Box::new(execute)),
+        });
+    }
+
+    /// Returns the number of passes that have been added to the graph.
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    /// Returns `true` if no passes have been added to the graph.
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Schedules and runs the passes in the graph.
+    ///
+    /// Once the graph has finished, the renderer will be drawing to the screen.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::InvalidRenderGraph`](crate::TetraError::InvalidRenderGraph) will be
+    ///   returned if the passes depend on each other in a cycle, if a pass reads from its own
+    ///   output, or if a pass reads from a target that no pass writes to.
+    /// * Any errors that occur while allocating a canvas, or that are returned by a pass,
+    ///   will be passed through. The remaining passes will not be run.
+    pub fn execute(mut self, ctx: &mut Context, pool: &mut RenderTargetPool) -> Result {
+        let order = schedule(&self.passes)?;
+
+        let mut last_use = vec![0; self.targets.len()];
+
+        for (position, &index) in order.iter().enumerate() {
+            let pass = &self.passes[index];
+
+            for target in pass.targets() {
+                last_use[target.0] = position;
+            }
+        }
+
+        let mut canvases: Vec<Option<Canvas>> = vec![None; self.targets.len()];
+
+        let result = (|| {
+            for (position, &index) in order.iter().enumerate() {
+                let pass = &mut self.passes[index];
+
+                match pass.output {
+                    PassOutput::Screen => graphics::reset_canvas(ctx),
+                    PassOutput::Target(target) => match &canvases[target.0] {
+                        Some(canvas) => graphics::set_canvas(ctx, canvas),
+                        None => {
+                            let canvas = pool.get_with(ctx, &self.targets[target.0])?;
+
+                            graphics::set_canvas(ctx, &canvas);
+                            graphics::clear(ctx, Color::rgba(0.0, 0.0, 0.0, 0.0));
+
+                            canvases[target.0] = Some(canvas);
+                        }
+                    },
+                }
+
+                if let Some(execute) = pass.execute.take() {
+                    execute(
+                        ctx,
+                        &PassResources {
+                            canvases: &canvases,
+                        },
+                    )?;
+                }
+
+                graphics::reset_canvas(ctx);
+
+                for target in pass.targets() {
+                    if last_use[target.0] == position {
+                        canvases[target.0] = None;
+                    }
+                }
+            }
+
+            Ok(())
+        })();
+
+        graphics::reset_canvas(ctx);
+
+        result
+    }
+}
+
+impl Default for RenderGraph<'_> {
+    fn default() -> Self {
+        RenderGraph::new()
+    }
+}
+
+impl Debug for RenderGraph<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RenderGraph")
+            .field("targets", &self.targets)
+            .field(
+                "passes",
+                &self.passes.iter().map(|p| &p.name).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl Pass<'_> {
+    fn targets(&self) -> impl Iterator<Item = RenderTarget> + '_ {
+        let output = match self.output {
+            PassOutput::Screen => None,
+            PassOutput::Target(target) => Some(target),
+        };
+
+        self.inputs.iter().copied().chain(output)
+    }
+}
+
+/// Returns the indices of the passes that need to run, in the order they should run in.
+fn schedule(passes: &[Pass<'_>]) -> Result<Vec<usize>> {
+    let invalid = |pass: &Pass<'_>, msg: &str| {
+        TetraError::InvalidRenderGraph(format!("pass '{}' {}", pass.name, msg))
+    };
+
+    let writers = |target: RenderTarget| {
+        passes
+            .iter()
+            .enumerate()
+            .filter(move |(_, p)| p.output == PassOutput::Target(target))
+            .map(|(i, _)| i)
+    };
+
+    // Readers depend on every pass that writes to their inputs, and writers depend on any
+    // earlier passes that wrote to the same target.
+    let mut dependencies = Vec::with_capacity(passes.len());
+
+    for (i, pass) in passes.iter().enumerate() {
+        let mut deps = Vec::new();
+
+        for &input in &pass.inputs {
+            if pass.output == PassOutput::Target(input) {
+                return Err(invalid(pass, "reads from its own output"));
+            }
+
+            let len = deps.len();
+            deps.extend(writers(input));
+
+            if deps.len() == len {
+                return Err(invalid(
+                    pass,
+                    "reads from a target that is never written to",
+                ));
+            }
+        }
+
+        if let PassOutput::Target(output) = pass.output {
+            deps.extend(writers(output).filter(|&w| w < i));
+        }
+
+        dependencies.push(deps);
+    }
+
+    // Only passes that contribute to the screen need to run.
+    let mut live = vec![false; passes.len()];
+    let mut stack: Vec<usize> = passes
+        .iter()
+        .enumerate()
+        .filter(|(_, p)| p.output == PassOutput::Screen)
+        .map(|(i, _)| i)
+        .collect();
+
+    while let Some(i) = stack.pop() {
+        if !live[i] {
+            live[i] = true;
+            stack.extend(&dependencies[i]);
+        }
+    }
+
+    let mut order = Vec::new();
+    let mut scheduled = vec![false; passes.len()];
+
+    while order.len() < live.iter().filter(|&&l| l).count() {
+        let next = (0..passes.len())
+            .find(|&i| live[i] && !scheduled[i] && dependencies[i].iter().all(|&d| scheduled[d]));
+
+        match next {
+            Some(i) => {
+                scheduled[i] = true;
+                order.push(i);
+            }
+            None => {
+                let stuck = (0..passes.len())
+                    .find(|&i| live[i] && !scheduled[i])
+                    .map(|i| &passes[i])
+                    .expect("a live pass should be unscheduled");
+
+                return Err(invalid(stuck, "is part of a dependency cycle"));
+            }
+        }
+    }
+
+    Ok(order)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pass(name: &str, inputs: &[RenderTarget], output: PassOutput) -> Pass<'static> {
+        Pass {
+            name: name.to_owned(),
+            inputs: inputs.to_vec(),
+            output,
+            execute: None,
+        }
+    }
+
+    #[test]
+    fn scheduling() {
+        let a = RenderTarget(0);
+        let b = RenderTarget(1);
+        let unused = RenderTarget(2);
+
+        let passes = [
+            pass("composite", &[a, b], PassOutput::Screen),
+            pass("blur", &[a], b.into()),
+            pass("debug", &[], unused.into()),
+            pass("scene", &[], a.into()),
+            pass("ui", &[], PassOutput::Screen),
+        ];
+
+        assert_eq!(schedule(&passes).unwrap(), vec![3, 1, 0, 4]);
+
+        let cycle = [
+            pass("first", &[b], a.into()),
+            pass("second", &[a], b.into()),
+            pass("composite", &[a], PassOutput::Screen),
+        ];
+
+        assert!(schedule(&cycle).is_err());
+        assert!(schedule(&[pass("feedback", &[a], a.into())]).is_err());
+    }
+}
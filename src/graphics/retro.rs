@@ -0,0 +1,98 @@
+use crate::error::Result;
+use crate::graphics::shader::{self, Shader};
+use crate::math::Vec2;
+use crate::Context;
+
+const RETRO_FRAGMENT_SHADER: &str = include_str!("../resources/retro.frag");
+
+/// Settings for a filter that emulates the look of an old CRT display.
+///
+/// The filter is applied to everything drawn to the screen when
+/// [`present`](super::present) is called - it can be enabled via
+/// [`set_retro_filter`](super::set_retro_filter). Each of the effects can be
+/// switched off by setting its strength to `0.0`.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct RetroFilter {
+    /// How much the gaps between scanlines are darkened, from `0.0` to `1.0`.
+    pub scanlines: f32,
+
+    /// The height of each scanline, in physical pixels.
+    ///
+    /// If you are using a [`ScreenScaler`](super::scaling::ScreenScaler), setting this to the
+    /// scale factor will give you one scanline per pixel of your game.
+    pub scanline_size: f32,
+
+    /// How much the image bulges outwards, like the glass of a CRT. `0.0` gives a flat image.
+    pub curvature: f32,
+
+    /// How visible the phosphor mask is, from `0.0` to `1.0`.
+    pub phosphor_mask: f32,
+
+    /// How far the color channels bleed horizontally, in physical pixels, imitating the
+    /// smearing of an NTSC signal.
+    pub color_bleed: f32,
+}
+
+impl RetroFilter {
+    /// Creates a filter with all of the effects disabled.
+    pub const fn none() -> RetroFilter {
+        RetroFilter {
+            scanlines: 0.0,
+            scanline_size: 3.0,
+            curvature: 0.0,
+            phosphor_mask: 0.0,
+            color_bleed: 0.0,
+        }
+    }
+
+    /// Creates a filter that imitates a consumer TV, with heavy curvature and color bleed.
+    pub const fn television() -> RetroFilter {
+        RetroFilter {
+            scanlines: 0.3,
+            scanline_size: 3.0,
+            curvature: 0.15,
+            phosphor_mask: 0.2,
+            color_bleed: 1.5,
+        }
+    }
+
+    /// Creates a filter that imitates a high quality monitor, with sharp scanlines and
+    /// very little distortion.
+    pub const fn monitor() -> RetroFilter {
+        RetroFilter {
+            scanlines: 0.5,
+            scanline_size: 3.0,
+            curvature: 0.03,
+            phosphor_mask: 0.1,
+            color_bleed: 0.0,
+        }
+    }
+
+    pub(crate) fn create_shader(ctx: &mut Context) -> Result<Shader> {
+        Shader::from_string(ctx, shader::DEFAULT_VERTEX_SHADER, RETRO_FRAGMENT_SHADER)
+    }
+
+    pub(crate) fn set_uniforms(&self, ctx: &mut Context, shader: &Shader, resolution: Vec2<f32>) {
+        shader.set_uniform(ctx, "u_resolution", resolution);
+        shader.set_uniform(ctx, "u_scanlines", self.scanlines);
+        shader.set_uniform(ctx, "u_scanline_size", self.scanline_size.max(1.0));
+        shader.set_uniform(ctx, "u_curvature", self.curvature);
+        shader.set_uniform(ctx, "u_mask", self.phosphor_mask);
+        shader.set_uniform(ctx, "u_color_bleed", self.color_bleed);
+    }
+}
+
+impl Default for RetroFilter {
+    fn default() -> RetroFilter {
+        RetroFilter::television()
+    }
+}
@@ -1,12 +1,15 @@
 //! Functions and types relating to screen scaling.
 
 use crate::error::Result;
-use crate::graphics::{self, Canvas, DrawParams, Rectangle};
+use crate::graphics::shader::{self, Shader};
+use crate::graphics::{self, Canvas, Color, DrawParams, Rectangle};
 use crate::input;
 use crate::math::Vec2;
 use crate::window;
 use crate::Context;
 
+const SHARP_BILINEAR_FRAGMENT_SHADER: &str = include_str!("../resources/sharp_bilinear.frag");
+
 /// A wrapper for a [`Canvas`] that handles scaling the image to fit the screen.
 ///
 /// # Examples
@@ -21,6 +24,7 @@ pub struct ScreenScaler {
     screen_rect: Rectangle,
     outer_width: i32,
     outer_height: i32,
+    letterbox_color: Option<Color>,
 }
 
 impl ScreenScaler {
@@ -35,6 +39,12 @@ impl ScreenScaler {
         mode: ScalingMode,
     ) -> Result<ScreenScaler> {
         let canvas = Canvas::new(ctx, inner_width, inner_height)?;
+
+        if mode == ScalingMode::PixelPerfectSmooth {
+            // Compile the shader up front, so that errors can be reported.
+            sharp_bilinear_shader(ctx)?;
+        }
+
         let screen_rect =
             get_screen_rect(mode, inner_width, inner_height, outer_width, outer_height);
 
@@ -44,6 +54,7 @@ impl ScreenScaler {
             screen_rect,
             outer_width,
             outer_height,
+            letterbox_color: None,
         })
     }
 
@@ -68,7 +79,67 @@ impl ScreenScaler {
     }
 
     /// Draws the scaled image to the screen.
+    ///
+    /// If a [letterbox color](Self::set_letterbox_color) is set, the parts of the outer
+    /// area that the image does not cover will be filled in with it.
     pub fn draw(&self, ctx: &mut Context) {
+        if let Some(color) = self.letterbox_color {
+            self.draw_letterbox(ctx, color);
+        }
+
+        if self.mode == ScalingMode::PixelPerfectSmooth {
+            match sharp_bilinear_shader(ctx) {
+                Ok(shader) => {
+                    let previous = ctx.graphics.shader.clone();
+
+                    graphics::set_shader_ex(ctx, Some(&shader));
+                    self.draw_quad(ctx);
+                    graphics::set_shader_ex(ctx, previous.as_ref());
+
+                    return;
+                }
+                Err(e) => {
+                    log::error!(
+                        target: "tetra::graphics",
+                        "Failed to compile sharp bilinear shader, falling back to default: {}",
+                        e
+                    );
+                }
+            }
+        }
+
+        self.draw_quad(ctx);
+    }
+
+    fn draw_letterbox(&self, ctx: &mut Context, color: Color) {
+        let outer_width = self.outer_width as f32;
+        let outer_height = self.outer_height as f32;
+
+        let left = self.screen_rect.x.max(0.0);
+        let top = self.screen_rect.y.max(0.0);
+        let right = (self.screen_rect.x + self.screen_rect.width).min(outer_width);
+        let bottom = (self.screen_rect.y + self.screen_rect.height).min(outer_height);
+
+        let bars = [
+            (0.0, 0.0, left, outer_height),
+            (right, 0.0, outer_width, outer_height),
+            (left, 0.0, right, top),
+            (left, bottom, right, outer_height),
+        ];
+
+        let texture = ctx.graphics.default_texture.clone();
+        graphics::set_texture(ctx, &texture);
+
+        let params = DrawParams::new().color(color);
+
+        for &(x1, y1, x2, y2) in &bars {
+            if x2 > x1 && y2 > y1 {
+                graphics::push_quad(ctx, x1, y1, x2, y2, 0.0, 0.0, 1.0, 1.0, &params);
+            }
+        }
+    }
+
+    fn draw_quad(&self, ctx: &mut Context) {
         graphics::set_texture(ctx, &self.canvas.texture);
 
         graphics::push_quad(
@@ -102,6 +173,23 @@ impl ScreenScaler {
         }
     }
 
+    /// Returns the color that the letterbox bars are filled with, if one is set.
+    pub fn letterbox_color(&self) -> Option<Color> {
+        self.letterbox_color
+    }
+
+    /// Sets the color that the letterbox bars (the parts of the outer area that the scaled
+    /// image does not cover) should be filled with when the scaler is drawn.
+    ///
+    /// If this is set to `None`, the bars will be left untouched, so whatever was previously
+    /// drawn there (e.g. the color the screen was [cleared](graphics::clear) to, or a
+    /// patterned background) will show through.
+    ///
+    /// Defaults to `None`.
+    pub fn set_letterbox_color(&mut self, color: Option<Color>) {
+        self.letterbox_color = color;
+    }
+
     /// Returns a reference to the canvas that is being scaled.
     pub fn canvas(&self) -> &Canvas {
         &self.canvas
@@ -200,6 +288,22 @@ impl ScreenScaler {
     }
 }
 
+fn sharp_bilinear_shader(ctx: &mut Context) -> Result<Shader> {
+    if let Some(shader) = &ctx.graphics.sharp_bilinear_shader {
+        return Ok(shader.clone());
+    }
+
+    let shader = Shader::from_string(
+        ctx,
+        shader::DEFAULT_VERTEX_SHADER,
+        SHARP_BILINEAR_FRAGMENT_SHADER,
+    )?;
+
+    ctx.graphics.sharp_bilinear_shader = Some(shader.clone());
+
+    Ok(shader)
+}
+
 fn project_impl(window_pos: f32, rect_pos: f32, rect_size: f32, real_size: f32) -> f32 {
     (real_size * (window_pos - rect_pos)) / rect_size
 }
@@ -233,6 +337,13 @@ pub enum ScalingMode {
 
     /// Works the same as Crop, but will only scale by integer values.
     CropPixelPerfect,
+
+    /// Works the same as ShowAll, but the image is drawn using a 'sharp bilinear' shader.
+    ///
+    /// Each pixel of the screen is scaled up by the largest whole number that fits, and
+    /// only the remaining fraction is smoothed. This keeps pixel art crisp at any window
+    /// size, without the large borders that [`ScalingMode::ShowAllPixelPerfect`] can leave.
+    PixelPerfectSmooth,
 }
 
 /// Converts a screen's dimensions into a rectangle that is scaled to fit in the given bounds.
@@ -267,7 +378,7 @@ pub fn get_screen_rect(
             )
         }
         ScalingMode::Stretch => Rectangle::new(0.0, 0.0, outer_width as f32, outer_height as f32),
-        ScalingMode::ShowAll => {
+        ScalingMode::ShowAll | ScalingMode::PixelPerfectSmooth => {
             let scale_factor = if internal_aspect_ratio > screen_aspect_ratio {
                 f_outer_width / f_inner_width
             } else {
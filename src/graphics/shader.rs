@@ -1,15 +1,16 @@
 //! Functions and types relating to shader programs.
 
-use std::cell::{Cell, RefCell};
 use std::path::Path;
-use std::rc::Rc;
 use std::slice;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 
-use hashbrown::HashMap;
+use hashbrown::{HashMap, HashSet};
 
+use crate::debug::{ResourceKind, TrackedResource};
 use crate::error::Result;
 use crate::fs;
-use crate::graphics::{Color, Texture};
+use crate::graphics::{Color, Cubemap, Texture};
 use crate::math::{Mat2, Mat3, Mat4, Vec2, Vec3, Vec4};
 use crate::platform::{GraphicsDevice, RawShader};
 use crate::Context;
@@ -24,17 +25,52 @@ pub const DEFAULT_VERTEX_SHADER: &str = include_str!("../resources/shader.vert")
 /// The source code for this shader is available in [`src/resources/shader.vert`](https://github.com/17cupsofcoffee/tetra/blob/main/src/resources/shader.frag).
 pub const DEFAULT_FRAGMENT_SHADER: &str = include_str!("../resources/shader.frag");
 
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SamplerBinding {
+    Texture(Texture),
+    Cubemap(Cubemap),
+}
+
 #[derive(Debug)]
 pub(crate) struct Sampler {
-    pub(crate) texture: Texture,
+    pub(crate) binding: SamplerBinding,
     pub(crate) unit: u32,
 }
 
 #[derive(Debug)]
 pub(crate) struct ShaderSharedData {
     pub(crate) handle: RawShader,
-    pub(crate) samplers: RefCell<HashMap<String, Sampler>>,
-    pub(crate) next_unit: Cell<u32>,
+    pub(crate) samplers: Mutex<HashMap<String, Sampler>>,
+    pub(crate) next_unit: AtomicU32,
+    reflection: Mutex<Reflection>,
+    _tracker: TrackedResource,
+}
+
+#[derive(Debug, Default)]
+struct Reflection {
+    uniforms: Vec<UniformInfo>,
+    attributes: Vec<AttributeInfo>,
+    warned: HashSet<String>,
+}
+
+impl ShaderSharedData {
+    pub(crate) fn samplers(&self) -> MutexGuard<'_, HashMap<String, Sampler>> {
+        self.samplers.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn reflection(&self) -> MutexGuard<'_, Reflection> {
+        self.reflection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Re-queries the active uniforms and attributes. This needs to be called whenever
+    /// the program is (re)linked.
+    pub(crate) fn refresh_reflection(&self, device: &GraphicsDevice) {
+        let mut reflection = self.reflection();
+
+        reflection.uniforms = device.get_active_uniforms(&self.handle);
+        reflection.attributes = device.get_active_attributes(&self.handle);
+        reflection.warned.clear();
+    }
 }
 
 impl PartialEq for ShaderSharedData {
@@ -51,12 +87,18 @@ impl PartialEq for ShaderSharedData {
 ///
 /// ## Vertex Shaders
 ///
-/// Vertex shaders take in data via three attributes:
+/// Vertex shaders take in data via five attributes:
 ///
 /// * `a_position` - A `vec2` representing the position of the vertex in world space.
 /// * `a_uv` - A `vec2` representing the texture co-ordinates that are associated with the vertex.
 /// * `a_color` - A `vec4` representing the color of the vertex. This will be multiplied by
 ///   `u_diffuse` and the color sampled from `u_texture` (see 'Uniforms' below).
+/// * `a_overlay` - A `vec4` representing a flat color that the sampled texture color should be
+///   blended towards, with the amount of blending stored in the alpha channel (see
+///   [`DrawParams::overlay_color`](super::DrawParams::overlay_color)).
+/// * `a_params` - A `vec4` containing arbitrary values that were set for the draw (see
+///   [`DrawParams::shader_params`](super::DrawParams::shader_params)). The default vertex
+///   shader passes this through to the fragment shader as `v_params`.
 ///
 /// Position data should be output as a `vec4` to the built-in `gl_Position` variable.
 ///
@@ -87,7 +129,7 @@ impl PartialEq for ShaderSharedData {
 /// Creating a shader is quite an expensive operation, as it involves parsing and validating the GLSL code.
 /// Try to reuse shaders, rather than recreating them every frame.
 ///
-/// You can clone a shader cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/rc/struct.Rc.html)
+/// You can clone a shader cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/sync/struct.Arc.html)
 /// handle to a GPU resource. However, this does mean that modifying a shader (e.g.
 /// setting a uniform) will also affect any clones that exist of it.
 ///
@@ -98,7 +140,7 @@ impl PartialEq for ShaderSharedData {
 /// variables.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Shader {
-    pub(crate) data: Rc<ShaderSharedData>,
+    pub(crate) data: Arc<ShaderSharedData>,
 }
 
 impl Shader {
@@ -112,15 +154,26 @@ impl Shader {
     /// if the files could not be loaded.
     /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
     /// shader could not be compiled.
+    #[track_caller]
     pub fn new<P>(ctx: &mut Context, vertex_path: P, fragment_path: P) -> Result<Shader>
     where
         P: AsRef<Path>,
     {
-        Shader::with_device(
+        let shader = Shader::with_device(
             &mut ctx.device,
-            &fs::read_to_string(vertex_path)?,
-            &fs::read_to_string(fragment_path)?,
-        )
+            &fs::read_to_string(&vertex_path)?,
+            &fs::read_to_string(&fragment_path)?,
+        )?;
+
+        #[cfg(feature = "hot_reload")]
+        crate::hot_reload::watch_shader(
+            ctx,
+            Some(vertex_path.as_ref()),
+            Some(fragment_path.as_ref()),
+            &shader,
+        );
+
+        Ok(shader)
     }
 
     /// Creates a new shader program from the given vertex shader file.
@@ -135,15 +188,21 @@ impl Shader {
     /// if the file could not be loaded.
     /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
     /// shader could not be compiled.
+    #[track_caller]
     pub fn from_vertex_file<P>(ctx: &mut Context, path: P) -> Result<Shader>
     where
         P: AsRef<Path>,
     {
-        Shader::with_device(
+        let shader = Shader::with_device(
             &mut ctx.device,
-            &fs::read_to_string(path)?,
+            &fs::read_to_string(&path)?,
             DEFAULT_FRAGMENT_SHADER,
-        )
+        )?;
+
+        #[cfg(feature = "hot_reload")]
+        crate::hot_reload::watch_shader(ctx, Some(path.as_ref()), None, &shader);
+
+        Ok(shader)
     }
 
     /// Creates a new shader program from the given fragment shader file.
@@ -158,15 +217,21 @@ impl Shader {
     /// if the file could not be loaded.
     /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
     /// shader could not be compiled.
+    #[track_caller]
     pub fn from_fragment_file<P>(ctx: &mut Context, path: P) -> Result<Shader>
     where
         P: AsRef<Path>,
     {
-        Shader::with_device(
+        let shader = Shader::with_device(
             &mut ctx.device,
             DEFAULT_VERTEX_SHADER,
-            &fs::read_to_string(path)?,
-        )
+            &fs::read_to_string(&path)?,
+        )?;
+
+        #[cfg(feature = "hot_reload")]
+        crate::hot_reload::watch_shader(ctx, None, Some(path.as_ref()), &shader);
+
+        Ok(shader)
     }
 
     /// Creates a new shader program from the given strings.
@@ -177,6 +242,7 @@ impl Shader {
     /// underlying graphics API encounters an error.
     /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
     /// shader could not be compiled.
+    #[track_caller]
     pub fn from_string(
         ctx: &mut Context,
         vertex_shader: &str,
@@ -195,6 +261,7 @@ impl Shader {
     /// underlying graphics API encounters an error.
     /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
     /// shader could not be compiled.
+    #[track_caller]
     pub fn from_vertex_string<P>(ctx: &mut Context, shader: &str) -> Result<Shader> {
         Shader::with_device(&mut ctx.device, shader, DEFAULT_FRAGMENT_SHADER)
     }
@@ -209,10 +276,12 @@ impl Shader {
     /// underlying graphics API encounters an error.
     /// * [`TetraError::InvalidShader`](crate::TetraError::InvalidShader) will be returned if the
     /// shader could not be compiled.
+    #[track_caller]
     pub fn from_fragment_string<P>(ctx: &mut Context, shader: &str) -> Result<Shader> {
         Shader::with_device(&mut ctx.device, DEFAULT_VERTEX_SHADER, shader)
     }
 
+    #[track_caller]
     pub(crate) fn with_device(
         device: &mut GraphicsDevice,
         vertex_shader: &str,
@@ -220,23 +289,83 @@ impl Shader {
     ) -> Result<Shader> {
         let handle = device.new_shader(vertex_shader, fragment_shader)?;
 
+        let data = ShaderSharedData {
+            handle,
+            samplers: Mutex::new(HashMap::new()),
+            next_unit: AtomicU32::new(1),
+            reflection: Mutex::new(Reflection::default()),
+            _tracker: TrackedResource::new(ResourceKind::Shader, None),
+        };
+
+        data.refresh_reflection(device);
+
         Ok(Shader {
-            data: Rc::new(ShaderSharedData {
-                handle,
-                samplers: RefCell::new(HashMap::new()),
-                next_unit: Cell::new(1),
-            }),
+            data: Arc::new(data),
         })
     }
 
+    /// Returns the uniforms that are active in the shader program.
+    ///
+    /// Uniforms that are declared but never used may be optimized out by the graphics
+    /// driver, in which case they will not be included in this list.
+    pub fn uniforms(&self) -> Vec<UniformInfo> {
+        self.data.reflection().uniforms.clone()
+    }
+
+    /// Returns information about the active uniform with the given name, if it exists.
+    ///
+    /// For arrays, the name should not include an index (e.g. `u_lights`, rather than
+    /// `u_lights[0]`).
+    pub fn uniform(&self, name: &str) -> Option<UniformInfo> {
+        self.data
+            .reflection()
+            .uniforms
+            .iter()
+            .find(|u| u.name == name)
+            .cloned()
+    }
+
+    /// Returns the vertex attributes that are active in the shader program.
+    pub fn attributes(&self) -> Vec<AttributeInfo> {
+        self.data.reflection().attributes.clone()
+    }
+
     /// Sets the value of the specifed uniform parameter.
     ///
     /// See the [`UniformValue`] trait's docs for a list of which types can be used as a uniform,
     /// and what their corresponding GLSL types are.
+    ///
+    /// If the uniform is active in the shader and its type does not match the type of the
+    /// value, a warning will be logged (once per uniform) and the value will not be set.
     pub fn set_uniform<V>(&self, ctx: &mut Context, name: &str, value: V)
     where
         V: UniformValue,
     {
+        if let Some(expected) = value.data_type() {
+            let mut reflection = self.data.reflection();
+
+            let actual = reflection
+                .uniforms
+                .iter()
+                .find(|u| u.name == name)
+                .map(|u| u.data_type);
+
+            if let Some(actual) = actual {
+                if !actual.accepts(expected) {
+                    if reflection.warned.insert(name.to_owned()) {
+                        log::warn!(
+                            "uniform '{}' is declared as {:?} in the shader, but a value of type {:?} was provided",
+                            name,
+                            actual,
+                            expected
+                        );
+                    }
+
+                    return;
+                }
+            }
+        }
+
         value.set_uniform(ctx, self, name)
     }
 
@@ -246,10 +375,17 @@ impl Shader {
         projection: Mat4<f32>,
         diffuse: Color,
     ) -> Result {
-        let samplers = self.data.samplers.borrow();
+        let samplers = self.data.samplers();
 
         for sampler in samplers.values() {
-            device.attach_texture_to_sampler(&sampler.texture.data.handle, sampler.unit)?;
+            match &sampler.binding {
+                SamplerBinding::Texture(texture) => {
+                    device.attach_texture_to_sampler(&texture.data.handle, sampler.unit)?
+                }
+                SamplerBinding::Cubemap(cubemap) => {
+                    device.attach_cubemap_to_sampler(&cubemap.data.handle, sampler.unit)?
+                }
+            }
         }
 
         let projection_location = device.get_uniform_location(&self.data.handle, "u_projection");
@@ -272,6 +408,130 @@ impl Shader {
     }
 }
 
+/// Information about an active uniform in a [`Shader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct UniformInfo {
+    /// The name of the uniform.
+    ///
+    /// For arrays, this will not include an index (e.g. `u_lights`, rather than `u_lights[0]`).
+    pub name: String,
+
+    /// The GLSL type of the uniform.
+    pub data_type: ShaderDataType,
+
+    /// The number of elements in the uniform. This will be `1` unless the uniform is an array.
+    pub size: i32,
+}
+
+/// Information about an active vertex attribute in a [`Shader`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct AttributeInfo {
+    /// The name of the attribute.
+    pub name: String,
+
+    /// The GLSL type of the attribute.
+    pub data_type: ShaderDataType,
+
+    /// The number of elements in the attribute. This will be `1` unless the attribute is an array.
+    pub size: i32,
+
+    /// The location that the attribute is bound to.
+    pub location: Option<u32>,
+}
+
+/// The GLSL type of a uniform or vertex attribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ShaderDataType {
+    /// A `float`.
+    Float,
+
+    /// A `vec2`.
+    Vec2,
+
+    /// A `vec3`.
+    Vec3,
+
+    /// A `vec4`.
+    Vec4,
+
+    /// An `int`.
+    Int,
+
+    /// An `ivec2`.
+    IVec2,
+
+    /// An `ivec3`.
+    IVec3,
+
+    /// An `ivec4`.
+    IVec4,
+
+    /// A `uint`.
+    UInt,
+
+    /// A `uvec2`.
+    UVec2,
+
+    /// A `uvec3`.
+    UVec3,
+
+    /// A `uvec4`.
+    UVec4,
+
+    /// A `bool`.
+    Bool,
+
+    /// A `bvec2`.
+    BVec2,
+
+    /// A `bvec3`.
+    BVec3,
+
+    /// A `bvec4`.
+    BVec4,
+
+    /// A `mat2`.
+    Mat2,
+
+    /// A `mat3`.
+    Mat3,
+
+    /// A `mat4`.
+    Mat4,
+
+    /// A `sampler2D`.
+    Sampler2D,
+
+    /// A `samplerCube`.
+    SamplerCube,
+
+    /// A type that Tetra does not currently recognize.
+    Other,
+}
+
+impl ShaderDataType {
+    /// Returns whether a value of the `provided` type can be used to set a uniform of this type.
+    ///
+    /// As in OpenGL, booleans can be set via any scalar/vector type with the same number of
+    /// components, and samplers can be set via an `int`.
+    fn accepts(self, provided: ShaderDataType) -> bool {
+        use ShaderDataType::*;
+
+        match (self, provided) {
+            (Other, _) => true,
+            (Bool, Float | Int | UInt) => true,
+            (BVec2, Vec2 | IVec2 | UVec2) => true,
+            (BVec3, Vec3 | IVec3 | UVec3) => true,
+            (BVec4, Vec4 | IVec4 | UVec4) => true,
+            (Sampler2D | SamplerCube, Int) => true,
+            (a, b) => a == b,
+        }
+    }
+}
+
 /// Implemented for types that can be passed as a uniform value to a shader.
 ///
 /// As the implementation of this trait currently interacts directly with the platform layer,
@@ -279,10 +539,15 @@ impl Shader {
 pub trait UniformValue {
     #[doc(hidden)]
     fn set_uniform(&self, ctx: &mut Context, shader: &Shader, name: &str);
+
+    #[doc(hidden)]
+    fn data_type(&self) -> Option<ShaderDataType> {
+        None
+    }
 }
 
 macro_rules! simple_uniforms {
-    ($($t:ty => $f:ident, $doc:expr, $arraydoc:expr),* $(,)?) => {
+    ($($t:ty => $f:ident, $dt:ident, $doc:expr, $arraydoc:expr),* $(,)?) => {
         $(
             #[doc = $doc]
             impl UniformValue for $t {
@@ -296,6 +561,11 @@ macro_rules! simple_uniforms {
                     let location = ctx.device.get_uniform_location(&shader.data.handle, name);
                     ctx.device.$f(&shader.data.handle, location.as_ref(), slice::from_ref(self));
                 }
+
+                #[doc(hidden)]
+                fn data_type(&self) -> Option<ShaderDataType> {
+                    Some(ShaderDataType::$dt)
+                }
             }
 
             #[doc = $arraydoc]
@@ -310,6 +580,11 @@ macro_rules! simple_uniforms {
                     let location = ctx.device.get_uniform_location(&shader.data.handle, name);
                     ctx.device.$f(&shader.data.handle, location.as_ref(), self);
                 }
+
+                #[doc(hidden)]
+                fn data_type(&self) -> Option<ShaderDataType> {
+                    Some(ShaderDataType::$dt)
+                }
             }
 
             #[doc = $arraydoc]
@@ -324,57 +599,87 @@ macro_rules! simple_uniforms {
                     let location = ctx.device.get_uniform_location(&shader.data.handle, name);
                     ctx.device.$f(&shader.data.handle, location.as_ref(), self);
                 }
+
+                #[doc(hidden)]
+                fn data_type(&self) -> Option<ShaderDataType> {
+                    Some(ShaderDataType::$dt)
+                }
             }
         )*
     };
 }
 
 simple_uniforms! {
-    i32 => set_uniform_i32, "Can be accessed as an `int` in your shader.", "Can be accessed as an array of `int`s in your shader.",
-    u32 => set_uniform_u32, "Can be accessed as a `uint` in your shader.", "Can be accessed as an array of `uint`s in your shader.",
-    f32 => set_uniform_f32, "Can be accessed as a `float` in your shader.", "Can be accessed as an array of `float`s in your shader.",
-    Vec2<f32> => set_uniform_vec2, "Can be accessed as a `vec2` in your shader.", "Can be accessed as an array of `vec2`s in your shader.",
-    Vec3<f32> => set_uniform_vec3, "Can be accessed as a `vec3` in your shader.", "Can be accessed as an array of `vec3`s in your shader.",
-    Vec4<f32> => set_uniform_vec4, "Can be accessed as a `vec4` in your shader.", "Can be accessed as an array of `vec4`s in your shader.",
-    Mat2<f32> => set_uniform_mat2, "Can be accessed as a `mat2` in your shader.", "Can be accessed as an array of `mat2`s in your shader.",
-    Mat3<f32> => set_uniform_mat3, "Can be accessed as a `mat3` in your shader.", "Can be accessed as an array of `mat3`s in your shader.",
-    Mat4<f32> => set_uniform_mat4, "Can be accessed as a `mat4` in your shader.", "Can be accessed as an array of `mat4`s in your shader.",
-    Color => set_uniform_color, "Can be accessed as a `vec4` in your shader.", "Can be accessed as an array of `vec4`s in your shader.",
+    i32 => set_uniform_i32, Int, "Can be accessed as an `int` in your shader.", "Can be accessed as an array of `int`s in your shader.",
+    u32 => set_uniform_u32, UInt, "Can be accessed as a `uint` in your shader.", "Can be accessed as an array of `uint`s in your shader.",
+    f32 => set_uniform_f32, Float, "Can be accessed as a `float` in your shader.", "Can be accessed as an array of `float`s in your shader.",
+    Vec2<f32> => set_uniform_vec2, Vec2, "Can be accessed as a `vec2` in your shader.", "Can be accessed as an array of `vec2`s in your shader.",
+    Vec3<f32> => set_uniform_vec3, Vec3, "Can be accessed as a `vec3` in your shader.", "Can be accessed as an array of `vec3`s in your shader.",
+    Vec4<f32> => set_uniform_vec4, Vec4, "Can be accessed as a `vec4` in your shader.", "Can be accessed as an array of `vec4`s in your shader.",
+    Mat2<f32> => set_uniform_mat2, Mat2, "Can be accessed as a `mat2` in your shader.", "Can be accessed as an array of `mat2`s in your shader.",
+    Mat3<f32> => set_uniform_mat3, Mat3, "Can be accessed as a `mat3` in your shader.", "Can be accessed as an array of `mat3`s in your shader.",
+    Mat4<f32> => set_uniform_mat4, Mat4, "Can be accessed as a `mat4` in your shader.", "Can be accessed as an array of `mat4`s in your shader.",
+    Color => set_uniform_color, Vec4, "Can be accessed as a `vec4` in your shader.", "Can be accessed as an array of `vec4`s in your shader.",
+}
+
+fn set_sampler(ctx: &mut Context, shader: &Shader, name: &str, binding: SamplerBinding) {
+    let mut samplers = shader.data.samplers();
+
+    if let Some(sampler) = samplers.get_mut(name) {
+        if sampler.binding != binding {
+            sampler.binding = binding;
+        }
+    } else {
+        let next_unit = shader.data.next_unit.load(Ordering::Relaxed);
+
+        samplers.insert(
+            name.to_owned(),
+            Sampler {
+                binding,
+                unit: next_unit,
+            },
+        );
+
+        // Sampler uniforms have to be set via glUniform1i
+        (next_unit as i32).set_uniform(ctx, shader, name);
+
+        shader
+            .data
+            .next_unit
+            .store(next_unit + 1, Ordering::Relaxed);
+    }
 }
 
 /// Can be accessed via a `sampler2D` in your shader.
 impl UniformValue for Texture {
     #[doc(hidden)]
     fn set_uniform(&self, ctx: &mut Context, shader: &Shader, name: &str) {
-        let mut samplers = shader.data.samplers.borrow_mut();
-
-        if let Some(sampler) = samplers.get_mut(name) {
-            if sampler.texture != *self {
-                sampler.texture = self.clone();
-            }
-        } else {
-            let next_unit = shader.data.next_unit.get();
+        set_sampler(ctx, shader, name, SamplerBinding::Texture(self.clone()));
+    }
 
-            samplers.insert(
-                name.to_owned(),
-                Sampler {
-                    texture: self.clone(),
-                    unit: next_unit,
-                },
-            );
+    #[doc(hidden)]
+    fn data_type(&self) -> Option<ShaderDataType> {
+        Some(ShaderDataType::Sampler2D)
+    }
+}
 
-            // Sampler uniforms have to be set via glUniform1i
-            (next_unit as i32).set_uniform(ctx, shader, name);
+/// Can be accessed via a `samplerCube` in your shader.
+impl UniformValue for Cubemap {
+    #[doc(hidden)]
+    fn set_uniform(&self, ctx: &mut Context, shader: &Shader, name: &str) {
+        set_sampler(ctx, shader, name, SamplerBinding::Cubemap(self.clone()));
+    }
 
-            shader.data.next_unit.set(next_unit + 1);
-        }
+    #[doc(hidden)]
+    fn data_type(&self) -> Option<ShaderDataType> {
+        Some(ShaderDataType::SamplerCube)
     }
 }
 
 /// Any type that can be passed by value to a shader can also be passed by reference.
 impl<'a, T> UniformValue for &'a T
 where
-    T: UniformValue,
+    T: UniformValue + ?Sized,
 {
     #[doc(hidden)]
     fn set_uniform(&self, ctx: &mut Context, shader: &Shader, name: &str) {
@@ -383,4 +688,9 @@ where
             inner.set_uniform(ctx, shader, name);
         }
     }
+
+    #[doc(hidden)]
+    fn data_type(&self) -> Option<ShaderDataType> {
+        (*self).data_type()
+    }
 }
@@ -0,0 +1,120 @@
+/// A handle to a named sorting layer.
+///
+/// Sorting layers split up sorted draws into groups, which are always drawn in a fixed
+/// order - for example, a game might have separate layers for the background, the
+/// characters and the UI. This means that the draw depths used within each layer don't
+/// have to be co-ordinated with each other.
+///
+/// The order of the layers is defined via [`set_sorting_layers`](super::set_sorting_layers),
+/// and handles can be looked up by name via [`find_sorting_layer`](super::find_sorting_layer).
+/// Layers only have an effect while [sorting is enabled](super::begin_sorted).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SortingLayer(u32);
+
+impl SortingLayer {
+    /// The layer that draws are placed on by default, which is named `"Default"`.
+    ///
+    /// This layer always exists, even if it is not included when the layers are set.
+    pub const DEFAULT: SortingLayer = SortingLayer(0);
+
+    const DEFAULT_NAME: &'static str = "Default";
+}
+
+/// The ordered list of sorting layers that have been defined.
+#[derive(Debug, Clone)]
+pub(crate) struct SortingLayers {
+    layers: Vec<(SortingLayer, String)>,
+    next_id: u32,
+}
+
+impl SortingLayers {
+    pub(crate) fn new() -> SortingLayers {
+        SortingLayers {
+            layers: vec![(SortingLayer::DEFAULT, SortingLayer::DEFAULT_NAME.to_owned())],
+            next_id: 1,
+        }
+    }
+
+    /// Replaces the list of layers, keeping the handles of any layers whose names
+    /// were already in use.
+    pub(crate) fn set(&mut self, names: &[&str]) {
+        let mut layers = Vec::with_capacity(names.len() + 1);
+
+        for name in names {
+            if layers.iter().any(|(_, n)| n == name) {
+                continue;
+            }
+
+            let layer = match self.find(name) {
+                Some(layer) => layer,
+                None => {
+                    let layer = SortingLayer(self.next_id);
+                    self.next_id += 1;
+                    layer
+                }
+            };
+
+            layers.push((layer, (*name).to_owned()));
+        }
+
+        if !layers.iter().any(|(l, _)| *l == SortingLayer::DEFAULT) {
+            layers.insert(
+                0,
+                (SortingLayer::DEFAULT, SortingLayer::DEFAULT_NAME.to_owned()),
+            );
+        }
+
+        self.layers = layers;
+    }
+
+    pub(crate) fn find(&self, name: &str) -> Option<SortingLayer> {
+        self.layers
+            .iter()
+            .find(|(_, n)| n == name)
+            .map(|(layer, _)| *layer)
+    }
+
+    pub(crate) fn name(&self, layer: SortingLayer) -> Option<&str> {
+        self.layers
+            .iter()
+            .find(|(l, _)| *l == layer)
+            .map(|(_, name)| name.as_str())
+    }
+
+    /// Returns the position of the layer in the draw order. Layers that no longer
+    /// exist are treated as if they were the default layer.
+    pub(crate) fn rank(&self, layer: SortingLayer) -> usize {
+        let position = |layer| self.layers.iter().position(|(l, _)| *l == layer);
+
+        position(layer)
+            .or_else(|| position(SortingLayer::DEFAULT))
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_order() {
+        let mut layers = SortingLayers::new();
+        layers.set(&["Background", "Characters", "UI"]);
+
+        let background = layers.find("Background").unwrap();
+        let ui = layers.find("UI").unwrap();
+
+        assert_eq!(layers.rank(SortingLayer::DEFAULT), 0);
+        assert_eq!(layers.rank(background), 1);
+        assert_eq!(layers.rank(ui), 3);
+
+        // Handles are kept when the layers are reordered.
+        layers.set(&["UI", "Default", "Background"]);
+
+        assert_eq!(layers.find("UI"), Some(ui));
+        assert_eq!(layers.rank(ui), 0);
+        assert_eq!(layers.rank(SortingLayer::DEFAULT), 1);
+        assert_eq!(layers.find("Characters"), None);
+        assert_eq!(layers.name(background), Some("Background"));
+    }
+}
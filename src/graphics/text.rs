@@ -2,24 +2,33 @@
 
 mod bmfont;
 mod cache;
+mod cached;
+mod dynamic;
 mod packer;
 #[cfg(feature = "font_ttf")]
 mod vector;
 
 use std::cell::RefCell;
-use std::fmt::{self, Debug, Formatter};
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::Range;
 use std::path::Path;
 use std::rc::Rc;
 
 use crate::error::Result;
-use crate::graphics::text::cache::{FontCache, TextGeometry};
+use crate::graphics::text::cache::{TextGeometry, TextLimits};
 use crate::graphics::{self, DrawParams, Rectangle};
+use crate::i18n::{self, Arg};
+use crate::math::Vec2;
 use crate::Context;
 
+pub(crate) use crate::graphics::text::cache::FontCache;
+
 #[cfg(feature = "font_ttf")]
 pub use crate::graphics::text::vector::VectorFontBuilder;
 
 pub use crate::graphics::text::bmfont::BmFontBuilder;
+pub use crate::graphics::text::cached::CachedText;
+pub use crate::graphics::text::dynamic::DynamicText;
 
 use super::FilterMode;
 
@@ -41,7 +50,7 @@ use super::FilterMode;
 /// example demonstrates how to load a font and then draw some text.
 #[derive(Clone)]
 pub struct Font {
-    data: Rc<RefCell<FontCache>>,
+    pub(crate) data: Rc<RefCell<FontCache>>,
 }
 
 impl Font {
@@ -65,7 +74,13 @@ impl Font {
     where
         P: AsRef<Path>,
     {
-        VectorFontBuilder::new(path)?.with_size(ctx, size)
+        let path = path.as_ref();
+        let font = VectorFontBuilder::new(path)?.with_size(ctx, size)?;
+
+        #[cfg(feature = "hot_reload")]
+        crate::hot_reload::watch_font(ctx, path, size, &font);
+
+        Ok(font)
     }
 
     /// Creates a `Font` from a slice of binary data.
@@ -171,7 +186,42 @@ pub struct Text {
     content: String,
     font: Font,
     max_width: Option<f32>,
+    max_height: Option<f32>,
+    overflow: TextOverflow,
     geometry: Option<TextGeometry>,
+    localization: Option<Localization>,
+}
+
+#[derive(Debug, Clone)]
+struct Localization {
+    key: String,
+    args: Vec<(String, String)>,
+    font: Font,
+    generation: Option<u64>,
+}
+
+/// How text should be cut off when it does not fit within its maximum size.
+///
+/// Text is laid out line by line, wrapping at the [maximum width](Text::set_max_width)
+/// if there is one. Once the [maximum height](Text::set_max_height) is reached, the
+/// remaining text will be cut off.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextOverflow {
+    /// The lines that do not fit are removed.
+    ///
+    /// This is the default.
+    Clip,
+
+    /// The lines that do not fit are removed, and the last line is filled up as far as
+    /// possible, followed by an ellipsis ("…").
+    ///
+    /// This can also be used with a single line of text (by setting the maximum height to
+    /// the height of one line), in order to truncate a long name to fit within a fixed
+    /// width.
+    ///
+    /// If the font does not have a glyph for the ellipsis character, three full stops
+    /// will be used instead.
+    Ellipsis,
 }
 
 impl Text {
@@ -184,7 +234,10 @@ impl Text {
             content: content.into(),
             font,
             max_width: None,
+            max_height: None,
+            overflow: TextOverflow::Clip,
             geometry: None,
+            localization: None,
         }
     }
 
@@ -202,7 +255,54 @@ impl Text {
             content: content.into(),
             font,
             max_width: Some(max_width),
+            max_height: None,
+            overflow: TextOverflow::Clip,
+            geometry: None,
+            localization: None,
+        }
+    }
+
+    /// Creates a new `Text`, with its content set to the translation of the given key.
+    ///
+    /// The text will automatically update when the current language changes. If a font has
+    /// been set for the current language via [`i18n::set_language_font`], it will be used
+    /// instead of the font passed in here.
+    ///
+    /// The translation is looked up the next time the text is rendered or measured, so
+    /// [`content`](Text::content) will return the key until then.
+    pub fn localized<K>(key: K, font: Font) -> Text
+    where
+        K: Into<String>,
+    {
+        Text::localized_with_args(key, &[], font)
+    }
+
+    /// Creates a new `Text`, with its content set to the translation of the given key,
+    /// with the given arguments substituted in.
+    ///
+    /// See [`Text::localized`] for more details.
+    pub fn localized_with_args<K>(key: K, args: &[Arg<'_>], font: Font) -> Text
+    where
+        K: Into<String>,
+    {
+        let key = key.into();
+
+        Text {
+            content: key.clone(),
+            font: font.clone(),
+            max_width: None,
+            max_height: None,
+            overflow: TextOverflow::Clip,
             geometry: None,
+            localization: Some(Localization {
+                key,
+                args: args
+                    .iter()
+                    .map(|(name, value)| (name.to_string(), value.to_string()))
+                    .collect(),
+                font,
+                generation: None,
+            }),
         }
     }
 
@@ -213,7 +313,7 @@ impl Text {
     {
         self.update_geometry(ctx);
 
-        let params = params.into();
+        let mut params = params.into();
 
         let data = self.font.data.borrow();
         let texture = data.texture();
@@ -222,6 +322,17 @@ impl Text {
             .as_ref()
             .expect("geometry should have been generated");
 
+        // The text's bounds don't necessarily start at (0, 0), so the normalized
+        // origin has to be offset as well as scaled.
+        if let Some(bounds) = geometry.bounds {
+            if let Some(normalized) = params.origin_normalized.take() {
+                params.origin = Vec2::new(
+                    bounds.x + normalized.x * bounds.width,
+                    bounds.y + normalized.y * bounds.height,
+                );
+            }
+        }
+
         graphics::set_texture(ctx, texture);
         let (texture_width, texture_height) = texture.size();
 
@@ -248,6 +359,9 @@ impl Text {
 
     /// Sets the content of the text.
     ///
+    /// If the text was [localized](Text::localized), it will stop updating when the
+    /// language changes.
+    ///
     /// Calling this function will cause a re-layout of the text the next time it
     /// is rendered.
     pub fn set_content<C>(&mut self, content: C)
@@ -255,9 +369,15 @@ impl Text {
         C: Into<String>,
     {
         self.geometry.take();
+        self.localization.take();
         self.content = content.into();
     }
 
+    /// Returns the translation key of the text, if it was [localized](Text::localized).
+    pub fn localization_key(&self) -> Option<&str> {
+        self.localization.as_ref().map(|l| l.key.as_str())
+    }
+
     /// Gets the font of the text.
     pub fn font(&self) -> &Font {
         &self.font
@@ -269,6 +389,13 @@ impl Text {
     /// is rendered.
     pub fn set_font(&mut self, font: Font) {
         self.geometry.take();
+
+        if let Some(localization) = &mut self.localization {
+            // The language's font (if there is one) should still take priority.
+            localization.font = font.clone();
+            localization.generation = None;
+        }
+
         self.font = font;
     }
 
@@ -297,12 +424,61 @@ impl Text {
         self.max_width = max_width;
     }
 
+    /// Gets the maximum height of the text, if one is set.
+    pub fn max_height(&self) -> Option<f32> {
+        self.max_height
+    }
+
+    /// Sets the maximum height of the text.
+    ///
+    /// If `Some` is passed, any lines that would not fit within the height will be cut
+    /// off, as configured by [`set_overflow`](Text::set_overflow). At least one line
+    /// will always be shown, even if it does not fit. If `None` is passed, the text
+    /// can have any number of lines.
+    ///
+    /// Calling this function will cause a re-layout of the text the next time it
+    /// is rendered.
+    pub fn set_max_height(&mut self, max_height: Option<f32>) {
+        self.geometry.take();
+        self.max_height = max_height;
+    }
+
+    /// Gets how the text will be cut off if it does not fit within its maximum size.
+    pub fn overflow(&self) -> TextOverflow {
+        self.overflow
+    }
+
+    /// Sets how the text will be cut off if it does not fit within its maximum size.
+    ///
+    /// Defaults to [`TextOverflow::Clip`].
+    ///
+    /// Calling this function will cause a re-layout of the text the next time it
+    /// is rendered.
+    pub fn set_overflow(&mut self, overflow: TextOverflow) {
+        self.geometry.take();
+        self.overflow = overflow;
+    }
+
+    /// Returns `true` if the text was cut off because it did not fit within its
+    /// maximum size.
+    ///
+    /// If the text's layout needs calculating, this method will do so.
+    pub fn is_truncated(&mut self, ctx: &mut Context) -> bool {
+        self.update_geometry(ctx);
+
+        self.geometry
+            .as_ref()
+            .expect("geometry should have been generated")
+            .truncated
+    }
+
     /// Appends the given character to the end of the text.
     ///
     /// Calling this function will cause a re-layout of the text the next time it
     /// is rendered.
     pub fn push(&mut self, ch: char) {
         self.geometry.take();
+        self.localization.take();
         self.content.push(ch);
     }
 
@@ -312,6 +488,7 @@ impl Text {
     /// is rendered.
     pub fn push_str(&mut self, string: &str) {
         self.geometry.take();
+        self.localization.take();
         self.content.push_str(string);
     }
 
@@ -323,6 +500,7 @@ impl Text {
     /// is rendered.
     pub fn pop(&mut self) -> Option<char> {
         self.geometry.take();
+        self.localization.take();
         self.content.pop()
     }
 
@@ -340,7 +518,101 @@ impl Text {
             .bounds
     }
 
-    fn update_geometry(&mut self, ctx: &mut Context) {
+    /// Returns the height of each line of the text.
+    ///
+    /// This is also the height of the carets returned by [`get_caret_position`](Self::get_caret_position)
+    /// and the selection rectangles returned by [`get_selection_rects`](Self::get_selection_rects).
+    pub fn get_line_height(&mut self, ctx: &mut Context) -> f32 {
+        self.geometry(ctx).line_height
+    }
+
+    /// Returns the position of the top of a caret placed before the character at the given
+    /// byte offset into the [content](Self::content).
+    ///
+    /// If the offset is not on a character boundary, the caret will be placed before the
+    /// character that contains it. If the offset is past the end of the text (or past the
+    /// point where the text was cut off by its [maximum height](Self::set_max_height)), the
+    /// caret will be placed at the end.
+    ///
+    /// Like [`get_bounds`](Self::get_bounds), this does not take into account the positioning
+    /// applied to the text via [`DrawParams`].
+    pub fn get_caret_position(&mut self, ctx: &mut Context, index: usize) -> Vec2<f32> {
+        self.geometry(ctx).caret_position(index)
+    }
+
+    /// Returns the byte offset into the [content](Self::content) of the caret position that
+    /// is closest to the given point.
+    ///
+    /// This can be used to find where the caret should be placed when the player clicks on
+    /// the text. The point should be relative to the text's origin - if you are drawing the
+    /// text with a transform, you will need to apply the inverse of it to the mouse position
+    /// first.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use tetra::Context;
+    /// # use tetra::graphics::text::Text;
+    /// # use tetra::input;
+    /// # use tetra::math::Vec2;
+    /// # fn example(ctx: &mut Context, text: &mut Text) {
+    /// let text_position = Vec2::new(32.0, 32.0);
+    ///
+    /// let index = text.hit_test(ctx, input::get_mouse_position(ctx) - text_position);
+    /// let caret = text.get_caret_position(ctx, index) + text_position;
+    /// # }
+    /// ```
+    pub fn hit_test(&mut self, ctx: &mut Context, point: Vec2<f32>) -> usize {
+        self.geometry(ctx).hit_test(point)
+    }
+
+    /// Returns the rectangles that should be highlighted to show that the given range of
+    /// the [content](Self::content) is selected.
+    ///
+    /// The range is measured in bytes. One rectangle will be returned for each line that the
+    /// selection covers.
+    ///
+    /// Like [`get_bounds`](Self::get_bounds), this does not take into account the positioning
+    /// applied to the text via [`DrawParams`].
+    pub fn get_selection_rects(
+        &mut self,
+        ctx: &mut Context,
+        range: Range<usize>,
+    ) -> Vec<Rectangle> {
+        self.geometry(ctx).selection_rects(range.start, range.end)
+    }
+
+    fn geometry(&mut self, ctx: &mut Context) -> &TextGeometry {
+        self.update_geometry(ctx);
+
+        self.geometry
+            .as_ref()
+            .expect("geometry should have been generated")
+    }
+
+    fn update_localization(&mut self, ctx: &Context) {
+        let localization = match &mut self.localization {
+            Some(l) if l.generation != Some(ctx.i18n.generation()) => l,
+            _ => return,
+        };
+
+        let args: Vec<Arg<'_>> = localization
+            .args
+            .iter()
+            .map(|(name, value)| (name.as_str(), value as &dyn Display))
+            .collect();
+
+        self.content = i18n::translate_with_args(ctx, &localization.key, &args);
+        self.font = ctx.i18n.font().unwrap_or(&localization.font).clone();
+
+        localization.generation = Some(ctx.i18n.generation());
+        self.geometry.take();
+    }
+
+    /// Returns whether the geometry had to be regenerated.
+    fn update_geometry(&mut self, ctx: &mut Context) -> bool {
+        self.update_localization(ctx);
+
         let mut data = self.font.data.borrow_mut();
 
         let needs_render = match &self.geometry {
@@ -349,8 +621,16 @@ impl Text {
         };
 
         if needs_render {
-            let new_geometry = data.render(&mut ctx.device, &self.content, self.max_width);
+            let limits = TextLimits {
+                max_width: self.max_width,
+                max_height: self.max_height,
+                overflow: self.overflow,
+            };
+
+            let new_geometry = data.render(&mut ctx.device, &self.content, &limits);
             self.geometry = Some(new_geometry);
         }
+
+        needs_render
     }
 }
@@ -3,6 +3,7 @@ use hashbrown::HashMap;
 use xi_unicode::LineBreakIterator;
 
 use crate::graphics::text::packer::ShelfPacker;
+use crate::graphics::text::TextOverflow;
 use crate::graphics::{FilterMode, Rectangle, Texture};
 use crate::math::Vec2;
 use crate::platform::GraphicsDevice;
@@ -90,6 +91,128 @@ pub(crate) struct TextGeometry {
     pub quads: Vec<TextQuad>,
     pub bounds: Option<Rectangle>,
     pub resize_count: usize,
+    pub truncated: bool,
+    pub chars: Vec<CharLayout>,
+    pub line_height: f32,
+}
+
+/// The position of a single character within a `TextGeometry`, used for placing carets
+/// and hit testing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct CharLayout {
+    /// The character.
+    pub ch: char,
+
+    /// The byte offset of the character in the text's content.
+    pub index: usize,
+
+    /// The line that the character is on.
+    pub line: usize,
+
+    /// The position of the left edge of the character.
+    pub x: f32,
+
+    /// The horizontal advance of the character.
+    pub width: f32,
+}
+
+impl TextGeometry {
+    /// Returns the top of the caret that would be placed before the character at the given
+    /// byte offset.
+    ///
+    /// Offsets past the end of the laid out characters are clamped to the end.
+    pub fn caret_position(&self, index: usize) -> Vec2<f32> {
+        let top = |line: usize| line as f32 * self.line_height;
+
+        match self
+            .chars
+            .iter()
+            .find(|c| c.index + c.ch.len_utf8() > index)
+        {
+            Some(c) => Vec2::new(c.x, top(c.line)),
+            None => match self.chars.last() {
+                Some(c) if is_line_break(c.ch) => Vec2::new(0.0, top(c.line + 1)),
+                Some(c) => Vec2::new(c.x + c.width, top(c.line)),
+                None => Vec2::zero(),
+            },
+        }
+    }
+
+    /// Returns the byte offset of the caret position closest to the given point.
+    pub fn hit_test(&self, point: Vec2<f32>) -> usize {
+        let last = match self.chars.last() {
+            Some(last) => last,
+            None => return 0,
+        };
+
+        let line = if point.y < 0.0 {
+            0
+        } else {
+            ((point.y / self.line_height).floor() as usize).min(last.line)
+        };
+
+        let mut on_line = self.chars.iter().filter(|c| c.line == line).peekable();
+
+        let mut result = None;
+
+        while let Some(c) = on_line.next() {
+            let is_last = on_line.peek().is_none();
+
+            if point.x < c.x + c.width / 2.0 || (is_last && is_line_break(c.ch)) {
+                return c.index;
+            }
+
+            result = Some(c.index + c.ch.len_utf8());
+        }
+
+        result.unwrap_or(last.index + last.ch.len_utf8())
+    }
+
+    /// Returns one rectangle for each line that is covered by the given range of byte offsets.
+    pub fn selection_rects(&self, start: usize, end: usize) -> Vec<Rectangle> {
+        let mut rects: Vec<(usize, Rectangle)> = Vec::new();
+
+        for c in self
+            .chars
+            .iter()
+            .filter(|c| c.index >= start && c.index < end)
+        {
+            let rect = Rectangle::new(
+                c.x,
+                c.line as f32 * self.line_height,
+                c.width,
+                self.line_height,
+            );
+
+            match rects.last_mut() {
+                Some((line, existing)) if *line == c.line => *existing = existing.combine(&rect),
+                _ => rects.push((c.line, rect)),
+            }
+        }
+
+        rects.into_iter().map(|(_, rect)| rect).collect()
+    }
+}
+
+/// The constraints that text should be laid out within.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TextLimits {
+    pub max_width: Option<f32>,
+    pub max_height: Option<f32>,
+    pub overflow: TextOverflow,
+}
+
+/// The layout of a single character, used for text that is laid out incrementally.
+#[derive(Debug, Clone)]
+pub(crate) struct GlyphLayout {
+    /// The character that was laid out.
+    pub ch: char,
+
+    /// The quad for the character, if it has a visible glyph.
+    pub quad: Option<TextQuad>,
+
+    /// The position of the cursor after the character.
+    pub cursor: Vec2<f32>,
 }
 
 /// Renders text using a generated texture atlas.
@@ -115,6 +238,17 @@ impl FontCache {
         })
     }
 
+    /// Replaces the contents of this cache with another cache, while making sure that
+    /// any geometry generated from the old cache is treated as stale.
+    #[cfg_attr(
+        not(all(feature = "hot_reload", feature = "font_ttf")),
+        allow(dead_code)
+    )]
+    pub fn replace(&mut self, mut other: FontCache) {
+        other.resize_count = self.resize_count + 1;
+        *self = other;
+    }
+
     /// Returns the current texture atlas.
     pub fn texture(&self) -> &Texture {
         self.packer.texture()
@@ -141,10 +275,10 @@ impl FontCache {
         &mut self,
         device: &mut GraphicsDevice,
         input: &str,
-        max_width: Option<f32>,
+        limits: &TextLimits,
     ) -> TextGeometry {
         loop {
-            match self.try_render(device, input, max_width) {
+            match self.try_render(device, input, limits) {
                 Ok(new_geometry) => return new_geometry,
                 Err(CacheError::OutOfSpace) => {
                     self.resize(device).expect("Failed to resize font texture");
@@ -159,73 +293,263 @@ impl FontCache {
         &mut self,
         device: &mut GraphicsDevice,
         input: &str,
-        max_width: Option<f32>,
+        limits: &TextLimits,
     ) -> std::result::Result<TextGeometry, CacheError> {
         let line_height = self.rasterizer.line_height().round();
+        let max_lines = limits
+            .max_height
+            .map(|h| ((h / line_height).floor() as usize).max(1));
+        let use_ellipsis = limits.overflow == TextOverflow::Ellipsis;
 
         let mut quads = Vec::new();
+        let mut chars = Vec::new();
 
         let mut cursor = Vec2::new(0.0, self.rasterizer.ascent().round());
         let mut last_glyph: Option<char> = None;
-        let mut text_bounds: Option<Rectangle> = None;
         let mut words_on_line = 0;
+        let mut line = 0;
+        let mut truncated = false;
+
+        // The cursor position, quad count and char count before each character on the
+        // current line, so that characters can be removed to make room for an ellipsis.
+        let mut line_chars: Vec<(f32, usize, usize, char)> = Vec::new();
+
+        let is_last_line = |line: usize| matches!(max_lines, Some(m) if line + 1 >= m);
+
+        macro_rules! new_line {
+            () => {
+                cursor.x = 0.0;
+                cursor.y += line_height;
+                last_glyph = None;
+                words_on_line = 0;
+                line += 1;
+                line_chars.clear();
+            };
+        }
 
-        for (word, _) in UnicodeLineBreaks::new(input) {
-            if let Some(max_width) = max_width {
+        'words: for (word, hard_break, end) in UnicodeLineBreaks::new(input) {
+            if let Some(max_width) = limits.max_width {
                 // We only allow wrapping to take place after the first word on each line,
                 // to avoid extra line breaks appearing when a word is too long to fit on
                 // a single line.
                 if words_on_line > 0 && cursor.x + self.measure_word(word) > max_width {
-                    cursor.x = 0.0;
-                    cursor.y += line_height;
-                    last_glyph = None;
-                    words_on_line = 0;
+                    if !is_last_line(line) {
+                        new_line!();
+                    } else if !use_ellipsis {
+                        truncated = true;
+                        break;
+                    }
+
+                    // If an ellipsis is being used, the last line gets filled up as far as
+                    // possible instead.
                 }
             }
 
             words_on_line += 1;
 
-            for ch in word.chars() {
-                if ch.is_control() {
-                    if ch == '\n' {
-                        cursor.x = 0.0;
-                        cursor.y += line_height;
-                        last_glyph = None;
-                        words_on_line = 0;
-                    }
+            let start = end - word.len();
+
+            for (offset, ch) in word.char_indices() {
+                if is_line_break(ch) || is_skipped_control(ch) {
+                    chars.push(CharLayout {
+                        ch,
+                        index: start + offset,
+                        line,
+                        x: cursor.x,
+                        width: 0.0,
+                    });
 
                     continue;
                 }
 
+                let x = cursor.x;
+
                 if let Some(last_glyph) = last_glyph {
                     cursor.x += self.rasterizer.kerning(last_glyph, ch);
                 }
 
-                if let Some(quad) = self.rasterize_char(device, ch, cursor)? {
-                    // Expand the cached bounds of the text geometry:
-                    match &mut text_bounds {
-                        Some(existing) => *existing = quad.bounds().combine(existing),
-                        None => {
-                            text_bounds.replace(quad.bounds());
-                        }
-                    }
+                let advance = self.rasterizer.advance(ch);
 
+                if use_ellipsis
+                    && is_last_line(line)
+                    && !ch.is_whitespace()
+                    && matches!(limits.max_width, Some(w) if cursor.x + advance > w)
+                {
+                    cursor.x = x;
+                    truncated = true;
+                    break 'words;
+                }
+
+                line_chars.push((x, quads.len(), chars.len(), ch));
+
+                if let Some(quad) = self.rasterize_char(device, ch, cursor)? {
                     quads.push(quad);
                 }
 
-                cursor.x += self.rasterizer.advance(ch);
+                chars.push(CharLayout {
+                    ch,
+                    index: start + offset,
+                    line,
+                    x,
+                    width: cursor.x + advance - x,
+                });
+
+                cursor.x += advance;
 
                 last_glyph = Some(ch);
             }
+
+            // Mandatory breaks (as defined by UAX #14) start a new line, unless they're
+            // at the very end of the text.
+            if hard_break && end < input.len() {
+                if is_last_line(line) {
+                    truncated = true;
+                    break;
+                }
+
+                new_line!();
+            }
+        }
+
+        if truncated && use_ellipsis {
+            self.push_ellipsis(
+                device,
+                &mut quads,
+                &mut chars,
+                &mut line_chars,
+                cursor,
+                limits,
+            )?;
         }
 
+        let bounds = quads
+            .iter()
+            .map(TextQuad::bounds)
+            .reduce(|a, b| a.combine(&b));
+
         Ok(TextGeometry {
             quads,
             resize_count: self.resize_count,
-            bounds: text_bounds,
+            bounds,
+            truncated,
+            chars,
+            line_height,
         })
     }
 
+    /// Adds an ellipsis to the end of the current line, removing characters from the end
+    /// of the line until it fits.
+    fn push_ellipsis(
+        &mut self,
+        device: &mut GraphicsDevice,
+        quads: &mut Vec<TextQuad>,
+        chars: &mut Vec<CharLayout>,
+        line_chars: &mut Vec<(f32, usize, usize, char)>,
+        mut cursor: Vec2<f32>,
+        limits: &TextLimits,
+    ) -> std::result::Result<(), CacheError> {
+        // Not every font will have a glyph for the ellipsis character, so fall back
+        // to three full stops if it's missing.
+        let ellipsis: &[char] = if self.rasterizer.advance('\u{2026}') > 0.0 {
+            &['\u{2026}']
+        } else {
+            &['.', '.', '.']
+        };
+
+        let ellipsis_width: f32 = ellipsis.iter().map(|c| self.rasterizer.advance(*c)).sum();
+        let max_width = limits.max_width.unwrap_or(f32::INFINITY);
+
+        while let Some(&(x, quad_count, char_count, ch)) = line_chars.last() {
+            if ch.is_whitespace() || cursor.x + ellipsis_width > max_width {
+                cursor.x = x;
+                quads.truncate(quad_count);
+                chars.truncate(char_count);
+                line_chars.pop();
+            } else {
+                break;
+            }
+        }
+
+        for ch in ellipsis {
+            if let Some(quad) = self.rasterize_char(device, *ch, cursor)? {
+                quads.push(quad);
+            }
+
+            cursor.x += self.rasterizer.advance(*ch);
+        }
+
+        Ok(())
+    }
+
+    /// Lays out the characters of `input` that come after the ones that are already in
+    /// `glyphs`, appending them to the end.
+    ///
+    /// The existing glyphs are assumed to be a valid layout of the start of `input`. If the
+    /// texture atlas has to be resized, they will be discarded and the whole string will be
+    /// laid out again.
+    ///
+    /// Unlike `render`, this does not support word wrapping, as wrapping can cause a change
+    /// at the end of the string to move the characters before it.
+    pub fn append_glyphs(
+        &mut self,
+        device: &mut GraphicsDevice,
+        glyphs: &mut Vec<GlyphLayout>,
+        input: &str,
+    ) {
+        loop {
+            match self.try_append_glyphs(device, glyphs, input) {
+                Ok(()) => return,
+                Err(CacheError::OutOfSpace) => {
+                    self.resize(device).expect("Failed to resize font texture");
+                    glyphs.clear();
+                }
+            }
+        }
+    }
+
+    fn try_append_glyphs(
+        &mut self,
+        device: &mut GraphicsDevice,
+        glyphs: &mut Vec<GlyphLayout>,
+        input: &str,
+    ) -> std::result::Result<(), CacheError> {
+        let line_height = self.rasterizer.line_height().round();
+
+        let (mut cursor, mut last_glyph) = match glyphs.last() {
+            Some(last) if is_line_break(last.ch) || is_skipped_control(last.ch) => {
+                (last.cursor, None)
+            }
+            Some(last) => (last.cursor, Some(last.ch)),
+            None => (Vec2::new(0.0, self.rasterizer.ascent().round()), None),
+        };
+
+        for ch in input.chars().skip(glyphs.len()) {
+            let mut quad = None;
+
+            if is_line_break(ch) {
+                cursor.x = 0.0;
+                cursor.y += line_height;
+            } else if !is_skipped_control(ch) {
+                if let Some(last_glyph) = last_glyph {
+                    cursor.x += self.rasterizer.kerning(last_glyph, ch);
+                }
+
+                quad = self.rasterize_char(device, ch, cursor)?;
+                cursor.x += self.rasterizer.advance(ch);
+            }
+
+            glyphs.push(GlyphLayout { ch, quad, cursor });
+
+            last_glyph = if is_line_break(ch) || is_skipped_control(ch) {
+                None
+            } else {
+                Some(ch)
+            };
+        }
+
+        Ok(())
+    }
+
     /// Measures the width of a word, not including any trailing whitespace.
     ///
     /// This is mainly used to determine if a word needs to break onto a
@@ -337,6 +661,17 @@ fn add_glyph_to_texture(
     })
 }
 
+/// Returns whether a character moves the cursor onto a new line.
+fn is_line_break(ch: char) -> bool {
+    matches!(ch, '\n' | '\u{2028}' | '\u{2029}')
+}
+
+/// Returns whether a character is a control character other than a line break (e.g. a
+/// tab or a carriage return), and so should be laid out without being rendered.
+fn is_skipped_control(ch: char) -> bool {
+    ch.is_control() && !is_line_break(ch)
+}
+
 struct UnicodeLineBreaks<'a> {
     input: &'a str,
     breaker: LineBreakIterator<'a>,
@@ -354,13 +689,93 @@ impl<'a> UnicodeLineBreaks<'a> {
 }
 
 impl<'a> Iterator for UnicodeLineBreaks<'a> {
-    type Item = (&'a str, bool);
+    /// The word, whether it ends in a mandatory break, and the offset of the end of the word.
+    type Item = (&'a str, bool, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
         self.breaker.next().map(|(offset, hard_break)| {
             let word = &self.input[self.last_break..offset];
             self.last_break = offset;
-            (word, hard_break)
+            (word, hard_break, offset)
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn geometry(text: &str, wrap_after: usize) -> TextGeometry {
+        let chars = text
+            .char_indices()
+            .enumerate()
+            .map(|(i, (index, ch))| CharLayout {
+                ch,
+                index,
+                line: i / wrap_after,
+                x: (i % wrap_after) as f32 * 10.0,
+                width: 10.0,
+            })
+            .collect();
+
+        TextGeometry {
+            quads: Vec::new(),
+            bounds: None,
+            resize_count: 0,
+            truncated: false,
+            chars,
+            line_height: 20.0,
+        }
+    }
+
+    #[test]
+    fn caret_positions() {
+        let geometry = geometry("abcdéf", 4);
+
+        assert_eq!(geometry.caret_position(0), Vec2::new(0.0, 0.0));
+        assert_eq!(geometry.caret_position(3), Vec2::new(30.0, 0.0));
+        assert_eq!(geometry.caret_position(5), Vec2::new(0.0, 20.0));
+        assert_eq!(geometry.caret_position(6), Vec2::new(10.0, 20.0));
+        assert_eq!(geometry.caret_position(100), Vec2::new(20.0, 20.0));
+    }
+
+    #[test]
+    fn hit_testing() {
+        let geometry = geometry("abcdéf", 4);
+
+        assert_eq!(geometry.hit_test(Vec2::new(-5.0, -5.0)), 0);
+        assert_eq!(geometry.hit_test(Vec2::new(14.0, 5.0)), 1);
+        assert_eq!(geometry.hit_test(Vec2::new(16.0, 5.0)), 2);
+        assert_eq!(geometry.hit_test(Vec2::new(14.0, 25.0)), 6);
+        assert_eq!(geometry.hit_test(Vec2::new(500.0, 500.0)), 7);
+    }
+
+    #[test]
+    fn line_breaks_and_controls() {
+        for ch in ['\n', '\u{2028}', '\u{2029}'] {
+            assert!(is_line_break(ch));
+            assert!(!is_skipped_control(ch));
+        }
+
+        for ch in ['\t', '\r', '\u{7}'] {
+            assert!(!is_line_break(ch));
+            assert!(is_skipped_control(ch));
+        }
+
+        assert!(!is_line_break('a'));
+        assert!(!is_skipped_control('a'));
+    }
+
+    #[test]
+    fn selection() {
+        let geometry = geometry("abcdéf", 4);
+
+        assert_eq!(
+            geometry.selection_rects(2, 6),
+            vec![
+                Rectangle::new(20.0, 0.0, 20.0, 20.0),
+                Rectangle::new(0.0, 20.0, 10.0, 20.0),
+            ]
+        );
+    }
+}
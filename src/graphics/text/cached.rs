@@ -0,0 +1,147 @@
+use crate::error::Result;
+use crate::graphics::text::Text;
+use crate::graphics::{CachedCanvas, DrawParams, Rectangle};
+use crate::math::Vec2;
+use crate::Context;
+
+/// A [`Text`] that is rendered to a [`CachedCanvas`], so that it only needs to be
+/// drawn glyph-by-glyph when it changes.
+///
+/// Drawing a `Text` requires a quad per character, which adds up quickly for large
+/// paragraphs (e.g. in dialogue boxes). `CachedText` draws the whole text as a single
+/// quad instead, and automatically re-renders the cache when the text's content, font
+/// or layout changes (including when a [localized](Text::localized) text is updated
+/// for a new language).
+///
+/// The text is accessed via [`text_mut`](Self::text_mut), which marks the cache as dirty.
+/// Use [`text`](Self::text) if you only need to read from it.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::text::{CachedText, Font, Text};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context, font: Font) -> tetra::Result {
+/// let mut dialogue = CachedText::new(Text::wrapped("A long paragraph...", font, 480.0));
+///
+/// // Every frame:
+/// dialogue.draw(ctx, Vec2::new(16.0, 300.0))?;
+///
+/// // When the dialogue advances:
+/// dialogue.text_mut().set_content("Another long paragraph...");
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct CachedText {
+    text: Text,
+    cache: CachedCanvas,
+    offset: Vec2<f32>,
+    bounds: Option<Rectangle>,
+}
+
+impl CachedText {
+    /// Creates a new `CachedText`, wrapping the given text.
+    pub fn new(text: Text) -> CachedText {
+        CachedText {
+            text,
+            cache: CachedCanvas::new(1, 1),
+            offset: Vec2::zero(),
+            bounds: None,
+        }
+    }
+
+    /// Returns a reference to the text.
+    pub fn text(&self) -> &Text {
+        &self.text
+    }
+
+    /// Returns a mutable reference to the text, and marks the cache as dirty.
+    pub fn text_mut(&mut self) -> &mut Text {
+        self.cache.mark_dirty();
+        &mut self.text
+    }
+
+    /// Consumes the `CachedText`, returning the text.
+    pub fn into_text(self) -> Text {
+        self.text
+    }
+
+    /// Marks the cache as dirty, so that the text will be rendered again the next time
+    /// it is drawn.
+    ///
+    /// You usually will not need to call this, as changes to the text are detected
+    /// automatically.
+    pub fn mark_dirty(&mut self) {
+        self.cache.mark_dirty();
+    }
+
+    /// Draws the text to the screen (or to a canvas, if one is enabled).
+    ///
+    /// If the text has changed since it was last drawn, the cache will be updated first.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+    ///   if the cache's canvas could not be created.
+    pub fn draw<P>(&mut self, ctx: &mut Context, params: P) -> Result
+    where
+        P: Into<DrawParams>,
+    {
+        self.update(ctx)?;
+
+        let mut params = params.into();
+
+        if let Some(bounds) = self.bounds {
+            if let Some(normalized) = params.origin_normalized.take() {
+                params.origin = Vec2::new(
+                    bounds.x + normalized.x * bounds.width,
+                    bounds.y + normalized.y * bounds.height,
+                );
+            }
+
+            // The canvas starts at the top left of the text's bounds, rather than at (0, 0).
+            params.origin -= self.offset;
+
+            self.cache.draw(ctx, params);
+        }
+
+        Ok(())
+    }
+
+    /// Get the outer bounds of the text when rendered to the screen.
+    ///
+    /// See [`Text::get_bounds`] for more details.
+    pub fn get_bounds(&mut self, ctx: &mut Context) -> Option<Rectangle> {
+        self.text.get_bounds(ctx)
+    }
+
+    fn update(&mut self, ctx: &mut Context) -> Result {
+        if self.text.update_geometry(ctx) {
+            self.cache.mark_dirty();
+        }
+
+        if !self.cache.is_dirty() {
+            return Ok(());
+        }
+
+        self.bounds = self.text.get_bounds(ctx);
+
+        if let Some(bounds) = self.bounds {
+            self.offset = Vec2::new(bounds.x.floor(), bounds.y.floor());
+
+            let width = (bounds.right() - self.offset.x).ceil() as i32;
+            let height = (bounds.bottom() - self.offset.y).ceil() as i32;
+
+            self.cache.set_size(width, height);
+
+            let text = &mut self.text;
+            let offset = self.offset;
+
+            self.cache.update(ctx, |ctx| text.draw(ctx, -offset))?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,218 @@
+use std::fmt::{self, Write};
+
+use crate::graphics::text::cache::GlyphLayout;
+use crate::graphics::text::Font;
+use crate::graphics::{self, DrawParams, Rectangle};
+use crate::math::Vec2;
+use crate::Context;
+
+/// A piece of text that is optimized for content that changes every frame, such as
+/// an FPS counter, a timer or a score.
+///
+/// Changing the content of a [`Text`](super::Text) causes the whole string to be laid
+/// out again the next time it is drawn. `DynamicText` instead keeps the layout of each
+/// character, and when the content changes, only the characters after the first
+/// difference are laid out again. For a counter like `Score: 1234`, this means that
+/// usually only the last digit or two need to be updated.
+///
+/// The text has a fixed capacity, measured in characters, which is allocated up front.
+/// Any content past the capacity will be cut off. Word wrapping is not supported, but
+/// line breaks (`\n`) are.
+///
+/// `DynamicText` implements [`std::fmt::Write`], so the content can be updated without
+/// allocating a new string every frame.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::fmt::Write;
+/// # use tetra::Context;
+/// # use tetra::graphics::text::{DynamicText, Font};
+/// # use tetra::graphics::DrawParams;
+/// # use tetra::time;
+/// # fn example(ctx: &mut Context, font: Font) {
+/// let mut fps = DynamicText::new(font, 16);
+///
+/// // Every frame:
+/// fps.clear();
+/// write!(fps, "FPS: {:.0}", time::get_fps(ctx)).unwrap();
+/// fps.draw(ctx, DrawParams::new());
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DynamicText {
+    content: String,
+    capacity: usize,
+    len: usize,
+    font: Font,
+    glyphs: Vec<GlyphLayout>,
+    resize_count: usize,
+}
+
+impl DynamicText {
+    /// Creates a new, empty `DynamicText`, with space for the given number of characters.
+    pub fn new(font: Font, capacity: usize) -> DynamicText {
+        DynamicText {
+            // Most of the text this is used for will be ASCII, so this is a reasonable guess.
+            content: String::with_capacity(capacity),
+            capacity,
+            len: 0,
+            font,
+            glyphs: Vec::with_capacity(capacity),
+            resize_count: 0,
+        }
+    }
+
+    /// Draws the text to the screen (or to a canvas, if one is enabled).
+    pub fn draw<P>(&mut self, ctx: &mut Context, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        self.update_layout(ctx);
+
+        let mut params = params.into();
+
+        if let Some(bounds) = self.bounds() {
+            if let Some(normalized) = params.origin_normalized.take() {
+                params.origin = Vec2::new(
+                    bounds.x + normalized.x * bounds.width,
+                    bounds.y + normalized.y * bounds.height,
+                );
+            }
+        }
+
+        let data = self.font.data.borrow();
+        let texture = data.texture();
+
+        graphics::set_texture(ctx, texture);
+        let (texture_width, texture_height) = texture.size();
+
+        for quad in self.glyphs.iter().filter_map(|g| g.quad.as_ref()) {
+            graphics::push_quad(
+                ctx,
+                quad.position.x,
+                quad.position.y,
+                quad.position.x + quad.region.width,
+                quad.position.y + quad.region.height,
+                quad.region.x / (texture_width as f32),
+                quad.region.y / (texture_height as f32),
+                quad.region.right() / (texture_width as f32),
+                quad.region.bottom() / (texture_height as f32),
+                &params,
+            );
+        }
+    }
+
+    /// Returns a reference to the content of the text.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Sets the content of the text.
+    ///
+    /// Only the characters after the first difference between the old and new content
+    /// will be laid out again. If the content is longer than the capacity, it will be
+    /// cut off.
+    pub fn set_content(&mut self, content: &str) {
+        if self.content != content {
+            self.clear();
+            self.push_str(content);
+        }
+    }
+
+    /// Removes all of the content from the text.
+    ///
+    /// The layout of the old content is kept until the text is next drawn or measured,
+    /// so clearing the text and then writing similar content back into it is cheap.
+    pub fn clear(&mut self) {
+        self.content.clear();
+        self.len = 0;
+    }
+
+    /// Appends the given string slice to the end of the text.
+    ///
+    /// Any characters that do not fit within the capacity will be discarded.
+    pub fn push_str(&mut self, string: &str) {
+        for ch in string.chars().take(self.capacity - self.len) {
+            self.content.push(ch);
+            self.len += 1;
+        }
+    }
+
+    /// Returns the maximum number of characters that the text can hold.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of characters in the text.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the text is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets the font of the text.
+    pub fn font(&self) -> &Font {
+        &self.font
+    }
+
+    /// Sets the font of the text.
+    ///
+    /// Calling this function will cause a full re-layout of the text the next time it
+    /// is rendered.
+    pub fn set_font(&mut self, font: Font) {
+        self.glyphs.clear();
+        self.font = font;
+    }
+
+    /// Get the outer bounds of the text when rendered to the screen.
+    ///
+    /// If the text's layout needs updating, this method will do so.
+    ///
+    /// Note that this method will not take into account the positioning applied to the text via [`DrawParams`].
+    pub fn get_bounds(&mut self, ctx: &mut Context) -> Option<Rectangle> {
+        self.update_layout(ctx);
+        self.bounds()
+    }
+
+    fn bounds(&self) -> Option<Rectangle> {
+        self.glyphs
+            .iter()
+            .filter_map(|g| g.quad.as_ref())
+            .map(|q| Rectangle::new(q.position.x, q.position.y, q.region.width, q.region.height))
+            .reduce(|a, b| a.combine(&b))
+    }
+
+    fn update_layout(&mut self, ctx: &mut Context) {
+        let mut data = self.font.data.borrow_mut();
+
+        if self.resize_count != data.resize_count() {
+            self.glyphs.clear();
+            self.resize_count = data.resize_count();
+        }
+
+        let unchanged = self
+            .glyphs
+            .iter()
+            .zip(self.content.chars())
+            .take_while(|(glyph, ch)| glyph.ch == *ch)
+            .count();
+
+        self.glyphs.truncate(unchanged);
+
+        if self.glyphs.len() < self.len {
+            data.append_glyphs(&mut ctx.device, &mut self.glyphs, &self.content);
+            self.resize_count = data.resize_count();
+        }
+    }
+}
+
+impl Write for DynamicText {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.push_str(s);
+        Ok(())
+    }
+}
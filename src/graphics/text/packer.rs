@@ -1,4 +1,5 @@
-use crate::graphics::{FilterMode, Texture};
+use crate::graphics::{FilterMode, Texture, WhitePixel};
+use crate::math::Vec2;
 use crate::platform::GraphicsDevice;
 use crate::{Context, Result};
 
@@ -28,7 +29,7 @@ impl ShelfPacker {
         texture_height: i32,
         filter_mode: FilterMode,
     ) -> Result<ShelfPacker> {
-        Ok(ShelfPacker {
+        let mut packer = ShelfPacker {
             texture: Texture::with_device_empty(
                 device,
                 texture_width,
@@ -37,7 +38,11 @@ impl ShelfPacker {
             )?,
             shelves: Vec::new(),
             next_y: Self::PADDING,
-        })
+        };
+
+        packer.reserve_white_pixel(device);
+
+        Ok(packer)
     }
 
     /// Returns a reference to the current atlas texture.
@@ -70,9 +75,21 @@ impl ShelfPacker {
         self.shelves.clear();
         self.next_y = Self::PADDING;
 
+        self.reserve_white_pixel(device);
+
         Ok(())
     }
 
+    /// Reserves a small white square in the atlas, so that shapes can be drawn in the
+    /// same batch as text.
+    fn reserve_white_pixel(&mut self, device: &mut GraphicsDevice) {
+        if let Some((x, y)) = self.insert(device, &[255; 3 * 3 * 4], 3, 3) {
+            self.texture
+                .data
+                .set_white_pixel(WhitePixel::Found(Vec2::new(x + 1, y + 1)));
+        }
+    }
+
     /// Tries to insert RGBA data into the atlas, and returns the position.
     ///
     /// If the data will not fit into the remaining space, `None` will be returned.
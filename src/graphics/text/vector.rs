@@ -126,6 +126,7 @@ enum VectorFontData {
 #[derive(Debug, Clone)]
 pub struct VectorFontBuilder {
     data: VectorFontData,
+    owned_bytes: usize,
 }
 
 impl VectorFontBuilder {
@@ -139,11 +140,16 @@ impl VectorFontBuilder {
     where
         P: AsRef<Path>,
     {
-        let font_bytes = fs::read(path)?;
-        let font = FontVec::try_from_vec(font_bytes).map_err(|_| TetraError::InvalidFont)?;
+        VectorFontBuilder::from_vec(fs::read(path)?)
+    }
+
+    pub(crate) fn from_vec(data: Vec<u8>) -> Result<VectorFontBuilder> {
+        let owned_bytes = data.len();
+        let font = FontVec::try_from_vec(data).map_err(|_| TetraError::InvalidFont)?;
 
         Ok(VectorFontBuilder {
             data: VectorFontData::Owned(Rc::new(font)),
+            owned_bytes,
         })
     }
 
@@ -157,9 +163,24 @@ impl VectorFontBuilder {
 
         Ok(VectorFontBuilder {
             data: VectorFontData::Slice(Rc::new(font)),
+            owned_bytes: 0,
         })
     }
 
+    /// Returns the number of builders that are sharing this font's data.
+    pub(crate) fn handle_count(&self) -> usize {
+        match &self.data {
+            VectorFontData::Owned(f) => Rc::strong_count(f),
+            VectorFontData::Slice(f) => Rc::strong_count(f),
+        }
+    }
+
+    /// Returns the size of the font data that was loaded into memory, in bytes.
+    /// Fonts created from static data don't own their data, so this will be zero.
+    pub(crate) fn owned_bytes(&self) -> usize {
+        self.owned_bytes
+    }
+
     /// Creates a `Font` with the given size.
     ///
     /// # Errors
@@ -1,22 +1,29 @@
 //! Functions and types relating to textures.
 
-use std::cell::Cell;
 use std::path::Path;
-use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
 
 use image::{Rgba, RgbaImage, SubImage};
 
+use crate::debug::{ResourceKind, TrackedResource};
 use crate::error::{Result, TetraError};
 use crate::fs;
-use crate::graphics::{self, Color, DrawParams, Rectangle};
+use crate::graphics::{self, AlphaMask, CollisionMask, Color, DrawParams, Origin, Rectangle};
 use crate::math::Vec2;
-use crate::platform::{GraphicsDevice, RawTexture};
+use crate::platform::{GraphicsDevice, RawTexture, TextureFormat};
 use crate::Context;
 
 #[derive(Debug)]
 pub(crate) struct TextureSharedData {
     pub(crate) handle: RawTexture,
-    filter_mode: Cell<FilterMode>,
+    filter_mode: Mutex<FilterMode>,
+    pub(crate) premultiplied: AtomicBool,
+    anisotropy: AtomicU8,
+    alpha_mask: Mutex<Option<Arc<AlphaMask>>>,
+    white_pixel: Mutex<WhitePixel>,
+    swizzle: Swizzle,
+    _tracker: TrackedResource,
 }
 
 impl PartialEq for TextureSharedData {
@@ -28,6 +35,92 @@ impl PartialEq for TextureSharedData {
     }
 }
 
+/// Whether a texture contains an opaque white pixel that untextured geometry can be
+/// drawn with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum WhitePixel {
+    /// The texture's data has not been searched for a white pixel yet.
+    Unsearched,
+
+    /// The texture has a white pixel at the given position.
+    Found(Vec2<i32>),
+
+    /// The texture does not have a white pixel, or is not suitable for searching
+    /// (e.g. because it is a canvas, and so its contents change every frame).
+    Missing,
+}
+
+impl TextureSharedData {
+    pub(crate) fn white_pixel(&self) -> Option<Vec2<i32>> {
+        match *self.white_pixel.lock().unwrap_or_else(|e| e.into_inner()) {
+            WhitePixel::Found(position) => Some(position),
+            WhitePixel::Unsearched | WhitePixel::Missing => None,
+        }
+    }
+
+    pub(crate) fn set_white_pixel(&self, white_pixel: WhitePixel) {
+        *self.white_pixel.lock().unwrap_or_else(|e| e.into_inner()) = white_pixel;
+    }
+
+    /// Returns the UV co-ordinates of the center of the white pixel, if there is one.
+    ///
+    /// If the texture has not been searched for a white pixel yet, its data will be read
+    /// back from the GPU and searched, and the result will be cached for future calls.
+    pub(crate) fn white_pixel_uv(&self, device: &mut GraphicsDevice) -> Option<Vec2<f32>> {
+        let position = {
+            let mut white_pixel = self.white_pixel.lock().unwrap_or_else(|e| e.into_inner());
+
+            if *white_pixel == WhitePixel::Unsearched {
+                let data = device.get_texture_data(&self.handle);
+
+                *white_pixel =
+                    match find_white_pixel(self.handle.width(), self.handle.height(), &data) {
+                        Some(position) => WhitePixel::Found(position),
+                        None => WhitePixel::Missing,
+                    };
+            }
+
+            match *white_pixel {
+                WhitePixel::Found(position) => position,
+                WhitePixel::Unsearched | WhitePixel::Missing => return None,
+            }
+        };
+
+        Some(Vec2::new(
+            (position.x as f32 + 0.5) / self.handle.width() as f32,
+            (position.y as f32 + 0.5) / self.handle.height() as f32,
+        ))
+    }
+}
+
+/// Finds an opaque white pixel in a slice of RGBA data, for drawing untextured geometry.
+///
+/// Pixels that are surrounded by other white pixels are required, so that filtering
+/// doesn't cause the surrounding colors to bleed in. Neighbours that would be outside
+/// of the image are ignored.
+pub(crate) fn find_white_pixel(width: i32, height: i32, data: &[u8]) -> Option<Vec2<i32>> {
+    let is_white = |x: i32, y: i32| {
+        if x < 0 || y < 0 || x >= width || y >= height {
+            return true;
+        }
+
+        let index = (y * width + x) as usize * 4;
+        data.get(index..index + 4) == Some(&[255; 4][..])
+    };
+
+    for y in 0..height {
+        for x in 0..width {
+            if is_white(x, y)
+                && (y - 1..=y + 1).all(|ny| (x - 1..=x + 1).all(|nx| is_white(nx, ny)))
+            {
+                return Some(Vec2::new(x, y));
+            }
+        }
+    }
+
+    None
+}
+
 /// A texture, held in GPU memory.
 ///
 /// # Supported Formats
@@ -52,17 +145,21 @@ impl PartialEq for TextureSharedData {
 /// Creating a texture is quite an expensive operation, as it involves 'uploading' the texture
 /// data to the GPU. Try to reuse textures, rather than recreating them every frame.
 ///
-/// You can clone a texture cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/rc/struct.Rc.html)
+/// You can clone a texture cheaply, as it is a [reference-counted](https://doc.rust-lang.org/std/sync/struct.Arc.html)
 /// handle to a GPU resource. However, this does mean that modifying a texture (e.g.
 /// setting the filter mode) will also affect any clones that exist of it.
 ///
+/// Texture handles can be sent between threads (e.g. to store them in an ECS resource).
+/// The texture itself can only be used on the main thread, so when the last handle is
+/// dropped, the GPU resource will be deleted at the end of the next frame.
+///
 /// # Examples
 ///
 /// The [`texture`](https://github.com/17cupsofcoffee/tetra/blob/main/examples/texture.rs)
 /// example demonstrates how to draw a simple texture.
 #[derive(Debug, Clone, PartialEq)]
 pub struct Texture {
-    pub(crate) data: Rc<TextureSharedData>,
+    pub(crate) data: Arc<TextureSharedData>,
 }
 
 impl Texture {
@@ -70,17 +167,29 @@ impl Texture {
     ///
     /// The format will be determined based on the file extension.
     ///
+    /// If [`graphics::set_default_premultiplied_alpha`] has been enabled, the
+    /// texture's alpha will be premultiplied as it is loaded.
+    ///
     /// # Errors
     ///
     /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`] will be returned if the texture is larger than the
+    ///   [maximum texture size](crate::graphics::Capabilities::max_texture_size) supported by the device.
     /// * [`TetraError::FailedToLoadAsset`] will be returned if the file could not be loaded.
     /// * [`TetraError::InvalidTexture`] will be returned if the texture data was invalid.
+    #[track_caller]
     pub fn new<P>(ctx: &mut Context, path: P) -> Result<Texture>
     where
         P: AsRef<Path>,
     {
+        let path = path.as_ref();
         let data = ImageData::from_file(path)?;
-        Texture::from_image_data(ctx, &data)
+        let texture = Texture::from_loaded_image(ctx, data)?;
+
+        #[cfg(feature = "hot_reload")]
+        crate::hot_reload::watch_texture(ctx, path, &texture);
+
+        Ok(texture)
     }
 
     /// Creates a new texture from a slice of data, encoded in one of Tetra's supported
@@ -94,13 +203,38 @@ impl Texture {
     /// might have to be added later. Note that TGA files do not have recognizable magic
     /// bytes, so this function will not recognize them.
     ///
+    /// If [`graphics::set_default_premultiplied_alpha`] has been enabled, the
+    /// texture's alpha will be premultiplied as it is loaded.
+    ///
     /// # Errors
     ///
     /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`] will be returned if the texture is larger than the
+    ///   [maximum texture size](crate::graphics::Capabilities::max_texture_size) supported by the device.
     /// * [`TetraError::InvalidTexture`] will be returned if the texture data was invalid.
+    #[track_caller]
     pub fn from_file_data(ctx: &mut Context, data: &[u8]) -> Result<Texture> {
         let data = ImageData::from_file_data(data)?;
-        Texture::from_image_data(ctx, &data)
+        Texture::from_loaded_image(ctx, data)
+    }
+
+    /// Creates a texture from an image that was loaded from a file, premultiplying
+    /// it first if that has been enabled.
+    #[track_caller]
+    pub(crate) fn from_loaded_image(ctx: &mut Context, mut data: ImageData) -> Result<Texture> {
+        let premultiply = ctx.graphics.default_premultiplied_alpha;
+
+        if premultiply {
+            data.premultiply();
+        }
+
+        let texture = Texture::from_image_data(ctx, &data)?;
+        texture
+            .data
+            .premultiplied
+            .store(premultiply, Ordering::Relaxed);
+
+        Ok(texture)
     }
 
     /// Creates a new texture from an [`ImageData`].
@@ -108,6 +242,9 @@ impl Texture {
     /// # Errors
     ///
     /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`] will be returned if the texture is larger than the
+    ///   [maximum texture size](crate::graphics::Capabilities::max_texture_size) supported by the device.
+    #[track_caller]
     pub fn from_image_data(ctx: &mut Context, data: &ImageData) -> Result<Texture> {
         Texture::from_rgba(ctx, data.width(), data.height(), data.as_bytes())
     }
@@ -123,27 +260,56 @@ impl Texture {
     /// # Errors
     ///
     /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`] will be returned if the texture is larger than the
+    ///   [maximum texture size](crate::graphics::Capabilities::max_texture_size) supported by the device.
     /// * [`TetraError::NotEnoughData`] will be returned if not enough data is provided to fill
     /// the texture. This is to prevent the graphics API from trying to read uninitialized memory.
+    #[track_caller]
     pub fn from_rgba(ctx: &mut Context, width: i32, height: i32, data: &[u8]) -> Result<Texture> {
-        Texture::with_device(
+        let mut texture = Texture::with_device(
             &mut ctx.device,
             width,
             height,
             data,
             ctx.graphics.default_filter_mode,
-        )
+        )?;
+
+        texture.apply_default_anisotropy(ctx);
+
+        Ok(texture)
+    }
+
+    pub(crate) fn apply_default_anisotropy(&mut self, ctx: &mut Context) {
+        let level = ctx.graphics.default_anisotropy;
+
+        if level > 1 {
+            self.set_anisotropy(ctx, level);
+        }
     }
 
+    #[track_caller]
     pub(crate) fn from_raw(handle: RawTexture, filter_mode: FilterMode) -> Texture {
+        // This is only used for the color attachments of canvases, so we track it as one.
+        let tracker = TrackedResource::new(
+            ResourceKind::Canvas,
+            Some((handle.width(), handle.height())),
+        );
+
         Texture {
-            data: Rc::new(TextureSharedData {
+            data: Arc::new(TextureSharedData {
                 handle,
-                filter_mode: Cell::new(filter_mode),
+                filter_mode: Mutex::new(filter_mode),
+                premultiplied: AtomicBool::new(false),
+                anisotropy: AtomicU8::new(1),
+                alpha_mask: Mutex::new(None),
+                white_pixel: Mutex::new(WhitePixel::Missing),
+                swizzle: Swizzle::IDENTITY,
+                _tracker: tracker,
             }),
         }
     }
 
+    #[track_caller]
     pub(crate) fn with_device(
         device: &mut GraphicsDevice,
         width: i32,
@@ -151,18 +317,25 @@ impl Texture {
         data: &[u8],
         filter_mode: FilterMode,
     ) -> Result<Texture> {
-        let handle = device.new_texture(width, height, filter_mode, false)?;
+        let handle = device.new_texture(width, height, filter_mode, TextureFormat::Rgba8)?;
 
         device.set_texture_data(&handle, data, 0, 0, width, height)?;
 
         Ok(Texture {
-            data: Rc::new(TextureSharedData {
+            data: Arc::new(TextureSharedData {
                 handle,
-                filter_mode: Cell::new(filter_mode),
+                filter_mode: Mutex::new(filter_mode),
+                premultiplied: AtomicBool::new(false),
+                anisotropy: AtomicU8::new(1),
+                alpha_mask: Mutex::new(None),
+                white_pixel: Mutex::new(WhitePixel::Unsearched),
+                swizzle: Swizzle::IDENTITY,
+                _tracker: TrackedResource::new(ResourceKind::Texture, Some((width, height))),
             }),
         })
     }
 
+    #[track_caller]
     pub(crate) fn with_device_empty(
         device: &mut GraphicsDevice,
         width: i32,
@@ -176,12 +349,97 @@ impl Texture {
         Texture::with_device(device, width, height, &data, filter_mode)
     }
 
+    /// Creates a new texture from a slice of single channel (8-bit) pixel data.
+    ///
+    /// Single channel textures use a quarter of the memory of a normal RGBA texture, which
+    /// makes them a good fit for grayscale masks, lightmaps and signed distance field atlases.
+    /// The swizzle determines what shaders will see when the texture is sampled - for
+    /// example, [`Swizzle::GRAYSCALE`] makes the texture appear as an opaque grayscale image,
+    /// so it can be used with the default shader (or any other shader that expects RGBA data).
+    ///
+    /// If the device does not support [texture swizzling](crate::graphics::Capabilities::texture_swizzle),
+    /// the data will be expanded to RGBA before it is uploaded. This will give the same results,
+    /// but will not save any memory.
+    ///
+    /// This method requires you to provide enough data to fill the texture (one byte per pixel).
+    /// If you provide too little data, an error will be returned.
+    /// If you provide too much data, it will be truncated.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`] will be returned if the texture is larger than the
+    ///   [maximum texture size](crate::graphics::Capabilities::max_texture_size) supported by the device.
+    /// * [`TetraError::NotEnoughData`] will be returned if not enough data is provided to fill
+    ///   the texture. This is to prevent the graphics API from trying to read uninitialized memory.
+    #[track_caller]
+    pub fn from_r8(
+        ctx: &mut Context,
+        width: i32,
+        height: i32,
+        data: &[u8],
+        swizzle: Swizzle,
+    ) -> Result<Texture> {
+        if !ctx.device.get_capabilities().texture_swizzle {
+            let expected = (width * height) as usize;
+
+            if data.len() < expected {
+                return Err(TetraError::NotEnoughData {
+                    expected,
+                    actual: data.len(),
+                });
+            }
+
+            let rgba: Vec<u8> = data[..expected]
+                .iter()
+                .flat_map(|&value| swizzle.apply([value, 0, 0, 255]))
+                .collect();
+
+            return Texture::from_rgba(ctx, width, height, &rgba);
+        }
+
+        let filter_mode = ctx.graphics.default_filter_mode;
+        let handle = ctx
+            .device
+            .new_texture(width, height, filter_mode, TextureFormat::R8)?;
+
+        ctx.device
+            .set_texture_data(&handle, data, 0, 0, width, height)?;
+        ctx.device.set_texture_swizzle(&handle, swizzle)?;
+
+        let mut texture = Texture {
+            data: Arc::new(TextureSharedData {
+                handle,
+                filter_mode: Mutex::new(filter_mode),
+                premultiplied: AtomicBool::new(false),
+                anisotropy: AtomicU8::new(1),
+                alpha_mask: Mutex::new(None),
+                white_pixel: Mutex::new(WhitePixel::Missing),
+                swizzle,
+                _tracker: TrackedResource::new(ResourceKind::Texture, Some((width, height))),
+            }),
+        };
+
+        texture.apply_default_anisotropy(ctx);
+
+        Ok(texture)
+    }
+
     /// Draws the texture to the screen (or to a canvas, if one is enabled).
+    ///
+    /// If the texture belongs to the canvas that is currently being rendered to, nothing
+    /// will be drawn, as reading from and writing to the same texture is not supported.
     pub fn draw<P>(&self, ctx: &mut Context, params: P)
     where
         P: Into<DrawParams>,
     {
-        let params = params.into();
+        if graphics::is_feedback_loop(ctx, self) {
+            return;
+        }
+
+        let params = params
+            .into()
+            .resolve_origin(Vec2::new(self.width() as f32, self.height() as f32));
 
         graphics::set_texture(ctx, self);
         graphics::push_quad(
@@ -199,11 +457,20 @@ impl Texture {
     }
 
     /// Draws a region of the texture to the screen (or to a canvas, if one is enabled).
+    ///
+    /// If the texture belongs to the canvas that is currently being rendered to, nothing
+    /// will be drawn.
     pub fn draw_region<P>(&self, ctx: &mut Context, region: Rectangle, params: P)
     where
         P: Into<DrawParams>,
     {
-        let params = params.into();
+        if graphics::is_feedback_loop(ctx, self) {
+            return;
+        }
+
+        let params = params
+            .into()
+            .resolve_origin(Vec2::new(region.width, region.height));
 
         let texture_width = self.width() as f32;
         let texture_height = self.height() as f32;
@@ -235,7 +502,11 @@ impl Texture {
     ) where
         P: Into<DrawParams>,
     {
-        let params = params.into();
+        if graphics::is_feedback_loop(ctx, self) {
+            return;
+        }
+
+        let params = params.into().resolve_origin(Vec2::new(width, height));
 
         let texture_width = self.width() as f32;
         let texture_height = self.height() as f32;
@@ -288,6 +559,80 @@ impl Texture {
         graphics::push_quad(ctx, x3, y3, x4, y4, u3, v3, u4, v4, &params);
     }
 
+    /// Fills a destination rectangle by repeating a region of the texture.
+    ///
+    /// The tiles start from the top left of the destination, and any tiles that overlap
+    /// the right or bottom edge will be cut off. This can be used for tiled backgrounds,
+    /// the fill of a progress bar, or dashed borders.
+    ///
+    /// The destination is specified in the same co-ordinate space as the origin of the
+    /// params, so it will be scaled, rotated and positioned along with the rest of the
+    /// graphic. A [normalized origin](DrawParams::origin_normalized) is relative to the
+    /// destination rectangle.
+    ///
+    /// Each tile is drawn as a separate quad, so this works with regions of a sprite sheet,
+    /// and does not depend on the texture's wrap mode. Try to avoid filling very large
+    /// areas with very small tiles, as this will generate a lot of vertices.
+    pub fn draw_tiled<P>(&self, ctx: &mut Context, region: Rectangle, dest: Rectangle, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        if graphics::is_feedback_loop(ctx, self) {
+            return;
+        }
+
+        if region.width <= 0.0 || region.height <= 0.0 {
+            return;
+        }
+
+        let mut params = params.into();
+
+        if let Some(normalized) = params.origin_normalized.take() {
+            params.origin = Vec2::new(
+                dest.x + normalized.x * dest.width,
+                dest.y + normalized.y * dest.height,
+            );
+        }
+
+        let texture_width = self.width() as f32;
+        let texture_height = self.height() as f32;
+
+        graphics::set_texture(ctx, self);
+
+        let mut y = dest.y;
+
+        while y < dest.bottom() {
+            let tile_height = region.height.min(dest.bottom() - y);
+            let v1 = region.y / texture_height;
+            let v2 = (region.y + tile_height) / texture_height;
+
+            let mut x = dest.x;
+
+            while x < dest.right() {
+                let tile_width = region.width.min(dest.right() - x);
+                let u1 = region.x / texture_width;
+                let u2 = (region.x + tile_width) / texture_width;
+
+                graphics::push_quad(
+                    ctx,
+                    x,
+                    y,
+                    x + tile_width,
+                    y + tile_height,
+                    u1,
+                    v1,
+                    u2,
+                    v2,
+                    &params,
+                );
+
+                x += region.width;
+            }
+
+            y += region.height;
+        }
+    }
+
     /// Returns the width of the texture.
     pub fn width(&self) -> i32 {
         self.data.handle.width()
@@ -303,9 +648,54 @@ impl Texture {
         (self.data.handle.width(), self.data.handle.height())
     }
 
+    /// Returns `true` if the texture stores a single channel of data per pixel.
+    ///
+    /// See [`Texture::from_r8`] for more details.
+    pub fn is_single_channel(&self) -> bool {
+        self.data.handle.format() == TextureFormat::R8
+    }
+
+    /// Returns the swizzle that is applied when the texture is sampled.
+    ///
+    /// This will be [`Swizzle::IDENTITY`] for textures that were not created via
+    /// [`Texture::from_r8`].
+    pub fn swizzle(&self) -> Swizzle {
+        self.data.swizzle
+    }
+
+    /// Returns `true` if the texture's alpha was premultiplied when it was loaded.
+    ///
+    /// See [`graphics::set_default_premultiplied_alpha`] for more details.
+    pub fn is_premultiplied(&self) -> bool {
+        self.data.premultiplied.load(Ordering::Relaxed)
+    }
+
+    /// Returns the position of an opaque white pixel within the texture, if one is known.
+    ///
+    /// The first time that untextured geometry (such as shapes drawn via
+    /// [`Mesh`](super::mesh::Mesh) or
+    /// [`GeometryBuilder::draw`](super::mesh::GeometryBuilder::draw)) is drawn while a
+    /// texture that was created from RGBA data is bound, Tetra looks for a white pixel
+    /// that is surrounded by other white pixels. If one is found, untextured geometry will
+    /// sample it while the texture is bound, rather than switching to a separate white
+    /// texture and breaking the batch. This will return `None` until that search has
+    /// taken place.
+    ///
+    /// If your texture atlas doesn't contain a suitable pixel, or you want to avoid the
+    /// cost of searching for one, it can be set manually via [`graphics::set_white_pixel`].
+    /// The pixel is forgotten if it is overwritten via [`set_data`](Self::set_data) or
+    /// [`replace_data`](Self::replace_data).
+    pub fn white_pixel(&self) -> Option<Vec2<i32>> {
+        self.data.white_pixel()
+    }
+
     /// Returns the filter mode being used by the texture.
     pub fn filter_mode(&self) -> FilterMode {
-        self.data.filter_mode.get()
+        *self
+            .data
+            .filter_mode
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
     }
 
     /// Sets the filter mode that should be used by the texture.
@@ -313,7 +703,32 @@ impl Texture {
         ctx.device
             .set_texture_filter_mode(&self.data.handle, filter_mode);
 
-        self.data.filter_mode.set(filter_mode);
+        *self
+            .data
+            .filter_mode
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = filter_mode;
+    }
+
+    /// Returns the level of anisotropic filtering being used by the texture.
+    pub fn anisotropy(&self) -> u8 {
+        self.data.anisotropy.load(Ordering::Relaxed)
+    }
+
+    /// Sets the level of anisotropic filtering that should be used by the texture.
+    ///
+    /// Anisotropic filtering improves the quality of textures that are scaled down
+    /// unevenly (e.g. a ground texture that has been squashed by a rotated camera).
+    /// A level of `1` disables it, and higher levels (usually up to `16`) give better
+    /// results at the cost of performance. It is intended for use with [`FilterMode::Linear`].
+    ///
+    /// The level will be clamped to the
+    /// [maximum supported by the device](crate::graphics::Capabilities::max_anisotropy) -
+    /// if anisotropic filtering is not supported at all, this method will do nothing.
+    pub fn set_anisotropy(&mut self, ctx: &mut Context, level: u8) {
+        let level = ctx.device.set_texture_anisotropy(&self.data.handle, level);
+
+        self.data.anisotropy.store(level, Ordering::Relaxed);
     }
 
     /// Gets the texture's data from the GPU.
@@ -321,15 +736,84 @@ impl Texture {
     /// This can be useful if you need to do some image processing on the CPU,
     /// or if you want to output the image data somewhere. This is a fairly
     /// slow operation, so avoid doing it too often!
+    ///
+    /// For single channel textures, the swizzle will be applied to the data, so the
+    /// output will match what is seen by shaders.
     pub fn get_data(&self, ctx: &mut Context) -> ImageData {
         let (width, height) = self.size();
-        let buffer = ctx.device.get_texture_data(&self.data.handle);
+        let mut buffer = ctx.device.get_texture_data(&self.data.handle);
+
+        if self.is_single_channel() {
+            for pixel in buffer.chunks_exact_mut(4) {
+                let swizzled = self.data.swizzle.apply([pixel[0], 0, 0, 255]);
+                pixel.copy_from_slice(&swizzled);
+            }
+        }
 
         ImageData::from_rgba8(width, height, buffer).expect("buffer should be exact size for image")
     }
 
+    /// Reads the texture's data back from the GPU, and creates an [`AlphaMask`] from it.
+    ///
+    /// Pixels with an alpha value greater than the threshold (from `0.0` to `1.0`) are
+    /// considered opaque. Like [`get_data`](Self::get_data), this is a fairly slow
+    /// operation, so the mask should be created once and then reused.
+    pub fn alpha_mask(&self, ctx: &mut Context, threshold: f32) -> AlphaMask {
+        AlphaMask::from_image_data(&self.get_data(ctx), threshold)
+    }
+
+    /// Reads the texture's data back from the GPU, and creates a [`CollisionMask`] from it,
+    /// for pixel-perfect collision checks.
+    ///
+    /// Pixels with an alpha value greater than the threshold (from `0.0` to `1.0`) are
+    /// considered solid. Like [`get_data`](Self::get_data), this is a fairly slow
+    /// operation, so the mask should be created once and then reused.
+    pub fn to_collision_mask(&self, ctx: &mut Context, alpha_threshold: f32) -> CollisionMask {
+        CollisionMask::from_image_data(&self.get_data(ctx), alpha_threshold)
+    }
+
+    /// Returns whether the pixel at the given position is opaque (i.e. has a non-zero
+    /// alpha value).
+    ///
+    /// The first time this is called, an [`AlphaMask`] is read back from the GPU and
+    /// stored alongside the texture, so subsequent calls are cheap. The stored mask is
+    /// discarded when the texture's data is changed via [`set_data`](Self::set_data) or
+    /// [`replace_data`](Self::replace_data) - however, changes made by drawing to a
+    /// [`Canvas`](super::Canvas) are not tracked. Use [`alpha_mask`](Self::alpha_mask) to
+    /// control when the mask is updated, or to use a different threshold.
+    ///
+    /// Positions outside of the texture are considered transparent.
+    pub fn is_opaque_at(&self, ctx: &mut Context, position: Vec2<i32>) -> bool {
+        let mask = self
+            .data
+            .alpha_mask
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+
+        let mask = match mask {
+            Some(mask) => mask,
+            None => {
+                let mask = Arc::new(self.alpha_mask(ctx, 0.0));
+
+                *self
+                    .data
+                    .alpha_mask
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner()) = Some(Arc::clone(&mask));
+
+                mask
+            }
+        };
+
+        mask.is_opaque_at(position)
+    }
+
     /// Writes RGBA pixel data to a specified region of the texture.
     ///
+    /// If the texture is [single channel](Self::is_single_channel), the data should
+    /// contain one byte per pixel instead.
+    ///
     /// This method requires you to provide enough data to fill the target rectangle.
     /// If you provide too little data, an error will be returned.
     /// If you provide too much data, it will be truncated.
@@ -356,7 +840,26 @@ impl Texture {
         data: &[u8],
     ) -> Result {
         ctx.device
-            .set_texture_data(&self.data.handle, data, x, y, width, height)
+            .set_texture_data(&self.data.handle, data, x, y, width, height)?;
+
+        *self
+            .data
+            .alpha_mask
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = None;
+
+        if let Some(pixel) = self.data.white_pixel() {
+            let overwritten = pixel.x >= x - 1
+                && pixel.y >= y - 1
+                && pixel.x <= x + width
+                && pixel.y <= y + height;
+
+            if overwritten {
+                self.data.set_white_pixel(WhitePixel::Missing);
+            }
+        }
+
+        Ok(())
     }
 
     /// Overwrites the entire texture with new RGBA pixel data.
@@ -378,6 +881,111 @@ impl Texture {
     }
 }
 
+/// Where a channel of a texture gets its value from when it is sampled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SwizzleSource {
+    /// The value of the red channel.
+    Red,
+
+    /// The value of the green channel.
+    Green,
+
+    /// The value of the blue channel.
+    Blue,
+
+    /// The value of the alpha channel.
+    Alpha,
+
+    /// Always `0.0`.
+    Zero,
+
+    /// Always `1.0`.
+    One,
+}
+
+/// Remaps the channels of a texture when it is sampled by a shader.
+///
+/// This is mainly useful for [single channel textures](Texture::from_r8), which only
+/// store a red channel - the swizzle determines how that channel appears to shaders.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Swizzle {
+    /// Where the red channel gets its value from.
+    pub r: SwizzleSource,
+
+    /// Where the green channel gets its value from.
+    pub g: SwizzleSource,
+
+    /// Where the blue channel gets its value from.
+    pub b: SwizzleSource,
+
+    /// Where the alpha channel gets its value from.
+    pub a: SwizzleSource,
+}
+
+impl Swizzle {
+    /// Leaves every channel as it is (`rgba`).
+    pub const IDENTITY: Swizzle = Swizzle::new(
+        SwizzleSource::Red,
+        SwizzleSource::Green,
+        SwizzleSource::Blue,
+        SwizzleSource::Alpha,
+    );
+
+    /// Copies the red channel into the other color channels, and makes the texture
+    /// opaque (`rrr1`). This is useful for grayscale images and lightmaps.
+    pub const GRAYSCALE: Swizzle = Swizzle::new(
+        SwizzleSource::Red,
+        SwizzleSource::Red,
+        SwizzleSource::Red,
+        SwizzleSource::One,
+    );
+
+    /// Makes the texture white, with the red channel used as the alpha (`111r`). This is
+    /// useful for masks and signed distance fields, which can then be tinted via
+    /// [`DrawParams::color`].
+    pub const ALPHA: Swizzle = Swizzle::new(
+        SwizzleSource::One,
+        SwizzleSource::One,
+        SwizzleSource::One,
+        SwizzleSource::Red,
+    );
+
+    /// Creates a new swizzle.
+    pub const fn new(
+        r: SwizzleSource,
+        g: SwizzleSource,
+        b: SwizzleSource,
+        a: SwizzleSource,
+    ) -> Swizzle {
+        Swizzle { r, g, b, a }
+    }
+
+    /// Applies the swizzle to an RGBA pixel.
+    fn apply(self, pixel: [u8; 4]) -> [u8; 4] {
+        let channel = |source| match source {
+            SwizzleSource::Red => pixel[0],
+            SwizzleSource::Green => pixel[1],
+            SwizzleSource::Blue => pixel[2],
+            SwizzleSource::Alpha => pixel[3],
+            SwizzleSource::Zero => 0,
+            SwizzleSource::One => 255,
+        };
+
+        [
+            channel(self.r),
+            channel(self.g),
+            channel(self.b),
+            channel(self.a),
+        ]
+    }
+}
+
+impl Default for Swizzle {
+    fn default() -> Swizzle {
+        Swizzle::IDENTITY
+    }
+}
+
 /// Filtering algorithms that can be used when scaling an image.
 ///
 /// Tetra currently defaults to using `Nearest` for all newly created textures.
@@ -392,6 +1000,118 @@ pub enum FilterMode {
     Linear,
 }
 
+/// A region of a texture, which can be drawn like a texture in its own right.
+///
+/// This is a convenient way to pass around individual sprites from a sprite sheet, rather
+/// than having to keep track of a texture and a [`Rectangle`] separately.
+///
+/// # Performance
+///
+/// Creating or cloning a `TextureRegion` is cheap, as the texture handle is
+/// reference-counted.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::graphics::{DrawParams, Origin, Rectangle, Texture, TextureRegion};
+/// # use tetra::math::Vec2;
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// let sheet = Texture::new(ctx, "./sprites.png")?;
+/// let player = TextureRegion::new(sheet, Rectangle::new(0.0, 16.0, 16.0, 24.0));
+///
+/// player.draw(
+///     ctx,
+///     DrawParams::new()
+///         .position(Vec2::new(64.0, 64.0))
+///         .origin_preset(Origin::BottomCenter),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextureRegion {
+    texture: Texture,
+    region: Rectangle,
+}
+
+impl TextureRegion {
+    /// Creates a new region of the given texture.
+    pub fn new(texture: Texture, region: Rectangle) -> TextureRegion {
+        TextureRegion { texture, region }
+    }
+
+    /// Returns a reference to the texture that the region is taken from.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns the area of the texture that the region covers.
+    pub fn region(&self) -> Rectangle {
+        self.region
+    }
+
+    /// Sets the area of the texture that the region covers.
+    pub fn set_region(&mut self, region: Rectangle) {
+        self.region = region;
+    }
+
+    /// Creates a new region from an area within this region.
+    ///
+    /// The given rectangle is relative to the top left of this region.
+    pub fn sub_region(&self, region: Rectangle) -> TextureRegion {
+        TextureRegion {
+            texture: self.texture.clone(),
+            region: Rectangle::new(
+                self.region.x + region.x,
+                self.region.y + region.y,
+                region.width,
+                region.height,
+            ),
+        }
+    }
+
+    /// Returns the width of the region.
+    pub fn width(&self) -> f32 {
+        self.region.width
+    }
+
+    /// Returns the height of the region.
+    pub fn height(&self) -> f32 {
+        self.region.height
+    }
+
+    /// Returns the size of the region.
+    pub fn size(&self) -> Vec2<f32> {
+        Vec2::new(self.region.width, self.region.height)
+    }
+
+    /// Returns the position of one of the preset origins, in pixels.
+    ///
+    /// This is useful if you need to know where the origin is (e.g. to position other
+    /// things relative to it) - if you just want to draw the region, use
+    /// [`DrawParams::origin_preset`] instead.
+    pub fn origin(&self, origin: Origin) -> Vec2<f32> {
+        origin.to_normalized() * self.size()
+    }
+
+    /// Draws the region to the screen (or to a canvas, if one is enabled).
+    pub fn draw<P>(&self, ctx: &mut Context, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        self.texture.draw_region(ctx, self.region, params);
+    }
+}
+
+impl From<Texture> for TextureRegion {
+    /// Creates a region that covers the whole texture.
+    fn from(texture: Texture) -> TextureRegion {
+        let region = Rectangle::new(0.0, 0.0, texture.width() as f32, texture.height() as f32);
+        TextureRegion { texture, region }
+    }
+}
+
 /// Information on how to slice a texture so that it can be stretched or squashed without
 /// distorting the borders.
 ///
@@ -572,6 +1292,7 @@ impl ImageData {
     /// This is not currently exposed publicly, as some more thought is needed
     /// into whether doing so would cause issues once different pixel formats
     /// are supported.
+    #[cfg_attr(feature = "backend_winit", allow(dead_code))]
     pub(crate) fn as_mut_bytes(&mut self) -> &mut [u8] {
         &mut self.data
     }
@@ -604,6 +1325,9 @@ impl ImageData {
     /// # Errors
     ///
     /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters an error.
+    /// * [`TetraError::UnsupportedFeature`] will be returned if the texture is larger than the
+    ///   [maximum texture size](crate::graphics::Capabilities::max_texture_size) supported by the device.
+    #[track_caller]
     pub fn to_texture(&self, ctx: &mut Context) -> Result<Texture> {
         Texture::from_image_data(ctx, self)
     }
@@ -647,3 +1371,33 @@ impl ImageData {
         self.transform(|_, color| color.to_premultiplied())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_surrounded_white_pixel() {
+        let mut data = vec![0; 5 * 5 * 4];
+
+        for y in 1..4 {
+            for x in 2..5 {
+                let index = (y * 5 + x) * 4;
+                data[index..index + 4].copy_from_slice(&[255; 4]);
+            }
+        }
+
+        assert_eq!(find_white_pixel(5, 5, &data), Some(Vec2::new(3, 2)));
+
+        data[(2 * 5 + 2) * 4] = 254;
+
+        // The pixel on the right edge still counts, as its missing neighbours are
+        // outside of the image.
+        assert_eq!(find_white_pixel(5, 5, &data), Some(Vec2::new(4, 2)));
+
+        data[(2 * 5 + 4) * 4] = 254;
+
+        assert_eq!(find_white_pixel(5, 5, &data), None);
+        assert_eq!(find_white_pixel(1, 1, &[255; 4]), Some(Vec2::new(0, 0)));
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::error::Result;
+use crate::graphics::mesh::{Mesh, PrimitiveTopology, Vertex, VertexBuffer};
+use crate::graphics::{Color, DrawParams, Texture};
+use crate::math::Vec2;
+use crate::time;
+use crate::Context;
+
+#[derive(Debug, Clone, Copy)]
+struct TrailPoint {
+    position: Vec2<f32>,
+    age: Duration,
+}
+
+/// A ribbon that follows a moving point, fading out over time.
+///
+/// Each time [`push`](Self::push) is called, the trail records the new position of
+/// the point it is following. Calling [`advance`](Self::advance) ages the recorded points,
+/// and removes them once they are older than the trail's lifetime. When the trail is drawn,
+/// a mesh is generated that connects the points, tapering from the [`width`](Self::width)
+/// at the head to the [`end_width`](Self::end_width) at the tail, and blending from the
+/// [`color`](Self::color) to the [`end_color`](Self::end_color).
+///
+/// This is useful for effects like sword swipes, projectile trails and motion streaks.
+///
+/// If a texture is set, it will be stretched along the length of the trail - the U
+/// co-ordinate runs from `0.0` at the head to `1.0` at the tail, and the V co-ordinate
+/// runs across the width of the ribbon.
+///
+/// # Performance
+///
+/// The trail's mesh is regenerated every time it is drawn, but the underlying vertex buffer
+/// is reused between frames, and will only be reallocated if the trail grows past its
+/// current capacity.
+#[derive(Debug, Clone)]
+pub struct Trail {
+    points: VecDeque<TrailPoint>,
+    lifetime: Duration,
+    min_distance: f32,
+    width: f32,
+    end_width: f32,
+    color: Color,
+    end_color: Color,
+    texture: Option<Texture>,
+
+    vertices: Vec<Vertex>,
+    mesh: Option<Mesh>,
+}
+
+impl Trail {
+    /// Creates a new trail, with the points it records lasting for the given amount of time.
+    ///
+    /// The trail will initially be 8 pixels wide at the head, tapering to a point at the tail,
+    /// and will fade from white to transparent.
+    pub fn new(lifetime: Duration) -> Trail {
+        Trail {
+            points: VecDeque::new(),
+            lifetime,
+            min_distance: 4.0,
+            width: 8.0,
+            end_width: 0.0,
+            color: Color::WHITE,
+            end_color: Color::WHITE.with_alpha(0.0),
+            texture: None,
+
+            vertices: Vec::new(),
+            mesh: None,
+        }
+    }
+
+    /// Records a new position for the head of the trail.
+    ///
+    /// If the head has not yet moved far enough from the previous point (as set via
+    /// [`set_min_distance`](Self::set_min_distance)), the head will be moved to the new
+    /// position rather than a new point being added.
+    pub fn push(&mut self, position: Vec2<f32>) {
+        let len = self.points.len();
+
+        if len >= 2 && self.points[len - 2].position.distance(position) < self.min_distance {
+            let head = &mut self.points[len - 1];
+
+            head.position = position;
+            head.age = Duration::from_secs(0);
+        } else {
+            self.points.push_back(TrailPoint {
+                position,
+                age: Duration::from_secs(0),
+            });
+        }
+    }
+
+    /// Ages the points in the trail, removing any that have outlived the trail's lifetime.
+    ///
+    /// This method uses the current [delta time](crate::time::get_delta_time)
+    /// to calculate how much time has passed.
+    pub fn advance(&mut self, ctx: &Context) {
+        self.advance_by(time::get_delta_time(ctx));
+    }
+
+    /// Ages the points in the trail by a specified amount, removing any that have
+    /// outlived the trail's lifetime.
+    pub fn advance_by(&mut self, duration: Duration) {
+        for point in &mut self.points {
+            point.age += duration;
+        }
+
+        while let Some(point) = self.points.front() {
+            if point.age < self.lifetime {
+                break;
+            }
+
+            self.points.pop_front();
+        }
+    }
+
+    /// Removes all of the points from the trail.
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+
+    /// Returns the number of points currently in the trail.
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    /// Returns `true` if the trail currently has no points.
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Returns an iterator over the positions of the points in the trail, from the tail
+    /// to the head.
+    pub fn points(&self) -> impl Iterator<Item = Vec2<f32>> + '_ {
+        self.points.iter().map(|p| p.position)
+    }
+
+    /// Draws the trail to the screen (or to a canvas, if one is enabled).
+    ///
+    /// Nothing will be drawn if the trail has fewer than two points.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+    ///   if the underlying graphics API encounters an error.
+    pub fn draw<P>(&mut self, ctx: &mut Context, params: P) -> Result
+    where
+        P: Into<DrawParams>,
+    {
+        self.build_vertices();
+
+        if self.vertices.is_empty() {
+            return Ok(());
+        }
+
+        let capacity = self
+            .mesh
+            .as_ref()
+            .map(|m| m.vertex_buffer().len())
+            .unwrap_or(0);
+
+        if capacity < self.vertices.len() {
+            // Grow the buffer with some headroom, so that it doesn't need to be
+            // reallocated every time the trail gets a little longer.
+            let mut data = self.vertices.clone();
+            data.resize(self.vertices.len() * 2, Vertex::default());
+
+            let mut mesh = VertexBuffer::new(ctx, &data)?.into_mesh();
+            mesh.set_topology(PrimitiveTopology::TriangleStrip);
+            mesh.set_backface_culling(false);
+
+            self.mesh = Some(mesh);
+        } else if let Some(mesh) = &self.mesh {
+            mesh.vertex_buffer().set_sub_data(ctx, 0, &self.vertices);
+        }
+
+        if let Some(mesh) = &mut self.mesh {
+            match &self.texture {
+                Some(texture) => mesh.set_texture(texture.clone()),
+                None => mesh.reset_texture(),
+            }
+
+            mesh.set_draw_range(0, self.vertices.len());
+            mesh.draw(ctx, params);
+        }
+
+        Ok(())
+    }
+
+    /// Returns how long each point in the trail lasts for.
+    pub fn lifetime(&self) -> Duration {
+        self.lifetime
+    }
+
+    /// Sets how long each point in the trail lasts for.
+    pub fn set_lifetime(&mut self, lifetime: Duration) {
+        self.lifetime = lifetime;
+    }
+
+    /// Returns the minimum distance between points in the trail.
+    pub fn min_distance(&self) -> f32 {
+        self.min_distance
+    }
+
+    /// Sets the minimum distance between points in the trail.
+    ///
+    /// Lower values will give a smoother trail, at the cost of generating more geometry.
+    ///
+    /// Defaults to `4.0`.
+    pub fn set_min_distance(&mut self, min_distance: f32) {
+        self.min_distance = min_distance;
+    }
+
+    /// Returns the width of the trail at its head.
+    pub fn width(&self) -> f32 {
+        self.width
+    }
+
+    /// Sets the width of the trail at its head.
+    ///
+    /// Defaults to `8.0`.
+    pub fn set_width(&mut self, width: f32) {
+        self.width = width;
+    }
+
+    /// Returns the width of the trail at its tail.
+    pub fn end_width(&self) -> f32 {
+        self.end_width
+    }
+
+    /// Sets the width of the trail at its tail.
+    ///
+    /// Defaults to `0.0`.
+    pub fn set_end_width(&mut self, end_width: f32) {
+        self.end_width = end_width;
+    }
+
+    /// Returns the color of the trail at its head.
+    pub fn color(&self) -> Color {
+        self.color
+    }
+
+    /// Sets the color of the trail at its head.
+    ///
+    /// Defaults to [`Color::WHITE`].
+    pub fn set_color(&mut self, color: Color) {
+        self.color = color;
+    }
+
+    /// Returns the color of the trail at its tail.
+    pub fn end_color(&self) -> Color {
+        self.end_color
+    }
+
+    /// Sets the color of the trail at its tail.
+    ///
+    /// Defaults to transparent white.
+    pub fn set_end_color(&mut self, end_color: Color) {
+        self.end_color = end_color;
+    }
+
+    /// Returns a reference to the texture being used by the trail, if one has been set.
+    pub fn texture(&self) -> Option<&Texture> {
+        self.texture.as_ref()
+    }
+
+    /// Sets the texture that will be stretched along the trail.
+    pub fn set_texture(&mut self, texture: Texture) {
+        self.texture = Some(texture);
+    }
+
+    /// Resets the trail to be untextured.
+    pub fn reset_texture(&mut self) {
+        self.texture = None;
+    }
+
+    fn build_vertices(&mut self) {
+        self.vertices.clear();
+
+        let len = self.points.len();
+
+        if len < 2 {
+            return;
+        }
+
+        let lifetime = self.lifetime.as_secs_f32();
+        let mut last_normal = Vec2::zero();
+
+        for (i, point) in self.points.iter().enumerate() {
+            let prev = self.points[i.saturating_sub(1)].position;
+            let next = self.points[(i + 1).min(len - 1)].position;
+
+            let direction = next - prev;
+
+            let normal = if direction.magnitude_squared() > f32::EPSILON {
+                Vec2::new(-direction.y, direction.x).normalized()
+            } else {
+                last_normal
+            };
+
+            last_normal = normal;
+
+            let t = if lifetime > 0.0 {
+                (point.age.as_secs_f32() / lifetime).min(1.0)
+            } else {
+                1.0
+            };
+
+            let half_width = (self.width + (self.end_width - self.width) * t) / 2.0;
+            let color = lerp_color(self.color, self.end_color, t);
+            let u = 1.0 - i as f32 / (len - 1) as f32;
+
+            self.vertices.push(Vertex::new(
+                point.position + normal * half_width,
+                Vec2::new(u, 0.0),
+                color,
+            ));
+
+            self.vertices.push(Vertex::new(
+                point.position - normal * half_width,
+                Vec2::new(u, 1.0),
+                color,
+            ));
+        }
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    Color::rgba(
+        from.r + (to.r - from.r) * t,
+        from.g + (to.g - from.g) * t,
+        from.b + (to.b - from.b) * t,
+        from.a + (to.a - from.a) * t,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_expire() {
+        let mut trail = Trail::new(Duration::from_secs(1));
+        trail.set_min_distance(5.0);
+
+        trail.push(Vec2::new(0.0, 0.0));
+        trail.push(Vec2::new(10.0, 0.0));
+        trail.advance_by(Duration::from_millis(600));
+
+        trail.push(Vec2::new(12.0, 0.0));
+        assert_eq!(trail.len(), 3);
+
+        // Too close to the previous point, so the head is moved instead.
+        trail.push(Vec2::new(13.0, 0.0));
+        assert_eq!(trail.len(), 3);
+
+        trail.advance_by(Duration::from_millis(600));
+        assert_eq!(trail.points().collect::<Vec<_>>(), [Vec2::new(13.0, 0.0)]);
+    }
+
+    #[test]
+    fn ribbon_tapers() {
+        let mut trail = Trail::new(Duration::from_secs(1));
+        trail.set_width(4.0);
+
+        trail.push(Vec2::new(0.0, 0.0));
+        trail.advance_by(Duration::from_millis(500));
+        trail.push(Vec2::new(10.0, 0.0));
+
+        trail.build_vertices();
+
+        let v = &trail.vertices;
+        assert_eq!(v.len(), 4);
+
+        // The tail is half way through its lifetime, so it should be half the width.
+        assert_eq!(v[0].position, Vec2::new(0.0, 1.0));
+        assert_eq!(v[1].position, Vec2::new(0.0, -1.0));
+        assert_eq!(v[2].position, Vec2::new(10.0, 2.0));
+        assert_eq!(v[3].position, Vec2::new(10.0, -2.0));
+
+        assert_eq!(v[0].uv, Vec2::new(1.0, 0.0));
+        assert_eq!(v[2].uv, Vec2::new(0.0, 0.0));
+        assert_eq!(v[0].color.a, 0.5);
+    }
+}
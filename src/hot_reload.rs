@@ -0,0 +1,343 @@
+//! Automatic reloading of assets when they change on disk.
+//!
+//! Assets that are loaded from a path register themselves here when they are created.
+//! The game loop then periodically checks their modification times, and patches the
+//! live resources in place when a change is detected - this means that any existing
+//! handles (and clones of them) will pick up the new data automatically.
+//!
+//! Errors that occur while reloading are logged rather than returned, and the
+//! previous version of the asset is kept.
+
+#[cfg(feature = "font_ttf")]
+use std::cell::RefCell;
+use std::error::Error;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Weak as ArcWeak};
+use std::time::{Duration, Instant, SystemTime};
+
+#[cfg(feature = "font_ttf")]
+use std::rc::{Rc, Weak};
+#[cfg(feature = "audio")]
+use std::sync::Mutex;
+
+use image::error::{ImageError, ParameterError, ParameterErrorKind};
+#[cfg(feature = "audio")]
+use once_cell::sync::Lazy;
+
+use crate::error::{Result, TetraError};
+use crate::fs;
+use crate::graphics::{
+    ImageData, Shader, ShaderSharedData, Texture, TextureSharedData, WhitePixel,
+    DEFAULT_FRAGMENT_SHADER, DEFAULT_VERTEX_SHADER,
+};
+use crate::Context;
+
+#[cfg(feature = "audio")]
+use crate::audio::{Sound, SoundData};
+
+#[cfg(feature = "font_ttf")]
+use crate::graphics::text::{Font, FontCache, VectorFontBuilder};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Sounds can be created without access to the context (and potentially on another
+// thread), so they have to be tracked separately.
+#[cfg(feature = "audio")]
+static SOUNDS: Lazy<Mutex<Vec<Watched<ArcWeak<SoundData>>>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+struct Watched<T> {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    resource: T,
+}
+
+impl<T> Watched<T> {
+    fn new(path: &Path, resource: T) -> Watched<T> {
+        Watched {
+            path: path.to_owned(),
+            modified: fs::modified(path),
+            resource,
+        }
+    }
+
+    /// Returns `true` if the file has changed since the last time this was called.
+    fn check(&mut self) -> bool {
+        let modified = fs::modified(&self.path);
+
+        if modified.is_some() && modified != self.modified {
+            self.modified = modified;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+struct WatchedShader {
+    data: ArcWeak<ShaderSharedData>,
+    vertex: Option<Watched<()>>,
+    fragment: Option<Watched<()>>,
+}
+
+#[cfg(feature = "font_ttf")]
+struct WatchedFont {
+    data: Weak<RefCell<FontCache>>,
+    size: f32,
+}
+
+pub(crate) struct HotReloadContext {
+    last_poll: Instant,
+
+    textures: Vec<Watched<ArcWeak<TextureSharedData>>>,
+    shaders: Vec<WatchedShader>,
+
+    #[cfg(feature = "font_ttf")]
+    fonts: Vec<Watched<WatchedFont>>,
+}
+
+impl HotReloadContext {
+    pub(crate) fn new() -> HotReloadContext {
+        HotReloadContext {
+            last_poll: Instant::now(),
+
+            textures: Vec::new(),
+            shaders: Vec::new(),
+
+            #[cfg(feature = "font_ttf")]
+            fonts: Vec::new(),
+        }
+    }
+}
+
+pub(crate) fn watch_texture(ctx: &mut Context, path: &Path, texture: &Texture) {
+    ctx.hot_reload
+        .textures
+        .push(Watched::new(path, Arc::downgrade(&texture.data)));
+}
+
+pub(crate) fn watch_shader(
+    ctx: &mut Context,
+    vertex_path: Option<&Path>,
+    fragment_path: Option<&Path>,
+    shader: &Shader,
+) {
+    ctx.hot_reload.shaders.push(WatchedShader {
+        data: Arc::downgrade(&shader.data),
+        vertex: vertex_path.map(|p| Watched::new(p, ())),
+        fragment: fragment_path.map(|p| Watched::new(p, ())),
+    });
+}
+
+#[cfg(feature = "font_ttf")]
+pub(crate) fn watch_font(ctx: &mut Context, path: &Path, size: f32, font: &Font) {
+    ctx.hot_reload.fonts.push(Watched::new(
+        path,
+        WatchedFont {
+            data: Rc::downgrade(&font.data),
+            size,
+        },
+    ));
+}
+
+#[cfg(feature = "audio")]
+pub(crate) fn watch_sound(path: &Path, sound: &Sound) {
+    sounds().push(Watched::new(path, Arc::downgrade(&sound.data)));
+}
+
+#[cfg(feature = "audio")]
+fn sounds() -> std::sync::MutexGuard<'static, Vec<Watched<ArcWeak<SoundData>>>> {
+    SOUNDS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// Checks all of the watched assets for changes, reloading them if needed.
+///
+/// This is called every frame, but will only hit the filesystem every
+/// `POLL_INTERVAL`.
+pub(crate) fn poll(ctx: &mut Context) {
+    if ctx.hot_reload.last_poll.elapsed() < POLL_INTERVAL {
+        return;
+    }
+
+    ctx.hot_reload.last_poll = Instant::now();
+
+    poll_textures(ctx);
+    poll_shaders(ctx);
+
+    #[cfg(feature = "font_ttf")]
+    poll_fonts(ctx);
+
+    #[cfg(feature = "audio")]
+    poll_sounds();
+}
+
+fn poll_textures(ctx: &mut Context) {
+    let mut textures = std::mem::take(&mut ctx.hot_reload.textures);
+
+    textures.retain(|watched| watched.resource.strong_count() > 0);
+
+    for watched in &mut textures {
+        if let Some(data) = watched.resource.upgrade() {
+            if watched.check() {
+                report(&watched.path, reload_texture(ctx, &watched.path, &data));
+            }
+        }
+    }
+
+    ctx.hot_reload.textures = textures;
+}
+
+fn reload_texture(ctx: &mut Context, path: &Path, data: &TextureSharedData) -> Result {
+    let mut image = ImageData::from_file(path)?;
+    let (width, height) = image.size();
+
+    if data.premultiplied.load(Ordering::Relaxed) {
+        image.premultiply();
+    }
+
+    if (width, height) != (data.handle.width(), data.handle.height()) {
+        return Err(TetraError::InvalidTexture(ImageError::Parameter(
+            ParameterError::from_kind(ParameterErrorKind::Generic(
+                "the texture changed size, and textures cannot be resized while the game is running"
+                    .into(),
+            )),
+        )));
+    }
+
+    ctx.device
+        .set_texture_data(&data.handle, image.as_bytes(), 0, 0, width, height)?;
+
+    data.set_white_pixel(WhitePixel::Unsearched);
+
+    Ok(())
+}
+
+fn poll_shaders(ctx: &mut Context) {
+    let mut shaders = std::mem::take(&mut ctx.hot_reload.shaders);
+
+    shaders.retain(|watched| watched.data.strong_count() > 0);
+
+    for watched in &mut shaders {
+        if let Some(data) = watched.data.upgrade() {
+            // Both files need to be checked, so that their timestamps stay up to date.
+            let vertex_changed = watched.vertex.as_mut().map(Watched::check) == Some(true);
+            let fragment_changed = watched.fragment.as_mut().map(Watched::check) == Some(true);
+
+            if vertex_changed || fragment_changed {
+                let changed = if vertex_changed {
+                    &watched.vertex
+                } else {
+                    &watched.fragment
+                };
+
+                let path = &changed
+                    .as_ref()
+                    .expect("changed file should be watched")
+                    .path;
+
+                let result = reload_shader(
+                    ctx,
+                    watched.vertex.as_ref().map(|w| w.path.as_path()),
+                    watched.fragment.as_ref().map(|w| w.path.as_path()),
+                    &data,
+                );
+
+                report(path, result);
+            }
+        }
+    }
+
+    ctx.hot_reload.shaders = shaders;
+}
+
+fn reload_shader(
+    ctx: &mut Context,
+    vertex_path: Option<&Path>,
+    fragment_path: Option<&Path>,
+    data: &ShaderSharedData,
+) -> Result {
+    let vertex = match vertex_path {
+        Some(path) => fs::read_to_string(path)?,
+        None => DEFAULT_VERTEX_SHADER.to_owned(),
+    };
+
+    let fragment = match fragment_path {
+        Some(path) => fs::read_to_string(path)?,
+        None => DEFAULT_FRAGMENT_SHADER.to_owned(),
+    };
+
+    ctx.device.link_shader(&data.handle, &vertex, &fragment)?;
+    data.refresh_reflection(&ctx.device);
+
+    Ok(())
+}
+
+#[cfg(feature = "font_ttf")]
+fn poll_fonts(ctx: &mut Context) {
+    let mut fonts = std::mem::take(&mut ctx.hot_reload.fonts);
+
+    fonts.retain(|watched| watched.resource.data.strong_count() > 0);
+
+    for watched in &mut fonts {
+        if let Some(data) = watched.resource.data.upgrade() {
+            if watched.check() {
+                let size = watched.resource.size;
+                report(&watched.path, reload_font(ctx, &watched.path, size, &data));
+            }
+        }
+    }
+
+    ctx.hot_reload.fonts = fonts;
+}
+
+#[cfg(feature = "font_ttf")]
+fn reload_font(ctx: &mut Context, path: &Path, size: f32, data: &RefCell<FontCache>) -> Result {
+    let font = VectorFontBuilder::new(path)?.with_size(ctx, size)?;
+
+    // The new font was only just created, so nothing else can be holding on to its data.
+    let new_cache = Rc::try_unwrap(font.data)
+        .map_err(|_| ())
+        .expect("newly created font should not be shared")
+        .into_inner();
+
+    data.borrow_mut().replace(new_cache);
+
+    Ok(())
+}
+
+#[cfg(feature = "audio")]
+fn poll_sounds() {
+    let mut sounds = sounds();
+
+    sounds.retain(|watched| watched.resource.strong_count() > 0);
+
+    for watched in sounds.iter_mut() {
+        if let Some(data) = watched.resource.upgrade() {
+            if watched.check() {
+                let result = fs::read(&watched.path).map(|bytes| data.replace(bytes.into()));
+                report(&watched.path, result);
+            }
+        }
+    }
+}
+
+fn report(path: &Path, result: Result) {
+    match result {
+        Ok(()) => log::info!(target: "tetra::hot_reload", "Reloaded {}", path.display()),
+        Err(e) => match e.source() {
+            Some(source) => log::error!(
+                target: "tetra::hot_reload",
+                "Failed to reload {}: {} ({})",
+                path.display(),
+                e,
+                source
+            ),
+            None => log::error!(
+                target: "tetra::hot_reload",
+                "Failed to reload {}: {}",
+                path.display(),
+                e
+            ),
+        },
+    }
+}
@@ -0,0 +1,298 @@
+//! Functions and types relating to localization.
+//!
+//! Translations are stored as simple key-value files, with one string per line:
+//!
+//! ```text
+//! # Lines starting with a '#' are comments.
+//! menu.start = Start Game
+//! menu.quit = Quit
+//! hud.score = Score: {score}
+//! dialog.intro = First line\nSecond line
+//! ```
+//!
+//! Values can contain named arguments in curly braces, which are filled in via
+//! [`translate_with_args`]. A literal brace can be written by doubling it (`{{` or `}}`),
+//! and `\n` is converted into a line break.
+//!
+//! Strings are looked up in the current language first, then in the fallback language (if
+//! one is set). If neither contains the key, the key itself is returned, so that missing
+//! translations are easy to spot.
+//!
+//! [`Text::localized`](crate::graphics::text::Text::localized) can be used to create text
+//! that automatically updates when the language is changed. If a font has been assigned to
+//! the current language via [`set_language_font`], that will be used in place of the text's
+//! own font - this is useful for languages that need glyphs your main font doesn't have.
+//!
+//! ```no_run
+//! # use tetra::Context;
+//! use tetra::i18n;
+//!
+//! # fn example(ctx: &mut Context) -> tetra::Result {
+//! i18n::load_language(ctx, "en", "./lang/en.txt")?;
+//! i18n::load_language(ctx, "fr", "./lang/fr.txt")?;
+//!
+//! i18n::set_fallback_language(ctx, "en");
+//! i18n::set_language(ctx, "fr");
+//!
+//! let score = i18n::translate_with_args(ctx, "hud.score", &[("score", &100)]);
+//! # Ok(())
+//! # }
+//! ```
+
+use std::fmt::Display;
+use std::path::Path;
+
+use hashbrown::HashMap;
+
+use crate::error::{Result, TetraError};
+use crate::fs;
+use crate::graphics::text::Font;
+use crate::Context;
+
+/// A named argument that can be substituted into a translated string.
+pub type Arg<'a> = (&'a str, &'a dyn Display);
+
+#[derive(Default)]
+struct Language {
+    strings: HashMap<String, String>,
+    font: Option<Font>,
+}
+
+pub(crate) struct I18nContext {
+    languages: HashMap<String, Language>,
+    current: Option<String>,
+    fallback: Option<String>,
+
+    // Incremented whenever something changes that could affect the result of
+    // a lookup, so that localized text knows when to update.
+    generation: u64,
+}
+
+impl I18nContext {
+    pub(crate) fn new() -> I18nContext {
+        I18nContext {
+            languages: HashMap::new(),
+            current: None,
+            fallback: None,
+            generation: 0,
+        }
+    }
+
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    fn lookup(&self, key: &str) -> Option<&str> {
+        let current = self.current.as_deref().and_then(|l| self.languages.get(l));
+        let fallback = self.fallback.as_deref().and_then(|l| self.languages.get(l));
+
+        current
+            .and_then(|l| l.strings.get(key))
+            .or_else(|| fallback.and_then(|l| l.strings.get(key)))
+            .map(String::as_str)
+    }
+
+    pub(crate) fn font(&self) -> Option<&Font> {
+        self.current
+            .as_deref()
+            .and_then(|l| self.languages.get(l))
+            .and_then(|l| l.font.as_ref())
+    }
+}
+
+/// Loads translations for a language from a file.
+///
+/// If the language already has translations loaded, the new strings will be merged in,
+/// replacing any existing strings with the same key.
+///
+/// # Errors
+///
+/// * [`TetraError::FailedToLoadAsset`] will be returned if the file could not be loaded.
+/// * [`TetraError::InvalidLocalization`] will be returned if the file could not be parsed.
+pub fn load_language<P>(ctx: &mut Context, language: &str, path: P) -> Result
+where
+    P: AsRef<Path>,
+{
+    let source = fs::read_to_string(path)?;
+    add_language(ctx, language, &source)
+}
+
+/// Adds translations for a language from a string.
+///
+/// If the language already has translations loaded, the new strings will be merged in,
+/// replacing any existing strings with the same key.
+///
+/// # Errors
+///
+/// * [`TetraError::InvalidLocalization`] will be returned if the source could not be parsed.
+pub fn add_language(ctx: &mut Context, language: &str, source: &str) -> Result {
+    let strings = parse(source)?;
+
+    ctx.i18n
+        .languages
+        .entry(language.to_owned())
+        .or_default()
+        .strings
+        .extend(strings);
+
+    ctx.i18n.generation += 1;
+
+    Ok(())
+}
+
+/// Returns the names of the languages that have been loaded, in arbitrary order.
+pub fn get_languages(ctx: &Context) -> impl Iterator<Item = &str> {
+    ctx.i18n.languages.keys().map(String::as_str)
+}
+
+/// Returns the current language, if one has been set.
+pub fn get_language(ctx: &Context) -> Option<&str> {
+    ctx.i18n.current.as_deref()
+}
+
+/// Sets the current language.
+///
+/// The language does not have to be loaded yet - if it is not, lookups will use the fallback
+/// language until it is.
+pub fn set_language(ctx: &mut Context, language: &str) {
+    ctx.i18n.current = Some(language.to_owned());
+    ctx.i18n.generation += 1;
+}
+
+/// Returns the fallback language, if one has been set.
+pub fn get_fallback_language(ctx: &Context) -> Option<&str> {
+    ctx.i18n.fallback.as_deref()
+}
+
+/// Sets the language that will be used when a string is missing from the current language.
+pub fn set_fallback_language(ctx: &mut Context, language: &str) {
+    ctx.i18n.fallback = Some(language.to_owned());
+    ctx.i18n.generation += 1;
+}
+
+/// Sets the font that localized text should use while the given language is active.
+///
+/// Passing `None` will make localized text use its own font again.
+pub fn set_language_font(ctx: &mut Context, language: &str, font: Option<Font>) {
+    ctx.i18n
+        .languages
+        .entry(language.to_owned())
+        .or_default()
+        .font = font;
+
+    ctx.i18n.generation += 1;
+}
+
+/// Returns the translation of the given key.
+///
+/// If the key is not present in either the current or the fallback language, the key itself
+/// will be returned.
+pub fn translate<'a>(ctx: &'a Context, key: &'a str) -> &'a str {
+    ctx.i18n.lookup(key).unwrap_or(key)
+}
+
+/// Returns the translation of the given key, with the given arguments substituted in.
+///
+/// Placeholders that do not have a matching argument will be left as-is.
+pub fn translate_with_args(ctx: &Context, key: &str, args: &[Arg<'_>]) -> String {
+    format(translate(ctx, key), args)
+}
+
+fn parse(source: &str) -> Result<HashMap<String, String>> {
+    let mut strings = HashMap::new();
+
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = match line.split_once('=') {
+            Some((key, value)) if !key.trim().is_empty() => (key.trim(), value.trim()),
+            _ => {
+                return Err(TetraError::InvalidLocalization(format!(
+                    "expected 'key = value' on line {}",
+                    i + 1
+                )))
+            }
+        };
+
+        strings.insert(key.to_owned(), value.replace("\\n", "\n"));
+    }
+
+    Ok(strings)
+}
+
+fn format(template: &str, args: &[Arg<'_>]) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        output.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if rest.starts_with("{{") || rest.starts_with("}}") {
+            output.push_str(&rest[..1]);
+            rest = &rest[2..];
+            continue;
+        }
+
+        let arg = if rest.starts_with('{') {
+            rest.find('}').and_then(|end| {
+                let name = &rest[1..end];
+
+                args.iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, value)| (end, value))
+            })
+        } else {
+            None
+        };
+
+        match arg {
+            Some((end, value)) => {
+                output.push_str(&value.to_string());
+                rest = &rest[end + 1..];
+            }
+            None => {
+                output.push_str(&rest[..1]);
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    output.push_str(rest);
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_strings() {
+        let strings = parse("# Comment\n\nmenu.start = Start Game\nintro=A\\nB\n").unwrap();
+
+        assert_eq!(strings.len(), 2);
+        assert_eq!(strings["menu.start"], "Start Game");
+        assert_eq!(strings["intro"], "A\nB");
+
+        assert!(parse("missing equals sign").is_err());
+        assert!(parse(" = no key").is_err());
+    }
+
+    #[test]
+    fn format_args() {
+        assert_eq!(
+            format(
+                "{name} has {count} coins",
+                &[("name", &"Bob"), ("count", &3)]
+            ),
+            "Bob has 3 coins"
+        );
+
+        assert_eq!(format("{{literal}} {missing}", &[]), "{literal} {missing}");
+        assert_eq!(format("unclosed {", &[]), "unclosed {");
+    }
+}
@@ -23,18 +23,31 @@
 //! The [`text_input`](https://github.com/17cupsofcoffee/tetra/blob/main/examples/text_input.rs)
 //! example demonstrates how to handle text entry.
 
+// The winit backend does not support gamepads, so the internal state management is unused.
+#[cfg_attr(feature = "backend_winit", allow(dead_code))]
 mod gamepad;
+mod haptics;
 mod keyboard;
 mod mouse;
+mod prompts;
+mod raw;
+#[cfg(feature = "virtual_gamepads")]
+mod virtual_gamepad;
 
 use hashbrown::HashSet;
 
+use crate::graphics::Rectangle;
 use crate::math::Vec2;
 use crate::{Context, Result};
 
 pub use gamepad::*;
+pub use haptics::*;
 pub use keyboard::*;
 pub use mouse::*;
+pub use prompts::*;
+pub use raw::*;
+#[cfg(feature = "virtual_gamepads")]
+pub use virtual_gamepad::*;
 
 pub(crate) struct InputContext {
     keys_down: HashSet<Key>,
@@ -48,6 +61,8 @@ pub(crate) struct InputContext {
     mouse_buttons_released: HashSet<MouseButton>,
     mouse_position: Vec2<f32>,
     mouse_wheel_movement: Vec2<i32>,
+    mouse_wheel_delta: Vec2<f32>,
+    mouse_wheel_remainder: Vec2<f32>,
 
     current_text_input: Option<String>,
 
@@ -68,6 +83,8 @@ impl InputContext {
             mouse_buttons_released: HashSet::new(),
             mouse_position: Vec2::zero(),
             mouse_wheel_movement: Vec2::zero(),
+            mouse_wheel_delta: Vec2::zero(),
+            mouse_wheel_remainder: Vec2::zero(),
 
             current_text_input: None,
 
@@ -82,6 +99,7 @@ pub(crate) fn clear(ctx: &mut Context) {
     ctx.input.mouse_buttons_pressed.clear();
     ctx.input.mouse_buttons_released.clear();
     ctx.input.mouse_wheel_movement = Vec2::zero();
+    ctx.input.mouse_wheel_delta = Vec2::zero();
 
     ctx.input.current_text_input = None;
 
@@ -93,10 +111,49 @@ pub(crate) fn clear(ctx: &mut Context) {
 
 /// Returns the text that the user entered since the last update.
 /// This will match the user's keyboard and OS settings.
+///
+/// This will always return `None` while text input is inactive - see [`start_text_input`].
 pub fn get_text_input(ctx: &Context) -> Option<&str> {
     ctx.input.current_text_input.as_deref()
 }
 
+/// Starts accepting text input.
+///
+/// While text input is active, [`TextInput`](crate::Event::TextInput) and
+/// [`TextEditing`](crate::Event::TextEditing) events will be fired as the user types,
+/// and [`get_text_input`] will return the entered text. On platforms that use an
+/// on-screen keyboard (e.g. touch devices), this will also show the keyboard.
+///
+/// Text input is active by default on desktop platforms. It is a good idea to stop it
+/// (via [`stop_text_input`]) when the player is not interacting with a text field, as
+/// some input method editors will otherwise intercept keypresses that are meant
+/// for the game.
+///
+/// When using the winit backend, this only controls whether text events are fired.
+pub fn start_text_input(ctx: &mut Context) {
+    ctx.window.start_text_input();
+}
+
+/// Stops accepting text input, and hides the on-screen keyboard if it is visible.
+pub fn stop_text_input(ctx: &mut Context) {
+    ctx.window.stop_text_input();
+}
+
+/// Returns whether or not text input is currently active.
+pub fn is_text_input_active(ctx: &Context) -> bool {
+    ctx.window.is_text_input_active()
+}
+
+/// Sets the area of the window (in physical pixels) where text is being entered.
+///
+/// This is used as a hint for where the input method editor's candidate list should be
+/// displayed, so that it does not cover up the text field. On some platforms, it is
+/// also used to decide whether the window needs to be scrolled to keep the text field
+/// visible above the on-screen keyboard.
+pub fn set_text_input_rect(ctx: &mut Context, rect: Rectangle<i32>) {
+    ctx.window.set_text_input_rect(rect);
+}
+
 /// Gets the text currently stored in the system's clipboard.
 ///
 /// # Errors
@@ -1,10 +1,15 @@
 use hashbrown::{HashMap, HashSet};
 
+use std::path::Path;
+
+use crate::fs;
+use crate::input::GamepadType;
 use crate::math::Vec2;
-use crate::Context;
+use crate::{Context, Result};
 
 pub(crate) struct GamepadState {
     pub platform_id: u32,
+    pub virtual_gamepad: Option<VirtualGamepad>,
     pub buttons_down: HashSet<GamepadButton>,
     pub buttons_pressed: HashSet<GamepadButton>,
     pub buttons_released: HashSet<GamepadButton>,
@@ -15,6 +20,7 @@ impl GamepadState {
     pub(crate) fn new(platform_id: u32) -> GamepadState {
         GamepadState {
             platform_id,
+            virtual_gamepad: None,
             buttons_down: HashSet::new(),
             buttons_pressed: HashSet::new(),
             buttons_released: HashSet::new(),
@@ -22,6 +28,15 @@ impl GamepadState {
         }
     }
 
+    /// Returns the ID that the platform backend uses for this gamepad, or `None` if
+    /// it is a virtual gamepad (in which case the platform does not know about it).
+    pub(crate) fn platform_id(&self) -> Option<u32> {
+        match self.virtual_gamepad {
+            Some(_) => None,
+            None => Some(self.platform_id),
+        }
+    }
+
     pub(crate) fn set_button_down(&mut self, btn: GamepadButton) -> bool {
         let was_up = self.buttons_down.insert(btn);
 
@@ -47,6 +62,14 @@ impl GamepadState {
     }
 }
 
+/// The details of a gamepad that was connected by the game (e.g. via a Steam Input
+/// integration), rather than by the platform.
+#[cfg_attr(not(feature = "virtual_gamepads"), allow(dead_code))]
+pub(crate) struct VirtualGamepad {
+    pub name: String,
+    pub gamepad_type: GamepadType,
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 #[cfg_attr(
     feature = "serde_support",
@@ -124,9 +147,95 @@ pub fn is_gamepad_connected(ctx: &Context, gamepad_id: usize) -> bool {
 
 /// Returns the name of the specified gamepad, or [`None`] if it is not connected.
 pub fn get_gamepad_name(ctx: &Context, gamepad_id: usize) -> Option<String> {
-    get_gamepad(ctx, gamepad_id)
-        .map(|g| g.platform_id)
-        .map(|id| ctx.window.get_gamepad_name(id))
+    get_gamepad(ctx, gamepad_id).map(|pad| match &pad.virtual_gamepad {
+        Some(virtual_gamepad) => virtual_gamepad.name.clone(),
+        None => ctx.window.get_gamepad_name(pad.platform_id),
+    })
+}
+
+/// Returns an iterator of the IDs of the gamepads that are currently connected,
+/// in ascending order.
+///
+/// As gamepad IDs are reused when a gamepad is disconnected, there may be gaps in the
+/// sequence - this function can be used to visit each connected gamepad without having
+/// to probe the IDs one by one.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::input;
+/// # fn example(ctx: &Context) {
+/// for id in input::get_connected_gamepads(ctx) {
+///     if let Some(info) = input::get_gamepad_info(ctx, id) {
+///         println!("Player {}: {}", id + 1, info.name);
+///     }
+/// }
+/// # }
+/// ```
+pub fn get_connected_gamepads(ctx: &Context) -> impl Iterator<Item = usize> + '_ {
+    ctx.input
+        .pads
+        .iter()
+        .enumerate()
+        .filter(|(_, pad)| pad.is_some())
+        .map(|(id, _)| id)
+}
+
+/// Returns the number of gamepads that are currently connected.
+pub fn get_gamepad_count(ctx: &Context) -> usize {
+    ctx.input.pads.iter().flatten().count()
+}
+
+/// Information about a connected gamepad, as returned by [`get_gamepad_info`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GamepadInfo {
+    /// The ID of the gamepad.
+    pub id: usize,
+
+    /// The name of the gamepad, as reported by the platform.
+    pub name: String,
+
+    /// The type of the gamepad.
+    pub gamepad_type: GamepadType,
+
+    /// Whether the gamepad supports vibration.
+    pub vibration_supported: bool,
+}
+
+/// Returns information about the specified gamepad, or [`None`] if it is not connected.
+pub fn get_gamepad_info(ctx: &Context, gamepad_id: usize) -> Option<GamepadInfo> {
+    get_gamepad_name(ctx, gamepad_id).map(|name| GamepadInfo {
+        id: gamepad_id,
+        name,
+        gamepad_type: get_gamepad_type(ctx, gamepad_id),
+        vibration_supported: is_gamepad_vibration_supported(ctx, gamepad_id),
+    })
+}
+
+/// Returns the type of the specified gamepad.
+///
+/// If the platform cannot identify the gamepad, a guess will be made based on its name.
+/// If the gamepad is disconnected, or its type could not be guessed, this will return
+/// [`GamepadType::Unknown`].
+///
+/// This can be used to show button prompts that match the player's controller, via
+/// [`GamepadType::layout`].
+pub fn get_gamepad_type(ctx: &Context, gamepad_id: usize) -> GamepadType {
+    match get_gamepad(ctx, gamepad_id) {
+        Some(GamepadState {
+            virtual_gamepad: Some(virtual_gamepad),
+            ..
+        }) => virtual_gamepad.gamepad_type,
+        Some(pad) => match ctx.window.get_gamepad_type(pad.platform_id) {
+            GamepadType::Unknown => {
+                GamepadType::from_name(&ctx.window.get_gamepad_name(pad.platform_id))
+            }
+            gamepad_type => gamepad_type,
+        },
+        None => GamepadType::Unknown,
+    }
 }
 
 /// Returns true if the specified gamepad button is currently down.
@@ -279,8 +388,8 @@ pub fn get_gamepad_stick_position(
 ///
 /// If the gamepad is disconnected, this will always return `false`.
 pub fn is_gamepad_vibration_supported(ctx: &Context, gamepad_id: usize) -> bool {
-    if let Some(pad) = get_gamepad(ctx, gamepad_id) {
-        ctx.window.is_gamepad_vibration_supported(pad.platform_id)
+    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).and_then(|g| g.platform_id()) {
+        ctx.window.is_gamepad_vibration_supported(platform_id)
     } else {
         false
     }
@@ -288,7 +397,7 @@ pub fn is_gamepad_vibration_supported(ctx: &Context, gamepad_id: usize) -> bool
 
 /// Sets the specified gamepad's motors to vibrate indefinitely.
 pub fn set_gamepad_vibration(ctx: &mut Context, gamepad_id: usize, strength: f32) {
-    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).map(|g| g.platform_id) {
+    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).and_then(|g| g.platform_id()) {
         ctx.window.set_gamepad_vibration(platform_id, strength);
     }
 }
@@ -296,7 +405,7 @@ pub fn set_gamepad_vibration(ctx: &mut Context, gamepad_id: usize, strength: f32
 /// Sets the specified gamepad's motors to vibrate for a set duration, specified in milliseconds.
 /// After this time has passed, the vibration will automatically stop.
 pub fn start_gamepad_vibration(ctx: &mut Context, gamepad_id: usize, strength: f32, duration: u32) {
-    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).map(|g| g.platform_id) {
+    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).and_then(|g| g.platform_id()) {
         ctx.window
             .start_gamepad_vibration(platform_id, strength, duration);
     }
@@ -304,22 +413,85 @@ pub fn start_gamepad_vibration(ctx: &mut Context, gamepad_id: usize, strength: f
 
 /// Stops the specified gamepad's motors from vibrating.
 pub fn stop_gamepad_vibration(ctx: &mut Context, gamepad_id: usize) {
-    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).map(|g| g.platform_id) {
+    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).and_then(|g| g.platform_id()) {
         ctx.window.stop_gamepad_vibration(platform_id);
     }
 }
 
+/// Adds gamepad mappings in the [SDL_GameControllerDB](https://github.com/gabomdq/SDL_GameControllerDB)
+/// format, so that controllers that are not recognized out of the box can be used.
+///
+/// The mappings can either be a single mapping string, or the contents of a whole
+/// `gamecontrollerdb.txt` file (one mapping per line). Lines that are empty or start with
+/// `#` are ignored, as are mappings for other platforms. If a mapping already exists for
+/// a controller, it will be replaced.
+///
+/// Controllers that are already plugged in will be updated to use the new mappings, and
+/// controllers that were previously unrecognized will be connected.
+///
+/// Returns the number of mappings that were added.
+///
+/// When using the winit backend, gamepads are not supported, so this will always
+/// return `Ok(0)`.
+///
+/// # Errors
+///
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+///   if the mappings could not be parsed.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::input;
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// input::add_gamepad_mappings(
+///     ctx,
+///     "03000000c82d00000060000000000000,8BitDo SF30 Pro,a:b1,b:b0,x:b4,y:b3,platform:Windows,",
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub fn add_gamepad_mappings(ctx: &mut Context, mappings: &str) -> Result<usize> {
+    ctx.window.add_gamepad_mappings(mappings)
+}
+
+/// Adds gamepad mappings from a file in the
+/// [SDL_GameControllerDB](https://github.com/gabomdq/SDL_GameControllerDB) format.
+///
+/// The file will be loaded via the [`fs`](crate::fs) module, so it can be read from
+/// mounted archives. See [`add_gamepad_mappings`] for details of how the mappings
+/// are applied.
+///
+/// # Errors
+///
+/// * [`TetraError::FailedToLoadAsset`](crate::TetraError::FailedToLoadAsset) will be returned
+///   if the file could not be loaded.
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+///   if the mappings could not be parsed.
+pub fn add_gamepad_mappings_from_file<P>(ctx: &mut Context, path: P) -> Result<usize>
+where
+    P: AsRef<Path>,
+{
+    let mappings = fs::read_to_string(path)?;
+    add_gamepad_mappings(ctx, &mappings)
+}
+
 pub(crate) fn add_gamepad(ctx: &mut Context, platform_id: u32) -> usize {
+    insert_gamepad(ctx, GamepadState::new(platform_id))
+}
+
+pub(crate) fn insert_gamepad(ctx: &mut Context, pad: GamepadState) -> usize {
     for (i, slot) in ctx.input.pads.iter_mut().enumerate() {
         if slot.is_none() {
-            *slot = Some(GamepadState::new(platform_id));
+            *slot = Some(pad);
             return i;
         }
     }
 
     // There wasn't an existing free slot...
     let i = ctx.input.pads.len();
-    ctx.input.pads.push(Some(GamepadState::new(platform_id)));
+    ctx.input.pads.push(Some(pad));
     i
 }
 
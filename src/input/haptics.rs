@@ -0,0 +1,242 @@
+use crate::input::get_gamepad;
+use crate::Context;
+
+/// A trigger on a gamepad.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[allow(missing_docs)]
+pub enum GamepadTrigger {
+    LeftTrigger,
+    RightTrigger,
+}
+
+/// The haptic features that a gamepad supports, as returned by [`get_gamepad_haptics`].
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct GamepadHaptics {
+    /// Whether the gamepad has rumble motors.
+    ///
+    /// If this is `true`, the strength of the low and high frequency motors can be
+    /// controlled separately via [`start_gamepad_rumble`].
+    pub rumble: bool,
+
+    /// Whether the gamepad has rumble motors in its triggers (e.g. the impulse triggers
+    /// on an Xbox One controller).
+    pub trigger_rumble: bool,
+
+    /// Whether the gamepad has triggers that can push back against the player's fingers
+    /// (e.g. the adaptive triggers on a PlayStation 5 controller).
+    pub adaptive_triggers: bool,
+}
+
+/// An effect that can be applied to an adaptive trigger, via [`set_gamepad_trigger_effect`].
+///
+/// Positions are measured from `0.0` (the trigger is at rest) to `1.0` (the trigger
+/// is fully pressed). Strengths, amplitudes and frequencies are also measured from
+/// `0.0` to `1.0`.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum TriggerEffect {
+    /// The trigger moves freely.
+    Off,
+
+    /// The trigger resists being pressed past the start position.
+    Resistance {
+        /// The position at which the resistance starts.
+        start: f32,
+
+        /// The strength of the resistance.
+        strength: f32,
+    },
+
+    /// The trigger resists being pressed between the start and end positions, and then
+    /// gives way, like the trigger of a gun.
+    Weapon {
+        /// The position at which the resistance starts.
+        start: f32,
+
+        /// The position at which the trigger gives way.
+        end: f32,
+
+        /// The strength of the resistance.
+        strength: f32,
+    },
+
+    /// The trigger vibrates when pressed past the start position.
+    Vibration {
+        /// The position at which the vibration starts.
+        start: f32,
+
+        /// The amplitude of the vibration.
+        amplitude: f32,
+
+        /// The frequency of the vibration.
+        frequency: f32,
+    },
+}
+
+impl TriggerEffect {
+    /// Encodes the effect in the format expected by the DualSense controller.
+    #[cfg_attr(feature = "backend_winit", allow(dead_code))]
+    pub(crate) fn to_dualsense(self) -> [u8; 11] {
+        fn byte(value: f32) -> u8 {
+            (value.clamp(0.0, 1.0) * 255.0).round() as u8
+        }
+
+        let mut data = [0; 11];
+
+        match self {
+            TriggerEffect::Off => {
+                data[0] = 0x05;
+            }
+
+            TriggerEffect::Resistance { start, strength } => {
+                data[0] = 0x01;
+                data[1] = byte(start);
+                data[2] = byte(strength);
+            }
+
+            TriggerEffect::Weapon {
+                start,
+                end,
+                strength,
+            } => {
+                data[0] = 0x02;
+                data[1] = byte(start);
+                data[2] = byte(end.max(start));
+                data[3] = byte(strength);
+            }
+
+            TriggerEffect::Vibration {
+                start,
+                amplitude,
+                frequency,
+            } => {
+                data[0] = 0x06;
+                data[1] = byte(frequency);
+                data[2] = byte(amplitude);
+                data[3] = byte(start);
+            }
+        }
+
+        data
+    }
+}
+
+/// Returns the haptic features that the specified gamepad supports.
+///
+/// If the gamepad is disconnected, or the platform backend does not support haptics,
+/// all of the features will be reported as unsupported.
+pub fn get_gamepad_haptics(ctx: &Context, gamepad_id: usize) -> GamepadHaptics {
+    match get_gamepad(ctx, gamepad_id).and_then(|g| g.platform_id()) {
+        Some(platform_id) => ctx.window.get_gamepad_haptics(platform_id),
+        None => GamepadHaptics::default(),
+    }
+}
+
+/// Sets the specified gamepad's low and high frequency motors to vibrate for a set
+/// duration, specified in milliseconds. A duration of `0` will make the motors vibrate
+/// indefinitely.
+///
+/// Unlike [`start_gamepad_vibration`](super::start_gamepad_vibration), this allows the
+/// motors to be controlled separately - the low frequency motor gives a heavy rumble,
+/// whereas the high frequency motor gives a lighter buzz.
+pub fn start_gamepad_rumble(
+    ctx: &mut Context,
+    gamepad_id: usize,
+    low_frequency: f32,
+    high_frequency: f32,
+    duration: u32,
+) {
+    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).and_then(|g| g.platform_id()) {
+        ctx.window
+            .start_gamepad_rumble(platform_id, low_frequency, high_frequency, duration);
+    }
+}
+
+/// Sets the motors in the specified gamepad's triggers to vibrate for a set duration,
+/// specified in milliseconds. A duration of `0` will make the motors vibrate indefinitely.
+///
+/// If the gamepad does not have rumble motors in its triggers, its standard rumble
+/// motors will be used instead, with the left trigger's strength being applied to the
+/// low frequency motor and the right trigger's strength being applied to the high
+/// frequency motor.
+pub fn start_gamepad_trigger_rumble(
+    ctx: &mut Context,
+    gamepad_id: usize,
+    left: f32,
+    right: f32,
+    duration: u32,
+) {
+    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).and_then(|g| g.platform_id()) {
+        if ctx.window.get_gamepad_haptics(platform_id).trigger_rumble {
+            ctx.window
+                .start_gamepad_trigger_rumble(platform_id, left, right, duration);
+        } else {
+            ctx.window
+                .start_gamepad_rumble(platform_id, left, right, duration);
+        }
+    }
+}
+
+/// Applies an effect to one of the specified gamepad's adaptive triggers.
+///
+/// The effect will stay active until it is replaced, or until [`TriggerEffect::Off`]
+/// is applied.
+///
+/// If the gamepad does not have adaptive triggers, this function will do nothing.
+/// Adaptive triggers are currently only supported for PlayStation 5 controllers,
+/// when using the SDL2 backend with SDL 2.0.16 or later.
+pub fn set_gamepad_trigger_effect(
+    ctx: &mut Context,
+    gamepad_id: usize,
+    trigger: GamepadTrigger,
+    effect: TriggerEffect,
+) {
+    if let Some(platform_id) = get_gamepad(ctx, gamepad_id).and_then(|g| g.platform_id()) {
+        if ctx
+            .window
+            .get_gamepad_haptics(platform_id)
+            .adaptive_triggers
+        {
+            ctx.window
+                .set_gamepad_trigger_effect(platform_id, trigger, effect);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dualsense_trigger_effects() {
+        assert_eq!(TriggerEffect::Off.to_dualsense()[0], 0x05);
+
+        assert_eq!(
+            TriggerEffect::Weapon {
+                start: 0.2,
+                end: 0.1,
+                strength: 2.0,
+            }
+            .to_dualsense(),
+            [0x02, 51, 51, 255, 0, 0, 0, 0, 0, 0, 0]
+        );
+    }
+}
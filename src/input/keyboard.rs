@@ -1,4 +1,5 @@
 use std::fmt::{self, Display, Formatter};
+use std::ops::{BitOr, BitOrAssign};
 
 use crate::Context;
 
@@ -575,6 +576,107 @@ impl Display for KeyModifier {
     }
 }
 
+/// A set of key modifiers.
+///
+/// This can be used to check for keyboard shortcuts (such as Ctrl+S) without having
+/// to check each modifier individually. Sets can be combined via the `|` operator.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::input::{self, Key, KeyModifiers};
+/// # fn example(ctx: &Context) {
+/// if input::is_key_pressed_with(ctx, Key::S, KeyModifiers::CTRL | KeyModifiers::SHIFT) {
+///     // Save as...
+/// } else if input::is_key_pressed_with(ctx, Key::S, KeyModifiers::CTRL) {
+///     // Save
+/// }
+/// # }
+/// ```
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct KeyModifiers {
+    bits: u8,
+}
+
+impl KeyModifiers {
+    /// No modifiers.
+    pub const NONE: KeyModifiers = KeyModifiers { bits: 0 };
+
+    /// The Ctrl modifier.
+    pub const CTRL: KeyModifiers = KeyModifiers { bits: 1 };
+
+    /// The Alt modifier.
+    pub const ALT: KeyModifiers = KeyModifiers { bits: 1 << 1 };
+
+    /// The Shift modifier.
+    pub const SHIFT: KeyModifiers = KeyModifiers { bits: 1 << 2 };
+
+    /// Returns true if no modifiers are in the set.
+    pub fn is_empty(self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns true if all of the modifiers in `other` are also in this set.
+    pub fn contains(self, other: KeyModifiers) -> bool {
+        self.bits & other.bits == other.bits
+    }
+}
+
+impl BitOr for KeyModifiers {
+    type Output = KeyModifiers;
+
+    fn bitor(self, rhs: KeyModifiers) -> KeyModifiers {
+        KeyModifiers {
+            bits: self.bits | rhs.bits,
+        }
+    }
+}
+
+impl BitOrAssign for KeyModifiers {
+    fn bitor_assign(&mut self, rhs: KeyModifiers) {
+        self.bits |= rhs.bits;
+    }
+}
+
+impl From<KeyModifier> for KeyModifiers {
+    fn from(key_modifier: KeyModifier) -> KeyModifiers {
+        match key_modifier {
+            KeyModifier::Ctrl => KeyModifiers::CTRL,
+            KeyModifier::Alt => KeyModifiers::ALT,
+            KeyModifier::Shift => KeyModifiers::SHIFT,
+        }
+    }
+}
+
+impl Display for KeyModifiers {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+
+        for key_modifier in &[KeyModifier::Ctrl, KeyModifier::Alt, KeyModifier::Shift] {
+            if self.contains((*key_modifier).into()) {
+                if !first {
+                    write!(f, "+")?;
+                }
+
+                write!(f, "{}", key_modifier)?;
+                first = false;
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Default, Debug)]
 pub(crate) struct KeyModifierState {
     pub ctrl: bool,
@@ -582,6 +684,26 @@ pub(crate) struct KeyModifierState {
     pub shift: bool,
 }
 
+impl KeyModifierState {
+    pub(crate) fn to_modifiers(&self) -> KeyModifiers {
+        let mut modifiers = KeyModifiers::NONE;
+
+        if self.ctrl {
+            modifiers |= KeyModifiers::CTRL;
+        }
+
+        if self.alt {
+            modifiers |= KeyModifiers::ALT;
+        }
+
+        if self.shift {
+            modifiers |= KeyModifiers::SHIFT;
+        }
+
+        modifiers
+    }
+}
+
 /// Returns true if the specified key is currently down.
 pub fn is_key_down(ctx: &Context, key: Key) -> bool {
     ctx.input.keys_down.contains(&key)
@@ -620,6 +742,31 @@ pub fn is_key_modifier_up(ctx: &Context, key_modifier: KeyModifier) -> bool {
     }
 }
 
+/// Returns the set of key modifiers that are currently down.
+pub fn get_key_modifiers(ctx: &Context) -> KeyModifiers {
+    ctx.input.key_modifier_state.to_modifiers()
+}
+
+/// Returns true if the specified key is currently down, and exactly the specified
+/// modifiers are down.
+///
+/// The modifiers must match exactly, so that (for example) Ctrl+S and Ctrl+Shift+S can
+/// be bound to different actions. Pass [`KeyModifiers::NONE`] to check that the key is
+/// down without any modifiers.
+pub fn is_key_down_with(ctx: &Context, key: Key, modifiers: KeyModifiers) -> bool {
+    is_key_down(ctx, key) && get_key_modifiers(ctx) == modifiers
+}
+
+/// Returns true if the specified key was pressed since the last update, and exactly the
+/// specified modifiers are down.
+///
+/// The modifiers must match exactly, so that (for example) Ctrl+S and Ctrl+Shift+S can
+/// be bound to different actions. Pass [`KeyModifiers::NONE`] to check that the key was
+/// pressed without any modifiers.
+pub fn is_key_pressed_with(ctx: &Context, key: Key, modifiers: KeyModifiers) -> bool {
+    is_key_pressed(ctx, key) && get_key_modifiers(ctx) == modifiers
+}
+
 /// Returns an iterator of the keys that are currently down.
 pub fn get_keys_down(ctx: &Context) -> impl Iterator<Item = &Key> {
     ctx.input.keys_down.iter()
@@ -692,3 +839,20 @@ pub(crate) fn set_key_up(ctx: &mut Context, key: Key) -> bool {
 pub(crate) fn set_key_modifier_state(ctx: &mut Context, state: KeyModifierState) {
     ctx.input.key_modifier_state = state;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_modifiers() {
+        let ctrl_shift = KeyModifiers::CTRL | KeyModifiers::SHIFT;
+
+        assert!(ctrl_shift.contains(KeyModifiers::CTRL));
+        assert!(!ctrl_shift.contains(KeyModifiers::CTRL | KeyModifiers::ALT));
+        assert!(KeyModifiers::NONE.is_empty());
+
+        assert_eq!(ctrl_shift.to_string(), "Ctrl+Shift");
+        assert_eq!(KeyModifiers::NONE.to_string(), "");
+    }
+}
@@ -1,3 +1,4 @@
+use super::{get_key_modifiers, KeyModifiers};
 use crate::math::Vec2;
 use crate::Context;
 
@@ -36,6 +37,19 @@ pub fn is_mouse_button_pressed(ctx: &Context, button: MouseButton) -> bool {
     ctx.input.mouse_buttons_pressed.contains(&button)
 }
 
+/// Returns true if the specified mouse button was pressed since the last update, and
+/// exactly the specified key modifiers are down.
+///
+/// This can be used for actions such as Shift+Click. As with
+/// [`is_key_pressed_with`](super::is_key_pressed_with), the modifiers must match exactly.
+pub fn is_mouse_button_pressed_with(
+    ctx: &Context,
+    button: MouseButton,
+    modifiers: KeyModifiers,
+) -> bool {
+    is_mouse_button_pressed(ctx, button) && get_key_modifiers(ctx) == modifiers
+}
+
 /// Returns true if the specified mouse button was released since the last update.
 pub fn is_mouse_button_released(ctx: &Context, button: MouseButton) -> bool {
     ctx.input.mouse_buttons_released.contains(&button)
@@ -73,10 +87,29 @@ pub fn get_mouse_position(ctx: &Context) -> Vec2<f32> {
 ///
 /// Positive values correspond to scrolling up/right, negative values correspond to scrolling
 /// down/left.
+///
+/// This is measured in whole steps of the wheel. Devices that scroll smoothly (such as
+/// trackpads) will only register movement here once they have scrolled far enough to make
+/// up a full step - use [`get_mouse_wheel_delta`] if you need more precision.
 pub fn get_mouse_wheel_movement(ctx: &Context) -> Vec2<i32> {
     ctx.input.mouse_wheel_movement
 }
 
+/// Get the precise amount that the mouse wheel moved since the last update.
+///
+/// This is measured in the same units as [`get_mouse_wheel_movement`] (one unit per step of a
+/// normal mouse wheel), but includes fractional movement from devices that scroll smoothly,
+/// such as trackpads. This makes it a better fit for things like zooming and panning in editors.
+///
+/// Positive values correspond to scrolling up/right, negative values correspond to scrolling
+/// down/left.
+///
+/// Precise scrolling is currently only supported by the winit backend - when using SDL,
+/// this will always contain whole numbers.
+pub fn get_mouse_wheel_delta(ctx: &Context) -> Vec2<f32> {
+    ctx.input.mouse_wheel_delta
+}
+
 pub(crate) fn set_mouse_button_down(ctx: &mut Context, btn: MouseButton) -> bool {
     let was_up = ctx.input.mouse_buttons_down.insert(btn);
 
@@ -101,6 +134,49 @@ pub(crate) fn set_mouse_position(ctx: &mut Context, position: Vec2<f32>) {
     ctx.input.mouse_position = position;
 }
 
-pub(crate) fn apply_mouse_wheel_movement(ctx: &mut Context, wheel_movement: Vec2<i32>) {
-    ctx.input.mouse_wheel_movement += wheel_movement;
+/// Applies a precise wheel movement, and returns the number of whole steps that it
+/// resulted in.
+pub(crate) fn apply_mouse_wheel_movement(ctx: &mut Context, delta: Vec2<f32>) -> Vec2<i32> {
+    let amount = take_whole_steps(&mut ctx.input.mouse_wheel_remainder, delta);
+
+    ctx.input.mouse_wheel_delta += delta;
+    ctx.input.mouse_wheel_movement += amount;
+
+    amount
+}
+
+/// Adds the delta to the remainder, then removes and returns any whole steps. This
+/// allows small movements from smooth scrolling devices to add up over time.
+fn take_whole_steps(remainder: &mut Vec2<f32>, delta: Vec2<f32>) -> Vec2<i32> {
+    *remainder += delta;
+
+    let steps = Vec2::new(remainder.x.trunc(), remainder.y.trunc());
+    *remainder -= steps;
+
+    Vec2::new(steps.x as i32, steps.y as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wheel_steps_accumulate() {
+        let mut remainder = Vec2::zero();
+
+        assert_eq!(
+            take_whole_steps(&mut remainder, Vec2::new(0.0, 0.6)),
+            Vec2::new(0, 0)
+        );
+
+        assert_eq!(
+            take_whole_steps(&mut remainder, Vec2::new(-1.0, 0.6)),
+            Vec2::new(-1, 1)
+        );
+
+        assert_eq!(
+            take_whole_steps(&mut remainder, Vec2::new(0.0, 2.0)),
+            Vec2::new(0, 2)
+        );
+    }
 }
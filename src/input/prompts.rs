@@ -0,0 +1,299 @@
+use crate::input::{GamepadAxis, GamepadButton};
+
+/// The type of a gamepad, as detected by the platform.
+///
+/// This can be used to show button prompts that match the controller the player is
+/// holding - see [`GamepadLayout`] for the functions that do this.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[non_exhaustive]
+pub enum GamepadType {
+    /// The type of the gamepad could not be determined.
+    ///
+    /// This is also returned for disconnected gamepads, and when using a platform
+    /// backend that does not support gamepads.
+    Unknown,
+
+    /// An Xbox 360 controller.
+    Xbox360,
+
+    /// An Xbox One or Xbox Series controller.
+    XboxOne,
+
+    /// A PlayStation 3 controller (DualShock 3).
+    PlayStation3,
+
+    /// A PlayStation 4 controller (DualShock 4).
+    PlayStation4,
+
+    /// A PlayStation 5 controller (DualSense).
+    PlayStation5,
+
+    /// A Nintendo Switch Pro Controller.
+    SwitchPro,
+
+    /// A virtual controller, created by the platform or by software.
+    Virtual,
+}
+
+impl GamepadType {
+    /// Returns the button layout that the gamepad uses.
+    ///
+    /// Unknown and virtual gamepads use [`GamepadLayout::Xbox`], as that is the layout
+    /// that gamepad mappings are based on.
+    pub fn layout(self) -> GamepadLayout {
+        match self {
+            GamepadType::PlayStation3 | GamepadType::PlayStation4 | GamepadType::PlayStation5 => {
+                GamepadLayout::PlayStation
+            }
+            GamepadType::SwitchPro => GamepadLayout::Nintendo,
+            _ => GamepadLayout::Xbox,
+        }
+    }
+
+    /// Makes a best guess at the type of a gamepad, based on its name.
+    ///
+    /// This is used as a fallback when the platform cannot identify the gamepad.
+    pub(crate) fn from_name(name: &str) -> GamepadType {
+        let name = name.to_lowercase();
+
+        if name.contains("dualsense") || name.contains("ps5") {
+            GamepadType::PlayStation5
+        } else if name.contains("dualshock 4") || name.contains("ps4") {
+            GamepadType::PlayStation4
+        } else if name.contains("ps3") || name.contains("playstation") {
+            GamepadType::PlayStation3
+        } else if name.contains("switch") || name.contains("nintendo") || name.contains("joy-con") {
+            GamepadType::SwitchPro
+        } else if name.contains("xbox 360") {
+            GamepadType::Xbox360
+        } else if name.contains("xbox") {
+            GamepadType::XboxOne
+        } else {
+            GamepadType::Unknown
+        }
+    }
+}
+
+/// The family of button labels that a gamepad uses.
+///
+/// Gamepad buttons in Tetra are named based on their position, following the Xbox
+/// layout - for example, [`GamepadButton::A`] is always the bottom face button. This
+/// type can be used to get the name and glyph that the player will actually see on
+/// their controller.
+///
+/// Note that Nintendo controllers swap the labels of the face buttons, so on those
+/// controllers [`GamepadButton::A`] is labelled 'B'.
+///
+/// # Glyph Atlases
+///
+/// [`GamepadLayout::button_glyph_index`] and [`GamepadLayout::axis_glyph_index`] return
+/// indices into a sprite sheet of prompt glyphs, laid out as follows:
+///
+/// * One row per layout, in the order `Xbox`, `PlayStation`, `Nintendo`.
+/// * Each row contains [`GLYPHS_PER_LAYOUT`](GamepadLayout::GLYPHS_PER_LAYOUT) glyphs: one
+///   for each [`GamepadButton`] (in the order they are declared), followed by one for each
+///   [`GamepadAxis`] (in the order they are declared).
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::input::{self, GamepadButton};
+/// # fn example(ctx: &Context) {
+/// let layout = input::get_gamepad_type(ctx, 0).layout();
+/// let prompt = format!("Press [{}] to jump", layout.button_name(GamepadButton::A));
+/// # }
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum GamepadLayout {
+    /// Xbox-style labels (A, B, X, Y, LB, RB...).
+    Xbox,
+
+    /// PlayStation-style labels (Cross, Circle, Square, Triangle, L1, R1...).
+    PlayStation,
+
+    /// Nintendo-style labels (B, A, Y, X, L, R...).
+    Nintendo,
+}
+
+impl GamepadLayout {
+    /// The number of glyphs in each row of a prompt glyph atlas.
+    pub const GLYPHS_PER_LAYOUT: usize = 23;
+
+    /// Returns the name of a button, as it is labelled on this type of controller.
+    pub fn button_name(self, button: GamepadButton) -> &'static str {
+        use GamepadButton::*;
+        use GamepadLayout::*;
+
+        match (self, button) {
+            (Xbox, A) | (Nintendo, B) => "A",
+            (Xbox, B) | (Nintendo, A) => "B",
+            (Xbox, X) | (Nintendo, Y) => "X",
+            (Xbox, Y) | (Nintendo, X) => "Y",
+            (PlayStation, A) => "Cross",
+            (PlayStation, B) => "Circle",
+            (PlayStation, X) => "Square",
+            (PlayStation, Y) => "Triangle",
+
+            (_, Up) => "D-Pad Up",
+            (_, Down) => "D-Pad Down",
+            (_, Left) => "D-Pad Left",
+            (_, Right) => "D-Pad Right",
+
+            (Xbox, LeftShoulder) => "LB",
+            (Xbox, RightShoulder) => "RB",
+            (Xbox, LeftTrigger) => "LT",
+            (Xbox, RightTrigger) => "RT",
+            (Xbox, LeftStick) => "LS",
+            (Xbox, RightStick) => "RS",
+            (Xbox, Start) => "Menu",
+            (Xbox, Back) => "View",
+            (Xbox, Guide) => "Xbox",
+
+            (PlayStation, LeftShoulder) => "L1",
+            (PlayStation, RightShoulder) => "R1",
+            (PlayStation, LeftTrigger) => "L2",
+            (PlayStation, RightTrigger) => "R2",
+            (PlayStation, LeftStick) => "L3",
+            (PlayStation, RightStick) => "R3",
+            (PlayStation, Start) => "Options",
+            (PlayStation, Back) => "Share",
+            (PlayStation, Guide) => "PS",
+
+            (Nintendo, LeftShoulder) => "L",
+            (Nintendo, RightShoulder) => "R",
+            (Nintendo, LeftTrigger) => "ZL",
+            (Nintendo, RightTrigger) => "ZR",
+            (Nintendo, LeftStick) => "Left Stick",
+            (Nintendo, RightStick) => "Right Stick",
+            (Nintendo, Start) => "+",
+            (Nintendo, Back) => "-",
+            (Nintendo, Guide) => "Home",
+        }
+    }
+
+    /// Returns the name of an axis, as it is labelled on this type of controller.
+    pub fn axis_name(self, axis: GamepadAxis) -> &'static str {
+        match axis {
+            GamepadAxis::LeftStickX => "Left Stick X",
+            GamepadAxis::LeftStickY => "Left Stick Y",
+            GamepadAxis::RightStickX => "Right Stick X",
+            GamepadAxis::RightStickY => "Right Stick Y",
+            GamepadAxis::LeftTrigger => self.button_name(GamepadButton::LeftTrigger),
+            GamepadAxis::RightTrigger => self.button_name(GamepadButton::RightTrigger),
+        }
+    }
+
+    /// Returns the index of a button's glyph in a prompt glyph atlas.
+    ///
+    /// See the [type-level docs](GamepadLayout#glyph-atlases) for the expected layout
+    /// of the atlas.
+    pub fn button_glyph_index(self, button: GamepadButton) -> usize {
+        let column = match button {
+            GamepadButton::A => 0,
+            GamepadButton::B => 1,
+            GamepadButton::X => 2,
+            GamepadButton::Y => 3,
+            GamepadButton::Up => 4,
+            GamepadButton::Down => 5,
+            GamepadButton::Left => 6,
+            GamepadButton::Right => 7,
+            GamepadButton::LeftShoulder => 8,
+            GamepadButton::LeftTrigger => 9,
+            GamepadButton::LeftStick => 10,
+            GamepadButton::RightShoulder => 11,
+            GamepadButton::RightTrigger => 12,
+            GamepadButton::RightStick => 13,
+            GamepadButton::Start => 14,
+            GamepadButton::Back => 15,
+            GamepadButton::Guide => 16,
+        };
+
+        self.row_start() + column
+    }
+
+    /// Returns the index of an axis' glyph in a prompt glyph atlas.
+    ///
+    /// See the [type-level docs](GamepadLayout#glyph-atlases) for the expected layout
+    /// of the atlas.
+    pub fn axis_glyph_index(self, axis: GamepadAxis) -> usize {
+        let column = match axis {
+            GamepadAxis::LeftStickX => 17,
+            GamepadAxis::LeftStickY => 18,
+            GamepadAxis::LeftTrigger => 19,
+            GamepadAxis::RightStickX => 20,
+            GamepadAxis::RightStickY => 21,
+            GamepadAxis::RightTrigger => 22,
+        };
+
+        self.row_start() + column
+    }
+
+    fn row_start(self) -> usize {
+        let row = match self {
+            GamepadLayout::Xbox => 0,
+            GamepadLayout::PlayStation => 1,
+            GamepadLayout::Nintendo => 2,
+        };
+
+        row * GamepadLayout::GLYPHS_PER_LAYOUT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nintendo_swaps_face_buttons() {
+        assert_eq!(GamepadLayout::Nintendo.button_name(GamepadButton::A), "B");
+        assert_eq!(GamepadLayout::Xbox.button_name(GamepadButton::A), "A");
+        assert_eq!(
+            GamepadLayout::PlayStation.button_name(GamepadButton::A),
+            "Cross"
+        );
+    }
+
+    #[test]
+    fn guess_type_from_name() {
+        assert_eq!(
+            GamepadType::from_name("PS4 Controller"),
+            GamepadType::PlayStation4
+        );
+        assert_eq!(
+            GamepadType::from_name("Nintendo Switch Pro Controller").layout(),
+            GamepadLayout::Nintendo
+        );
+        assert_eq!(
+            GamepadType::from_name("Generic USB Pad"),
+            GamepadType::Unknown
+        );
+    }
+
+    #[test]
+    fn glyph_indices() {
+        assert_eq!(GamepadLayout::Xbox.button_glyph_index(GamepadButton::A), 0);
+        assert_eq!(
+            GamepadLayout::PlayStation.axis_glyph_index(GamepadAxis::RightTrigger),
+            2 * GamepadLayout::GLYPHS_PER_LAYOUT - 1
+        );
+    }
+}
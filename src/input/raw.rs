@@ -0,0 +1,59 @@
+use crate::math::Vec2;
+
+use super::{Key, MouseButton};
+
+/// Input that was received from a specific mouse or keyboard.
+///
+/// Raw input is delivered via [`Event::RawInput`](crate::Event::RawInput), and must be
+/// enabled via [`ContextBuilder::raw_input`](crate::ContextBuilder::raw_input). Unlike
+/// the normal input events, it is tagged with the device that it came from, which allows
+/// games to tell multiple mice or keyboards apart (for example, in a local multiplayer
+/// game where each player has their own mouse).
+///
+/// Raw input is not affected by the window's cursor position, OS-level pointer
+/// acceleration, or key repeat.
+///
+/// # Platform Support
+///
+/// Raw input is currently only supported by the winit backend, on Windows and Linux (X11).
+/// On other platforms, all input will be reported as coming from a single device.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum RawInput {
+    /// The mouse was moved.
+    MouseMoved {
+        /// The distance that the mouse moved, in device-specific units.
+        delta: Vec2<f32>,
+    },
+
+    /// A button on the mouse was pressed.
+    MouseButtonPressed {
+        /// The button that was pressed.
+        button: MouseButton,
+    },
+
+    /// A button on the mouse was released.
+    MouseButtonReleased {
+        /// The button that was released.
+        button: MouseButton,
+    },
+
+    /// The mouse wheel was moved.
+    MouseWheelMoved {
+        /// The amount that the wheel was moved, in the same units as
+        /// [`get_mouse_wheel_delta`](super::get_mouse_wheel_delta).
+        delta: Vec2<f32>,
+    },
+
+    /// A key on the keyboard was pressed.
+    KeyPressed {
+        /// The key that was pressed.
+        key: Key,
+    },
+
+    /// A key on the keyboard was released.
+    KeyReleased {
+        /// The key that was released.
+        key: Key,
+    },
+}
@@ -0,0 +1,102 @@
+use crate::input::{
+    get_gamepad, get_gamepad_mut, insert_gamepad, remove_gamepad, GamepadAxis, GamepadButton,
+    GamepadState, GamepadType, VirtualGamepad,
+};
+use crate::Context;
+
+/// Connects a virtual gamepad, and returns the ID that was allocated to it.
+///
+/// Virtual gamepads are not backed by a physical device - instead, their state is set by
+/// your game via [`set_virtual_gamepad_button`] and [`set_virtual_gamepad_axis`]. This
+/// allows input from another source (e.g. the action sets provided by Steam Input) to be
+/// fed into Tetra, so that it can be read via the same functions as any other gamepad.
+///
+/// Virtual gamepads share IDs with physical gamepads, following the rules described in
+/// the [module documentation](crate::input). They do not support vibration or haptics.
+///
+/// As virtual gamepads are controlled by your game rather than by the platform, changes
+/// to their state do not fire any events. If you need to react to a button press, check
+/// [`is_gamepad_button_pressed`](super::is_gamepad_button_pressed) in your `update` method.
+pub fn connect_virtual_gamepad(ctx: &mut Context, name: &str, gamepad_type: GamepadType) -> usize {
+    let mut pad = GamepadState::new(0);
+
+    pad.virtual_gamepad = Some(VirtualGamepad {
+        name: name.to_owned(),
+        gamepad_type,
+    });
+
+    insert_gamepad(ctx, pad)
+}
+
+/// Disconnects a virtual gamepad, freeing up its ID.
+///
+/// If the specified gamepad is not a virtual gamepad, this function will do nothing.
+pub fn disconnect_virtual_gamepad(ctx: &mut Context, gamepad_id: usize) {
+    if is_virtual_gamepad(ctx, gamepad_id) {
+        remove_gamepad(ctx, gamepad_id);
+    }
+}
+
+/// Returns true if the specified gamepad was connected via [`connect_virtual_gamepad`].
+pub fn is_virtual_gamepad(ctx: &Context, gamepad_id: usize) -> bool {
+    matches!(get_gamepad(ctx, gamepad_id), Some(pad) if pad.virtual_gamepad.is_some())
+}
+
+/// Sets whether a button on a virtual gamepad is down.
+///
+/// This should be called before your game's `update` method reads the gamepad's state,
+/// so that [`is_gamepad_button_pressed`](super::is_gamepad_button_pressed) and
+/// [`is_gamepad_button_released`](super::is_gamepad_button_released) are reported for
+/// the same tick that the change was made in.
+///
+/// If the specified gamepad is not a virtual gamepad, this function will do nothing.
+pub fn set_virtual_gamepad_button(
+    ctx: &mut Context,
+    gamepad_id: usize,
+    button: GamepadButton,
+    down: bool,
+) {
+    if let Some(pad) = get_virtual_gamepad_mut(ctx, gamepad_id) {
+        if down {
+            pad.set_button_down(button);
+        } else {
+            pad.set_button_up(button);
+        }
+    }
+}
+
+/// Sets the position of an axis on a virtual gamepad.
+///
+/// The position should be between `-1.0` and `1.0` for the sticks, and between `0.0` and
+/// `1.0` for the triggers. As with physical gamepads, moving a trigger axis will also
+/// update the state of the matching trigger button.
+///
+/// If the specified gamepad is not a virtual gamepad, this function will do nothing.
+pub fn set_virtual_gamepad_axis(
+    ctx: &mut Context,
+    gamepad_id: usize,
+    axis: GamepadAxis,
+    position: f32,
+) {
+    if let Some(pad) = get_virtual_gamepad_mut(ctx, gamepad_id) {
+        pad.set_axis_position(axis, position);
+
+        let button = match axis {
+            GamepadAxis::LeftTrigger => Some(GamepadButton::LeftTrigger),
+            GamepadAxis::RightTrigger => Some(GamepadButton::RightTrigger),
+            _ => None,
+        };
+
+        if let Some(button) = button {
+            if position > 0.0 {
+                pad.set_button_down(button);
+            } else {
+                pad.set_button_up(button);
+            }
+        }
+    }
+}
+
+fn get_virtual_gamepad_mut(ctx: &mut Context, gamepad_id: usize) -> Option<&mut GamepadState> {
+    get_gamepad_mut(ctx, gamepad_id).filter(|pad| pad.virtual_gamepad.is_some())
+}
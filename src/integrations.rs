@@ -0,0 +1,9 @@
+//! Optional integrations with third-party platforms and services.
+//!
+//! Each integration is gated behind its own Cargo feature, so that games only pay for
+//! the ones they use.
+
+#[cfg(feature = "accessibility")]
+pub mod accessibility;
+#[cfg(feature = "discord")]
+pub mod discord;
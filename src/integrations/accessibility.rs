@@ -0,0 +1,422 @@
+//! Functions and types for exposing your game's UI to screen readers and other
+//! assistive technologies, via [AccessKit](https://accesskit.dev/).
+//!
+//! Games usually draw their own UI, so the operating system has no way of knowing what
+//! is on screen. An [`AccessibilityTree`] describes the UI as a set of nodes (buttons,
+//! labels, text inputs, etc.), and produces AccessKit tree updates whenever that
+//! description changes. This allows a blind player to navigate your menus with a screen
+//! reader, in the same way as they would in a native application.
+//!
+//! This module requires the `accessibility` feature to be enabled.
+//!
+//! # Platform Adapters
+//!
+//! Tetra does not talk to the operating system's accessibility APIs itself - the
+//! updates need to be passed to one of AccessKit's platform adapters (such as
+//! `accesskit_windows`, `accesskit_macos` or `accesskit_unix`). The adapters should be
+//! given the handler returned by [`AccessibilityTree::action_handler`], so that requests
+//! from the screen reader (e.g. to focus or activate a node) can be read back via
+//! [`AccessibilityTree::next_action`].
+//!
+//! The version of AccessKit that Tetra uses is re-exported as [`accesskit`], to ensure
+//! that the adapter you choose is compatible.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use tetra::graphics::Rectangle;
+//! use tetra::integrations::accessibility::accesskit::{Action, Role};
+//! use tetra::integrations::accessibility::{AccessNode, AccessibilityTree};
+//!
+//! let mut tree = AccessibilityTree::new();
+//!
+//! // Every frame:
+//! tree.set_node(1, AccessNode::new(Role::Button, "New Game", Rectangle::new(16.0, 16.0, 128.0, 32.0)));
+//! tree.set_node(2, AccessNode::new(Role::Button, "Quit", Rectangle::new(16.0, 64.0, 128.0, 32.0)));
+//! tree.set_focus(Some(1));
+//!
+//! if let Some(update) = tree.take_update() {
+//!     // Pass the update to the platform adapter here.
+//! }
+//!
+//! while let Some(request) = tree.next_action() {
+//!     if request.action == Action::Default && request.target.0 == 2 {
+//!         // Quit the game.
+//!     }
+//! }
+//! ```
+
+use std::collections::VecDeque;
+use std::fmt::{self, Debug, Formatter};
+use std::sync::{Arc, Mutex};
+
+pub use accesskit;
+
+use accesskit::{
+    Action, ActionHandler, ActionRequest, Node, NodeBuilder, NodeClassSet, NodeId, Rect, Role,
+    Tree, TreeUpdate,
+};
+
+use crate::graphics::Rectangle;
+
+/// A description of a single UI element, for use in an [`AccessibilityTree`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccessNode {
+    /// The type of UI element that the node represents.
+    pub role: Role,
+
+    /// The text that will be read out to describe the node.
+    pub label: String,
+
+    /// The current value of the node (e.g. the contents of a text input), if it has one.
+    pub value: Option<String>,
+
+    /// The position and size of the node on screen, in physical pixels relative to the
+    /// top left of the window.
+    ///
+    /// This is used by screen readers to highlight the node, and by screen magnifiers
+    /// to follow the focus.
+    pub bounds: Rectangle,
+
+    /// Whether the node is disabled. Disabled nodes can not be focused or activated.
+    pub disabled: bool,
+}
+
+impl AccessNode {
+    /// Creates a new node.
+    pub fn new<S>(role: Role, label: S, bounds: Rectangle) -> AccessNode
+    where
+        S: Into<String>,
+    {
+        AccessNode {
+            role,
+            label: label.into(),
+            value: None,
+            bounds,
+            disabled: false,
+        }
+    }
+
+    /// Sets the value of the node.
+    pub fn value<S>(mut self, value: S) -> AccessNode
+    where
+        S: Into<String>,
+    {
+        self.value = Some(value.into());
+        self
+    }
+
+    /// Sets whether the node is disabled.
+    pub fn disabled(mut self, disabled: bool) -> AccessNode {
+        self.disabled = disabled;
+        self
+    }
+}
+
+/// Describes the UI of a game to assistive technologies.
+///
+/// Nodes are identified by a `u64` ID of your choosing, and are presented in the order
+/// that they were first added. The tree keeps track of which nodes have changed, so
+/// calling [`set_node`](Self::set_node) with the same data every frame is cheap, and will
+/// not cause any updates to be sent.
+///
+/// The ID [`ROOT`](Self::ROOT) is reserved for the window, which is the parent of all
+/// of the other nodes.
+///
+/// See the [module-level docs](self) for more information.
+pub struct AccessibilityTree {
+    app_name: Option<String>,
+    nodes: Vec<(u64, AccessNode)>,
+    changed: Vec<u64>,
+    children_changed: bool,
+    focus: Option<u64>,
+    focus_changed: bool,
+    initialized: bool,
+    classes: NodeClassSet,
+    actions: AccessibilityActionHandler,
+}
+
+impl AccessibilityTree {
+    /// The ID of the root node, which represents the window.
+    pub const ROOT: u64 = 0;
+
+    /// Creates a new, empty tree.
+    pub fn new() -> AccessibilityTree {
+        AccessibilityTree {
+            app_name: None,
+            nodes: Vec::new(),
+            changed: Vec::new(),
+            children_changed: false,
+            focus: None,
+            focus_changed: false,
+            initialized: false,
+            classes: NodeClassSet::new(),
+            actions: AccessibilityActionHandler::default(),
+        }
+    }
+
+    /// Sets the name of the application, which some screen readers will announce
+    /// when the window is focused.
+    pub fn set_app_name<S>(&mut self, name: S)
+    where
+        S: Into<String>,
+    {
+        self.app_name = Some(name.into());
+        self.initialized = false;
+    }
+
+    /// Adds a node to the tree, or replaces the node with the given ID.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `id` is [`ROOT`](Self::ROOT).
+    pub fn set_node(&mut self, id: u64, node: AccessNode) {
+        assert_ne!(
+            id,
+            Self::ROOT,
+            "the root node ID is reserved for the window"
+        );
+
+        match self.nodes.iter_mut().find(|(i, _)| *i == id) {
+            Some((_, existing)) => {
+                if *existing != node {
+                    *existing = node;
+                    self.mark_changed(id);
+                }
+            }
+            None => {
+                self.nodes.push((id, node));
+                self.mark_changed(id);
+                self.children_changed = true;
+            }
+        }
+    }
+
+    /// Returns the node with the given ID, if it is in the tree.
+    pub fn node(&self, id: u64) -> Option<&AccessNode> {
+        self.nodes.iter().find(|(i, _)| *i == id).map(|(_, n)| n)
+    }
+
+    /// Removes the node with the given ID from the tree.
+    ///
+    /// If the node was focused, the focus will move back to the window.
+    pub fn remove_node(&mut self, id: u64) {
+        if let Some(index) = self.nodes.iter().position(|(i, _)| *i == id) {
+            self.nodes.remove(index);
+            self.changed.retain(|i| *i != id);
+            self.children_changed = true;
+
+            if self.focus == Some(id) {
+                self.set_focus(None);
+            }
+        }
+    }
+
+    /// Removes all of the nodes from the tree.
+    pub fn clear(&mut self) {
+        if !self.nodes.is_empty() {
+            self.nodes.clear();
+            self.changed.clear();
+            self.children_changed = true;
+            self.set_focus(None);
+        }
+    }
+
+    /// Returns the ID of the focused node, or `None` if the window itself is focused.
+    pub fn focus(&self) -> Option<u64> {
+        self.focus
+    }
+
+    /// Sets which node is focused. Passing `None` will move the focus to the window.
+    ///
+    /// This should be kept in sync with the focus of your UI (e.g. via
+    /// [`FocusNavigator::focus`](crate::ui::FocusNavigator::focus)), so that the screen
+    /// reader announces each item as the player moves through a menu.
+    pub fn set_focus(&mut self, focus: Option<u64>) {
+        if self.focus != focus {
+            self.focus = focus;
+            self.focus_changed = true;
+        }
+    }
+
+    /// Returns a handler that can be passed to an AccessKit platform adapter, so that
+    /// action requests can be received via [`next_action`](Self::next_action).
+    ///
+    /// The handler can be cloned and sent to other threads.
+    pub fn action_handler(&self) -> AccessibilityActionHandler {
+        self.actions.clone()
+    }
+
+    /// Returns the next action that was requested by an assistive technology, if any.
+    ///
+    /// The target of the request will be the ID that was passed to
+    /// [`set_node`](Self::set_node).
+    pub fn next_action(&mut self) -> Option<ActionRequest> {
+        self.actions.queue.lock().unwrap().pop_front()
+    }
+
+    /// Returns the update that needs to be sent to the platform adapter, or `None` if
+    /// nothing has changed since the last update.
+    ///
+    /// The first update contains the full tree, which is what the platform adapters
+    /// expect to be returned when they are initialized.
+    pub fn take_update(&mut self) -> Option<TreeUpdate> {
+        if self.initialized
+            && self.changed.is_empty()
+            && !self.children_changed
+            && !self.focus_changed
+        {
+            return None;
+        }
+
+        let mut nodes = Vec::new();
+
+        if !self.initialized || self.children_changed {
+            let mut root = NodeBuilder::new(Role::Window);
+
+            if let Some(name) = &self.app_name {
+                root.set_name(name.clone());
+            }
+
+            root.set_children(
+                self.nodes
+                    .iter()
+                    .map(|(id, _)| NodeId(*id))
+                    .collect::<Vec<_>>(),
+            );
+
+            nodes.push((NodeId(Self::ROOT), root.build(&mut self.classes)));
+        }
+
+        for (id, node) in &self.nodes {
+            if !self.initialized || self.changed.contains(id) {
+                nodes.push((NodeId(*id), build_node(node, &mut self.classes)));
+            }
+        }
+
+        let tree = if self.initialized {
+            None
+        } else {
+            let mut tree = Tree::new(NodeId(Self::ROOT));
+            tree.app_name = self.app_name.clone();
+            tree.toolkit_name = Some("Tetra".into());
+            tree.toolkit_version = Some(env!("CARGO_PKG_VERSION").into());
+            Some(tree)
+        };
+
+        self.changed.clear();
+        self.children_changed = false;
+        self.focus_changed = false;
+        self.initialized = true;
+
+        Some(TreeUpdate {
+            nodes,
+            tree,
+            focus: NodeId(self.focus.unwrap_or(Self::ROOT)),
+        })
+    }
+
+    fn mark_changed(&mut self, id: u64) {
+        if !self.changed.contains(&id) {
+            self.changed.push(id);
+        }
+    }
+}
+
+impl Debug for AccessibilityTree {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessibilityTree")
+            .field("app_name", &self.app_name)
+            .field("nodes", &self.nodes)
+            .field("focus", &self.focus)
+            .finish()
+    }
+}
+
+impl Default for AccessibilityTree {
+    fn default() -> Self {
+        AccessibilityTree::new()
+    }
+}
+
+/// Receives action requests from an AccessKit platform adapter, and queues them up
+/// for an [`AccessibilityTree`].
+///
+/// This is created via [`AccessibilityTree::action_handler`].
+#[derive(Debug, Clone, Default)]
+pub struct AccessibilityActionHandler {
+    queue: Arc<Mutex<VecDeque<ActionRequest>>>,
+}
+
+impl ActionHandler for AccessibilityActionHandler {
+    fn do_action(&mut self, request: ActionRequest) {
+        self.queue.lock().unwrap().push_back(request);
+    }
+}
+
+fn build_node(node: &AccessNode, classes: &mut NodeClassSet) -> Node {
+    let mut builder = NodeBuilder::new(node.role);
+
+    builder.set_name(node.label.clone());
+
+    if let Some(value) = &node.value {
+        builder.set_value(value.clone());
+    }
+
+    builder.set_bounds(Rect {
+        x0: node.bounds.x as f64,
+        y0: node.bounds.y as f64,
+        x1: node.bounds.right() as f64,
+        y1: node.bounds.bottom() as f64,
+    });
+
+    if node.disabled {
+        builder.set_disabled();
+    } else {
+        builder.add_action(Action::Focus);
+        builder.add_action(Action::Default);
+    }
+
+    builder.build(classes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_changes_are_sent() {
+        let bounds = Rectangle::new(0.0, 0.0, 32.0, 16.0);
+        let mut tree = AccessibilityTree::new();
+
+        tree.set_node(1, AccessNode::new(Role::Button, "Play", bounds));
+        tree.set_node(2, AccessNode::new(Role::Button, "Quit", bounds));
+
+        let update = tree.take_update().unwrap();
+        assert!(update.tree.is_some());
+        assert_eq!(update.nodes.len(), 3);
+        assert_eq!(update.focus, NodeId(AccessibilityTree::ROOT));
+
+        tree.set_node(1, AccessNode::new(Role::Button, "Play", bounds));
+        assert!(tree.take_update().is_none());
+
+        tree.set_node(
+            2,
+            AccessNode::new(Role::Button, "Quit", bounds).disabled(true),
+        );
+        tree.set_focus(Some(1));
+
+        let update = tree.take_update().unwrap();
+        assert!(update.tree.is_none());
+        assert_eq!(update.nodes.len(), 1);
+        assert_eq!(update.nodes[0].0, NodeId(2));
+        assert_eq!(update.focus, NodeId(1));
+
+        tree.remove_node(1);
+
+        let update = tree.take_update().unwrap();
+        assert_eq!(update.nodes.len(), 1);
+        assert_eq!(update.nodes[0].0, NodeId(AccessibilityTree::ROOT));
+        assert_eq!(update.focus, NodeId(AccessibilityTree::ROOT));
+    }
+}
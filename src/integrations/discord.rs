@@ -0,0 +1,574 @@
+//! Functions and types relating to Discord Rich Presence.
+//!
+//! This allows your game to show what the player is currently doing on their Discord
+//! profile (e.g. "Playing World 1-2" or "In a party (2 of 4)").
+//!
+//! Tetra talks to the Discord client directly over its local IPC socket, so no Discord
+//! SDK is required. The connection is managed on a background thread, which will keep
+//! trying to connect if Discord is not running (or is restarted while the game is open),
+//! so your game does not need to handle any of that itself.
+//!
+//! This module requires the `discord` feature to be enabled.
+//!
+//! # Setup
+//!
+//! You will need to create an application in the
+//! [Discord Developer Portal](https://discord.com/developers/applications), and pass
+//! its client ID to [`DiscordPresence::new`]. Any images that you want to display
+//! should be uploaded as 'Rich Presence Assets' for the application, and then referred
+//! to by name.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::time::SystemTime;
+//!
+//! use tetra::integrations::discord::{Activity, DiscordPresence};
+//!
+//! let discord = DiscordPresence::new("123456789012345678");
+//!
+//! discord.set_activity(
+//!     Activity::new()
+//!         .details("World 1-2")
+//!         .state("Playing solo")
+//!         .start_time(SystemTime::now())
+//!         .large_image("logo"),
+//! );
+//! ```
+
+use std::env;
+#[cfg(windows)]
+use std::fs::OpenOptions;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde_json::{json, Map, Value};
+
+/// How long to wait between attempts to connect to Discord.
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Discord rate limits activity updates to five every 20 seconds, so we leave a gap
+/// between updates and only ever send the latest one.
+const UPDATE_INTERVAL: Duration = Duration::from_secs(4);
+
+const OP_HANDSHAKE: u32 = 0;
+const OP_FRAME: u32 = 1;
+const OP_CLOSE: u32 = 2;
+
+/// What the player is currently doing, for display on their Discord profile.
+///
+/// All of the fields are optional - any that are not set will not be displayed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Activity {
+    state: Option<String>,
+    details: Option<String>,
+    start_time: Option<SystemTime>,
+    end_time: Option<SystemTime>,
+    large_image: Option<String>,
+    large_text: Option<String>,
+    small_image: Option<String>,
+    small_text: Option<String>,
+    party_size: Option<(u32, u32)>,
+}
+
+impl Activity {
+    /// Creates a new, empty `Activity`.
+    pub fn new() -> Activity {
+        Activity::default()
+    }
+
+    /// Sets the player's current party status (e.g. "In a party"). This is displayed
+    /// on the second line, after the details.
+    pub fn state<S>(mut self, state: S) -> Activity
+    where
+        S: Into<String>,
+    {
+        self.state = Some(state.into());
+        self
+    }
+
+    /// Sets what the player is currently doing (e.g. "World 1-2"). This is displayed
+    /// on the first line.
+    pub fn details<S>(mut self, details: S) -> Activity
+    where
+        S: Into<String>,
+    {
+        self.details = Some(details.into());
+        self
+    }
+
+    /// Sets the time that the activity started. This causes Discord to display the
+    /// time elapsed since then.
+    pub fn start_time(mut self, start_time: SystemTime) -> Activity {
+        self.start_time = Some(start_time);
+        self
+    }
+
+    /// Sets the time that the activity will end. This causes Discord to display the
+    /// time remaining until then.
+    pub fn end_time(mut self, end_time: SystemTime) -> Activity {
+        self.end_time = Some(end_time);
+        self
+    }
+
+    /// Sets the name of the large image asset to display.
+    pub fn large_image<S>(mut self, key: S) -> Activity
+    where
+        S: Into<String>,
+    {
+        self.large_image = Some(key.into());
+        self
+    }
+
+    /// Sets the text that is displayed when hovering over the large image.
+    pub fn large_text<S>(mut self, text: S) -> Activity
+    where
+        S: Into<String>,
+    {
+        self.large_text = Some(text.into());
+        self
+    }
+
+    /// Sets the name of the small image asset to display, in the corner of the large image.
+    pub fn small_image<S>(mut self, key: S) -> Activity
+    where
+        S: Into<String>,
+    {
+        self.small_image = Some(key.into());
+        self
+    }
+
+    /// Sets the text that is displayed when hovering over the small image.
+    pub fn small_text<S>(mut self, text: S) -> Activity
+    where
+        S: Into<String>,
+    {
+        self.small_text = Some(text.into());
+        self
+    }
+
+    /// Sets the size of the player's party, and the maximum size that it can be.
+    /// This is displayed after the state (e.g. "In a party (2 of 4)").
+    pub fn party_size(mut self, size: u32, max: u32) -> Activity {
+        self.party_size = Some((size, max));
+        self
+    }
+
+    fn to_json(&self) -> Value {
+        let mut activity = Map::new();
+
+        if let Some(state) = &self.state {
+            activity.insert("state".into(), state.as_str().into());
+        }
+
+        if let Some(details) = &self.details {
+            activity.insert("details".into(), details.as_str().into());
+        }
+
+        let mut timestamps = Map::new();
+
+        if let Some(start) = self.start_time.and_then(unix_millis) {
+            timestamps.insert("start".into(), start.into());
+        }
+
+        if let Some(end) = self.end_time.and_then(unix_millis) {
+            timestamps.insert("end".into(), end.into());
+        }
+
+        if !timestamps.is_empty() {
+            activity.insert("timestamps".into(), timestamps.into());
+        }
+
+        let mut assets = Map::new();
+
+        for (key, value) in &[
+            ("large_image", &self.large_image),
+            ("large_text", &self.large_text),
+            ("small_image", &self.small_image),
+            ("small_text", &self.small_text),
+        ] {
+            if let Some(value) = value {
+                assets.insert((*key).into(), value.as_str().into());
+            }
+        }
+
+        if !assets.is_empty() {
+            activity.insert("assets".into(), assets.into());
+        }
+
+        if let Some((size, max)) = self.party_size {
+            activity.insert("party".into(), json!({ "size": [size, max] }));
+        }
+
+        activity.into()
+    }
+}
+
+fn unix_millis(time: SystemTime) -> Option<u64> {
+    time.duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+/// A connection to the Discord client, for displaying Rich Presence.
+///
+/// The connection is managed on a background thread, so all of the methods on this type
+/// return immediately, and are cheap enough to call from your game loop. Updates are
+/// rate limited, so if the activity changes several times in quick succession, only the
+/// latest version will be sent.
+///
+/// When this is dropped, the player's activity will be cleared and the connection
+/// will be closed.
+#[derive(Debug)]
+pub struct DiscordPresence {
+    sender: Option<Sender<Option<Activity>>>,
+    connected: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl DiscordPresence {
+    /// Starts connecting to Discord in the background, using the given application
+    /// client ID.
+    ///
+    /// If Discord is not running, this will not fail - the background thread will
+    /// periodically retry until it is able to connect.
+    pub fn new<S>(client_id: S) -> DiscordPresence
+    where
+        S: Into<String>,
+    {
+        let client_id = client_id.into();
+        let (sender, receiver) = mpsc::channel();
+        let connected = Arc::new(AtomicBool::new(false));
+
+        let thread = {
+            let connected = Arc::clone(&connected);
+
+            thread::Builder::new()
+                .name("tetra-discord".into())
+                .spawn(move || run(&client_id, &receiver, &connected))
+                .ok()
+        };
+
+        DiscordPresence {
+            sender: Some(sender),
+            connected,
+            thread,
+        }
+    }
+
+    /// Sets the activity that is displayed on the player's profile.
+    pub fn set_activity(&self, activity: Activity) {
+        self.send(Some(activity));
+    }
+
+    /// Clears the activity that is displayed on the player's profile.
+    pub fn clear_activity(&self) {
+        self.send(None);
+    }
+
+    /// Returns whether or not the background thread is currently connected to Discord.
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    fn send(&self, activity: Option<Activity>) {
+        if let Some(sender) = &self.sender {
+            // The thread only exits early if it failed to spawn, in which case there's
+            // nothing useful we can do.
+            let _ = sender.send(activity);
+        }
+    }
+}
+
+impl Drop for DiscordPresence {
+    fn drop(&mut self) {
+        // Dropping the sender tells the thread to shut down.
+        self.sender = None;
+
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+trait Stream: Read + Write + Send {}
+
+impl<T> Stream for T where T: Read + Write + Send {}
+
+struct Connection {
+    stream: Box<dyn Stream>,
+    nonce: u64,
+}
+
+impl Connection {
+    fn open(client_id: &str) -> io::Result<Connection> {
+        let mut last_error = io::Error::new(io::ErrorKind::NotFound, "Discord is not running");
+
+        for path in socket_paths() {
+            match open_socket(&path) {
+                Ok(stream) => {
+                    let mut connection = Connection { stream, nonce: 0 };
+
+                    match connection.handshake(client_id) {
+                        Ok(()) => return Ok(connection),
+                        Err(e) => last_error = e,
+                    }
+                }
+
+                Err(e) => last_error = e,
+            }
+        }
+
+        Err(last_error)
+    }
+
+    fn handshake(&mut self, client_id: &str) -> io::Result<()> {
+        self.write(OP_HANDSHAKE, &json!({ "v": 1, "client_id": client_id }))?;
+
+        // Discord replies with a READY event if the handshake was accepted, or closes
+        // the connection if it wasn't (e.g. because the client ID was invalid).
+        self.read()?;
+
+        Ok(())
+    }
+
+    fn set_activity(&mut self, activity: Option<&Activity>) -> io::Result<()> {
+        self.nonce += 1;
+
+        let payload = json!({
+            "cmd": "SET_ACTIVITY",
+            "args": {
+                "pid": std::process::id(),
+                "activity": activity.map(Activity::to_json),
+            },
+            "nonce": self.nonce.to_string(),
+        });
+
+        self.write(OP_FRAME, &payload)?;
+        let response = self.read()?;
+
+        if response.get("evt").and_then(Value::as_str) == Some("ERROR") {
+            log::warn!(
+                target: "tetra::discord",
+                "Discord rejected activity update: {}",
+                response["data"]["message"]
+            );
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, opcode: u32, payload: &Value) -> io::Result<()> {
+        self.stream.write_all(&encode_frame(opcode, payload))?;
+        self.stream.flush()
+    }
+
+    fn read(&mut self) -> io::Result<Value> {
+        let mut header = [0; 8];
+        self.stream.read_exact(&mut header)?;
+
+        let opcode = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        let length = u32::from_le_bytes([header[4], header[5], header[6], header[7]]);
+
+        let mut body = vec![0; length as usize];
+        self.stream.read_exact(&mut body)?;
+
+        let payload: Value = serde_json::from_slice(&body)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+        if opcode == OP_CLOSE {
+            return Err(io::Error::new(
+                io::ErrorKind::ConnectionAborted,
+                payload["message"]
+                    .as_str()
+                    .unwrap_or("connection closed by Discord")
+                    .to_owned(),
+            ));
+        }
+
+        Ok(payload)
+    }
+}
+
+fn encode_frame(opcode: u32, payload: &Value) -> Vec<u8> {
+    let body = payload.to_string();
+
+    let mut frame = Vec::with_capacity(8 + body.len());
+    frame.extend_from_slice(&opcode.to_le_bytes());
+    frame.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    frame.extend_from_slice(body.as_bytes());
+
+    frame
+}
+
+#[cfg(unix)]
+fn socket_paths() -> Vec<PathBuf> {
+    let base = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(env::var_os)
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("/tmp"));
+
+    // Flatpak and Snap installs of Discord put the socket in a subdirectory.
+    let dirs = [
+        base.clone(),
+        base.join("app/com.discordapp.Discord"),
+        base.join("snap.discord"),
+    ];
+
+    dirs.iter()
+        .flat_map(|dir| (0..10).map(move |i| dir.join(format!("discord-ipc-{}", i))))
+        .collect()
+}
+
+#[cfg(windows)]
+fn socket_paths() -> Vec<PathBuf> {
+    (0..10)
+        .map(|i| PathBuf::from(format!(r"\\?\pipe\discord-ipc-{}", i)))
+        .collect()
+}
+
+#[cfg(unix)]
+fn open_socket(path: &Path) -> io::Result<Box<dyn Stream>> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    Ok(Box::new(stream))
+}
+
+#[cfg(windows)]
+fn open_socket(path: &Path) -> io::Result<Box<dyn Stream>> {
+    let pipe = OpenOptions::new().read(true).write(true).open(path)?;
+    Ok(Box::new(pipe))
+}
+
+#[cfg(not(any(unix, windows)))]
+fn socket_paths() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+#[cfg(not(any(unix, windows)))]
+fn open_socket(_path: &Path) -> io::Result<Box<dyn Stream>> {
+    Err(io::Error::new(
+        io::ErrorKind::Other,
+        "Discord is not supported on this platform",
+    ))
+}
+
+fn run(client_id: &str, receiver: &Receiver<Option<Activity>>, connected: &AtomicBool) {
+    let mut connection: Option<Connection> = None;
+    let mut last_attempt: Option<Instant> = None;
+    let mut last_update: Option<Instant> = None;
+
+    // `None` means there's nothing new to send, `Some(None)` means the activity
+    // should be cleared.
+    let mut pending: Option<Option<Activity>> = None;
+
+    loop {
+        match receiver.recv_timeout(Duration::from_millis(500)) {
+            Ok(activity) => {
+                pending = Some(activity);
+
+                // Only the latest update matters, so skip past any that are queued up.
+                while let Ok(activity) = receiver.try_recv() {
+                    pending = Some(activity);
+                }
+            }
+
+            Err(RecvTimeoutError::Timeout) => {}
+
+            Err(RecvTimeoutError::Disconnected) => {
+                if let Some(connection) = &mut connection {
+                    let _ = connection.set_activity(None);
+                }
+
+                connected.store(false, Ordering::Relaxed);
+                return;
+            }
+        }
+
+        if connection.is_none() && has_elapsed(last_attempt, RECONNECT_INTERVAL) {
+            last_attempt = Some(Instant::now());
+
+            match Connection::open(client_id) {
+                Ok(c) => {
+                    log::info!(target: "tetra::discord", "Connected to Discord");
+
+                    connection = Some(c);
+                    connected.store(true, Ordering::Relaxed);
+
+                    // The new connection won't know about anything we sent before.
+                    last_update = None;
+                }
+
+                Err(e) => {
+                    log::debug!(target: "tetra::discord", "Failed to connect to Discord: {}", e);
+                }
+            }
+        }
+
+        let can_update = has_elapsed(last_update, UPDATE_INTERVAL);
+
+        if let (Some(c), true) = (&mut connection, can_update) {
+            if let Some(activity) = pending.take() {
+                last_update = Some(Instant::now());
+
+                if let Err(e) = c.set_activity(activity.as_ref()) {
+                    log::info!(target: "tetra::discord", "Lost connection to Discord: {}", e);
+
+                    // Hold onto the activity, so that it gets sent again once we reconnect.
+                    pending = Some(activity);
+                    connection = None;
+                    connected.store(false, Ordering::Relaxed);
+                }
+            }
+        }
+    }
+}
+
+fn has_elapsed(since: Option<Instant>, interval: Duration) -> bool {
+    match since {
+        Some(since) => since.elapsed() >= interval,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn activity_json() {
+        let activity = Activity::new()
+            .details("World 1-2")
+            .start_time(UNIX_EPOCH + Duration::from_secs(10))
+            .large_image("logo")
+            .party_size(2, 4);
+
+        assert_eq!(
+            activity.to_json(),
+            json!({
+                "details": "World 1-2",
+                "timestamps": { "start": 10000 },
+                "assets": { "large_image": "logo" },
+                "party": { "size": [2, 4] },
+            })
+        );
+
+        assert_eq!(Activity::new().to_json(), json!({}));
+    }
+
+    #[test]
+    fn frame_encoding() {
+        let frame = encode_frame(OP_FRAME, &json!({ "a": 1 }));
+
+        assert_eq!(&frame[0..4], &[1, 0, 0, 0]);
+        assert_eq!(&frame[4..8], &[7, 0, 0, 0]);
+        assert_eq!(&frame[8..], br#"{"a":1}"#);
+    }
+}
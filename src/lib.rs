@@ -61,19 +61,30 @@
 
 #![warn(missing_docs)]
 
+pub mod assets;
 #[cfg(feature = "audio")]
 pub mod audio;
 mod context;
+pub mod debug;
 pub mod error;
-mod fs;
+mod error_screen;
+pub mod fs;
 pub mod graphics;
+#[cfg(feature = "hot_reload")]
+mod hot_reload;
+pub mod i18n;
 pub mod input;
+#[cfg(any(feature = "discord", feature = "accessibility"))]
+pub mod integrations;
 mod lifecycle;
 pub mod math;
 mod platform;
 pub mod time;
+pub mod ui;
+#[cfg(feature = "video")]
+pub mod video;
 pub mod window;
 
 pub use crate::context::{Context, ContextBuilder};
 pub use crate::error::{Result, TetraError};
-pub use crate::lifecycle::{Event, State};
+pub use crate::lifecycle::{Event, EventKind, EventMode, EventSender, State};
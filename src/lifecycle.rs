@@ -1,6 +1,11 @@
+use std::any::Any;
 use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
 
-use crate::input::{GamepadAxis, GamepadButton, GamepadStick, Key, MouseButton};
+use hashbrown::HashMap;
+
+use crate::input::{GamepadAxis, GamepadButton, GamepadStick, Key, MouseButton, RawInput};
 use crate::math::Vec2;
 use crate::{Context, TetraError};
 
@@ -40,6 +45,21 @@ pub trait State<E = TetraError> {
     fn event(&mut self, ctx: &mut Context, event: Event) -> Result<(), E> {
         Ok(())
     }
+
+    /// Called when the game is about to stop running, either because
+    /// [`window::quit`](crate::window::quit) was called or because the window was closed.
+    ///
+    /// This runs before the state or the window are torn down, so it is a good place
+    /// to save the player's progress. It is also called if the game stops because
+    /// [`update`](State::update), [`draw`](State::draw) or [`event`](State::event)
+    /// returned an error.
+    ///
+    /// If an error is returned, it will be returned from [`Context::run`]. If the game is
+    /// already stopping because of an error, that error takes priority, and the one
+    /// returned from this method is discarded.
+    fn on_exit(&mut self, ctx: &mut Context) -> Result<(), E> {
+        Ok(())
+    }
 }
 
 /// Events that can occur while the game is running.
@@ -54,12 +74,42 @@ pub trait State<E = TetraError> {
 #[derive(Debug, Clone)]
 pub enum Event {
     /// The game window was resized.
+    ///
+    /// All of the size information is captured at the time of the resize, so there is no
+    /// need to query the window again (which may return a newer, intermediate size if the
+    /// window is still being resized).
     Resized {
         /// The new width of the game window.
+        ///
+        /// As with [`window::get_width`](crate::window::get_width), this will be consistent
+        /// regardless of whether high DPI support is enabled.
         width: i32,
 
         /// The new height of the game window.
+        ///
+        /// As with [`window::get_height`](crate::window::get_height), this will be consistent
+        /// regardless of whether high DPI support is enabled.
         height: i32,
+
+        /// The new width of the game window, in physical pixels.
+        physical_width: i32,
+
+        /// The new height of the game window, in physical pixels.
+        physical_height: i32,
+
+        /// The ratio of the physical size to the logical size, as returned by
+        /// [`window::get_dpi_scale`](crate::window::get_dpi_scale).
+        scale: f32,
+    },
+
+    /// The DPI scale of the game window changed (e.g. because it was moved to a monitor
+    /// with a different DPI).
+    ///
+    /// The physical size of the window will usually change at the same time, which
+    /// will be reported via a separate [`Resized`](Event::Resized) event.
+    DpiScaleChanged {
+        /// The new DPI scale, as returned by [`window::get_dpi_scale`](crate::window::get_dpi_scale).
+        scale: f32,
     },
 
     /// The game window was restored to normal size and position by the user, either by
@@ -72,6 +122,17 @@ pub enum Event {
     /// The game window was maximized by the user.
     Maximized,
 
+    /// The game window entered or exited fullscreen mode.
+    ///
+    /// This is emitted whenever the fullscreen state actually changes, whether
+    /// that was caused by [`window::set_fullscreen`](crate::window::set_fullscreen) or
+    /// by the operating system (e.g. the user clicking the fullscreen button
+    /// on macOS).
+    FullscreenChanged {
+        /// Whether the window is now fullscreen.
+        fullscreen: bool,
+    },
+
     /// The game window was focused by the user.
     FocusGained,
 
@@ -124,7 +185,17 @@ pub enum Event {
         ///
         /// Positive values correspond to scrolling up/right, negative values correspond to scrolling
         /// down/left.
+        ///
+        /// This is measured in whole steps of the wheel, so it may be zero if a device that
+        /// scrolls smoothly (such as a trackpad) was only moved a small amount.
         amount: Vec2<i32>,
+
+        /// The precise amount that the wheel was moved, including fractional movement
+        /// from devices that scroll smoothly.
+        ///
+        /// See [`input::get_mouse_wheel_delta`](crate::input::get_mouse_wheel_delta) for
+        /// more details.
+        delta: Vec2<f32>,
     },
 
     /// A gamepad was connected to the system.
@@ -169,6 +240,22 @@ pub enum Event {
         position: f32,
     },
 
+    /// Input was received from a specific mouse or keyboard.
+    ///
+    /// This will only be fired if raw input was enabled via
+    /// [`ContextBuilder::raw_input`](crate::ContextBuilder::raw_input). See [`RawInput`] for
+    /// more details and platform support.
+    RawInput {
+        /// The ID of the device.
+        ///
+        /// Devices are assigned IDs in the order that they first send input, starting
+        /// from 0. IDs are not reused if a device is disconnected.
+        device: usize,
+
+        /// The input that was received.
+        input: RawInput,
+    },
+
     /// A control stick on a gamepad was moved.
     GamepadStickMoved {
         /// The ID of the gamepad.
@@ -182,11 +269,35 @@ pub enum Event {
     },
 
     /// The user typed some text.
+    ///
+    /// This will only be fired while text input is active - see
+    /// [`input::start_text_input`](crate::input::start_text_input).
     TextInput {
         /// The text that was typed by the user.
         text: String,
     },
 
+    /// The user is composing some text via an input method editor (IME), but has
+    /// not yet committed it.
+    ///
+    /// This is used when typing languages such as Chinese or Japanese, where multiple
+    /// keypresses are needed to enter a single character. The composition should be
+    /// displayed (usually underlined) at the cursor position, replacing any previous
+    /// composition. Once the user commits the text, a [`TextInput`](Event::TextInput)
+    /// event will be fired.
+    ///
+    /// This event is not currently supported by the winit backend.
+    TextEditing {
+        /// The text that is being composed.
+        text: String,
+
+        /// The position of the cursor within the composition, in characters.
+        cursor: usize,
+
+        /// The number of characters selected after the cursor.
+        selection_length: usize,
+    },
+
     /// The user dropped a file into the window.
     ///
     /// This event will be fired multiple times if the user dropped multiple files at the
@@ -199,4 +310,397 @@ pub enum Event {
         /// The path of the file that was dropped.
         path: PathBuf,
     },
+
+    /// A custom event was sent by the game, either via [`Context::push_event`] or
+    /// via an [`EventSender`].
+    ///
+    /// Custom events are delivered after the platform's events for the frame, in the
+    /// order they were sent.
+    Custom {
+        /// The data that was sent. This can be converted back to its original type
+        /// via [`downcast_ref`](https://doc.rust-lang.org/std/any/trait.Any.html#method.downcast_ref-2).
+        data: Arc<dyn Any + Send + Sync>,
+    },
+}
+
+impl Event {
+    /// Returns the kind of the event.
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::Resized { .. } => EventKind::Resized,
+            Event::DpiScaleChanged { .. } => EventKind::DpiScaleChanged,
+            Event::Restored => EventKind::Restored,
+            Event::Minimized => EventKind::Minimized,
+            Event::Maximized => EventKind::Maximized,
+            Event::FullscreenChanged { .. } => EventKind::FullscreenChanged,
+            Event::FocusGained => EventKind::FocusGained,
+            Event::FocusLost => EventKind::FocusLost,
+            Event::KeyPressed { .. } => EventKind::KeyPressed,
+            Event::KeyReleased { .. } => EventKind::KeyReleased,
+            Event::MouseButtonPressed { .. } => EventKind::MouseButtonPressed,
+            Event::MouseButtonReleased { .. } => EventKind::MouseButtonReleased,
+            Event::MouseMoved { .. } => EventKind::MouseMoved,
+            Event::MouseWheelMoved { .. } => EventKind::MouseWheelMoved,
+            Event::GamepadAdded { .. } => EventKind::GamepadAdded,
+            Event::GamepadRemoved { .. } => EventKind::GamepadRemoved,
+            Event::GamepadButtonPressed { .. } => EventKind::GamepadButtonPressed,
+            Event::GamepadButtonReleased { .. } => EventKind::GamepadButtonReleased,
+            Event::GamepadAxisMoved { .. } => EventKind::GamepadAxisMoved,
+            Event::RawInput { .. } => EventKind::RawInput,
+            Event::GamepadStickMoved { .. } => EventKind::GamepadStickMoved,
+            Event::TextInput { .. } => EventKind::TextInput,
+            Event::TextEditing { .. } => EventKind::TextEditing,
+            Event::FileDropped { .. } => EventKind::FileDropped,
+            Event::Custom { .. } => EventKind::Custom,
+        }
+    }
+}
+
+/// The different kinds of [`Event`], without any of their associated data.
+///
+/// This is used to configure how each kind of event is delivered - see
+/// [`Context::set_event_mode`].
+#[non_exhaustive]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[allow(missing_docs)]
+pub enum EventKind {
+    Resized,
+    DpiScaleChanged,
+    Restored,
+    Minimized,
+    Maximized,
+    FullscreenChanged,
+    FocusGained,
+    FocusLost,
+    KeyPressed,
+    KeyReleased,
+    MouseButtonPressed,
+    MouseButtonReleased,
+    MouseMoved,
+    MouseWheelMoved,
+    GamepadAdded,
+    GamepadRemoved,
+    GamepadButtonPressed,
+    GamepadButtonReleased,
+    GamepadAxisMoved,
+    RawInput,
+    GamepadStickMoved,
+    TextInput,
+    TextEditing,
+    FileDropped,
+    Custom,
+}
+
+impl EventKind {
+    /// Returns whether events of this kind come from the player's input devices.
+    #[cfg(feature = "overlay_support")]
+    pub(crate) fn is_input(self) -> bool {
+        matches!(
+            self,
+            EventKind::KeyPressed
+                | EventKind::KeyReleased
+                | EventKind::MouseButtonPressed
+                | EventKind::MouseButtonReleased
+                | EventKind::MouseMoved
+                | EventKind::MouseWheelMoved
+                | EventKind::GamepadButtonPressed
+                | EventKind::GamepadButtonReleased
+                | EventKind::GamepadAxisMoved
+                | EventKind::GamepadStickMoved
+                | EventKind::RawInput
+                | EventKind::TextInput
+                | EventKind::TextEditing
+        )
+    }
+}
+
+/// How events of a given kind should be delivered to [`State::event`].
+///
+/// This only affects which events your [`State`] receives - the input state that is
+/// returned by the functions in the [`input`](crate::input) module is always kept
+/// up to date.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EventMode {
+    /// Every event is delivered as soon as it occurs.
+    ///
+    /// This is the default for all kinds of event.
+    Enabled,
+
+    /// Events that occur during a frame are combined, and delivered once all of the
+    /// other events for the frame have been handled.
+    ///
+    /// This is useful for high-frequency events, where only the final state matters:
+    ///
+    /// * [`MouseMoved`](Event::MouseMoved) events are combined into a single event,
+    ///   with the final position and the total delta.
+    /// * [`MouseWheelMoved`](Event::MouseWheelMoved) events are combined into a single
+    ///   event, with the total amount and delta.
+    /// * [`GamepadAxisMoved`](Event::GamepadAxisMoved) and
+    ///   [`GamepadStickMoved`](Event::GamepadStickMoved) events are combined into one
+    ///   event per gamepad and axis/stick, with the final position.
+    /// * [`Resized`](Event::Resized) events are combined into a single event, with the
+    ///   final size.
+    ///
+    /// Other kinds of event cannot be combined, so they are delivered individually, but
+    /// are still deferred until the end of the frame's events.
+    Coalesced,
+
+    /// Events are not delivered at all.
+    Disabled,
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct EventFilter {
+    modes: HashMap<EventKind, EventMode>,
+    coalesced: Vec<Event>,
+}
+
+impl EventFilter {
+    pub(crate) fn new() -> EventFilter {
+        EventFilter::default()
+    }
+
+    pub(crate) fn mode(&self, kind: EventKind) -> EventMode {
+        self.modes.get(&kind).copied().unwrap_or(EventMode::Enabled)
+    }
+
+    pub(crate) fn set_mode(&mut self, kind: EventKind, mode: EventMode) {
+        if mode == EventMode::Enabled {
+            self.modes.remove(&kind);
+        } else {
+            self.modes.insert(kind, mode);
+        }
+    }
+
+    /// Drops any coalesced input events that have not been delivered yet.
+    #[cfg(feature = "overlay_support")]
+    pub(crate) fn discard_coalesced_input(&mut self) {
+        self.coalesced.retain(|event| !event.kind().is_input());
+    }
+
+    fn coalesce(&mut self, event: Event) {
+        if !self
+            .coalesced
+            .iter_mut()
+            .any(|pending| merge(pending, &event))
+        {
+            self.coalesced.push(event);
+        }
+    }
+}
+
+/// Merges `event` into `pending`, returning `false` if they cannot be merged.
+fn merge(pending: &mut Event, event: &Event) -> bool {
+    match (pending, event) {
+        (
+            Event::MouseMoved { position, delta },
+            Event::MouseMoved {
+                position: new_position,
+                delta: new_delta,
+            },
+        ) => {
+            *position = *new_position;
+            *delta += *new_delta;
+            true
+        }
+
+        (
+            Event::MouseWheelMoved { amount, delta },
+            Event::MouseWheelMoved {
+                amount: new_amount,
+                delta: new_delta,
+            },
+        ) => {
+            *amount += *new_amount;
+            *delta += *new_delta;
+            true
+        }
+
+        (
+            Event::GamepadAxisMoved { id, axis, position },
+            Event::GamepadAxisMoved {
+                id: new_id,
+                axis: new_axis,
+                position: new_position,
+            },
+        ) if id == new_id && axis == new_axis => {
+            *position = *new_position;
+            true
+        }
+
+        (
+            Event::GamepadStickMoved {
+                id,
+                stick,
+                position,
+            },
+            Event::GamepadStickMoved {
+                id: new_id,
+                stick: new_stick,
+                position: new_position,
+            },
+        ) if id == new_id && stick == new_stick => {
+            *position = *new_position;
+            true
+        }
+
+        (pending @ Event::Resized { .. }, Event::Resized { .. }) => {
+            *pending = event.clone();
+            true
+        }
+
+        _ => false,
+    }
+}
+
+/// Passes an event to the state, taking the configured [`EventMode`] into account.
+pub(crate) fn dispatch<S, E>(ctx: &mut Context, state: &mut S, event: Event) -> Result<(), E>
+where
+    S: State<E>,
+{
+    // While an overlay is open, input is meant for the overlay rather than the game.
+    #[cfg(feature = "overlay_support")]
+    if ctx.overlay_active && event.kind().is_input() {
+        return Ok(());
+    }
+
+    match ctx.events.mode(event.kind()) {
+        EventMode::Enabled => state.event(ctx, event),
+        EventMode::Coalesced => {
+            ctx.events.coalesce(event);
+            Ok(())
+        }
+        EventMode::Disabled => Ok(()),
+    }
+}
+
+/// Delivers any events that were coalesced during the frame.
+pub(crate) fn flush_coalesced<S, E>(ctx: &mut Context, state: &mut S) -> Result<(), E>
+where
+    S: State<E>,
+{
+    if ctx.events.coalesced.is_empty() {
+        return Ok(());
+    }
+
+    let events = std::mem::take(&mut ctx.events.coalesced);
+
+    for event in events {
+        state.event(ctx, event)?;
+    }
+
+    Ok(())
+}
+
+/// A handle that can be used to send custom events to the game from any thread.
+///
+/// This is useful for delivering the results of background work (e.g. network requests
+/// or asset loading) through [`State::event`], alongside the platform's events. Each
+/// value that is sent will be received as an [`Event::Custom`] at the start of the next
+/// frame.
+///
+/// An `EventSender` can be obtained via [`Context::event_sender`], and can be cloned
+/// cheaply.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::thread;
+/// # use tetra::{Context, Event, State};
+/// struct Downloaded(Vec<u8>);
+///
+/// struct GameState;
+///
+/// impl GameState {
+///     fn new(ctx: &mut Context) -> tetra::Result<GameState> {
+///         let sender = ctx.event_sender();
+///
+///         thread::spawn(move || {
+///             let data = vec![1, 2, 3];
+///             sender.send(Downloaded(data));
+///         });
+///
+///         Ok(GameState)
+///     }
+/// }
+///
+/// impl State for GameState {
+///     fn event(&mut self, ctx: &mut Context, event: Event) -> tetra::Result {
+///         if let Event::Custom { data } = event {
+///             if let Some(Downloaded(bytes)) = data.downcast_ref::<Downloaded>() {
+///                 println!("Downloaded {} bytes", bytes.len());
+///             }
+///         }
+///
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct EventSender {
+    pub(crate) sender: Sender<Arc<dyn Any + Send + Sync>>,
+}
+
+impl EventSender {
+    /// Sends a custom event to the game.
+    ///
+    /// Returns `false` if the event could not be delivered, because the [`Context`]
+    /// has been dropped.
+    pub fn send<T>(&self, data: T) -> bool
+    where
+        T: Any + Send + Sync,
+    {
+        self.sender.send(Arc::new(data)).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn coalesce_events() {
+        let mut filter = EventFilter::new();
+
+        filter.coalesce(Event::MouseMoved {
+            position: Vec2::new(1.0, 1.0),
+            delta: Vec2::new(1.0, 0.0),
+        });
+
+        filter.coalesce(Event::KeyPressed { key: Key::A });
+
+        filter.coalesce(Event::MouseMoved {
+            position: Vec2::new(3.0, 2.0),
+            delta: Vec2::new(2.0, 1.0),
+        });
+
+        assert_eq!(filter.coalesced.len(), 2);
+
+        match filter.coalesced[0] {
+            Event::MouseMoved { position, delta } => {
+                assert_eq!(position, Vec2::new(3.0, 2.0));
+                assert_eq!(delta, Vec2::new(3.0, 1.0));
+            }
+            _ => panic!("expected a mouse event"),
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "overlay_support")]
+    fn discard_coalesced_input() {
+        let mut filter = EventFilter::new();
+
+        filter.coalesce(Event::KeyPressed { key: Key::A });
+        filter.coalesce(Event::FocusLost);
+        filter.coalesce(Event::GamepadAxisMoved {
+            id: 0,
+            axis: GamepadAxis::LeftStickX,
+            position: 1.0,
+        });
+
+        filter.discard_coalesced_input();
+
+        assert_eq!(filter.coalesced.len(), 1);
+        assert_eq!(filter.coalesced[0].kind(), EventKind::FocusLost);
+    }
 }
@@ -12,6 +12,24 @@
 //! Note that all of the important types in [`vek`] (such as [`Vec2`] and [`Mat4`]) are
 //! re-exported at the top level - you don't need to dig down into the submodules
 //! when importing things.
+//!
+//! In addition, this module provides a seeded random number generator ([`Rng`]),
+//! interpolation helpers (such as [`lerp`] and [`move_toward`]), [easing
+//! curves](easing), [splines](Spline), [grids and pathfinding](grid), and a set of
+//! [deterministic](deterministic) math functions for games that need their simulation
+//! to behave identically on every machine.
+
+pub mod deterministic;
+pub mod easing;
+pub mod grid;
+mod interpolation;
+pub mod noise;
+mod rng;
+mod spline;
+
+pub use interpolation::*;
+pub use rng::*;
+pub use spline::*;
 
 #[doc(no_inline)]
 pub use vek::*;
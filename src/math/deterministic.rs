@@ -0,0 +1,302 @@
+//! Math functions that give identical results on every platform.
+//!
+//! Basic floating point arithmetic (addition, subtraction, multiplication, division and
+//! square roots) is specified exactly by IEEE 754, so it will give the same results on
+//! every machine. However, the standard library's trigonometric functions (such as
+//! [`f32::sin`]) call into the platform's math library, which can round differently
+//! depending on the OS, the CPU and the compiler version.
+//!
+//! This is usually not noticeable, but in a game that relies on simulations staying in
+//! sync (e.g. lockstep netcode, or replays that are stored as a list of inputs), the tiny
+//! differences can build up over time and cause the simulations to diverge. The functions
+//! in this module are implemented purely in terms of basic arithmetic, so they can
+//! be used in place of the standard versions when this matters.
+//!
+//! Other parts of Tetra that are commonly used in game logic are deterministic too:
+//!
+//! * When using a fixed timestep, [`time::get_delta_time`](crate::time::get_delta_time)
+//!   always returns the same value during an update, and the accumulator is tracked
+//!   with integer precision.
+//! * [`Rng`](super::Rng) produces the same sequence for a given seed on every platform.
+//! * When the `deterministic_math` feature is enabled, the [easing curves](super::easing)
+//!   use the functions from this module rather than the standard library's.
+//!
+//! These functions are slower than the standard library versions, and are slightly less
+//! accurate (with an error of around `1e-7`), so they should only be used where
+//! determinism is required - there's no need to use them for rendering.
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, LN_2, PI};
+
+use super::Vec2;
+
+/// tan(pi/8), which is used to split the range of `atan` into two halves.
+const TAN_PI_8: f64 = 0.414_213_562_373_095_03;
+
+/// Computes the sine of an angle (in radians).
+pub fn sin(angle: f32) -> f32 {
+    sin_cos(angle).0
+}
+
+/// Computes the cosine of an angle (in radians).
+pub fn cos(angle: f32) -> f32 {
+    sin_cos(angle).1
+}
+
+/// Computes the tangent of an angle (in radians).
+pub fn tan(angle: f32) -> f32 {
+    let (sin, cos) = sin_cos(angle);
+    sin / cos
+}
+
+/// Computes both the sine and cosine of an angle (in radians).
+pub fn sin_cos(angle: f32) -> (f32, f32) {
+    if !angle.is_finite() {
+        return (f32::NAN, f32::NAN);
+    }
+
+    let x = angle as f64;
+
+    // Reduce the angle to the range [-pi/4, pi/4], keeping track of which
+    // quadrant it was in.
+    let quadrant = (x / FRAC_PI_2).round();
+    let r = x - quadrant * FRAC_PI_2;
+
+    let sin = sin_poly(r);
+    let cos = cos_poly(r);
+
+    let (sin, cos) = match (quadrant as i64).rem_euclid(4) {
+        0 => (sin, cos),
+        1 => (cos, -sin),
+        2 => (-sin, -cos),
+        _ => (-cos, sin),
+    };
+
+    (sin as f32, cos as f32)
+}
+
+/// Computes the four quadrant arctangent of `y` and `x`, in radians.
+///
+/// This matches the behaviour of [`f32::atan2`] for finite inputs.
+pub fn atan2(y: f32, x: f32) -> f32 {
+    if y.is_nan() || x.is_nan() {
+        return f32::NAN;
+    }
+
+    let (y, x) = (y as f64, x as f64);
+
+    if x == 0.0 && y == 0.0 {
+        return if x.is_sign_negative() {
+            (PI as f32).copysign(y as f32)
+        } else {
+            (0.0f32).copysign(y as f32)
+        };
+    }
+
+    let (ax, ay) = (x.abs(), y.abs());
+
+    // Calculate the angle from the X axis, in the range [0, pi/2].
+    let angle = if ay <= ax {
+        atan_unit(ay / ax)
+    } else {
+        FRAC_PI_2 - atan_unit(ax / ay)
+    };
+
+    let angle = if x < 0.0 { PI - angle } else { angle };
+    let angle = if y < 0.0 { -angle } else { angle };
+
+    angle as f32
+}
+
+/// Computes the arctangent of a number, in radians.
+pub fn atan(value: f32) -> f32 {
+    atan2(value, 1.0)
+}
+
+/// Rotates a vector by an angle (in radians).
+pub fn rotate(vector: Vec2<f32>, angle: f32) -> Vec2<f32> {
+    let (sin, cos) = sin_cos(angle);
+
+    Vec2::new(
+        vector.x * cos - vector.y * sin,
+        vector.x * sin + vector.y * cos,
+    )
+}
+
+/// Returns the angle of a vector from the positive X axis, in radians.
+pub fn angle(vector: Vec2<f32>) -> f32 {
+    atan2(vector.y, vector.x)
+}
+
+/// Computes `2` raised to the power of `exponent`.
+pub fn exp2(exponent: f32) -> f32 {
+    if exponent.is_nan() {
+        return f32::NAN;
+    }
+
+    // Anything outside of this range overflows or underflows an f32.
+    if exponent >= 128.0 {
+        return f32::INFINITY;
+    }
+
+    if exponent < -150.0 {
+        return 0.0;
+    }
+
+    let x = exponent as f64;
+    let whole = x.floor();
+
+    // 2^x = 2^whole * e^(fraction * ln(2)), where the first half can be built
+    // directly from the bits of an f64.
+    let y = (x - whole) * LN_2;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+
+    for i in 1..16 {
+        term *= y / i as f64;
+        sum += term;
+    }
+
+    let scale = f64::from_bits(((whole as i64 + 1023) as u64) << 52);
+
+    (sum * scale) as f32
+}
+
+/// Raises a number to an integer power.
+///
+/// Unlike [`f32::powi`], the order of the multiplications is fixed, so the result
+/// does not depend on the platform or the optimizer.
+pub fn powi(base: f32, exponent: i32) -> f32 {
+    let mut result = 1.0f64;
+    let mut square = base as f64;
+    let mut n = exponent.unsigned_abs();
+
+    while n > 0 {
+        if n & 1 == 1 {
+            result *= square;
+        }
+
+        square *= square;
+        n >>= 1;
+    }
+
+    if exponent < 0 {
+        (1.0 / result) as f32
+    } else {
+        result as f32
+    }
+}
+
+/// Taylor series for sin, accurate to well within f32 precision on [-pi/4, pi/4].
+fn sin_poly(x: f64) -> f64 {
+    let x2 = x * x;
+
+    x * (1.0
+        - x2 / 6.0
+            * (1.0
+                - x2 / 20.0
+                    * (1.0
+                        - x2 / 42.0 * (1.0 - x2 / 72.0 * (1.0 - x2 / 110.0 * (1.0 - x2 / 156.0))))))
+}
+
+/// Taylor series for cos, accurate to well within f32 precision on [-pi/4, pi/4].
+fn cos_poly(x: f64) -> f64 {
+    let x2 = x * x;
+
+    1.0 - x2 / 2.0
+        * (1.0
+            - x2 / 12.0
+                * (1.0 - x2 / 30.0 * (1.0 - x2 / 56.0 * (1.0 - x2 / 90.0 * (1.0 - x2 / 132.0)))))
+}
+
+/// Computes atan for a value in the range [0, 1].
+fn atan_unit(x: f64) -> f64 {
+    // Shifting the upper half of the range down makes the series converge much faster.
+    if x > TAN_PI_8 {
+        FRAC_PI_4 + atan_series((x - 1.0) / (x + 1.0))
+    } else {
+        atan_series(x)
+    }
+}
+
+/// Taylor series for atan, accurate to well within f32 precision on [-tan(pi/8), tan(pi/8)].
+fn atan_series(x: f64) -> f64 {
+    let x2 = x * x;
+    let mut term = x;
+    let mut sum = 0.0;
+
+    for i in 0..12 {
+        sum += term / (2 * i + 1) as f64;
+        term *= -x2;
+    }
+
+    sum
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-6;
+
+    #[test]
+    fn trig_matches_std() {
+        for i in -2000..=2000 {
+            let angle = i as f32 * 0.01;
+
+            assert!((sin(angle) - angle.sin()).abs() < EPSILON, "sin({})", angle);
+            assert!((cos(angle) - angle.cos()).abs() < EPSILON, "cos({})", angle);
+        }
+    }
+
+    #[test]
+    fn exp2_matches_std() {
+        for i in -400..=400 {
+            let exponent = i as f32 * 0.05;
+            let expected = exponent.exp2();
+
+            assert!(
+                ((exp2(exponent) - expected) / expected).abs() < EPSILON,
+                "exp2({})",
+                exponent
+            );
+        }
+
+        assert_eq!(exp2(200.0), f32::INFINITY);
+        assert_eq!(exp2(-200.0), 0.0);
+    }
+
+    #[test]
+    fn powi_matches_std() {
+        for i in -20..=20 {
+            let base = i as f32 * 0.15;
+
+            for n in -3..=5 {
+                let expected = base.powi(n);
+
+                assert!(
+                    powi(base, n) == expected
+                        || (powi(base, n) - expected).abs() <= expected.abs() * EPSILON,
+                    "powi({}, {})",
+                    base,
+                    n
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn atan2_matches_std() {
+        for y in -20..=20 {
+            for x in -20..=20 {
+                let (y, x) = (y as f32 * 0.37, x as f32 * 0.41);
+
+                assert!(
+                    (atan2(y, x) - y.atan2(x)).abs() < EPSILON,
+                    "atan2({}, {})",
+                    y,
+                    x
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,454 @@
+//! Easing functions, for making animations and transitions feel less mechanical.
+//!
+//! Each function takes a progress value between `0.0` and `1.0`, and returns the eased
+//! progress. The output is always `0.0` at the start and `1.0` at the end, but some
+//! curves (such as [`back_in`] and [`elastic_out`]) overshoot in between.
+//!
+//! The [`Easing`] enum wraps all of these functions up into a value, which can be
+//! stored (or loaded from a config file) and then applied to any type that implements
+//! [`Lerp`](crate::math::Lerp).
+//!
+//! See [easings.net](https://easings.net/) for a visual reference of each curve.
+//!
+//! When the `deterministic_math` feature is enabled, the curves that need more than
+//! basic arithmetic (such as [`sine_in`] and [`elastic_out`]) use the functions from
+//! [`math::deterministic`](super::deterministic), so that they give identical results
+//! on every platform.
+//!
+//! # Examples
+//!
+//! ```
+//! # use tetra::math::easing::{self, Easing};
+//! # use tetra::math::Vec2;
+//! let progress = 0.25;
+//!
+//! let alpha = easing::cubic_out(progress);
+//!
+//! let position = Easing::BackOut.interpolate(
+//!     Vec2::new(0.0, 0.0),
+//!     Vec2::new(100.0, 50.0),
+//!     progress,
+//! );
+//! ```
+
+use std::f32::consts::PI;
+
+use super::Lerp;
+
+#[cfg(feature = "deterministic_math")]
+use super::deterministic::{cos, exp2, powi, sin};
+
+#[cfg(not(feature = "deterministic_math"))]
+fn sin(x: f32) -> f32 {
+    x.sin()
+}
+
+#[cfg(not(feature = "deterministic_math"))]
+fn cos(x: f32) -> f32 {
+    x.cos()
+}
+
+#[cfg(not(feature = "deterministic_math"))]
+fn exp2(x: f32) -> f32 {
+    x.exp2()
+}
+
+#[cfg(not(feature = "deterministic_math"))]
+fn powi(x: f32, n: i32) -> f32 {
+    x.powi(n)
+}
+
+const BACK_C1: f32 = 1.70158;
+const BACK_C2: f32 = BACK_C1 * 1.525;
+const BACK_C3: f32 = BACK_C1 + 1.0;
+const ELASTIC_C4: f32 = (2.0 * PI) / 3.0;
+const ELASTIC_C5: f32 = (2.0 * PI) / 4.5;
+
+/// No easing - the progress is returned unchanged.
+pub fn linear(t: f32) -> f32 {
+    t
+}
+
+/// Starts slow, and accelerates (quadratic).
+pub fn quad_in(t: f32) -> f32 {
+    t * t
+}
+
+/// Starts fast, and decelerates (quadratic).
+pub fn quad_out(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+/// Accelerates until halfway, then decelerates (quadratic).
+pub fn quad_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - powi(-2.0 * t + 2.0, 2) / 2.0
+    }
+}
+
+/// Starts slow, and accelerates (cubic).
+pub fn cubic_in(t: f32) -> f32 {
+    t * t * t
+}
+
+/// Starts fast, and decelerates (cubic).
+pub fn cubic_out(t: f32) -> f32 {
+    1.0 - powi(1.0 - t, 3)
+}
+
+/// Accelerates until halfway, then decelerates (cubic).
+pub fn cubic_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - powi(-2.0 * t + 2.0, 3) / 2.0
+    }
+}
+
+/// Starts slow, and accelerates (quartic).
+pub fn quart_in(t: f32) -> f32 {
+    powi(t, 4)
+}
+
+/// Starts fast, and decelerates (quartic).
+pub fn quart_out(t: f32) -> f32 {
+    1.0 - powi(1.0 - t, 4)
+}
+
+/// Accelerates until halfway, then decelerates (quartic).
+pub fn quart_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        8.0 * powi(t, 4)
+    } else {
+        1.0 - powi(-2.0 * t + 2.0, 4) / 2.0
+    }
+}
+
+/// Starts slow, and accelerates (quintic).
+pub fn quint_in(t: f32) -> f32 {
+    powi(t, 5)
+}
+
+/// Starts fast, and decelerates (quintic).
+pub fn quint_out(t: f32) -> f32 {
+    1.0 - powi(1.0 - t, 5)
+}
+
+/// Accelerates until halfway, then decelerates (quintic).
+pub fn quint_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        16.0 * powi(t, 5)
+    } else {
+        1.0 - powi(-2.0 * t + 2.0, 5) / 2.0
+    }
+}
+
+/// Starts slow, and accelerates (sinusoidal).
+pub fn sine_in(t: f32) -> f32 {
+    1.0 - cos(t * PI / 2.0)
+}
+
+/// Starts fast, and decelerates (sinusoidal).
+pub fn sine_out(t: f32) -> f32 {
+    sin(t * PI / 2.0)
+}
+
+/// Accelerates until halfway, then decelerates (sinusoidal).
+pub fn sine_in_out(t: f32) -> f32 {
+    -(cos(PI * t) - 1.0) / 2.0
+}
+
+/// Starts very slow, and accelerates sharply (exponential).
+pub fn expo_in(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else {
+        exp2(10.0 * t - 10.0)
+    }
+}
+
+/// Starts very fast, and decelerates sharply (exponential).
+pub fn expo_out(t: f32) -> f32 {
+    if t >= 1.0 {
+        1.0
+    } else {
+        1.0 - exp2(-10.0 * t)
+    }
+}
+
+/// Accelerates sharply until halfway, then decelerates sharply (exponential).
+pub fn expo_in_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        exp2(20.0 * t - 10.0) / 2.0
+    } else {
+        (2.0 - exp2(-20.0 * t + 10.0)) / 2.0
+    }
+}
+
+/// Starts slow, and accelerates (circular).
+pub fn circ_in(t: f32) -> f32 {
+    1.0 - (1.0 - t * t).max(0.0).sqrt()
+}
+
+/// Starts fast, and decelerates (circular).
+pub fn circ_out(t: f32) -> f32 {
+    (1.0 - powi(t - 1.0, 2)).max(0.0).sqrt()
+}
+
+/// Accelerates until halfway, then decelerates (circular).
+pub fn circ_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        (1.0 - (1.0 - powi(2.0 * t, 2)).max(0.0).sqrt()) / 2.0
+    } else {
+        ((1.0 - powi(-2.0 * t + 2.0, 2)).max(0.0).sqrt() + 1.0) / 2.0
+    }
+}
+
+/// Pulls back slightly before accelerating.
+pub fn back_in(t: f32) -> f32 {
+    BACK_C3 * t * t * t - BACK_C1 * t * t
+}
+
+/// Overshoots the target slightly before settling.
+pub fn back_out(t: f32) -> f32 {
+    1.0 + BACK_C3 * powi(t - 1.0, 3) + BACK_C1 * powi(t - 1.0, 2)
+}
+
+/// Pulls back slightly at the start, and overshoots slightly at the end.
+pub fn back_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        (powi(2.0 * t, 2) * ((BACK_C2 + 1.0) * 2.0 * t - BACK_C2)) / 2.0
+    } else {
+        (powi(2.0 * t - 2.0, 2) * ((BACK_C2 + 1.0) * (t * 2.0 - 2.0) + BACK_C2) + 2.0) / 2.0
+    }
+}
+
+/// Wobbles with increasing intensity, like a spring being pulled.
+pub fn elastic_in(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        -exp2(10.0 * t - 10.0) * sin((t * 10.0 - 10.75) * ELASTIC_C4)
+    }
+}
+
+/// Overshoots the target and wobbles back and forth, like a spring being released.
+pub fn elastic_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        exp2(-10.0 * t) * sin((t * 10.0 - 0.75) * ELASTIC_C4) + 1.0
+    }
+}
+
+/// Wobbles at both the start and the end.
+pub fn elastic_in_out(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        -(exp2(20.0 * t - 10.0) * sin((20.0 * t - 11.125) * ELASTIC_C5)) / 2.0
+    } else {
+        (exp2(-20.0 * t + 10.0) * sin((20.0 * t - 11.125) * ELASTIC_C5)) / 2.0 + 1.0
+    }
+}
+
+/// Bounces off the start, with increasing height.
+pub fn bounce_in(t: f32) -> f32 {
+    1.0 - bounce_out(1.0 - t)
+}
+
+/// Bounces against the target, like a ball being dropped.
+pub fn bounce_out(t: f32) -> f32 {
+    const N1: f32 = 7.5625;
+    const D1: f32 = 2.75;
+
+    if t < 1.0 / D1 {
+        N1 * t * t
+    } else if t < 2.0 / D1 {
+        let t = t - 1.5 / D1;
+        N1 * t * t + 0.75
+    } else if t < 2.5 / D1 {
+        let t = t - 2.25 / D1;
+        N1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D1;
+        N1 * t * t + 0.984375
+    }
+}
+
+/// Bounces at both the start and the end.
+pub fn bounce_in_out(t: f32) -> f32 {
+    if t < 0.5 {
+        (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0
+    } else {
+        (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0
+    }
+}
+
+/// An easing curve.
+///
+/// Each variant corresponds to one of the functions in the [`easing`](self) module.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[allow(missing_docs)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    QuartIn,
+    QuartOut,
+    QuartInOut,
+    QuintIn,
+    QuintOut,
+    QuintInOut,
+    SineIn,
+    SineOut,
+    SineInOut,
+    ExpoIn,
+    ExpoOut,
+    ExpoInOut,
+    CircIn,
+    CircOut,
+    CircInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl Easing {
+    /// Applies the easing curve to a progress value between `0.0` and `1.0`.
+    ///
+    /// Values outside of that range will be clamped.
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+
+        match self {
+            Easing::Linear => linear(t),
+            Easing::QuadIn => quad_in(t),
+            Easing::QuadOut => quad_out(t),
+            Easing::QuadInOut => quad_in_out(t),
+            Easing::CubicIn => cubic_in(t),
+            Easing::CubicOut => cubic_out(t),
+            Easing::CubicInOut => cubic_in_out(t),
+            Easing::QuartIn => quart_in(t),
+            Easing::QuartOut => quart_out(t),
+            Easing::QuartInOut => quart_in_out(t),
+            Easing::QuintIn => quint_in(t),
+            Easing::QuintOut => quint_out(t),
+            Easing::QuintInOut => quint_in_out(t),
+            Easing::SineIn => sine_in(t),
+            Easing::SineOut => sine_out(t),
+            Easing::SineInOut => sine_in_out(t),
+            Easing::ExpoIn => expo_in(t),
+            Easing::ExpoOut => expo_out(t),
+            Easing::ExpoInOut => expo_in_out(t),
+            Easing::CircIn => circ_in(t),
+            Easing::CircOut => circ_out(t),
+            Easing::CircInOut => circ_in_out(t),
+            Easing::BackIn => back_in(t),
+            Easing::BackOut => back_out(t),
+            Easing::BackInOut => back_in_out(t),
+            Easing::ElasticIn => elastic_in(t),
+            Easing::ElasticOut => elastic_out(t),
+            Easing::ElasticInOut => elastic_in_out(t),
+            Easing::BounceIn => bounce_in(t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut => bounce_in_out(t),
+        }
+    }
+
+    /// Interpolates between two values, using the easing curve.
+    ///
+    /// The progress will be clamped between `0.0` and `1.0`, but the result will not be,
+    /// as some curves intentionally overshoot the target.
+    pub fn interpolate<T>(self, from: T, to: T, t: f32) -> T
+    where
+        T: Lerp<f32, Output = T>,
+    {
+        T::lerp_unclamped(from, to, self.apply(t))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL: [Easing; 31] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::QuartIn,
+        Easing::QuartOut,
+        Easing::QuartInOut,
+        Easing::QuintIn,
+        Easing::QuintOut,
+        Easing::QuintInOut,
+        Easing::SineIn,
+        Easing::SineOut,
+        Easing::SineInOut,
+        Easing::ExpoIn,
+        Easing::ExpoOut,
+        Easing::ExpoInOut,
+        Easing::CircIn,
+        Easing::CircOut,
+        Easing::CircInOut,
+        Easing::BackIn,
+        Easing::BackOut,
+        Easing::BackInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+    ];
+
+    #[test]
+    fn curves_hit_endpoints() {
+        for easing in ALL.iter() {
+            assert!(easing.apply(0.0).abs() < 1e-4, "{:?} at 0", easing);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-4, "{:?} at 1", easing);
+        }
+    }
+
+    #[test]
+    fn in_out_curves_are_symmetrical() {
+        assert!((quad_in_out(0.5) - 0.5).abs() < 1e-6);
+        assert!((bounce_in_out(0.5) - 0.5).abs() < 1e-6);
+        assert!((sine_in_out(0.25) + sine_in_out(0.75) - 1.0).abs() < 1e-6);
+    }
+}
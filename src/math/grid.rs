@@ -0,0 +1,638 @@
+//! Functions and types for working with 2D grids, such as tilemaps.
+//!
+//! This module provides a [`Grid`] container, along with line-of-sight checks and
+//! pathfinding (via [A*](astar) and [Dijkstra maps](dijkstra_map)), which are useful
+//! for top-down games and roguelikes.
+//!
+//! Grid positions are represented as [`Vec2<i32>`], with `(0, 0)` at the top left.
+//!
+//! # Examples
+//!
+//! ```
+//! # use tetra::math::grid::{self, Adjacency, Grid};
+//! # use tetra::math::Vec2;
+//! let map = Grid::from_fn(10, 10, |pos| pos.x == 5 && pos.y < 8);
+//!
+//! let path = grid::astar(
+//!     &map,
+//!     Vec2::new(0, 0),
+//!     Vec2::new(9, 0),
+//!     Adjacency::Four,
+//!     |&wall| if wall { None } else { Some(1.0) },
+//! );
+//!
+//! assert!(path.is_some());
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::ops::{Index, IndexMut};
+
+use super::Vec2;
+
+const DIAGONAL_COST: f32 = std::f32::consts::SQRT_2;
+
+/// A fixed-size 2D grid of values.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Grid<T> {
+    width: i32,
+    height: i32,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Creates a new grid, with every cell set to the given value.
+    ///
+    /// Negative sizes will be treated as zero.
+    pub fn new(width: i32, height: i32, value: T) -> Grid<T>
+    where
+        T: Clone,
+    {
+        let width = width.max(0);
+        let height = height.max(0);
+
+        Grid {
+            width,
+            height,
+            cells: vec![value; (width * height) as usize],
+        }
+    }
+
+    /// Creates a new grid, using a function to generate the value for each cell.
+    ///
+    /// Negative sizes will be treated as zero.
+    pub fn from_fn<F>(width: i32, height: i32, mut f: F) -> Grid<T>
+    where
+        F: FnMut(Vec2<i32>) -> T,
+    {
+        let width = width.max(0);
+        let height = height.max(0);
+
+        let mut cells = Vec::with_capacity((width * height) as usize);
+
+        for y in 0..height {
+            for x in 0..width {
+                cells.push(f(Vec2::new(x, y)));
+            }
+        }
+
+        Grid {
+            width,
+            height,
+            cells,
+        }
+    }
+
+    /// Creates a new grid from a `Vec` of values, in row-major order.
+    ///
+    /// Returns `None` if the length of the `Vec` does not match the size of the grid.
+    pub fn from_vec(width: i32, height: i32, cells: Vec<T>) -> Option<Grid<T>> {
+        if width < 0 || height < 0 || cells.len() != (width * height) as usize {
+            return None;
+        }
+
+        Some(Grid {
+            width,
+            height,
+            cells,
+        })
+    }
+
+    /// Returns the width of the grid.
+    pub fn width(&self) -> i32 {
+        self.width
+    }
+
+    /// Returns the height of the grid.
+    pub fn height(&self) -> i32 {
+        self.height
+    }
+
+    /// Returns the width and height of the grid.
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    /// Returns whether the given position is inside the grid.
+    pub fn in_bounds(&self, position: Vec2<i32>) -> bool {
+        position.x >= 0 && position.y >= 0 && position.x < self.width && position.y < self.height
+    }
+
+    /// Returns a reference to the value at the given position, or `None` if the
+    /// position is outside of the grid.
+    pub fn get(&self, position: Vec2<i32>) -> Option<&T> {
+        self.index_of(position).map(|i| &self.cells[i])
+    }
+
+    /// Returns a mutable reference to the value at the given position, or `None` if
+    /// the position is outside of the grid.
+    pub fn get_mut(&mut self, position: Vec2<i32>) -> Option<&mut T> {
+        match self.index_of(position) {
+            Some(i) => Some(&mut self.cells[i]),
+            None => None,
+        }
+    }
+
+    /// Sets the value at the given position, returning the previous value.
+    ///
+    /// If the position is outside of the grid, the grid will not be modified, and
+    /// `None` will be returned.
+    pub fn set(&mut self, position: Vec2<i32>, value: T) -> Option<T> {
+        self.get_mut(position)
+            .map(|cell| std::mem::replace(cell, value))
+    }
+
+    /// Sets every cell in the grid to the given value.
+    pub fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        for cell in &mut self.cells {
+            *cell = value.clone();
+        }
+    }
+
+    /// Returns the cells of the grid as a slice, in row-major order.
+    pub fn as_slice(&self) -> &[T] {
+        &self.cells
+    }
+
+    /// Returns the cells of the grid as a mutable slice, in row-major order.
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        &mut self.cells
+    }
+
+    /// Returns an iterator over the positions and values of every cell in the grid,
+    /// in row-major order.
+    pub fn iter(&self) -> impl Iterator<Item = (Vec2<i32>, &T)> + '_ {
+        let width = self.width;
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(i, cell)| (position_of(i, width), cell))
+    }
+
+    /// Returns a mutable iterator over the positions and values of every cell in the
+    /// grid, in row-major order.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Vec2<i32>, &mut T)> + '_ {
+        let width = self.width;
+
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(i, cell)| (position_of(i, width), cell))
+    }
+
+    /// Returns an iterator over the positions that neighbor the given position and are
+    /// inside the grid.
+    pub fn neighbors(
+        &self,
+        position: Vec2<i32>,
+        adjacency: Adjacency,
+    ) -> impl Iterator<Item = Vec2<i32>> + '_ {
+        adjacency
+            .offsets()
+            .iter()
+            .map(move |offset| position + *offset)
+            .filter(move |neighbor| self.in_bounds(*neighbor))
+    }
+
+    /// Creates a new grid by applying a function to every cell of this grid.
+    pub fn map<U, F>(&self, f: F) -> Grid<U>
+    where
+        F: FnMut(&T) -> U,
+    {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.cells.iter().map(f).collect(),
+        }
+    }
+
+    fn index_of(&self, position: Vec2<i32>) -> Option<usize> {
+        if self.in_bounds(position) {
+            Some((position.y * self.width + position.x) as usize)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T> Index<Vec2<i32>> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, position: Vec2<i32>) -> &T {
+        self.get(position).expect("grid position out of bounds")
+    }
+}
+
+impl<T> IndexMut<Vec2<i32>> for Grid<T> {
+    fn index_mut(&mut self, position: Vec2<i32>) -> &mut T {
+        self.get_mut(position).expect("grid position out of bounds")
+    }
+}
+
+fn position_of(index: usize, width: i32) -> Vec2<i32> {
+    let index = index as i32;
+    Vec2::new(index % width, index / width)
+}
+
+/// Which cells are considered to be next to each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub enum Adjacency {
+    /// Only the four orthogonal neighbors (up, down, left and right).
+    Four,
+
+    /// The four orthogonal neighbors, plus the four diagonal neighbors.
+    ///
+    /// When pathfinding, diagonal moves cost `sqrt(2)` times as much as orthogonal
+    /// moves, and paths will not cut across the corners of impassable cells.
+    Eight,
+}
+
+impl Adjacency {
+    fn offsets(self) -> &'static [Vec2<i32>] {
+        const OFFSETS: [Vec2<i32>; 8] = [
+            Vec2::new(0, -1),
+            Vec2::new(1, 0),
+            Vec2::new(0, 1),
+            Vec2::new(-1, 0),
+            Vec2::new(1, -1),
+            Vec2::new(1, 1),
+            Vec2::new(-1, 1),
+            Vec2::new(-1, -1),
+        ];
+
+        match self {
+            Adjacency::Four => &OFFSETS[..4],
+            Adjacency::Eight => &OFFSETS,
+        }
+    }
+
+    fn heuristic(self, from: Vec2<i32>, to: Vec2<i32>) -> f32 {
+        let dx = (from.x - to.x).abs() as f32;
+        let dy = (from.y - to.y).abs() as f32;
+
+        match self {
+            Adjacency::Four => dx + dy,
+            Adjacency::Eight => dx.max(dy) + (DIAGONAL_COST - 1.0) * dx.min(dy),
+        }
+    }
+}
+
+/// Returns the cells along a straight line between two positions (inclusive), using
+/// Bresenham's line algorithm.
+pub fn line(from: Vec2<i32>, to: Vec2<i32>) -> Vec<Vec2<i32>> {
+    let mut points = Vec::new();
+    walk_line(from, to, |point| {
+        points.push(point);
+        true
+    });
+    points
+}
+
+/// Returns whether there is a clear line of sight between two positions.
+///
+/// The `blocks` function is called for each cell between the two positions (not
+/// including the start and end), and should return `true` if the cell blocks vision.
+pub fn line_of_sight<F>(from: Vec2<i32>, to: Vec2<i32>, mut blocks: F) -> bool
+where
+    F: FnMut(Vec2<i32>) -> bool,
+{
+    walk_line(from, to, |point| {
+        point == from || point == to || !blocks(point)
+    })
+}
+
+/// Calls the visitor for each point along a line, stopping early if it returns `false`.
+/// Returns whether the whole line was visited.
+fn walk_line<F>(from: Vec2<i32>, to: Vec2<i32>, mut visit: F) -> bool
+where
+    F: FnMut(Vec2<i32>) -> bool,
+{
+    let dx = (to.x - from.x).abs();
+    let dy = -(to.y - from.y).abs();
+    let sx = if from.x < to.x { 1 } else { -1 };
+    let sy = if from.y < to.y { 1 } else { -1 };
+
+    let mut error = dx + dy;
+    let mut current = from;
+
+    loop {
+        if !visit(current) {
+            return false;
+        }
+
+        if current == to {
+            return true;
+        }
+
+        let e2 = 2 * error;
+
+        if e2 >= dy {
+            error += dy;
+            current.x += sx;
+        }
+
+        if e2 <= dx {
+            error += dx;
+            current.y += sy;
+        }
+    }
+}
+
+/// A path through a grid.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Path {
+    /// The positions along the path, including the start and the goal.
+    pub positions: Vec<Vec2<i32>>,
+
+    /// The total cost of moving along the path.
+    pub cost: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct OpenNode {
+    priority: f32,
+    index: usize,
+}
+
+impl Eq for OpenNode {}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed, so that the binary heap pops the lowest priority first.
+        other
+            .priority
+            .partial_cmp(&self.priority)
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| other.index.cmp(&self.index))
+    }
+}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Calls `visit` with each neighbor of `position` that can be moved into, along with
+/// the cost of moving there.
+fn for_each_move<T, C, F>(
+    grid: &Grid<T>,
+    position: Vec2<i32>,
+    adjacency: Adjacency,
+    cost: &mut C,
+    mut visit: F,
+) where
+    C: FnMut(&T) -> Option<f32>,
+    F: FnMut(Vec2<i32>, f32),
+{
+    for offset in adjacency.offsets() {
+        let neighbor = position + *offset;
+
+        let step_cost = match grid.get(neighbor).and_then(&mut *cost) {
+            Some(c) => c,
+            None => continue,
+        };
+
+        if offset.x != 0 && offset.y != 0 {
+            // Don't allow cutting corners around impassable cells.
+            let horizontal = grid.get(Vec2::new(neighbor.x, position.y));
+            let vertical = grid.get(Vec2::new(position.x, neighbor.y));
+
+            if horizontal.and_then(&mut *cost).is_none() || vertical.and_then(&mut *cost).is_none()
+            {
+                continue;
+            }
+
+            visit(neighbor, step_cost * DIAGONAL_COST);
+        } else {
+            visit(neighbor, step_cost);
+        }
+    }
+}
+
+/// Finds the cheapest path between two positions, using the A* algorithm.
+///
+/// The `cost` function is called with the value of a cell, and should return the cost
+/// of moving into that cell, or `None` if the cell cannot be entered. Costs should be
+/// at least `1.0` - lower costs are allowed, but the returned path may not be the
+/// cheapest one.
+///
+/// Returns `None` if there is no path between the two positions, or if either position
+/// is outside of the grid.
+pub fn astar<T, C>(
+    grid: &Grid<T>,
+    start: Vec2<i32>,
+    goal: Vec2<i32>,
+    adjacency: Adjacency,
+    mut cost: C,
+) -> Option<Path>
+where
+    C: FnMut(&T) -> Option<f32>,
+{
+    let start_index = grid.index_of(start)?;
+    let goal_index = grid.index_of(goal)?;
+
+    let mut best = vec![f32::INFINITY; grid.cells.len()];
+    let mut came_from = vec![usize::MAX; grid.cells.len()];
+    let mut open = BinaryHeap::new();
+
+    best[start_index] = 0.0;
+    open.push(OpenNode {
+        priority: adjacency.heuristic(start, goal),
+        index: start_index,
+    });
+
+    while let Some(OpenNode { priority, index }) = open.pop() {
+        if index == goal_index {
+            let mut positions = vec![goal];
+            let mut current = index;
+
+            while current != start_index {
+                current = came_from[current];
+                positions.push(position_of(current, grid.width));
+            }
+
+            positions.reverse();
+
+            return Some(Path {
+                positions,
+                cost: best[goal_index],
+            });
+        }
+
+        let position = position_of(index, grid.width);
+
+        // Skip stale entries that have since been reached more cheaply.
+        if priority > best[index] + adjacency.heuristic(position, goal) {
+            continue;
+        }
+
+        let current_cost = best[index];
+
+        for_each_move(grid, position, adjacency, &mut cost, |neighbor, step| {
+            let neighbor_index = (neighbor.y * grid.width + neighbor.x) as usize;
+            let new_cost = current_cost + step;
+
+            if new_cost < best[neighbor_index] {
+                best[neighbor_index] = new_cost;
+                came_from[neighbor_index] = index;
+
+                open.push(OpenNode {
+                    priority: new_cost + adjacency.heuristic(neighbor, goal),
+                    index: neighbor_index,
+                });
+            }
+        });
+    }
+
+    None
+}
+
+/// Calculates the cost of the cheapest path from every cell in the grid to the nearest
+/// of the given sources, using Dijkstra's algorithm.
+///
+/// This is often called a 'Dijkstra map', and is a cheap way of making lots of
+/// entities move towards (or away from) the same targets - each entity can simply
+/// step to whichever neighboring cell has the lowest value.
+///
+/// The `cost` function works the same way as in [`astar`]. Cells that cannot be reached
+/// from any of the sources will be `None`.
+pub fn dijkstra_map<T, C>(
+    grid: &Grid<T>,
+    sources: &[Vec2<i32>],
+    adjacency: Adjacency,
+    mut cost: C,
+) -> Grid<Option<f32>>
+where
+    C: FnMut(&T) -> Option<f32>,
+{
+    let mut best = vec![f32::INFINITY; grid.cells.len()];
+    let mut open = BinaryHeap::new();
+
+    for source in sources {
+        if let Some(index) = grid.index_of(*source) {
+            best[index] = 0.0;
+            open.push(OpenNode {
+                priority: 0.0,
+                index,
+            });
+        }
+    }
+
+    while let Some(OpenNode { priority, index }) = open.pop() {
+        if priority > best[index] {
+            continue;
+        }
+
+        let position = position_of(index, grid.width);
+
+        for_each_move(grid, position, adjacency, &mut cost, |neighbor, step| {
+            let neighbor_index = (neighbor.y * grid.width + neighbor.x) as usize;
+            let new_cost = priority + step;
+
+            if new_cost < best[neighbor_index] {
+                best[neighbor_index] = new_cost;
+
+                open.push(OpenNode {
+                    priority: new_cost,
+                    index: neighbor_index,
+                });
+            }
+        });
+    }
+
+    Grid {
+        width: grid.width,
+        height: grid.height,
+        cells: best
+            .into_iter()
+            .map(|c| if c.is_finite() { Some(c) } else { None })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn passable(wall: &bool) -> Option<f32> {
+        if *wall {
+            None
+        } else {
+            Some(1.0)
+        }
+    }
+
+    #[test]
+    fn bresenham_lines() {
+        assert_eq!(
+            line(Vec2::new(0, 0), Vec2::new(3, 1)),
+            vec![
+                Vec2::new(0, 0),
+                Vec2::new(1, 0),
+                Vec2::new(2, 1),
+                Vec2::new(3, 1)
+            ]
+        );
+
+        assert_eq!(line(Vec2::new(2, 5), Vec2::new(2, 2)).len(), 4);
+        assert!(!line_of_sight(Vec2::new(0, 0), Vec2::new(4, 0), |p| p.x == 2));
+        assert!(line_of_sight(Vec2::new(0, 0), Vec2::new(2, 0), |p| p.x == 2));
+    }
+
+    #[test]
+    fn astar_goes_around_walls() {
+        let map = Grid::from_fn(5, 5, |pos| pos.x == 2 && pos.y < 4);
+
+        let path = astar(
+            &map,
+            Vec2::new(0, 0),
+            Vec2::new(4, 0),
+            Adjacency::Four,
+            passable,
+        )
+        .unwrap();
+
+        assert_eq!(path.cost, 12.0);
+        assert_eq!(path.positions.len(), 13);
+        assert!(path.positions.iter().all(|p| !map[*p]));
+
+        let blocked = Grid::from_fn(5, 5, |pos| pos.x == 2);
+        assert!(astar(
+            &blocked,
+            Vec2::new(0, 0),
+            Vec2::new(4, 0),
+            Adjacency::Eight,
+            passable
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn dijkstra_map_costs() {
+        let map = Grid::new(3, 3, false);
+        let costs = dijkstra_map(&map, &[Vec2::new(0, 0)], Adjacency::Eight, passable);
+
+        assert_eq!(costs[Vec2::new(0, 0)], Some(0.0));
+        assert_eq!(costs[Vec2::new(2, 0)], Some(2.0));
+        assert_eq!(costs[Vec2::new(1, 1)], Some(DIAGONAL_COST));
+    }
+}
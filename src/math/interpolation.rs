@@ -0,0 +1,125 @@
+use super::{Lerp, Vec2};
+use crate::graphics::Color;
+
+/// Linearly interpolates between two values.
+///
+/// A `t` of `0.0` returns `from`, and a `t` of `1.0` returns `to`. Values outside of
+/// that range are not clamped, and will extrapolate past the ends.
+///
+/// This works with any type that implements [`Lerp`], which includes [`f32`],
+/// [`Vec2`] and [`Color`].
+pub fn lerp<T>(from: T, to: T, t: f32) -> T
+where
+    T: Lerp<f32, Output = T>,
+{
+    T::lerp_unclamped(from, to, t)
+}
+
+/// Returns how far `value` is between `from` and `to`.
+///
+/// This is the inverse of [`lerp`] - a value equal to `from` returns `0.0`, and a value
+/// equal to `to` returns `1.0`. The result is not clamped. If `from` and `to` are equal,
+/// `0.0` will be returned.
+pub fn inverse_lerp(from: f32, to: f32, value: f32) -> f32 {
+    if from == to {
+        0.0
+    } else {
+        (value - from) / (to - from)
+    }
+}
+
+/// Maps a value from one range into another.
+///
+/// For example, remapping `5.0` from the range `0.0..10.0` into the range
+/// `100.0..200.0` returns `150.0`. The result is not clamped.
+pub fn remap(value: f32, from_min: f32, from_max: f32, to_min: f32, to_max: f32) -> f32 {
+    lerp(to_min, to_max, inverse_lerp(from_min, from_max, value))
+}
+
+/// Smoothly interpolates between `0.0` and `1.0` as `value` moves from `edge0` to
+/// `edge1`, in the same way as the GLSL function of the same name.
+///
+/// Values outside of the range are clamped.
+pub fn smoothstep(edge0: f32, edge1: f32, value: f32) -> f32 {
+    let t = inverse_lerp(edge0, edge1, value).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+/// Moves a value towards a target, by at most `max_delta`.
+///
+/// The target will never be overshot, which makes this useful for things like
+/// acceleration, or smoothly following a target at a fixed speed.
+///
+/// This works with any type that implements [`MoveToward`], which includes [`f32`],
+/// [`Vec2`] and [`Color`].
+pub fn move_toward<T>(current: T, target: T, max_delta: f32) -> T
+where
+    T: MoveToward,
+{
+    T::move_toward(current, target, max_delta)
+}
+
+/// Types that can be moved towards a target value at a fixed rate.
+///
+/// See [`move_toward`] for more information.
+pub trait MoveToward: Sized {
+    /// Moves `current` towards `target`, by at most `max_delta`.
+    fn move_toward(current: Self, target: Self, max_delta: f32) -> Self;
+}
+
+impl MoveToward for f32 {
+    fn move_toward(current: f32, target: f32, max_delta: f32) -> f32 {
+        if (target - current).abs() <= max_delta {
+            target
+        } else {
+            current + (target - current).signum() * max_delta
+        }
+    }
+}
+
+impl MoveToward for Vec2<f32> {
+    fn move_toward(current: Vec2<f32>, target: Vec2<f32>, max_delta: f32) -> Vec2<f32> {
+        let offset = target - current;
+        let distance = offset.magnitude();
+
+        if distance <= max_delta || distance == 0.0 {
+            target
+        } else {
+            current + offset / distance * max_delta
+        }
+    }
+}
+
+impl MoveToward for Color {
+    /// Moves each component of the color independently.
+    fn move_toward(current: Color, target: Color, max_delta: f32) -> Color {
+        Color::rgba(
+            f32::move_toward(current.r, target.r, max_delta),
+            f32::move_toward(current.g, target.g, max_delta),
+            f32::move_toward(current.b, target.b, max_delta),
+            f32::move_toward(current.a, target.a, max_delta),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn remapping() {
+        assert_eq!(inverse_lerp(10.0, 20.0, 15.0), 0.5);
+        assert_eq!(remap(5.0, 0.0, 10.0, 100.0, 200.0), 150.0);
+        assert_eq!(smoothstep(0.0, 1.0, 2.0), 1.0);
+        assert_eq!(smoothstep(0.0, 1.0, 0.5), 0.5);
+    }
+
+    #[test]
+    fn move_toward_does_not_overshoot() {
+        assert_eq!(move_toward(0.0, 1.0, 0.25), 0.25);
+        assert_eq!(move_toward(0.0, -1.0, 5.0), -1.0);
+
+        let v = move_toward(Vec2::new(0.0, 0.0), Vec2::new(3.0, 4.0), 2.5);
+        assert_eq!(v, Vec2::new(1.5, 2.0));
+    }
+}
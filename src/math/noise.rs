@@ -0,0 +1,696 @@
+//! Functions and types for generating coherent noise.
+//!
+//! Coherent noise is random, but changes smoothly over space, which makes it useful
+//! for procedural generation (e.g. terrain heightmaps, clouds, or wobbling effects).
+//!
+//! Three kinds of noise are provided:
+//!
+//! * [`Perlin`] - classic gradient noise.
+//! * [`Simplex`] - gradient noise with fewer directional artifacts than Perlin noise,
+//!   which is also faster in higher dimensions.
+//! * [`Worley`] - cellular noise, which looks like cells or stones.
+//!
+//! All of these implement the [`Noise`] trait, and can be layered into more detailed
+//! noise using [`Fbm`].
+//!
+//! Noise is generated from a seed, and the output for a given seed is the same on
+//! every platform.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! # use tetra::Context;
+//! # use tetra::math::noise::{self, Fbm, Noise, Simplex};
+//! # fn example(ctx: &mut Context) -> tetra::Result {
+//! let clouds = Fbm::new(Simplex::new(1234)).octaves(5);
+//!
+//! let height = clouds.get_2d(12.5, 3.0);
+//!
+//! let texture = noise::to_image_data(&clouds, 256, 256, 0.02)?.to_texture(ctx)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::graphics::ImageData;
+use crate::Result;
+
+use super::Rng;
+
+/// A source of coherent noise.
+///
+/// Unless otherwise specified, implementations return values roughly in the range
+/// `-1.0` to `1.0`.
+pub trait Noise {
+    /// Samples one-dimensional noise.
+    fn get_1d(&self, x: f32) -> f32;
+
+    /// Samples two-dimensional noise.
+    fn get_2d(&self, x: f32, y: f32) -> f32;
+
+    /// Samples three-dimensional noise.
+    ///
+    /// A common trick is to use the third dimension as time, which allows
+    /// two-dimensional noise to be smoothly animated.
+    fn get_3d(&self, x: f32, y: f32, z: f32) -> f32;
+}
+
+impl<N> Noise for &N
+where
+    N: Noise + ?Sized,
+{
+    fn get_1d(&self, x: f32) -> f32 {
+        (**self).get_1d(x)
+    }
+
+    fn get_2d(&self, x: f32, y: f32) -> f32 {
+        (**self).get_2d(x, y)
+    }
+
+    fn get_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        (**self).get_3d(x, y, z)
+    }
+}
+
+/// A shuffled table of the numbers 0 to 255, repeated twice to avoid having to wrap
+/// indices. This is used to hash lattice coordinates in gradient noise.
+#[derive(Clone)]
+struct Permutation {
+    table: [u8; 512],
+}
+
+impl Permutation {
+    fn new(seed: u64) -> Permutation {
+        let mut values = [0u8; 256];
+
+        for (i, value) in values.iter_mut().enumerate() {
+            *value = i as u8;
+        }
+
+        Rng::new(seed).shuffle(&mut values);
+
+        let mut table = [0; 512];
+        table[..256].copy_from_slice(&values);
+        table[256..].copy_from_slice(&values);
+
+        Permutation { table }
+    }
+
+    fn hash(&self, i: i32) -> usize {
+        self.table[(i & 255) as usize] as usize
+    }
+}
+
+impl std::fmt::Debug for Permutation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Permutation").finish()
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + t * (b - a)
+}
+
+fn grad_1d(hash: usize, x: f32) -> f32 {
+    // Gradients between -8 and 8, excluding 0.
+    let grad = 1.0 + (hash & 7) as f32;
+
+    if hash & 8 == 0 {
+        grad * x
+    } else {
+        -grad * x
+    }
+}
+
+fn grad_2d(hash: usize, x: f32, y: f32) -> f32 {
+    match hash & 7 {
+        0 => x + y,
+        1 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x,
+        5 => -x,
+        6 => y,
+        _ => -y,
+    }
+}
+
+fn grad_3d(hash: usize, x: f32, y: f32, z: f32) -> f32 {
+    match hash & 15 {
+        0 | 12 => x + y,
+        1 | 14 => -x + y,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => -x + z,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 | 13 => -y + z,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
+
+/// Classic Perlin gradient noise.
+#[derive(Debug, Clone)]
+pub struct Perlin {
+    perm: Permutation,
+}
+
+impl Perlin {
+    /// Creates a new Perlin noise generator from the given seed.
+    pub fn new(seed: u64) -> Perlin {
+        Perlin {
+            perm: Permutation::new(seed),
+        }
+    }
+}
+
+impl Noise for Perlin {
+    fn get_1d(&self, x: f32) -> f32 {
+        let xi = x.floor() as i32;
+        let xf = x - x.floor();
+
+        let a = grad_1d(self.perm.hash(xi), xf);
+        let b = grad_1d(self.perm.hash(xi + 1), xf - 1.0);
+
+        // The maximum value of 1D gradient noise is half the maximum gradient.
+        lerp(a, b, fade(xf)) * 0.25
+    }
+
+    fn get_2d(&self, x: f32, y: f32) -> f32 {
+        let (xi, yi) = (x.floor() as i32, y.floor() as i32);
+        let (xf, yf) = (x - x.floor(), y - y.floor());
+        let (u, v) = (fade(xf), fade(yf));
+
+        let p = &self.perm;
+        let a = p.hash(xi) as i32;
+        let b = p.hash(xi + 1) as i32;
+
+        let aa = p.hash(a + yi);
+        let ab = p.hash(a + yi + 1);
+        let ba = p.hash(b + yi);
+        let bb = p.hash(b + yi + 1);
+
+        let x1 = lerp(grad_2d(aa, xf, yf), grad_2d(ba, xf - 1.0, yf), u);
+        let x2 = lerp(
+            grad_2d(ab, xf, yf - 1.0),
+            grad_2d(bb, xf - 1.0, yf - 1.0),
+            u,
+        );
+
+        lerp(x1, x2, v)
+    }
+
+    fn get_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, yi, zi) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let (xf, yf, zf) = (x - x.floor(), y - y.floor(), z - z.floor());
+        let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+
+        let p = &self.perm;
+        let a = p.hash(xi) as i32;
+        let b = p.hash(xi + 1) as i32;
+
+        let aa = p.hash(a + yi) as i32;
+        let ab = p.hash(a + yi + 1) as i32;
+        let ba = p.hash(b + yi) as i32;
+        let bb = p.hash(b + yi + 1) as i32;
+
+        let x1 = lerp(
+            grad_3d(p.hash(aa + zi), xf, yf, zf),
+            grad_3d(p.hash(ba + zi), xf - 1.0, yf, zf),
+            u,
+        );
+
+        let x2 = lerp(
+            grad_3d(p.hash(ab + zi), xf, yf - 1.0, zf),
+            grad_3d(p.hash(bb + zi), xf - 1.0, yf - 1.0, zf),
+            u,
+        );
+
+        let x3 = lerp(
+            grad_3d(p.hash(aa + zi + 1), xf, yf, zf - 1.0),
+            grad_3d(p.hash(ba + zi + 1), xf - 1.0, yf, zf - 1.0),
+            u,
+        );
+
+        let x4 = lerp(
+            grad_3d(p.hash(ab + zi + 1), xf, yf - 1.0, zf - 1.0),
+            grad_3d(p.hash(bb + zi + 1), xf - 1.0, yf - 1.0, zf - 1.0),
+            u,
+        );
+
+        lerp(lerp(x1, x2, v), lerp(x3, x4, v), w)
+    }
+}
+
+/// Simplex gradient noise.
+///
+/// Simplex noise has fewer directional artifacts than [`Perlin`] noise, and scales
+/// better to higher dimensions.
+#[derive(Debug, Clone)]
+pub struct Simplex {
+    perm: Permutation,
+}
+
+impl Simplex {
+    /// Creates a new Simplex noise generator from the given seed.
+    pub fn new(seed: u64) -> Simplex {
+        Simplex {
+            perm: Permutation::new(seed),
+        }
+    }
+}
+
+impl Noise for Simplex {
+    fn get_1d(&self, x: f32) -> f32 {
+        let i0 = x.floor() as i32;
+        let x0 = x - x.floor();
+        let x1 = x0 - 1.0;
+
+        let corner = |hash: usize, x: f32| {
+            let t = (1.0 - x * x).max(0.0);
+            let t2 = t * t;
+            t2 * t2 * grad_1d(hash, x)
+        };
+
+        let n = corner(self.perm.hash(i0), x0) + corner(self.perm.hash(i0 + 1), x1);
+
+        // Scales the output to roughly fit in [-1, 1].
+        0.395 * n
+    }
+
+    fn get_2d(&self, x: f32, y: f32) -> f32 {
+        const F2: f32 = 0.366_025_42; // (sqrt(3) - 1) / 2
+        const G2: f32 = 0.211_324_87; // (3 - sqrt(3)) / 6
+
+        // Skew the input space to find which simplex cell we're in.
+        let s = (x + y) * F2;
+        let i = (x + s).floor();
+        let j = (y + s).floor();
+
+        let t = (i + j) * G2;
+        let x0 = x - (i - t);
+        let y0 = y - (j - t);
+
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+        let x1 = x0 - i1 as f32 + G2;
+        let y1 = y0 - j1 as f32 + G2;
+        let x2 = x0 - 1.0 + 2.0 * G2;
+        let y2 = y0 - 1.0 + 2.0 * G2;
+
+        let (i, j) = (i as i32, j as i32);
+        let p = &self.perm;
+
+        let corner = |hash: usize, x: f32, y: f32| {
+            let t = 0.5 - x * x - y * y;
+
+            if t < 0.0 {
+                0.0
+            } else {
+                let t2 = t * t;
+                t2 * t2 * grad_2d(hash, x, y)
+            }
+        };
+
+        let n0 = corner(p.hash(i + p.hash(j) as i32), x0, y0);
+        let n1 = corner(p.hash(i + i1 + p.hash(j + j1) as i32), x1, y1);
+        let n2 = corner(p.hash(i + 1 + p.hash(j + 1) as i32), x2, y2);
+
+        // Scales the output to roughly fit in [-1, 1].
+        70.0 * (n0 + n1 + n2)
+    }
+
+    fn get_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        const F3: f32 = 1.0 / 3.0;
+        const G3: f32 = 1.0 / 6.0;
+
+        let s = (x + y + z) * F3;
+        let i = (x + s).floor();
+        let j = (y + s).floor();
+        let k = (z + s).floor();
+
+        let t = (i + j + k) * G3;
+        let x0 = x - (i - t);
+        let y0 = y - (j - t);
+        let z0 = z - (k - t);
+
+        // Work out which of the six tetrahedra in the cell we're in.
+        let (i1, j1, k1, i2, j2, k2) = if x0 >= y0 {
+            if y0 >= z0 {
+                (1, 0, 0, 1, 1, 0)
+            } else if x0 >= z0 {
+                (1, 0, 0, 1, 0, 1)
+            } else {
+                (0, 0, 1, 1, 0, 1)
+            }
+        } else if y0 < z0 {
+            (0, 0, 1, 0, 1, 1)
+        } else if x0 < z0 {
+            (0, 1, 0, 0, 1, 1)
+        } else {
+            (0, 1, 0, 1, 1, 0)
+        };
+
+        let x1 = x0 - i1 as f32 + G3;
+        let y1 = y0 - j1 as f32 + G3;
+        let z1 = z0 - k1 as f32 + G3;
+        let x2 = x0 - i2 as f32 + 2.0 * G3;
+        let y2 = y0 - j2 as f32 + 2.0 * G3;
+        let z2 = z0 - k2 as f32 + 2.0 * G3;
+        let x3 = x0 - 1.0 + 3.0 * G3;
+        let y3 = y0 - 1.0 + 3.0 * G3;
+        let z3 = z0 - 1.0 + 3.0 * G3;
+
+        let (i, j, k) = (i as i32, j as i32, k as i32);
+        let p = &self.perm;
+
+        let hash = |di: i32, dj: i32, dk: i32| {
+            p.hash(i + di + p.hash(j + dj + p.hash(k + dk) as i32) as i32)
+        };
+
+        let corner = |hash: usize, x: f32, y: f32, z: f32| {
+            let t = 0.6 - x * x - y * y - z * z;
+
+            if t < 0.0 {
+                0.0
+            } else {
+                let t2 = t * t;
+                t2 * t2 * grad_3d(hash, x, y, z)
+            }
+        };
+
+        let n0 = corner(hash(0, 0, 0), x0, y0, z0);
+        let n1 = corner(hash(i1, j1, k1), x1, y1, z1);
+        let n2 = corner(hash(i2, j2, k2), x2, y2, z2);
+        let n3 = corner(hash(1, 1, 1), x3, y3, z3);
+
+        // Scales the output to roughly fit in [-1, 1].
+        32.0 * (n0 + n1 + n2 + n3)
+    }
+}
+
+/// Worley (cellular) noise.
+///
+/// This scatters one random point in each cell of a grid, and returns the distance
+/// from the sampled position to the nearest point. Unlike the other noise types, the
+/// output is in the range `0.0` to roughly `1.0`, with zero being directly on a point.
+#[derive(Debug, Clone)]
+pub struct Worley {
+    seed: u32,
+}
+
+impl Worley {
+    /// Creates a new Worley noise generator from the given seed.
+    pub fn new(seed: u64) -> Worley {
+        Worley {
+            seed: (seed ^ (seed >> 32)) as u32,
+        }
+    }
+
+    fn hash(&self, x: i32, y: i32, z: i32) -> u32 {
+        let mut h = self.seed
+            ^ (x as u32).wrapping_mul(0x27d4_eb2d)
+            ^ (y as u32).wrapping_mul(0x1656_67b1)
+            ^ (z as u32).wrapping_mul(0x9e37_79b9);
+
+        // Finalizer from MurmurHash3, to mix the bits thoroughly.
+        h ^= h >> 16;
+        h = h.wrapping_mul(0x85eb_ca6b);
+        h ^= h >> 13;
+        h = h.wrapping_mul(0xc2b2_ae35);
+        h ^= h >> 16;
+
+        h
+    }
+
+    /// Returns the position of the feature point in the given cell, relative to the
+    /// cell's origin.
+    fn point(&self, x: i32, y: i32, z: i32) -> (f32, f32, f32) {
+        let h = self.hash(x, y, z);
+
+        let a = (h & 0x3ff) as f32 / 1024.0;
+        let b = ((h >> 10) & 0x3ff) as f32 / 1024.0;
+        let c = ((h >> 20) & 0x3ff) as f32 / 1024.0;
+
+        (a, b, c)
+    }
+}
+
+impl Noise for Worley {
+    fn get_1d(&self, x: f32) -> f32 {
+        let xi = x.floor() as i32;
+        let mut nearest = f32::MAX;
+
+        for dx in -1..=1 {
+            let (px, _, _) = self.point(xi + dx, 0, 0);
+            let distance = ((xi + dx) as f32 + px - x).abs();
+            nearest = nearest.min(distance);
+        }
+
+        nearest
+    }
+
+    fn get_2d(&self, x: f32, y: f32) -> f32 {
+        let (xi, yi) = (x.floor() as i32, y.floor() as i32);
+        let mut nearest = f32::MAX;
+
+        for dy in -1..=1 {
+            for dx in -1..=1 {
+                let (cx, cy) = (xi + dx, yi + dy);
+                let (px, py, _) = self.point(cx, cy, 0);
+
+                let ox = cx as f32 + px - x;
+                let oy = cy as f32 + py - y;
+
+                nearest = nearest.min(ox * ox + oy * oy);
+            }
+        }
+
+        nearest.sqrt()
+    }
+
+    fn get_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        let (xi, yi, zi) = (x.floor() as i32, y.floor() as i32, z.floor() as i32);
+        let mut nearest = f32::MAX;
+
+        for dz in -1..=1 {
+            for dy in -1..=1 {
+                for dx in -1..=1 {
+                    let (cx, cy, cz) = (xi + dx, yi + dy, zi + dz);
+                    let (px, py, pz) = self.point(cx, cy, cz);
+
+                    let ox = cx as f32 + px - x;
+                    let oy = cy as f32 + py - y;
+                    let oz = cz as f32 + pz - z;
+
+                    nearest = nearest.min(ox * ox + oy * oy + oz * oz);
+                }
+            }
+        }
+
+        nearest.sqrt()
+    }
+}
+
+/// Fractal Brownian motion, which layers multiple octaves of noise at increasing
+/// frequencies to add finer detail.
+///
+/// The output is normalized, so it will have roughly the same range as the
+/// underlying noise.
+#[derive(Debug, Clone)]
+pub struct Fbm<N> {
+    noise: N,
+    octaves: u32,
+    lacunarity: f32,
+    gain: f32,
+}
+
+impl<N> Fbm<N>
+where
+    N: Noise,
+{
+    /// Creates a new fBm generator, wrapping the given noise.
+    pub fn new(noise: N) -> Fbm<N> {
+        Fbm {
+            noise,
+            octaves: 4,
+            lacunarity: 2.0,
+            gain: 0.5,
+        }
+    }
+
+    /// Sets the number of layers of noise. More octaves add more detail, but
+    /// are slower to sample.
+    ///
+    /// Defaults to `4`.
+    pub fn octaves(mut self, octaves: u32) -> Fbm<N> {
+        self.octaves = octaves.max(1);
+        self
+    }
+
+    /// Sets how much the frequency increases with each octave.
+    ///
+    /// Defaults to `2.0`.
+    pub fn lacunarity(mut self, lacunarity: f32) -> Fbm<N> {
+        self.lacunarity = lacunarity;
+        self
+    }
+
+    /// Sets how much the amplitude decreases with each octave. Lower values give
+    /// smoother results, and higher values give rougher results.
+    ///
+    /// Defaults to `0.5`.
+    pub fn gain(mut self, gain: f32) -> Fbm<N> {
+        self.gain = gain;
+        self
+    }
+
+    fn sample<F>(&self, mut sample: F) -> f32
+    where
+        F: FnMut(f32) -> f32,
+    {
+        let mut total = 0.0;
+        let mut frequency = 1.0;
+        let mut amplitude = 1.0;
+        let mut max = 0.0;
+
+        for _ in 0..self.octaves {
+            total += sample(frequency) * amplitude;
+            max += amplitude;
+
+            frequency *= self.lacunarity;
+            amplitude *= self.gain;
+        }
+
+        total / max
+    }
+}
+
+impl<N> Noise for Fbm<N>
+where
+    N: Noise,
+{
+    fn get_1d(&self, x: f32) -> f32 {
+        self.sample(|f| self.noise.get_1d(x * f))
+    }
+
+    fn get_2d(&self, x: f32, y: f32) -> f32 {
+        self.sample(|f| self.noise.get_2d(x * f, y * f))
+    }
+
+    fn get_3d(&self, x: f32, y: f32, z: f32) -> f32 {
+        self.sample(|f| self.noise.get_3d(x * f, y * f, z * f))
+    }
+}
+
+/// Bakes two-dimensional noise into a greyscale image.
+///
+/// Each pixel is sampled at its position multiplied by `scale` (so smaller scales will
+/// 'zoom in' on the noise). Values from `-1.0` to `1.0` are mapped to black through
+/// to white - for [`Worley`] noise, which has a different range, use
+/// [`to_image_data_with`] instead.
+///
+/// This can be turned into a [`Texture`](crate::graphics::Texture) via
+/// [`ImageData::to_texture`], for use in shaders or as a lookup table.
+///
+/// # Errors
+///
+/// * [`TetraError::NotEnoughData`](crate::TetraError::NotEnoughData) will be returned if
+///   the width or height are negative.
+pub fn to_image_data<N>(noise: &N, width: i32, height: i32, scale: f32) -> Result<ImageData>
+where
+    N: Noise + ?Sized,
+{
+    to_image_data_with(noise, width, height, scale, |value| value * 0.5 + 0.5)
+}
+
+/// Bakes two-dimensional noise into a greyscale image, using a custom function to map
+/// the noise values to brightness.
+///
+/// The mapping function should return a value between `0.0` (black) and `1.0` (white) -
+/// values outside of that range will be clamped.
+///
+/// # Errors
+///
+/// * [`TetraError::NotEnoughData`](crate::TetraError::NotEnoughData) will be returned if
+///   the width or height are negative.
+pub fn to_image_data_with<N, F>(
+    noise: &N,
+    width: i32,
+    height: i32,
+    scale: f32,
+    mut map: F,
+) -> Result<ImageData>
+where
+    N: Noise + ?Sized,
+    F: FnMut(f32) -> f32,
+{
+    let mut data = Vec::with_capacity((width.max(0) * height.max(0) * 4) as usize);
+
+    for y in 0..height {
+        for x in 0..width {
+            let value = map(noise.get_2d(x as f32 * scale, y as f32 * scale));
+            let byte = (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+            data.extend_from_slice(&[byte, byte, byte, 255]);
+        }
+    }
+
+    ImageData::from_rgba8(width, height, data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_range<N: Noise>(noise: &N, min: f32, max: f32) {
+        for i in 0..500 {
+            let t = i as f32 * 0.173;
+
+            for value in &[
+                noise.get_1d(t),
+                noise.get_2d(t, t * 0.7 - 20.0),
+                noise.get_3d(t * 0.3, -t, t * 1.1),
+            ] {
+                assert!(
+                    *value >= min && *value <= max,
+                    "{} out of range at {}",
+                    value,
+                    t
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn output_ranges() {
+        check_range(&Perlin::new(1), -1.0, 1.0);
+        check_range(&Simplex::new(2), -1.0, 1.0);
+        check_range(&Worley::new(3), 0.0, 1.8);
+        check_range(&Fbm::new(Simplex::new(4)).octaves(6), -1.0, 1.0);
+    }
+
+    #[test]
+    fn gradient_noise_is_zero_on_lattice() {
+        let perlin = Perlin::new(5);
+
+        assert_eq!(perlin.get_2d(3.0, -7.0), 0.0);
+        assert_eq!(perlin.get_3d(1.0, 2.0, 3.0), 0.0);
+    }
+
+    #[test]
+    fn same_seed_same_output() {
+        let a = Simplex::new(99);
+        let b = Simplex::new(99);
+        let c = Simplex::new(100);
+
+        assert_eq!(a.get_2d(1.3, 2.7), b.get_2d(1.3, 2.7));
+        assert_ne!(a.get_2d(1.3, 2.7), c.get_2d(1.3, 2.7));
+    }
+}
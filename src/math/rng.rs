@@ -0,0 +1,372 @@
+use std::ops::{Range, RangeInclusive};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{deterministic, Vec2};
+use crate::graphics::Rectangle;
+
+/// A seeded pseudo-random number generator.
+///
+/// This uses the [PCG32](https://www.pcg-random.org/) algorithm, which is fast, has a
+/// small state, and produces good quality output for games. It is **not** suitable for
+/// cryptographic purposes.
+///
+/// The output for a given seed is guaranteed to be the same on every platform, and will
+/// not change between versions of Tetra. This makes it suitable for things like replays,
+/// procedural generation from a shareable seed, and lockstep networking. The generator can
+/// also be cloned (or serialized) to save its current position in the sequence.
+///
+/// # Examples
+///
+/// ```
+/// # use tetra::math::{Rng, Vec2};
+/// let mut rng = Rng::new(1234);
+///
+/// let damage = rng.range(10..=20);
+/// let spread = rng.range(-0.1..0.1);
+/// let direction: Vec2<f32> = rng.unit_vector();
+///
+/// if rng.chance(0.05) {
+///     // Critical hit!
+/// }
+///
+/// let loot = rng.choose_weighted(&[("Sword", 1.0), ("Gold", 10.0)], |item| item.1);
+/// ```
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Rng {
+    state: u64,
+}
+
+const MULTIPLIER: u64 = 6364136223846793005;
+const INCREMENT: u64 = 1442695040888963407;
+
+impl Rng {
+    /// Creates a new generator from the given seed.
+    pub fn new(seed: u64) -> Rng {
+        let mut rng = Rng { state: 0 };
+
+        rng.next_u32();
+        rng.state = rng.state.wrapping_add(seed);
+        rng.next_u32();
+
+        rng
+    }
+
+    /// Creates a new generator with a seed derived from the current time.
+    ///
+    /// Each call will return a different generator, even if they happen in quick
+    /// succession. If you need to be able to reproduce the output later, use
+    /// [`new`](Self::new) with a seed that you store instead.
+    pub fn from_entropy() -> Rng {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos() as u64)
+            .unwrap_or(0);
+
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+
+        Rng::new(time ^ count.wrapping_mul(MULTIPLIER))
+    }
+
+    /// Creates a new generator, seeded from this one.
+    ///
+    /// This is useful for giving each part of your game (e.g. level generation and
+    /// enemy AI) its own generator, so that they don't affect each other's output,
+    /// while still being reproducible from a single seed.
+    pub fn fork(&mut self) -> Rng {
+        Rng::new(self.next_u64())
+    }
+
+    /// Returns a random `u32`.
+    pub fn next_u32(&mut self) -> u32 {
+        let old_state = self.state;
+
+        self.state = old_state.wrapping_mul(MULTIPLIER).wrapping_add(INCREMENT);
+
+        let xor_shifted = (((old_state >> 18) ^ old_state) >> 27) as u32;
+        let rotation = (old_state >> 59) as u32;
+
+        xor_shifted.rotate_right(rotation)
+    }
+
+    /// Returns a random `u64`.
+    pub fn next_u64(&mut self) -> u64 {
+        let high = self.next_u32() as u64;
+        let low = self.next_u32() as u64;
+
+        (high << 32) | low
+    }
+
+    /// Returns a random `f32` between `0.0` (inclusive) and `1.0` (exclusive).
+    pub fn next_f32(&mut self) -> f32 {
+        // An f32 has 24 bits of precision, so we use the top 24 bits of the output.
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    /// Returns a random `f64` between `0.0` (inclusive) and `1.0` (exclusive).
+    pub fn next_f64(&mut self) -> f64 {
+        // An f64 has 53 bits of precision, so we use the top 53 bits of the output.
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns a random `bool`.
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u32() >> 31 == 1
+    }
+
+    /// Returns a random number within the given range.
+    ///
+    /// Both exclusive (`a..b`) and inclusive (`a..=b`) ranges are supported, for all
+    /// of the primitive integer and float types.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty.
+    pub fn range<T, R>(&mut self, range: R) -> T
+    where
+        R: SampleRange<T>,
+    {
+        range.sample(self)
+    }
+
+    /// Returns `true` with the given probability (between `0.0` and `1.0`).
+    pub fn chance(&mut self, probability: f32) -> bool {
+        self.next_f32() < probability
+    }
+
+    /// Returns a random vector with a length of 1.
+    pub fn unit_vector(&mut self) -> Vec2<f32> {
+        let angle = self.next_f32() * std::f32::consts::TAU;
+        let (sin, cos) = deterministic::sin_cos(angle);
+
+        Vec2::new(cos, sin)
+    }
+
+    /// Returns a random point within a rectangle.
+    pub fn point_in_rectangle(&mut self, rectangle: Rectangle) -> Vec2<f32> {
+        Vec2::new(
+            rectangle.x + self.next_f32() * rectangle.width,
+            rectangle.y + self.next_f32() * rectangle.height,
+        )
+    }
+
+    /// Returns a random point within a circle.
+    ///
+    /// The points will be evenly distributed across the area of the circle, rather
+    /// than being clustered around the center.
+    pub fn point_in_circle(&mut self, center: Vec2<f32>, radius: f32) -> Vec2<f32> {
+        let distance = radius * self.next_f32().sqrt();
+        center + self.unit_vector() * distance
+    }
+
+    /// Returns a random element from a slice, or `None` if the slice is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            None
+        } else {
+            Some(&items[self.below(items.len() as u64) as usize])
+        }
+    }
+
+    /// Returns a random element from a slice, with the probability of each element
+    /// being picked determined by its weight.
+    ///
+    /// For example, an item with a weight of `2.0` is twice as likely to be picked as one
+    /// with a weight of `1.0`. Elements with a weight of zero or less will never be picked.
+    ///
+    /// Returns `None` if the slice is empty, or none of the elements have a positive weight.
+    pub fn choose_weighted<'a, T, F>(&mut self, items: &'a [T], weight: F) -> Option<&'a T>
+    where
+        F: Fn(&T) -> f32,
+    {
+        let total: f32 = items.iter().map(|i| weight(i).max(0.0)).sum();
+
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut target = self.next_f32() * total;
+        let mut last_valid = None;
+
+        for item in items {
+            let item_weight = weight(item);
+
+            if item_weight > 0.0 {
+                if target < item_weight {
+                    return Some(item);
+                }
+
+                target -= item_weight;
+                last_valid = Some(item);
+            }
+        }
+
+        // Floating point error can mean we fall off the end of the list.
+        last_valid
+    }
+
+    /// Shuffles a slice into a random order.
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            let j = self.below(i as u64 + 1) as usize;
+            items.swap(i, j);
+        }
+    }
+
+    /// Returns a uniformly distributed number in the range `[0, n)`.
+    fn below(&mut self, n: u64) -> u64 {
+        debug_assert!(n > 0);
+
+        // Rejecting values from the incomplete chunk at the top of the range avoids
+        // biasing the output towards lower numbers.
+        let zone = u64::MAX - (u64::MAX - n + 1) % n;
+
+        loop {
+            let value = self.next_u64();
+
+            if value <= zone {
+                return value % n;
+            }
+        }
+    }
+}
+
+/// A range that [`Rng::range`] can pick a random value from.
+pub trait SampleRange<T> {
+    /// Picks a random value from the range.
+    fn sample(self, rng: &mut Rng) -> T;
+}
+
+macro_rules! impl_int_range {
+    ($($int:ty => $unsigned:ty),*) => {
+        $(
+            impl SampleRange<$int> for Range<$int> {
+                fn sample(self, rng: &mut Rng) -> $int {
+                    assert!(self.start < self.end, "cannot sample from an empty range");
+
+                    let size = self.end.wrapping_sub(self.start) as $unsigned as u64;
+                    self.start.wrapping_add(rng.below(size) as $int)
+                }
+            }
+
+            impl SampleRange<$int> for RangeInclusive<$int> {
+                fn sample(self, rng: &mut Rng) -> $int {
+                    let (start, end) = self.into_inner();
+                    assert!(start <= end, "cannot sample from an empty range");
+
+                    let size = end.wrapping_sub(start) as $unsigned as u64;
+
+                    // The range covers every possible value, so any output will do.
+                    if size == u64::MAX {
+                        return rng.next_u64() as $int;
+                    }
+
+                    start.wrapping_add(rng.below(size + 1) as $int)
+                }
+            }
+        )*
+    };
+}
+
+impl_int_range!(
+    i8 => u8, i16 => u16, i32 => u32, i64 => u64, isize => usize,
+    u8 => u8, u16 => u16, u32 => u32, u64 => u64, usize => usize
+);
+
+macro_rules! impl_float_range {
+    ($($float:ty => $next:ident),*) => {
+        $(
+            impl SampleRange<$float> for Range<$float> {
+                fn sample(self, rng: &mut Rng) -> $float {
+                    assert!(self.start < self.end, "cannot sample from an empty range");
+
+                    let value = self.start + rng.$next() * (self.end - self.start);
+
+                    // Rounding can occasionally push the value up to the end of
+                    // the range, which should be excluded.
+                    if value < self.end {
+                        value
+                    } else {
+                        self.start
+                    }
+                }
+            }
+
+            impl SampleRange<$float> for RangeInclusive<$float> {
+                fn sample(self, rng: &mut Rng) -> $float {
+                    let (start, end) = self.into_inner();
+                    assert!(start <= end, "cannot sample from an empty range");
+
+                    (start + rng.$next() * (end - start)).min(end)
+                }
+            }
+        )*
+    };
+}
+
+impl_float_range!(f32 => next_f32, f64 => next_f64);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn output_is_stable() {
+        let mut rng = Rng::new(42);
+
+        // If this test fails, the output of the generator has changed, which will
+        // break people's replays and saved seeds!
+        let output: Vec<u32> = (0..4).map(|_| rng.next_u32()).collect();
+        assert_eq!(output, [3270867926, 1795671209, 1924641435, 1143034755]);
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = Rng::new(7);
+
+        for _ in 0..1000 {
+            assert!((-5..5).contains(&rng.range(-5..5)));
+            assert!((250..=255).contains(&rng.range(250u8..=255)));
+            assert!((1.0..2.0).contains(&rng.range(1.0f32..2.0)));
+        }
+
+        assert_eq!(rng.range(3..=3), 3);
+        rng.range(i64::MIN..=i64::MAX);
+    }
+
+    #[test]
+    fn weighted_choice() {
+        let mut rng = Rng::new(3);
+        let items = [("never", 0.0), ("always", 1.0), ("never", -1.0)];
+
+        for _ in 0..100 {
+            assert_eq!(rng.choose_weighted(&items, |i| i.1).unwrap().0, "always");
+        }
+
+        assert!(rng.choose_weighted(&items[..1], |i| i.1).is_none());
+    }
+
+    #[test]
+    fn floats_are_in_range() {
+        let mut rng = Rng::new(1);
+
+        for _ in 0..1000 {
+            let f = rng.next_f32();
+            assert!((0.0..1.0).contains(&f));
+
+            let f = rng.next_f64();
+            assert!((0.0..1.0).contains(&f));
+        }
+    }
+}
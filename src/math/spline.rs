@@ -0,0 +1,506 @@
+use super::{CubicBezier2, QuadraticBezier2, Vec2};
+
+/// The maximum number of times a curve will be subdivided when flattening.
+const MAX_FLATTEN_DEPTH: u32 = 12;
+
+/// A smooth curve made up of one or more segments.
+///
+/// Positions along a spline are specified via a parameter `t`, which runs from `0.0` at
+/// the start of the spline to `1.0` at the end, with each segment taking up an equal
+/// share of that range. Note that this means `t` does not move at a constant speed along
+/// the curve - if you need that (e.g. for moving an enemy along a path), use
+/// [`ArcLength`] to convert between distances and `t` values.
+pub trait Spline {
+    /// Returns the number of segments in the spline.
+    fn segment_count(&self) -> usize;
+
+    /// Returns the cubic Bezier curve for the given segment.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the segment index is out of bounds.
+    fn segment(&self, index: usize) -> CubicBezier2<f32>;
+
+    /// Returns the point at `t` along the spline.
+    ///
+    /// If the spline is empty, this returns the origin.
+    fn point(&self, t: f32) -> Vec2<f32> {
+        match locate(self.segment_count(), t) {
+            Some((index, local_t)) => self.segment(index).evaluate(local_t),
+            None => Vec2::zero(),
+        }
+    }
+
+    /// Returns the tangent at `t` along the spline.
+    ///
+    /// This is the rate of change of the position with respect to `t`, and is not
+    /// normalized - call [`Vec2::normalized`] on the result if you only need the
+    /// direction (e.g. to rotate a sprite to follow the curve).
+    fn tangent(&self, t: f32) -> Vec2<f32> {
+        let count = self.segment_count();
+
+        match locate(count, t) {
+            Some((index, local_t)) => {
+                self.segment(index).evaluate_derivative(local_t) * count as f32
+            }
+            None => Vec2::zero(),
+        }
+    }
+
+    /// Approximates the spline as a list of points, which can be drawn via
+    /// [`Mesh::polyline`](crate::graphics::mesh::Mesh::polyline) or
+    /// [`GeometryBuilder::polyline`](crate::graphics::mesh::GeometryBuilder::polyline).
+    ///
+    /// Segments are subdivided adaptively, so that the line never strays further than
+    /// `tolerance` from the true curve. Straight sections will use very few points,
+    /// while tight bends will use many.
+    fn flatten(&self, tolerance: f32) -> Vec<Vec2<f32>> {
+        flatten_with_t(self, tolerance)
+            .into_iter()
+            .map(|(_, point)| point)
+            .collect()
+    }
+}
+
+/// Maps a global `t` value to a segment index and a `t` value within that segment.
+fn locate(segment_count: usize, t: f32) -> Option<(usize, f32)> {
+    if segment_count == 0 {
+        return None;
+    }
+
+    let scaled = t.clamp(0.0, 1.0) * segment_count as f32;
+    let index = (scaled.floor() as usize).min(segment_count - 1);
+
+    Some((index, scaled - index as f32))
+}
+
+fn flatten_with_t<S>(spline: &S, tolerance: f32) -> Vec<(f32, Vec2<f32>)>
+where
+    S: Spline + ?Sized,
+{
+    let count = spline.segment_count();
+    let tolerance = tolerance.max(f32::EPSILON);
+
+    if count == 0 {
+        return Vec::new();
+    }
+
+    let mut points = vec![(0.0, spline.segment(0).start)];
+
+    for index in 0..count {
+        let segment = spline.segment(index);
+        let to_global = |local_t: f32| (index as f32 + local_t) / count as f32;
+
+        let mut local = Vec::new();
+        subdivide(segment, 0.0, 1.0, tolerance, 0, &mut local);
+
+        points.extend(local.into_iter().map(|(t, p)| (to_global(t), p)));
+    }
+
+    points
+}
+
+/// Recursively splits a curve until each piece is within the tolerance of a straight
+/// line, pushing the end point of each piece.
+fn subdivide(
+    curve: CubicBezier2<f32>,
+    t0: f32,
+    t1: f32,
+    tolerance: f32,
+    depth: u32,
+    out: &mut Vec<(f32, Vec2<f32>)>,
+) {
+    let start = curve.evaluate(t0);
+    let end = curve.evaluate(t1);
+    let mid_t = (t0 + t1) / 2.0;
+
+    // An S-shaped curve can pass through the midpoint of its chord, so always split
+    // at least twice before trusting the flatness check.
+    let flat = depth >= 2 && {
+        let quarter = curve.evaluate((t0 + mid_t) / 2.0);
+        let mid = curve.evaluate(mid_t);
+        let three_quarter = curve.evaluate((mid_t + t1) / 2.0);
+
+        distance_to_segment(mid, start, end) <= tolerance
+            && distance_to_segment(quarter, start, end) <= tolerance
+            && distance_to_segment(three_quarter, start, end) <= tolerance
+    };
+
+    if flat || depth >= MAX_FLATTEN_DEPTH {
+        out.push((t1, end));
+    } else {
+        subdivide(curve, t0, mid_t, tolerance, depth + 1, out);
+        subdivide(curve, mid_t, t1, tolerance, depth + 1, out);
+    }
+}
+
+fn distance_to_segment(point: Vec2<f32>, a: Vec2<f32>, b: Vec2<f32>) -> f32 {
+    let ab = b - a;
+    let length_squared = ab.magnitude_squared();
+
+    if length_squared == 0.0 {
+        return point.distance(a);
+    }
+
+    let t = ((point - a).dot(ab) / length_squared).clamp(0.0, 1.0);
+    point.distance(a + ab * t)
+}
+
+/// A path made up of straight lines and Bezier curves, joined end to end.
+///
+/// # Examples
+///
+/// ```
+/// # use tetra::math::{BezierPath, Spline, Vec2};
+/// let mut path = BezierPath::new(Vec2::new(0.0, 0.0));
+///
+/// path.line_to(Vec2::new(100.0, 0.0))
+///     .quadratic_to(Vec2::new(150.0, 0.0), Vec2::new(150.0, 50.0))
+///     .cubic_to(
+///         Vec2::new(150.0, 100.0),
+///         Vec2::new(50.0, 100.0),
+///         Vec2::new(50.0, 150.0),
+///     );
+///
+/// let midpoint = path.point(0.5);
+/// let points = path.flatten(0.25);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct BezierPath {
+    start: Vec2<f32>,
+    segments: Vec<CubicBezier2<f32>>,
+}
+
+impl BezierPath {
+    /// Creates a new, empty path starting at the given point.
+    pub fn new(start: Vec2<f32>) -> BezierPath {
+        BezierPath {
+            start,
+            segments: Vec::new(),
+        }
+    }
+
+    /// Returns the current end point of the path.
+    pub fn end(&self) -> Vec2<f32> {
+        self.segments.last().map(|s| s.end).unwrap_or(self.start)
+    }
+
+    /// Adds a straight line from the end of the path to the given point.
+    pub fn line_to(&mut self, end: Vec2<f32>) -> &mut BezierPath {
+        let start = self.end();
+
+        self.segments.push(CubicBezier2 {
+            start,
+            ctrl0: Vec2::lerp(start, end, 1.0 / 3.0),
+            ctrl1: Vec2::lerp(start, end, 2.0 / 3.0),
+            end,
+        });
+
+        self
+    }
+
+    /// Adds a quadratic Bezier curve from the end of the path to the given point.
+    pub fn quadratic_to(&mut self, ctrl: Vec2<f32>, end: Vec2<f32>) -> &mut BezierPath {
+        let start = self.end();
+
+        self.segments
+            .push(QuadraticBezier2 { start, ctrl, end }.into_cubic());
+
+        self
+    }
+
+    /// Adds a cubic Bezier curve from the end of the path to the given point.
+    pub fn cubic_to(
+        &mut self,
+        ctrl0: Vec2<f32>,
+        ctrl1: Vec2<f32>,
+        end: Vec2<f32>,
+    ) -> &mut BezierPath {
+        let start = self.end();
+
+        self.segments.push(CubicBezier2 {
+            start,
+            ctrl0,
+            ctrl1,
+            end,
+        });
+
+        self
+    }
+
+    /// Adds a straight line back to the start of the path, if the path is not
+    /// already closed.
+    pub fn close(&mut self) -> &mut BezierPath {
+        if self.end() != self.start {
+            self.line_to(self.start);
+        }
+
+        self
+    }
+}
+
+impl Spline for BezierPath {
+    fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    fn segment(&self, index: usize) -> CubicBezier2<f32> {
+        self.segments[index]
+    }
+}
+
+/// A Catmull-Rom spline, which passes smoothly through every one of its points.
+///
+/// This is usually the easiest kind of spline to work with, as there are no
+/// control points to place - it's a good fit for things like camera rails or
+/// patrol routes.
+///
+/// # Examples
+///
+/// ```
+/// # use tetra::math::{CatmullRom, Spline, Vec2};
+/// let rail = CatmullRom::new(vec![
+///     Vec2::new(0.0, 0.0),
+///     Vec2::new(100.0, 50.0),
+///     Vec2::new(200.0, 0.0),
+/// ]);
+///
+/// assert_eq!(rail.point(0.5), Vec2::new(100.0, 50.0));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatmullRom {
+    points: Vec<Vec2<f32>>,
+    closed: bool,
+}
+
+impl CatmullRom {
+    /// Creates a spline that passes through the given points, from the first to
+    /// the last.
+    pub fn new(points: Vec<Vec2<f32>>) -> CatmullRom {
+        CatmullRom {
+            points,
+            closed: false,
+        }
+    }
+
+    /// Creates a spline that passes through the given points, and then loops back
+    /// around to the first point.
+    pub fn closed(points: Vec<Vec2<f32>>) -> CatmullRom {
+        CatmullRom {
+            points,
+            closed: true,
+        }
+    }
+
+    /// Returns the points that the spline passes through.
+    pub fn points(&self) -> &[Vec2<f32>] {
+        &self.points
+    }
+
+    /// Returns a mutable reference to the points that the spline passes through.
+    pub fn points_mut(&mut self) -> &mut Vec<Vec2<f32>> {
+        &mut self.points
+    }
+
+    /// Returns whether the spline loops back around to its first point.
+    pub fn is_closed(&self) -> bool {
+        self.closed
+    }
+
+    fn point_wrapped(&self, index: isize) -> Vec2<f32> {
+        let len = self.points.len() as isize;
+
+        if self.closed {
+            self.points[index.rem_euclid(len) as usize]
+        } else {
+            self.points[index.clamp(0, len - 1) as usize]
+        }
+    }
+}
+
+impl Spline for CatmullRom {
+    fn segment_count(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 0,
+            len if self.closed => len,
+            len => len - 1,
+        }
+    }
+
+    fn segment(&self, index: usize) -> CubicBezier2<f32> {
+        assert!(index < self.segment_count(), "segment index out of bounds");
+
+        let i = index as isize;
+
+        let p0 = self.point_wrapped(i - 1);
+        let p1 = self.point_wrapped(i);
+        let p2 = self.point_wrapped(i + 1);
+        let p3 = self.point_wrapped(i + 2);
+
+        CubicBezier2 {
+            start: p1,
+            ctrl0: p1 + (p2 - p0) / 6.0,
+            ctrl1: p2 - (p3 - p1) / 6.0,
+            end: p2,
+        }
+    }
+}
+
+/// A lookup table for converting between distances along a spline and `t` values.
+///
+/// Calculating the exact length of a curve is expensive, so this approximates it by
+/// flattening the spline and measuring the resulting lines. The table should be
+/// rebuilt if the spline changes.
+///
+/// # Examples
+///
+/// ```
+/// # use tetra::math::{ArcLength, CatmullRom, Spline, Vec2};
+/// let path = CatmullRom::new(vec![
+///     Vec2::new(0.0, 0.0),
+///     Vec2::new(10.0, 80.0),
+///     Vec2::new(200.0, 0.0),
+/// ]);
+///
+/// let table = ArcLength::new(&path, 0.1);
+///
+/// // Move along the path at a constant speed, regardless of the shape of the curve.
+/// let distance_travelled = 150.0;
+/// let position = path.point(table.t_at_distance(distance_travelled));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArcLength {
+    samples: Vec<(f32, f32)>,
+}
+
+impl ArcLength {
+    /// Builds a lookup table for the given spline.
+    ///
+    /// The `tolerance` is passed to [`Spline::flatten`] - smaller values give more
+    /// accurate results, at the cost of a larger table.
+    pub fn new<S>(spline: &S, tolerance: f32) -> ArcLength
+    where
+        S: Spline + ?Sized,
+    {
+        let points = flatten_with_t(spline, tolerance);
+        let mut samples = Vec::with_capacity(points.len());
+        let mut distance = 0.0;
+
+        for (i, &(t, point)) in points.iter().enumerate() {
+            if i > 0 {
+                distance += point.distance(points[i - 1].1);
+            }
+
+            samples.push((t, distance));
+        }
+
+        ArcLength { samples }
+    }
+
+    /// Returns the approximate total length of the spline.
+    pub fn length(&self) -> f32 {
+        self.samples.last().map(|s| s.1).unwrap_or(0.0)
+    }
+
+    /// Returns the `t` value that is the given distance along the spline.
+    ///
+    /// The distance will be clamped between zero and the length of the spline.
+    pub fn t_at_distance(&self, distance: f32) -> f32 {
+        let index = self.samples.partition_point(|s| s.1 < distance);
+
+        if index == 0 {
+            return 0.0;
+        }
+
+        match self.samples.get(index) {
+            Some(&(t1, d1)) => {
+                let (t0, d0) = self.samples[index - 1];
+                t0 + (t1 - t0) * ((distance - d0) / (d1 - d0))
+            }
+
+            None => 1.0,
+        }
+    }
+
+    /// Returns the approximate distance along the spline at the given `t` value.
+    pub fn distance_at_t(&self, t: f32) -> f32 {
+        let index = self.samples.partition_point(|s| s.0 < t);
+
+        if index == 0 {
+            return 0.0;
+        }
+
+        match self.samples.get(index) {
+            Some(&(t1, d1)) => {
+                let (t0, d0) = self.samples[index - 1];
+                d0 + (d1 - d0) * ((t - t0) / (t1 - t0))
+            }
+
+            None => self.length(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catmull_rom_passes_through_points() {
+        let points = vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 30.0),
+            Vec2::new(40.0, -5.0),
+            Vec2::new(50.0, 10.0),
+        ];
+
+        let spline = CatmullRom::new(points.clone());
+
+        for (i, point) in points.iter().enumerate() {
+            let t = i as f32 / 3.0;
+            assert!(spline.point(t).distance(*point) < 1e-4);
+        }
+
+        assert_eq!(CatmullRom::closed(points).segment_count(), 4);
+    }
+
+    #[test]
+    fn flatten_respects_tolerance() {
+        let mut path = BezierPath::new(Vec2::new(0.0, 0.0));
+        path.line_to(Vec2::new(100.0, 0.0));
+
+        // Straight lines only need the minimum amount of subdivision.
+        assert_eq!(path.flatten(0.1).len(), 5);
+
+        path.cubic_to(
+            Vec2::new(200.0, 0.0),
+            Vec2::new(200.0, 100.0),
+            Vec2::new(100.0, 100.0),
+        );
+
+        let points = path.flatten(0.1);
+
+        for i in 0..=100 {
+            let p = path.point(i as f32 / 100.0);
+            let closest = points
+                .windows(2)
+                .map(|w| distance_to_segment(p, w[0], w[1]))
+                .fold(f32::MAX, f32::min);
+
+            assert!(closest <= 0.1 + 1e-3);
+        }
+    }
+
+    #[test]
+    fn arc_length_of_line() {
+        let mut path = BezierPath::new(Vec2::new(0.0, 0.0));
+        path.line_to(Vec2::new(100.0, 0.0))
+            .line_to(Vec2::new(100.0, 300.0));
+
+        let table = ArcLength::new(&path, 0.1);
+
+        assert!((table.length() - 400.0).abs() < 1e-3);
+        assert!((path.point(table.t_at_distance(250.0)).y - 150.0).abs() < 1e-3);
+        assert!((table.distance_at_t(0.5) - 100.0).abs() < 1e-3);
+    }
+}
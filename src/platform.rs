@@ -1,8 +1,17 @@
 mod device_gl;
+#[cfg(all(feature = "backend_sdl2", not(feature = "backend_winit")))]
 mod window_sdl;
+#[cfg(feature = "backend_winit")]
+mod window_winit;
+
+#[cfg(not(any(feature = "backend_sdl2", feature = "backend_winit")))]
+compile_error!("Tetra requires a platform backend - enable either the `backend_sdl2` or the `backend_winit` feature.");
 
 pub use device_gl::{
-    GraphicsDevice, RawCanvas, RawIndexBuffer, RawRenderbuffer, RawShader, RawTexture,
-    RawVertexBuffer,
+    GraphicsDevice, RawBackbuffer, RawCanvas, RawIndexBuffer, RawQuery, RawRenderbuffer, RawShader,
+    RawTexture, RawVertexBuffer, TextureFormat,
 };
+#[cfg(all(feature = "backend_sdl2", not(feature = "backend_winit")))]
 pub use window_sdl::{handle_events, Window};
+#[cfg(feature = "backend_winit")]
+pub use window_winit::{handle_events, Window};
@@ -1,26 +1,34 @@
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::mem;
 use std::rc::Rc;
 use std::slice;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 
 use glow::{Context as GlowContext, HasContext, PixelPackData, PixelUnpackData};
 
 use crate::error::{Result, TetraError};
 use crate::graphics::{
-    mesh::{BufferUsage, Vertex, VertexWinding},
+    mesh::{BufferUsage, PrimitiveTopology, Vertex, VertexWinding},
     StencilState, StencilTest,
 };
 use crate::graphics::{
-    BlendFactor, BlendOperation, BlendState, Color, FilterMode, GraphicsDeviceInfo, StencilAction,
+    AttributeInfo, BlendFactor, BlendOperation, BlendState, Capabilities, Color, FilterMode,
+    GpuResource, GpuResourceKind, GraphicsDeviceInfo, ShaderDataType, StencilAction, Swizzle,
+    SwizzleSource, UniformInfo,
 };
 use crate::math::{Mat2, Mat3, Mat4, Vec2, Vec3, Vec4};
 
 type BufferId = <GlowContext as HasContext>::Buffer;
 type ProgramId = <GlowContext as HasContext>::Program;
+type ShaderId = <GlowContext as HasContext>::Shader;
 type TextureId = <GlowContext as HasContext>::Texture;
 type FramebufferId = <GlowContext as HasContext>::Framebuffer;
 type RenderbufferId = <GlowContext as HasContext>::Renderbuffer;
 type VertexArrayId = <GlowContext as HasContext>::VertexArray;
+type SamplerId = <GlowContext as HasContext>::Sampler;
+type QueryId = <GlowContext as HasContext>::Query;
 type UniformLocation = <GlowContext as HasContext>::UniformLocation;
 
 #[derive(Debug)]
@@ -38,7 +46,95 @@ struct GraphicsState {
     vertex_array: VertexArrayId,
     resolve_framebuffer: FramebufferId,
 
-    max_samples: u8,
+    // One sampler per filter mode, used to override a texture's filtering for a draw.
+    filter_samplers: Option<[SamplerId; 2]>,
+    current_filter_override: Cell<Option<FilterMode>>,
+
+    capabilities: Capabilities,
+    debug: bool,
+
+    resources: RefCell<ResourceTracker>,
+    deletions: Arc<DeletionQueue>,
+}
+
+impl GraphicsState {
+    fn track_resource(&self, resource: GpuResource) -> u64 {
+        let mut tracker = self.resources.borrow_mut();
+
+        let handle = tracker.next_handle;
+        tracker.next_handle += 1;
+        tracker.live.insert(handle, resource);
+
+        handle
+    }
+
+    fn untrack_resource(&self, handle: u64) {
+        self.resources.borrow_mut().live.remove(&handle);
+    }
+}
+
+/// Resources that have been dropped, and are waiting to be deleted.
+///
+/// The resource handles can be sent between threads, but OpenGL calls can only be made
+/// from the main thread while the context is current - so rather than deleting the
+/// underlying objects when a handle is dropped, they get pushed onto this queue, which
+/// is processed at the end of each frame.
+///
+/// Once the device has been destroyed, the queue is closed, and any handles that are
+/// dropped after that point are ignored - the objects will have been freed along with
+/// the OpenGL context.
+#[derive(Debug, Default)]
+struct DeletionQueue {
+    pending: Mutex<Vec<PendingDeletion>>,
+    closed: AtomicBool,
+}
+
+impl DeletionQueue {
+    fn push(&self, deletion: PendingDeletion) {
+        if self.closed.load(Ordering::Acquire) {
+            return;
+        }
+
+        self.pending().push(deletion);
+    }
+
+    fn take(&self) -> Vec<PendingDeletion> {
+        mem::take(&mut *self.pending())
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::Release);
+        self.pending().clear();
+    }
+
+    fn pending(&self) -> std::sync::MutexGuard<'_, Vec<PendingDeletion>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[derive(Debug)]
+enum PendingDeletion {
+    VertexBuffer(BufferId, u64),
+    IndexBuffer(BufferId, u64),
+    Shader(ProgramId),
+    Texture(TextureId, u64),
+    Canvas(FramebufferId),
+    Renderbuffer(RenderbufferId, u64),
+    Query(QueryId),
+}
+
+/// Keeps track of the estimated size of every live GPU resource, so that memory usage
+/// can be reported back to the user.
+#[derive(Debug, Default)]
+struct ResourceTracker {
+    next_handle: u64,
+    live: HashMap<u64, GpuResource>,
+}
+
+impl Drop for GraphicsState {
+    fn drop(&mut self) {
+        self.deletions.close();
+    }
 }
 
 pub struct GraphicsDevice {
@@ -46,8 +142,10 @@ pub struct GraphicsDevice {
 }
 
 impl GraphicsDevice {
-    pub fn new(gl: GlowContext) -> Result<GraphicsDevice> {
+    pub fn new(gl: GlowContext, debug: bool) -> Result<GraphicsDevice> {
         unsafe {
+            let debug = debug && enable_debug_output(&gl);
+
             gl.enable(glow::CULL_FACE);
             gl.enable(glow::BLEND);
 
@@ -66,6 +164,11 @@ impl GraphicsDevice {
 
             gl.bind_vertex_array(Some(vertex_array));
 
+            if debug {
+                let name = gl.get_parameter_i32(glow::VERTEX_ARRAY_BINDING) as u32;
+                gl.object_label(glow::VERTEX_ARRAY, name, Some("Tetra Vertex Array"));
+            }
+
             // TODO: Find a nice way of exposing this via the platform layer
             // println!("Swap Interval: {:?}", video.gl_get_swap_interval());
 
@@ -74,7 +177,16 @@ impl GraphicsDevice {
 
             let resolve_framebuffer = gl.create_framebuffer().map_err(TetraError::PlatformError)?;
 
-            let max_samples = gl.get_parameter_i32(glow::MAX_SAMPLES) as u8;
+            let capabilities = query_capabilities(&gl);
+
+            let filter_samplers = if capabilities.sampler_objects {
+                let nearest = create_filter_sampler(&gl, FilterMode::Nearest)?;
+                let linear = create_filter_sampler(&gl, FilterMode::Linear)?;
+
+                Some([nearest, linear])
+            } else {
+                None
+            };
 
             let state = GraphicsState {
                 gl,
@@ -90,7 +202,14 @@ impl GraphicsDevice {
                 vertex_array,
                 resolve_framebuffer,
 
-                max_samples,
+                filter_samplers,
+                current_filter_override: Cell::new(None),
+
+                capabilities,
+                debug,
+
+                resources: RefCell::new(ResourceTracker::default()),
+                deletions: Arc::new(DeletionQueue::default()),
             };
 
             Ok(GraphicsDevice {
@@ -113,6 +232,102 @@ impl GraphicsDevice {
         }
     }
 
+    pub fn get_capabilities(&self) -> Capabilities {
+        self.state.capabilities
+    }
+
+    pub fn get_resources(&self) -> Vec<GpuResource> {
+        self.state
+            .resources
+            .borrow()
+            .live
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Deletes any resources whose handles have been dropped since the last call.
+    pub fn delete_pending_resources(&mut self) {
+        let state = &self.state;
+        let pending = state.deletions.take();
+
+        if !pending.is_empty() {
+            log::trace!(
+                target: "tetra::graphics",
+                "Deleting {} dropped resources",
+                pending.len()
+            );
+        }
+
+        for deletion in pending {
+            unsafe {
+                match deletion {
+                    PendingDeletion::VertexBuffer(id, handle) => {
+                        if state.current_vertex_buffer.get() == Some(id) {
+                            state.current_vertex_buffer.set(None);
+                        }
+
+                        state.gl.delete_buffer(id);
+                        state.untrack_resource(handle);
+                    }
+
+                    PendingDeletion::IndexBuffer(id, handle) => {
+                        if state.current_index_buffer.get() == Some(id) {
+                            state.current_index_buffer.set(None);
+                        }
+
+                        state.gl.delete_buffer(id);
+                        state.untrack_resource(handle);
+                    }
+
+                    PendingDeletion::Shader(id) => {
+                        if state.current_program.get() == Some(id) {
+                            state.current_program.set(None);
+                        }
+
+                        state.gl.delete_program(id);
+                    }
+
+                    PendingDeletion::Texture(id, handle) => {
+                        for bound in &state.current_textures {
+                            if bound.get() == Some(id) {
+                                bound.set(None);
+                            }
+                        }
+
+                        state.gl.delete_texture(id);
+                        state.untrack_resource(handle);
+                    }
+
+                    PendingDeletion::Canvas(id) => {
+                        if state.current_read_framebuffer.get() == Some(id) {
+                            state.current_read_framebuffer.set(None);
+                        }
+
+                        if state.current_draw_framebuffer.get() == Some(id) {
+                            state.current_draw_framebuffer.set(None);
+                        }
+
+                        state.gl.delete_framebuffer(id);
+                    }
+
+                    PendingDeletion::Renderbuffer(id, handle) => {
+                        if state.current_renderbuffer.get() == Some(id) {
+                            state.current_renderbuffer.set(None);
+                        }
+
+                        state.gl.delete_renderbuffer(id);
+                        state.untrack_resource(handle);
+                    }
+
+                    PendingDeletion::Query(id) => {
+                        state.gl.delete_query(id);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn clear(&mut self, color: Color) {
         unsafe {
             self.state
@@ -143,6 +358,10 @@ impl GraphicsDevice {
         unsafe { self.state.gl.scissor(x, y, width, height) }
     }
 
+    pub fn is_scissor_test_enabled(&self) -> bool {
+        unsafe { self.state.gl.is_enabled(glow::SCISSOR_TEST) }
+    }
+
     pub fn scissor_test(&mut self, scissor_test: bool) {
         unsafe {
             if scissor_test {
@@ -175,6 +394,24 @@ impl GraphicsDevice {
         }
     }
 
+    pub fn set_depth_test(&mut self, enabled: bool) {
+        unsafe {
+            if enabled {
+                self.state.gl.enable(glow::DEPTH_TEST);
+                self.state.gl.depth_func(glow::LEQUAL);
+            } else {
+                self.state.gl.disable(glow::DEPTH_TEST);
+            }
+        }
+    }
+
+    pub fn clear_depth(&mut self) {
+        unsafe {
+            self.state.gl.clear_depth_f32(1.0);
+            self.state.gl.clear(glow::DEPTH_BUFFER_BIT);
+        }
+    }
+
     pub fn clear_stencil(&mut self, value: u8) {
         unsafe {
             self.state.gl.clear_stencil(value.into());
@@ -201,13 +438,24 @@ impl GraphicsDevice {
                 .map_err(TetraError::PlatformError)?;
 
             let buffer = RawVertexBuffer {
-                state: Rc::clone(&self.state),
+                deletions: Arc::clone(&self.state.deletions),
                 id,
                 count,
+                handle: self.state.track_resource(GpuResource {
+                    kind: GpuResourceKind::VertexBuffer,
+                    size: count * mem::size_of::<Vertex>(),
+                    dimensions: None,
+                }),
             };
 
             self.bind_vertex_buffer(Some(buffer.id));
 
+            self.label_bound_object(
+                glow::BUFFER,
+                glow::ARRAY_BUFFER_BINDING,
+                "Tetra Vertex Buffer",
+            );
+
             self.clear_errors();
 
             self.state
@@ -282,9 +530,29 @@ impl GraphicsDevice {
                 16,
             );
 
+            self.state.gl.vertex_attrib_pointer_f32(
+                3,
+                4,
+                glow::UNSIGNED_BYTE,
+                true,
+                buffer.stride() as i32,
+                32,
+            );
+
+            self.state.gl.vertex_attrib_pointer_f32(
+                4,
+                4,
+                glow::FLOAT,
+                false,
+                buffer.stride() as i32,
+                36,
+            );
+
             self.state.gl.enable_vertex_attrib_array(0);
             self.state.gl.enable_vertex_attrib_array(1);
             self.state.gl.enable_vertex_attrib_array(2);
+            self.state.gl.enable_vertex_attrib_array(3);
+            self.state.gl.enable_vertex_attrib_array(4);
         }
     }
 
@@ -297,13 +565,24 @@ impl GraphicsDevice {
                 .map_err(TetraError::PlatformError)?;
 
             let buffer = RawIndexBuffer {
-                state: Rc::clone(&self.state),
+                deletions: Arc::clone(&self.state.deletions),
                 id,
                 count,
+                handle: self.state.track_resource(GpuResource {
+                    kind: GpuResourceKind::IndexBuffer,
+                    size: count * mem::size_of::<u32>(),
+                    dimensions: None,
+                }),
             };
 
             self.bind_index_buffer(Some(buffer.id));
 
+            self.label_bound_object(
+                glow::BUFFER,
+                glow::ELEMENT_ARRAY_BUFFER_BINDING,
+                "Tetra Index Buffer",
+            );
+
             self.clear_errors();
 
             self.state.gl.buffer_data_size(
@@ -356,59 +635,124 @@ impl GraphicsDevice {
                 .bind_attrib_location(program_id, 0, "a_position");
             self.state.gl.bind_attrib_location(program_id, 1, "a_uv");
             self.state.gl.bind_attrib_location(program_id, 2, "a_color");
-
-            let vertex_id = self
-                .state
+            self.state
                 .gl
-                .create_shader(glow::VERTEX_SHADER)
-                .map_err(TetraError::PlatformError)?;
+                .bind_attrib_location(program_id, 3, "a_overlay");
+            self.state
+                .gl
+                .bind_attrib_location(program_id, 4, "a_params");
+
+            let shader = RawShader {
+                deletions: Arc::clone(&self.state.deletions),
+                id: program_id,
+            };
+
+            self.link_shader(&shader, vertex_shader, fragment_shader)?;
+
+            // Setting the sampler uniform will have bound the program for us.
+            self.label_bound_object(glow::PROGRAM, glow::CURRENT_PROGRAM, "Tetra Shader");
+
+            Ok(shader)
+        }
+    }
+
+    /// Compiles the given source code and links it into an existing program.
+    ///
+    /// This can also be used to replace the code of a program that has already
+    /// been linked - any uniform values that were previously set will be lost.
+    pub fn link_shader(
+        &mut self,
+        shader: &RawShader,
+        vertex_shader: &str,
+        fragment_shader: &str,
+    ) -> Result {
+        unsafe {
+            let vertex_id = self.compile_shader(glow::VERTEX_SHADER, vertex_shader)?;
+
+            let fragment_id = match self.compile_shader(glow::FRAGMENT_SHADER, fragment_shader) {
+                Ok(id) => id,
+                Err(e) => {
+                    self.state.gl.delete_shader(vertex_id);
+                    return Err(e);
+                }
+            };
+
+            self.state.gl.attach_shader(shader.id, vertex_id);
+            self.state.gl.attach_shader(shader.id, fragment_id);
 
-            self.state.gl.shader_source(vertex_id, vertex_shader);
-            self.state.gl.compile_shader(vertex_id);
-            self.state.gl.attach_shader(program_id, vertex_id);
+            self.state.gl.link_program(shader.id);
+
+            // The program keeps hold of the compiled code once it has been linked, so
+            // we can detach the shaders straight away - this means the program can
+            // be linked again later on without having to keep track of them.
+            self.state.gl.detach_shader(shader.id, vertex_id);
+            self.state.gl.detach_shader(shader.id, fragment_id);
+            self.state.gl.delete_shader(vertex_id);
+            self.state.gl.delete_shader(fragment_id);
 
-            if !self.state.gl.get_shader_compile_status(vertex_id) {
+            if !self.state.gl.get_program_link_status(shader.id) {
                 return Err(TetraError::InvalidShader(
-                    self.state.gl.get_shader_info_log(vertex_id),
+                    self.state.gl.get_program_info_log(shader.id),
                 ));
             }
 
-            let fragment_id = self
+            let sampler_location = self.get_uniform_location(shader, "u_texture");
+            self.set_uniform_i32(shader, sampler_location.as_ref(), &[0]);
+
+            Ok(())
+        }
+    }
+
+    fn compile_shader(&mut self, shader_type: u32, source: &str) -> Result<ShaderId> {
+        unsafe {
+            let id = self
                 .state
                 .gl
-                .create_shader(glow::FRAGMENT_SHADER)
+                .create_shader(shader_type)
                 .map_err(TetraError::PlatformError)?;
 
-            self.state.gl.shader_source(fragment_id, fragment_shader);
-            self.state.gl.compile_shader(fragment_id);
-            self.state.gl.attach_shader(program_id, fragment_id);
-
-            if !self.state.gl.get_shader_compile_status(fragment_id) {
-                return Err(TetraError::InvalidShader(
-                    self.state.gl.get_shader_info_log(fragment_id),
-                ));
-            }
+            self.state.gl.shader_source(id, source);
+            self.state.gl.compile_shader(id);
 
-            self.state.gl.link_program(program_id);
+            if !self.state.gl.get_shader_compile_status(id) {
+                let log = self.state.gl.get_shader_info_log(id);
+                self.state.gl.delete_shader(id);
 
-            if !self.state.gl.get_program_link_status(program_id) {
-                return Err(TetraError::InvalidShader(
-                    self.state.gl.get_program_info_log(program_id),
-                ));
+                return Err(TetraError::InvalidShader(log));
             }
 
-            self.state.gl.delete_shader(vertex_id);
-            self.state.gl.delete_shader(fragment_id);
-
-            let shader = RawShader {
-                state: Rc::clone(&self.state),
-                id: program_id,
-            };
+            Ok(id)
+        }
+    }
 
-            let sampler_location = self.get_uniform_location(&shader, "u_texture");
-            self.set_uniform_i32(&shader, sampler_location.as_ref(), &[0]);
+    pub fn get_active_uniforms(&self, shader: &RawShader) -> Vec<UniformInfo> {
+        unsafe {
+            let count = self.state.gl.get_active_uniforms(shader.id);
+
+            (0..count)
+                .filter_map(|i| self.state.gl.get_active_uniform(shader.id, i))
+                .map(|u| UniformInfo {
+                    name: strip_array_suffix(u.name),
+                    data_type: convert_data_type(u.utype),
+                    size: u.size,
+                })
+                .collect()
+        }
+    }
 
-            Ok(shader)
+    pub fn get_active_attributes(&self, shader: &RawShader) -> Vec<AttributeInfo> {
+        unsafe {
+            let count = self.state.gl.get_active_attributes(shader.id);
+
+            (0..count)
+                .filter_map(|i| self.state.gl.get_active_attribute(shader.id, i))
+                .map(|a| AttributeInfo {
+                    location: self.state.gl.get_attrib_location(shader.id, &a.name),
+                    name: strip_array_suffix(a.name),
+                    data_type: convert_data_type(a.atype),
+                    size: a.size,
+                })
+                .collect()
         }
     }
 
@@ -609,8 +953,23 @@ impl GraphicsDevice {
         width: i32,
         height: i32,
         filter_mode: FilterMode,
-        hdr: bool,
+        format: TextureFormat,
     ) -> Result<RawTexture> {
+        let max_size = self.state.capabilities.max_texture_size;
+
+        if width > max_size || height > max_size {
+            return Err(TetraError::UnsupportedFeature(format!(
+                "textures larger than {}x{} pixels",
+                max_size, max_size
+            )));
+        }
+
+        if format == TextureFormat::Rgba16F && !self.state.capabilities.float_textures {
+            return Err(TetraError::UnsupportedFeature(
+                "floating point (HDR) textures".into(),
+            ));
+        }
+
         // TODO: I don't think we need mipmaps?
         unsafe {
             let id = self
@@ -620,15 +979,27 @@ impl GraphicsDevice {
                 .map_err(TetraError::PlatformError)?;
 
             let texture = RawTexture {
-                state: Rc::clone(&self.state),
+                deletions: Arc::clone(&self.state.deletions),
 
                 id,
                 width,
                 height,
+                format,
+                handle: self.state.track_resource(GpuResource {
+                    kind: GpuResourceKind::Texture,
+                    size: width as usize * height as usize * format.bytes_per_pixel(),
+                    dimensions: Some((width, height)),
+                }),
             };
 
             self.bind_default_texture(Some(texture.id));
 
+            self.label_bound_object(
+                glow::TEXTURE,
+                glow::TEXTURE_BINDING_2D,
+                &format!("Tetra Texture ({}x{})", width, height),
+            );
+
             self.state.gl.tex_parameter_i32(
                 glow::TEXTURE_2D,
                 glow::TEXTURE_MIN_FILTER,
@@ -663,7 +1034,11 @@ impl GraphicsDevice {
 
             self.clear_errors();
 
-            let internal_format = if hdr { glow::RGBA16F } else { glow::RGBA };
+            let (internal_format, pixel_format) = match format {
+                TextureFormat::Rgba8 => (glow::RGBA, glow::RGBA),
+                TextureFormat::Rgba16F => (glow::RGBA16F, glow::RGBA),
+                TextureFormat::R8 => (glow::R8, glow::RED),
+            };
 
             self.state.gl.tex_image_2d(
                 glow::TEXTURE_2D,
@@ -672,7 +1047,7 @@ impl GraphicsDevice {
                 width,
                 height,
                 0,
-                glow::RGBA,
+                pixel_format,
                 glow::UNSIGNED_BYTE,
                 None,
             );
@@ -702,7 +1077,13 @@ impl GraphicsDevice {
             "tried to write outside of texture bounds"
         );
 
-        let expected = (width * height * 4) as usize;
+        // HDR textures are still written to via 8-bit RGBA data.
+        let (pixel_format, bytes_per_pixel) = match texture.format {
+            TextureFormat::Rgba8 | TextureFormat::Rgba16F => (glow::RGBA, 4),
+            TextureFormat::R8 => (glow::RED, 1),
+        };
+
+        let expected = (width * height * bytes_per_pixel) as usize;
         let actual = data.len();
 
         if expected > actual {
@@ -712,6 +1093,12 @@ impl GraphicsDevice {
         self.bind_default_texture(Some(texture.id));
 
         unsafe {
+            // Rows of single channel data aren't necessarily aligned to 4 bytes,
+            // which is what OpenGL expects by default.
+            if bytes_per_pixel != 4 {
+                self.state.gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 1);
+            }
+
             self.state.gl.tex_sub_image_2d(
                 glow::TEXTURE_2D,
                 0,
@@ -719,10 +1106,14 @@ impl GraphicsDevice {
                 y,
                 width,
                 height,
-                glow::RGBA,
+                pixel_format,
                 glow::UNSIGNED_BYTE,
                 PixelUnpackData::Slice(data),
-            )
+            );
+
+            if bytes_per_pixel != 4 {
+                self.state.gl.pixel_store_i32(glow::UNPACK_ALIGNMENT, 4);
+            }
         }
 
         Ok(())
@@ -764,10 +1155,212 @@ impl GraphicsDevice {
         }
     }
 
+    /// Sets the anisotropy level of the texture, clamped to the range supported
+    /// by the device. Returns the level that was actually applied.
+    pub fn set_texture_anisotropy(&mut self, texture: &RawTexture, level: u8) -> u8 {
+        let max_anisotropy = self.state.capabilities.max_anisotropy;
+
+        if max_anisotropy <= 1 {
+            return 1;
+        }
+
+        let level = level.clamp(1, max_anisotropy);
+
+        self.bind_default_texture(Some(texture.id));
+
+        unsafe {
+            self.state.gl.tex_parameter_f32(
+                glow::TEXTURE_2D,
+                glow::TEXTURE_MAX_ANISOTROPY_EXT,
+                level as f32,
+            );
+        }
+
+        level
+    }
+
+    /// Sets where each channel of the texture gets its value from when it is sampled.
+    pub fn set_texture_swizzle(&mut self, texture: &RawTexture, swizzle: Swizzle) -> Result {
+        if !self.state.capabilities.texture_swizzle {
+            return Err(TetraError::UnsupportedFeature("texture swizzling".into()));
+        }
+
+        self.bind_default_texture(Some(texture.id));
+
+        let parameters = [
+            (glow::TEXTURE_SWIZZLE_R, swizzle.r),
+            (glow::TEXTURE_SWIZZLE_G, swizzle.g),
+            (glow::TEXTURE_SWIZZLE_B, swizzle.b),
+            (glow::TEXTURE_SWIZZLE_A, swizzle.a),
+        ];
+
+        unsafe {
+            for (parameter, source) in parameters {
+                let value = match source {
+                    SwizzleSource::Red => glow::RED,
+                    SwizzleSource::Green => glow::GREEN,
+                    SwizzleSource::Blue => glow::BLUE,
+                    SwizzleSource::Alpha => glow::ALPHA,
+                    SwizzleSource::Zero => glow::ZERO,
+                    SwizzleSource::One => glow::ONE,
+                };
+
+                self.state
+                    .gl
+                    .tex_parameter_i32(glow::TEXTURE_2D, parameter, value as i32);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Overrides the filter mode of the texture bound to the first texture unit, or
+    /// goes back to using the texture's own filter mode if `None` is passed.
+    ///
+    /// This does nothing if sampler objects are not supported.
+    pub fn set_filter_override(&mut self, filter_mode: Option<FilterMode>) {
+        let samplers = match self.state.filter_samplers {
+            Some(samplers) => samplers,
+            None => return,
+        };
+
+        if self.state.current_filter_override.get() != filter_mode {
+            let sampler = filter_mode.map(|f| match f {
+                FilterMode::Nearest => samplers[0],
+                FilterMode::Linear => samplers[1],
+            });
+
+            unsafe {
+                self.state.gl.bind_sampler(0, sampler);
+            }
+
+            self.state.current_filter_override.set(filter_mode);
+        }
+    }
+
     pub fn attach_texture_to_sampler(&mut self, texture: &RawTexture, unit: u32) -> Result {
         self.bind_texture(Some(texture.id), unit)
     }
 
+    pub fn new_cubemap(
+        &mut self,
+        size: i32,
+        faces: [&[u8]; 6],
+        filter_mode: FilterMode,
+    ) -> Result<RawTexture> {
+        let max_size = self.state.capabilities.max_texture_size;
+
+        if size > max_size {
+            return Err(TetraError::UnsupportedFeature(format!(
+                "cubemaps larger than {}x{} pixels",
+                max_size, max_size
+            )));
+        }
+
+        let expected = (size * size * 4) as usize;
+
+        for face in &faces {
+            if face.len() < expected {
+                return Err(TetraError::NotEnoughData {
+                    expected,
+                    actual: face.len(),
+                });
+            }
+        }
+
+        unsafe {
+            let id = self
+                .state
+                .gl
+                .create_texture()
+                .map_err(TetraError::PlatformError)?;
+
+            let cubemap = RawTexture {
+                deletions: Arc::clone(&self.state.deletions),
+
+                id,
+                width: size,
+                height: size,
+                format: TextureFormat::Rgba8,
+                handle: self.state.track_resource(GpuResource {
+                    kind: GpuResourceKind::Texture,
+                    size: expected * 6,
+                    dimensions: Some((size, size)),
+                }),
+            };
+
+            // Cubemaps are bound to a different target, so binding one doesn't disturb
+            // the 2D texture bindings that we keep track of.
+            self.state.gl.active_texture(glow::TEXTURE0);
+            self.state
+                .gl
+                .bind_texture(glow::TEXTURE_CUBE_MAP, Some(cubemap.id));
+
+            self.label_bound_object(
+                glow::TEXTURE,
+                glow::TEXTURE_BINDING_CUBE_MAP,
+                &format!("Tetra Cubemap ({}x{})", size, size),
+            );
+
+            for (parameter, value) in [
+                (glow::TEXTURE_MIN_FILTER, filter_mode.into()),
+                (glow::TEXTURE_MAG_FILTER, filter_mode.into()),
+                (glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32),
+                (glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32),
+                (glow::TEXTURE_WRAP_R, glow::CLAMP_TO_EDGE as i32),
+                (glow::TEXTURE_BASE_LEVEL, 0),
+                (glow::TEXTURE_MAX_LEVEL, 0),
+            ] {
+                self.state
+                    .gl
+                    .tex_parameter_i32(glow::TEXTURE_CUBE_MAP, parameter, value);
+            }
+
+            self.clear_errors();
+
+            for (i, face) in faces.iter().enumerate() {
+                self.state.gl.tex_image_2d(
+                    glow::TEXTURE_CUBE_MAP_POSITIVE_X + i as u32,
+                    0,
+                    glow::RGBA as i32,
+                    size,
+                    size,
+                    0,
+                    glow::RGBA,
+                    glow::UNSIGNED_BYTE,
+                    Some(&face[..expected]),
+                );
+            }
+
+            self.state.gl.bind_texture(glow::TEXTURE_CUBE_MAP, None);
+
+            if let Some(e) = self.get_error() {
+                return Err(TetraError::PlatformError(format_gl_error(
+                    "failed to create cubemap",
+                    e,
+                )));
+            }
+
+            Ok(cubemap)
+        }
+    }
+
+    pub fn attach_cubemap_to_sampler(&mut self, cubemap: &RawTexture, unit: u32) -> Result {
+        if unit as usize >= self.state.current_textures.len() {
+            return Err(TetraError::PlatformError("invalid texture unit".into()));
+        }
+
+        unsafe {
+            self.state.gl.active_texture(glow::TEXTURE0 + unit);
+            self.state
+                .gl
+                .bind_texture(glow::TEXTURE_CUBE_MAP, Some(cubemap.id));
+            self.state.gl.active_texture(glow::TEXTURE0);
+        }
+
+        Ok(())
+    }
+
     pub fn new_canvas(
         &mut self,
         width: i32,
@@ -777,6 +1370,10 @@ impl GraphicsDevice {
         with_stencil_buffer: bool,
         hdr: bool,
     ) -> Result<RawCanvasWithAttachments> {
+        if samples > 0 && self.state.capabilities.max_samples == 0 {
+            return Err(TetraError::UnsupportedFeature("multisampling".into()));
+        }
+
         unsafe {
             let previous_read = self.state.current_read_framebuffer.get();
             let previous_draw = self.state.current_draw_framebuffer.get();
@@ -788,13 +1385,35 @@ impl GraphicsDevice {
                 .map_err(TetraError::PlatformError)?;
 
             let canvas = RawCanvas {
-                state: Rc::clone(&self.state),
+                deletions: Arc::clone(&self.state.deletions),
                 id,
             };
 
             self.bind_framebuffer(Some(canvas.id));
 
-            let color = self.new_texture(width, height, filter_mode, hdr)?;
+            self.label_bound_object(
+                glow::FRAMEBUFFER,
+                glow::DRAW_FRAMEBUFFER_BINDING,
+                &format!("Tetra Canvas ({}x{})", width, height),
+            );
+
+            let format = if hdr {
+                TextureFormat::Rgba16F
+            } else {
+                TextureFormat::Rgba8
+            };
+
+            let color = self.new_texture(width, height, filter_mode, format)?;
+
+            if let Some(resource) = self
+                .state
+                .resources
+                .borrow_mut()
+                .live
+                .get_mut(&color.handle)
+            {
+                resource.kind = GpuResourceKind::Canvas;
+            }
 
             self.state.gl.framebuffer_texture_2d(
                 glow::FRAMEBUFFER,
@@ -806,7 +1425,7 @@ impl GraphicsDevice {
 
             self.clear(Color::rgba(0.0, 0.0, 0.0, 0.0));
 
-            let actual_samples = u8::min(samples, self.state.max_samples);
+            let actual_samples = u8::min(samples, self.state.capabilities.max_samples);
 
             let multisample_color = if actual_samples > 0 {
                 let renderbuffer = self.new_color_renderbuffer(width, height, actual_samples)?;
@@ -846,6 +1465,15 @@ impl GraphicsDevice {
 
             let status = self.state.gl.check_framebuffer_status(glow::FRAMEBUFFER);
 
+            // The driver is allowed to give us more samples than we asked for, so
+            // we need to ask how many we actually got. This is only valid for a
+            // complete framebuffer.
+            let samples = if status == glow::FRAMEBUFFER_COMPLETE && multisample_color.is_some() {
+                self.state.gl.get_parameter_i32(glow::SAMPLES).clamp(0, 255) as u8
+            } else {
+                0
+            };
+
             // Revert the bindings before checking the status, so we don't end up
             // in a weird state if there's an error:
             self.bind_read_framebuffer(previous_read);
@@ -863,10 +1491,135 @@ impl GraphicsDevice {
                 color,
                 multisample_color,
                 depth_stencil,
+                samples,
             })
         }
     }
 
+    /// Creates a multisampled framebuffer that can be rendered to in place of the
+    /// window's default framebuffer, for when multisampling is enabled at runtime.
+    pub fn new_backbuffer(
+        &mut self,
+        width: i32,
+        height: i32,
+        samples: u8,
+        with_stencil_buffer: bool,
+    ) -> Result<RawBackbuffer> {
+        if self.state.capabilities.max_samples == 0 {
+            return Err(TetraError::UnsupportedFeature("multisampling".into()));
+        }
+
+        let samples = u8::min(samples, self.state.capabilities.max_samples);
+
+        unsafe {
+            let previous_read = self.state.current_read_framebuffer.get();
+            let previous_draw = self.state.current_draw_framebuffer.get();
+
+            let id = self
+                .state
+                .gl
+                .create_framebuffer()
+                .map_err(TetraError::PlatformError)?;
+
+            let canvas = RawCanvas {
+                deletions: Arc::clone(&self.state.deletions),
+                id,
+            };
+
+            self.bind_framebuffer(Some(canvas.id));
+
+            self.label_bound_object(
+                glow::FRAMEBUFFER,
+                glow::DRAW_FRAMEBUFFER_BINDING,
+                &format!("Tetra Backbuffer ({}x{}, {}x MSAA)", width, height, samples),
+            );
+
+            let color = self.new_color_renderbuffer(width, height, samples)?;
+
+            self.state.gl.framebuffer_renderbuffer(
+                glow::FRAMEBUFFER,
+                glow::COLOR_ATTACHMENT0,
+                glow::RENDERBUFFER,
+                Some(color.id),
+            );
+
+            let depth_stencil = if with_stencil_buffer {
+                let renderbuffer = self.new_depth_stencil_renderbuffer(width, height, samples)?;
+
+                self.state.gl.framebuffer_renderbuffer(
+                    glow::FRAMEBUFFER,
+                    glow::DEPTH_STENCIL_ATTACHMENT,
+                    glow::RENDERBUFFER,
+                    Some(renderbuffer.id),
+                );
+
+                Some(renderbuffer)
+            } else {
+                None
+            };
+
+            let status = self.state.gl.check_framebuffer_status(glow::FRAMEBUFFER);
+
+            self.bind_read_framebuffer(previous_read);
+            self.bind_draw_framebuffer(previous_draw);
+
+            if status != glow::FRAMEBUFFER_COMPLETE {
+                return Err(TetraError::PlatformError(format_gl_framebuffer_status(
+                    "failed to create backbuffer",
+                    status,
+                )));
+            }
+
+            Ok(RawBackbuffer {
+                canvas,
+                width,
+                height,
+                samples,
+                _color: color,
+                _depth_stencil: depth_stencil,
+            })
+        }
+    }
+
+    /// Resolves a backbuffer into the window's default framebuffer.
+    pub fn resolve_backbuffer(&mut self, backbuffer: &RawBackbuffer) {
+        unsafe {
+            let previous_read = self.state.current_read_framebuffer.get();
+            let previous_draw = self.state.current_draw_framebuffer.get();
+
+            // Blits are affected by the scissor test, so it needs to be
+            // temporarily disabled.
+            let scissor_test = self.state.gl.is_enabled(glow::SCISSOR_TEST);
+
+            if scissor_test {
+                self.state.gl.disable(glow::SCISSOR_TEST);
+            }
+
+            self.bind_read_framebuffer(Some(backbuffer.canvas.id));
+            self.bind_draw_framebuffer(None);
+
+            self.state.gl.blit_framebuffer(
+                0,
+                0,
+                backbuffer.width,
+                backbuffer.height,
+                0,
+                0,
+                backbuffer.width,
+                backbuffer.height,
+                glow::COLOR_BUFFER_BIT,
+                glow::NEAREST,
+            );
+
+            if scissor_test {
+                self.state.gl.enable(glow::SCISSOR_TEST);
+            }
+
+            self.bind_read_framebuffer(previous_read);
+            self.bind_draw_framebuffer(previous_draw);
+        }
+    }
+
     pub fn set_canvas(&mut self, canvas: Option<&RawCanvas>) {
         self.bind_framebuffer(canvas.map(|f| f.id));
     }
@@ -937,13 +1690,25 @@ impl GraphicsDevice {
                 .create_renderbuffer()
                 .map_err(TetraError::PlatformError)?;
 
+            // Both of the formats we use for renderbuffers are 32 bits per pixel.
             let renderbuffer = RawRenderbuffer {
-                state: Rc::clone(&self.state),
+                deletions: Arc::clone(&self.state.deletions),
                 id,
+                handle: self.state.track_resource(GpuResource {
+                    kind: GpuResourceKind::Renderbuffer,
+                    size: width as usize * height as usize * 4 * usize::from(samples.max(1)),
+                    dimensions: Some((width, height)),
+                }),
             };
 
             self.bind_renderbuffer(Some(renderbuffer.id));
 
+            self.label_bound_object(
+                glow::RENDERBUFFER,
+                glow::RENDERBUFFER_BINDING,
+                &format!("Tetra Renderbuffer ({}x{})", width, height),
+            );
+
             if samples > 0 {
                 self.state.gl.renderbuffer_storage_multisample(
                     glow::RENDERBUFFER,
@@ -962,6 +1727,56 @@ impl GraphicsDevice {
         }
     }
 
+    pub fn new_query(&mut self) -> Result<RawQuery> {
+        unsafe {
+            let id = self
+                .state
+                .gl
+                .create_query()
+                .map_err(TetraError::PlatformError)?;
+
+            Ok(RawQuery {
+                deletions: Arc::clone(&self.state.deletions),
+                id,
+            })
+        }
+    }
+
+    pub fn begin_query(&mut self, query: &RawQuery) {
+        unsafe {
+            self.state.gl.begin_query(glow::SAMPLES_PASSED, query.id);
+        }
+    }
+
+    pub fn end_query(&mut self) {
+        unsafe {
+            self.state.gl.end_query(glow::SAMPLES_PASSED);
+        }
+    }
+
+    /// Returns the number of samples that passed the depth and stencil tests while
+    /// the query was active. If `wait` is `false` and the result is not available yet,
+    /// `None` will be returned.
+    pub fn get_query_result(&mut self, query: &RawQuery, wait: bool) -> Option<u32> {
+        unsafe {
+            if !wait
+                && self
+                    .state
+                    .gl
+                    .get_query_parameter_u32(query.id, glow::QUERY_RESULT_AVAILABLE)
+                    == 0
+            {
+                return None;
+            }
+
+            Some(
+                self.state
+                    .gl
+                    .get_query_parameter_u32(query.id, glow::QUERY_RESULT),
+            )
+        }
+    }
+
     pub fn viewport(&mut self, x: i32, y: i32, width: i32, height: i32) {
         unsafe {
             self.state.gl.viewport(x, y, width, height);
@@ -985,6 +1800,7 @@ impl GraphicsDevice {
             offset,
             count,
             1,
+            PrimitiveTopology::Triangles,
         );
     }
 
@@ -998,7 +1814,10 @@ impl GraphicsDevice {
         offset: usize,
         count: usize,
         instances: usize,
+        topology: PrimitiveTopology,
     ) {
+        let mode = topology.into();
+
         self.bind_vertex_buffer(Some(vertex_buffer.id));
         self.bind_default_texture(Some(texture.id));
         self.bind_program(Some(shader.id));
@@ -1016,7 +1835,7 @@ impl GraphicsDevice {
                 if instances > 1 {
                     unsafe {
                         self.state.gl.draw_elements_instanced(
-                            glow::TRIANGLES,
+                            mode,
                             count as i32,
                             glow::UNSIGNED_INT,
                             (index_buffer.stride() * offset) as i32,
@@ -1026,7 +1845,7 @@ impl GraphicsDevice {
                 } else {
                     unsafe {
                         self.state.gl.draw_elements(
-                            glow::TRIANGLES,
+                            mode,
                             count as i32,
                             glow::UNSIGNED_INT,
                             (index_buffer.stride() * offset) as i32,
@@ -1044,7 +1863,7 @@ impl GraphicsDevice {
                 if instances > 1 {
                     unsafe {
                         self.state.gl.draw_arrays_instanced(
-                            glow::TRIANGLES,
+                            mode,
                             offset as i32,
                             count as i32,
                             instances as i32,
@@ -1052,9 +1871,7 @@ impl GraphicsDevice {
                     }
                 } else {
                     unsafe {
-                        self.state
-                            .gl
-                            .draw_arrays(glow::TRIANGLES, offset as i32, count as i32);
+                        self.state.gl.draw_arrays(mode, offset as i32, count as i32);
                     }
                 }
             }
@@ -1079,6 +1896,20 @@ impl GraphicsDevice {
         }
     }
 
+    /// Attaches a debug label to the object that is currently bound to `binding`.
+    ///
+    /// Glow doesn't expose the raw names of the objects it creates, so we have to
+    /// ask the driver for them instead - this is only done when debug output is
+    /// enabled, so it won't affect performance otherwise.
+    fn label_bound_object(&self, identifier: u32, binding: u32, label: &str) {
+        if self.state.debug {
+            unsafe {
+                let name = self.state.gl.get_parameter_i32(binding) as u32;
+                self.state.gl.object_label(identifier, name, Some(label));
+            }
+        }
+    }
+
     fn bind_program(&mut self, id: Option<ProgramId>) {
         unsafe {
             if self.state.current_program.get() != id {
@@ -1176,6 +2007,12 @@ impl Drop for GraphicsDevice {
                 .delete_framebuffer(self.state.resolve_framebuffer);
 
             self.state.gl.delete_vertex_array(self.state.vertex_array);
+
+            if let Some(samplers) = self.state.filter_samplers {
+                for sampler in samplers {
+                    self.state.gl.delete_sampler(sampler);
+                }
+            }
         }
     }
 }
@@ -1201,6 +2038,20 @@ impl From<VertexWinding> for u32 {
     }
 }
 
+#[doc(hidden)]
+impl From<PrimitiveTopology> for u32 {
+    fn from(topology: PrimitiveTopology) -> u32 {
+        match topology {
+            PrimitiveTopology::Triangles => glow::TRIANGLES,
+            PrimitiveTopology::TriangleStrip => glow::TRIANGLE_STRIP,
+            PrimitiveTopology::TriangleFan => glow::TRIANGLE_FAN,
+            PrimitiveTopology::Lines => glow::LINES,
+            PrimitiveTopology::LineStrip => glow::LINE_STRIP,
+            PrimitiveTopology::Points => glow::POINTS,
+        }
+    }
+}
+
 #[doc(hidden)]
 impl From<FilterMode> for i32 {
     fn from(filter_mode: FilterMode) -> i32 {
@@ -1277,10 +2128,11 @@ impl StencilAction {
 
 #[derive(Debug)]
 pub struct RawVertexBuffer {
-    state: Rc<GraphicsState>,
+    deletions: Arc<DeletionQueue>,
     id: BufferId,
 
     count: usize,
+    handle: u64,
 }
 
 impl RawVertexBuffer {
@@ -1308,22 +2160,18 @@ impl PartialEq for RawVertexBuffer {
 
 impl Drop for RawVertexBuffer {
     fn drop(&mut self) {
-        unsafe {
-            if self.state.current_vertex_buffer.get() == Some(self.id) {
-                self.state.current_vertex_buffer.set(None);
-            }
-
-            self.state.gl.delete_buffer(self.id);
-        }
+        self.deletions
+            .push(PendingDeletion::VertexBuffer(self.id, self.handle));
     }
 }
 
 #[derive(Debug)]
 pub struct RawIndexBuffer {
-    state: Rc<GraphicsState>,
+    deletions: Arc<DeletionQueue>,
     id: BufferId,
 
     count: usize,
+    handle: u64,
 }
 
 impl RawIndexBuffer {
@@ -1351,19 +2199,14 @@ impl PartialEq for RawIndexBuffer {
 
 impl Drop for RawIndexBuffer {
     fn drop(&mut self) {
-        unsafe {
-            if self.state.current_index_buffer.get() == Some(self.id) {
-                self.state.current_index_buffer.set(None);
-            }
-
-            self.state.gl.delete_buffer(self.id);
-        }
+        self.deletions
+            .push(PendingDeletion::IndexBuffer(self.id, self.handle));
     }
 }
 
 #[derive(Debug)]
 pub struct RawShader {
-    state: Rc<GraphicsState>,
+    deletions: Arc<DeletionQueue>,
     id: ProgramId,
 }
 
@@ -1375,23 +2218,19 @@ impl PartialEq for RawShader {
 
 impl Drop for RawShader {
     fn drop(&mut self) {
-        unsafe {
-            if self.state.current_program.get() == Some(self.id) {
-                self.state.current_program.set(None);
-            }
-
-            self.state.gl.delete_program(self.id);
-        }
+        self.deletions.push(PendingDeletion::Shader(self.id));
     }
 }
 
 #[derive(Debug)]
 pub struct RawTexture {
-    state: Rc<GraphicsState>,
+    deletions: Arc<DeletionQueue>,
     id: TextureId,
 
     width: i32,
     height: i32,
+    format: TextureFormat,
+    handle: u64,
 }
 
 impl RawTexture {
@@ -1402,6 +2241,34 @@ impl RawTexture {
     pub fn height(&self) -> i32 {
         self.height
     }
+
+    pub fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    /// Returns the amount of GPU memory used by the texture's data, in bytes.
+    pub fn size_in_bytes(&self) -> usize {
+        self.width as usize * self.height as usize * self.format.bytes_per_pixel()
+    }
+}
+
+/// The format that a texture's data is stored in on the GPU.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    Rgba8,
+    Rgba16F,
+    R8,
+}
+
+impl TextureFormat {
+    /// Returns the number of bytes that each pixel takes up on the GPU.
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            TextureFormat::Rgba8 => 4,
+            TextureFormat::Rgba16F => 8,
+            TextureFormat::R8 => 1,
+        }
+    }
 }
 
 impl PartialEq for RawTexture {
@@ -1412,21 +2279,14 @@ impl PartialEq for RawTexture {
 
 impl Drop for RawTexture {
     fn drop(&mut self) {
-        unsafe {
-            for bound in &self.state.current_textures {
-                if bound.get() == Some(self.id) {
-                    bound.set(None);
-                }
-            }
-
-            self.state.gl.delete_texture(self.id);
-        }
+        self.deletions
+            .push(PendingDeletion::Texture(self.id, self.handle));
     }
 }
 
 #[derive(Debug)]
 pub struct RawCanvas {
-    state: Rc<GraphicsState>,
+    deletions: Arc<DeletionQueue>,
     id: FramebufferId,
 }
 
@@ -1438,17 +2298,25 @@ impl PartialEq for RawCanvas {
 
 impl Drop for RawCanvas {
     fn drop(&mut self) {
-        unsafe {
-            if self.state.current_read_framebuffer.get() == Some(self.id) {
-                self.state.current_read_framebuffer.set(None);
-            }
+        self.deletions.push(PendingDeletion::Canvas(self.id));
+    }
+}
 
-            if self.state.current_draw_framebuffer.get() == Some(self.id) {
-                self.state.current_draw_framebuffer.set(None);
-            }
+#[derive(Debug)]
+pub struct RawQuery {
+    deletions: Arc<DeletionQueue>,
+    id: QueryId,
+}
 
-            self.state.gl.delete_framebuffer(self.id);
-        }
+impl PartialEq for RawQuery {
+    fn eq(&self, other: &RawQuery) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Drop for RawQuery {
+    fn drop(&mut self) {
+        self.deletions.push(PendingDeletion::Query(self.id));
     }
 }
 
@@ -1457,12 +2325,25 @@ pub struct RawCanvasWithAttachments {
     pub color: RawTexture,
     pub multisample_color: Option<RawRenderbuffer>,
     pub depth_stencil: Option<RawRenderbuffer>,
+    pub samples: u8,
+}
+
+pub struct RawBackbuffer {
+    pub canvas: RawCanvas,
+    pub width: i32,
+    pub height: i32,
+    pub samples: u8,
+
+    // These just need to be kept alive for as long as the framebuffer is.
+    _color: RawRenderbuffer,
+    _depth_stencil: Option<RawRenderbuffer>,
 }
 
 #[derive(Debug)]
 pub struct RawRenderbuffer {
-    state: Rc<GraphicsState>,
+    deletions: Arc<DeletionQueue>,
     id: RenderbufferId,
+    handle: u64,
 }
 
 impl PartialEq for RawRenderbuffer {
@@ -1473,16 +2354,122 @@ impl PartialEq for RawRenderbuffer {
 
 impl Drop for RawRenderbuffer {
     fn drop(&mut self) {
-        unsafe {
-            if self.state.current_renderbuffer.get() == Some(self.id) {
-                self.state.current_renderbuffer.set(None);
-            }
+        self.deletions
+            .push(PendingDeletion::Renderbuffer(self.id, self.handle));
+    }
+}
+
+fn enable_debug_output(gl: &GlowContext) -> bool {
+    if !gl.supports_debug() {
+        log::warn!(
+            target: "tetra::graphics",
+            "Graphics debugging was requested, but the graphics device does not support KHR_debug"
+        );
+
+        return false;
+    }
+
+    unsafe {
+        gl.enable(glow::DEBUG_OUTPUT);
+
+        // This makes the messages get reported from the GL call that caused them, rather
+        // than at some arbitrary point later on - much more useful when debugging.
+        gl.enable(glow::DEBUG_OUTPUT_SYNCHRONOUS);
+
+        // Glow stores a pointer to the callback it's given, rather than boxing it, so
+        // we pass a plain function (which is zero-sized) to avoid it dangling.
+        gl.debug_message_callback(log_debug_message);
+    }
+
+    true
+}
 
-            self.state.gl.delete_renderbuffer(self.id);
+fn log_debug_message(source: u32, message_type: u32, id: u32, severity: u32, message: &str) {
+    let level = match severity {
+        glow::DEBUG_SEVERITY_HIGH => log::Level::Error,
+        glow::DEBUG_SEVERITY_MEDIUM => log::Level::Warn,
+        glow::DEBUG_SEVERITY_LOW => log::Level::Info,
+        _ => log::Level::Debug,
+    };
+
+    let source = match source {
+        glow::DEBUG_SOURCE_API => "API",
+        glow::DEBUG_SOURCE_WINDOW_SYSTEM => "Window System",
+        glow::DEBUG_SOURCE_SHADER_COMPILER => "Shader Compiler",
+        glow::DEBUG_SOURCE_THIRD_PARTY => "Third Party",
+        glow::DEBUG_SOURCE_APPLICATION => "Application",
+        _ => "Other",
+    };
+
+    let message_type = match message_type {
+        glow::DEBUG_TYPE_ERROR => "Error",
+        glow::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "Deprecated Behavior",
+        glow::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "Undefined Behavior",
+        glow::DEBUG_TYPE_PORTABILITY => "Portability",
+        glow::DEBUG_TYPE_PERFORMANCE => "Performance",
+        glow::DEBUG_TYPE_MARKER => "Marker",
+        _ => "Other",
+    };
+
+    log::log!(
+        target: "tetra::graphics",
+        level,
+        "OpenGL {} {} ({}): {}",
+        source,
+        message_type,
+        id,
+        message
+    );
+}
+
+fn query_capabilities(gl: &GlowContext) -> Capabilities {
+    let version = gl.version();
+    let extensions = gl.supported_extensions();
+
+    // Everything we check for here is core in OpenGL 3.0/3.1, which is lower than the
+    // version we request - however, some drivers (e.g. software renderers) may hand us
+    // a context that is missing features, so we fall back to checking extensions too.
+    let at_least = |major, minor| (version.major, version.minor) >= (major, minor);
+    let has_extension = |name: &str| extensions.contains(name);
+
+    unsafe {
+        Capabilities {
+            max_texture_size: gl.get_parameter_i32(glow::MAX_TEXTURE_SIZE),
+            max_samples: gl
+                .get_parameter_i32(glow::MAX_SAMPLES)
+                .clamp(0, u8::MAX as i32) as u8,
+            float_textures: at_least(3, 0) || has_extension("GL_ARB_texture_float"),
+            instancing: at_least(3, 1) || has_extension("GL_ARB_draw_instanced"),
+            srgb: at_least(3, 0) || has_extension("GL_EXT_texture_sRGB"),
+            sampler_objects: at_least(3, 3) || has_extension("GL_ARB_sampler_objects"),
+            texture_swizzle: at_least(3, 3)
+                || has_extension("GL_ARB_texture_swizzle")
+                || has_extension("GL_EXT_texture_swizzle"),
+            max_anisotropy: if at_least(4, 6)
+                || has_extension("GL_ARB_texture_filter_anisotropic")
+                || has_extension("GL_EXT_texture_filter_anisotropic")
+            {
+                gl.get_parameter_f32(glow::MAX_TEXTURE_MAX_ANISOTROPY_EXT)
+                    .clamp(1.0, u8::MAX as f32) as u8
+            } else {
+                1
+            },
         }
     }
 }
 
+unsafe fn create_filter_sampler(gl: &GlowContext, filter_mode: FilterMode) -> Result<SamplerId> {
+    let sampler = gl.create_sampler().map_err(TetraError::PlatformError)?;
+
+    // These need to match the parameters that are set when creating a texture.
+    gl.sampler_parameter_i32(sampler, glow::TEXTURE_MIN_FILTER, filter_mode.into());
+    gl.sampler_parameter_i32(sampler, glow::TEXTURE_MAG_FILTER, filter_mode.into());
+    gl.sampler_parameter_i32(sampler, glow::TEXTURE_WRAP_S, glow::CLAMP_TO_EDGE as i32);
+    gl.sampler_parameter_i32(sampler, glow::TEXTURE_WRAP_T, glow::CLAMP_TO_EDGE as i32);
+
+    Ok(sampler)
+}
+
 /// This function exists because Vek isn't currently compatible with Bytemuck, so
 /// there's not an easy way of converting slices of that library's types.
 ///
@@ -1529,3 +2516,40 @@ fn format_gl_framebuffer_status(prefix: &str, value: u32) -> String {
         _ => format!("{} (OpenGL error: {:#4X})", prefix, value),
     }
 }
+
+// Arrays are reported with a `[0]` suffix on their name - this is stripped so that the
+// names match what would be passed to `set_uniform`.
+fn strip_array_suffix(mut name: String) -> String {
+    if name.ends_with("[0]") {
+        name.truncate(name.len() - 3);
+    }
+
+    name
+}
+
+fn convert_data_type(value: u32) -> ShaderDataType {
+    match value {
+        glow::FLOAT => ShaderDataType::Float,
+        glow::FLOAT_VEC2 => ShaderDataType::Vec2,
+        glow::FLOAT_VEC3 => ShaderDataType::Vec3,
+        glow::FLOAT_VEC4 => ShaderDataType::Vec4,
+        glow::INT => ShaderDataType::Int,
+        glow::INT_VEC2 => ShaderDataType::IVec2,
+        glow::INT_VEC3 => ShaderDataType::IVec3,
+        glow::INT_VEC4 => ShaderDataType::IVec4,
+        glow::UNSIGNED_INT => ShaderDataType::UInt,
+        glow::UNSIGNED_INT_VEC2 => ShaderDataType::UVec2,
+        glow::UNSIGNED_INT_VEC3 => ShaderDataType::UVec3,
+        glow::UNSIGNED_INT_VEC4 => ShaderDataType::UVec4,
+        glow::BOOL => ShaderDataType::Bool,
+        glow::BOOL_VEC2 => ShaderDataType::BVec2,
+        glow::BOOL_VEC3 => ShaderDataType::BVec3,
+        glow::BOOL_VEC4 => ShaderDataType::BVec4,
+        glow::FLOAT_MAT2 => ShaderDataType::Mat2,
+        glow::FLOAT_MAT3 => ShaderDataType::Mat3,
+        glow::FLOAT_MAT4 => ShaderDataType::Mat4,
+        glow::SAMPLER_2D => ShaderDataType::Sampler2D,
+        glow::SAMPLER_CUBE => ShaderDataType::SamplerCube,
+        _ => ShaderDataType::Other,
+    }
+}
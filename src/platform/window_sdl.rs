@@ -1,16 +1,19 @@
 // TODO: This file is getting way too huge.
+use std::os::raw::{c_char, c_int, c_void};
 use std::path::PathBuf;
 use std::result;
 
 use glow::Context as GlowContext;
 use hashbrown::HashMap;
+use once_cell::sync::OnceCell;
 use sdl2::controller::{Axis as SdlGamepadAxis, Button as SdlGamepadButton, GameController};
 use sdl2::event::{Event as SdlEvent, WindowEvent};
 use sdl2::keyboard::{Keycode, Mod, Scancode};
 use sdl2::mouse::{MouseButton as SdlMouseButton, MouseWheelDirection};
 use sdl2::pixels::PixelMasks;
+use sdl2::rect::Rect;
 use sdl2::surface::Surface;
-use sdl2::sys::SDL_WINDOWPOS_CENTERED_MASK;
+use sdl2::sys::{SDL_GameController, SDL_WINDOWPOS_CENTERED_MASK};
 use sdl2::video::{
     FullscreenType, GLContext as SdlGlContext, GLProfile, SwapInterval, Window as SdlWindow,
     WindowPos,
@@ -18,10 +21,12 @@ use sdl2::video::{
 use sdl2::{EventPump, GameControllerSubsystem, JoystickSubsystem, Sdl, VideoSubsystem};
 
 use crate::error::{Result, TetraError};
-use crate::graphics::{self, ImageData};
+use crate::graphics::{self, ImageData, Rectangle};
 use crate::input::{
-    self, GamepadAxis, GamepadButton, GamepadStick, Key, KeyLabel, KeyModifierState, MouseButton,
+    self, GamepadAxis, GamepadButton, GamepadHaptics, GamepadStick, GamepadTrigger, GamepadType,
+    Key, KeyLabel, KeyModifierState, MouseButton, TriggerEffect,
 };
+use crate::lifecycle::dispatch;
 use crate::math::Vec2;
 use crate::window::WindowPosition;
 use crate::{Context, ContextBuilder, Event, State};
@@ -30,6 +35,43 @@ struct SdlController {
     controller: GameController,
     slot: usize,
     supports_rumble: bool,
+    supports_trigger_rumble: bool,
+}
+
+type SendEffectFn = unsafe extern "C" fn(*mut SDL_GameController, *const c_void, c_int) -> c_int;
+
+/// Looks up `SDL_GameControllerSendEffect`, which was added in SDL 2.0.16.
+///
+/// The sdl2 crate binds to an older version of SDL, so this has to be done at runtime
+/// to avoid failing to link against older versions of the library.
+fn send_effect_fn() -> Option<SendEffectFn> {
+    static SEND_EFFECT: OnceCell<Option<SendEffectFn>> = OnceCell::new();
+
+    *SEND_EFFECT.get_or_init(|| unsafe {
+        // Passing null gets a handle to the current process, but that doesn't work on Windows.
+        let library: *const c_char = if cfg!(windows) {
+            b"SDL2.dll\0".as_ptr() as *const c_char
+        } else {
+            std::ptr::null()
+        };
+
+        let handle = sdl2::sys::SDL_LoadObject(library);
+
+        if handle.is_null() {
+            return None;
+        }
+
+        let function = sdl2::sys::SDL_LoadFunction(
+            handle,
+            b"SDL_GameControllerSendEffect\0".as_ptr() as *const c_char,
+        );
+
+        if function.is_null() {
+            None
+        } else {
+            Some(std::mem::transmute::<*mut c_void, SendEffectFn>(function))
+        }
+    })
 }
 
 pub struct Window {
@@ -45,6 +87,8 @@ pub struct Window {
     controllers: HashMap<u32, SdlController>,
 
     window_visible: bool,
+    window_fullscreen: bool,
+    dpi_scale: f32,
 
     key_repeat: bool,
 }
@@ -78,6 +122,10 @@ impl Window {
             gl_attr.set_stencil_size(8);
         }
 
+        if settings.graphics_debug {
+            gl_attr.set_context_flags().debug().set();
+        }
+
         if settings.screen_saver_enabled {
             video_sys.enable_screen_saver();
         } else {
@@ -163,6 +211,8 @@ impl Window {
             SwapInterval::Immediate
         });
 
+        let dpi_scale = sdl_window.drawable_size().0 as f32 / sdl_window.size().0 as f32;
+
         let window = Window {
             sdl,
             sdl_window,
@@ -176,6 +226,8 @@ impl Window {
             controllers: HashMap::new(),
 
             window_visible: false,
+            window_fullscreen: settings.fullscreen,
+            dpi_scale,
 
             key_repeat: settings.key_repeat,
         };
@@ -287,6 +339,37 @@ impl Window {
         self.window_visible
     }
 
+    pub fn get_opacity(&self) -> f32 {
+        self.sdl_window.opacity().unwrap_or(1.0)
+    }
+
+    pub fn set_opacity(&mut self, opacity: f32) -> Result {
+        self.sdl_window
+            .set_opacity(opacity.clamp(0.0, 1.0))
+            .map_err(TetraError::PlatformError)
+    }
+
+    pub fn get_brightness(&self) -> f32 {
+        self.sdl_window.brightness() as f32
+    }
+
+    pub fn set_brightness(&mut self, brightness: f32) -> Result {
+        self.sdl_window
+            .set_brightness(brightness.max(0.0) as f64)
+            .map_err(TetraError::PlatformError)
+    }
+
+    pub fn set_gamma_ramp(
+        &mut self,
+        red: &[u16; 256],
+        green: &[u16; 256],
+        blue: &[u16; 256],
+    ) -> Result {
+        self.sdl_window
+            .set_gamma_ramp(red, green, blue)
+            .map_err(TetraError::PlatformError)
+    }
+
     pub fn set_visible(&mut self, visible: bool) {
         if visible {
             self.sdl_window.show();
@@ -322,6 +405,21 @@ impl Window {
         Ok((display_mode.w, display_mode.h))
     }
 
+    pub fn get_monitor_refresh_rate(&self, monitor_index: i32) -> Result<i32> {
+        let display_mode = self
+            .video_sys
+            .desktop_display_mode(monitor_index)
+            .map_err(TetraError::PlatformError)?;
+
+        if display_mode.refresh_rate > 0 {
+            Ok(display_mode.refresh_rate)
+        } else {
+            Err(TetraError::PlatformError(
+                "could not determine the monitor's refresh rate".into(),
+            ))
+        }
+    }
+
     pub fn get_current_monitor(&self) -> Result<i32> {
         self.sdl_window
             .display_index()
@@ -342,8 +440,8 @@ impl Window {
         self.video_sys.gl_get_swap_interval() != SwapInterval::Immediate
     }
 
-    pub fn set_fullscreen(&mut self, fullscreen: bool) -> Result {
-        if fullscreen {
+    pub fn set_fullscreen(&mut self, fullscreen: bool) -> Result<bool> {
+        let result = if fullscreen {
             self.sdl_window
                 .display_mode()
                 .map_err(TetraError::FailedToChangeDisplayMode)
@@ -362,13 +460,37 @@ impl Window {
                     let size = self.sdl_window.drawable_size();
                     self.set_window_size(size.0 as i32, size.1 as i32)
                 })
-        }
+        };
+
+        result.map(|_| self.is_fullscreen())
     }
 
     pub fn is_fullscreen(&self) -> bool {
         self.sdl_window.fullscreen_state() != FullscreenType::Off
     }
 
+    fn take_fullscreen_change(&mut self) -> Option<bool> {
+        let fullscreen = self.is_fullscreen();
+
+        if fullscreen != self.window_fullscreen {
+            self.window_fullscreen = fullscreen;
+            Some(fullscreen)
+        } else {
+            None
+        }
+    }
+
+    fn take_dpi_scale_change(&mut self) -> Option<f32> {
+        let dpi_scale = self.get_dpi_scale();
+
+        if (dpi_scale - self.dpi_scale).abs() > f32::EPSILON {
+            self.dpi_scale = dpi_scale;
+            Some(dpi_scale)
+        } else {
+            None
+        }
+    }
+
     pub fn set_mouse_visible(&mut self, mouse_visible: bool) -> Result {
         self.sdl.mouse().show_cursor(mouse_visible);
         Ok(())
@@ -418,6 +540,61 @@ impl Window {
         self.controllers[&platform_id].controller.name()
     }
 
+    pub fn get_gamepad_type(&self, platform_id: u32) -> GamepadType {
+        use sdl2::sys::SDL_GameControllerType::*;
+
+        if !self.controllers.contains_key(&platform_id) {
+            return GamepadType::Unknown;
+        }
+
+        // The sdl2 crate doesn't wrap this function yet.
+        let controller_type = unsafe {
+            let raw = sdl2::sys::SDL_GameControllerFromInstanceID(platform_id as i32);
+
+            if raw.is_null() {
+                return GamepadType::Unknown;
+            }
+
+            sdl2::sys::SDL_GameControllerGetType(raw)
+        };
+
+        match controller_type {
+            SDL_CONTROLLER_TYPE_XBOX360 => GamepadType::Xbox360,
+            SDL_CONTROLLER_TYPE_XBOXONE => GamepadType::XboxOne,
+            SDL_CONTROLLER_TYPE_PS3 => GamepadType::PlayStation3,
+            SDL_CONTROLLER_TYPE_PS4 => GamepadType::PlayStation4,
+            SDL_CONTROLLER_TYPE_PS5 => GamepadType::PlayStation5,
+            SDL_CONTROLLER_TYPE_NINTENDO_SWITCH_PRO => GamepadType::SwitchPro,
+            SDL_CONTROLLER_TYPE_VIRTUAL => GamepadType::Virtual,
+            _ => GamepadType::Unknown,
+        }
+    }
+
+    pub fn add_gamepad_mappings(&mut self, mappings: &str) -> Result<usize> {
+        let platform = format!("platform:{},", sdl2::get_platform());
+        let mut count = 0;
+
+        // SDL's own loader skips mappings that don't specify a platform, which would
+        // make it awkward to add a one-off mapping, so we filter the lines ourselves.
+        for line in mappings.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.contains("platform:") && !line.contains(&platform) {
+                continue;
+            }
+
+            self.controller_sys
+                .add_mapping(line)
+                .map_err(|e| TetraError::PlatformError(e.to_string()))?;
+
+            count += 1;
+        }
+
+        Ok(count)
+    }
+
     pub fn is_gamepad_vibration_supported(&self, platform_id: u32) -> bool {
         self.controllers
             .get(&platform_id)
@@ -430,14 +607,94 @@ impl Window {
     }
 
     pub fn start_gamepad_vibration(&mut self, platform_id: u32, strength: f32, duration: u32) {
+        self.start_gamepad_rumble(platform_id, strength, strength, duration);
+    }
+
+    pub fn get_gamepad_haptics(&self, platform_id: u32) -> GamepadHaptics {
+        match self.controllers.get(&platform_id) {
+            Some(controller) => GamepadHaptics {
+                rumble: controller.supports_rumble,
+                trigger_rumble: controller.supports_trigger_rumble,
+                adaptive_triggers: self.get_gamepad_type(platform_id) == GamepadType::PlayStation5
+                    && send_effect_fn().is_some(),
+            },
+            None => GamepadHaptics::default(),
+        }
+    }
+
+    pub fn start_gamepad_rumble(
+        &mut self,
+        platform_id: u32,
+        low_frequency: f32,
+        high_frequency: f32,
+        duration: u32,
+    ) {
         if let Some(controller) = self
             .controllers
             .get_mut(&platform_id)
             .map(|c| &mut c.controller)
         {
-            let int_strength = ((u16::MAX as f32) * strength) as u16;
+            let _ = controller.set_rumble(
+                rumble_strength(low_frequency),
+                rumble_strength(high_frequency),
+                duration,
+            );
+        }
+    }
+
+    pub fn start_gamepad_trigger_rumble(
+        &mut self,
+        platform_id: u32,
+        left: f32,
+        right: f32,
+        duration: u32,
+    ) {
+        if self.controllers.contains_key(&platform_id) {
+            // The sdl2 crate doesn't wrap this function yet.
+            unsafe {
+                let raw = sdl2::sys::SDL_GameControllerFromInstanceID(platform_id as i32);
+
+                if !raw.is_null() {
+                    sdl2::sys::SDL_GameControllerRumbleTriggers(
+                        raw,
+                        rumble_strength(left),
+                        rumble_strength(right),
+                        duration,
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn set_gamepad_trigger_effect(
+        &mut self,
+        platform_id: u32,
+        trigger: GamepadTrigger,
+        effect: TriggerEffect,
+    ) {
+        let send_effect = match send_effect_fn() {
+            Some(send_effect) if self.controllers.contains_key(&platform_id) => send_effect,
+            _ => return,
+        };
+
+        // This is the layout of the DualSense output report, starting with two bytes of
+        // flags which determine which parts of the controller's state get updated.
+        let mut data = [0u8; 47];
+
+        let (flag, offset) = match trigger {
+            GamepadTrigger::RightTrigger => (0x04, 10),
+            GamepadTrigger::LeftTrigger => (0x08, 21),
+        };
+
+        data[0] = flag;
+        data[offset..offset + 11].copy_from_slice(&effect.to_dualsense());
 
-            let _ = controller.set_rumble(int_strength, int_strength, duration);
+        unsafe {
+            let raw = sdl2::sys::SDL_GameControllerFromInstanceID(platform_id as i32);
+
+            if !raw.is_null() {
+                send_effect(raw, data.as_ptr() as *const c_void, data.len() as c_int);
+            }
         }
     }
 
@@ -471,6 +728,27 @@ impl Window {
         self.key_repeat
     }
 
+    pub fn start_text_input(&mut self) {
+        self.video_sys.text_input().start();
+    }
+
+    pub fn stop_text_input(&mut self) {
+        self.video_sys.text_input().stop();
+    }
+
+    pub fn is_text_input_active(&self) -> bool {
+        self.video_sys.text_input().is_active()
+    }
+
+    pub fn set_text_input_rect(&mut self, rect: Rectangle<i32>) {
+        self.video_sys.text_input().set_rect(Rect::new(
+            rect.x,
+            rect.y,
+            rect.width.max(0) as u32,
+            rect.height.max(0) as u32,
+        ));
+    }
+
     pub fn get_key_with_label(&self, key_label: KeyLabel) -> Option<Key> {
         let sdl_keycode = into_sdl_keycode(key_label);
         let sdl_scancode = Scancode::from_keycode(sdl_keycode)?;
@@ -496,27 +774,46 @@ where
             SdlEvent::Window { win_event, .. } => match win_event {
                 WindowEvent::SizeChanged(width, height) => {
                     graphics::set_viewport_size(ctx);
-                    state.event(ctx, Event::Resized { width, height })?;
+
+                    let (physical_width, physical_height) = ctx.window.get_physical_size();
+
+                    dispatch(
+                        ctx,
+                        state,
+                        Event::Resized {
+                            width,
+                            height,
+                            physical_width,
+                            physical_height,
+                            scale: ctx.window.get_dpi_scale(),
+                        },
+                    )?;
                 }
 
                 WindowEvent::Restored => {
-                    state.event(ctx, Event::Restored)?;
+                    dispatch(ctx, state, Event::Restored)?;
                 }
 
                 WindowEvent::Minimized => {
-                    state.event(ctx, Event::Minimized)?;
+                    dispatch(ctx, state, Event::Minimized)?;
                 }
 
                 WindowEvent::Maximized => {
-                    state.event(ctx, Event::Maximized)?;
+                    dispatch(ctx, state, Event::Maximized)?;
                 }
 
                 WindowEvent::FocusGained => {
-                    state.event(ctx, Event::FocusGained)?;
+                    #[cfg(feature = "audio")]
+                    ctx.audio.set_focused(true);
+
+                    dispatch(ctx, state, Event::FocusGained)?;
                 }
 
                 WindowEvent::FocusLost => {
-                    state.event(ctx, Event::FocusLost)?;
+                    #[cfg(feature = "audio")]
+                    ctx.audio.set_focused(false);
+
+                    dispatch(ctx, state, Event::FocusLost)?;
                 }
 
                 _ => {}
@@ -539,7 +836,7 @@ where
 
                     if let Some(key) = from_sdl_scancode(scancode) {
                         input::set_key_down(ctx, key);
-                        state.event(ctx, Event::KeyPressed { key })?;
+                        dispatch(ctx, state, Event::KeyPressed { key })?;
                     }
                 }
             }
@@ -555,21 +852,21 @@ where
                     // TODO: This can cause some inputs to be missed at low tick rates.
                     // Could consider buffering input releases like Otter2D does?
                     input::set_key_up(ctx, key);
-                    state.event(ctx, Event::KeyReleased { key })?;
+                    dispatch(ctx, state, Event::KeyReleased { key })?;
                 }
             }
 
             SdlEvent::MouseButtonDown { mouse_btn, .. } => {
                 if let Some(button) = into_mouse_button(mouse_btn) {
                     input::set_mouse_button_down(ctx, button);
-                    state.event(ctx, Event::MouseButtonPressed { button })?;
+                    dispatch(ctx, state, Event::MouseButtonPressed { button })?;
                 }
             }
 
             SdlEvent::MouseButtonUp { mouse_btn, .. } => {
                 if let Some(button) = into_mouse_button(mouse_btn) {
                     input::set_mouse_button_up(ctx, button);
-                    state.event(ctx, Event::MouseButtonReleased { button })?;
+                    dispatch(ctx, state, Event::MouseButtonReleased { button })?;
                 }
             }
 
@@ -580,29 +877,47 @@ where
                 let delta = Vec2::new(xrel as f32, yrel as f32);
 
                 input::set_mouse_position(ctx, position);
-                state.event(ctx, Event::MouseMoved { position, delta })?;
+                dispatch(ctx, state, Event::MouseMoved { position, delta })?;
             }
 
             SdlEvent::MouseWheel {
                 x, y, direction, ..
             } => {
-                let amount = match direction {
-                    MouseWheelDirection::Flipped => Vec2::new(-x, -y),
-                    _ => Vec2::new(x, y),
+                let delta = match direction {
+                    MouseWheelDirection::Flipped => Vec2::new(-x as f32, -y as f32),
+                    _ => Vec2::new(x as f32, y as f32),
                 };
 
-                input::apply_mouse_wheel_movement(ctx, amount);
-                state.event(ctx, Event::MouseWheelMoved { amount })?
+                let amount = input::apply_mouse_wheel_movement(ctx, delta);
+                dispatch(ctx, state, Event::MouseWheelMoved { amount, delta })?
             }
 
             SdlEvent::TextInput { text, .. } => {
                 input::push_text_input(ctx, &text);
-                state.event(ctx, Event::TextInput { text })?;
+                dispatch(ctx, state, Event::TextInput { text })?;
+            }
+
+            SdlEvent::TextEditing {
+                text,
+                start,
+                length,
+                ..
+            } => {
+                dispatch(
+                    ctx,
+                    state,
+                    Event::TextEditing {
+                        text,
+                        cursor: start.max(0) as usize,
+                        selection_length: length.max(0) as usize,
+                    },
+                )?;
             }
 
             SdlEvent::DropFile { filename, .. } => {
-                state.event(
+                dispatch(
                     ctx,
+                    state,
                     Event::FileDropped {
                         path: PathBuf::from(filename),
                     },
@@ -620,6 +935,10 @@ where
                 let slot = input::add_gamepad(ctx, id);
 
                 let supports_rumble = controller.set_rumble(0, 0, 0).is_ok();
+                let supports_trigger_rumble = unsafe {
+                    let raw = sdl2::sys::SDL_GameControllerFromInstanceID(id as i32);
+                    !raw.is_null() && sdl2::sys::SDL_GameControllerRumbleTriggers(raw, 0, 0, 0) == 0
+                };
 
                 ctx.window.controllers.insert(
                     id,
@@ -627,18 +946,20 @@ where
                         controller,
                         slot,
                         supports_rumble,
+                        supports_trigger_rumble,
                     },
                 );
 
-                state.event(ctx, Event::GamepadAdded { id: slot })?;
+                dispatch(ctx, state, Event::GamepadAdded { id: slot })?;
             }
 
             SdlEvent::ControllerDeviceRemoved { which, .. } => {
                 let controller = ctx.window.controllers.remove(&which).unwrap();
                 input::remove_gamepad(ctx, controller.slot);
 
-                state.event(
+                dispatch(
                     ctx,
+                    state,
                     Event::GamepadRemoved {
                         id: controller.slot,
                     },
@@ -650,7 +971,7 @@ where
                     if let Some(pad) = input::get_gamepad_mut(ctx, slot) {
                         if let Some(button) = into_gamepad_button(button) {
                             pad.set_button_down(button);
-                            state.event(ctx, Event::GamepadButtonPressed { id: slot, button })?;
+                            dispatch(ctx, state, Event::GamepadButtonPressed { id: slot, button })?;
                         }
                     }
                 }
@@ -663,7 +984,11 @@ where
                             // TODO: This can cause some inputs to be missed at low tick rates.
                             // Could consider buffering input releases like Otter2D does?
                             pad.set_button_up(button);
-                            state.event(ctx, Event::GamepadButtonReleased { id: slot, button })?;
+                            dispatch(
+                                ctx,
+                                state,
+                                Event::GamepadButtonReleased { id: slot, button },
+                            )?;
                         }
                     }
                 }
@@ -695,8 +1020,9 @@ where
                                 let pressed = pad.set_button_down(button);
 
                                 if pressed {
-                                    state.event(
+                                    dispatch(
                                         ctx,
+                                        state,
                                         Event::GamepadButtonPressed { id: slot, button },
                                     )?;
                                 }
@@ -704,16 +1030,18 @@ where
                                 let released = pad.set_button_up(button);
 
                                 if released {
-                                    state.event(
+                                    dispatch(
                                         ctx,
+                                        state,
                                         Event::GamepadButtonReleased { id: slot, button },
                                     )?;
                                 }
                             }
                         }
 
-                        state.event(
+                        dispatch(
                             ctx,
+                            state,
                             Event::GamepadAxisMoved {
                                 id: slot,
                                 axis,
@@ -732,8 +1060,9 @@ where
                         };
 
                         if let Some(stick) = stick {
-                            state.event(
+                            dispatch(
                                 ctx,
+                                state,
                                 Event::GamepadStickMoved {
                                     id: slot,
                                     stick,
@@ -749,9 +1078,26 @@ where
         }
     }
 
+    // The fullscreen state can be changed by the OS without the game asking for it
+    // (e.g. via the green button on macOS), so we check for changes every frame rather
+    // than relying on the platform to send an event.
+    if let Some(fullscreen) = ctx.window.take_fullscreen_change() {
+        dispatch(ctx, state, Event::FullscreenChanged { fullscreen })?;
+    }
+
+    // Moving the window to a different monitor can change the DPI scale, and not every
+    // platform reports this consistently, so this is also checked every frame.
+    if let Some(scale) = ctx.window.take_dpi_scale_change() {
+        dispatch(ctx, state, Event::DpiScaleChanged { scale })?;
+    }
+
     Ok(())
 }
 
+fn rumble_strength(strength: f32) -> u16 {
+    ((u16::MAX as f32) * strength.clamp(0.0, 1.0)) as u16
+}
+
 fn into_mouse_button(button: SdlMouseButton) -> Option<MouseButton> {
     match button {
         SdlMouseButton::Left => Some(MouseButton::Left),
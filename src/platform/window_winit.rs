@@ -0,0 +1,1134 @@
+// This backend is intended to be a drop-in replacement for the SDL2 backend, for users who
+// would rather not depend on native libraries. Anything that winit doesn't support (gamepads,
+// the clipboard, changing vsync at runtime) is stubbed out, rather than changing the public API.
+use std::cell::Cell;
+use std::convert::TryFrom;
+use std::result;
+
+use glow::Context as GlowContext;
+use glutin::dpi::{LogicalSize, PhysicalPosition};
+use glutin::event::{
+    DeviceEvent, DeviceId, ElementState, Event as WinitEvent, KeyboardInput, ModifiersState,
+    MouseButton as WinitMouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent,
+};
+use glutin::event_loop::{ControlFlow, EventLoop};
+use glutin::monitor::MonitorHandle;
+use glutin::platform::run_return::EventLoopExtRunReturn;
+use glutin::window::{Fullscreen, Icon, Window as WinitWindow, WindowBuilder};
+use glutin::{
+    Api, ContextBuilder as GlutinContextBuilder, GlProfile, GlRequest, PossiblyCurrent,
+    WindowedContext,
+};
+use hashbrown::HashMap;
+
+use crate::error::{Result, TetraError};
+use crate::graphics::{self, ImageData, Rectangle};
+use crate::input::{
+    self, GamepadHaptics, GamepadTrigger, GamepadType, Key, KeyLabel, KeyModifierState,
+    MouseButton, RawInput, TriggerEffect,
+};
+use crate::lifecycle::dispatch;
+use crate::math::Vec2;
+use crate::window::WindowPosition;
+use crate::{Context, ContextBuilder, Event, State};
+
+pub struct Window {
+    event_loop: EventLoop<()>,
+    gl_window: WindowedContext<PossiblyCurrent>,
+
+    title: String,
+    minimum_size: (i32, i32),
+    maximum_size: (i32, i32),
+
+    window_visible: bool,
+    window_maximized: bool,
+    window_fullscreen: bool,
+    dpi_scale: f32,
+    mouse_visible: bool,
+    mouse_grabbed: bool,
+    relative_mouse_mode: bool,
+    screen_saver_enabled: Cell<bool>,
+    vsync: bool,
+
+    key_repeat: bool,
+    text_input_active: bool,
+
+    raw_input: bool,
+    raw_devices: HashMap<DeviceId, usize>,
+}
+
+impl Window {
+    pub fn new(settings: &ContextBuilder) -> Result<(Window, GlowContext, i32, i32)> {
+        let event_loop = EventLoop::new();
+
+        // The window starts hidden, so that it doesn't look weird if we
+        // maximize/minimize/fullscreen the window after it opens.
+        let mut window_builder = WindowBuilder::new()
+            .with_title(&settings.title)
+            .with_inner_size(LogicalSize::new(
+                settings.window_width,
+                settings.window_height,
+            ))
+            .with_visible(false)
+            .with_resizable(settings.resizable)
+            .with_decorations(!settings.borderless)
+            .with_maximized(settings.maximized);
+
+        if settings.fullscreen {
+            window_builder = window_builder.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+
+        let mut context_builder = GlutinContextBuilder::new()
+            .with_gl(GlRequest::Specific(Api::OpenGl, (3, 2)))
+            .with_gl_profile(GlProfile::Core)
+            .with_pixel_format(24, 8)
+            .with_double_buffer(Some(true))
+            .with_vsync(settings.vsync)
+            .with_gl_debug_flag(settings.graphics_debug);
+
+        if settings.multisampling > 0 {
+            context_builder = context_builder.with_multisampling(settings.multisampling.into());
+        }
+
+        if settings.stencil_buffer {
+            context_builder = context_builder.with_stencil_buffer(8);
+        }
+
+        let gl_window = context_builder
+            .build_windowed(window_builder, &event_loop)
+            .map_err(|e| TetraError::PlatformError(e.to_string()))?;
+
+        let gl_window = unsafe {
+            gl_window
+                .make_current()
+                .map_err(|(_, e)| TetraError::PlatformError(e.to_string()))?
+        };
+
+        let gl_ctx =
+            unsafe { GlowContext::from_loader_function(|s| gl_window.get_proc_address(s)) };
+
+        if settings.minimized {
+            gl_window.window().set_minimized(true);
+        }
+
+        let dpi_scale = gl_window.window().scale_factor() as f32;
+
+        let mut window = Window {
+            event_loop,
+            gl_window,
+
+            title: settings.title.clone(),
+            minimum_size: (0, 0),
+            maximum_size: (0, 0),
+
+            window_visible: false,
+            window_maximized: settings.maximized,
+            window_fullscreen: settings.fullscreen,
+            dpi_scale,
+            mouse_visible: settings.show_mouse,
+            mouse_grabbed: false,
+            relative_mouse_mode: false,
+            screen_saver_enabled: Cell::new(settings.screen_saver_enabled),
+            vsync: settings.vsync,
+
+            key_repeat: settings.key_repeat,
+            text_input_active: true,
+
+            raw_input: settings.raw_input,
+            raw_devices: HashMap::new(),
+        };
+
+        window.set_mouse_visible(settings.show_mouse)?;
+        window.set_mouse_grabbed(settings.grab_mouse);
+        window.set_relative_mouse_mode(settings.relative_mouse_mode);
+
+        let (window_width, window_height) = window.get_window_size();
+
+        Ok((window, gl_ctx, window_width, window_height))
+    }
+
+    fn window(&self) -> &WinitWindow {
+        self.gl_window.window()
+    }
+
+    pub fn maximize(&mut self) {
+        self.window().set_maximized(true);
+    }
+
+    pub fn minimize(&mut self) {
+        self.window().set_minimized(true);
+    }
+
+    pub fn restore(&mut self) {
+        self.window().set_minimized(false);
+        self.window().set_maximized(false);
+    }
+
+    pub fn focus(&mut self) {
+        self.window().focus_window();
+    }
+
+    pub fn get_window_title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn set_window_title<S>(&mut self, title: S)
+    where
+        S: AsRef<str>,
+    {
+        self.title = title.as_ref().to_owned();
+        self.window().set_title(&self.title);
+    }
+
+    pub fn get_window_size(&self) -> (i32, i32) {
+        let size: LogicalSize<i32> = self
+            .window()
+            .inner_size()
+            .to_logical(self.window().scale_factor());
+
+        (size.width, size.height)
+    }
+
+    pub fn get_physical_size(&self) -> (i32, i32) {
+        let size = self.window().inner_size();
+        (size.width as i32, size.height as i32)
+    }
+
+    pub fn set_window_size(&mut self, width: i32, height: i32) -> Result {
+        self.window()
+            .set_inner_size(LogicalSize::new(width.max(1), height.max(1)));
+
+        Ok(())
+    }
+
+    pub fn set_minimum_size(&mut self, width: i32, height: i32) -> Result {
+        self.minimum_size = (width, height);
+
+        self.window()
+            .set_min_inner_size(Some(LogicalSize::new(width, height)));
+
+        Ok(())
+    }
+
+    pub fn get_minimum_size(&self) -> (i32, i32) {
+        self.minimum_size
+    }
+
+    pub fn set_maximum_size(&mut self, width: i32, height: i32) -> Result {
+        self.maximum_size = (width, height);
+
+        // SDL treats a maximum size of zero as 'no limit', so we do the same here.
+        if width > 0 && height > 0 {
+            self.window()
+                .set_max_inner_size(Some(LogicalSize::new(width, height)));
+        } else {
+            self.window().set_max_inner_size(None::<LogicalSize<i32>>);
+        }
+
+        Ok(())
+    }
+
+    pub fn get_maximum_size(&self) -> (i32, i32) {
+        self.maximum_size
+    }
+
+    pub fn set_position(&mut self, x: WindowPosition, y: WindowPosition) {
+        let outer_size = self.window().outer_size();
+
+        let x = self.resolve_position(x, |m| (m.position().x, m.size().width, outer_size.width));
+        let y = self.resolve_position(y, |m| (m.position().y, m.size().height, outer_size.height));
+
+        self.window()
+            .set_outer_position(PhysicalPosition::new(x, y));
+    }
+
+    fn resolve_position<F>(&self, pos: WindowPosition, axis: F) -> i32
+    where
+        F: Fn(&MonitorHandle) -> (i32, u32, u32),
+    {
+        match pos {
+            WindowPosition::Centered(monitor_index) => {
+                match self.get_monitor(monitor_index).or_else(|_| {
+                    self.window()
+                        .current_monitor()
+                        .ok_or_else(|| TetraError::PlatformError("no monitor found".into()))
+                }) {
+                    Ok(monitor) => {
+                        let (monitor_pos, monitor_size, window_size) = axis(&monitor);
+                        monitor_pos + (monitor_size as i32 - window_size as i32) / 2
+                    }
+                    Err(_) => 0,
+                }
+            }
+            WindowPosition::Positioned(value) => value,
+        }
+    }
+
+    pub fn get_position(&self) -> (i32, i32) {
+        self.window()
+            .outer_position()
+            .map(|p| (p.x, p.y))
+            .unwrap_or((0, 0))
+    }
+
+    pub fn set_decorated(&mut self, bordered: bool) {
+        self.window().set_decorations(bordered);
+    }
+
+    pub fn set_icon(&mut self, data: &mut ImageData) -> Result {
+        let (width, height) = data.size();
+
+        let icon = Icon::from_rgba(data.as_bytes().to_vec(), width as u32, height as u32)
+            .map_err(|e| TetraError::PlatformError(e.to_string()))?;
+
+        self.window().set_window_icon(Some(icon));
+
+        Ok(())
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.window_visible
+    }
+
+    pub fn get_opacity(&self) -> f32 {
+        1.0
+    }
+
+    pub fn set_opacity(&mut self, _opacity: f32) -> Result {
+        Err(TetraError::PlatformError(
+            "window opacity is not supported by the winit backend".into(),
+        ))
+    }
+
+    pub fn get_brightness(&self) -> f32 {
+        1.0
+    }
+
+    pub fn set_brightness(&mut self, _brightness: f32) -> Result {
+        Err(TetraError::PlatformError(
+            "display brightness is not supported by the winit backend".into(),
+        ))
+    }
+
+    pub fn set_gamma_ramp(
+        &mut self,
+        _red: &[u16; 256],
+        _green: &[u16; 256],
+        _blue: &[u16; 256],
+    ) -> Result {
+        Err(TetraError::PlatformError(
+            "gamma ramps are not supported by the winit backend".into(),
+        ))
+    }
+
+    pub fn set_visible(&mut self, visible: bool) {
+        self.window().set_visible(visible);
+        self.window_visible = visible;
+    }
+
+    pub fn get_dpi_scale(&self) -> f32 {
+        self.window().scale_factor() as f32
+    }
+
+    fn get_monitor(&self, monitor_index: i32) -> Result<MonitorHandle> {
+        usize::try_from(monitor_index)
+            .ok()
+            .and_then(|i| self.window().available_monitors().nth(i))
+            .ok_or_else(|| {
+                TetraError::PlatformError(format!("invalid monitor index: {}", monitor_index))
+            })
+    }
+
+    pub fn get_monitor_count(&self) -> Result<i32> {
+        Ok(self.window().available_monitors().count() as i32)
+    }
+
+    pub fn get_monitor_name(&self, monitor_index: i32) -> Result<String> {
+        let monitor = self.get_monitor(monitor_index)?;
+        Ok(monitor.name().unwrap_or_default())
+    }
+
+    pub fn get_monitor_size(&self, monitor_index: i32) -> Result<(i32, i32)> {
+        let monitor = self.get_monitor(monitor_index)?;
+        let size: LogicalSize<i32> = monitor.size().to_logical(monitor.scale_factor());
+
+        Ok((size.width, size.height))
+    }
+
+    pub fn get_monitor_refresh_rate(&self, monitor_index: i32) -> Result<i32> {
+        let monitor = self.get_monitor(monitor_index)?;
+        let size = monitor.size();
+
+        // winit doesn't expose the current display mode, so this assumes the monitor is
+        // running at the fastest rate it supports for its current resolution.
+        monitor
+            .video_modes()
+            .filter(|m| m.size() == size)
+            .map(|m| i32::from(m.refresh_rate()))
+            .max()
+            .filter(|r| *r > 0)
+            .ok_or_else(|| {
+                TetraError::PlatformError("could not determine the monitor's refresh rate".into())
+            })
+    }
+
+    pub fn get_current_monitor(&self) -> Result<i32> {
+        let current = self
+            .window()
+            .current_monitor()
+            .ok_or_else(|| TetraError::PlatformError("no monitor found".into()))?;
+
+        self.window()
+            .available_monitors()
+            .position(|m| m == current)
+            .map(|i| i as i32)
+            .ok_or_else(|| TetraError::PlatformError("no monitor found".into()))
+    }
+
+    pub fn set_vsync(&mut self, vsync: bool) -> Result {
+        // glutin only allows the swap interval to be set at context creation time.
+        if vsync == self.vsync {
+            Ok(())
+        } else {
+            Err(TetraError::FailedToChangeDisplayMode(
+                "vsync cannot be changed at runtime when using the winit backend".into(),
+            ))
+        }
+    }
+
+    pub fn is_vsync_enabled(&self) -> bool {
+        self.vsync
+    }
+
+    pub fn set_fullscreen(&mut self, fullscreen: bool) -> Result<bool> {
+        if fullscreen {
+            self.window()
+                .set_fullscreen(Some(Fullscreen::Borderless(None)));
+        } else {
+            self.window().set_fullscreen(None);
+        }
+
+        Ok(self.is_fullscreen())
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.window().fullscreen().is_some()
+    }
+
+    fn take_fullscreen_change(&mut self) -> Option<bool> {
+        let fullscreen = self.is_fullscreen();
+
+        if fullscreen != self.window_fullscreen {
+            self.window_fullscreen = fullscreen;
+            Some(fullscreen)
+        } else {
+            None
+        }
+    }
+
+    fn take_dpi_scale_change(&mut self) -> Option<f32> {
+        let dpi_scale = self.get_dpi_scale();
+
+        if (dpi_scale - self.dpi_scale).abs() > f32::EPSILON {
+            self.dpi_scale = dpi_scale;
+            Some(dpi_scale)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_mouse_visible(&mut self, mouse_visible: bool) -> Result {
+        self.mouse_visible = mouse_visible;
+        self.window()
+            .set_cursor_visible(mouse_visible && !self.relative_mouse_mode);
+
+        Ok(())
+    }
+
+    pub fn is_mouse_visible(&self) -> bool {
+        self.mouse_visible
+    }
+
+    pub fn set_mouse_grabbed(&mut self, mouse_grabbed: bool) {
+        if self
+            .window()
+            .set_cursor_grab(mouse_grabbed || self.relative_mouse_mode)
+            .is_ok()
+        {
+            self.mouse_grabbed = mouse_grabbed;
+        }
+    }
+
+    pub fn is_mouse_grabbed(&self) -> bool {
+        self.mouse_grabbed
+    }
+
+    pub fn set_relative_mouse_mode(&mut self, relative_mouse_mode: bool) {
+        // winit doesn't have a direct equivalent to SDL's relative mouse mode, so we
+        // emulate it by hiding and grabbing the cursor, and then reporting the raw
+        // device motion as the mouse delta.
+        self.relative_mouse_mode = relative_mouse_mode;
+
+        let _ = self
+            .window()
+            .set_cursor_grab(relative_mouse_mode || self.mouse_grabbed);
+
+        self.window()
+            .set_cursor_visible(self.mouse_visible && !relative_mouse_mode);
+    }
+
+    pub fn is_relative_mouse_mode(&self) -> bool {
+        self.relative_mouse_mode
+    }
+
+    pub fn get_clipboard_text(&self) -> Result<String> {
+        Err(TetraError::PlatformError(
+            "the clipboard is not supported by the winit backend".into(),
+        ))
+    }
+
+    pub fn set_clipboard_text(&self, _text: &str) -> Result {
+        Err(TetraError::PlatformError(
+            "the clipboard is not supported by the winit backend".into(),
+        ))
+    }
+
+    pub fn swap_buffers(&self) {
+        let _ = self.gl_window.swap_buffers();
+    }
+
+    // winit does not support gamepads, so no gamepads will ever be registered with
+    // the input context, and these methods will never be reached with a valid ID.
+
+    pub fn get_gamepad_name(&self, _platform_id: u32) -> String {
+        String::new()
+    }
+
+    pub fn get_gamepad_type(&self, _platform_id: u32) -> GamepadType {
+        GamepadType::Unknown
+    }
+
+    pub fn add_gamepad_mappings(&mut self, _mappings: &str) -> Result<usize> {
+        Ok(0)
+    }
+
+    pub fn is_gamepad_vibration_supported(&self, _platform_id: u32) -> bool {
+        false
+    }
+
+    pub fn set_gamepad_vibration(&mut self, _platform_id: u32, _strength: f32) {}
+
+    pub fn get_gamepad_haptics(&self, _platform_id: u32) -> GamepadHaptics {
+        GamepadHaptics::default()
+    }
+
+    pub fn start_gamepad_rumble(
+        &mut self,
+        _platform_id: u32,
+        _low_frequency: f32,
+        _high_frequency: f32,
+        _duration: u32,
+    ) {
+    }
+
+    pub fn start_gamepad_trigger_rumble(
+        &mut self,
+        _platform_id: u32,
+        _left: f32,
+        _right: f32,
+        _duration: u32,
+    ) {
+    }
+
+    pub fn set_gamepad_trigger_effect(
+        &mut self,
+        _platform_id: u32,
+        _trigger: GamepadTrigger,
+        _effect: TriggerEffect,
+    ) {
+    }
+
+    pub fn start_gamepad_vibration(&mut self, _platform_id: u32, _strength: f32, _duration: u32) {}
+
+    pub fn stop_gamepad_vibration(&mut self, _platform_id: u32) {}
+
+    pub fn set_screen_saver_enabled(&self, screen_saver_enabled: bool) {
+        // winit has no API for inhibiting the screen saver, so this is only tracked
+        // so that the getter is consistent.
+        self.screen_saver_enabled.set(screen_saver_enabled);
+    }
+
+    pub fn is_screen_saver_enabled(&self) -> bool {
+        self.screen_saver_enabled.get()
+    }
+
+    pub fn set_key_repeat_enabled(&mut self, key_repeat: bool) {
+        self.key_repeat = key_repeat;
+    }
+
+    pub fn is_key_repeat_enabled(&self) -> bool {
+        self.key_repeat
+    }
+
+    // winit doesn't have a way of toggling text input at the platform level (or
+    // of showing an on-screen keyboard), so we just filter the events ourselves.
+
+    pub fn start_text_input(&mut self) {
+        self.text_input_active = true;
+    }
+
+    pub fn stop_text_input(&mut self) {
+        self.text_input_active = false;
+    }
+
+    pub fn is_text_input_active(&self) -> bool {
+        self.text_input_active
+    }
+
+    pub fn set_text_input_rect(&mut self, rect: Rectangle<i32>) {
+        self.window()
+            .set_ime_position(PhysicalPosition::new(rect.x, rect.y + rect.height));
+    }
+
+    // winit 0.26 only reports layout-aware key codes, so these assume that the
+    // labels match the physical positions on a US QWERTY keyboard.
+
+    pub fn get_key_with_label(&self, key_label: KeyLabel) -> Option<Key> {
+        from_winit_keycode(into_winit_keycode(key_label)?)
+    }
+
+    pub fn get_key_label(&self, key: Key) -> Option<KeyLabel> {
+        label_from_winit_keycode(key_into_winit_keycode(key)?)
+    }
+}
+
+/// Trackpads report scrolling in pixels rather than lines, so we need to convert
+/// them to match the units that are used for normal mouse wheels. This is roughly
+/// the line height that browsers use for the same conversion.
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+pub fn handle_events<S, E>(ctx: &mut Context, state: &mut S) -> result::Result<(), E>
+where
+    S: State<E>,
+    E: From<TetraError>,
+{
+    let mut events = Vec::new();
+
+    // winit wants to own the main loop, so we pump it once per frame and then
+    // process the buffered events afterwards, so that the state can be given
+    // mutable access to the context.
+    ctx.window
+        .event_loop
+        .run_return(|event, _, control_flow| match event {
+            WinitEvent::MainEventsCleared => *control_flow = ControlFlow::Exit,
+            event => {
+                *control_flow = ControlFlow::Poll;
+
+                if let Some(event) = event.to_static() {
+                    events.push(event);
+                }
+            }
+        });
+
+    for event in events {
+        let raw_event = if ctx.window.raw_input {
+            into_raw_input(&event)
+        } else {
+            None
+        };
+
+        match event {
+            WinitEvent::WindowEvent { event, .. } => match event {
+                WindowEvent::CloseRequested => ctx.running = false,
+
+                WindowEvent::Resized(size) => {
+                    ctx.window.gl_window.resize(size);
+                    graphics::set_viewport_size(ctx);
+
+                    // winit doesn't have dedicated maximize/restore events, so we
+                    // detect them when the window changes size.
+                    let maximized = ctx.window.window().is_maximized();
+
+                    if maximized != ctx.window.window_maximized {
+                        ctx.window.window_maximized = maximized;
+
+                        dispatch(
+                            ctx,
+                            state,
+                            if maximized {
+                                Event::Maximized
+                            } else {
+                                Event::Restored
+                            },
+                        )?;
+                    }
+
+                    let (width, height) = ctx.window.get_window_size();
+
+                    dispatch(
+                        ctx,
+                        state,
+                        Event::Resized {
+                            width,
+                            height,
+                            physical_width: size.width as i32,
+                            physical_height: size.height as i32,
+                            scale: ctx.window.get_dpi_scale(),
+                        },
+                    )?;
+                }
+
+                WindowEvent::Focused(true) => {
+                    #[cfg(feature = "audio")]
+                    ctx.audio.set_focused(true);
+
+                    dispatch(ctx, state, Event::FocusGained)?;
+                }
+
+                WindowEvent::Focused(false) => {
+                    #[cfg(feature = "audio")]
+                    ctx.audio.set_focused(false);
+
+                    dispatch(ctx, state, Event::FocusLost)?;
+                }
+
+                WindowEvent::ModifiersChanged(modifiers) => {
+                    input::set_key_modifier_state(ctx, from_winit_modifiers(modifiers));
+                }
+
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            state: key_state,
+                            virtual_keycode: Some(keycode),
+                            ..
+                        },
+                    ..
+                } => {
+                    if let Some(key) = from_winit_keycode(keycode) {
+                        match key_state {
+                            ElementState::Pressed => {
+                                // winit doesn't flag repeated key presses, so we have to
+                                // work it out from the current input state.
+                                let repeat = input::is_key_down(ctx, key);
+
+                                if !repeat || ctx.window.is_key_repeat_enabled() {
+                                    if key == Key::Escape && ctx.quit_on_escape {
+                                        ctx.running = false;
+                                    }
+
+                                    input::set_key_down(ctx, key);
+                                    dispatch(ctx, state, Event::KeyPressed { key })?;
+                                }
+                            }
+
+                            ElementState::Released => {
+                                input::set_key_up(ctx, key);
+                                dispatch(ctx, state, Event::KeyReleased { key })?;
+                            }
+                        }
+                    }
+                }
+
+                WindowEvent::MouseInput {
+                    state: button_state,
+                    button,
+                    ..
+                } => {
+                    if let Some(button) = into_mouse_button(button) {
+                        match button_state {
+                            ElementState::Pressed => {
+                                input::set_mouse_button_down(ctx, button);
+                                dispatch(ctx, state, Event::MouseButtonPressed { button })?;
+                            }
+
+                            ElementState::Released => {
+                                input::set_mouse_button_up(ctx, button);
+                                dispatch(ctx, state, Event::MouseButtonReleased { button })?;
+                            }
+                        }
+                    }
+                }
+
+                WindowEvent::CursorMoved { position, .. } => {
+                    // When relative mouse mode is on, the delta comes from the raw device
+                    // events instead.
+                    if !ctx.window.relative_mouse_mode {
+                        let scale = ctx.window.window().scale_factor();
+                        let logical = position.to_logical::<f32>(scale);
+
+                        let position = Vec2::new(logical.x, logical.y);
+                        let delta = position - input::get_mouse_position(ctx);
+
+                        input::set_mouse_position(ctx, position);
+                        dispatch(ctx, state, Event::MouseMoved { position, delta })?;
+                    }
+                }
+
+                WindowEvent::MouseWheel { delta, .. } => {
+                    let delta = match delta {
+                        MouseScrollDelta::LineDelta(x, y) => Vec2::new(x, y),
+                        MouseScrollDelta::PixelDelta(PhysicalPosition { x, y }) => {
+                            Vec2::new(x as f32, y as f32) / PIXELS_PER_SCROLL_LINE
+                        }
+                    };
+
+                    let amount = input::apply_mouse_wheel_movement(ctx, delta);
+                    dispatch(ctx, state, Event::MouseWheelMoved { amount, delta })?;
+                }
+
+                WindowEvent::ReceivedCharacter(ch) => {
+                    if !ch.is_control() && ctx.window.text_input_active {
+                        let text = ch.to_string();
+
+                        input::push_text_input(ctx, &text);
+                        dispatch(ctx, state, Event::TextInput { text })?;
+                    }
+                }
+
+                WindowEvent::DroppedFile(path) => {
+                    dispatch(ctx, state, Event::FileDropped { path })?;
+                }
+
+                _ => {}
+            },
+
+            WinitEvent::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta: (x, y) },
+                ..
+            } => {
+                if ctx.window.relative_mouse_mode {
+                    let position = input::get_mouse_position(ctx);
+                    let delta = Vec2::new(x as f32, y as f32);
+
+                    dispatch(ctx, state, Event::MouseMoved { position, delta })?;
+                }
+            }
+
+            _ => {}
+        }
+
+        if let Some((device_id, input)) = raw_event {
+            let next_device = ctx.window.raw_devices.len();
+            let device = *ctx
+                .window
+                .raw_devices
+                .entry(device_id)
+                .or_insert(next_device);
+
+            dispatch(ctx, state, Event::RawInput { device, input })?;
+        }
+    }
+
+    // The fullscreen state can be changed by the OS without the game asking for it
+    // (e.g. via the green button on macOS), so we check for changes every frame rather
+    // than relying on the platform to send an event.
+    if let Some(fullscreen) = ctx.window.take_fullscreen_change() {
+        dispatch(ctx, state, Event::FullscreenChanged { fullscreen })?;
+    }
+
+    // Moving the window to a different monitor can change the DPI scale, and not every
+    // platform reports this consistently, so this is also checked every frame.
+    if let Some(scale) = ctx.window.take_dpi_scale_change() {
+        dispatch(ctx, state, Event::DpiScaleChanged { scale })?;
+    }
+
+    Ok(())
+}
+
+fn into_raw_input(event: &WinitEvent<'_, ()>) -> Option<(DeviceId, RawInput)> {
+    let (device_id, event) = match event {
+        WinitEvent::DeviceEvent { device_id, event } => (*device_id, event),
+        _ => return None,
+    };
+
+    let input = match event {
+        DeviceEvent::MouseMotion { delta: (x, y) } => RawInput::MouseMoved {
+            delta: Vec2::new(*x as f32, *y as f32),
+        },
+
+        DeviceEvent::MouseWheel { delta } => RawInput::MouseWheelMoved {
+            delta: match delta {
+                MouseScrollDelta::LineDelta(x, y) => Vec2::new(*x, *y),
+                MouseScrollDelta::PixelDelta(PhysicalPosition { x, y }) => {
+                    Vec2::new(*x as f32, *y as f32) / PIXELS_PER_SCROLL_LINE
+                }
+            },
+        },
+
+        DeviceEvent::Button { button, state } => {
+            let button = raw_mouse_button(*button)?;
+
+            match state {
+                ElementState::Pressed => RawInput::MouseButtonPressed { button },
+                ElementState::Released => RawInput::MouseButtonReleased { button },
+            }
+        }
+
+        DeviceEvent::Key(KeyboardInput {
+            virtual_keycode: Some(keycode),
+            state,
+            ..
+        }) => {
+            let key = from_winit_keycode(*keycode)?;
+
+            match state {
+                ElementState::Pressed => RawInput::KeyPressed { key },
+                ElementState::Released => RawInput::KeyReleased { key },
+            }
+        }
+
+        _ => return None,
+    };
+
+    Some((device_id, input))
+}
+
+/// winit reports raw mouse buttons as platform-specific numbers. Windows and X11 both
+/// use the X11 numbering, while macOS uses its own.
+fn raw_mouse_button(button: u32) -> Option<MouseButton> {
+    if cfg!(target_os = "macos") {
+        match button {
+            0 => Some(MouseButton::Left),
+            1 => Some(MouseButton::Right),
+            2 => Some(MouseButton::Middle),
+            3 => Some(MouseButton::X1),
+            4 => Some(MouseButton::X2),
+            _ => None,
+        }
+    } else {
+        match button {
+            1 => Some(MouseButton::Left),
+            2 => Some(MouseButton::Middle),
+            3 => Some(MouseButton::Right),
+            8 => Some(MouseButton::X1),
+            9 => Some(MouseButton::X2),
+            _ => None,
+        }
+    }
+}
+
+fn into_mouse_button(button: WinitMouseButton) -> Option<MouseButton> {
+    match button {
+        WinitMouseButton::Left => Some(MouseButton::Left),
+        WinitMouseButton::Middle => Some(MouseButton::Middle),
+        WinitMouseButton::Right => Some(MouseButton::Right),
+        WinitMouseButton::Other(4) => Some(MouseButton::X1),
+        WinitMouseButton::Other(5) => Some(MouseButton::X2),
+        _ => None,
+    }
+}
+
+fn from_winit_modifiers(modifiers: ModifiersState) -> KeyModifierState {
+    KeyModifierState {
+        ctrl: modifiers.ctrl(),
+        alt: modifiers.alt(),
+        shift: modifiers.shift(),
+    }
+}
+
+macro_rules! key_mappings {
+    (
+        both {
+            $($winit_both:ident => $tetra_both:ident),*$(,)?
+        }
+
+        keys {
+            $($winit_key:ident => $tetra_key:ident),*$(,)?
+        }
+
+        labels {
+            $($winit_label:ident => $tetra_key_label:ident),*$(,)?
+        }
+    ) => {
+        fn from_winit_keycode(keycode: VirtualKeyCode) -> Option<Key> {
+            match keycode {
+                $(
+                    VirtualKeyCode::$winit_both => Some(Key::$tetra_both),
+                )*
+
+                $(
+                    VirtualKeyCode::$winit_key => Some(Key::$tetra_key),
+                )*
+
+                _ => None,
+            }
+        }
+
+        fn key_into_winit_keycode(key: Key) -> Option<VirtualKeyCode> {
+            match key {
+                $(
+                    Key::$tetra_both => Some(VirtualKeyCode::$winit_both),
+                )*
+
+                $(
+                    Key::$tetra_key => Some(VirtualKeyCode::$winit_key),
+                )*
+
+                _ => None,
+            }
+        }
+
+        fn label_from_winit_keycode(keycode: VirtualKeyCode) -> Option<KeyLabel> {
+            match keycode {
+                $(
+                    VirtualKeyCode::$winit_both => Some(KeyLabel::$tetra_both),
+                )*
+
+                $(
+                    VirtualKeyCode::$winit_label => Some(KeyLabel::$tetra_key_label),
+                )*
+
+                _ => None,
+            }
+        }
+
+        fn into_winit_keycode(key_label: KeyLabel) -> Option<VirtualKeyCode> {
+            match key_label {
+                $(
+                    KeyLabel::$tetra_both => Some(VirtualKeyCode::$winit_both),
+                )*
+
+                $(
+                    KeyLabel::$tetra_key_label => Some(VirtualKeyCode::$winit_label),
+                )*
+
+                _ => None,
+            }
+        }
+    };
+}
+
+key_mappings! {
+    both {
+        A => A,
+        B => B,
+        C => C,
+        D => D,
+        E => E,
+        F => F,
+        G => G,
+        H => H,
+        I => I,
+        J => J,
+        K => K,
+        L => L,
+        M => M,
+        N => N,
+        O => O,
+        P => P,
+        Q => Q,
+        R => R,
+        S => S,
+        T => T,
+        U => U,
+        V => V,
+        W => W,
+        X => X,
+        Y => Y,
+        Z => Z,
+
+        Key0 => Num0,
+        Key1 => Num1,
+        Key2 => Num2,
+        Key3 => Num3,
+        Key4 => Num4,
+        Key5 => Num5,
+        Key6 => Num6,
+        Key7 => Num7,
+        Key8 => Num8,
+        Key9 => Num9,
+
+        F1 => F1,
+        F2 => F2,
+        F3 => F3,
+        F4 => F4,
+        F5 => F5,
+        F6 => F6,
+        F7 => F7,
+        F8 => F8,
+        F9 => F9,
+        F10 => F10,
+        F11 => F11,
+        F12 => F12,
+        F13 => F13,
+        F14 => F14,
+        F15 => F15,
+        F16 => F16,
+        F17 => F17,
+        F18 => F18,
+        F19 => F19,
+        F20 => F20,
+        F21 => F21,
+        F22 => F22,
+        F23 => F23,
+        F24 => F24,
+
+        Numlock => NumLock,
+        Numpad1 => NumPad1,
+        Numpad2 => NumPad2,
+        Numpad3 => NumPad3,
+        Numpad4 => NumPad4,
+        Numpad5 => NumPad5,
+        Numpad6 => NumPad6,
+        Numpad7 => NumPad7,
+        Numpad8 => NumPad8,
+        Numpad9 => NumPad9,
+        Numpad0 => NumPad0,
+        NumpadAdd => NumPadPlus,
+        NumpadSubtract => NumPadMinus,
+        NumpadMultiply => NumPadMultiply,
+        NumpadDivide => NumPadDivide,
+        NumpadEnter => NumPadEnter,
+
+        LControl => LeftCtrl,
+        LShift => LeftShift,
+        LAlt => LeftAlt,
+        RControl => RightCtrl,
+        RShift => RightShift,
+        RAlt => RightAlt,
+
+        Up => Up,
+        Down => Down,
+        Left => Left,
+        Right => Right,
+
+        Backslash => Backslash,
+        Back => Backspace,
+        Capital => CapsLock,
+        Comma => Comma,
+        Delete => Delete,
+        End => End,
+        Return => Enter,
+        Equals => Equals,
+        Escape => Escape,
+        Home => Home,
+        Insert => Insert,
+        LBracket => LeftBracket,
+        Minus => Minus,
+        PageDown => PageDown,
+        PageUp => PageUp,
+        Pause => Pause,
+        Period => Period,
+        Snapshot => PrintScreen,
+        RBracket => RightBracket,
+        Scroll => ScrollLock,
+        Semicolon => Semicolon,
+        Slash => Slash,
+        Space => Space,
+        Tab => Tab,
+        Grave => Backquote,
+        Apostrophe => Quote,
+    }
+
+    keys {}
+
+    labels {
+        Asterisk => Asterisk,
+        At => At,
+        Caret => Caret,
+        Colon => Colon,
+        Plus => Plus,
+        Underline => Underscore,
+    }
+}
@@ -0,0 +1,35 @@
+#version 150
+
+in vec2 v_uv;
+in vec4 v_color;
+
+uniform sampler2D u_texture;
+uniform vec4 u_diffuse;
+
+uniform mat3 u_color_matrix;
+uniform float u_contrast;
+
+out vec4 o_color;
+
+vec3 to_linear(vec3 color) {
+    return pow(color, vec3(2.2));
+}
+
+vec3 to_srgb(vec3 color) {
+    return pow(color, vec3(1.0 / 2.2));
+}
+
+void main() {
+    vec3 color = texture(u_texture, v_uv).rgb;
+
+    // Color vision deficiencies are simulated in linear space.
+    color = to_srgb(clamp(u_color_matrix * to_linear(color), 0.0, 1.0));
+
+    // Contrast is increased around mid-grey, and saturation is boosted slightly so that
+    // colors stay distinct.
+    float luma = dot(color, vec3(0.2126, 0.7152, 0.0722));
+    color = mix(vec3(luma), color, 1.0 + u_contrast * 0.5);
+    color = (color - 0.5) * (1.0 + u_contrast) + 0.5;
+
+    o_color = vec4(clamp(color, 0.0, 1.0), 1.0);
+}
@@ -0,0 +1,41 @@
+#version 150
+
+in vec2 v_uv;
+in vec4 v_color;
+
+uniform sampler2D u_texture;
+uniform vec4 u_diffuse;
+
+uniform vec2 u_viewport;
+uniform vec2 u_texture_size;
+uniform vec2 u_position;
+uniform vec2 u_forward;
+uniform float u_height;
+uniform float u_horizon;
+uniform float u_focal_length;
+uniform float u_repeat;
+
+out vec4 o_color;
+
+void main() {
+    vec2 screen = v_uv * u_viewport;
+    float below_horizon = screen.y - u_horizon;
+
+    if (below_horizon <= 0.0) {
+        discard;
+    }
+
+    float depth = u_height * u_focal_length / below_horizon;
+    float lateral = (screen.x - u_viewport.x / 2.0) * depth / u_focal_length;
+
+    vec2 right = vec2(-u_forward.y, u_forward.x);
+    vec2 uv = (u_position + u_forward * depth + right * lateral) / u_texture_size;
+
+    if (u_repeat > 0.5) {
+        uv = fract(uv);
+    } else if (any(lessThan(uv, vec2(0.0))) || any(greaterThan(uv, vec2(1.0)))) {
+        discard;
+    }
+
+    o_color = texture(u_texture, uv) * u_diffuse * v_color;
+}
\ No newline at end of file
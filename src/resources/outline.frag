@@ -0,0 +1,60 @@
+#version 150
+
+in vec2 v_uv;
+in vec4 v_color;
+
+uniform sampler2D u_texture;
+uniform vec4 u_diffuse;
+
+uniform vec2 u_texel_size;
+uniform vec4 u_region;
+uniform float u_thickness;
+uniform vec4 u_outline_color;
+uniform float u_silhouette;
+uniform float u_premultiplied;
+
+out vec4 o_color;
+
+vec4 sample_region(vec2 uv) {
+    if (any(lessThan(uv, u_region.xy)) || any(greaterThan(uv, u_region.zw))) {
+        return vec4(0.0);
+    }
+
+    vec4 texel = texture(u_texture, uv);
+
+    if (u_premultiplied < 0.5) {
+        texel.rgb *= texel.a;
+    }
+
+    return texel;
+}
+
+void main() {
+    vec4 sprite = sample_region(v_uv);
+    vec4 fill = vec4(u_outline_color.rgb * u_outline_color.a, u_outline_color.a);
+
+    int radius = int(ceil(u_thickness));
+    float coverage = 0.0;
+
+    for (int y = -radius; y <= radius; y++) {
+        for (int x = -radius; x <= radius; x++) {
+            vec2 offset = vec2(x, y);
+
+            if (length(offset) <= u_thickness) {
+                coverage = max(coverage, sample_region(v_uv + offset * u_texel_size).a);
+            }
+        }
+    }
+
+    if (u_silhouette > 0.5) {
+        sprite = fill * sprite.a;
+    }
+
+    vec4 color = sprite + fill * coverage * (1.0 - sprite.a);
+
+    if (u_premultiplied < 0.5 && color.a > 0.0) {
+        color.rgb /= color.a;
+    }
+
+    o_color = color * u_diffuse * v_color;
+}
@@ -0,0 +1,48 @@
+#version 150
+
+in vec2 v_uv;
+in vec4 v_color;
+
+uniform sampler2D u_texture;
+uniform vec4 u_diffuse;
+
+uniform sampler2D u_dither;
+uniform sampler2D u_palette;
+uniform int u_palette_size;
+uniform float u_levels;
+uniform float u_spread;
+
+out vec4 o_color;
+
+void main() {
+    vec3 color = texture(u_texture, v_uv).rgb;
+
+    ivec2 dither_size = textureSize(u_dither, 0);
+    float threshold = texelFetch(u_dither, ivec2(gl_FragCoord.xy) % dither_size, 0).r - 0.5;
+
+    color += threshold * u_spread;
+
+    if (u_palette_size == 0) {
+        color = floor(color * u_levels + 0.5) / u_levels;
+    } else {
+        int width = textureSize(u_palette, 0).x;
+
+        vec3 closest = vec3(0.0);
+        float closest_distance = 1000.0;
+
+        for (int i = 0; i < u_palette_size; i++) {
+            vec3 entry = texelFetch(u_palette, ivec2(i % width, i / width), 0).rgb;
+            vec3 delta = entry - color;
+            float distance = dot(delta, delta);
+
+            if (distance < closest_distance) {
+                closest = entry;
+                closest_distance = distance;
+            }
+        }
+
+        color = closest;
+    }
+
+    o_color = vec4(clamp(color, 0.0, 1.0), 1.0) * u_diffuse * v_color;
+}
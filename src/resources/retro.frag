@@ -0,0 +1,54 @@
+#version 150
+
+in vec2 v_uv;
+in vec4 v_color;
+
+uniform sampler2D u_texture;
+uniform vec4 u_diffuse;
+
+uniform vec2 u_resolution;
+uniform float u_scanlines;
+uniform float u_scanline_size;
+uniform float u_curvature;
+uniform float u_mask;
+uniform float u_color_bleed;
+uniform vec4 u_border_color;
+
+out vec4 o_color;
+
+vec2 curve(vec2 uv) {
+    uv = uv * 2.0 - 1.0;
+
+    vec2 offset = abs(uv.yx) * u_curvature;
+    uv += uv * offset * offset;
+
+    return uv * 0.5 + 0.5;
+}
+
+void main() {
+    vec2 uv = curve(v_uv);
+
+    if (uv.x < 0.0 || uv.x > 1.0 || uv.y < 0.0 || uv.y > 1.0) {
+        o_color = u_border_color;
+        return;
+    }
+
+    // NTSC-style color bleed: the chroma channels are smeared horizontally.
+    vec2 bleed = vec2(u_color_bleed / u_resolution.x, 0.0);
+
+    vec3 color = vec3(
+        texture(u_texture, uv - bleed).r,
+        texture(u_texture, uv).g,
+        texture(u_texture, uv + bleed).b
+    );
+
+    float line = sin(uv.y * u_resolution.y / u_scanline_size * 6.2831853) * 0.5 + 0.5;
+    color *= 1.0 - u_scanlines * line;
+
+    // Aperture grille, with one column each for the red, green and blue phosphors.
+    vec3 mask = vec3(1.0 - u_mask);
+    mask[int(mod(gl_FragCoord.x, 3.0))] = 1.0;
+    color *= mask;
+
+    o_color = vec4(color, 1.0) * u_diffuse * v_color;
+}
@@ -2,6 +2,7 @@
 
 in vec2 v_uv;
 in vec4 v_color;
+in vec4 v_overlay;
 
 uniform sampler2D u_texture;
 uniform vec4 u_diffuse;
@@ -9,5 +10,8 @@ uniform vec4 u_diffuse;
 out vec4 o_color;
 
 void main() {
-    o_color = texture(u_texture, v_uv) * u_diffuse * v_color;
+    vec4 texel = texture(u_texture, v_uv);
+    texel.rgb = mix(texel.rgb, v_overlay.rgb, v_overlay.a);
+
+    o_color = texel * u_diffuse * v_color;
 }
\ No newline at end of file
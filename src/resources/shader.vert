@@ -3,15 +3,21 @@
 in vec2 a_position;
 in vec2 a_uv;
 in vec4 a_color;
+in vec4 a_overlay;
+in vec4 a_params;
 
 uniform mat4 u_projection;
 
 out vec2 v_uv;
 out vec4 v_color;
+out vec4 v_overlay;
+out vec4 v_params;
 
 void main() {
     v_color = a_color;
     v_uv = a_uv;
+    v_overlay = a_overlay;
+    v_params = a_params;
 
     gl_Position = u_projection * vec4(a_position, 0.0, 1.0);
 }
\ No newline at end of file
@@ -0,0 +1,37 @@
+#version 150
+
+in vec2 v_uv;
+in vec4 v_color;
+
+uniform sampler2D u_texture;
+uniform vec4 u_diffuse;
+
+out vec4 o_color;
+
+vec4 fetch(ivec2 position, ivec2 size) {
+    return texelFetch(u_texture, clamp(position, ivec2(0), size - 1), 0);
+}
+
+void main() {
+    ivec2 size = textureSize(u_texture, 0);
+    vec2 texel = v_uv * vec2(size);
+
+    // Scale each texel up by the largest whole number that fits, and then only blend
+    // across the fractional remainder at the edges.
+    vec2 scale = max(floor(1.0 / fwidth(texel)), 1.0);
+
+    vec2 offset = fract(texel) - 0.5;
+    vec2 region = 0.5 - 0.5 / scale;
+    vec2 sharpened = floor(texel) + (offset - clamp(offset, -region, region)) * scale + 0.5;
+
+    // Bilinear filtering is done by hand, so that it works regardless of the
+    // texture's filter mode.
+    vec2 position = sharpened - 0.5;
+    ivec2 base = ivec2(floor(position));
+    vec2 t = fract(position);
+
+    vec4 top = mix(fetch(base, size), fetch(base + ivec2(1, 0), size), t.x);
+    vec4 bottom = mix(fetch(base + ivec2(0, 1), size), fetch(base + ivec2(1, 1), size), t.x);
+
+    o_color = mix(top, bottom, t.y) * u_diffuse * v_color;
+}
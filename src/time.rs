@@ -4,6 +4,7 @@ use std::collections::VecDeque;
 
 use std::time::Duration;
 
+use crate::window;
 use crate::Context;
 
 /// The different timestep modes that a game can have.
@@ -46,6 +47,89 @@ pub enum Timestep {
     Variable,
 }
 
+/// Statistics about the game's recent frame times.
+///
+/// These are calculated from the last 200 frames, and can be retrieved via
+/// [`get_frame_stats`].
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct FrameStats {
+    /// The number of frames that the statistics were calculated from.
+    pub frame_count: usize,
+
+    /// The shortest frame time.
+    pub min: Duration,
+
+    /// The longest frame time.
+    pub max: Duration,
+
+    /// The mean frame time.
+    pub average: Duration,
+
+    /// The median frame time.
+    ///
+    /// When vsync is enabled and the game is keeping up with the display, this will
+    /// be very close to the monitor's refresh interval.
+    pub median: Duration,
+
+    /// The frame time that 99% of frames were faster than.
+    ///
+    /// This is a good indicator of how bad the worst stutters are, without being
+    /// thrown off by a single outlier.
+    pub percentile_99: Duration,
+
+    /// The number of frames that missed vsync (see [`did_miss_vsync`]).
+    pub missed_vsync: usize,
+}
+
+/// How long each stage of a frame took, as returned by [`get_stage_timings`].
+///
+/// These timings are measured on the CPU, so time that the GPU spends rendering will
+/// generally show up in [`present`](Self::present), as that is where the game waits for
+/// the GPU to catch up (and for vsync, if it is enabled).
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[non_exhaustive]
+pub struct StageTimings {
+    /// The time spent processing window and input events.
+    pub events: Duration,
+
+    /// The time spent in [`State::update`](crate::State::update). When using a fixed
+    /// timestep, this is the total time across all of the updates that ran in the frame.
+    pub update: Duration,
+
+    /// The time spent in [`State::draw`](crate::State::draw).
+    pub draw: Duration,
+
+    /// The time spent flushing the final batch of draws and presenting the frame to
+    /// the screen.
+    pub present: Duration,
+
+    /// The remainder of the frame, which was not spent in any of the other stages. This
+    /// includes the short sleep at the end of each frame, and any time spent outside of
+    /// the game loop (e.g. while the window was being dragged).
+    pub idle: Duration,
+
+    /// The total time from the start of the frame to the start of the next one.
+    pub total: Duration,
+}
+
+impl StageTimings {
+    fn busy(&self) -> Duration {
+        self.events + self.update + self.draw + self.present
+    }
+}
+
+/// Frames that take this much longer than the median are considered to have missed vsync.
+///
+/// This is 1.5 rather than 2.0 so that frames which only narrowly missed a refresh
+/// (and so were delayed by a full interval) are caught, without normal jitter
+/// being counted.
+const MISSED_VSYNC_THRESHOLD: f64 = 1.5;
+
+/// Frame times that are within this distance of a whole number of refresh intervals
+/// are snapped to it, when refresh rate snapping is enabled.
+const REFRESH_SNAP_TOLERANCE: Duration = Duration::from_micros(200);
+
 pub(crate) struct FpsTracker {
     buffer: VecDeque<f64>,
 }
@@ -68,6 +152,72 @@ impl FpsTracker {
     fn get_fps(&self) -> f64 {
         1.0 / (self.buffer.iter().sum::<f64>() / self.buffer.len() as f64)
     }
+
+    fn sorted(&self) -> Vec<f64> {
+        let mut sorted: Vec<f64> = self.buffer.iter().copied().collect();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        sorted
+    }
+
+    fn missed_vsync_limit(sorted: &[f64]) -> f64 {
+        percentile(sorted, 0.5) * MISSED_VSYNC_THRESHOLD
+    }
+
+    fn did_miss_vsync(&self) -> bool {
+        match self.buffer.back() {
+            Some(last) => *last > FpsTracker::missed_vsync_limit(&self.sorted()),
+            None => false,
+        }
+    }
+
+    fn get_stats(&self) -> FrameStats {
+        let sorted = self.sorted();
+        let limit = FpsTracker::missed_vsync_limit(&sorted);
+
+        let average = if sorted.is_empty() {
+            0.0
+        } else {
+            sorted.iter().sum::<f64>() / sorted.len() as f64
+        };
+
+        FrameStats {
+            frame_count: sorted.len(),
+            min: Duration::from_secs_f64(sorted.first().copied().unwrap_or(0.0)),
+            max: Duration::from_secs_f64(sorted.last().copied().unwrap_or(0.0)),
+            average: Duration::from_secs_f64(average),
+            median: Duration::from_secs_f64(percentile(&sorted, 0.5)),
+            percentile_99: Duration::from_secs_f64(percentile(&sorted, 0.99)),
+            missed_vsync: sorted.iter().filter(|t| **t > limit).count(),
+        }
+    }
+
+    fn get_histogram(&self, bucket_width: Duration, bucket_count: usize) -> Vec<usize> {
+        let mut buckets = vec![0; bucket_count];
+
+        if bucket_count == 0 || bucket_width == Duration::from_secs(0) {
+            return buckets;
+        }
+
+        let bucket_width = bucket_width.as_secs_f64();
+
+        for frame_time in &self.buffer {
+            let bucket = ((frame_time / bucket_width) as usize).min(bucket_count - 1);
+            buckets[bucket] += 1;
+        }
+
+        buckets
+    }
+}
+
+/// Returns the value at the given percentile (from 0.0 to 1.0) of a sorted slice,
+/// using the nearest-rank method.
+fn percentile(sorted: &[f64], percentile: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+
+    let rank = (percentile * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
 }
 
 pub(crate) struct TimeContext {
@@ -76,6 +226,9 @@ pub(crate) struct TimeContext {
     pub(crate) tick_rate: Option<Duration>,
     pub(crate) delta_time: Duration,
     pub(crate) accumulator: Duration,
+    pub(crate) stage_timings: StageTimings,
+    pub(crate) current_stages: StageTimings,
+    pub(crate) refresh_rate_snapping: bool,
 }
 
 impl TimeContext {
@@ -96,8 +249,51 @@ impl TimeContext {
             tick_rate,
             delta_time: Duration::from_secs(0),
             accumulator: Duration::from_secs(0),
+            stage_timings: StageTimings::default(),
+            current_stages: StageTimings::default(),
+            refresh_rate_snapping: false,
+        }
+    }
+}
+
+/// Finishes the timings for the previous frame, now that its total length is known.
+pub(crate) fn finish_stage_timings(ctx: &mut Context, frame_time: Duration) {
+    let mut timings = std::mem::take(&mut ctx.time.current_stages);
+
+    timings.idle = frame_time.saturating_sub(timings.busy());
+    timings.total = frame_time;
+
+    ctx.time.stage_timings = timings;
+}
+
+/// Returns the amount of time that the game loop should advance by for a frame, taking
+/// refresh rate snapping into account.
+pub(crate) fn get_step_time(ctx: &Context, frame_time: Duration) -> Duration {
+    if !ctx.time.refresh_rate_snapping || !window::is_vsync_enabled(ctx) {
+        return frame_time;
+    }
+
+    match window::get_current_monitor_refresh_rate(ctx) {
+        Ok(rate) => {
+            snap_to_refresh_interval(frame_time, Duration::from_secs_f64(1.0 / rate as f64))
+        }
+        Err(_) => frame_time,
+    }
+}
+
+fn snap_to_refresh_interval(frame_time: Duration, interval: Duration) -> Duration {
+    let intervals = (frame_time.as_secs_f64() / interval.as_secs_f64()).round();
+
+    if intervals >= 1.0 {
+        let snapped = interval.mul_f64(intervals);
+        let difference = (snapped.as_secs_f64() - frame_time.as_secs_f64()).abs();
+
+        if difference <= REFRESH_SNAP_TOLERANCE.as_secs_f64() {
+            return snapped;
         }
     }
+
+    frame_time
 }
 
 pub(crate) fn reset(ctx: &mut Context) {
@@ -190,3 +386,161 @@ pub fn set_timestep(ctx: &mut Context, timestep: Timestep) {
 pub fn get_fps(ctx: &Context) -> f64 {
     ctx.time.fps_tracker.get_fps()
 }
+
+/// Returns statistics about the game's frame times, calculated over the last 200 frames.
+///
+/// This can be used to detect sustained performance problems at runtime - for example,
+/// if [`FrameStats::missed_vsync`] stays high, the game could automatically lower its
+/// quality settings.
+pub fn get_frame_stats(ctx: &Context) -> FrameStats {
+    ctx.time.fps_tracker.get_stats()
+}
+
+/// Returns a histogram of the game's frame times, calculated over the last 200 frames.
+///
+/// Each element of the returned `Vec` is the number of frames that took between
+/// `i * bucket_width` and `(i + 1) * bucket_width`. Frames that took longer than the
+/// range covered by the histogram are counted in the last bucket.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use std::time::Duration;
+/// # use tetra::Context;
+/// # use tetra::time;
+/// # fn example(ctx: &Context) {
+/// // One bucket per millisecond, with everything over 50ms in the last bucket.
+/// let histogram = time::get_frame_histogram(ctx, Duration::from_millis(1), 51);
+/// # }
+/// ```
+pub fn get_frame_histogram(
+    ctx: &Context,
+    bucket_width: Duration,
+    bucket_count: usize,
+) -> Vec<usize> {
+    ctx.time
+        .fps_tracker
+        .get_histogram(bucket_width, bucket_count)
+}
+
+/// Returns whether or not the most recent frame missed vsync.
+///
+/// A frame is considered to have missed vsync if it took more than 1.5 times as long
+/// as the median frame time over the last 200 frames. When vsync is enabled, the median
+/// will usually match the monitor's refresh interval, so this indicates that a frame was
+/// presented late and the player probably saw a stutter.
+///
+/// A single missed frame is not usually worth reacting to - for sustained drops, check
+/// [`FrameStats::missed_vsync`] via [`get_frame_stats`].
+pub fn did_miss_vsync(ctx: &Context) -> bool {
+    ctx.time.fps_tracker.did_miss_vsync()
+}
+
+/// Returns whether refresh rate snapping is enabled.
+pub fn is_refresh_rate_snapping_enabled(ctx: &Context) -> bool {
+    ctx.time.refresh_rate_snapping
+}
+
+/// Sets whether the game loop should snap frame times to the monitor's refresh rate.
+///
+/// Even with vsync enabled, the measured time between frames will jitter slightly
+/// around the refresh interval of the display (e.g. 16.5ms, then 16.8ms, on a 60hz
+/// monitor). With a fixed timestep, this jitter can cause some frames to run no updates
+/// and others to run two, which shows up as stutter. When snapping is enabled, frame
+/// times that are very close to a whole number of refresh intervals are rounded to it,
+/// so updates stay locked to the display.
+///
+/// To run the simulation at exactly the display rate, combine this with a fixed timestep
+/// that matches the refresh rate of the monitor:
+///
+/// ```no_run
+/// # use tetra::Context;
+/// # use tetra::time::{self, Timestep};
+/// # use tetra::window;
+/// # fn example(ctx: &mut Context) -> tetra::Result {
+/// let refresh_rate = window::get_current_monitor_refresh_rate(ctx)?;
+///
+/// time::set_timestep(ctx, Timestep::Fixed(refresh_rate as f64));
+/// time::set_refresh_rate_snapping(ctx, true);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Snapping only takes effect while vsync is enabled. The measured frame times reported
+/// by [`get_fps`] and [`get_frame_stats`] are not affected.
+///
+/// Defaults to `false`.
+pub fn set_refresh_rate_snapping(ctx: &mut Context, enabled: bool) {
+    ctx.time.refresh_rate_snapping = enabled;
+}
+
+/// Returns how long each stage of the most recently completed frame took.
+///
+/// This can be used to tell at a glance where the game is spending its time - for
+/// example, a long [`update`](StageTimings::update) means that the game logic is the
+/// bottleneck, while a long [`present`](StageTimings::present) usually means that the
+/// game is waiting on the GPU or on vsync.
+///
+/// Note that as a frame's total length is only known once the next frame starts, the
+/// timings will lag one frame behind.
+pub fn get_stage_timings(ctx: &Context) -> StageTimings {
+    ctx.time.stage_timings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tracker(frame_times_ms: &[u64]) -> FpsTracker {
+        let mut tracker = FpsTracker::new();
+
+        for ms in frame_times_ms {
+            tracker.push(Duration::from_millis(*ms));
+        }
+
+        tracker
+    }
+
+    #[test]
+    fn frame_stats() {
+        let tracker = tracker(&[16, 17, 16, 33, 16, 17, 16]);
+        let stats = tracker.get_stats();
+
+        assert_eq!(stats.frame_count, 7);
+        assert_eq!(stats.min, Duration::from_millis(16));
+        assert_eq!(stats.max, Duration::from_millis(33));
+        assert_eq!(stats.median, Duration::from_millis(16));
+        assert_eq!(stats.missed_vsync, 1);
+
+        assert!(!tracker.did_miss_vsync());
+        assert!(self::tracker(&[16, 16, 16, 33]).did_miss_vsync());
+    }
+
+    #[test]
+    fn frame_histogram() {
+        let tracker = tracker(&[1, 2, 2, 3, 100]);
+
+        assert_eq!(
+            tracker.get_histogram(Duration::from_millis(1), 4),
+            [0, 1, 2, 2]
+        );
+    }
+
+    #[test]
+    fn refresh_interval_snapping() {
+        let interval = Duration::from_micros(16_667);
+
+        assert_eq!(
+            snap_to_refresh_interval(Duration::from_micros(16_550), interval),
+            interval
+        );
+        assert_eq!(
+            snap_to_refresh_interval(Duration::from_micros(33_400), interval),
+            interval * 2
+        );
+        assert_eq!(
+            snap_to_refresh_interval(Duration::from_micros(20_000), interval),
+            Duration::from_micros(20_000)
+        );
+    }
+}
@@ -0,0 +1,12 @@
+//! Simple building blocks for in-game user interfaces.
+//!
+//! Tetra does not try to provide a full UI framework, but some parts of a UI are fiddly
+//! to get right and are needed by almost every game (such as text entry). This module
+//! provides standalone components for these, which can be used alongside whatever
+//! approach you are taking for the rest of your UI.
+
+mod focus;
+mod text_box;
+
+pub use focus::*;
+pub use text_box::*;
@@ -0,0 +1,416 @@
+use crate::graphics::Rectangle;
+use crate::input::{GamepadButton, Key};
+use crate::math::Vec2;
+use crate::Event;
+
+/// A direction that focus can be moved in.
+///
+/// # Serde
+///
+/// Serialization and deserialization of this type (via [Serde](https://serde.rs/))
+/// can be enabled via the `serde_support` feature.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde_support",
+    derive(serde::Serialize, serde::Deserialize)
+)]
+#[allow(missing_docs)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Direction {
+    /// Returns the direction that corresponds to an arrow key, if there is one.
+    pub fn from_key(key: Key) -> Option<Direction> {
+        match key {
+            Key::Up => Some(Direction::Up),
+            Key::Down => Some(Direction::Down),
+            Key::Left => Some(Direction::Left),
+            Key::Right => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// Returns the direction that corresponds to a D-pad button, if there is one.
+    pub fn from_gamepad_button(button: GamepadButton) -> Option<Direction> {
+        match button {
+            GamepadButton::Up => Some(Direction::Up),
+            GamepadButton::Down => Some(Direction::Down),
+            GamepadButton::Left => Some(Direction::Left),
+            GamepadButton::Right => Some(Direction::Right),
+            _ => None,
+        }
+    }
+
+    /// Returns the direction that an analog stick is pointing in, if it has been moved
+    /// further than the given deadzone.
+    pub fn from_stick(position: Vec2<f32>, deadzone: f32) -> Option<Direction> {
+        if position.magnitude() <= deadzone {
+            None
+        } else if position.x.abs() > position.y.abs() {
+            Some(if position.x > 0.0 {
+                Direction::Right
+            } else {
+                Direction::Left
+            })
+        } else {
+            Some(if position.y > 0.0 {
+                Direction::Down
+            } else {
+                Direction::Up
+            })
+        }
+    }
+
+    /// Splits a vector into the component along this direction, and the component
+    /// perpendicular to it.
+    fn split(self, delta: Vec2<f32>) -> (f32, f32) {
+        match self {
+            Direction::Up => (-delta.y, delta.x),
+            Direction::Down => (delta.y, delta.x),
+            Direction::Left => (-delta.x, delta.y),
+            Direction::Right => (delta.x, delta.y),
+        }
+    }
+
+    /// Returns whether `to` is in this direction from `from`.
+    ///
+    /// The leading edge of `to` must be past the leading edge of `from`, so that items which
+    /// overlap or contain the current item are only chosen if they extend past it.
+    fn is_towards(self, from: &Rectangle, to: &Rectangle) -> bool {
+        match self {
+            Direction::Up => {
+                (from.bottom() > to.bottom() || from.y >= to.bottom()) && from.y > to.y
+            }
+            Direction::Down => {
+                (from.y < to.y || from.bottom() <= to.y) && from.bottom() < to.bottom()
+            }
+            Direction::Left => (from.right() > to.right() || from.x >= to.right()) && from.x > to.x,
+            Direction::Right => {
+                (from.x < to.x || from.right() <= to.x) && from.right() < to.right()
+            }
+        }
+    }
+
+    fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+        }
+    }
+
+    /// Returns the gap between two rectangles on the axis perpendicular to this direction,
+    /// or zero if they overlap on that axis.
+    fn perpendicular_gap(self, a: &Rectangle, b: &Rectangle) -> f32 {
+        match self {
+            Direction::Up | Direction::Down => (a.x - b.right()).max(b.x - a.right()).max(0.0),
+            Direction::Left | Direction::Right => (a.y - b.bottom()).max(b.y - a.bottom()).max(0.0),
+        }
+    }
+}
+
+/// Directional focus navigation for menus.
+///
+/// Controller-friendly menus need to decide which item to move to when the player presses
+/// a direction, which usually means writing a table of neighbours for every menu by hand.
+/// `FocusNavigator` works this out from the on-screen bounds of each item instead,
+/// picking the nearest item in the given direction (and preferring items that are lined up
+/// with the current one).
+///
+/// Items are identified by their index, in the order that they were added.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::{Context, Event};
+/// # use tetra::graphics::Rectangle;
+/// # use tetra::ui::FocusNavigator;
+/// # fn example(ctx: &mut Context, event: Event) {
+/// let mut menu = FocusNavigator::new();
+///
+/// menu.push(Rectangle::new(16.0, 16.0, 128.0, 24.0)); // New Game
+/// menu.push(Rectangle::new(16.0, 48.0, 128.0, 24.0)); // Options
+/// menu.push(Rectangle::new(16.0, 80.0, 128.0, 24.0)); // Quit
+/// menu.set_focus(Some(0));
+///
+/// // In `State::event`:
+/// if menu.event(&event) {
+///     // Play a sound, update the highlight, etc.
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct FocusNavigator {
+    items: Vec<Rectangle>,
+    focus: Option<usize>,
+    wrapping: bool,
+}
+
+impl FocusNavigator {
+    /// Creates a new navigator with no items.
+    pub fn new() -> FocusNavigator {
+        FocusNavigator::default()
+    }
+
+    /// Adds an item with the given bounds, returning its index.
+    pub fn push(&mut self, bounds: Rectangle) -> usize {
+        self.items.push(bounds);
+        self.items.len() - 1
+    }
+
+    /// Replaces all of the items.
+    ///
+    /// The focused index will be kept if it is still valid, so this can be called every
+    /// frame for layouts that move around.
+    pub fn set_items<I>(&mut self, items: I)
+    where
+        I: IntoIterator<Item = Rectangle>,
+    {
+        self.items.clear();
+        self.items.extend(items);
+
+        if matches!(self.focus, Some(focus) if focus >= self.items.len()) {
+            self.focus = None;
+        }
+    }
+
+    /// Removes all of the items, and clears the focus.
+    pub fn clear(&mut self) {
+        self.items.clear();
+        self.focus = None;
+    }
+
+    /// Returns the bounds of the item with the given index.
+    pub fn get(&self, index: usize) -> Option<Rectangle> {
+        self.items.get(index).copied()
+    }
+
+    /// Sets the bounds of the item with the given index.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is out of bounds.
+    pub fn set(&mut self, index: usize, bounds: Rectangle) {
+        self.items[index] = bounds;
+    }
+
+    /// Returns the number of items.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if there are no items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the index of the focused item, if there is one.
+    pub fn focus(&self) -> Option<usize> {
+        self.focus
+    }
+
+    /// Returns the bounds of the focused item, if there is one.
+    pub fn focused_bounds(&self) -> Option<Rectangle> {
+        self.focus.and_then(|focus| self.get(focus))
+    }
+
+    /// Sets the focused item.
+    ///
+    /// Indexes that are out of bounds will clear the focus.
+    pub fn set_focus(&mut self, focus: Option<usize>) {
+        self.focus = focus.filter(|&focus| focus < self.items.len());
+    }
+
+    /// Returns whether navigating past the edge of the menu wraps around to the other side.
+    pub fn is_wrapping(&self) -> bool {
+        self.wrapping
+    }
+
+    /// Sets whether navigating past the edge of the menu wraps around to the other side.
+    ///
+    /// Defaults to `false`.
+    pub fn set_wrapping(&mut self, wrapping: bool) {
+        self.wrapping = wrapping;
+    }
+
+    /// Moves the focus in the given direction, returning `true` if it changed.
+    ///
+    /// If nothing is focused, the first item will be focused.
+    pub fn navigate(&mut self, direction: Direction) -> bool {
+        let next = match self.focus {
+            Some(focus) => self.find(focus, direction),
+            None if !self.items.is_empty() => Some(0),
+            None => None,
+        };
+
+        match next {
+            Some(next) if Some(next) != self.focus => {
+                self.focus = Some(next);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Handles an event, moving the focus if the arrow keys or the D-pad were pressed.
+    ///
+    /// Returns `true` if the focus changed.
+    pub fn event(&mut self, event: &Event) -> bool {
+        let direction = match event {
+            Event::KeyPressed { key } => Direction::from_key(*key),
+            Event::GamepadButtonPressed { button, .. } => Direction::from_gamepad_button(*button),
+            _ => None,
+        };
+
+        match direction {
+            Some(direction) => self.navigate(direction),
+            None => false,
+        }
+    }
+
+    /// Returns the index of the item that would be focused by moving in the given direction
+    /// from the given item, without changing the focus.
+    pub fn find(&self, from: usize, direction: Direction) -> Option<usize> {
+        let current = self.items.get(from)?;
+
+        let candidates = self
+            .items
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| *i != from)
+            .map(|(i, item)| {
+                let (along, across) = direction.split(item.center() - current.center());
+                let gap = direction.perpendicular_gap(current, item);
+
+                Candidate {
+                    index: i,
+                    along,
+                    across: across.abs(),
+                    gap,
+                    forwards: direction.is_towards(current, item),
+                    backwards: direction.opposite().is_towards(current, item),
+                }
+            });
+
+        // Items that are lined up with the current one are strongly preferred, and then
+        // the closest item wins.
+        let nearest = candidates
+            .clone()
+            .filter(|c| c.forwards)
+            .min_by(|a, b| a.score().total_cmp(&b.score()))
+            .map(|c| c.index);
+
+        if nearest.is_some() || !self.wrapping {
+            return nearest;
+        }
+
+        // When wrapping, the furthest item in the opposite direction is chosen instead.
+        candidates
+            .filter(|c| c.backwards)
+            .min_by(|a, b| {
+                let key = |c: &Candidate| c.gap * 2.0 + c.across * 0.5;
+                key(a)
+                    .total_cmp(&key(b))
+                    .then_with(|| a.along.total_cmp(&b.along))
+            })
+            .map(|c| c.index)
+    }
+}
+
+/// An item that could be navigated to, measured relative to the current item.
+#[derive(Clone, Copy)]
+struct Candidate {
+    index: usize,
+
+    /// The distance between the centers of the items, in the direction of travel.
+    along: f32,
+
+    /// The distance between the centers of the items, perpendicular to the direction
+    /// of travel.
+    across: f32,
+
+    /// The gap between the edges of the items, perpendicular to the direction of travel.
+    gap: f32,
+
+    forwards: bool,
+    backwards: bool,
+}
+
+impl Candidate {
+    fn score(&self) -> f32 {
+        self.along.abs() + self.gap * 2.0 + self.across * 0.5
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid() -> FocusNavigator {
+        let mut navigator = FocusNavigator::new();
+
+        for y in 0..3 {
+            for x in 0..3 {
+                navigator.push(Rectangle::new(x as f32 * 40.0, y as f32 * 40.0, 32.0, 32.0));
+            }
+        }
+
+        navigator
+    }
+
+    #[test]
+    fn grid_navigation() {
+        let navigator = grid();
+
+        assert_eq!(navigator.find(4, Direction::Up), Some(1));
+        assert_eq!(navigator.find(4, Direction::Down), Some(7));
+        assert_eq!(navigator.find(4, Direction::Left), Some(3));
+        assert_eq!(navigator.find(4, Direction::Right), Some(5));
+        assert_eq!(navigator.find(2, Direction::Right), None);
+    }
+
+    #[test]
+    fn wrapping() {
+        let mut navigator = grid();
+        navigator.set_wrapping(true);
+
+        assert_eq!(navigator.find(2, Direction::Right), Some(0));
+        assert_eq!(navigator.find(7, Direction::Down), Some(1));
+    }
+
+    #[test]
+    fn prefers_aligned_items() {
+        let mut navigator = FocusNavigator::new();
+
+        navigator.push(Rectangle::new(0.0, 0.0, 200.0, 20.0));
+        navigator.push(Rectangle::new(30.0, 40.0, 20.0, 20.0));
+        navigator.push(Rectangle::new(250.0, 25.0, 20.0, 20.0));
+
+        assert_eq!(navigator.find(0, Direction::Down), Some(1));
+        assert_eq!(navigator.find(1, Direction::Up), Some(0));
+        assert_eq!(navigator.find(1, Direction::Right), Some(2));
+    }
+
+    #[test]
+    fn nan_bounds_do_not_panic() {
+        let mut navigator = grid();
+        navigator.set_wrapping(true);
+        navigator.push(Rectangle::new(f32::NAN, 40.0, 32.0, f32::NAN));
+
+        for from in 0..navigator.len() {
+            for direction in [
+                Direction::Up,
+                Direction::Down,
+                Direction::Left,
+                Direction::Right,
+            ] {
+                navigator.find(from, direction);
+            }
+        }
+    }
+}
@@ -0,0 +1,754 @@
+use std::ops::Range;
+use std::time::Duration;
+
+use crate::graphics::text::{Font, Text};
+use crate::graphics::{self, Color, DrawParams, Rectangle};
+use crate::input::{self, Key, KeyModifier, MouseButton};
+use crate::math::Vec2;
+use crate::time;
+use crate::window;
+use crate::{Context, Event};
+
+const BLINK_INTERVAL: Duration = Duration::from_millis(500);
+const CARET_WIDTH: f32 = 1.0;
+
+/// A single-line text entry field.
+///
+/// `TextBox` handles the details of text entry that are easy to get wrong:
+///
+/// * Text is inserted at a caret, which can be moved with the arrow keys, Home and End (or
+///   by clicking), and blinks while the box is focused. Holding Ctrl moves by whole words.
+/// * Text can be selected by holding Shift while moving the caret, by dragging with the
+///   mouse, or by pressing Ctrl+A.
+/// * Ctrl+C, Ctrl+X and Ctrl+V copy, cut and paste via the system clipboard.
+/// * Text that is being composed via an input method editor (IME) is displayed underlined
+///   at the caret, and the IME candidate window is positioned next to the box.
+/// * When the content is wider than the box, it scrolls horizontally to keep the caret
+///   visible.
+///
+/// Input is passed to the text box via [`event`](Self::event), which should be called from
+/// your [`State::event`](crate::State::event) implementation. Clicking on the box focuses
+/// it, and clicking anywhere else removes the focus. While the box is focused, text input
+/// is [started](input::start_text_input); it is stopped again when the box loses focus.
+///
+/// The bounds of the box are in the same co-ordinate space as the
+/// [mouse position](input::get_mouse_position), so the box should be drawn without a
+/// transform matrix. The content is clipped to the bounds via the
+/// [scissor rectangle](graphics::set_scissor), which will be reset after drawing.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::{Context, Event, State};
+/// # use tetra::graphics::Rectangle;
+/// # use tetra::graphics::text::Font;
+/// # use tetra::input::Key;
+/// # use tetra::ui::TextBox;
+/// struct GameState {
+///     name: TextBox,
+/// }
+///
+/// impl GameState {
+///     fn new(ctx: &mut Context) -> tetra::Result<GameState> {
+///         let font = Font::vector(ctx, "./fonts/font.ttf", 16.0)?;
+///         let mut name = TextBox::new(font, Rectangle::new(16.0, 16.0, 200.0, 24.0));
+///
+///         name.set_max_length(Some(16));
+///         name.set_focused(ctx, true);
+///
+///         Ok(GameState { name })
+///     }
+/// }
+///
+/// impl State for GameState {
+///     fn update(&mut self, ctx: &mut Context) -> tetra::Result {
+///         self.name.update(ctx);
+///         Ok(())
+///     }
+///
+///     fn draw(&mut self, ctx: &mut Context) -> tetra::Result {
+///         self.name.draw(ctx);
+///         Ok(())
+///     }
+///
+///     fn event(&mut self, ctx: &mut Context, event: Event) -> tetra::Result {
+///         self.name.event(ctx, &event);
+///
+///         if let Event::KeyPressed { key: Key::Enter } = event {
+///             println!("Hello, {}!", self.name.content());
+///         }
+///
+///         Ok(())
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct TextBox {
+    content: String,
+    text: Text,
+    text_dirty: bool,
+    composition: Option<Composition>,
+
+    bounds: Rectangle,
+    scroll: f32,
+
+    caret: usize,
+    anchor: usize,
+    focused: bool,
+    dragging: bool,
+    blink_timer: Duration,
+
+    max_length: Option<usize>,
+    text_color: Color,
+    selection_color: Color,
+    caret_color: Color,
+}
+
+#[derive(Debug, Clone)]
+struct Composition {
+    text: String,
+    cursor: usize,
+}
+
+impl TextBox {
+    /// Creates a new, empty text box.
+    pub fn new(font: Font, bounds: Rectangle) -> TextBox {
+        TextBox {
+            content: String::new(),
+            text: Text::new("", font),
+            text_dirty: false,
+            composition: None,
+
+            bounds,
+            scroll: 0.0,
+
+            caret: 0,
+            anchor: 0,
+            focused: false,
+            dragging: false,
+            blink_timer: Duration::from_secs(0),
+
+            max_length: None,
+            text_color: Color::WHITE,
+            selection_color: Color::rgba(0.2, 0.4, 0.9, 0.6),
+            caret_color: Color::WHITE,
+        }
+    }
+
+    /// Returns the content of the text box.
+    pub fn content(&self) -> &str {
+        &self.content
+    }
+
+    /// Sets the content of the text box, and moves the caret to the end.
+    ///
+    /// Line breaks and other control characters will be removed, and the content will be
+    /// cut off if it is longer than the [maximum length](Self::set_max_length).
+    pub fn set_content(&mut self, content: &str) {
+        self.content.clear();
+        self.caret = 0;
+        self.anchor = 0;
+        self.insert(content);
+    }
+
+    /// Returns the font of the text box.
+    pub fn font(&self) -> &Font {
+        self.text.font()
+    }
+
+    /// Sets the font of the text box.
+    pub fn set_font(&mut self, font: Font) {
+        self.text.set_font(font);
+    }
+
+    /// Returns the bounds of the text box.
+    pub fn bounds(&self) -> Rectangle {
+        self.bounds
+    }
+
+    /// Sets the bounds of the text box.
+    pub fn set_bounds(&mut self, bounds: Rectangle) {
+        self.bounds = bounds;
+    }
+
+    /// Returns the maximum number of characters that the text box can hold.
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Sets the maximum number of characters that the text box can hold, or removes the
+    /// limit if `None` is passed.
+    ///
+    /// If the current content is longer than the new limit, it will be cut off.
+    ///
+    /// Defaults to `None`.
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+
+        if let Some(max_length) = max_length {
+            if let Some((index, _)) = self.content.char_indices().nth(max_length) {
+                self.content.truncate(index);
+                self.caret = self.caret.min(index);
+                self.anchor = self.anchor.min(index);
+                self.text_dirty = true;
+            }
+        }
+    }
+
+    /// Returns the color of the text.
+    pub fn text_color(&self) -> Color {
+        self.text_color
+    }
+
+    /// Sets the color of the text.
+    ///
+    /// Defaults to [`Color::WHITE`].
+    pub fn set_text_color(&mut self, color: Color) {
+        self.text_color = color;
+    }
+
+    /// Returns the color of the selection highlight.
+    pub fn selection_color(&self) -> Color {
+        self.selection_color
+    }
+
+    /// Sets the color of the selection highlight.
+    ///
+    /// Defaults to a translucent blue.
+    pub fn set_selection_color(&mut self, color: Color) {
+        self.selection_color = color;
+    }
+
+    /// Returns the color of the caret.
+    pub fn caret_color(&self) -> Color {
+        self.caret_color
+    }
+
+    /// Sets the color of the caret.
+    ///
+    /// Defaults to [`Color::WHITE`].
+    pub fn set_caret_color(&mut self, color: Color) {
+        self.caret_color = color;
+    }
+
+    /// Returns a node describing the text box, for use in an
+    /// [`AccessibilityTree`](crate::integrations::accessibility::AccessibilityTree).
+    ///
+    /// The label should describe what the player is expected to enter (e.g. "Name").
+    #[cfg(feature = "accessibility")]
+    pub fn access_node<S>(&self, label: S) -> crate::integrations::accessibility::AccessNode
+    where
+        S: Into<String>,
+    {
+        use crate::integrations::accessibility::{accesskit::Role, AccessNode};
+
+        AccessNode::new(Role::TextInput, label, self.bounds).value(self.content.clone())
+    }
+
+    /// Returns whether the text box is focused, and so is receiving text input.
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+
+    /// Sets whether the text box is focused.
+    ///
+    /// Focusing the text box starts text input, and removing the focus stops it.
+    pub fn set_focused(&mut self, ctx: &mut Context, focused: bool) {
+        if focused == self.focused {
+            return;
+        }
+
+        self.focused = focused;
+        self.dragging = false;
+        self.composition = None;
+        self.text_dirty = true;
+        self.blink_timer = Duration::from_secs(0);
+
+        if focused {
+            input::start_text_input(ctx);
+            self.update_text_input_rect(ctx);
+        } else {
+            input::stop_text_input(ctx);
+        }
+    }
+
+    /// Returns the byte offset of the caret within the content.
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// Returns the range of the content (in bytes) that is selected, if any.
+    pub fn selection(&self) -> Option<Range<usize>> {
+        if self.caret == self.anchor {
+            None
+        } else {
+            Some(self.caret.min(self.anchor)..self.caret.max(self.anchor))
+        }
+    }
+
+    /// Selects all of the content.
+    pub fn select_all(&mut self) {
+        self.anchor = 0;
+        self.caret = self.content.len();
+    }
+
+    /// Handles an event, updating the text box if it is relevant.
+    ///
+    /// Returns `true` if the content of the text box was changed.
+    pub fn event(&mut self, ctx: &mut Context, event: &Event) -> bool {
+        match event {
+            Event::MouseButtonPressed {
+                button: MouseButton::Left,
+            } => {
+                let position = input::get_mouse_position(ctx);
+
+                if self.bounds.contains_point(position) {
+                    let extend =
+                        self.focused && input::is_key_modifier_down(ctx, KeyModifier::Shift);
+
+                    self.set_focused(ctx, true);
+                    self.composition = None;
+
+                    let index = self.hit_test(ctx, position);
+                    self.move_caret(index, extend);
+                    self.dragging = true;
+                } else {
+                    self.set_focused(ctx, false);
+                }
+
+                false
+            }
+
+            Event::MouseButtonReleased {
+                button: MouseButton::Left,
+            } => {
+                self.dragging = false;
+                false
+            }
+
+            Event::MouseMoved { position, .. } if self.dragging => {
+                let index = self.hit_test(ctx, *position);
+                self.move_caret(index, true);
+                false
+            }
+
+            Event::FocusLost => {
+                self.dragging = false;
+                false
+            }
+
+            _ if !self.focused => false,
+
+            Event::TextInput { text } => {
+                self.composition = None;
+                self.insert(text)
+            }
+
+            Event::TextEditing { text, cursor, .. } => {
+                self.composition = if text.is_empty() {
+                    None
+                } else {
+                    Some(Composition {
+                        text: text.clone(),
+                        cursor: *cursor,
+                    })
+                };
+
+                self.text_dirty = true;
+                self.blink_timer = Duration::from_secs(0);
+
+                false
+            }
+
+            Event::KeyPressed { key } => self.key_pressed(ctx, *key),
+
+            _ => false,
+        }
+    }
+
+    /// Updates the text box's caret blinking.
+    ///
+    /// This should be called once per update.
+    pub fn update(&mut self, ctx: &mut Context) {
+        if self.focused {
+            self.blink_timer += time::get_delta_time(ctx);
+        }
+    }
+
+    /// Draws the text box to the screen (or to a canvas, if one is enabled).
+    pub fn draw(&mut self, ctx: &mut Context) {
+        self.update_text(ctx);
+
+        let line_height = self.text.get_line_height(ctx);
+        let display_caret = self.display_caret();
+        let caret_position = self.text.get_caret_position(ctx, display_caret);
+
+        // Keep the caret within the visible area, without leaving empty space at the end
+        // if the content has been shortened.
+        let end = self.text.get_caret_position(ctx, usize::MAX).x;
+        let visible_width = (self.bounds.width - CARET_WIDTH).max(0.0);
+
+        if caret_position.x - self.scroll > visible_width {
+            self.scroll = caret_position.x - visible_width;
+        }
+
+        self.scroll = self
+            .scroll
+            .min(caret_position.x)
+            .min((end - visible_width).max(0.0))
+            .max(0.0);
+
+        let origin = self.text_origin(line_height);
+
+        graphics::set_scissor(
+            ctx,
+            Rectangle::new(
+                self.bounds.x as i32,
+                self.bounds.y as i32,
+                self.bounds.width.ceil() as i32,
+                self.bounds.height.ceil() as i32,
+            ),
+        );
+
+        if self.composition.is_none() {
+            if let Some(selection) = self.selection() {
+                for rect in self.text.get_selection_rects(ctx, selection) {
+                    graphics::fill_rect(
+                        ctx,
+                        Rectangle::new(
+                            rect.x + origin.x,
+                            rect.y + origin.y,
+                            rect.width,
+                            rect.height,
+                        ),
+                        self.selection_color,
+                    );
+                }
+            }
+        }
+
+        self.text.draw(
+            ctx,
+            DrawParams::new().position(origin).color(self.text_color),
+        );
+
+        if let Some(composition) = &self.composition {
+            let start = self.text.get_caret_position(ctx, self.caret);
+            let end = self
+                .text
+                .get_caret_position(ctx, self.caret + composition.text.len());
+
+            graphics::fill_rect(
+                ctx,
+                Rectangle::new(
+                    start.x + origin.x,
+                    start.y + origin.y + line_height - 1.0,
+                    end.x - start.x,
+                    1.0,
+                ),
+                self.text_color,
+            );
+        }
+
+        let blink_phase = self.blink_timer.as_millis() / BLINK_INTERVAL.as_millis();
+
+        if self.focused && blink_phase & 1 == 0 {
+            graphics::fill_rect(
+                ctx,
+                Rectangle::new(
+                    caret_position.x + origin.x,
+                    caret_position.y + origin.y,
+                    CARET_WIDTH,
+                    line_height,
+                ),
+                self.caret_color,
+            );
+        }
+
+        graphics::reset_scissor(ctx);
+    }
+
+    fn key_pressed(&mut self, ctx: &mut Context, key: Key) -> bool {
+        let ctrl = input::is_key_modifier_down(ctx, KeyModifier::Ctrl);
+        let shift = input::is_key_modifier_down(ctx, KeyModifier::Shift);
+
+        // While text is being composed, the IME handles the keyboard.
+        if self.composition.is_some() {
+            return false;
+        }
+
+        match key {
+            Key::Left => {
+                let index = match self.selection() {
+                    Some(selection) if !shift => selection.start,
+                    _ if ctrl => prev_word(&self.content, self.caret),
+                    _ => prev_char(&self.content, self.caret),
+                };
+
+                self.move_caret(index, shift);
+                false
+            }
+
+            Key::Right => {
+                let index = match self.selection() {
+                    Some(selection) if !shift => selection.end,
+                    _ if ctrl => next_word(&self.content, self.caret),
+                    _ => next_char(&self.content, self.caret),
+                };
+
+                self.move_caret(index, shift);
+                false
+            }
+
+            Key::Home => {
+                self.move_caret(0, shift);
+                false
+            }
+
+            Key::End => {
+                self.move_caret(self.content.len(), shift);
+                false
+            }
+
+            Key::Backspace => {
+                if self.selection().is_none() {
+                    self.anchor = if ctrl {
+                        prev_word(&self.content, self.caret)
+                    } else {
+                        prev_char(&self.content, self.caret)
+                    };
+                }
+
+                self.delete_selection()
+            }
+
+            Key::Delete => {
+                if self.selection().is_none() {
+                    self.anchor = if ctrl {
+                        next_word(&self.content, self.caret)
+                    } else {
+                        next_char(&self.content, self.caret)
+                    };
+                }
+
+                self.delete_selection()
+            }
+
+            Key::A if ctrl => {
+                self.select_all();
+                false
+            }
+
+            Key::C if ctrl => {
+                if let Some(selection) = self.selection() {
+                    let _ = input::set_clipboard_text(ctx, &self.content[selection]);
+                }
+
+                false
+            }
+
+            Key::X if ctrl => {
+                if let Some(selection) = self.selection() {
+                    let _ = input::set_clipboard_text(ctx, &self.content[selection]);
+                }
+
+                self.delete_selection()
+            }
+
+            Key::V if ctrl => match input::get_clipboard_text(ctx) {
+                Ok(text) => self.insert(&text),
+                Err(_) => false,
+            },
+
+            _ => false,
+        }
+    }
+
+    fn move_caret(&mut self, index: usize, extend_selection: bool) {
+        self.caret = index;
+
+        if !extend_selection {
+            self.anchor = index;
+        }
+
+        self.blink_timer = Duration::from_secs(0);
+    }
+
+    /// Replaces the selection with the given text, returning whether the content changed.
+    fn insert(&mut self, text: &str) -> bool {
+        let removed = self.delete_selection();
+
+        let available = match self.max_length {
+            Some(max_length) => max_length.saturating_sub(self.content.chars().count()),
+            None => usize::MAX,
+        };
+
+        let filtered: String = text
+            .chars()
+            .filter(|ch| !ch.is_control())
+            .take(available)
+            .collect();
+
+        self.content.insert_str(self.caret, &filtered);
+        self.move_caret(self.caret + filtered.len(), false);
+        self.text_dirty = true;
+
+        removed || !filtered.is_empty()
+    }
+
+    /// Removes the selected text, returning whether the content changed.
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some(selection) => {
+                let start = selection.start;
+
+                self.content.replace_range(selection, "");
+                self.move_caret(start, false);
+                self.text_dirty = true;
+
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn update_text(&mut self, ctx: &mut Context) {
+        if self.text_dirty {
+            match &self.composition {
+                Some(composition) => {
+                    let mut display = self.content.clone();
+                    display.insert_str(self.caret, &composition.text);
+                    self.text.set_content(display);
+                }
+                None => self.text.set_content(self.content.as_str()),
+            }
+
+            self.text_dirty = false;
+
+            if self.focused {
+                self.update_text_input_rect(ctx);
+            }
+        }
+    }
+
+    /// Returns the position of the caret within the displayed text, which includes
+    /// any text that is being composed.
+    fn display_caret(&self) -> usize {
+        match &self.composition {
+            Some(composition) => {
+                let offset = composition
+                    .text
+                    .char_indices()
+                    .nth(composition.cursor)
+                    .map(|(i, _)| i)
+                    .unwrap_or_else(|| composition.text.len());
+
+                self.caret + offset
+            }
+            None => self.caret,
+        }
+    }
+
+    fn text_origin(&self, line_height: f32) -> Vec2<f32> {
+        Vec2::new(
+            self.bounds.x - self.scroll,
+            self.bounds.y + ((self.bounds.height - line_height) / 2.0).round(),
+        )
+    }
+
+    fn hit_test(&mut self, ctx: &mut Context, position: Vec2<f32>) -> usize {
+        self.update_text(ctx);
+
+        let line_height = self.text.get_line_height(ctx);
+        let origin = self.text_origin(line_height);
+
+        self.text
+            .hit_test(ctx, position - origin)
+            .min(self.content.len())
+    }
+
+    fn update_text_input_rect(&self, ctx: &mut Context) {
+        let scale = window::get_dpi_scale(ctx);
+
+        input::set_text_input_rect(
+            ctx,
+            Rectangle::new(
+                (self.bounds.x * scale) as i32,
+                (self.bounds.y * scale) as i32,
+                (self.bounds.width * scale) as i32,
+                (self.bounds.height * scale) as i32,
+            ),
+        );
+    }
+}
+
+fn prev_char(text: &str, index: usize) -> usize {
+    text[..index]
+        .char_indices()
+        .next_back()
+        .map(|(i, _)| i)
+        .unwrap_or(0)
+}
+
+fn next_char(text: &str, index: usize) -> usize {
+    text[index..]
+        .chars()
+        .next()
+        .map(|ch| index + ch.len_utf8())
+        .unwrap_or(index)
+}
+
+/// Finds the start of the word before the given index, skipping any whitespace.
+fn prev_word(text: &str, index: usize) -> usize {
+    let mut chars = text[..index].char_indices().rev().peekable();
+
+    while let Some((_, ch)) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else {
+            break;
+        }
+    }
+
+    let mut start = chars.peek().map(|(i, _)| *i).unwrap_or(0);
+
+    for (i, ch) in chars {
+        if ch.is_whitespace() {
+            break;
+        }
+
+        start = i;
+    }
+
+    start
+}
+
+/// Finds the end of the word after the given index, skipping any whitespace.
+fn next_word(text: &str, index: usize) -> usize {
+    let rest = &text[index..];
+    let trimmed = rest.trim_start();
+    let word_len = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+
+    index + (rest.len() - trimmed.len()) + word_len
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn word_boundaries() {
+        let text = "héllo  big world";
+
+        assert_eq!(prev_char(text, 3), 1);
+        assert_eq!(next_char(text, 1), 3);
+
+        assert_eq!(prev_word(text, text.len()), 12);
+        assert_eq!(prev_word(text, 12), 8);
+        assert_eq!(prev_word(text, 4), 0);
+
+        assert_eq!(next_word(text, 0), 6);
+        assert_eq!(next_word(text, 6), 11);
+        assert_eq!(next_word(text, 11), text.len());
+    }
+}
@@ -0,0 +1,438 @@
+//! Functions and types relating to video playback.
+//!
+//! Videos are decoded on the CPU, a frame at a time, and streamed into a [`Texture`] that
+//! can be drawn like any other. Animated GIFs are supported out of the box - other formats
+//! can be played by implementing the [`VideoDecoder`] trait (e.g. on top of an FFmpeg or
+//! AV1 binding), and passing the decoder to [`Video::from_decoder`].
+//!
+//! If the `audio` feature is enabled, a [`Sound`](crate::audio::Sound) can be attached to a
+//! video as its soundtrack, via [`Video::set_audio`]. The sound will be played through the
+//! normal mixer, and will be paused, resumed and stopped along with the video.
+//!
+//! This module requires the `video` feature to be enabled.
+
+use std::fmt::{self, Debug, Formatter};
+use std::io::Cursor;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+use image::codecs::gif::GifDecoder as ImageGifDecoder;
+use image::{AnimationDecoder, Frames};
+
+#[cfg(feature = "audio")]
+use crate::audio::{Sound, SoundInstance};
+use crate::error::{Result, TetraError};
+use crate::fs;
+use crate::graphics::{DrawParams, Texture};
+use crate::time;
+use crate::Context;
+
+/// Implemented by types that can decode video frames for a [`Video`].
+pub trait VideoDecoder {
+    /// Returns the width and height of the video's frames, in pixels.
+    fn size(&self) -> (i32, i32);
+
+    /// Decodes the next frame of the video into the given buffer, as RGBA8 pixel data,
+    /// and returns how long the frame should be displayed for.
+    ///
+    /// The buffer will be exactly `width * height * 4` bytes long. `None` should be
+    /// returned once the end of the video has been reached.
+    fn next_frame(&mut self, buffer: &mut [u8]) -> Result<Option<Duration>>;
+
+    /// Seeks back to the start of the video.
+    fn rewind(&mut self) -> Result;
+}
+
+/// A [`VideoDecoder`] for animated GIFs.
+///
+/// Frames are decoded as they are needed, rather than all at once.
+pub struct GifDecoder {
+    data: Rc<[u8]>,
+    width: i32,
+    height: i32,
+    frames: Frames<'static>,
+}
+
+impl GifDecoder {
+    /// Creates a decoder for the given GIF data.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::InvalidTexture`] will be returned if the data is not a valid GIF.
+    pub fn new(data: Vec<u8>) -> Result<GifDecoder> {
+        let data: Rc<[u8]> = data.into();
+        let (width, height, frames) = GifDecoder::open(&data)?;
+
+        Ok(GifDecoder {
+            data,
+            width,
+            height,
+            frames,
+        })
+    }
+
+    fn open(data: &Rc<[u8]>) -> Result<(i32, i32, Frames<'static>)> {
+        use image::ImageDecoder;
+
+        let decoder = ImageGifDecoder::new(Cursor::new(Rc::clone(data)))
+            .map_err(TetraError::InvalidTexture)?;
+
+        let (width, height) = decoder.dimensions();
+
+        Ok((width as i32, height as i32, decoder.into_frames()))
+    }
+}
+
+impl VideoDecoder for GifDecoder {
+    fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+
+    fn next_frame(&mut self, buffer: &mut [u8]) -> Result<Option<Duration>> {
+        let frame = match self.frames.next() {
+            Some(frame) => frame.map_err(TetraError::InvalidTexture)?,
+            None => return Ok(None),
+        };
+
+        let (numer, denom) = frame.delay().numer_denom_ms();
+
+        // Browsers treat very short delays as 100ms, as many GIFs rely on that.
+        let delay = match numer / denom.max(1) {
+            0..=10 => Duration::from_millis(100),
+            ms => Duration::from_millis(ms.into()),
+        };
+
+        let image = frame.into_buffer().into_raw();
+        let len = buffer.len().min(image.len());
+        buffer[..len].copy_from_slice(&image[..len]);
+
+        Ok(Some(delay))
+    }
+
+    fn rewind(&mut self) -> Result {
+        self.frames = GifDecoder::open(&self.data)?.2;
+        Ok(())
+    }
+}
+
+impl Debug for GifDecoder {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GifDecoder")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish()
+    }
+}
+
+/// The states that playback of a [`Video`] can be in.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VideoState {
+    /// The video is currently playing.
+    Playing,
+
+    /// The video is paused. If playback is resumed, it will continue
+    /// from the point where it was paused.
+    Paused,
+
+    /// The video has stopped, either manually or as a result of it reaching the
+    /// end. If playback is resumed, it will start over from the beginning.
+    Stopped,
+}
+
+/// A video that can be played back and drawn to the screen.
+///
+/// Calling [`advance`](Self::advance) within [`State::update`](crate::State::update)
+/// or [`State::draw`](crate::State::draw) will drive playback, decoding new frames into
+/// the video's [`texture`](Self::texture) as they are needed.
+///
+/// Videos are created in the [`Stopped`](VideoState::Stopped) state - call
+/// [`play`](Self::play) to start playback.
+///
+/// # Performance
+///
+/// Decoding is done on the main thread, and each new frame has to be uploaded to the GPU,
+/// so playing back high resolution videos can be expensive.
+pub struct Video {
+    decoder: Box<dyn VideoDecoder>,
+    texture: Texture,
+    buffer: Vec<u8>,
+
+    state: VideoState,
+    repeating: bool,
+    frame_length: Duration,
+    timer: Duration,
+
+    #[cfg(feature = "audio")]
+    audio: Option<SoundInstance>,
+}
+
+impl Video {
+    /// Loads an animated GIF from the given file.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::FailedToLoadAsset`] will be returned if the file could not be loaded.
+    /// * [`TetraError::InvalidTexture`] will be returned if the file is not a valid GIF.
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters
+    ///   an error.
+    pub fn new<P>(ctx: &mut Context, path: P) -> Result<Video>
+    where
+        P: AsRef<Path>,
+    {
+        Video::from_decoder(ctx, GifDecoder::new(fs::read(path)?)?)
+    }
+
+    /// Loads an animated GIF from a slice of binary data.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::InvalidTexture`] will be returned if the data is not a valid GIF.
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters
+    ///   an error.
+    pub fn from_file_data(ctx: &mut Context, data: &[u8]) -> Result<Video> {
+        Video::from_decoder(ctx, GifDecoder::new(data.to_vec())?)
+    }
+
+    /// Creates a video that will be decoded by the given decoder.
+    ///
+    /// The first frame will be decoded straight away, so that there is something to draw
+    /// before playback starts.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters
+    ///   an error.
+    /// * Any errors returned by the decoder will be passed through.
+    pub fn from_decoder<D>(ctx: &mut Context, decoder: D) -> Result<Video>
+    where
+        D: VideoDecoder + 'static,
+    {
+        let (width, height) = decoder.size();
+        let buffer = vec![0; (width * height * 4) as usize];
+        let texture = Texture::from_rgba(ctx, width, height, &buffer)?;
+
+        let mut video = Video {
+            decoder: Box::new(decoder),
+            texture,
+            buffer,
+
+            state: VideoState::Stopped,
+            repeating: false,
+            frame_length: Duration::from_secs(0),
+            timer: Duration::from_secs(0),
+
+            #[cfg(feature = "audio")]
+            audio: None,
+        };
+
+        video.next_frame(ctx)?;
+
+        Ok(video)
+    }
+
+    /// Draws the current frame to the screen (or to a canvas, if one is enabled).
+    pub fn draw<P>(&self, ctx: &mut Context, params: P)
+    where
+        P: Into<DrawParams>,
+    {
+        self.texture.draw(ctx, params);
+    }
+
+    /// Advances playback, decoding new frames if required.
+    ///
+    /// This method uses the current [delta time](crate::time::get_delta_time)
+    /// to calculate how much time has passed.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters
+    ///   an error.
+    /// * Any errors returned by the decoder will be passed through.
+    pub fn advance(&mut self, ctx: &mut Context) -> Result {
+        let delta = time::get_delta_time(ctx);
+        self.advance_by(ctx, delta)
+    }
+
+    /// Advances playback by a specified amount, decoding new frames if required.
+    ///
+    /// If the specified duration is longer than the current frame, frames will be skipped.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters
+    ///   an error.
+    /// * Any errors returned by the decoder will be passed through.
+    pub fn advance_by(&mut self, ctx: &mut Context, duration: Duration) -> Result {
+        if self.state != VideoState::Playing {
+            return Ok(());
+        }
+
+        self.timer += duration;
+
+        while self.state == VideoState::Playing && self.timer >= self.frame_length {
+            self.timer -= self.frame_length;
+            self.next_frame(ctx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Starts playback if the video is stopped, or resumes it if the video is paused.
+    pub fn play(&mut self) {
+        self.state = VideoState::Playing;
+
+        #[cfg(feature = "audio")]
+        if let Some(audio) = &self.audio {
+            audio.play();
+        }
+    }
+
+    /// Pauses playback. If playback is resumed, it will continue from the point where
+    /// it was paused.
+    pub fn pause(&mut self) {
+        if self.state == VideoState::Playing {
+            self.state = VideoState::Paused;
+
+            #[cfg(feature = "audio")]
+            if let Some(audio) = &self.audio {
+                audio.pause();
+            }
+        }
+    }
+
+    /// Stops playback and rewinds the video to the first frame.
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::PlatformError`] will be returned if the underlying graphics API encounters
+    ///   an error.
+    /// * Any errors returned by the decoder will be passed through.
+    pub fn stop(&mut self, ctx: &mut Context) -> Result {
+        self.state = VideoState::Stopped;
+
+        #[cfg(feature = "audio")]
+        if let Some(audio) = &self.audio {
+            audio.stop();
+        }
+
+        self.rewind(ctx)
+    }
+
+    /// Returns the current state of playback.
+    ///
+    /// Once a non-repeating video reaches its end, it will be [`Stopped`](VideoState::Stopped),
+    /// with the last frame still displayed.
+    pub fn state(&self) -> VideoState {
+        self.state
+    }
+
+    /// Returns whether the video will start over once it reaches the end.
+    pub fn repeating(&self) -> bool {
+        self.repeating
+    }
+
+    /// Sets whether the video should start over once it reaches the end.
+    ///
+    /// Defaults to `false`.
+    pub fn set_repeating(&mut self, repeating: bool) {
+        self.repeating = repeating;
+
+        #[cfg(feature = "audio")]
+        if let Some(audio) = &self.audio {
+            audio.set_repeating(repeating);
+        }
+    }
+
+    /// Sets the sound that will be played alongside the video.
+    ///
+    /// The sound will be started, paused and stopped along with the video. If the video
+    /// is already playing, the sound will start straight away, so it is usually best to
+    /// call this before [`play`](Self::play).
+    ///
+    /// # Errors
+    ///
+    /// * [`TetraError::NoAudioDevice`] will be returned if no audio device is active.
+    #[cfg(feature = "audio")]
+    pub fn set_audio(&mut self, ctx: &Context, sound: &Sound) -> Result {
+        if let Some(audio) = self.audio.take() {
+            audio.stop();
+        }
+
+        let audio = sound.spawn(ctx)?;
+        audio.set_repeating(self.repeating);
+
+        if self.state == VideoState::Playing {
+            audio.play();
+        }
+
+        self.audio = Some(audio);
+
+        Ok(())
+    }
+
+    /// Returns a reference to the texture that the video's frames are decoded into.
+    pub fn texture(&self) -> &Texture {
+        &self.texture
+    }
+
+    /// Returns the width and height of the video, in pixels.
+    pub fn size(&self) -> (i32, i32) {
+        self.texture.size()
+    }
+
+    fn next_frame(&mut self, ctx: &mut Context) -> Result {
+        match self.decoder.next_frame(&mut self.buffer)? {
+            Some(length) => {
+                self.set_frame_length(length);
+                self.texture.replace_data(ctx, &self.buffer)?;
+            }
+
+            None if self.repeating => {
+                self.rewind(ctx)?;
+            }
+
+            None => {
+                self.state = VideoState::Stopped;
+                self.timer = Duration::from_secs(0);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_frame_length(&mut self, length: Duration) {
+        // A zero length frame would stop playback from ever advancing.
+        self.frame_length = length.max(Duration::from_millis(1));
+    }
+
+    fn rewind(&mut self, ctx: &mut Context) -> Result {
+        self.decoder.rewind()?;
+        self.timer = Duration::from_secs(0);
+
+        match self.decoder.next_frame(&mut self.buffer)? {
+            Some(length) => {
+                self.set_frame_length(length);
+                self.texture.replace_data(ctx, &self.buffer)
+            }
+
+            // An empty video will never have anything to show.
+            None => {
+                self.state = VideoState::Stopped;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl Debug for Video {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Video")
+            .field("texture", &self.texture)
+            .field("state", &self.state)
+            .field("repeating", &self.repeating)
+            .field("frame_length", &self.frame_length)
+            .field("timer", &self.timer)
+            .finish()
+    }
+}
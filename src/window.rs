@@ -10,6 +10,84 @@ pub fn quit(ctx: &mut Context) {
     ctx.running = false;
 }
 
+/// Quits the game with the given exit code, if it is currently running.
+///
+/// Tetra does not exit the process itself - the code is returned from
+/// [`Context::run_with_exit_code`](crate::Context::run_with_exit_code) (or can be
+/// retrieved via [`get_exit_code`] after [`Context::run`](crate::Context::run) returns),
+/// and can then be passed to [`std::process::exit`]. This allows launchers and test
+/// harnesses to tell different outcomes apart.
+///
+/// As with [`quit`], this does not take effect until the end of the current cycle of
+/// the game loop.
+///
+/// # Examples
+///
+/// ```no_run
+/// # use tetra::{Context, ContextBuilder, State};
+/// # struct GameState;
+/// # impl State for GameState {}
+/// fn main() -> tetra::Result {
+///     let exit_code = ContextBuilder::new("Hello, world!", 1280, 720)
+///         .build()?
+///         .run_with_exit_code(|_| Ok(GameState))?;
+///
+///     std::process::exit(exit_code);
+/// }
+/// ```
+pub fn quit_with_result(ctx: &mut Context, exit_code: i32) {
+    ctx.running = false;
+    ctx.exit_code = exit_code;
+}
+
+/// Returns the exit code that was passed to [`quit_with_result`].
+///
+/// If the game was quit in any other way (e.g. via [`quit`], or by closing the window),
+/// this will return `0`. The code is reset each time the game starts running.
+pub fn get_exit_code(ctx: &Context) -> i32 {
+    ctx.exit_code
+}
+
+/// Tells Tetra whether an overlay (such as the Steam overlay) is currently being shown
+/// on top of the game.
+///
+/// Overlays of this kind draw themselves when the window's buffers are swapped, so they
+/// rely on the game presenting a frame every time around the game loop - which Tetra
+/// always does, even while the window does not have focus. While an overlay is active:
+///
+/// * Before each frame is presented, the window's framebuffer is bound, the viewport
+///   is reset to cover the whole window, and scissoring is switched off, so that the
+///   overlay isn't drawn into a canvas or clipped. Your graphics state is restored
+///   afterwards.
+/// * Input events (such as [`KeyPressed`](crate::Event::KeyPressed),
+///   [`MouseMoved`](crate::Event::MouseMoved) or
+///   [`GamepadButtonPressed`](crate::Event::GamepadButtonPressed)) are not delivered to
+///   your [`State`](crate::State), regardless of their [`EventMode`](crate::EventMode), as
+///   the player is interacting with the overlay rather than the game. Any input events
+///   that were [coalesced](crate::EventMode::Coalesced) but not yet delivered when the
+///   overlay opened are discarded. The functions in the [`input`](crate::input) module
+///   are still kept up to date, so you should check [`is_overlay_active`] before
+///   polling them.
+///
+/// Tetra cannot detect overlays itself - this should be called from whatever notifies
+/// your game that the overlay has been opened or closed (e.g. Steamworks'
+/// `GameOverlayActivated_t` callback).
+#[cfg(feature = "overlay_support")]
+pub fn set_overlay_active(ctx: &mut Context, active: bool) {
+    if active && !ctx.overlay_active {
+        ctx.events.discard_coalesced_input();
+    }
+
+    ctx.overlay_active = active;
+}
+
+/// Returns whether an overlay is currently being shown on top of the game, as set via
+/// [`set_overlay_active`].
+#[cfg(feature = "overlay_support")]
+pub fn is_overlay_active(ctx: &Context) -> bool {
+    ctx.overlay_active
+}
+
 /// Maximizes the window.
 pub fn maximize(ctx: &mut Context) {
     ctx.window.maximize();
@@ -218,10 +296,82 @@ pub fn is_visible(ctx: &mut Context) -> bool {
 }
 
 /// Sets whether or not the window should be visible to the user.
+///
+/// If the game was started with [`ContextBuilder::visible`](crate::ContextBuilder::visible)
+/// set to `false`, this is how you show the window once it is ready.
 pub fn set_visible(ctx: &mut Context, visible: bool) {
     ctx.window.set_visible(visible);
 }
 
+/// Returns the opacity of the window, from `0.0` (fully transparent) to `1.0`
+/// (fully opaque).
+///
+/// If the platform does not support window opacity, this will always return `1.0`.
+pub fn get_opacity(ctx: &Context) -> f32 {
+    ctx.window.get_opacity()
+}
+
+/// Sets the opacity of the window, from `0.0` (fully transparent) to `1.0` (fully opaque).
+///
+/// This affects the whole window, including its decorations, and can be used to fade
+/// a launcher or splash screen in and out.
+///
+/// # Errors
+///
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+/// if the platform does not support window opacity.
+pub fn set_opacity(ctx: &mut Context, opacity: f32) -> Result {
+    ctx.window.set_opacity(opacity)
+}
+
+/// Returns the brightness of the display that the window is on, as set by
+/// [`set_brightness`].
+///
+/// If the platform does not support changing the brightness, this will always return `1.0`.
+pub fn get_brightness(ctx: &Context) -> f32 {
+    ctx.window.get_brightness()
+}
+
+/// Sets the brightness of the display that the window is on, where `1.0` is the
+/// normal brightness.
+///
+/// Unlike adjusting the colors that you draw, this changes the gamma ramp of the display,
+/// so it affects the whole output (including the letterboxing and any output filters).
+/// This makes it suitable for implementing a brightness option in a settings menu.
+///
+/// The original gamma ramp will be restored when the game exits. Depending on the
+/// platform, the change may only be visible while the window is focused, or while it is
+/// fullscreen.
+///
+/// # Errors
+///
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+/// if the platform does not support changing the gamma ramp.
+pub fn set_brightness(ctx: &mut Context, brightness: f32) -> Result {
+    ctx.window.set_brightness(brightness)
+}
+
+/// Sets the gamma ramp of the display that the window is on.
+///
+/// Each table maps the 256 possible input values of a color channel to an output value,
+/// with `0` being the darkest and `65535` being the brightest. This allows for finer
+/// control than [`set_brightness`] (e.g. for applying a color tint to the whole display).
+///
+/// The same caveats as [`set_brightness`] apply.
+///
+/// # Errors
+///
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+/// if the platform does not support changing the gamma ramp.
+pub fn set_gamma_ramp(
+    ctx: &mut Context,
+    red: &[u16; 256],
+    green: &[u16; 256],
+    blue: &[u16; 256],
+) -> Result {
+    ctx.window.set_gamma_ramp(red, green, blue)
+}
+
 /// Sets whether the window should be vsynced.
 ///
 /// # Errors
@@ -239,11 +389,17 @@ pub fn is_vsync_enabled(ctx: &Context) -> bool {
 
 /// Sets whether the window should be in fullscreen mode.
 ///
+/// Returns whether the window is fullscreen after the change was applied. This may
+/// not match the requested mode, as some platforms apply fullscreen changes
+/// asynchronously, or may refuse them. Once the state actually changes, an
+/// [`Event::FullscreenChanged`](crate::Event::FullscreenChanged) will be emitted, which
+/// is the most reliable way to keep UI (such as a settings toggle) in sync.
+///
 /// # Errors
 ///
 /// * [`TetraError::FailedToChangeDisplayMode`](crate::TetraError::FailedToChangeDisplayMode)
 /// will be returned if the game was unable to enter or exit fullscreen.
-pub fn set_fullscreen(ctx: &mut Context, fullscreen: bool) -> Result {
+pub fn set_fullscreen(ctx: &mut Context, fullscreen: bool) -> Result<bool> {
     ctx.window.set_fullscreen(fullscreen)
 }
 
@@ -361,6 +517,16 @@ pub fn get_monitor_size(ctx: &Context, monitor_index: i32) -> Result<(i32, i32)>
     ctx.window.get_monitor_size(monitor_index)
 }
 
+/// Gets the refresh rate of a monitor connected to the device, in hertz.
+///
+/// # Errors
+///
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+/// if the monitor state was inaccessible, or if the refresh rate could not be determined.
+pub fn get_monitor_refresh_rate(ctx: &Context, monitor_index: i32) -> Result<i32> {
+    ctx.window.get_monitor_refresh_rate(monitor_index)
+}
+
 /// Gets the index of the monitor that the window is currently on.
 ///
 /// # Errors
@@ -413,6 +579,22 @@ pub fn get_current_monitor_size(ctx: &Context) -> Result<(i32, i32)> {
     ctx.window.get_monitor_size(monitor_index)
 }
 
+/// Gets the refresh rate of the monitor that the window is currently on, in hertz.
+///
+/// This can be passed to [`Timestep::Fixed`](crate::time::Timestep::Fixed) to run the
+/// game's updates at the same rate as the display - see
+/// [`time::set_refresh_rate_snapping`](crate::time::set_refresh_rate_snapping) for
+/// more information.
+///
+/// # Errors
+///
+/// * [`TetraError::PlatformError`](crate::TetraError::PlatformError) will be returned
+/// if the monitor state was inaccessible, or if the refresh rate could not be determined.
+pub fn get_current_monitor_refresh_rate(ctx: &Context) -> Result<i32> {
+    let monitor_index = ctx.window.get_current_monitor()?;
+    ctx.window.get_monitor_refresh_rate(monitor_index)
+}
+
 /// Sets whether or not the user's screen saver can be displayed while the game is running.
 pub fn set_screen_saver_enabled(ctx: &Context, screen_saver_enabled: bool) {
     ctx.window.set_screen_saver_enabled(screen_saver_enabled);